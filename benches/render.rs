@@ -0,0 +1,131 @@
+//! Render benchmarks: a fixed multi-voice sequence through the whole engine,
+//! and single algorithm passes over six sounding operators.
+//!
+//! Run with `cargo bench --no-default-features` to leave the GUI, audio and
+//! MIDI crates out of the build.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use synth_fm_rs::algorithms::process_algorithm;
+use synth_fm_rs::fm_synth::create_synth;
+use synth_fm_rs::operator::Operator;
+
+const SAMPLE_RATE: f32 = 44_100.0;
+const SECONDS: usize = 10;
+const FRAMES: usize = SAMPLE_RATE as usize * SECONDS;
+
+/// Samples per algorithm pass.
+const ALGORITHM_FRAMES: usize = 1024;
+
+/// (frame, is_note_on, note): a four-note chord every half second, each held
+/// for two seconds, so up to 16 voices overlap.
+fn sequence() -> Vec<(usize, bool, u8)> {
+    const CHORDS: [[u8; 4]; 4] = [
+        [48, 55, 60, 64],
+        [45, 52, 57, 60],
+        [41, 48, 53, 57],
+        [43, 50, 55, 59],
+    ];
+    let half_second = FRAMES / (SECONDS * 2);
+    let mut events = Vec::new();
+    for step in 0..SECONDS * 2 - 4 {
+        let start = step * half_second;
+        for &note in &CHORDS[step % CHORDS.len()] {
+            let note = note + 12 * (step % 2) as u8;
+            events.push((start, true, note));
+            events.push((start + 4 * half_second, false, note));
+        }
+    }
+    // Offs before ons on the same frame, as render::render_events does.
+    events.sort_by_key(|&(frame, on, _)| (frame, on));
+    events
+}
+
+/// Render the sequence one `process_stereo` call per frame.
+fn render_per_sample(events: &[(usize, bool, u8)]) -> f32 {
+    let (mut engine, mut controller) = create_synth(SAMPLE_RATE);
+    let mut next = 0;
+    let mut sum = 0.0;
+    for frame in 0..FRAMES {
+        while next < events.len() && events[next].0 == frame {
+            let (_, on, note) = events[next];
+            if on {
+                controller.note_on(note, 100);
+            } else {
+                controller.note_off(note);
+            }
+            next += 1;
+        }
+        engine.process_commands();
+        let (left, right) = engine.process_stereo();
+        sum += left + right;
+    }
+    sum
+}
+
+/// Render the sequence with `process_block`, one block per stretch between
+/// events.
+fn render_blocks(events: &[(usize, bool, u8)]) -> f32 {
+    let (mut engine, mut controller) = create_synth(SAMPLE_RATE);
+    let mut left = vec![0.0; FRAMES];
+    let mut right = vec![0.0; FRAMES];
+    let mut next = 0;
+    let mut frame = 0;
+    while frame < FRAMES {
+        while next < events.len() && events[next].0 == frame {
+            let (_, on, note) = events[next];
+            if on {
+                controller.note_on(note, 100);
+            } else {
+                controller.note_off(note);
+            }
+            next += 1;
+        }
+        let end = events.get(next).map_or(FRAMES, |e| e.0.min(FRAMES));
+        engine.process_block(&mut left[frame..end], &mut right[frame..end]);
+        frame = end;
+    }
+    left.iter().chain(&right).sum()
+}
+
+fn engine(c: &mut Criterion) {
+    let events = sequence();
+    let mut group = c.benchmark_group("engine_10s");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(FRAMES as u64));
+    group.bench_function("process_stereo", |b| {
+        b.iter(|| render_per_sample(black_box(&events)))
+    });
+    group.bench_function("process_block", |b| {
+        b.iter(|| render_blocks(black_box(&events)))
+    });
+    group.finish();
+}
+
+fn algorithms(c: &mut Criterion) {
+    let mut group = c.benchmark_group("algorithm");
+    group.throughput(Throughput::Elements(ALGORITHM_FRAMES as u64));
+    for algorithm in [1u8, 5, 16, 32] {
+        let mut ops: [Operator; 6] = std::array::from_fn(|_| Operator::new(SAMPLE_RATE));
+        for (i, op) in ops.iter_mut().enumerate() {
+            op.set_frequency_ratio(1.0 + i as f32);
+            op.trigger(220.0, 1.0, 57);
+        }
+        group.bench_with_input(
+            BenchmarkId::from_parameter(algorithm),
+            &algorithm,
+            |b, &algorithm| {
+                b.iter(|| {
+                    let mut sum = 0.0;
+                    for _ in 0..ALGORITHM_FRAMES {
+                        sum += process_algorithm(algorithm, &mut ops);
+                    }
+                    black_box(sum)
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, engine, algorithms);
+criterion_main!(benches);
@@ -0,0 +1,307 @@
+//! CLAP instrument built on nih-plug, and VST3 with the `vst3` feature.
+//!
+//! The host owns the audio thread, so the plugin keeps both halves of
+//! `create_synth` there: host notes, MIDI CCs and parameter automation go
+//! through the `SynthController`, exactly like the GUI and MIDI threads of
+//! the desktop app, and are applied by `process_block` at the next event
+//! boundary. Parameters are the host's source of truth; their defaults match
+//! the engine's Init Voice, so a fresh instance sounds like a fresh app.
+//!
+//! There is no editor: hosts show their generic parameter view.
+
+use nih_plug::prelude::*;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use synth_fm_rs::command_queue::OperatorParam;
+use synth_fm_rs::fm_synth::{create_synth, SynthController, SynthEngine};
+
+/// Global and per-operator slots in `SynthFmPlugin::sent`.
+const GLOBAL_PARAMS: usize = 5;
+const OPERATOR_PARAMS: usize = 5;
+const SENT_SLOTS: usize = GLOBAL_PARAMS + 6 * OPERATOR_PARAMS;
+
+#[derive(Params)]
+struct FmParams {
+    #[id = "algorithm"]
+    algorithm: IntParam,
+    #[id = "volume"]
+    master_volume: FloatParam,
+    #[id = "tune"]
+    master_tune: FloatParam,
+    #[id = "bend_range"]
+    pitch_bend_range: IntParam,
+    #[id = "porta_time"]
+    portamento_time: FloatParam,
+    /// IDs get an `_1`..`_6` suffix; OP1 is the first entry.
+    #[nested(array, group = "Operator")]
+    operators: [OperatorParams; 6],
+}
+
+#[derive(Params)]
+struct OperatorParams {
+    #[id = "level"]
+    level: IntParam,
+    #[id = "coarse"]
+    coarse: IntParam,
+    #[id = "fine"]
+    fine: IntParam,
+    #[id = "detune"]
+    detune: IntParam,
+    #[id = "feedback"]
+    feedback: IntParam,
+}
+
+impl Default for FmParams {
+    fn default() -> Self {
+        Self {
+            algorithm: IntParam::new("Algorithm", 1, IntRange::Linear { min: 1, max: 32 }),
+            master_volume: FloatParam::new(
+                "Volume",
+                0.7,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            master_tune: FloatParam::new(
+                "Master Tune",
+                0.0,
+                FloatRange::Linear {
+                    min: -150.0,
+                    max: 150.0,
+                },
+            )
+            .with_step_size(1.0)
+            .with_unit(" ct"),
+            pitch_bend_range: IntParam::new(
+                "Pitch Bend Range",
+                2,
+                IntRange::Linear { min: 0, max: 12 },
+            )
+            .with_unit(" st"),
+            portamento_time: FloatParam::new(
+                "Portamento Time",
+                50.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 99.0,
+                },
+            )
+            .with_step_size(1.0),
+            // INIT VOICE: only OP1 sounds.
+            operators: std::array::from_fn(|i| OperatorParams::new(if i == 0 { 99 } else { 0 })),
+        }
+    }
+}
+
+impl OperatorParams {
+    fn new(level: i32) -> Self {
+        Self {
+            level: IntParam::new("Level", level, IntRange::Linear { min: 0, max: 99 }),
+            coarse: IntParam::new("Coarse", 1, IntRange::Linear { min: 0, max: 31 }),
+            fine: IntParam::new("Fine", 0, IntRange::Linear { min: 0, max: 99 }),
+            detune: IntParam::new("Detune", 0, IntRange::Linear { min: -7, max: 7 }),
+            feedback: IntParam::new("Feedback", 0, IntRange::Linear { min: 0, max: 7 }),
+        }
+    }
+}
+
+pub struct SynthFmPlugin {
+    params: Arc<FmParams>,
+    /// Built in `initialize`, once the host has given us a sample rate.
+    synth: Option<(SynthEngine, SynthController)>,
+    /// Last value handed to the engine per parameter (NaN = never sent), so
+    /// a block only queues commands for what the host actually moved.
+    sent: [f32; SENT_SLOTS],
+}
+
+impl Default for SynthFmPlugin {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(FmParams::default()),
+            synth: None,
+            sent: [f32::NAN; SENT_SLOTS],
+        }
+    }
+}
+
+impl SynthFmPlugin {
+    /// Queue a command for every parameter that changed since the last block.
+    fn send_param_changes(&mut self) {
+        let Some((_, controller)) = self.synth.as_mut() else {
+            return;
+        };
+        let params = &self.params;
+        let mut slots = self.sent.iter_mut();
+        let mut changed = |value: f32| {
+            let slot = slots.next().expect("one slot per parameter");
+            let moved = *slot != value;
+            *slot = value;
+            moved
+        };
+
+        let algorithm = params.algorithm.value();
+        if changed(algorithm as f32) {
+            controller.set_algorithm(algorithm as u8);
+        }
+        let volume = params.master_volume.value();
+        if changed(volume) {
+            controller.set_master_volume(volume);
+        }
+        let tune = params.master_tune.value();
+        if changed(tune) {
+            controller.set_master_tune(tune);
+        }
+        let bend_range = params.pitch_bend_range.value() as f32;
+        if changed(bend_range) {
+            controller.set_pitch_bend_range(bend_range);
+        }
+        let portamento = params.portamento_time.value();
+        if changed(portamento) {
+            controller.set_portamento_time(portamento);
+        }
+
+        for (index, op) in params.operators.iter().enumerate() {
+            for (param, value) in [
+                (OperatorParam::Level, op.level.value()),
+                (OperatorParam::Coarse, op.coarse.value()),
+                (OperatorParam::Fine, op.fine.value()),
+                (OperatorParam::Detune, op.detune.value()),
+                (OperatorParam::Feedback, op.feedback.value()),
+            ] {
+                if changed(value as f32) {
+                    controller.set_operator_param(index as u8, param, value as f32);
+                }
+            }
+        }
+    }
+}
+
+/// Route one host event to the controller, the way `MidiHandler` routes the
+/// equivalent MIDI message.
+fn handle_event(controller: &mut SynthController, event: NoteEvent<()>) {
+    match event {
+        NoteEvent::NoteOn { note, velocity, .. } => {
+            controller.note_on(note, ((velocity * 127.0).round() as u8).clamp(1, 127));
+        }
+        NoteEvent::NoteOff { note, .. } => controller.note_off(note),
+        NoteEvent::MidiPitchBend { value, .. } => {
+            controller.pitch_bend((value * 16383.0).round() as i16 - 8192);
+        }
+        NoteEvent::MidiChannelPressure { pressure, .. } => controller.aftertouch(pressure),
+        NoteEvent::MidiCC { cc, value, .. } => match cc {
+            1 => controller.mod_wheel(value),
+            2 => controller.breath_controller(value),
+            4 => controller.foot_controller(value),
+            11 => controller.expression(value),
+            64 => controller.sustain_pedal(value >= 0.5),
+            120 => controller.panic(),
+            121 => controller.reset_controllers(),
+            123 => controller.all_notes_off(),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+impl Plugin for SynthFmPlugin {
+    const NAME: &'static str = "synth-fm-rs";
+    const VENDOR: &'static str = "eubide";
+    const URL: &'static str = "https://github.com/eubide/synth-fm-rs";
+    const EMAIL: &'static str = "";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: None,
+        main_output_channels: NonZeroU32::new(2),
+        ..AudioIOLayout::const_default()
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
+    const SAMPLE_ACCURATE_AUTOMATION: bool = false;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        buffer_config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.synth = Some(create_synth(buffer_config.sample_rate));
+        self.sent = [f32::NAN; SENT_SLOTS];
+        true
+    }
+
+    fn reset(&mut self) {
+        if let Some((engine, controller)) = self.synth.as_mut() {
+            controller.panic();
+            engine.process_commands();
+        }
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.send_param_changes();
+        let Some((engine, controller)) = self.synth.as_mut() else {
+            return ProcessStatus::Normal;
+        };
+
+        let frames = buffer.samples();
+        let [left, right] = buffer.as_slice() else {
+            return ProcessStatus::Error("synth-fm-rs renders to a stereo output");
+        };
+
+        // Render up to each event's timestamp, hand the event over, repeat:
+        // `process_block` applies the queued commands before rendering.
+        let mut next_event = context.next_event();
+        let mut frame = 0;
+        while frame < frames {
+            while let Some(event) = next_event {
+                if event.timing() as usize > frame {
+                    break;
+                }
+                handle_event(controller, event);
+                next_event = context.next_event();
+            }
+            let block_end =
+                next_event.map_or(frames, |event| (event.timing() as usize).min(frames));
+            engine.process_block(&mut left[frame..block_end], &mut right[frame..block_end]);
+            frame = block_end;
+        }
+
+        // Effects tails (delay, reverb) outlive the last note.
+        ProcessStatus::KeepAlive
+    }
+}
+
+impl ClapPlugin for SynthFmPlugin {
+    const CLAP_ID: &'static str = "com.eubide.synth-fm-rs";
+    const CLAP_DESCRIPTION: Option<&'static str> = Some("DX7-style 6-operator FM synthesizer");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::Instrument,
+        ClapFeature::Synthesizer,
+        ClapFeature::Stereo,
+    ];
+}
+
+#[cfg(feature = "vst3")]
+impl Vst3Plugin for SynthFmPlugin {
+    const VST3_CLASS_ID: [u8; 16] = *b"SynthFmRsDx7Inst";
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Instrument, Vst3SubCategory::Synth];
+}
+
+nih_export_clap!(SynthFmPlugin);
+#[cfg(feature = "vst3")]
+nih_export_vst3!(SynthFmPlugin);
@@ -0,0 +1,249 @@
+//! User-defined operator routing ("custom algorithm").
+//!
+//! Instead of one of the 32 hard-coded DX7 functions, the voice can run a
+//! routing matrix: any operator may modulate any other, any set of operators
+//! may be carriers and each operator has its own self-feedback amount.
+//!
+//! Operators run from OP6 down to OP1, the DX7 signal direction. A
+//! connection from a higher operator to a lower one uses this sample's
+//! output; one that points back up (e.g. OP2 → OP5) reads the source's
+//! previous sample, which lets loops exist without an evaluation-order
+//! cycle. Carriers are summed with the same 1/√n normalisation as the
+//! hard-coded algorithms.
+
+use crate::algorithms::{self, AlgorithmInfo, CARRIER_GAINS};
+use crate::operator::Operator;
+
+/// Highest self-feedback amount, as on the DX7 FEEDBACK parameter.
+pub const MAX_FEEDBACK: f32 = 7.0;
+
+/// Routing of the six operators. Indices are 0-based (0 = OP1).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlgorithmMatrix {
+    /// `modulators[to]` has bit `from` set when `from` modulates `to`.
+    modulators: [u8; 6],
+    /// Bit `i` set when operator `i` is heard.
+    carriers: u8,
+    feedback: [f32; 6],
+}
+
+impl Default for AlgorithmMatrix {
+    fn default() -> Self {
+        Self::from_algorithm(1, 0.0)
+    }
+}
+
+impl AlgorithmMatrix {
+    /// Routing of DX7 algorithm `number`, with `feedback` on its feedback
+    /// operator. The cross-feedback loops of algorithms 4 and 6 become
+    /// self-feedback on the same operator.
+    pub fn from_algorithm(number: u8, feedback: f32) -> Self {
+        let info = algorithms::topology(number);
+        let mut matrix = Self {
+            modulators: [0; 6],
+            carriers: 0,
+            feedback: [0.0; 6],
+        };
+        for &op in info.carriers {
+            matrix.set_carrier(op as usize - 1, true);
+        }
+        for &(from, to) in info.connections {
+            matrix.set_connection(from as usize - 1, to as usize - 1, true);
+        }
+        if info.feedback_op > 0 {
+            matrix.set_feedback(info.feedback_op as usize - 1, feedback);
+        }
+        matrix
+    }
+
+    /// True when operator `from` modulates operator `to`.
+    pub fn connected(&self, from: usize, to: usize) -> bool {
+        from < 6 && to < 6 && self.modulators[to] & (1 << from) != 0
+    }
+
+    /// Add or remove a connection. An operator feeding itself is set with
+    /// [`Self::set_feedback`] instead, so `from == to` is ignored.
+    pub fn set_connection(&mut self, from: usize, to: usize, on: bool) {
+        if from >= 6 || to >= 6 || from == to {
+            return;
+        }
+        if on {
+            self.modulators[to] |= 1 << from;
+        } else {
+            self.modulators[to] &= !(1 << from);
+        }
+    }
+
+    pub fn is_carrier(&self, op: usize) -> bool {
+        op < 6 && self.carriers & (1 << op) != 0
+    }
+
+    pub fn set_carrier(&mut self, op: usize, on: bool) {
+        if op >= 6 {
+            return;
+        }
+        if on {
+            self.carriers |= 1 << op;
+        } else {
+            self.carriers &= !(1 << op);
+        }
+    }
+
+    /// Carrier bitmask (bit `i` = operator `i + 1`).
+    pub fn carrier_mask(&self) -> u8 {
+        self.carriers
+    }
+
+    /// Self-feedback amount of `op`, 0 – 7.
+    pub fn feedback(&self, op: usize) -> f32 {
+        self.feedback.get(op).copied().unwrap_or(0.0)
+    }
+
+    pub fn set_feedback(&mut self, op: usize, amount: f32) {
+        if let Some(fb) = self.feedback.get_mut(op) {
+            *fb = amount.clamp(0.0, MAX_FEEDBACK);
+        }
+    }
+
+    /// The routing in the 1-indexed form the algorithm diagram draws.
+    /// `AlgorithmInfo` holds a single feedback operator: the one with the
+    /// most feedback.
+    pub fn info(&self) -> AlgorithmInfo {
+        let carriers = (0..6)
+            .filter(|&op| self.is_carrier(op))
+            .map(|op| op as u8 + 1);
+        let mut connections = Vec::new();
+        for to in 0..6 {
+            for from in 0..6 {
+                if self.connected(from, to) {
+                    connections.push((from as u8 + 1, to as u8 + 1));
+                }
+            }
+        }
+        let feedback_op = (0..6)
+            .filter(|&op| self.feedback[op] > 0.0)
+            .max_by(|&a, &b| self.feedback[a].total_cmp(&self.feedback[b]))
+            .map_or(0, |op| op as u8 + 1);
+        AlgorithmInfo {
+            carriers: carriers.collect(),
+            connections,
+            feedback_op,
+        }
+    }
+
+    /// Run one sample through the matrix and return the carrier sum.
+    ///
+    /// The operators' own FEEDBACK setting is ignored: the matrix amounts
+    /// replace it.
+    pub fn process(&self, ops: &mut [Operator; 6]) -> f32 {
+        let mut output = 0.0;
+        for to in (0..6).rev() {
+            let sources = self.modulators[to];
+            let mut modulation = 0.0;
+            for (from, op) in ops.iter().enumerate() {
+                if sources & (1 << from) != 0 {
+                    modulation += op.current_output();
+                }
+            }
+            let op = &mut ops[to];
+            modulation += op.cross_feedback_signal(self.feedback[to]);
+            let out = op.process_no_self_feedback(modulation);
+            if self.is_carrier(to) {
+                output += out;
+            }
+        }
+        output * CARRIER_GAINS[self.carriers.count_ones() as usize]
+    }
+
+    /// [`Self::process`] split into (main, sub) buses, as
+    /// [`algorithms::process_algorithm_split`] does for the DX7 algorithms.
+    pub fn process_split(&self, ops: &mut [Operator; 6]) -> (f32, f32) {
+        let main = self.process(ops);
+        algorithms::split_sub_bus(main, self.carriers, ops)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::process_algorithm;
+
+    const SR: f32 = 44_100.0;
+
+    fn voice_ops(ratios: [f32; 6]) -> [Operator; 6] {
+        let mut ops: [Operator; 6] = std::array::from_fn(|_| Operator::new(SR));
+        for (op, ratio) in ops.iter_mut().zip(ratios) {
+            op.frequency_ratio = ratio;
+            op.trigger(220.0, 1.0, 60);
+        }
+        ops
+    }
+
+    #[test]
+    fn matrix_of_every_dx7_algorithm_matches_the_hardcoded_one() {
+        // Without feedback this checks the shared topology table against
+        // the hardcoded routing of all 32 algorithms.
+        let ratios = [1.0, 2.0, 1.0, 3.0, 1.0, 0.5];
+        for alg in 1..=32u8 {
+            let mut hardcoded = voice_ops(ratios);
+            let mut custom = voice_ops(ratios);
+            let matrix = AlgorithmMatrix::from_algorithm(alg, 0.0);
+            for _ in 0..1024 {
+                let expected = process_algorithm(alg, &mut hardcoded);
+                let got = matrix.process(&mut custom);
+                assert!(
+                    (expected - got).abs() < 1e-4,
+                    "alg {alg}: {expected} vs {got}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn editing_connections_carriers_and_feedback() {
+        let mut matrix = AlgorithmMatrix::from_algorithm(32, 0.0);
+        assert_eq!(matrix.carrier_mask(), 0b111111);
+        assert!((0..6).all(|to| (0..6).all(|from| !matrix.connected(from, to))));
+
+        matrix.set_connection(1, 0, true);
+        matrix.set_connection(2, 2, true); // self link: use feedback instead
+        matrix.set_carrier(1, false);
+        matrix.set_feedback(3, 12.0);
+        assert!(matrix.connected(1, 0));
+        assert!(!matrix.connected(0, 1));
+        assert!(!matrix.connected(2, 2));
+        assert!(!matrix.is_carrier(1));
+        assert_eq!(matrix.feedback(3), MAX_FEEDBACK);
+
+        let with_fb = AlgorithmMatrix::from_algorithm(1, 5.0);
+        assert_eq!(with_fb.feedback(5), 5.0);
+        assert_eq!(with_fb.carrier_mask(), 0b000101);
+        let info = with_fb.info();
+        assert_eq!(info.carriers, vec![1, 3]);
+        assert_eq!(info.feedback_op, 6);
+        assert_eq!(info.connections.len(), 4);
+        assert!(info.connections.contains(&(6, 5)));
+    }
+
+    #[test]
+    fn backward_connections_and_silent_carriers_stay_finite() {
+        let mut matrix = AlgorithmMatrix::from_algorithm(1, 7.0);
+        // OP1 and OP3 feed back up into OP6, the top of a stack.
+        matrix.set_connection(0, 5, true);
+        matrix.set_connection(2, 5, true);
+        let mut ops = voice_ops([1.0; 6]);
+        let mut energy = 0.0;
+        for _ in 0..4096 {
+            let out = matrix.process(&mut ops);
+            assert!(out.is_finite());
+            energy += out * out;
+        }
+        assert!(energy > 0.0);
+
+        let mut silent = AlgorithmMatrix::from_algorithm(1, 0.0);
+        silent.set_carrier(0, false);
+        silent.set_carrier(2, false);
+        let mut ops = voice_ops([1.0; 6]);
+        assert!((0..256).all(|_| silent.process(&mut ops) == 0.0));
+    }
+}
@@ -40,6 +40,83 @@ pub fn process_algorithm(algorithm_number: u8, ops: &mut [Operator; 6]) -> f32 {
     }
 }
 
+/// Carrier bitmask per algorithm (bit `i` = operator `i + 1`), derived from
+/// `DX7_ALGORITHMS` at compile time so the audio thread never allocates.
+const CARRIER_MASKS: [u8; 32] = carrier_masks();
+
+const fn carrier_masks() -> [u8; 32] {
+    let mut masks = [0u8; 32];
+    let mut alg = 0;
+    while alg < 32 {
+        let carriers = DX7_ALGORITHMS[alg].carriers;
+        let mut i = 0;
+        while i < carriers.len() {
+            masks[alg] |= 1 << (carriers[i] - 1);
+            i += 1;
+        }
+        alg += 1;
+    }
+    masks
+}
+
+/// Carrier-sum normalisation used by the algorithm functions, indexed by
+/// carrier count (1/√n, rounded the same way as the hardcoded constants).
+pub(crate) const CARRIER_GAINS: [f32; 7] = [1.0, 1.0, 0.71, 0.58, 0.5, 0.45, 0.41];
+
+fn carrier_mask(algorithm_number: u8) -> u8 {
+    match algorithm_number {
+        1..=32 => CARRIER_MASKS[(algorithm_number - 1) as usize],
+        _ => CARRIER_MASKS[0],
+    }
+}
+
+/// Run an algorithm and split its output into (main, sub) buses.
+///
+/// Operators tagged with `sub_bus` are tapped after the algorithm has run:
+/// a tagged carrier is taken out of the main carrier sum and moved to the
+/// sub bus at the same normalised level, while a tagged modulator keeps
+/// modulating as usual and additionally feeds its own output to the sub bus.
+/// With no tagged operators this is exactly `process_algorithm`.
+pub fn process_algorithm_split(algorithm_number: u8, ops: &mut [Operator; 6]) -> (f32, f32) {
+    let main = process_algorithm(algorithm_number, ops);
+    split_sub_bus(main, carrier_mask(algorithm_number), ops)
+}
+
+/// Move the `sub_bus` operators of an already-run algorithm out of `main`;
+/// `carriers` is its carrier bitmask. Shared with the custom routing matrix.
+pub(crate) fn split_sub_bus(mut main: f32, carriers: u8, ops: &[Operator; 6]) -> (f32, f32) {
+    if !ops.iter().any(|op| op.sub_bus) {
+        return (main, 0.0);
+    }
+
+    let gain = CARRIER_GAINS[carriers.count_ones() as usize];
+    let mut sub = 0.0;
+    for (i, op) in ops.iter().enumerate() {
+        if !op.sub_bus {
+            continue;
+        }
+        let tapped = op.current_output() * gain;
+        if carriers & (1 << i) != 0 {
+            main -= tapped;
+        }
+        sub += tapped;
+    }
+    (main, sub)
+}
+
+/// Audition mix: the operators in `solo` (bit `i` = operator `i + 1`) summed
+/// as if they were the carriers, whatever their role in the algorithm that
+/// has just run. A soloed modulator is heard with its own modulators.
+pub(crate) fn solo_output(solo: u8, ops: &[Operator; 6]) -> f32 {
+    let sum: f32 = ops
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| solo & (1 << i) != 0)
+        .map(|(_, op)| op.current_output())
+        .sum();
+    sum * CARRIER_GAINS[(solo & 0x3F).count_ones() as usize]
+}
+
 /// Algorithm 1: Two Stacks
 /// Carriers: [1, 3] - Connections: [(2,1), (4,3), (5,4), (6,5), (6,6)]
 fn algorithm_1(ops: &mut [Operator; 6]) -> f32 {
@@ -97,16 +174,27 @@ fn algorithm_4(ops: &mut [Operator; 6]) -> f32 {
     let op1_out = ops[0].process(op2_out);
 
     // Stack 2: Op6 -> Op5 -> Op4 with cross-feedback loop (Op4 output → Op6 input)
-    // Op4's averaged previous output feeds Op6, depth controlled by Op4's feedback param
-    let fb_depth = ops[3].feedback;
+    // Op4's averaged previous output feeds Op6; no operator of the loop feeds itself
+    let fb_depth = loop_feedback(ops, &[3, 4, 5]);
     let op4_cross_fb = ops[3].cross_feedback_signal(fb_depth);
-    let op6_out = ops[5].process(op4_cross_fb);
-    let op5_out = ops[4].process(op6_out);
+    let op6_out = ops[5].process_no_self_feedback(op4_cross_fb);
+    let op5_out = ops[4].process_no_self_feedback(op6_out);
     let op4_out = ops[3].process_no_self_feedback(op5_out);
 
     (op1_out + op4_out) * 0.71 // √2 = 1.41, inverse = 0.71
 }
 
+/// Depth of a cross-feedback loop (0-based operator indices). The DX7 has
+/// one FEEDBACK value per voice; here it may sit on any operator of the
+/// loop (SysEx loads it on OP6, the algorithm diagram marks OP4 in
+/// algorithm 4), so the loop takes the largest.
+fn loop_feedback(ops: &[Operator; 6], members: &[usize]) -> f32 {
+    members
+        .iter()
+        .map(|&i| ops[i].smoothed_feedback())
+        .fold(0.0, f32::max)
+}
+
 /// Algorithm 5: Three Pairs
 /// Carriers: [1, 3, 5] - Connections: [(2,1), (4,3), (6,5)] - Feedback: Op6
 fn algorithm_5(ops: &mut [Operator; 6]) -> f32 {
@@ -139,11 +227,10 @@ fn algorithm_6(ops: &mut [Operator; 6]) -> f32 {
     let op3_out = ops[2].process(op4_out);
 
     // Op6 -> Op5 (carrier) with cross-feedback (Op5's previous output → Op6 input)
-    // Depth controlled by Op6's feedback param (presets set feedback_op: 6)
-    let fb_depth = ops[5].feedback;
+    let fb_depth = loop_feedback(ops, &[4, 5]);
     let op5_cross_fb = ops[4].cross_feedback_signal(fb_depth);
     let op6_out = ops[5].process_no_self_feedback(op5_cross_fb);
-    let op5_out = ops[4].process(op6_out);
+    let op5_out = ops[4].process_no_self_feedback(op6_out);
 
     (op1_out + op3_out + op5_out) * 0.58 // √3 = 1.73, inverse = 0.58
 }
@@ -632,170 +719,228 @@ pub struct AlgorithmInfo {
     pub feedback_op: u8,
 }
 
+/// Topology of one DX7 algorithm, operators 1-indexed. `DX7_ALGORITHMS` is
+/// the single description of the 32 routings: the diagram, the sub-bus
+/// carrier masks and the custom-algorithm matrix all read it, and a test
+/// checks it against the hardcoded functions above.
+pub(crate) struct Topology {
+    pub carriers: &'static [u8],
+    /// (from, to): `from` modulates `to`.
+    pub connections: &'static [(u8, u8)],
+    /// Operator with feedback, 0 if none.
+    pub feedback_op: u8,
+}
+
+const DX7_ALGORITHMS: [Topology; 32] = [
+    // 1
+    Topology {
+        carriers: &[1, 3],
+        connections: &[(2, 1), (4, 3), (5, 4), (6, 5)],
+        feedback_op: 6,
+    },
+    // 2
+    Topology {
+        carriers: &[1, 3],
+        connections: &[(2, 1), (4, 3), (5, 4), (6, 5)],
+        feedback_op: 2,
+    },
+    // 3
+    Topology {
+        carriers: &[1, 4],
+        connections: &[(2, 1), (3, 2), (5, 4), (6, 5)],
+        feedback_op: 6,
+    },
+    // 4. Cross-feedback: Op4→Op6 loop
+    Topology {
+        carriers: &[1, 4],
+        connections: &[(3, 2), (2, 1), (6, 5), (5, 4)],
+        feedback_op: 4,
+    },
+    // 5
+    Topology {
+        carriers: &[1, 3, 5],
+        connections: &[(2, 1), (4, 3), (6, 5)],
+        feedback_op: 6,
+    },
+    // 6. Cross-feedback: Op5→Op6 loop
+    Topology {
+        carriers: &[1, 3, 5],
+        connections: &[(2, 1), (4, 3), (6, 5)],
+        feedback_op: 6,
+    },
+    // 7
+    Topology {
+        carriers: &[1, 3],
+        connections: &[(2, 1), (4, 3), (5, 3), (6, 5)],
+        feedback_op: 6,
+    },
+    // 8
+    Topology {
+        carriers: &[1, 3],
+        connections: &[(2, 1), (4, 3), (5, 3), (6, 5)],
+        feedback_op: 4,
+    },
+    // 9
+    Topology {
+        carriers: &[1, 3],
+        connections: &[(2, 1), (4, 3), (5, 3), (6, 5)],
+        feedback_op: 2,
+    },
+    // 10
+    Topology {
+        carriers: &[1, 4],
+        connections: &[(2, 1), (3, 2), (5, 4), (6, 4)],
+        feedback_op: 3,
+    },
+    // 11
+    Topology {
+        carriers: &[1, 4],
+        connections: &[(2, 1), (3, 2), (5, 4), (6, 4)],
+        feedback_op: 6,
+    },
+    // 12
+    Topology {
+        carriers: &[1, 3],
+        connections: &[(2, 1), (4, 3), (5, 3), (6, 3)],
+        feedback_op: 2,
+    },
+    // 13
+    Topology {
+        carriers: &[1, 3],
+        connections: &[(2, 1), (4, 3), (5, 3), (6, 3)],
+        feedback_op: 6,
+    },
+    // 14
+    Topology {
+        carriers: &[1, 3],
+        connections: &[(2, 1), (4, 3), (5, 4), (6, 4)],
+        feedback_op: 6,
+    },
+    // 15
+    Topology {
+        carriers: &[1, 3],
+        connections: &[(2, 1), (4, 3), (5, 4), (6, 4)],
+        feedback_op: 2,
+    },
+    // 16
+    Topology {
+        carriers: &[1],
+        connections: &[(2, 1), (3, 1), (4, 3), (5, 1), (6, 5)],
+        feedback_op: 6,
+    },
+    // 17
+    Topology {
+        carriers: &[1],
+        connections: &[(2, 1), (3, 1), (4, 3), (5, 1), (6, 5)],
+        feedback_op: 2,
+    },
+    // 18
+    Topology {
+        carriers: &[1],
+        connections: &[(2, 1), (3, 1), (4, 1), (5, 4), (6, 5)],
+        feedback_op: 3,
+    },
+    // 19
+    Topology {
+        carriers: &[1, 4, 5],
+        connections: &[(3, 2), (2, 1), (6, 5), (6, 4)],
+        feedback_op: 6,
+    },
+    // 20
+    Topology {
+        carriers: &[1, 2, 4],
+        connections: &[(3, 1), (3, 2), (5, 4), (6, 4)],
+        feedback_op: 3,
+    },
+    // 21
+    Topology {
+        carriers: &[1, 2, 4, 5],
+        connections: &[(3, 1), (3, 2), (6, 4), (6, 5)],
+        feedback_op: 3,
+    },
+    // 22
+    Topology {
+        carriers: &[1, 3, 4, 5],
+        connections: &[(2, 1), (6, 3), (6, 4), (6, 5)],
+        feedback_op: 6,
+    },
+    // 23
+    Topology {
+        carriers: &[1, 2, 4, 5],
+        connections: &[(3, 2), (6, 4), (6, 5)],
+        feedback_op: 6,
+    },
+    // 24
+    Topology {
+        carriers: &[1, 2, 3, 4, 5],
+        connections: &[(6, 3), (6, 4), (6, 5)],
+        feedback_op: 6,
+    },
+    // 25
+    Topology {
+        carriers: &[1, 2, 3, 4, 5],
+        connections: &[(6, 4), (6, 5)],
+        feedback_op: 6,
+    },
+    // 26
+    Topology {
+        carriers: &[1, 2, 4],
+        connections: &[(3, 2), (5, 4), (6, 4)],
+        feedback_op: 6,
+    },
+    // 27
+    Topology {
+        carriers: &[1, 2, 4],
+        connections: &[(3, 2), (5, 4), (6, 4)],
+        feedback_op: 3,
+    },
+    // 28
+    Topology {
+        carriers: &[1, 3, 6],
+        connections: &[(2, 1), (4, 3), (5, 4)],
+        feedback_op: 5,
+    },
+    // 29
+    Topology {
+        carriers: &[1, 2, 3, 5],
+        connections: &[(4, 3), (6, 5)],
+        feedback_op: 6,
+    },
+    // 30
+    Topology {
+        carriers: &[1, 2, 3, 6],
+        connections: &[(4, 3), (5, 4)],
+        feedback_op: 5,
+    },
+    // 31
+    Topology {
+        carriers: &[1, 2, 3, 4, 5],
+        connections: &[(6, 5)],
+        feedback_op: 6,
+    },
+    // 32
+    Topology {
+        carriers: &[1, 2, 3, 4, 5, 6],
+        connections: &[],
+        feedback_op: 6,
+    },
+];
+
+/// Topology of `algorithm_number`; out-of-range numbers fall back to 1.
+pub(crate) fn topology(algorithm_number: u8) -> &'static Topology {
+    match algorithm_number {
+        1..=32 => &DX7_ALGORITHMS[(algorithm_number - 1) as usize],
+        _ => &DX7_ALGORITHMS[0],
+    }
+}
+
 /// Get algorithm structure for visualization
 pub fn get_algorithm_info(algorithm_number: u8) -> AlgorithmInfo {
-    match algorithm_number {
-        1 => AlgorithmInfo {
-            carriers: vec![1, 3],
-            connections: vec![(2, 1), (4, 3), (5, 4), (6, 5)],
-            feedback_op: 6,
-        },
-        2 => AlgorithmInfo {
-            carriers: vec![1, 3],
-            connections: vec![(2, 1), (4, 3), (5, 4), (6, 5)],
-            feedback_op: 2,
-        },
-        3 => AlgorithmInfo {
-            carriers: vec![1, 4],
-            connections: vec![(2, 1), (3, 2), (5, 4), (6, 5)],
-            feedback_op: 6,
-        },
-        4 => AlgorithmInfo {
-            carriers: vec![1, 4],
-            connections: vec![(3, 2), (2, 1), (6, 5), (5, 4)],
-            feedback_op: 4, // Cross-feedback: Op4→Op6 loop
-        },
-        5 => AlgorithmInfo {
-            carriers: vec![1, 3, 5],
-            connections: vec![(2, 1), (4, 3), (6, 5)],
-            feedback_op: 6,
-        },
-        6 => AlgorithmInfo {
-            carriers: vec![1, 3, 5],
-            connections: vec![(2, 1), (4, 3), (6, 5)],
-            feedback_op: 6, // Cross-feedback: Op5→Op6 loop
-        },
-        7 => AlgorithmInfo {
-            carriers: vec![1, 3],
-            connections: vec![(2, 1), (4, 3), (5, 3), (6, 5)],
-            feedback_op: 6,
-        },
-        8 => AlgorithmInfo {
-            carriers: vec![1, 3],
-            connections: vec![(2, 1), (4, 3), (5, 3), (6, 5)],
-            feedback_op: 4,
-        },
-        9 => AlgorithmInfo {
-            carriers: vec![1, 3],
-            connections: vec![(2, 1), (4, 3), (5, 3), (6, 5)],
-            feedback_op: 2,
-        },
-        10 => AlgorithmInfo {
-            carriers: vec![1, 4],
-            connections: vec![(2, 1), (3, 2), (5, 4), (6, 4)],
-            feedback_op: 3,
-        },
-        11 => AlgorithmInfo {
-            carriers: vec![1, 4],
-            connections: vec![(2, 1), (3, 2), (5, 4), (6, 4)],
-            feedback_op: 6,
-        },
-        12 => AlgorithmInfo {
-            carriers: vec![1, 3],
-            connections: vec![(2, 1), (4, 3), (5, 3), (6, 3)],
-            feedback_op: 2,
-        },
-        13 => AlgorithmInfo {
-            carriers: vec![1, 3],
-            connections: vec![(2, 1), (4, 3), (5, 3), (6, 3)],
-            feedback_op: 6,
-        },
-        14 => AlgorithmInfo {
-            carriers: vec![1, 3],
-            connections: vec![(2, 1), (4, 3), (5, 4), (6, 4)],
-            feedback_op: 6,
-        },
-        15 => AlgorithmInfo {
-            carriers: vec![1, 3],
-            connections: vec![(2, 1), (4, 3), (5, 4), (6, 4)],
-            feedback_op: 2,
-        },
-        16 => AlgorithmInfo {
-            carriers: vec![1],
-            connections: vec![(2, 1), (3, 1), (4, 3), (5, 1), (6, 5)],
-            feedback_op: 6,
-        },
-        17 => AlgorithmInfo {
-            carriers: vec![1],
-            connections: vec![(2, 1), (3, 1), (4, 3), (5, 1), (6, 5)],
-            feedback_op: 2,
-        },
-        18 => AlgorithmInfo {
-            carriers: vec![1],
-            connections: vec![(2, 1), (3, 1), (4, 1), (5, 4), (6, 5)],
-            feedback_op: 3,
-        },
-        19 => AlgorithmInfo {
-            carriers: vec![1, 4, 5],
-            connections: vec![(3, 2), (2, 1), (6, 5), (6, 4)],
-            feedback_op: 6,
-        },
-        20 => AlgorithmInfo {
-            carriers: vec![1, 2, 4],
-            connections: vec![(3, 1), (3, 2), (5, 4), (6, 4)],
-            feedback_op: 3,
-        },
-        21 => AlgorithmInfo {
-            carriers: vec![1, 2, 4, 5],
-            connections: vec![(3, 1), (3, 2), (6, 4), (6, 5)],
-            feedback_op: 3,
-        },
-        22 => AlgorithmInfo {
-            carriers: vec![1, 3, 4, 5],
-            connections: vec![(2, 1), (6, 3), (6, 4), (6, 5)],
-            feedback_op: 6,
-        },
-        23 => AlgorithmInfo {
-            carriers: vec![1, 2, 4, 5],
-            connections: vec![(3, 2), (6, 4), (6, 5)],
-            feedback_op: 6,
-        },
-        24 => AlgorithmInfo {
-            carriers: vec![1, 2, 3, 4, 5],
-            connections: vec![(6, 3), (6, 4), (6, 5)],
-            feedback_op: 6,
-        },
-        25 => AlgorithmInfo {
-            carriers: vec![1, 2, 3, 4, 5],
-            connections: vec![(6, 4), (6, 5)],
-            feedback_op: 6,
-        },
-        26 => AlgorithmInfo {
-            carriers: vec![1, 2, 4],
-            connections: vec![(3, 2), (5, 4), (6, 4)],
-            feedback_op: 6,
-        },
-        27 => AlgorithmInfo {
-            carriers: vec![1, 2, 4],
-            connections: vec![(3, 2), (5, 4), (6, 4)],
-            feedback_op: 3,
-        },
-        28 => AlgorithmInfo {
-            carriers: vec![1, 3, 6],
-            connections: vec![(2, 1), (4, 3), (5, 4)],
-            feedback_op: 5,
-        },
-        29 => AlgorithmInfo {
-            carriers: vec![1, 2, 3, 5],
-            connections: vec![(4, 3), (6, 5)],
-            feedback_op: 6,
-        },
-        30 => AlgorithmInfo {
-            carriers: vec![1, 2, 3, 6],
-            connections: vec![(4, 3), (5, 4)],
-            feedback_op: 5,
-        },
-        31 => AlgorithmInfo {
-            carriers: vec![1, 2, 3, 4, 5],
-            connections: vec![(6, 5)],
-            feedback_op: 6,
-        },
-        32 => AlgorithmInfo {
-            carriers: vec![1, 2, 3, 4, 5, 6],
-            connections: vec![],
-            feedback_op: 6,
-        },
-        _ => get_algorithm_info(1),
+    let topology = topology(algorithm_number);
+    AlgorithmInfo {
+        carriers: topology.carriers.to_vec(),
+        connections: topology.connections.to_vec(),
+        feedback_op: topology.feedback_op,
     }
 }
 
@@ -825,6 +970,12 @@ mod tests {
         ops
     }
 
+    fn tag_sub_bus(ops: &mut [Operator; 6], tagged: &[usize]) {
+        for &i in tagged {
+            ops[i].sub_bus = true;
+        }
+    }
+
     fn run_algorithm_for_samples(alg: u8, samples: usize) -> (f32, f32) {
         let mut ops = triggered_ops();
         // Warm up envelope to steady state
@@ -980,4 +1131,209 @@ mod tests {
         }
         assert!(diff > 100, "alg 6 cross feedback should differ ({diff})");
     }
+
+    /// Operators at f0 = SR / 100 with flat envelopes: every harmonic falls
+    /// on a DFT bin of a whole number of periods.
+    fn steady_ops(levels: [f32; 6]) -> [Operator; 6] {
+        let mut ops = build_ops();
+        for (op, level) in ops.iter_mut().zip(levels) {
+            op.output_level = level;
+            op.envelope.rate1 = 99.0;
+            op.envelope.level1 = 99.0;
+            op.envelope.level2 = 99.0;
+            op.envelope.level3 = 99.0;
+            op.trigger(SR / 100.0, 1.0, 60);
+        }
+        ops
+    }
+
+    /// Magnitudes of harmonics 1 – 49 of a `steady_ops` signal, and the
+    /// share of its energy that falls between harmonics.
+    fn harmonic_spectrum(mut next: impl FnMut() -> f32) -> (Vec<f32>, f32) {
+        const PERIOD: usize = 100;
+        const N: usize = PERIOD * 44;
+        for _ in 0..N {
+            next();
+        }
+        let x: Vec<f32> = (0..N).map(|_| next()).collect();
+        let total: f32 = x.iter().map(|s| s * s).sum();
+        let bin = |k: usize| {
+            let (mut re, mut im) = (0.0_f32, 0.0_f32);
+            for (n, s) in x.iter().enumerate() {
+                let w = 2.0 * std::f32::consts::PI * ((k * n) % PERIOD) as f32 / PERIOD as f32;
+                re += s * w.cos();
+                im -= s * w.sin();
+            }
+            re * re + im * im
+        };
+        // Parseval: DC holds |X0|²/N of the energy, harmonic k twice |Xk|²/N.
+        let mut harmonic_energy = bin(0) / N as f32;
+        let mags = (1..PERIOD / 2)
+            .map(|k| {
+                let power = bin(k);
+                harmonic_energy += 2.0 * power / N as f32;
+                2.0 * power.sqrt() / N as f32
+            })
+            .collect();
+        (mags, 1.0 - harmonic_energy / total)
+    }
+
+    fn overtones(mags: &[f32]) -> f32 {
+        mags[3..].iter().sum()
+    }
+
+    #[test]
+    fn feedback_loops_keep_carrier_spectra_harmonic_and_add_overtones() {
+        // Only the looped stack sounds: the 4→6→5→4 stack of algorithm 4
+        // and the 5↔6 pair of algorithm 6, modulators at a moderate index.
+        for (alg, levels) in [
+            (4, [0.0, 0.0, 0.0, 99.0, 70.0, 70.0]),
+            (6, [0.0, 0.0, 0.0, 0.0, 99.0, 70.0]),
+        ] {
+            let mut dry = steady_ops(levels);
+            let mut looped = steady_ops(levels);
+            looped[5].feedback = 7.0;
+            let (dry_mags, dry_noise) = harmonic_spectrum(|| process_algorithm(alg, &mut dry));
+            let (loop_mags, loop_noise) = harmonic_spectrum(|| process_algorithm(alg, &mut looped));
+            assert!(dry_noise.abs() < 1e-3, "alg {alg} dry: {dry_noise}");
+            assert!(loop_noise.abs() < 1e-3, "alg {alg} loop: {loop_noise}");
+            assert!(
+                overtones(&loop_mags) > overtones(&dry_mags) * 1.2,
+                "alg {alg}: {} vs {}",
+                overtones(&loop_mags),
+                overtones(&dry_mags)
+            );
+        }
+    }
+
+    #[test]
+    fn algorithm_4_loop_depth_is_shared_by_the_loop_operators() {
+        // SysEx loads FEEDBACK on OP6; the diagram edits it on OP4. Both
+        // drive the same OP4 → OP6 loop instead of OP6 self-feedback.
+        let levels = [0.0, 0.0, 0.0, 99.0, 70.0, 70.0];
+        let mut on_op4 = steady_ops(levels);
+        let mut on_op6 = steady_ops(levels);
+        on_op4[3].feedback = 6.0;
+        on_op6[5].feedback = 6.0;
+        for _ in 0..4096 {
+            assert_eq!(
+                process_algorithm(4, &mut on_op4),
+                process_algorithm(4, &mut on_op6)
+            );
+        }
+
+        // The OP6 self-feedback this replaced gives a different spectrum.
+        let mut self_fb = steady_ops(levels);
+        self_fb[5].feedback = 6.0;
+        let mut looped = steady_ops(levels);
+        looped[5].feedback = 6.0;
+        let (loop_mags, _) = harmonic_spectrum(|| process_algorithm(4, &mut looped));
+        let (self_mags, _) = harmonic_spectrum(|| {
+            let op6 = self_fb[5].process(0.0);
+            let op5 = self_fb[4].process(op6);
+            self_fb[3].process(op5) * 0.71
+        });
+        let distance: f32 = loop_mags
+            .iter()
+            .zip(&self_mags)
+            .map(|(a, b)| (a - b).abs())
+            .sum();
+        assert!(
+            distance > 0.05,
+            "loop and self-feedback spectra match: {distance}"
+        );
+    }
+
+    #[test]
+    fn silencing_the_carrier_that_closes_the_loop_opens_it() {
+        // The loop is fed by the carrier's output: with that carrier at
+        // level 0, the top modulator runs exactly as with no feedback.
+        for (alg, closing) in [(4, 3), (6, 4)] {
+            let mut levels = [99.0; 6];
+            levels[closing] = 0.0;
+            let mut dry = steady_ops(levels);
+            let mut looped = steady_ops(levels);
+            looped[5].feedback = 7.0;
+            for _ in 0..2048 {
+                process_algorithm(alg, &mut dry);
+                process_algorithm(alg, &mut looped);
+                assert_eq!(dry[5].current_output(), looped[5].current_output());
+            }
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Sub-bus routing
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn solo_plays_any_operator_as_a_carrier() {
+        let mut ops = triggered_ops();
+        for _ in 0..256 {
+            process_algorithm(1, &mut ops);
+            // OP2 modulates OP1 in algorithm 1; alone it sounds at full gain.
+            assert_eq!(solo_output(0b10, &ops), ops[1].current_output());
+            let pair = (ops[1].current_output() + ops[5].current_output()) * 0.71;
+            assert_eq!(solo_output(0b10_0010, &ops), pair);
+        }
+    }
+
+    #[test]
+    fn carrier_masks_match_algorithm_info() {
+        for alg in 1..=32u8 {
+            let expected = get_algorithm_info(alg)
+                .carriers
+                .iter()
+                .fold(0u8, |mask, c| mask | (1 << (c - 1)));
+            assert_eq!(carrier_mask(alg), expected, "alg {alg}");
+        }
+    }
+
+    #[test]
+    fn split_without_tags_matches_process_algorithm() {
+        let mut plain = triggered_ops();
+        let mut split = triggered_ops();
+        for _ in 0..512 {
+            let a = process_algorithm(5, &mut plain);
+            let (main, sub) = process_algorithm_split(5, &mut split);
+            assert_eq!(a, main);
+            assert_eq!(sub, 0.0);
+        }
+    }
+
+    #[test]
+    fn tagging_every_carrier_moves_whole_output_to_sub_bus() {
+        for alg in 1..=32u8 {
+            let mut plain = triggered_ops();
+            let mut split = triggered_ops();
+            let carriers: Vec<usize> = get_algorithm_info(alg)
+                .carriers
+                .iter()
+                .map(|c| (*c - 1) as usize)
+                .collect();
+            tag_sub_bus(&mut split, &carriers);
+            for _ in 0..256 {
+                let a = process_algorithm(alg, &mut plain);
+                let (main, sub) = process_algorithm_split(alg, &mut split);
+                assert!(main.abs() < 1e-4, "alg {alg}: main leaked {main}");
+                assert!((sub - a).abs() < 1e-4, "alg {alg}: sub {sub} vs {a}");
+            }
+        }
+    }
+
+    #[test]
+    fn tagged_modulator_still_modulates_and_feeds_sub_bus() {
+        let mut plain = triggered_ops();
+        let mut split = triggered_ops();
+        // Algorithm 1: Op2 modulates carrier Op1.
+        tag_sub_bus(&mut split, &[1]);
+        let mut sub_energy = 0.0;
+        for _ in 0..512 {
+            let a = process_algorithm(1, &mut plain);
+            let (main, sub) = process_algorithm_split(1, &mut split);
+            assert_eq!(a, main, "modulator tag must not change the main sum");
+            sub_energy += sub.abs();
+        }
+        assert!(sub_energy > 1.0, "sub bus should carry Op2 ({sub_energy})");
+    }
 }
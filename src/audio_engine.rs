@@ -1,14 +1,201 @@
-use crate::fm_synth::SynthEngine;
+use crate::fm_synth::{SynthController, SynthEngine};
+use crate::keybindings::config_dir;
+use crate::lock_free::{Handoff, HandoffCell};
+use crate::state_snapshot::AudioStats;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
-/// System default-output audio probe. Captures `device + config` so the
-/// sample rate can be read up front and the same handles reused at stream
-/// construction — avoids querying the OS twice at startup.
+/// A synth engine owned by the audio side: the stream callback borrows it
+/// every buffer, the command pump and stream rebuilds only while the
+/// callback is not running. GUI, MIDI and preset changes reach it through
+/// its `SynthController`.
+type SharedEngine = Arc<HandoffCell<SynthEngine>>;
+
+/// How often the command pump checks whether the stream is still calling back.
+const PUMP_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The pump only declares a stall after this many buffer lengths without a
+/// callback, so large buffers (4096 frames is ~90 ms) don't read as stalled
+/// between two beats.
+const STALL_BUFFERS: u32 = 3;
+
+/// Frames per `process_block` call. Scratch buffers of this size are
+/// allocated once per stream; larger host buffers are rendered in pieces.
+const RENDER_BLOCK_FRAMES: usize = 1024;
+
+/// A callback arriving more than this many buffer lengths after the previous
+/// one means the device ran out of samples in between.
+const XRUN_GAP_FACTOR: f64 = 1.5;
+
+/// Per-callback smoothing of the CPU load estimate.
+const LOAD_SMOOTHING: f32 = 0.05;
+
+/// Sample rates offered on the AUDIO page, filtered per device against the
+/// ranges it reports.
+pub const COMMON_SAMPLE_RATES: [u32; 6] = [22_050, 32_000, 44_100, 48_000, 88_200, 96_000];
+
+/// Buffer sizes (frames) offered on the AUDIO page.
+pub const COMMON_BUFFER_SIZES: [u32; 7] = [64, 128, 256, 512, 1024, 2048, 4096];
+
+/// File name of the saved AUDIO page settings inside the config directory.
+const SETTINGS_FILE: &str = "audio.json";
+
+/// Output device and stream format requested by the user. `None` fields
+/// fall back to the platform default host, its default device and that
+/// device's default config.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// Audio host (backend) by cpal name, e.g. "ALSA", "JACK", "WASAPI",
+    /// "ASIO". JACK and ASIO are only offered when built with the `jack` /
+    /// `asio` features.
+    pub host: Option<String>,
+    pub device: Option<String>,
+    pub sample_rate: Option<u32>,
+    /// Frames per callback.
+    pub buffer_size: Option<u32>,
+}
+
+impl AudioSettings {
+    /// Default location of the settings file, or None when no config
+    /// directory can be determined.
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(SETTINGS_FILE))
+    }
+
+    /// Load settings from `path`, falling back to the defaults when the file
+    /// is missing or unreadable.
+    pub fn load_or_default(path: &Path) -> Self {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("Invalid audio settings file {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+/// An output device as reported by the host, for the device picker.
+#[derive(Clone, Debug)]
+pub struct OutputDeviceInfo {
+    pub name: String,
+    pub is_default: bool,
+    pub default_sample_rate: u32,
+    /// The entries of `COMMON_SAMPLE_RATES` the device accepts.
+    pub sample_rates: Vec<u32>,
+    /// Accepted buffer sizes in frames, when the host reports them.
+    pub buffer_size_range: Option<(u32, u32)>,
+}
+
+/// Device, rate and buffer size of the stream that is actually open.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioStreamInfo {
+    pub host: String,
+    pub device: String,
+    pub sample_rate: u32,
+    /// `None` when the host picks the buffer size.
+    pub buffer_size: Option<u32>,
+}
+
+/// Output latency of one buffer, in milliseconds.
+pub fn buffer_latency_ms(frames: u32, sample_rate: u32) -> f32 {
+    frames as f32 * 1000.0 / sample_rate.max(1) as f32
+}
+
+fn device_name(device: &cpal::Device) -> Option<String> {
+    device.description().ok().map(|d| d.name().to_string())
+}
+
+/// Names of the audio hosts compiled into this build and usable on this
+/// machine; the platform default comes first.
+pub fn available_hosts() -> Vec<String> {
+    let default = cpal::default_host().id();
+    let mut ids = cpal::available_hosts();
+    ids.sort_by_key(|id| *id != default);
+    ids.into_iter().map(|id| id.name().to_string()).collect()
+}
+
+/// The host named `name` (case-insensitive), or the platform default.
+fn open_host(name: Option<&str>) -> Result<cpal::Host, String> {
+    let Some(name) = name else {
+        return Ok(cpal::default_host());
+    };
+    let id = cpal::available_hosts()
+        .into_iter()
+        .find(|id| id.name().eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            format!(
+                "Audio host not available: {name} (this build offers {})",
+                available_hosts().join(", ")
+            )
+        })?;
+    cpal::host_from_id(id).map_err(|e| e.to_string())
+}
+
+/// Enumerate the output devices of `host` (the default host when `None`).
+/// Devices that fail to report a name or a default config are skipped.
+pub fn list_output_devices(host: Option<&str>) -> Vec<OutputDeviceInfo> {
+    let Ok(host) = open_host(host) else {
+        return Vec::new();
+    };
+    let default_name = host.default_output_device().as_ref().and_then(device_name);
+    let Ok(devices) = host.output_devices() else {
+        return Vec::new();
+    };
+
+    devices
+        .filter_map(|device| {
+            let name = device_name(&device)?;
+            let default_config = device.default_output_config().ok()?;
+            let ranges: Vec<_> = device
+                .supported_output_configs()
+                .map(|ranges| ranges.collect())
+                .unwrap_or_default();
+            let sample_rates = COMMON_SAMPLE_RATES
+                .into_iter()
+                .filter(|rate| {
+                    ranges
+                        .iter()
+                        .any(|r| (r.min_sample_rate()..=r.max_sample_rate()).contains(rate))
+                })
+                .collect();
+            let buffer_size_range = match *default_config.buffer_size() {
+                cpal::SupportedBufferSize::Range { min, max } => Some((min, max)),
+                cpal::SupportedBufferSize::Unknown => None,
+            };
+            Some(OutputDeviceInfo {
+                is_default: default_name.as_ref() == Some(&name),
+                name,
+                default_sample_rate: default_config.sample_rate(),
+                sample_rates,
+                buffer_size_range,
+            })
+        })
+        .collect()
+}
+
+/// Output audio probe. Captures `device + config` so the sample rate can be
+/// read up front and the same handles reused at stream construction —
+/// avoids querying the OS twice at startup.
 pub struct AudioProbe {
+    host: String,
     device: cpal::Device,
     config: cpal::SupportedStreamConfig,
+    buffer_size: cpal::BufferSize,
 }
 
 impl AudioProbe {
@@ -23,7 +210,73 @@ impl AudioProbe {
         let host = cpal::default_host();
         let device = host.default_output_device()?;
         let config = device.default_output_config().ok()?;
-        Some(Self { device, config })
+        Some(Self {
+            host: host.id().name().to_string(),
+            device,
+            config,
+            buffer_size: cpal::BufferSize::Default,
+        })
+    }
+
+    /// Resolve `settings` against the host: find the named device, a config
+    /// that supports the requested rate (preferring the default config's
+    /// channel count and sample format) and check the buffer size against
+    /// the range the device reports.
+    pub fn with_settings(settings: &AudioSettings) -> Result<Self, String> {
+        let host = open_host(settings.host.as_deref())?;
+        let device = match &settings.device {
+            None => host
+                .default_output_device()
+                .ok_or("No output device available")?,
+            Some(name) => host
+                .output_devices()
+                .map_err(|e| e.to_string())?
+                .find(|d| device_name(d).as_deref() == Some(name.as_str()))
+                .ok_or_else(|| format!("Output device not found: {name}"))?,
+        };
+        let default = device.default_output_config().map_err(|e| e.to_string())?;
+
+        let config = match settings.sample_rate {
+            None => default,
+            Some(rate) if rate == default.sample_rate() => default,
+            Some(rate) => {
+                let ranges: Vec<_> = device
+                    .supported_output_configs()
+                    .map_err(|e| e.to_string())?
+                    .collect();
+                let same_shape = |r: &cpal::SupportedStreamConfigRange| {
+                    r.channels() == default.channels()
+                        && r.sample_format() == default.sample_format()
+                };
+                ranges
+                    .iter()
+                    .filter(|r| same_shape(r))
+                    .chain(ranges.iter().filter(|r| !same_shape(r)))
+                    .find_map(|r| r.try_with_sample_rate(rate))
+                    .ok_or_else(|| format!("{rate} Hz is not supported by this device"))?
+            }
+        };
+
+        let buffer_size = match settings.buffer_size {
+            None => cpal::BufferSize::Default,
+            Some(frames) => {
+                if let cpal::SupportedBufferSize::Range { min, max } = *config.buffer_size() {
+                    if !(min..=max).contains(&frames) {
+                        return Err(format!(
+                            "Buffer size {frames} is outside the device range {min}..{max}"
+                        ));
+                    }
+                }
+                cpal::BufferSize::Fixed(frames)
+            }
+        };
+
+        Ok(Self {
+            host: host.id().name().to_string(),
+            device,
+            config,
+            buffer_size,
+        })
     }
 
     pub fn sample_rate(&self) -> f32 {
@@ -31,80 +284,434 @@ impl AudioProbe {
     }
 }
 
+/// Liveness of the output stream, shared between the audio callback and the
+/// command pump. The callback bumps `heartbeat` once per buffer; the pump
+/// declares the stream stopped when it sees no beat for `stall_timeout`.
+/// The callback also records how many frames the host asked for, which is
+/// the only way to learn the buffer size when the host picks it.
+#[derive(Default)]
+pub struct StreamStatus {
+    heartbeat: AtomicU64,
+    running: AtomicBool,
+    callback_frames: AtomicUsize,
+    /// Length of the most recent callback's buffer, in nanoseconds.
+    buffer_nanos: AtomicU64,
+}
+
+impl StreamStatus {
+    fn beat(&self) {
+        self.heartbeat.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_frames(&self, frames: usize, buffer: Duration) {
+        self.callback_frames.store(frames, Ordering::Relaxed);
+        self.buffer_nanos
+            .store(buffer.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// How long the pump waits without a beat before calling the stream
+    /// stalled: one pump interval, or `STALL_BUFFERS` buffers if longer.
+    fn stall_timeout(&self, interval: Duration) -> Duration {
+        let buffer = Duration::from_nanos(self.buffer_nanos.load(Ordering::Relaxed));
+        interval.max(buffer * STALL_BUFFERS)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Frames in the most recent callback, or None before the first one.
+    pub fn callback_frames(&self) -> Option<usize> {
+        Some(self.callback_frames.load(Ordering::Relaxed)).filter(|&n| n > 0)
+    }
+}
+
+/// The pump's view of the heartbeat: when it last saw the count move.
+struct StallCheck {
+    last_beat: u64,
+    last_beat_seen: Instant,
+}
+
+impl StallCheck {
+    fn new(beat: u64, now: Instant) -> Self {
+        Self {
+            last_beat: beat,
+            last_beat_seen: now,
+        }
+    }
+
+    /// Note the heartbeat count `beat` read at `now`; true while the stream
+    /// has beaten at all and not gone `timeout` without a beat.
+    fn is_running(&mut self, beat: u64, now: Instant, timeout: Duration) -> bool {
+        if beat != self.last_beat {
+            self.last_beat = beat;
+            self.last_beat_seen = now;
+        }
+        beat != 0 && now.duration_since(self.last_beat_seen) < timeout
+    }
+}
+
+/// Callback timing for one stream: spots xruns from the gaps between
+/// callback timestamps and from renders that outlast their buffer, and
+/// keeps a smoothed render-time / buffer-time load.
+#[derive(Default)]
+struct CallbackTimer {
+    last_callback: Option<cpal::StreamInstant>,
+    load: f32,
+}
+
+impl CallbackTimer {
+    /// Note a callback at `now`; true if it came too late after the
+    /// previous one for a buffer of length `buffer`.
+    fn arrived(&mut self, now: cpal::StreamInstant, buffer: Duration) -> bool {
+        self.last_callback
+            .replace(now)
+            .and_then(|previous| now.duration_since(&previous))
+            .is_some_and(|gap| gap.as_secs_f64() > buffer.as_secs_f64() * XRUN_GAP_FACTOR)
+    }
+
+    /// Fold one render time into the load; true if it missed the deadline.
+    fn rendered(&mut self, elapsed: Duration, buffer: Duration) -> bool {
+        let load = (elapsed.as_secs_f64() / buffer.as_secs_f64().max(1e-9)) as f32;
+        self.load += (load - self.load) * LOAD_SMOOTHING;
+        load > 1.0
+    }
+}
+
+/// Borrow an engine whose stream is closed. Only the command pump can hold
+/// it then, and only for one drain, so this waits at most that long.
+fn borrow_without_stream(engine: &HandoffCell<SynthEngine>) -> Handoff<'_, SynthEngine> {
+    loop {
+        match engine.try_borrow() {
+            Some(synth) => return synth,
+            None => std::thread::yield_now(),
+        }
+    }
+}
+
+/// Count one underrun, with a log line every 500.
+fn count_underrun(counter: &AtomicUsize) {
+    let count = counter.fetch_add(1, Ordering::Relaxed) + 1;
+    if count == 1 || count.is_multiple_of(500) {
+        log::warn!("AUDIO WARNING: {} buffer underruns detected", count);
+    }
+}
+
+/// Fallback command processing for when the audio callback is not running
+/// (device stopped, stream paused, host suspended). Commands are normally
+/// drained at the start of each buffer; without this, the queue fills up and
+/// GUI edits silently stop taking effect. While the stream is stalled the
+/// pump drains commands and publishes snapshots itself, and drains the
+/// preview engine's queue too; as soon as the callback beats again it steps
+/// aside.
+pub struct CommandPump {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl CommandPump {
+    pub fn spawn(
+        engine: SharedEngine,
+        preview: Option<SharedEngine>,
+        status: Arc<StreamStatus>,
+        interval: Duration,
+    ) -> Self {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let stop = shutdown.clone();
+        let handle = std::thread::spawn(move || {
+            let mut check =
+                StallCheck::new(status.heartbeat.load(Ordering::Relaxed), Instant::now());
+            loop {
+                std::thread::sleep(interval);
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let running = check.is_running(
+                    status.heartbeat.load(Ordering::Relaxed),
+                    Instant::now(),
+                    status.stall_timeout(interval),
+                );
+                if status.running.swap(running, Ordering::Relaxed) != running {
+                    log::info!(
+                        "Audio stream {}",
+                        if running { "running" } else { "stalled" }
+                    );
+                }
+                if !running {
+                    if let Some(mut synth) = engine.try_borrow() {
+                        synth.process_commands();
+                        synth.update_snapshot();
+                    }
+                    if let Some(mut synth) = preview.as_ref().and_then(|p| p.try_borrow()) {
+                        synth.process_commands();
+                    }
+                }
+            }
+        });
+        Self {
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for CommandPump {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
 pub struct AudioEngine {
-    _stream: cpal::Stream,
-    _underrun_counter: Arc<AtomicUsize>,
+    /// `None` after a failed `reconfigure`; the command pump keeps edits
+    /// flowing until a stream is opened again.
+    stream: Option<cpal::Stream>,
+    info: AudioStreamInfo,
+    engine: SharedEngine,
+    preview: Option<SharedEngine>,
+    underrun_counter: Arc<AtomicUsize>,
+    status: Arc<StreamStatus>,
+    _pump: CommandPump,
 }
 
 impl AudioEngine {
+    /// Takes ownership of the engines: from here on they are only driven
+    /// through their controllers. `preview` is an optional second engine
+    /// (preset audition) mixed on top of the main one.
     pub fn new(
         probe: AudioProbe,
-        engine: Arc<Mutex<SynthEngine>>,
+        engine: SynthEngine,
+        preview: Option<SynthEngine>,
         underrun_counter: Arc<AtomicUsize>,
     ) -> Self {
-        let AudioProbe { device, config } = probe;
-        let sample_rate = config.sample_rate();
+        let engine = Arc::new(HandoffCell::new(engine));
+        let preview = preview.map(|p| Arc::new(HandoffCell::new(p)));
+        let status = Arc::new(StreamStatus::default());
+        let pump = CommandPump::spawn(
+            engine.clone(),
+            preview.clone(),
+            status.clone(),
+            PUMP_INTERVAL,
+        );
 
-        let stream = match config.sample_format() {
-            cpal::SampleFormat::F32 => {
-                Self::build_stream::<f32>(&device, &config.into(), engine, underrun_counter.clone())
-            }
-            cpal::SampleFormat::I16 => {
-                Self::build_stream::<i16>(&device, &config.into(), engine, underrun_counter.clone())
-            }
-            cpal::SampleFormat::U16 => {
-                Self::build_stream::<u16>(&device, &config.into(), engine, underrun_counter.clone())
-            }
-            format => panic!("Unsupported sample format: {:?}", format),
+        let (stream, info) = Self::open_stream(
+            probe,
+            engine.clone(),
+            preview.clone(),
+            underrun_counter.clone(),
+            status.clone(),
+        )
+        .unwrap_or_else(|e| panic!("Failed to start audio stream: {e}"));
+
+        Self {
+            stream: Some(stream),
+            info,
+            engine,
+            preview,
+            underrun_counter,
+            status,
+            _pump: pump,
+        }
+    }
+
+    /// True while the output stream is calling back.
+    pub fn is_stream_running(&self) -> bool {
+        self.status.is_running()
+    }
+
+    /// Device and format of the open (or last opened) stream.
+    pub fn stream_info(&self) -> &AudioStreamInfo {
+        &self.info
+    }
+
+    /// Frames per buffer and the latency they add, in milliseconds: the
+    /// requested buffer size, else what the host has been asking for per
+    /// callback. None until the first callback with a host-chosen size.
+    pub fn buffer_latency(&self) -> Option<(u32, f32)> {
+        let frames = self
+            .info
+            .buffer_size
+            .or_else(|| self.status.callback_frames().map(|n| n as u32))?;
+        Some((frames, buffer_latency_ms(frames, self.info.sample_rate)))
+    }
+
+    /// Reopen the output with `settings` and retune the synth engines for
+    /// the new sample rate (operator phase increments, envelopes, effect
+    /// buffers). The old stream is closed before the new one is built, as
+    /// some hosts open devices exclusively. Returns the new sample rate.
+    pub fn reconfigure(&mut self, settings: &AudioSettings) -> Result<f32, String> {
+        let probe = AudioProbe::with_settings(settings)?;
+        let sample_rate = probe.sample_rate();
+
+        self.stream = None;
+        for engine in std::iter::once(&self.engine).chain(self.preview.as_ref()) {
+            borrow_without_stream(engine).set_sample_rate(sample_rate);
+        }
+
+        let (stream, info) = Self::open_stream(
+            probe,
+            self.engine.clone(),
+            self.preview.clone(),
+            self.underrun_counter.clone(),
+            self.status.clone(),
+        )?;
+        self.stream = Some(stream);
+        self.info = info;
+        Ok(sample_rate)
+    }
+
+    fn open_stream(
+        probe: AudioProbe,
+        engine: SharedEngine,
+        preview: Option<SharedEngine>,
+        underrun_counter: Arc<AtomicUsize>,
+        status: Arc<StreamStatus>,
+    ) -> Result<(cpal::Stream, AudioStreamInfo), String> {
+        let AudioProbe {
+            host,
+            device,
+            config,
+            buffer_size,
+        } = probe;
+        status.record_frames(0, Duration::ZERO);
+        let info = AudioStreamInfo {
+            host,
+            device: device_name(&device).unwrap_or_else(|| "Unknown device".to_string()),
+            sample_rate: config.sample_rate(),
+            buffer_size: match buffer_size {
+                cpal::BufferSize::Fixed(frames) => Some(frames),
+                cpal::BufferSize::Default => None,
+            },
         };
+        let sample_format = config.sample_format();
+        let mut stream_config: cpal::StreamConfig = config.into();
+        stream_config.buffer_size = buffer_size;
 
-        stream.play().expect("Failed to start audio stream");
+        let stream = match sample_format {
+            cpal::SampleFormat::F32 => Self::build_stream::<f32>(
+                &device,
+                &stream_config,
+                engine,
+                preview,
+                underrun_counter,
+                status,
+            ),
+            cpal::SampleFormat::I16 => Self::build_stream::<i16>(
+                &device,
+                &stream_config,
+                engine,
+                preview,
+                underrun_counter,
+                status,
+            ),
+            cpal::SampleFormat::U16 => Self::build_stream::<u16>(
+                &device,
+                &stream_config,
+                engine,
+                preview,
+                underrun_counter,
+                status,
+            ),
+            format => return Err(format!("Unsupported sample format: {:?}", format)),
+        }?;
+
+        stream.play().map_err(|e| e.to_string())?;
 
         log::info!(
-            "Audio engine initialized with {} Hz sample rate",
-            sample_rate
+            "Audio stream opened on {} ({}) at {} Hz (buffer: {})",
+            info.device,
+            info.host,
+            info.sample_rate,
+            info.buffer_size
+                .map_or_else(|| "default".to_string(), |frames| frames.to_string())
         );
 
-        Self {
-            _stream: stream,
-            _underrun_counter: underrun_counter,
-        }
+        Ok((stream, info))
     }
 
     fn build_stream<T>(
         device: &cpal::Device,
         config: &cpal::StreamConfig,
-        engine: Arc<Mutex<SynthEngine>>,
+        engine: SharedEngine,
+        preview: Option<SharedEngine>,
         underrun_counter: Arc<AtomicUsize>,
-    ) -> cpal::Stream
+        status: Arc<StreamStatus>,
+    ) -> Result<cpal::Stream, String>
     where
         T: cpal::Sample + cpal::SizedSample + cpal::FromSample<f32>,
     {
         let channels = config.channels as usize;
-        let mut samples_since_snapshot = 0u32;
+        let sample_rate = config.sample_rate as f64;
+        let mut timer = CallbackTimer::default();
+        let mut samples_since_snapshot = 0usize;
         let snapshot_interval = 1024; // Update snapshot every N samples
+        let mut left = vec![0.0_f32; RENDER_BLOCK_FRAMES];
+        let mut right = vec![0.0_f32; RENDER_BLOCK_FRAMES];
+        let mut preview_left = vec![0.0_f32; RENDER_BLOCK_FRAMES];
+        let mut preview_right = vec![0.0_f32; RENDER_BLOCK_FRAMES];
 
         device
             .build_output_stream(
                 config,
-                move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-                    match engine.try_lock() {
-                        Ok(mut synth) => {
-                            // Process commands at the start of each buffer
-                            synth.process_commands();
+                move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+                    let started = Instant::now();
+                    let buffer =
+                        Duration::from_secs_f64((data.len() / channels) as f64 / sample_rate);
+                    status.beat();
+                    status.record_frames(data.len() / channels, buffer);
+                    let late = timer.arrived(info.timestamp().callback, buffer);
+                    match engine.try_borrow() {
+                        Some(mut synth) => {
+                            // The preview engine is best-effort: if it is out
+                            // being retuned, skip it for this buffer.
+                            let mut preview = preview.as_ref().and_then(|p| p.try_borrow());
 
-                            for frame in data.chunks_mut(channels) {
-                                let (left, right) = synth.process_stereo();
+                            for chunk in data.chunks_mut(channels * RENDER_BLOCK_FRAMES) {
+                                let frames = chunk.len() / channels;
+                                let (left, right) = (&mut left[..frames], &mut right[..frames]);
+                                // Commands are applied at the start of each block
+                                synth.process_block(left, right);
+                                if let Some(p) = preview.as_mut() {
+                                    // An idle preview (nothing auditioning)
+                                    // only has its commands applied.
+                                    p.process_commands();
+                                    if !p.is_idle() {
+                                        let pl = &mut preview_left[..frames];
+                                        let pr = &mut preview_right[..frames];
+                                        p.process_block(pl, pr);
+                                        for (out, add) in left.iter_mut().zip(pl.iter()) {
+                                            *out += add;
+                                        }
+                                        for (out, add) in right.iter_mut().zip(pr.iter()) {
+                                            *out += add;
+                                        }
+                                    }
+                                }
 
-                                if channels >= 2 {
-                                    frame[0] = T::from_sample(left);
-                                    frame[1] = T::from_sample(right);
-                                } else {
-                                    frame[0] = T::from_sample((left + right) * 0.5);
+                                for (frame, (&l, &r)) in chunk
+                                    .chunks_mut(channels)
+                                    .zip(left.iter().zip(right.iter()))
+                                {
+                                    if channels >= 2 {
+                                        frame[0] = T::from_sample(l);
+                                        frame[1] = T::from_sample(r);
+                                    } else {
+                                        frame[0] = T::from_sample((l + r) * 0.5);
+                                    }
                                 }
 
-                                samples_since_snapshot += 1;
+                                samples_since_snapshot += frames;
+                            }
+
+                            if timer.rendered(started.elapsed(), buffer) || late {
+                                count_underrun(&underrun_counter);
                             }
+                            synth.set_audio_stats(AudioStats {
+                                underruns: underrun_counter.load(Ordering::Relaxed) as u32,
+                                cpu_load: timer.load,
+                            });
 
                             // Update snapshot periodically (not every sample)
                             if samples_since_snapshot >= snapshot_interval {
@@ -112,14 +719,10 @@ impl AudioEngine {
                                 samples_since_snapshot = 0;
                             }
                         }
-                        Err(_) => {
-                            let underrun_count = underrun_counter.fetch_add(1, Ordering::Relaxed);
-                            if underrun_count.is_multiple_of(500) {
-                                log::warn!(
-                                    "AUDIO WARNING: {} buffer underruns detected",
-                                    underrun_count
-                                );
-                            }
+                        None => {
+                            // The command pump still holds the engine (the
+                            // stream just resumed): this buffer is silence.
+                            count_underrun(&underrun_counter);
 
                             for frame in data.chunks_mut(channels) {
                                 let value = T::from_sample(0.0);
@@ -133,14 +736,81 @@ impl AudioEngine {
                 |err| log::error!("Audio stream error: {}", err),
                 None,
             )
-            .expect("Failed to build output stream")
+            .map_err(|e| e.to_string())
     }
 }
 
+/// Notes of the test tone: a C major arpeggio up from middle C.
+const TEST_ARPEGGIO: [u8; 4] = [60, 64, 67, 72];
+const TEST_NOTE_LENGTH: Duration = Duration::from_millis(300);
+const TEST_NOTE_GAP: Duration = Duration::from_millis(50);
+
+/// Play a short arpeggio with the current voice after `delay`, so the user
+/// can check that the output works. Runs on its own thread and returns
+/// immediately.
+pub fn play_test_tone(controller: Arc<Mutex<SynthController>>, delay: Duration) {
+    play_arpeggio(controller, delay, TEST_NOTE_LENGTH, TEST_NOTE_GAP);
+}
+
+fn play_arpeggio(
+    controller: Arc<Mutex<SynthController>>,
+    delay: Duration,
+    note_length: Duration,
+    note_gap: Duration,
+) {
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        for note in TEST_ARPEGGIO {
+            if let Ok(mut ctrl) = controller.lock() {
+                ctrl.note_on(note, 80);
+            }
+            std::thread::sleep(note_length);
+            if let Ok(mut ctrl) = controller.lock() {
+                ctrl.note_off(note);
+            }
+            std::thread::sleep(note_gap);
+        }
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::fm_synth::create_synth;
+    use crate::fm_synth::{create_preview_synth, create_synth};
+
+    #[test]
+    fn play_test_tone_returns_immediately() {
+        let (_engine, controller) = create_synth(44_100.0);
+        let start = Instant::now();
+        play_test_tone(Arc::new(Mutex::new(controller)), Duration::from_millis(500));
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_arpeggio_eventually_pushes_notes() {
+        let (mut engine, controller) = create_synth(44_100.0);
+        play_arpeggio(
+            Arc::new(Mutex::new(controller)),
+            Duration::ZERO,
+            Duration::from_millis(5),
+            Duration::from_millis(1),
+        );
+
+        let deadline = Instant::now() + Duration::from_millis(200);
+        let mut active_seen = false;
+        while Instant::now() < deadline {
+            engine.process_commands();
+            if engine.voices().iter().any(|v| v.active) {
+                active_seen = true;
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        assert!(
+            active_seen,
+            "expected at least one note-on from the arpeggio"
+        );
+    }
 
     #[test]
     fn try_default_output_returns_a_valid_sample_rate_when_available() {
@@ -161,10 +831,229 @@ mod tests {
         };
         let sr = probe.sample_rate();
         let (engine, _ctrl) = create_synth(sr);
-        let engine = Arc::new(Mutex::new(engine));
         let underrun = Arc::new(AtomicUsize::new(0));
-        let _audio = AudioEngine::new(probe, engine, underrun.clone());
+        let _audio = AudioEngine::new(probe, engine, None, underrun.clone());
         std::thread::sleep(std::time::Duration::from_millis(5));
         assert_eq!(underrun.load(Ordering::Relaxed), 0);
     }
+
+    #[test]
+    fn with_settings_rejects_an_unknown_device() {
+        let settings = AudioSettings {
+            device: Some("no such output device".to_string()),
+            ..AudioSettings::default()
+        };
+        assert!(AudioProbe::with_settings(&settings).is_err());
+    }
+
+    #[test]
+    fn with_settings_rejects_a_host_missing_from_this_build() {
+        let settings = AudioSettings {
+            host: Some("no such audio host".to_string()),
+            ..AudioSettings::default()
+        };
+        let err = AudioProbe::with_settings(&settings).err().unwrap();
+        assert!(err.contains("not available"), "{err}");
+        assert!(list_output_devices(Some("no such audio host")).is_empty());
+    }
+
+    #[test]
+    fn the_default_host_is_listed_first() {
+        let hosts = available_hosts();
+        assert_eq!(
+            hosts.first().map(String::as_str),
+            Some(cpal::default_host().id().name())
+        );
+    }
+
+    #[test]
+    fn callback_gaps_longer_than_a_buffer_and_a_half_are_xruns() {
+        let buffer = Duration::from_millis(10);
+        let mut timer = CallbackTimer::default();
+        assert!(!timer.arrived(cpal::StreamInstant::new(1, 0), buffer));
+        assert!(!timer.arrived(cpal::StreamInstant::new(1, 10_000_000), buffer));
+        assert!(!timer.arrived(cpal::StreamInstant::new(1, 24_000_000), buffer));
+        assert!(timer.arrived(cpal::StreamInstant::new(1, 40_000_000), buffer));
+    }
+
+    #[test]
+    fn render_load_is_smoothed_and_overruns_are_flagged() {
+        let buffer = Duration::from_millis(10);
+        let mut timer = CallbackTimer::default();
+        for _ in 0..200 {
+            assert!(!timer.rendered(Duration::from_millis(3), buffer));
+        }
+        assert!((timer.load - 0.3).abs() < 0.01, "{}", timer.load);
+        assert!(timer.rendered(Duration::from_millis(12), buffer));
+        assert!(timer.load < 0.5, "one slow buffer barely moves the load");
+    }
+
+    #[test]
+    fn buffer_latency_is_frames_over_rate() {
+        assert!((buffer_latency_ms(256, 48_000) - 5.333).abs() < 1e-3);
+        assert!((buffer_latency_ms(1024, 44_100) - 23.22).abs() < 1e-2);
+        assert!(buffer_latency_ms(64, 0).is_finite());
+    }
+
+    #[test]
+    fn settings_round_trip_through_the_config_file() {
+        let path = std::env::temp_dir()
+            .join(format!("synth-fm-rs-audio-{}", std::process::id()))
+            .join(SETTINGS_FILE);
+        let settings = AudioSettings {
+            host: Some("JACK".to_string()),
+            device: Some("system".to_string()),
+            sample_rate: Some(48_000),
+            buffer_size: Some(128),
+        };
+        settings.save(&path).unwrap();
+        assert_eq!(AudioSettings::load_or_default(&path), settings);
+
+        std::fs::write(&path, r#"{"buffer_size": 64}"#).unwrap();
+        let partial = AudioSettings::load_or_default(&path);
+        assert_eq!(partial.buffer_size, Some(64));
+        assert_eq!(partial.host, None);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(
+            AudioSettings::load_or_default(&path),
+            AudioSettings::default()
+        );
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn listed_devices_only_offer_common_rates() {
+        let devices = list_output_devices(None);
+        assert!(devices.iter().filter(|d| d.is_default).count() <= 1);
+        for device in &devices {
+            assert!(device
+                .sample_rates
+                .iter()
+                .all(|rate| COMMON_SAMPLE_RATES.contains(rate)));
+        }
+    }
+
+    #[test]
+    fn reconfigure_switches_the_engine_rate_when_a_device_is_available() {
+        let Some(probe) = AudioProbe::try_default_output() else {
+            return;
+        };
+        let (engine, _ctrl) = create_synth(probe.sample_rate());
+        let mut audio = AudioEngine::new(probe, engine, None, Arc::new(AtomicUsize::new(0)));
+
+        let Some(device) = list_output_devices(None).into_iter().find(|d| d.is_default) else {
+            return;
+        };
+        let Some(&rate) = device
+            .sample_rates
+            .iter()
+            .find(|&&r| r != device.default_sample_rate)
+        else {
+            return;
+        };
+        let settings = AudioSettings {
+            sample_rate: Some(rate),
+            ..AudioSettings::default()
+        };
+        if let Ok(new_rate) = audio.reconfigure(&settings) {
+            assert_eq!(new_rate, rate as f32);
+            assert_eq!(audio.stream_info().sample_rate, rate);
+            audio.stream = None; // stop the callback so the engine can be read
+            assert_eq!(
+                borrow_without_stream(&audio.engine).sample_rate(),
+                rate as f32
+            );
+        }
+    }
+
+    #[test]
+    fn command_pump_drains_queue_when_stream_is_stalled() {
+        let (engine, mut ctrl) = create_synth(44_100.0);
+        let engine = Arc::new(HandoffCell::new(engine));
+        let (preview, mut preview_ctrl) = create_preview_synth(44_100.0);
+        let preview = Arc::new(HandoffCell::new(preview));
+        let status = Arc::new(StreamStatus::default());
+        let _pump = CommandPump::spawn(
+            engine.clone(),
+            Some(preview.clone()),
+            status.clone(),
+            Duration::from_millis(5),
+        );
+
+        ctrl.set_algorithm(12);
+        preview_ctrl.set_algorithm(5);
+        std::thread::sleep(Duration::from_millis(50));
+
+        assert!(!status.is_running());
+        assert_eq!(engine.try_borrow().unwrap().get_algorithm(), 12);
+        assert_eq!(ctrl.snapshot().algorithm, 12);
+        assert_eq!(preview.try_borrow().unwrap().get_algorithm(), 5);
+    }
+
+    #[test]
+    fn command_pump_stays_idle_while_stream_beats() {
+        let (engine, mut ctrl) = create_synth(44_100.0);
+        let engine = Arc::new(HandoffCell::new(engine));
+        let status = Arc::new(StreamStatus::default());
+        let pump = CommandPump::spawn(
+            engine.clone(),
+            None,
+            status.clone(),
+            Duration::from_millis(20),
+        );
+
+        ctrl.set_algorithm(12);
+        for _ in 0..20 {
+            status.beat();
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        drop(pump);
+
+        assert!(status.is_running());
+        assert_eq!(engine.try_borrow().unwrap().get_algorithm(), 1);
+    }
+
+    #[test]
+    fn beats_slower_than_the_pump_interval_are_not_a_stall() {
+        // A 4096-frame buffer at 44.1 kHz beats every ~93 ms; the pump
+        // checks every 50 ms.
+        let status = StreamStatus::default();
+        status.record_frames(4096, Duration::from_millis(93));
+        let timeout = status.stall_timeout(Duration::from_millis(50));
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        let mut check = StallCheck::new(1, start);
+        for tick in 1..=20 {
+            let beat = 1 + tick * 50 / 93;
+            assert!(
+                check.is_running(beat, at(tick * 50), timeout),
+                "tick {tick}"
+            );
+        }
+        // Three buffers without a beat is a stall.
+        assert!(!check.is_running(1 + 1000 / 93, at(1000 + 280), timeout));
+    }
+
+    #[test]
+    fn no_heartbeat_yet_is_not_running() {
+        let start = Instant::now();
+        let mut check = StallCheck::new(0, start);
+        assert!(!check.is_running(0, start + Duration::from_millis(1), Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn stall_timeout_covers_several_buffers() {
+        let status = StreamStatus::default();
+        let interval = Duration::from_millis(50);
+        assert_eq!(status.stall_timeout(interval), interval);
+        status.record_frames(256, Duration::from_micros(5_333));
+        assert_eq!(status.stall_timeout(interval), interval);
+        status.record_frames(4096, Duration::from_micros(92_880));
+        assert_eq!(
+            status.stall_timeout(interval),
+            Duration::from_micros(92_880 * 3)
+        );
+    }
 }
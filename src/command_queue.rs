@@ -1,15 +1,26 @@
+use crate::algorithm_matrix::AlgorithmMatrix;
+use crate::effects::{EffectSlot, EFFECT_SLOTS};
+use crate::mod_matrix::ModRoute;
+use crate::performance::Performance;
 use crate::presets::Dx7Preset;
+use crate::state_snapshot::ExpressionSource;
+use crate::tuning::Tuning;
 use rtrb::{Consumer, Producer, RingBuffer};
 
 /// Size of the command ring buffer.
 /// 1024 commands should be more than enough for any realistic GUI/MIDI interaction.
 const COMMAND_BUFFER_SIZE: usize = 1024;
+/// Size of the ring that hands replaced data back from the audio thread.
+const RETURN_BUFFER_SIZE: usize = 16;
 
 /// Parameters that can be set on an operator
 #[allow(dead_code)] // some variants are surfaced via JSON loader / future GUI panels
 #[derive(Debug, Clone, Copy)]
 pub enum OperatorParam {
-    Ratio,
+    Ratio,         // float ratio; COARSE/FINE follow, extended mode if they cannot reach it
+    Coarse,        // DX7 COARSE 0-31 (0 = x0.5)
+    Fine,          // DX7 FINE 0-99
+    ExtendedRatio, // bool: 1 = free float ratio, 0 = snap to COARSE/FINE
     Level,
     Detune,
     Feedback,
@@ -21,9 +32,15 @@ pub enum OperatorParam {
     KeyScaleLeftCurve, // payload: encoded KeyScaleCurve (0..3)
     KeyScaleRightCurve,
     AmSensitivity, // 0-3
+    PmSensitivity, // 0-7 per-operator PMS; negative = follow the voice PMS
+    Lfo2,          // bool: 1 = follow the second LFO instead of the main one
     OscillatorKeySync,
+    SubBus,         // bool: 1 = route output to the sub bus instead of the carrier sum
+    Expression,     // bool: 1 = output level follows the expression input
+    Waveform,       // payload: OperatorWaveform code (0..7, TX81Z W1 – W8)
     FixedFrequency, // bool: 0 = ratio, 1 = fixed
     FixedFreqHz,
+    PhaseOffset, // 0-360°: start phase of key-synced notes
     Enabled,
 }
 
@@ -63,23 +80,90 @@ pub enum LfoParam {
     AmpDepth,
     Waveform(u8), // 0-5 for different waveforms
     KeySync,
+    SyncDivision, // NoteDivision code; negative = free-running at `Rate`
+}
+
+/// Parameters of the master bus compressor / limiter
+#[derive(Debug, Clone, Copy)]
+pub enum CompressorParam {
+    Enabled,
+    Threshold, // dB, -40..0
+    Ratio,     // 1..20, 20 = limiter
+    Attack,    // ms
+    Release,   // ms
+    Makeup,    // dB, 0..24
 }
 
 /// Effect types for effect parameter commands
 #[derive(Debug, Clone, Copy)]
 pub enum EffectType {
+    Eq,
+    Filter,
+    Phaser,
     Chorus,
     AutoPan,
     Delay,
     Reverb,
 }
 
+impl EffectType {
+    /// Chain slot the effect runs in.
+    pub fn slot(self) -> EffectSlot {
+        match self {
+            EffectType::Eq => EffectSlot::Eq,
+            EffectType::Filter => EffectSlot::Filter,
+            EffectType::Phaser => EffectSlot::Phaser,
+            EffectType::Chorus => EffectSlot::Chorus,
+            EffectType::AutoPan => EffectSlot::AutoPan,
+            EffectType::Delay => EffectSlot::Delay,
+            EffectType::Reverb => EffectSlot::Reverb,
+        }
+    }
+
+    /// Effect that runs in `slot`.
+    pub fn for_slot(slot: EffectSlot) -> Self {
+        match slot {
+            EffectSlot::Eq => EffectType::Eq,
+            EffectSlot::Filter => EffectType::Filter,
+            EffectSlot::Phaser => EffectType::Phaser,
+            EffectSlot::Chorus => EffectType::Chorus,
+            EffectSlot::AutoPan => EffectType::AutoPan,
+            EffectSlot::Delay => EffectType::Delay,
+            EffectSlot::Reverb => EffectType::Reverb,
+        }
+    }
+}
+
 /// Parameters that can be set on effects
 #[derive(Debug, Clone, Copy)]
 pub enum EffectParam {
     // Common
     Enabled,
     Mix,
+    OutputGain, // dB trim on the effect's output, applied while it is on
+    SoloWet,    // > 0.5 auditions this effect's wet signal alone
+
+    // EQ (gains in dB, frequencies in Hz)
+    EqLowGain,
+    EqLowFreq,
+    EqMidGain,
+    EqMidFreq,
+    EqMidQ,
+    EqHighGain,
+    EqHighFreq,
+
+    // Resonant filter (runs only with SetExtensions on)
+    FilterCutoff,    // Hz
+    FilterResonance, // 0..1
+    FilterEnvAmount, // -1..1
+    FilterLfoRate,   // Hz
+    FilterLfoDepth,  // 0..1
+
+    // Phaser
+    PhaserStages, // 4 or 8
+    PhaserRate,
+    PhaserDepth,
+    PhaserFeedback,
 
     // Chorus
     ChorusRate,
@@ -95,11 +179,16 @@ pub enum EffectParam {
     DelayTime,
     DelayFeedback,
     DelayPingPong,
+    DelaySync, // NoteDivision code; negative = free time in ms
+    DelayHighCut,
+    DelayLowCut,
 
     // Reverb
     ReverbRoomSize,
     ReverbDamping,
     ReverbWidth,
+    ReverbPreDelay, // ms
+    ReverbType,     // ReverbType code
 }
 
 /// Commands sent from GUI/MIDI thread to audio thread
@@ -116,19 +205,86 @@ pub enum SynthCommand {
     },
 
     // Global parameters
-    SetAlgorithm(u8),
+    SetAlgorithm(u8), // also leaves custom-algorithm mode
+    /// Route the voices through `SetAlgorithmMatrix` instead of the DX7 algorithm.
+    SetCustomAlgorithm(bool),
+    SetAlgorithmMatrix(AlgorithmMatrix),
     SetMasterVolume(f32),
     SetMasterTune(f32),
     /// 0 = Poly, 1 = Mono (full portamento), 2 = Mono Legato (portamento only when previous note still held).
     SetVoiceMode(u8),
+    /// Poly re-strike of a sounding key: 0 = retrigger its voice, 1 = stack a new voice.
+    SetSameNoteMode(u8),
+    /// Held notes on a preset or algorithm change: 0 = crossfade into the
+    /// new patch, 1 = re-trigger them on it.
+    SetPatchChangeMode(u8),
+    /// Fill or clear one mod-matrix slot (0..8).
+    SetModRoute {
+        slot: u8,
+        route: Option<ModRoute>,
+    },
+    /// Mono key priority: 0 = last, 1 = low, 2 = high.
+    SetNotePriority(u8),
     SetPitchBendRange(f32),
+    /// Time in ms the bend takes to catch up with the wheel (0 = instant).
+    SetPitchBendSmoothing(f32),
     SetPortamentoEnable(bool),
+    /// 0 = full time (every note glides), 1 = fingered (only legato notes).
+    SetPortamentoMode(u8),
     SetPortamentoTime(f32),
     SetPortamentoGlissando(bool), // step (semitone) glide instead of continuous
-    SetTranspose(i8),             // -24..+24 semitones around C3
-    SetPitchModSensitivity(u8),   // 0-7 PMS for the LFO pitch depth
-    SetEgBiasSensitivity(u8),     // 0-7 mod-wheel routing depth for EG Bias (amp-side)
-    SetPitchBiasSensitivity(u8),  // 0-7 mod-wheel routing depth for Pitch Bias (semitone offset)
+    /// Voice-level OSC KEY SYNC (DX7 VCED byte 136): sets every operator's
+    /// phase-reset-on-note-on flag at once.
+    SetOscKeySync(bool),
+    SetTranspose(i8), // -24..+24 semitones around C3
+    /// Crossfade length in ms between a stolen voice's tail and the new note.
+    SetStealFadeTime(f32),
+    /// Anti-click fade-in length in ms applied on note-on.
+    SetAttackFadeTime(f32),
+    /// Stuck-note watchdog: release voices no key or pedal holds.
+    SetVoiceWatchdog(bool),
+    /// Release any voice this many seconds after its note-on; 0 = never.
+    SetVoiceTimeout(f32),
+    /// Per-voice tremolo / auto-pan: rate in Hz (0.1..10), depth and
+    /// stereo phase 0..1 (0 = tremolo, 1 = L/R opposite = auto-pan).
+    SetVoicePanRate(f32),
+    SetVoicePanDepth(f32),
+    SetVoicePanStereoPhase(f32),
+    /// Stereo placement of new notes: `VoiceSpread` code, spread width
+    /// 0..1, and a master pan -1..1 that shifts every voice.
+    SetVoiceSpread(u8),
+    SetVoiceSpreadWidth(f32),
+    SetMasterPan(f32),
+    /// Unison: stacks per note (1 = off), detune in cents and stereo
+    /// spread 0..1.
+    SetUnisonVoices(u8),
+    SetUnisonDetune(f32),
+    SetUnisonSpread(f32),
+    /// Global VINTAGE toggle: DAC companding, envelope quantization, LFO
+    /// jitter and the uninterpolated 12-bit sine all at once.
+    SetVintageMode(bool),
+    /// DX7 output stage (DAC, sample-and-hold, low-pass) on the master bus,
+    /// and its share of the mix 0..1.
+    SetOutputDac(bool),
+    SetOutputDacIntensity(f32),
+    /// Background hiss and mains hum ahead of the effects: on/off, level in
+    /// dBFS and mains frequency (50 or 60 Hz).
+    SetNoiseFloor(bool),
+    SetNoiseLevel(f32),
+    SetHumFrequency(f32),
+    SetCompressorParam {
+        param: CompressorParam,
+        value: f32,
+    },
+    /// Playable voice count (1..=64); shrinking releases the voices above it.
+    SetPolyphony(u8),
+    /// Sub-bus output level and effect sends (0..1 each).
+    SetSubBusLevel(f32),
+    SetSubBusDelaySend(f32),
+    SetSubBusReverbSend(f32),
+    SetPitchModSensitivity(u8),  // 0-7 PMS for the LFO pitch depth
+    SetEgBiasSensitivity(u8),    // 0-7 mod-wheel routing depth for EG Bias (amp-side)
+    SetPitchBiasSensitivity(u8), // 0-7 mod-wheel routing depth for Pitch Bias (semitone offset)
     // DX7S Aftertouch (channel pressure 0xD0) routing: 4 destinations (0-7 each)
     SetAftertouchPitchSens(u8),
     SetAftertouchAmpSens(u8),
@@ -151,12 +307,20 @@ pub enum SynthCommand {
     SustainPedal(bool),
     /// DX7S channel aftertouch (0..1, mapped from MIDI 0xD0).
     Aftertouch(f32),
+    /// Polyphonic key pressure (0..1, mapped from MIDI 0xA0) on one key.
+    /// Drives the aftertouch routes for that key's voice only.
+    PolyAftertouch {
+        note: u8,
+        value: f32,
+    },
     /// DX7 Breath Controller value (0..1, mapped from MIDI CC2).
     BreathController(f32),
     /// DX7S Foot Controller value (0..1, mapped from MIDI CC4).
     FootController(f32),
     /// MIDI Expression (CC11) — generic global attenuator (1.0 = full level).
     Expression(f32),
+    /// Which controller scales the operators flagged for expression.
+    SetExpressionSource(ExpressionSource),
     /// MIDI Bank Select MSB (CC0). Combined with LSB and the next Program Change
     /// to address banks beyond the original 128 DX7 presets.
     SetBankSelectMsb(u8),
@@ -165,6 +329,9 @@ pub enum SynthCommand {
     /// MIDI Program Change (0xC0). Combined with the current bank to compute the
     /// preset index = (msb << 14 | lsb << 7 | program).
     ProgramChange(u8),
+    /// Presets Program Change numbers load instead of the bank position,
+    /// indexed by program (`ProgramMap::resolve`); empty = no map.
+    SetProgramMap(Vec<Option<Dx7Preset>>),
 
     // Operator parameters
     SetOperatorParam {
@@ -173,6 +340,10 @@ pub enum SynthCommand {
         value: f32,
     },
 
+    /// Audition: soloed operators, bit `i` = OP `i + 1` (0 = off). They
+    /// are heard as carriers; the patch itself is unchanged.
+    SetOperatorSolo(u8),
+
     // Envelope parameters
     SetEnvelopeParam {
         operator: u8,
@@ -191,6 +362,12 @@ pub enum SynthCommand {
         param: LfoParam,
         value: f32,
     },
+    SetLfo2Param {
+        param: LfoParam,
+        value: f32,
+    },
+    SetLfo2Enabled(bool),
+    SetTempo(f32), // BPM shared by the tempo-synced LFOs and the delay
 
     // Effect parameters
     SetEffectParam {
@@ -198,10 +375,17 @@ pub enum SynthCommand {
         param: EffectParam,
         value: f32,
     },
+    SetEffectOrder([EffectSlot; EFFECT_SLOTS]), // ignored unless a permutation
+    SetEffectsBypass(bool),
+    SetExtensions(bool), // processing the DX7 never had (resonant filter)
 
     // Preset loading (for MIDI program change)
     LoadPreset(usize),
 
+    /// Apply a preset as the live edit buffer (preset browser, audition).
+    /// The bank stays untouched.
+    ApplyPreset(Box<Dx7Preset>),
+
     /// Apply a preset parsed from a DX7 SysEx single-voice dump as the live edit
     /// buffer. The bank stays untouched.
     LoadSysExSingleVoice(Box<Dx7Preset>),
@@ -209,25 +393,56 @@ pub enum SynthCommand {
     /// Replace the entire 32-voice bank with a SysEx bulk dump.
     LoadSysExBulk(Vec<Dx7Preset>),
 
+    /// Replace the per-key tuning table (MTS bulk dump).
+    SetTuning(Box<Tuning>),
+    /// Retune single keys, sounding notes included: `(key, cents offset)`.
+    RetuneNotes(Vec<(u8, f32)>),
+
+    /// Dual / split mode, split point and per-part volume and transpose.
+    SetPerformance(Performance),
+    /// Load the voice part B of a performance plays.
+    LoadPartB(Box<Dx7Preset>),
+
     // Voice initialization
     VoiceInitialize,
+    /// Function settings (voice mode, bend, portamento, master tune, mod
+    /// routes) back to their power-on values.
+    FunctionInitialize,
 
-    // Panic - stop all sound
+    /// MIDI All Notes Off: every key released, through the pedal.
+    AllNotesOff,
+    /// MIDI Reset All Controllers: bend, mod wheel, pressure, expression
+    /// and sustain back to rest.
+    ResetControllers,
+
+    // Panic - stop all sound (All Sound Off)
     Panic,
+
+    /// Attach an engine debug dump to the next snapshot.
+    RequestDebugDump,
 }
 
 /// Sender side of the command queue (GUI/MIDI thread)
 pub struct CommandSender {
     producer: Producer<SynthCommand>,
+    /// Commands the audio thread handed back, to be dropped here.
+    returned: Consumer<SynthCommand>,
 }
 
 impl CommandSender {
     /// Send a command to the audio thread.
     /// Returns true if the command was sent, false if the buffer is full.
     pub fn send(&mut self, command: SynthCommand) -> bool {
+        self.drop_returned();
         self.producer.push(command).is_ok()
     }
 
+    /// Free what the audio thread handed back with
+    /// [`CommandReceiver::hand_back`].
+    pub fn drop_returned(&mut self) {
+        while self.returned.pop().is_ok() {}
+    }
+
     /// Check how many slots are available in the buffer
     #[allow(dead_code)]
     pub fn available(&self) -> usize {
@@ -244,6 +459,7 @@ impl CommandSender {
 /// Receiver side of the command queue (audio thread)
 pub struct CommandReceiver {
     consumer: Consumer<SynthCommand>,
+    returned: Producer<SynthCommand>,
 }
 
 impl CommandReceiver {
@@ -265,12 +481,23 @@ impl CommandReceiver {
         }
     }
 
+    /// Pass a command holding data the engine replaced back to the sending
+    /// thread, so its allocations are freed there rather than on the audio
+    /// thread. Dropped here only if the sender has let the return ring fill.
+    pub fn hand_back(&mut self, command: SynthCommand) {
+        let _ = self.returned.push(command);
+    }
+
     /// Check how many commands are waiting
-    #[allow(dead_code)]
     pub fn pending(&self) -> usize {
         self.consumer.slots()
     }
 
+    /// Most commands the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.consumer.buffer().capacity()
+    }
+
     /// Check if there are any pending commands
     #[allow(dead_code)]
     pub fn is_empty(&self) -> bool {
@@ -281,8 +508,18 @@ impl CommandReceiver {
 /// Create a new command queue pair (sender, receiver)
 pub fn create_command_queue() -> (CommandSender, CommandReceiver) {
     let (producer, consumer) = RingBuffer::new(COMMAND_BUFFER_SIZE);
-
-    (CommandSender { producer }, CommandReceiver { consumer })
+    let (returned_tx, returned_rx) = RingBuffer::new(RETURN_BUFFER_SIZE);
+
+    (
+        CommandSender {
+            producer,
+            returned: returned_rx,
+        },
+        CommandReceiver {
+            consumer,
+            returned: returned_tx,
+        },
+    )
 }
 
 #[cfg(test)]
@@ -336,6 +573,21 @@ mod tests {
         assert!(receiver.is_empty());
     }
 
+    #[test]
+    fn handed_back_commands_are_dropped_by_the_sender() {
+        let (mut sender, mut receiver) = create_command_queue();
+        receiver.hand_back(SynthCommand::SetProgramMap(vec![None; 128]));
+        assert_eq!(sender.returned.slots(), 1);
+        sender.send(SynthCommand::Panic);
+        assert!(sender.returned.is_empty());
+
+        // A sender that never drains costs the audio thread a drop, not a block.
+        for _ in 0..RETURN_BUFFER_SIZE + 1 {
+            receiver.hand_back(SynthCommand::SetProgramMap(Vec::new()));
+        }
+        assert_eq!(sender.returned.slots(), RETURN_BUFFER_SIZE);
+    }
+
     #[test]
     fn test_buffer_capacity() {
         let (mut sender, mut receiver) = create_command_queue();
@@ -0,0 +1,426 @@
+//! A/B/C/D compare slots: park the current edit in memory and flip back to
+//! it instantly, without saving a preset.
+//!
+//! `EditState::capture` reads a snapshot the way preset saving does
+//! (`Dx7Preset::from_snapshot`) and adds what a DX7 voice can't hold: the
+//! custom algorithm, mod matrix, tempo sync, LFO 2, unison and every effect
+//! setting. `EditState::commands` turns it back into the commands the panels
+//! would send. The master section (volume, compressor) is left out so the
+//! slots compare at the same level.
+
+use crate::algorithm_matrix::AlgorithmMatrix;
+use crate::command_queue::{EffectParam, EffectType, LfoParam, SynthCommand};
+use crate::effects::{EffectSlot, EFFECT_SLOTS};
+use crate::lfo::LFOWaveform;
+use crate::mod_matrix::ModMatrix;
+use crate::presets::Dx7Preset;
+use crate::state_snapshot::{
+    AutoPanSnapshot, ChorusSnapshot, DelaySnapshot, EqSnapshot, FilterSnapshot, Lfo2Snapshot,
+    PhaserSnapshot, ReverbSnapshot, SubBusSnapshot, SynthSnapshot,
+};
+use crate::tempo::NoteDivision;
+
+/// Number of compare slots.
+pub const COMPARE_SLOTS: usize = 4;
+
+/// Slot labels, in order.
+pub const COMPARE_SLOT_NAMES: [&str; COMPARE_SLOTS] = ["A", "B", "C", "D"];
+
+/// Voice and effects settings captured from one snapshot.
+#[derive(Debug, Clone)]
+pub struct EditState {
+    pub voice: Dx7Preset,
+    custom_algorithm: bool,
+    algorithm_matrix: AlgorithmMatrix,
+    mod_matrix: ModMatrix,
+    lfo_sync: Option<NoteDivision>,
+    lfo2: Lfo2Snapshot,
+    unison: (u8, f32, f32), // voices, detune, spread
+    sub_bus: SubBusSnapshot,
+    eq: EqSnapshot,
+    filter: FilterSnapshot,
+    phaser: PhaserSnapshot,
+    chorus: ChorusSnapshot,
+    auto_pan: AutoPanSnapshot,
+    delay: DelaySnapshot,
+    reverb: ReverbSnapshot,
+    effect_order: [EffectSlot; EFFECT_SLOTS],
+    effects_bypass: bool,
+    effect_gains_db: [f32; EFFECT_SLOTS],
+}
+
+/// Parameter value for an on/off switch.
+fn flag(on: bool) -> f32 {
+    if on {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// `NoteDivision` code for a sync parameter; negative means free-running.
+fn sync_code(sync: Option<NoteDivision>) -> f32 {
+    sync.map_or(-1.0, |d| d.to_code() as f32)
+}
+
+fn waveform_code(waveform: LFOWaveform) -> u8 {
+    LFOWaveform::all()
+        .iter()
+        .position(|&w| w == waveform)
+        .unwrap_or(0) as u8
+}
+
+impl EditState {
+    pub fn capture(snapshot: &SynthSnapshot) -> Self {
+        Self {
+            voice: Dx7Preset::from_snapshot(snapshot),
+            custom_algorithm: snapshot.custom_algorithm,
+            algorithm_matrix: snapshot.algorithm_matrix,
+            mod_matrix: snapshot.mod_matrix,
+            lfo_sync: snapshot.lfo_sync,
+            lfo2: snapshot.lfo2,
+            unison: (
+                snapshot.unison_voices,
+                snapshot.unison_detune,
+                snapshot.unison_spread,
+            ),
+            sub_bus: snapshot.sub_bus,
+            eq: snapshot.eq,
+            filter: snapshot.filter,
+            phaser: snapshot.phaser,
+            chorus: snapshot.chorus,
+            auto_pan: snapshot.auto_pan,
+            delay: snapshot.delay,
+            reverb: snapshot.reverb,
+            effect_order: snapshot.effect_order,
+            effects_bypass: snapshot.effects_bypass,
+            effect_gains_db: snapshot.effect_gains_db,
+        }
+    }
+
+    /// Commands that put this state back. The voice goes first: applying a
+    /// preset leaves custom-algorithm mode, which is restored after it.
+    pub fn commands(&self) -> Vec<SynthCommand> {
+        let mut commands = vec![
+            SynthCommand::ApplyPreset(Box::new(self.voice.clone())),
+            SynthCommand::SetAlgorithmMatrix(self.algorithm_matrix),
+            SynthCommand::SetCustomAlgorithm(self.custom_algorithm),
+            SynthCommand::SetLfoParam {
+                param: LfoParam::SyncDivision,
+                value: sync_code(self.lfo_sync),
+            },
+            SynthCommand::SetLfo2Enabled(self.lfo2.enabled),
+            SynthCommand::SetUnisonVoices(self.unison.0),
+            SynthCommand::SetUnisonDetune(self.unison.1),
+            SynthCommand::SetUnisonSpread(self.unison.2),
+            SynthCommand::SetSubBusLevel(self.sub_bus.level),
+            SynthCommand::SetSubBusDelaySend(self.sub_bus.delay_send),
+            SynthCommand::SetSubBusReverbSend(self.sub_bus.reverb_send),
+            SynthCommand::SetEffectOrder(self.effect_order),
+            SynthCommand::SetEffectsBypass(self.effects_bypass),
+        ];
+        for (slot, route) in self.mod_matrix.routes().iter().enumerate() {
+            commands.push(SynthCommand::SetModRoute {
+                slot: slot as u8,
+                route: *route,
+            });
+        }
+
+        let lfo2 = &self.lfo2;
+        for (param, value) in [
+            (LfoParam::Rate, lfo2.rate),
+            (LfoParam::Delay, lfo2.delay),
+            (LfoParam::PitchDepth, lfo2.pitch_depth),
+            (LfoParam::AmpDepth, lfo2.amp_depth),
+            (LfoParam::Waveform(waveform_code(lfo2.waveform)), 0.0),
+            (LfoParam::KeySync, flag(lfo2.key_sync)),
+            (LfoParam::SyncDivision, sync_code(lfo2.sync)),
+        ] {
+            commands.push(SynthCommand::SetLfo2Param { param, value });
+        }
+
+        let (eq, filter, phaser) = (&self.eq, &self.filter, &self.phaser);
+        let chorus = &self.chorus;
+        let (auto_pan, delay, reverb) = (&self.auto_pan, &self.delay, &self.reverb);
+        let effects = [
+            (EffectType::Eq, EffectParam::Enabled, flag(eq.enabled)),
+            (EffectType::Eq, EffectParam::EqLowGain, eq.low.gain_db),
+            (EffectType::Eq, EffectParam::EqLowFreq, eq.low.freq_hz),
+            (EffectType::Eq, EffectParam::EqMidGain, eq.mid.gain_db),
+            (EffectType::Eq, EffectParam::EqMidFreq, eq.mid.freq_hz),
+            (EffectType::Eq, EffectParam::EqMidQ, eq.mid.q),
+            (EffectType::Eq, EffectParam::EqHighGain, eq.high.gain_db),
+            (EffectType::Eq, EffectParam::EqHighFreq, eq.high.freq_hz),
+            (
+                EffectType::Filter,
+                EffectParam::Enabled,
+                flag(filter.enabled),
+            ),
+            (
+                EffectType::Filter,
+                EffectParam::FilterCutoff,
+                filter.cutoff_hz,
+            ),
+            (
+                EffectType::Filter,
+                EffectParam::FilterResonance,
+                filter.resonance,
+            ),
+            (
+                EffectType::Filter,
+                EffectParam::FilterEnvAmount,
+                filter.env_amount,
+            ),
+            (
+                EffectType::Filter,
+                EffectParam::FilterLfoRate,
+                filter.lfo_rate,
+            ),
+            (
+                EffectType::Filter,
+                EffectParam::FilterLfoDepth,
+                filter.lfo_depth,
+            ),
+            (EffectType::Filter, EffectParam::Mix, filter.mix),
+            (
+                EffectType::Phaser,
+                EffectParam::Enabled,
+                flag(phaser.enabled),
+            ),
+            (
+                EffectType::Phaser,
+                EffectParam::PhaserStages,
+                phaser.stages as f32,
+            ),
+            (EffectType::Phaser, EffectParam::PhaserRate, phaser.rate),
+            (EffectType::Phaser, EffectParam::PhaserDepth, phaser.depth),
+            (
+                EffectType::Phaser,
+                EffectParam::PhaserFeedback,
+                phaser.feedback,
+            ),
+            (EffectType::Phaser, EffectParam::Mix, phaser.mix),
+            (
+                EffectType::Chorus,
+                EffectParam::Enabled,
+                flag(chorus.enabled),
+            ),
+            (EffectType::Chorus, EffectParam::ChorusRate, chorus.rate),
+            (EffectType::Chorus, EffectParam::ChorusDepth, chorus.depth),
+            (
+                EffectType::Chorus,
+                EffectParam::ChorusFeedback,
+                chorus.feedback,
+            ),
+            (EffectType::Chorus, EffectParam::Mix, chorus.mix),
+            (
+                EffectType::AutoPan,
+                EffectParam::Enabled,
+                flag(auto_pan.enabled),
+            ),
+            (
+                EffectType::AutoPan,
+                EffectParam::AutoPanRate,
+                auto_pan.rate_hz,
+            ),
+            (
+                EffectType::AutoPan,
+                EffectParam::AutoPanDepth,
+                auto_pan.depth,
+            ),
+            (EffectType::Delay, EffectParam::Enabled, flag(delay.enabled)),
+            (EffectType::Delay, EffectParam::DelayTime, delay.time_ms),
+            (
+                EffectType::Delay,
+                EffectParam::DelayFeedback,
+                delay.feedback,
+            ),
+            (
+                EffectType::Delay,
+                EffectParam::DelayPingPong,
+                flag(delay.ping_pong),
+            ),
+            (
+                EffectType::Delay,
+                EffectParam::DelaySync,
+                sync_code(delay.sync),
+            ),
+            (
+                EffectType::Delay,
+                EffectParam::DelayHighCut,
+                delay.high_cut_hz,
+            ),
+            (
+                EffectType::Delay,
+                EffectParam::DelayLowCut,
+                delay.low_cut_hz,
+            ),
+            (EffectType::Delay, EffectParam::Mix, delay.mix),
+            (
+                EffectType::Reverb,
+                EffectParam::Enabled,
+                flag(reverb.enabled),
+            ),
+            (
+                EffectType::Reverb,
+                EffectParam::ReverbType,
+                reverb.reverb_type.to_code() as f32,
+            ),
+            (
+                EffectType::Reverb,
+                EffectParam::ReverbPreDelay,
+                reverb.pre_delay_ms,
+            ),
+            (
+                EffectType::Reverb,
+                EffectParam::ReverbRoomSize,
+                reverb.room_size,
+            ),
+            (
+                EffectType::Reverb,
+                EffectParam::ReverbDamping,
+                reverb.damping,
+            ),
+            (EffectType::Reverb, EffectParam::ReverbWidth, reverb.width),
+            (EffectType::Reverb, EffectParam::Mix, reverb.mix),
+        ];
+        for (effect, param, value) in effects {
+            commands.push(SynthCommand::SetEffectParam {
+                effect,
+                param,
+                value,
+            });
+        }
+        for slot in EffectSlot::DEFAULT_ORDER {
+            commands.push(SynthCommand::SetEffectParam {
+                effect: EffectType::for_slot(slot),
+                param: EffectParam::OutputGain,
+                value: self.effect_gains_db[slot.index()],
+            });
+        }
+        commands
+    }
+}
+
+/// The four slots and which one was stored or recalled last.
+#[derive(Debug, Clone, Default)]
+pub struct CompareSlots {
+    slots: [Option<EditState>; COMPARE_SLOTS],
+    active: Option<usize>,
+}
+
+impl CompareSlots {
+    pub fn is_stored(&self, slot: usize) -> bool {
+        self.slots.get(slot).is_some_and(Option::is_some)
+    }
+
+    /// Slot last stored to or recalled, shown as the one being heard.
+    pub fn active(&self) -> Option<usize> {
+        self.active
+    }
+
+    /// Name of the voice in `slot`, if any.
+    pub fn voice_name(&self, slot: usize) -> Option<&str> {
+        self.slots
+            .get(slot)?
+            .as_ref()
+            .map(|s| s.voice.name.as_str())
+    }
+
+    /// Capture `snapshot` into `slot`, replacing what was there.
+    pub fn store(&mut self, slot: usize, snapshot: &SynthSnapshot) {
+        if let Some(entry) = self.slots.get_mut(slot) {
+            *entry = Some(EditState::capture(snapshot));
+            self.active = Some(slot);
+        }
+    }
+
+    /// Commands that restore `slot`, or None when it is empty.
+    pub fn recall(&mut self, slot: usize) -> Option<Vec<SynthCommand>> {
+        let commands = self.slots.get(slot)?.as_ref()?.commands();
+        self.active = Some(slot);
+        Some(commands)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::ReverbType;
+    use crate::fm_synth::create_synth;
+
+    #[test]
+    fn recall_puts_voice_and_effects_back() {
+        let (mut engine, mut ctrl) = create_synth(44_100.0);
+        ctrl.set_operator_param(0, crate::command_queue::OperatorParam::Level, 42.0);
+        ctrl.set_effect_param(EffectType::Delay, EffectParam::Enabled, 1.0);
+        ctrl.set_effect_param(EffectType::Delay, EffectParam::DelayTime, 480.0);
+        ctrl.set_effect_param(
+            EffectType::Reverb,
+            EffectParam::ReverbType,
+            ReverbType::Room.to_code() as f32,
+        );
+        ctrl.set_effect_param(EffectType::Chorus, EffectParam::OutputGain, -4.0);
+        ctrl.set_custom_algorithm(true);
+        ctrl.set_lfo2_enabled(true);
+        ctrl.set_lfo2_param(LfoParam::Waveform(3), 0.0);
+        engine.process_commands();
+        engine.update_snapshot();
+
+        let mut slots = CompareSlots::default();
+        slots.store(1, &ctrl.snapshot());
+        assert!(slots.is_stored(1) && !slots.is_stored(0));
+        assert_eq!(slots.active(), Some(1));
+
+        // Wander off: another voice, effects changed.
+        ctrl.voice_initialize();
+        ctrl.set_effect_param(EffectType::Delay, EffectParam::Enabled, 0.0);
+        ctrl.set_effect_param(EffectType::Reverb, EffectParam::ReverbType, 0.0);
+        ctrl.set_effect_param(EffectType::Chorus, EffectParam::OutputGain, 0.0);
+        ctrl.set_lfo2_enabled(false);
+        engine.process_commands();
+        engine.update_snapshot();
+        assert!(!ctrl.snapshot().delay.enabled);
+
+        assert!(slots.recall(0).is_none());
+        for command in slots.recall(1).unwrap() {
+            assert!(ctrl.send(command));
+        }
+        engine.process_commands();
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.operators[0].output_level, 42.0);
+        assert!(snap.custom_algorithm);
+        assert!(snap.delay.enabled);
+        assert_eq!(snap.delay.time_ms, 480.0);
+        assert_eq!(snap.reverb.reverb_type, ReverbType::Room);
+        assert_eq!(snap.effect_gains_db[EffectSlot::Chorus.index()], -4.0);
+        assert!(snap.lfo2.enabled);
+        assert_eq!(snap.lfo2.waveform, LFOWaveform::Square);
+    }
+
+    #[test]
+    fn master_section_is_left_alone() {
+        let (mut engine, ctrl) = create_synth(44_100.0);
+        engine.update_snapshot();
+        let mut slots = CompareSlots::default();
+        slots.store(0, &ctrl.snapshot());
+        let commands = slots.recall(0).unwrap();
+        assert!(!commands.iter().any(|c| matches!(
+            c,
+            SynthCommand::SetMasterVolume(_)
+                | SynthCommand::SetCompressorParam { .. }
+                | SynthCommand::SetExtensions(_)
+        )));
+        // Everything fits the command queue in one go.
+        assert!(commands.len() < 256);
+
+        slots.clear();
+        assert_eq!(slots.active(), None);
+        assert!(!slots.is_stored(0));
+    }
+}
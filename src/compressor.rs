@@ -0,0 +1,224 @@
+//! Master bus compressor / limiter.
+//!
+//! Feed-forward, stereo-linked: one gain follows the louder channel, so the
+//! image does not shift while it works. The level above `threshold_db` is
+//! divided by `ratio`; at `LIMIT_RATIO` and above it is held at the
+//! threshold (limiting). Attack and release smooth the gain reduction in dB,
+//! which keeps loud multi-carrier patches from pumping on every peak.
+
+/// Ratios from here up act as a limiter (∞:1).
+pub const LIMIT_RATIO: f32 = 20.0;
+
+/// Accepted parameter ranges.
+pub const MIN_THRESHOLD_DB: f32 = -40.0;
+pub const MAX_MAKEUP_DB: f32 = 24.0;
+
+pub struct MasterCompressor {
+    pub enabled: bool,
+    threshold_db: f32,
+    ratio: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    makeup_db: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    makeup_gain: f32,
+    gain_reduction_db: f32,
+    sample_rate: f32,
+}
+
+impl MasterCompressor {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut compressor = Self {
+            enabled: true,
+            threshold_db: -1.0,
+            ratio: LIMIT_RATIO,
+            attack_ms: 1.0,
+            release_ms: 150.0,
+            makeup_db: 0.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            makeup_gain: 1.0,
+            gain_reduction_db: 0.0,
+            sample_rate,
+        };
+        compressor.update_coeffs();
+        compressor
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update_coeffs();
+    }
+
+    pub fn threshold_db(&self) -> f32 {
+        self.threshold_db
+    }
+
+    pub fn ratio(&self) -> f32 {
+        self.ratio
+    }
+
+    pub fn attack_ms(&self) -> f32 {
+        self.attack_ms
+    }
+
+    pub fn release_ms(&self) -> f32 {
+        self.release_ms
+    }
+
+    pub fn makeup_db(&self) -> f32 {
+        self.makeup_db
+    }
+
+    /// Current gain reduction in dB (0 = untouched, positive = quieter).
+    pub fn gain_reduction_db(&self) -> f32 {
+        self.gain_reduction_db
+    }
+
+    pub fn set_threshold_db(&mut self, db: f32) {
+        self.threshold_db = db.clamp(MIN_THRESHOLD_DB, 0.0);
+    }
+
+    pub fn set_ratio(&mut self, ratio: f32) {
+        self.ratio = ratio.clamp(1.0, LIMIT_RATIO);
+    }
+
+    /// Attack time in ms, 0.1 – 100.
+    pub fn set_attack_ms(&mut self, ms: f32) {
+        self.attack_ms = ms.clamp(0.1, 100.0);
+        self.update_coeffs();
+    }
+
+    /// Release time in ms, 10 – 2000.
+    pub fn set_release_ms(&mut self, ms: f32) {
+        self.release_ms = ms.clamp(10.0, 2000.0);
+        self.update_coeffs();
+    }
+
+    pub fn set_makeup_db(&mut self, db: f32) {
+        self.makeup_db = db.clamp(0.0, MAX_MAKEUP_DB);
+        self.makeup_gain = 10.0_f32.powf(self.makeup_db / 20.0);
+    }
+
+    fn update_coeffs(&mut self) {
+        let coeff = |ms: f32| (-1.0 / (ms * 0.001 * self.sample_rate)).exp();
+        self.attack_coeff = coeff(self.attack_ms);
+        self.release_coeff = coeff(self.release_ms);
+    }
+
+    /// Static curve: how many dB to pull a `level_db` peak down.
+    fn target_reduction_db(&self, level_db: f32) -> f32 {
+        let over = level_db - self.threshold_db;
+        if over <= 0.0 {
+            0.0
+        } else if self.ratio >= LIMIT_RATIO {
+            over
+        } else {
+            over * (1.0 - 1.0 / self.ratio)
+        }
+    }
+
+    pub fn process(&mut self, l: f32, r: f32) -> (f32, f32) {
+        if !self.enabled {
+            self.gain_reduction_db = 0.0;
+            return (l, r);
+        }
+
+        let peak = l.abs().max(r.abs());
+        let target = if peak > 0.0 {
+            self.target_reduction_db(20.0 * peak.log10())
+        } else {
+            0.0
+        };
+        let coeff = if target > self.gain_reduction_db {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.gain_reduction_db = target + coeff * (self.gain_reduction_db - target);
+        // Flush the release tail so a quiet signal is passed bit-exact.
+        if self.gain_reduction_db < 1e-6 {
+            self.gain_reduction_db = 0.0;
+        }
+
+        let gain = if self.gain_reduction_db == 0.0 {
+            self.makeup_gain
+        } else {
+            10.0_f32.powf(-self.gain_reduction_db / 20.0) * self.makeup_gain
+        };
+        (l * gain, r * gain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SR: f32 = 44_100.0;
+
+    fn run(c: &mut MasterCompressor, amplitude: f32, samples: usize) -> f32 {
+        let mut peak = 0.0_f32;
+        for i in 0..samples {
+            let x = amplitude * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / SR).sin();
+            let (l, r) = c.process(x, x);
+            assert_eq!(l, r);
+            if i > samples / 2 {
+                peak = peak.max(l.abs());
+            }
+        }
+        peak
+    }
+
+    #[test]
+    fn quiet_signal_passes_untouched() {
+        let mut c = MasterCompressor::new(SR);
+        for i in 0..4096 {
+            let x = 0.5 * (i as f32 * 0.05).sin();
+            assert_eq!(c.process(x, -x), (x, -x));
+        }
+        assert_eq!(c.gain_reduction_db(), 0.0);
+    }
+
+    #[test]
+    fn limiter_holds_peaks_near_the_threshold() {
+        let mut c = MasterCompressor::new(SR);
+        c.set_threshold_db(-6.0);
+        let peak = run(&mut c, 2.0, 44_100);
+        let threshold = 10.0_f32.powf(-6.0 / 20.0);
+        assert!(peak < threshold * 1.1, "{peak}");
+        assert!(c.gain_reduction_db() > 10.0);
+    }
+
+    #[test]
+    fn ratio_compresses_proportionally_and_release_recovers() {
+        let mut c = MasterCompressor::new(SR);
+        c.set_threshold_db(-20.0);
+        c.set_ratio(4.0);
+        c.set_attack_ms(0.1);
+        c.set_release_ms(10.0);
+        for _ in 0..1000 {
+            c.process(1.0, 1.0);
+        }
+        // 20 dB over at 4:1 → 15 dB of reduction.
+        assert!((c.gain_reduction_db() - 15.0).abs() < 0.1);
+
+        for _ in 0..SR as usize {
+            c.process(0.0, 0.0);
+        }
+        assert_eq!(c.gain_reduction_db(), 0.0);
+    }
+
+    #[test]
+    fn makeup_and_bypass() {
+        let mut c = MasterCompressor::new(SR);
+        c.set_makeup_db(6.0);
+        let (l, _) = c.process(0.1, 0.1);
+        assert!((l - 0.1 * 10.0_f32.powf(6.0 / 20.0)).abs() < 1e-6);
+        c.enabled = false;
+        assert_eq!(c.process(3.0, -3.0), (3.0, -3.0));
+        assert_eq!(c.gain_reduction_db(), 0.0);
+        c.set_ratio(100.0);
+        assert_eq!(c.ratio(), LIMIT_RATIO);
+    }
+}
@@ -0,0 +1,216 @@
+//! Batch preset conversion for `--convert`: DX7 SysEx banks to user-preset
+//! files and user-preset files back to banks, optionally with a short WAV
+//! preview of every voice.
+//!
+//! Voice files are named by bank position and voice name
+//! (`07-e-piano-1.json`), so a bank of identical INIT VOICEs still gives one
+//! file each and a folder lists in bank order. Converting the folder back
+//! writes the voices in that order, 32 to a bank.
+
+use crate::preset_preview::default_phrase;
+use crate::presets::Dx7Preset;
+use crate::render::{render_events, write_wav};
+use crate::sysex::{self, SysexResult};
+use crate::user_presets;
+use std::path::{Path, PathBuf};
+
+/// Voices in a DX7 bank.
+pub const BANK_SIZE: usize = 32;
+/// Release rendered after the preview phrase, in seconds.
+const PREVIEW_TAIL_SECONDS: f32 = 1.0;
+
+/// True when `input` holds user presets (a `.json` file or a folder) rather
+/// than SysEx.
+pub fn is_preset_input(input: &Path) -> bool {
+    input.is_dir() || input.extension().is_some_and(|ext| ext == "json")
+}
+
+/// Voices in a SysEx file, plus a note for anything forgiven on the way.
+/// Takes a bulk dump or single voice, then falls back to the tolerant bank
+/// reader for dumps with an editor's header or bare cartridge data.
+pub fn read_syx(bytes: &[u8]) -> Result<(Vec<Dx7Preset>, Vec<String>), String> {
+    match sysex::parse_message(bytes) {
+        Ok(SysexResult::SingleVoice(preset)) => Ok((vec![*preset], Vec::new())),
+        Ok(SysexResult::Bulk(presets)) => Ok((presets, Vec::new())),
+        Ok(SysexResult::Tuning(_) | SysexResult::NoteTuning(_)) => {
+            Err("a tuning dump, not voices".to_string())
+        }
+        Err(e) => {
+            let bank = sysex::parse_bank_file(bytes).map_err(|_| e.to_string())?;
+            let mut notes = Vec::new();
+            if bank.header_len > 0 {
+                notes.push(format!("{}-byte header skipped", bank.header_len));
+            }
+            if bank.checksum_ok == Some(false) {
+                notes.push("checksum mismatch".to_string());
+            }
+            notes.extend(
+                bank.errors
+                    .iter()
+                    .map(|e| format!("voice {} {}: {}", e.voice, e.name, e.message)),
+            );
+            Ok((bank.presets, notes))
+        }
+    }
+}
+
+/// User presets from a `.json` file, or every one in a folder in file-name
+/// order (files that fail to load are logged and skipped).
+pub fn read_presets(input: &Path) -> Result<Vec<Dx7Preset>, String> {
+    let presets = if input.is_dir() {
+        user_presets::load_dir(input)
+    } else {
+        let json = std::fs::read_to_string(input).map_err(|e| e.to_string())?;
+        vec![user_presets::from_json(&json)?]
+    };
+    if presets.is_empty() {
+        return Err(format!("no presets in {}", input.display()));
+    }
+    Ok(presets)
+}
+
+/// File stem for voice `index` (0-based) of a conversion.
+fn voice_stem(index: usize, preset: &Dx7Preset) -> String {
+    let name = user_presets::file_name(&preset.name);
+    format!("{:02}-{}", index + 1, name.trim_end_matches(".json"))
+}
+
+/// Write each preset into `dir` as a user-preset file.
+pub fn write_presets(presets: &[Dx7Preset], dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    presets
+        .iter()
+        .enumerate()
+        .map(|(i, preset)| {
+            let path = dir.join(voice_stem(i, preset)).with_extension("json");
+            std::fs::write(&path, user_presets::to_json(preset))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Write the presets as 32-voice banks: the first to `out`, the next to
+/// `<out>-2.syx`, `<out>-3.syx` and so on. The last bank is padded with
+/// INIT VOICE.
+pub fn write_banks(presets: &[Dx7Preset], out: &Path) -> std::io::Result<Vec<PathBuf>> {
+    if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let stem = out.file_stem().unwrap_or_default().to_string_lossy();
+    presets
+        .chunks(BANK_SIZE)
+        .enumerate()
+        .map(|(i, bank)| {
+            let path = match i {
+                0 => out.to_path_buf(),
+                _ => out.with_file_name(format!("{}-{}.syx", stem, i + 1)),
+            };
+            std::fs::write(&path, sysex::encode_bank(bank, 0))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Render the browser's audition phrase with each preset into `dir`, as
+/// `<prefix><voice file stem>.wav`.
+pub fn write_previews(
+    presets: &[Dx7Preset],
+    dir: &Path,
+    prefix: &str,
+    sample_rate: u32,
+) -> std::io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let phrase = default_phrase();
+    presets
+        .iter()
+        .enumerate()
+        .map(|(i, preset)| {
+            let path = dir.join(format!("{}{}.wav", prefix, voice_stem(i, preset)));
+            let frames = render_events(
+                Some(preset),
+                &phrase,
+                sample_rate as f32,
+                PREVIEW_TAIL_SECONDS,
+            );
+            write_wav(&path, &frames, sample_rate)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presets::PresetOperator;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "synth-fm-rs-convert-{}-{}",
+            std::process::id(),
+            name
+        ))
+    }
+
+    fn preset(name: &str, algorithm: u8) -> Dx7Preset {
+        Dx7Preset {
+            name: name.to_string(),
+            algorithm,
+            operators: std::array::from_fn(|_| PresetOperator::default()),
+            ..Dx7Preset::default()
+        }
+    }
+
+    #[test]
+    fn banks_convert_to_files_and_back() {
+        let dir = temp_dir("round-trip");
+        let _ = std::fs::remove_dir_all(&dir);
+        let voices: Vec<Dx7Preset> = (0..40)
+            .map(|i| preset(if i < 2 { "INIT VOICE" } else { "BRASS 1" }, i % 32 + 1))
+            .collect();
+
+        let banks = write_banks(&voices, &dir.join("out.syx")).unwrap();
+        assert_eq!(banks, [dir.join("out.syx"), dir.join("out-2.syx")]);
+        let (first, notes) = read_syx(&std::fs::read(&banks[0]).unwrap()).unwrap();
+        assert!(notes.is_empty());
+        assert_eq!(first.len(), BANK_SIZE);
+
+        let files = write_presets(&first, &dir.join("voices")).unwrap();
+        assert_eq!(files.len(), BANK_SIZE);
+        assert_eq!(files[0].file_name().unwrap(), "01-init-voice.json");
+        assert_eq!(files[1].file_name().unwrap(), "02-init-voice.json");
+
+        let back = read_presets(&dir.join("voices")).unwrap();
+        let algorithms: Vec<u8> = back.iter().map(|p| p.algorithm).collect();
+        let expected: Vec<u8> = (1..=32).collect();
+        assert_eq!(algorithms, expected);
+        assert_eq!(back[5].name, "BRASS 1");
+        assert_eq!(read_presets(&files[5]).unwrap()[0].name, "BRASS 1");
+
+        // The second bank holds the last 8 voices, padded with INIT VOICE.
+        let (second, _) = read_syx(&std::fs::read(&banks[1]).unwrap()).unwrap();
+        assert_eq!(second[7].algorithm, 8);
+        assert_eq!(second[8].name, "INIT VOICE");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn previews_are_rendered_per_voice() {
+        let dir = temp_dir("previews");
+        let _ = std::fs::remove_dir_all(&dir);
+        let voices = [preset("E.PIANO 1", 5), preset("BASS", 16)];
+        let wavs = write_previews(&voices, &dir, "bank-", 22_050).unwrap();
+        assert_eq!(wavs[1].file_name().unwrap(), "bank-02-bass.wav");
+        let bytes = std::fs::read(&wavs[0]).unwrap();
+        assert_eq!(&bytes[..4], b"RIFF");
+        assert!(bytes.len() > 22_050 * 4);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unusable_inputs_are_refused() {
+        assert!(read_syx(&[0xF0, 0x43, 0x00, 0xF7]).is_err());
+        assert!(read_presets(&temp_dir("missing")).is_err());
+        assert!(is_preset_input(Path::new("voices/brass.json")));
+        assert!(!is_preset_input(Path::new("rom1a.syx")));
+    }
+}
@@ -0,0 +1,475 @@
+//! DX7-style data entry: the parameter last touched in the editor, which the
+//! big DATA ENTRY slider and the YES / NO buttons then edit.
+//!
+//! Like on the hardware every parameter here is a whole number in its DX7
+//! range. Values are read back from the snapshot, so the slider follows
+//! edits made anywhere else (MIDI, presets, the page's own sliders).
+
+use crate::command_queue::{EnvelopeParam, LfoParam, OperatorParam};
+use crate::fm_synth::SynthController;
+use crate::gui::midi_note_name;
+use crate::lfo::LFOWaveform;
+use crate::state_snapshot::{PortamentoMode, SynthSnapshot, VoiceMode};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Per-operator parameters reachable from data entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperatorField {
+    Coarse,
+    Fine,
+    Level,
+    Detune,
+    VelocitySensitivity,
+    Feedback,
+    AmSensitivity,
+    KeyScaleRate,
+    KeyScaleBreakpoint,
+    KeyScaleLeftDepth,
+    KeyScaleRightDepth,
+    /// Envelope rate 1..=4, stored 0-based.
+    EgRate(u8),
+    /// Envelope level 1..=4, stored 0-based.
+    EgLevel(u8),
+}
+
+/// Controller routings (FUNCTION mode): how far each controller moves
+/// pitch, amplitude, EG bias or volume.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControllerField {
+    ModWheelPitchBias,
+    ModWheelEgBias,
+    AftertouchPitch,
+    AftertouchAmp,
+    AftertouchEgBias,
+    AftertouchPitchBias,
+    BreathPitch,
+    BreathAmp,
+    BreathEgBias,
+    BreathPitchBias,
+    FootVolume,
+    FootPitch,
+    FootAmp,
+    FootEgBias,
+}
+
+/// A parameter the data-entry controls can edit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataEntryParam {
+    Algorithm,
+    PitchBendRange,
+    PortamentoTime,
+    LfoRate,
+    LfoDelay,
+    LfoPitchDepth,
+    LfoAmpDepth,
+    /// Index into `LFOWaveform::all()`.
+    LfoWaveform,
+    LfoKeySync,
+    PitchModSensitivity,
+    /// Semitones, 0 = no transpose.
+    Transpose,
+    /// Cents.
+    MasterTune,
+    /// 0 poly, 1 mono, 2 mono legato.
+    VoiceMode,
+    /// 0 full time, 1 fingered.
+    PortamentoMode,
+    PortamentoGlissando,
+    Controller(ControllerField),
+    /// Operator index 0..6 (OP1 first) and the parameter.
+    Operator(u8, OperatorField),
+}
+
+impl ControllerField {
+    pub const ALL: [ControllerField; 14] = [
+        ControllerField::ModWheelPitchBias,
+        ControllerField::ModWheelEgBias,
+        ControllerField::AftertouchPitch,
+        ControllerField::AftertouchAmp,
+        ControllerField::AftertouchEgBias,
+        ControllerField::AftertouchPitchBias,
+        ControllerField::BreathPitch,
+        ControllerField::BreathAmp,
+        ControllerField::BreathEgBias,
+        ControllerField::BreathPitchBias,
+        ControllerField::FootVolume,
+        ControllerField::FootPitch,
+        ControllerField::FootAmp,
+        ControllerField::FootEgBias,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            ControllerField::ModWheelPitchBias => "MW PITCH BIAS",
+            ControllerField::ModWheelEgBias => "MW EG BIAS",
+            ControllerField::AftertouchPitch => "AT PITCH",
+            ControllerField::AftertouchAmp => "AT AMP",
+            ControllerField::AftertouchEgBias => "AT EG BIAS",
+            ControllerField::AftertouchPitchBias => "AT P BIAS",
+            ControllerField::BreathPitch => "BC PITCH",
+            ControllerField::BreathAmp => "BC AMP",
+            ControllerField::BreathEgBias => "BC EG BIAS",
+            ControllerField::BreathPitchBias => "BC P BIAS",
+            ControllerField::FootVolume => "FC VOLUME",
+            ControllerField::FootPitch => "FC PITCH",
+            ControllerField::FootAmp => "FC AMP",
+            ControllerField::FootEgBias => "FC EG BIAS",
+        }
+    }
+
+    fn value(&self, snapshot: &SynthSnapshot) -> u8 {
+        match self {
+            ControllerField::ModWheelPitchBias => snapshot.pitch_bias_sensitivity,
+            ControllerField::ModWheelEgBias => snapshot.eg_bias_sensitivity,
+            ControllerField::AftertouchPitch => snapshot.aftertouch_pitch_sens,
+            ControllerField::AftertouchAmp => snapshot.aftertouch_amp_sens,
+            ControllerField::AftertouchEgBias => snapshot.aftertouch_eg_bias_sens,
+            ControllerField::AftertouchPitchBias => snapshot.aftertouch_pitch_bias_sens,
+            ControllerField::BreathPitch => snapshot.breath_pitch_sens,
+            ControllerField::BreathAmp => snapshot.breath_amp_sens,
+            ControllerField::BreathEgBias => snapshot.breath_eg_bias_sens,
+            ControllerField::BreathPitchBias => snapshot.breath_pitch_bias_sens,
+            ControllerField::FootVolume => snapshot.foot_volume_sens,
+            ControllerField::FootPitch => snapshot.foot_pitch_sens,
+            ControllerField::FootAmp => snapshot.foot_amp_sens,
+            ControllerField::FootEgBias => snapshot.foot_eg_bias_sens,
+        }
+    }
+
+    fn set(&self, ctrl: &mut SynthController, sens: u8) {
+        match self {
+            ControllerField::ModWheelPitchBias => ctrl.set_pitch_bias_sensitivity(sens),
+            ControllerField::ModWheelEgBias => ctrl.set_eg_bias_sensitivity(sens),
+            ControllerField::AftertouchPitch => ctrl.set_aftertouch_pitch_sens(sens),
+            ControllerField::AftertouchAmp => ctrl.set_aftertouch_amp_sens(sens),
+            ControllerField::AftertouchEgBias => ctrl.set_aftertouch_eg_bias_sens(sens),
+            ControllerField::AftertouchPitchBias => ctrl.set_aftertouch_pitch_bias_sens(sens),
+            ControllerField::BreathPitch => ctrl.set_breath_pitch_sens(sens),
+            ControllerField::BreathAmp => ctrl.set_breath_amp_sens(sens),
+            ControllerField::BreathEgBias => ctrl.set_breath_eg_bias_sens(sens),
+            ControllerField::BreathPitchBias => ctrl.set_breath_pitch_bias_sens(sens),
+            ControllerField::FootVolume => ctrl.set_foot_volume_sens(sens),
+            ControllerField::FootPitch => ctrl.set_foot_pitch_sens(sens),
+            ControllerField::FootAmp => ctrl.set_foot_amp_sens(sens),
+            ControllerField::FootEgBias => ctrl.set_foot_eg_bias_sens(sens),
+        }
+    }
+}
+
+impl OperatorField {
+    /// Name without the operator number, for screens that show it apart.
+    pub fn label(&self) -> String {
+        match self {
+            OperatorField::Coarse => "F COARSE".to_string(),
+            OperatorField::Fine => "F FINE".to_string(),
+            OperatorField::Level => "OUTPUT LEVEL".to_string(),
+            OperatorField::Detune => "OSC DETUNE".to_string(),
+            OperatorField::VelocitySensitivity => "KEY VELOCITY".to_string(),
+            OperatorField::Feedback => "FEEDBACK".to_string(),
+            OperatorField::AmSensitivity => "AMS".to_string(),
+            OperatorField::KeyScaleRate => "RATE SCALING".to_string(),
+            OperatorField::KeyScaleBreakpoint => "BREAK POINT".to_string(),
+            OperatorField::KeyScaleLeftDepth => "L DEPTH".to_string(),
+            OperatorField::KeyScaleRightDepth => "R DEPTH".to_string(),
+            OperatorField::EgRate(i) => format!("EG RATE {}", i + 1),
+            OperatorField::EgLevel(i) => format!("EG LEVEL {}", i + 1),
+        }
+    }
+}
+
+const EG_RATES: [EnvelopeParam; 4] = [
+    EnvelopeParam::Rate1,
+    EnvelopeParam::Rate2,
+    EnvelopeParam::Rate3,
+    EnvelopeParam::Rate4,
+];
+const EG_LEVELS: [EnvelopeParam; 4] = [
+    EnvelopeParam::Level1,
+    EnvelopeParam::Level2,
+    EnvelopeParam::Level3,
+    EnvelopeParam::Level4,
+];
+
+impl DataEntryParam {
+    /// Name shown on the LCD, in the DX7's wording where it has one.
+    pub fn label(&self) -> String {
+        match self {
+            DataEntryParam::Algorithm => "ALGORITHM SELECT".to_string(),
+            DataEntryParam::PitchBendRange => "P BEND RANGE".to_string(),
+            DataEntryParam::PortamentoTime => "PORTAMENTO TIME".to_string(),
+            DataEntryParam::LfoRate => "LFO SPEED".to_string(),
+            DataEntryParam::LfoDelay => "LFO DELAY".to_string(),
+            DataEntryParam::LfoPitchDepth => "LFO PM DEPTH".to_string(),
+            DataEntryParam::LfoAmpDepth => "LFO AM DEPTH".to_string(),
+            DataEntryParam::LfoWaveform => "LFO WAVE".to_string(),
+            DataEntryParam::LfoKeySync => "LFO KEY SYNC".to_string(),
+            DataEntryParam::PitchModSensitivity => "P MOD SENS".to_string(),
+            DataEntryParam::Transpose => "KEY TRANSPOSE".to_string(),
+            DataEntryParam::MasterTune => "MASTER TUNE".to_string(),
+            DataEntryParam::VoiceMode => "POLY/MONO".to_string(),
+            DataEntryParam::PortamentoMode => "PORTA MODE".to_string(),
+            DataEntryParam::PortamentoGlissando => "GLISSANDO".to_string(),
+            DataEntryParam::Controller(field) => field.label().to_string(),
+            DataEntryParam::Operator(op, field) => format!("OP{} {}", op + 1, field.label()),
+        }
+    }
+
+    pub fn range(&self) -> RangeInclusive<i32> {
+        match self {
+            DataEntryParam::Algorithm => 1..=32,
+            DataEntryParam::PitchBendRange => 0..=12,
+            DataEntryParam::PortamentoTime
+            | DataEntryParam::LfoRate
+            | DataEntryParam::LfoDelay
+            | DataEntryParam::LfoPitchDepth
+            | DataEntryParam::LfoAmpDepth => 0..=99,
+            DataEntryParam::LfoWaveform => 0..=LFOWaveform::all().len() as i32 - 1,
+            DataEntryParam::LfoKeySync
+            | DataEntryParam::PortamentoMode
+            | DataEntryParam::PortamentoGlissando => 0..=1,
+            DataEntryParam::PitchModSensitivity => 0..=7,
+            DataEntryParam::Transpose => -24..=24,
+            DataEntryParam::MasterTune => -150..=150,
+            DataEntryParam::VoiceMode => 0..=2,
+            DataEntryParam::Controller(ControllerField::FootVolume) => 0..=15,
+            DataEntryParam::Controller(_) => 0..=7,
+            DataEntryParam::Operator(_, field) => match field {
+                OperatorField::Coarse => 0..=31,
+                OperatorField::Detune => -7..=7,
+                OperatorField::VelocitySensitivity
+                | OperatorField::Feedback
+                | OperatorField::KeyScaleRate => 0..=7,
+                OperatorField::AmSensitivity => 0..=3,
+                OperatorField::KeyScaleBreakpoint => 0..=127,
+                OperatorField::Fine
+                | OperatorField::Level
+                | OperatorField::KeyScaleLeftDepth
+                | OperatorField::KeyScaleRightDepth
+                | OperatorField::EgRate(_)
+                | OperatorField::EgLevel(_) => 0..=99,
+            },
+        }
+    }
+
+    /// Current value, as reported by the engine.
+    pub fn value(&self, snapshot: &SynthSnapshot) -> i32 {
+        let value = match self {
+            DataEntryParam::Algorithm => snapshot.algorithm as f32,
+            DataEntryParam::PitchBendRange => snapshot.pitch_bend_range,
+            DataEntryParam::PortamentoTime => snapshot.portamento_time,
+            DataEntryParam::LfoRate => snapshot.lfo_rate,
+            DataEntryParam::LfoDelay => snapshot.lfo_delay,
+            DataEntryParam::LfoPitchDepth => snapshot.lfo_pitch_depth,
+            DataEntryParam::LfoAmpDepth => snapshot.lfo_amp_depth,
+            DataEntryParam::LfoWaveform => LFOWaveform::all()
+                .iter()
+                .position(|&w| w == snapshot.lfo_waveform)
+                .unwrap_or(0) as f32,
+            DataEntryParam::LfoKeySync => snapshot.lfo_key_sync as u8 as f32,
+            DataEntryParam::PitchModSensitivity => snapshot.pitch_mod_sensitivity as f32,
+            DataEntryParam::Transpose => snapshot.transpose_semitones as f32,
+            DataEntryParam::MasterTune => snapshot.master_tune,
+            DataEntryParam::VoiceMode => match snapshot.voice_mode {
+                VoiceMode::Poly => 0.0,
+                VoiceMode::Mono => 1.0,
+                VoiceMode::MonoLegato => 2.0,
+            },
+            DataEntryParam::PortamentoMode => match snapshot.portamento_mode {
+                PortamentoMode::FullTime => 0.0,
+                PortamentoMode::Fingered => 1.0,
+            },
+            DataEntryParam::PortamentoGlissando => snapshot.portamento_glissando as u8 as f32,
+            DataEntryParam::Controller(field) => field.value(snapshot) as f32,
+            DataEntryParam::Operator(op, field) => {
+                let op = &snapshot.operators[*op as usize];
+                match field {
+                    OperatorField::Coarse => op.coarse as f32,
+                    OperatorField::Fine => op.fine as f32,
+                    OperatorField::Level => op.output_level,
+                    OperatorField::Detune => op.detune,
+                    OperatorField::VelocitySensitivity => op.velocity_sensitivity,
+                    OperatorField::Feedback => op.feedback,
+                    OperatorField::AmSensitivity => op.am_sensitivity as f32,
+                    OperatorField::KeyScaleRate => op.key_scale_rate,
+                    OperatorField::KeyScaleBreakpoint => op.key_scale_breakpoint as f32,
+                    OperatorField::KeyScaleLeftDepth => op.key_scale_left_depth,
+                    OperatorField::KeyScaleRightDepth => op.key_scale_right_depth,
+                    OperatorField::EgRate(i) => {
+                        [op.rate1, op.rate2, op.rate3, op.rate4][*i as usize]
+                    }
+                    OperatorField::EgLevel(i) => {
+                        [op.level1, op.level2, op.level3, op.level4][*i as usize]
+                    }
+                }
+            }
+        };
+        value.round() as i32
+    }
+
+    /// Value as the LCD shows it.
+    pub fn format_value(&self, value: i32) -> String {
+        match self {
+            DataEntryParam::Operator(_, OperatorField::Coarse) if value == 0 => {
+                "0 (x0.5)".to_string()
+            }
+            DataEntryParam::Operator(_, OperatorField::Detune)
+            | DataEntryParam::Transpose
+            | DataEntryParam::MasterTune => format!("{value:+}"),
+            DataEntryParam::LfoWaveform => LFOWaveform::all()
+                .get(value.max(0) as usize)
+                .map_or("", |w| w.name())
+                .to_uppercase(),
+            DataEntryParam::LfoKeySync | DataEntryParam::PortamentoGlissando => {
+                if value > 0 { "ON" } else { "OFF" }.to_string()
+            }
+            DataEntryParam::VoiceMode => match value {
+                0 => "POLY",
+                1 => "MONO",
+                _ => "LEGATO",
+            }
+            .to_string(),
+            DataEntryParam::PortamentoMode => {
+                if value > 0 { "FINGERED" } else { "FULL TIME" }.to_string()
+            }
+            DataEntryParam::Operator(_, OperatorField::KeyScaleBreakpoint) => {
+                midi_note_name(value.clamp(0, 127) as u8)
+            }
+            _ => value.to_string(),
+        }
+    }
+
+    /// Send `value`, clamped to the range, to the engine.
+    pub fn set(&self, ctrl: &mut SynthController, value: i32) {
+        let range = self.range();
+        let value = value.clamp(*range.start(), *range.end());
+        let v = value as f32;
+        match self {
+            DataEntryParam::Algorithm => ctrl.set_algorithm(value as u8),
+            DataEntryParam::PitchBendRange => ctrl.set_pitch_bend_range(v),
+            DataEntryParam::PortamentoTime => ctrl.set_portamento_time(v),
+            DataEntryParam::LfoRate => ctrl.set_lfo_param(LfoParam::Rate, v),
+            DataEntryParam::LfoDelay => ctrl.set_lfo_param(LfoParam::Delay, v),
+            DataEntryParam::LfoPitchDepth => ctrl.set_lfo_param(LfoParam::PitchDepth, v),
+            DataEntryParam::LfoAmpDepth => ctrl.set_lfo_param(LfoParam::AmpDepth, v),
+            DataEntryParam::LfoWaveform => ctrl.set_lfo_param(LfoParam::Waveform(value as u8), 0.0),
+            DataEntryParam::LfoKeySync => ctrl.set_lfo_param(LfoParam::KeySync, v),
+            DataEntryParam::PitchModSensitivity => ctrl.set_pitch_mod_sensitivity(value as u8),
+            DataEntryParam::Transpose => ctrl.set_transpose(value as i8),
+            DataEntryParam::MasterTune => ctrl.set_master_tune(v),
+            DataEntryParam::VoiceMode => ctrl.set_voice_mode(match value {
+                0 => VoiceMode::Poly,
+                1 => VoiceMode::Mono,
+                _ => VoiceMode::MonoLegato,
+            }),
+            DataEntryParam::PortamentoMode => ctrl.set_portamento_mode(if value > 0 {
+                PortamentoMode::Fingered
+            } else {
+                PortamentoMode::FullTime
+            }),
+            DataEntryParam::PortamentoGlissando => ctrl.set_portamento_glissando(value > 0),
+            DataEntryParam::Controller(field) => field.set(ctrl, value as u8),
+            DataEntryParam::Operator(op, field) => {
+                let param = match field {
+                    OperatorField::EgRate(i) => {
+                        return ctrl.set_envelope_param(*op, EG_RATES[*i as usize], v);
+                    }
+                    OperatorField::EgLevel(i) => {
+                        return ctrl.set_envelope_param(*op, EG_LEVELS[*i as usize], v);
+                    }
+                    OperatorField::Coarse => OperatorParam::Coarse,
+                    OperatorField::Fine => OperatorParam::Fine,
+                    OperatorField::Level => OperatorParam::Level,
+                    OperatorField::Detune => OperatorParam::Detune,
+                    OperatorField::VelocitySensitivity => OperatorParam::VelocitySensitivity,
+                    OperatorField::Feedback => OperatorParam::Feedback,
+                    OperatorField::AmSensitivity => OperatorParam::AmSensitivity,
+                    OperatorField::KeyScaleRate => OperatorParam::KeyScaleRate,
+                    OperatorField::KeyScaleBreakpoint => OperatorParam::KeyScaleBreakpoint,
+                    OperatorField::KeyScaleLeftDepth => OperatorParam::KeyScaleLeftDepth,
+                    OperatorField::KeyScaleRightDepth => OperatorParam::KeyScaleRightDepth,
+                };
+                ctrl.set_operator_param(*op, param, v);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fm_synth::create_synth;
+
+    #[test]
+    fn labels_follow_the_dx7_wording() {
+        assert_eq!(DataEntryParam::Algorithm.label(), "ALGORITHM SELECT");
+        assert_eq!(
+            DataEntryParam::Operator(2, OperatorField::Level).label(),
+            "OP3 OUTPUT LEVEL"
+        );
+        assert_eq!(
+            DataEntryParam::Operator(0, OperatorField::EgRate(3)).label(),
+            "OP1 EG RATE 4"
+        );
+    }
+
+    #[test]
+    fn values_are_formatted_like_the_lcd() {
+        let detune = DataEntryParam::Operator(0, OperatorField::Detune);
+        assert_eq!(detune.format_value(3), "+3");
+        assert_eq!(detune.format_value(-2), "-2");
+        let coarse = DataEntryParam::Operator(0, OperatorField::Coarse);
+        assert_eq!(coarse.format_value(0), "0 (x0.5)");
+        let breakpoint = DataEntryParam::Operator(0, OperatorField::KeyScaleBreakpoint);
+        assert_eq!(breakpoint.format_value(60), "C3");
+        assert_eq!(DataEntryParam::LfoWaveform.format_value(5), "S&H");
+        assert_eq!(DataEntryParam::VoiceMode.format_value(1), "MONO");
+        assert_eq!(DataEntryParam::LfoKeySync.format_value(0), "OFF");
+    }
+
+    #[test]
+    fn set_clamps_and_reaches_the_engine() {
+        let (mut engine, mut ctrl) = create_synth(44_100.0);
+        let cases = [
+            (DataEntryParam::Algorithm, 40, 32),
+            (DataEntryParam::PitchBendRange, 7, 7),
+            (DataEntryParam::LfoRate, 55, 55),
+            (DataEntryParam::Operator(2, OperatorField::Level), 70, 70),
+            (DataEntryParam::Operator(4, OperatorField::Detune), -9, -7),
+            (
+                DataEntryParam::Operator(1, OperatorField::EgLevel(2)),
+                42,
+                42,
+            ),
+            (DataEntryParam::Operator(5, OperatorField::Coarse), 3, 3),
+            (DataEntryParam::LfoWaveform, 4, 4),
+            (DataEntryParam::Transpose, -30, -24),
+            (DataEntryParam::MasterTune, 25, 25),
+            (DataEntryParam::VoiceMode, 2, 2),
+            (DataEntryParam::PortamentoMode, 1, 1),
+            (DataEntryParam::PortamentoGlissando, 1, 1),
+            (
+                DataEntryParam::Controller(ControllerField::FootVolume),
+                12,
+                12,
+            ),
+            (
+                DataEntryParam::Controller(ControllerField::AftertouchAmp),
+                12,
+                7,
+            ),
+        ];
+        for (param, value, _) in cases {
+            param.set(&mut ctrl, value);
+        }
+        engine.process_commands();
+        engine.update_snapshot();
+        let snapshot = ctrl.snapshot();
+        for (param, _, expected) in cases {
+            assert_eq!(param.value(&snapshot), expected, "{}", param.label());
+        }
+    }
+}
@@ -3,6 +3,8 @@
 //! Standard form: `y[n] = x[n] - x[n-1] + R * y[n-1]`
 //! with `R = 1 - 2 * PI * fc / fs`.
 
+use crate::optimization::flush_denormal;
+
 pub struct DcBlocker {
     prev_input: f32,
     prev_output: f32,
@@ -11,20 +13,35 @@ pub struct DcBlocker {
 
 impl DcBlocker {
     pub fn new(sample_rate: f32, cutoff_hz: f32) -> Self {
-        let r = 1.0 - 2.0 * std::f32::consts::PI * cutoff_hz / sample_rate;
         Self {
             prev_input: 0.0,
             prev_output: 0.0,
-            r,
+            r: Self::coefficient(sample_rate, cutoff_hz),
         }
     }
 
+    /// Recompute the pole for a new sample rate, keeping the filter state.
+    pub fn set_sample_rate(&mut self, sample_rate: f32, cutoff_hz: f32) {
+        self.r = Self::coefficient(sample_rate, cutoff_hz);
+    }
+
+    fn coefficient(sample_rate: f32, cutoff_hz: f32) -> f32 {
+        1.0 - 2.0 * std::f32::consts::PI * cutoff_hz / sample_rate
+    }
+
     pub fn process(&mut self, input: f32) -> f32 {
         let output = input - self.prev_input + self.r * self.prev_output;
         self.prev_input = input;
-        self.prev_output = output;
+        // The pole keeps decaying forever after the input stops.
+        self.prev_output = flush_denormal(output);
         output
     }
+
+    /// Forget the filter history.
+    pub fn reset(&mut self) {
+        self.prev_input = 0.0;
+        self.prev_output = 0.0;
+    }
 }
 
 #[cfg(test)]
@@ -43,6 +60,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decay_after_an_impulse_ends_at_exact_zero() {
+        let mut hpf = DcBlocker::new(SAMPLE_RATE, CUTOFF);
+        hpf.process(1.0);
+        let mut last = 1.0;
+        for _ in 0..(SAMPLE_RATE as usize * 10) {
+            last = hpf.process(0.0);
+            assert!(!last.is_subnormal());
+        }
+        assert_eq!(last, 0.0);
+    }
+
     #[test]
     fn dc_input_converges_to_zero() {
         let mut hpf = DcBlocker::new(SAMPLE_RATE, CUTOFF);
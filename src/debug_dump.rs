@@ -0,0 +1,177 @@
+//! Engine state report for chasing stuck notes: which voices sound, where
+//! each operator's envelope is, which keys the engine thinks are down and
+//! how full the command queue is.
+//!
+//! Built by `SynthEngine::debug_dump`, once per request. The GUI asks for
+//! one a few times a second while its hidden debug panel is open
+//! (Ctrl+Shift+D), and prints one to stderr when the process receives
+//! SIGUSR1.
+
+use std::fmt;
+
+/// One operator of a sounding voice.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OperatorDump {
+    /// Envelope stage, as `EnvelopeStage::label` (R1..R3, REL, IDLE).
+    pub stage: &'static str,
+    /// Envelope output, 0..1.
+    pub level: f32,
+}
+
+/// One active voice.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoiceDump {
+    /// 'A' for the engine's own voices, 'B' for the performance's second part.
+    pub part: char,
+    /// Slot in the part's voice pool.
+    pub index: usize,
+    pub note: u8,
+    pub frequency: f32,
+    pub velocity: f32,
+    pub operators: [OperatorDump; 6],
+}
+
+impl VoiceDump {
+    /// Every operator is in its release segment or done.
+    pub fn released(&self) -> bool {
+        self.operators
+            .iter()
+            .all(|op| op.stage == "REL" || op.stage == "IDLE")
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EngineDump {
+    pub voices: Vec<VoiceDump>,
+    pub polyphony: usize,
+    pub voice_mode: String,
+    /// (key, voice) for each key the engine holds a voice for.
+    pub held_notes: Vec<(u8, usize)>,
+    /// Earlier voices of stacked keys (same-note mode STACK), as (key, voice).
+    pub stacked_voices: Vec<(u8, usize)>,
+    /// Keys let go while the sustain pedal was down.
+    pub sustained_notes: Vec<u8>,
+    /// Mono-mode key order, oldest first.
+    pub mono_held_order: Vec<u8>,
+    pub sustain_pedal: bool,
+    /// Commands waiting in the GUI / MIDI → audio queue, and its size.
+    pub pending_commands: usize,
+    pub command_capacity: usize,
+}
+
+impl EngineDump {
+    /// Voices still in an attack / decay / sustain stage although no key
+    /// (or pedal) holds them: the usual shape of a stuck note.
+    pub fn unheld_voices(&self) -> Vec<&VoiceDump> {
+        let held = |voice: &VoiceDump| {
+            self.held_notes
+                .iter()
+                .chain(&self.stacked_voices)
+                .any(|&(note, index)| note == voice.note && index == voice.index)
+                || self.sustained_notes.contains(&voice.note)
+                || self.mono_held_order.contains(&voice.note)
+        };
+        self.voices
+            .iter()
+            .filter(|v| v.part == 'A' && !v.released() && !held(v))
+            .collect()
+    }
+}
+
+fn note_list(notes: impl Iterator<Item = String>) -> String {
+    let list: Vec<String> = notes.collect();
+    if list.is_empty() {
+        "-".to_string()
+    } else {
+        list.join(" ")
+    }
+}
+
+impl fmt::Display for EngineDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "voices: {} active / {} ({})",
+            self.voices.len(),
+            self.polyphony,
+            self.voice_mode
+        )?;
+        let unheld = self.unheld_voices();
+        for voice in &self.voices {
+            let flag = if unheld.contains(&voice) {
+                "  <- not held"
+            } else {
+                ""
+            };
+            writeln!(
+                f,
+                "  {}{:<2} note {:>3}  {:>8.2} Hz  vel {:.2}{}",
+                voice.part, voice.index, voice.note, voice.frequency, voice.velocity, flag
+            )?;
+            let ops: Vec<String> = voice
+                .operators
+                .iter()
+                .enumerate()
+                .map(|(i, op)| format!("OP{} {:<4} {:.3}", i + 1, op.stage, op.level))
+                .collect();
+            writeln!(f, "      {}", ops.join(" | "))?;
+        }
+        let pairs = |list: &[(u8, usize)]| {
+            note_list(list.iter().map(|(note, voice)| format!("{note}->v{voice}")))
+        };
+        writeln!(f, "held notes: {}", pairs(&self.held_notes))?;
+        writeln!(f, "stacked: {}", pairs(&self.stacked_voices))?;
+        writeln!(
+            f,
+            "sustained: {}",
+            note_list(self.sustained_notes.iter().map(u8::to_string))
+        )?;
+        writeln!(
+            f,
+            "mono order: {}",
+            note_list(self.mono_held_order.iter().map(u8::to_string))
+        )?;
+        writeln!(
+            f,
+            "sustain pedal: {}",
+            if self.sustain_pedal { "down" } else { "up" }
+        )?;
+        write!(
+            f,
+            "command queue: {} / {} pending",
+            self.pending_commands, self.command_capacity
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voice(index: usize, note: u8, stage: &'static str) -> VoiceDump {
+        VoiceDump {
+            part: 'A',
+            index,
+            note,
+            frequency: 261.63,
+            velocity: 0.8,
+            operators: [OperatorDump { stage, level: 0.5 }; 6],
+        }
+    }
+
+    #[test]
+    fn voices_without_a_key_are_flagged() {
+        let dump = EngineDump {
+            voices: vec![voice(0, 60, "R3"), voice(1, 64, "R3"), voice(2, 67, "REL")],
+            held_notes: vec![(60, 0)],
+            ..EngineDump::default()
+        };
+        let unheld = dump.unheld_voices();
+        assert_eq!(unheld.len(), 1);
+        assert_eq!(unheld[0].note, 64);
+        let text = dump.to_string();
+        assert!(text.contains("note  64"));
+        assert!(text.contains("<- not held"));
+        assert!(text.contains("held notes: 60->v0"));
+    }
+}
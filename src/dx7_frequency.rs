@@ -1,3 +1,151 @@
+//! DX7 operator frequency parameters.
+//!
+//! In RATIO mode the DX7 sets an operator's pitch with COARSE (0 – 31; 0
+//! means ×0.5) and FINE (0 – 99, adding 1 % of the coarse ratio per step),
+//! plus DETUNE, stored 0 – 14 in the voice data and shown −7 … +7 on the
+//! panel. In FIXED mode the same COARSE / FINE pick a frequency on a log
+//! scale: COARSE 0 – 3 is the decade (1, 10, 100, 1000 Hz) and each FINE step
+//! a hundredth of it. The engine keeps a float ratio or Hz per operator;
+//! these functions convert between it and the hardware parameters.
+
+/// Highest COARSE value.
+pub const MAX_COARSE: u8 = 31;
+/// Highest FINE value.
+pub const MAX_FINE: u8 = 99;
+/// Stored DETUNE value that means "no detune".
+pub const DETUNE_CENTER: u8 = 7;
+/// Highest stored DETUNE value (+7 on the panel).
+pub const MAX_DETUNE: u8 = 14;
+
+/// Pitch offset of one DETUNE step. MSFA adds `13457` per step to a Q24
+/// log2 frequency: 13457 / 2^24 octave ≈ 0.96 cent.
+pub const DETUNE_CENTS_PER_STEP: f32 = 13457.0 / 16_777_216.0 * 1200.0;
+
+/// Frequency ratio of COARSE / FINE: `coarse × (1 + fine / 100)`, with
+/// coarse 0 standing for ×0.5.
+pub fn coarse_fine_to_ratio(coarse: u8, fine: u8) -> f32 {
+    let base = match coarse.min(MAX_COARSE) {
+        0 => 0.5,
+        c => c as f32,
+    };
+    base * (1.0 + fine.min(MAX_FINE) as f32 / 100.0)
+}
+
+/// The COARSE / FINE pair whose ratio is closest to `ratio`. Ratios both
+/// settings reach exactly (3.0 = 3 / 0 = 2 / 50) use the higher coarse, the
+/// way patches are programmed on the hardware.
+pub fn ratio_to_coarse_fine(ratio: f32) -> (u8, u8) {
+    let mut best = (1, 0);
+    let mut best_error = f32::INFINITY;
+    for coarse in (0..=MAX_COARSE).rev() {
+        let base = coarse_fine_to_ratio(coarse, 0);
+        let fine = ((ratio / base - 1.0) * 100.0)
+            .round()
+            .clamp(0.0, MAX_FINE as f32) as u8;
+        let error = (coarse_fine_to_ratio(coarse, fine) - ratio).abs();
+        if error < best_error {
+            best = (coarse, fine);
+            best_error = error;
+        }
+    }
+    best
+}
+
+/// True when `ratio` is exactly reachable with COARSE / FINE.
+pub fn is_dx7_ratio(ratio: f32) -> bool {
+    let (coarse, fine) = ratio_to_coarse_fine(ratio);
+    (coarse_fine_to_ratio(coarse, fine) - ratio).abs() < 1e-4
+}
+
+/// Frequency of a FIXED-mode COARSE / FINE: `10^(coarse % 4 + fine / 100)`
+/// Hz, as the DX7 and Dexed compute it (1 Hz … 9772 Hz).
+pub fn fixed_coarse_fine_to_hz(coarse: u8, fine: u8) -> f32 {
+    10f32.powf((coarse & 0x03) as f32 + fine.min(MAX_FINE) as f32 / 100.0)
+}
+
+/// The FIXED-mode COARSE / FINE pair closest to `hz`, clamped to the
+/// 1 Hz … 9772 Hz the hardware reaches.
+pub fn hz_to_fixed_coarse_fine(hz: f32) -> (u8, u8) {
+    let steps = (hz.max(1.0).log10() * 100.0).round().clamp(0.0, 399.0) as u16;
+    (
+        (steps / 100) as u8,
+        (steps % 100).min(MAX_FINE as u16) as u8,
+    )
+}
+
+/// Panel detune (−7 … +7) of a stored DETUNE value (0 – 14).
+pub fn detune_from_stored(stored: u8) -> f32 {
+    (stored.min(MAX_DETUNE) as i16 - DETUNE_CENTER as i16) as f32
+}
+
+/// Stored DETUNE value (0 – 14) of a panel detune, rounded and clamped.
+pub fn detune_to_stored(detune: f32) -> u8 {
+    (detune.round() as i16 + DETUNE_CENTER as i16).clamp(0, MAX_DETUNE as i16) as u8
+}
+
+/// Frequency multiplier of a panel detune (−7 … +7).
+pub fn detune_factor(detune: f32) -> f32 {
+    2.0_f32.powf(detune * DETUNE_CENTS_PER_STEP / 1200.0)
+}
+
+/// Frequency of a ratio-mode operator when the key plays `key_hz`.
+pub fn operator_frequency(key_hz: f32, ratio: f32, detune: f32) -> f32 {
+    key_hz * ratio * detune_factor(detune)
+}
+
+/// Interval names by semitone within the octave.
+const INTERVAL_NAMES: [&str; 12] = [
+    "unison", "min 2nd", "maj 2nd", "min 3rd", "maj 3rd", "4th", "tritone", "5th", "min 6th",
+    "maj 6th", "min 7th", "maj 7th",
+];
+
+/// Just ratios (within one octave) that count as a clean hit for each
+/// interval, next to the equal-tempered one.
+const PURE_RATIOS: [&[f32]; 12] = [
+    &[1.0],
+    &[16.0 / 15.0],
+    &[9.0 / 8.0, 10.0 / 9.0],
+    &[6.0 / 5.0],
+    &[5.0 / 4.0],
+    &[4.0 / 3.0],
+    &[],
+    &[3.0 / 2.0],
+    &[8.0 / 5.0],
+    &[5.0 / 3.0],
+    &[7.0 / 4.0, 16.0 / 9.0, 9.0 / 5.0],
+    &[15.0 / 8.0],
+];
+
+/// Further than this from every clean ratio, the interval reads "-ish".
+const PURE_TOLERANCE_CENTS: f32 = 3.0;
+
+/// Interval of `ratio` above the fundamental in musical terms: "+1 octave",
+/// "+1 oct +5th", "tritone-ish" (the suffix marks a ratio between clean
+/// intervals, heard as inharmonic).
+pub fn ratio_interval(ratio: f32) -> String {
+    if !ratio.is_finite() || ratio <= 0.0 {
+        return String::new();
+    }
+    let semitones = (12.0 * ratio.log2()).round() as i32;
+    let octaves = semitones.div_euclid(12);
+    let step = semitones.rem_euclid(12) as usize;
+    let within = ratio / 2.0_f32.powi(octaves);
+    let clean = |target: f32| (1200.0 * (within / target).log2()).abs() <= PURE_TOLERANCE_CENTS;
+    let exact =
+        clean(2.0_f32.powf(step as f32 / 12.0)) || PURE_RATIOS[step].iter().any(|&r| clean(r));
+
+    let mut text = match (octaves, step) {
+        (0, 0) => INTERVAL_NAMES[0].to_string(),
+        (o, 0) => format!("{:+} octave{}", o, if o.abs() == 1 { "" } else { "s" }),
+        (0, s) => INTERVAL_NAMES[s].to_string(),
+        (o, s) => format!("{:+} oct +{}", o, INTERVAL_NAMES[s]),
+    };
+    if !exact {
+        text.push_str("-ish");
+    }
+    text
+}
+
 /// Get the closest DX7 frequency ratio to a given value
 pub fn quantize_frequency_ratio(ratio: f32) -> f32 {
     // Special cases for fixed ratios
@@ -12,6 +160,23 @@ pub fn quantize_frequency_ratio(ratio: f32) -> f32 {
     ratio.round().clamp(1.0, 31.0)
 }
 
+/// How close a free ratio has to come to 0.5 or a whole number for
+/// [`quantize_free_ratio`] to pull it there.
+pub const RATIO_SNAP: f32 = 0.03;
+
+/// The EXT (free ratio) slider's take on [`quantize_frequency_ratio`]:
+/// `ratio` is pulled onto the DX7 ratio it quantizes to when within
+/// [`RATIO_SNAP`] of it, so a dragged free ratio lands on exact
+/// 1.00 / 2.00 / 3.00 (and COARSE / FINE follow); left alone otherwise.
+pub fn quantize_free_ratio(ratio: f32) -> f32 {
+    let harmonic = quantize_frequency_ratio(ratio);
+    if (ratio - harmonic).abs() <= RATIO_SNAP {
+        harmonic
+    } else {
+        ratio
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,6 +209,109 @@ mod tests {
         assert_eq!(quantize_frequency_ratio(1000.0), 31.0);
     }
 
+    #[test]
+    fn free_ratios_snap_only_near_harmonics() {
+        assert_eq!(quantize_free_ratio(1.98), 2.0);
+        assert_eq!(quantize_free_ratio(3.02), 3.0);
+        assert_eq!(quantize_free_ratio(0.52), 0.5);
+        assert_eq!(quantize_free_ratio(1.414), 1.414);
+        assert_eq!(quantize_free_ratio(2.5), 2.5);
+        assert_eq!(quantize_free_ratio(31.9), 31.9);
+        // Every snapped ratio is one COARSE / FINE reach exactly.
+        for ratio in [0.49, 1.02, 6.98, 30.99] {
+            assert!(is_dx7_ratio(quantize_free_ratio(ratio)));
+            assert_eq!(ratio_to_coarse_fine(quantize_free_ratio(ratio)).1, 0);
+        }
+    }
+
+    #[test]
+    fn coarse_fine_ratios_follow_the_dx7() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+        assert_eq!(coarse_fine_to_ratio(0, 0), 0.5);
+        assert!(close(coarse_fine_to_ratio(0, 50), 0.75));
+        assert!(close(coarse_fine_to_ratio(1, 41), 1.41));
+        assert!(close(coarse_fine_to_ratio(31, 99), 61.69));
+        assert_eq!(coarse_fine_to_ratio(40, 200), coarse_fine_to_ratio(31, 99));
+    }
+
+    #[test]
+    fn fixed_frequencies_follow_the_log_scale() {
+        let close = |a: f32, b: f32| (a - b).abs() / b < 1e-4;
+        assert!(close(fixed_coarse_fine_to_hz(0, 0), 1.0));
+        assert!(close(fixed_coarse_fine_to_hz(2, 0), 100.0));
+        assert!(close(fixed_coarse_fine_to_hz(2, 50), 316.23));
+        assert!(close(fixed_coarse_fine_to_hz(3, 99), 9772.4));
+        // Coarse wraps every four steps, as on the hardware.
+        assert_eq!(
+            fixed_coarse_fine_to_hz(5, 10),
+            fixed_coarse_fine_to_hz(1, 10)
+        );
+
+        assert_eq!(hz_to_fixed_coarse_fine(316.0), (2, 50));
+        assert_eq!(hz_to_fixed_coarse_fine(1000.0), (3, 0));
+        assert_eq!(hz_to_fixed_coarse_fine(0.1), (0, 0));
+        assert_eq!(hz_to_fixed_coarse_fine(20_000.0), (3, 99));
+        for coarse in 0..4 {
+            for fine in [0, 1, 50, 99] {
+                let hz = fixed_coarse_fine_to_hz(coarse, fine);
+                assert_eq!(hz_to_fixed_coarse_fine(hz), (coarse, fine));
+            }
+        }
+    }
+
+    #[test]
+    fn ratios_map_back_to_the_nearest_coarse_fine() {
+        assert_eq!(ratio_to_coarse_fine(0.5), (0, 0));
+        assert_eq!(ratio_to_coarse_fine(1.0), (1, 0));
+        assert_eq!(ratio_to_coarse_fine(3.0), (3, 0));
+        assert_eq!(ratio_to_coarse_fine(1.41), (1, 41));
+        assert_eq!(ratio_to_coarse_fine(1.414), (1, 41));
+        assert_eq!(ratio_to_coarse_fine(100.0), (31, 99));
+        for coarse in 0..=MAX_COARSE {
+            for fine in [0, 7, 50, 99] {
+                let ratio = coarse_fine_to_ratio(coarse, fine);
+                assert!(is_dx7_ratio(ratio), "{coarse}/{fine}");
+            }
+        }
+        assert!(!is_dx7_ratio(1.414));
+    }
+
+    #[test]
+    fn detune_steps_are_just_under_a_cent() {
+        assert_eq!(detune_from_stored(0), -7.0);
+        assert_eq!(detune_from_stored(DETUNE_CENTER), 0.0);
+        assert_eq!(detune_from_stored(20), 7.0);
+        assert_eq!(detune_to_stored(-7.0), 0);
+        assert_eq!(detune_to_stored(9.0), MAX_DETUNE);
+        assert_eq!(detune_factor(0.0), 1.0);
+        let cents = 1200.0 * detune_factor(7.0).log2();
+        assert!((cents - 6.74).abs() < 0.01, "{cents}");
+    }
+
+    #[test]
+    fn ratios_read_as_intervals() {
+        assert_eq!(ratio_interval(1.0), "unison");
+        assert_eq!(ratio_interval(2.0), "+1 octave");
+        assert_eq!(ratio_interval(4.0), "+2 octaves");
+        assert_eq!(ratio_interval(0.5), "-1 octave");
+        assert_eq!(ratio_interval(3.0), "+1 oct +5th");
+        assert_eq!(ratio_interval(5.0), "+2 oct +maj 3rd");
+        assert_eq!(ratio_interval(7.0), "+2 oct +min 7th");
+        assert_eq!(ratio_interval(1.5), "5th");
+        assert_eq!(ratio_interval(0.75), "-1 oct +5th");
+        assert_eq!(ratio_interval(1.41), "tritone-ish");
+        assert_eq!(ratio_interval(2.0_f32.sqrt()), "tritone");
+        assert_eq!(ratio_interval(1.02), "unison-ish");
+        assert_eq!(ratio_interval(0.0), "");
+    }
+
+    #[test]
+    fn operator_frequency_applies_ratio_and_detune() {
+        assert_eq!(operator_frequency(261.63, 2.0, 0.0), 523.26);
+        let detuned = operator_frequency(440.0, 1.0, 7.0);
+        assert!((1200.0 * (detuned / 440.0).log2() - 6.74).abs() < 0.01);
+    }
+
     #[test]
     fn integer_round_trips() {
         for r in 2..=31 {
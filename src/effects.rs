@@ -1,6 +1,32 @@
+use crate::optimization::flush_denormal;
+use crate::tempo::{clamp_bpm, NoteDivision, DEFAULT_BPM};
 use std::f32::consts::PI;
 
-const MAX_DELAY_SAMPLES: usize = 88200; // 2 seconds at 44.1kHz
+const MAX_DELAY_SECONDS: f32 = 2.0;
+
+/// Delay feedback filter range. At the ends of the range the filter is
+/// bypassed, so the defaults leave the repeats untouched.
+pub const DELAY_LOW_CUT_MIN_HZ: f32 = 20.0;
+pub const DELAY_HIGH_CUT_MAX_HZ: f32 = 20_000.0;
+const CHORUS_BUFFER_SECONDS: f32 = 0.05;
+/// Chorus modulation depth range, ms.
+pub const CHORUS_MAX_DEPTH_MS: f32 = 10.0;
+/// Delay feedback ceiling; above it the repeats build up instead of dying.
+pub const DELAY_MAX_FEEDBACK: f32 = 0.9;
+
+/// Mod-matrix offsets on effect parameters, set once per control block.
+/// The stored parameters are left alone; each offset is added and the sum
+/// clamped to the parameter's range.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EffectModulation {
+    /// Chorus, delay and reverb wet mix.
+    pub mix: f32,
+    /// Chorus depth, ms.
+    pub chorus_depth_ms: f32,
+    pub delay_feedback: f32,
+    /// Reverb wet mix, on top of `mix`.
+    pub reverb_mix: f32,
+}
 
 // ============================================================================
 // CHORUS EFFECT
@@ -19,11 +45,14 @@ pub struct Chorus {
     pub depth: f32,    // Modulation depth in ms (0.0 - 10.0)
     pub mix: f32,      // Wet/dry mix (0.0 - 1.0)
     pub feedback: f32, // Feedback amount (0.0 - 0.7)
+    mix_mod: f32,      // Mod-matrix offset on `mix`
+    depth_mod: f32,    // Mod-matrix offset on `depth`, ms
+    wet_only: bool,    // Wet-solo audition: dry path muted
 }
 
 impl Chorus {
     pub fn new(sample_rate: f32) -> Self {
-        let buffer_size = (sample_rate * 0.05) as usize; // 50ms buffer
+        let buffer_size = Self::buffer_size_for(sample_rate);
         Self {
             buffer_l: vec![0.0; buffer_size],
             buffer_r: vec![0.0; buffer_size],
@@ -35,9 +64,32 @@ impl Chorus {
             depth: 3.0,
             mix: 0.5,
             feedback: 0.2,
+            mix_mod: 0.0,
+            depth_mod: 0.0,
+            wet_only: false,
         }
     }
 
+    fn buffer_size_for(sample_rate: f32) -> usize {
+        (sample_rate * CHORUS_BUFFER_SECONDS) as usize
+    }
+
+    /// Reallocate the modulation buffers for a new sample rate. The buffer
+    /// content is discarded (a few ms of wet signal) rather than resampled.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        let buffer_size = Self::buffer_size_for(sample_rate);
+        self.buffer_l = vec![0.0; buffer_size];
+        self.buffer_r = vec![0.0; buffer_size];
+        self.write_pos = 0;
+        self.sample_rate = sample_rate;
+    }
+
+    /// Silence the delay lines without reallocating them.
+    pub fn clear(&mut self) {
+        self.buffer_l.fill(0.0);
+        self.buffer_r.fill(0.0);
+    }
+
     pub fn process(&mut self, input: f32) -> (f32, f32) {
         if !self.enabled {
             return (input, input);
@@ -51,8 +103,9 @@ impl Chorus {
 
         // Calculate delay times in samples (keep as float for interpolation)
         let base_delay_ms = 7.0; // Base delay
-        let delay_l_ms = base_delay_ms + self.depth * lfo_l;
-        let delay_r_ms = base_delay_ms + self.depth * lfo_r;
+        let depth = (self.depth + self.depth_mod).clamp(0.0, CHORUS_MAX_DEPTH_MS);
+        let delay_l_ms = base_delay_ms + depth * lfo_l;
+        let delay_r_ms = base_delay_ms + depth * lfo_r;
 
         let delay_l_samples = delay_l_ms * self.sample_rate / 1000.0;
         let delay_r_samples = delay_r_ms * self.sample_rate / 1000.0;
@@ -62,8 +115,8 @@ impl Chorus {
         let delayed_r = self.read_interpolated(&self.buffer_r, delay_r_samples, buffer_size);
 
         // Write to buffers with feedback
-        self.buffer_l[self.write_pos] = input + delayed_l * self.feedback;
-        self.buffer_r[self.write_pos] = input + delayed_r * self.feedback;
+        self.buffer_l[self.write_pos] = flush_denormal(input + delayed_l * self.feedback);
+        self.buffer_r[self.write_pos] = flush_denormal(input + delayed_r * self.feedback);
 
         // Advance write position
         self.write_pos = (self.write_pos + 1) % buffer_size;
@@ -75,12 +128,30 @@ impl Chorus {
         }
 
         // Mix dry and wet
-        let out_l = input * (1.0 - self.mix) + delayed_l * self.mix;
-        let out_r = input * (1.0 - self.mix) + delayed_r * self.mix;
+        let mix = if self.wet_only {
+            1.0
+        } else {
+            (self.mix + self.mix_mod).clamp(0.0, 1.0)
+        };
+        let out_l = input * (1.0 - mix) + delayed_l * mix;
+        let out_r = input * (1.0 - mix) + delayed_r * mix;
 
         (out_l, out_r)
     }
 
+    /// Stereo-in variant for when the chorus is not first in the chain: the
+    /// mid goes through the modulated lines, the side passes dry, so a mono
+    /// input (`l == r`) sounds exactly like `process`.
+    pub fn process_stereo(&mut self, l: f32, r: f32) -> (f32, f32) {
+        if !self.enabled {
+            return (l, r);
+        }
+        let mid = (l + r) * 0.5;
+        let side = if self.wet_only { 0.0 } else { (l - r) * 0.5 };
+        let (out_l, out_r) = self.process(mid);
+        (out_l + side, out_r - side)
+    }
+
     /// Read from delay buffer with linear interpolation for smooth modulation
     fn read_interpolated(&self, buffer: &[f32], delay_samples: f32, buffer_size: usize) -> f32 {
         let delay_clamped = delay_samples.clamp(1.0, (buffer_size - 2) as f32);
@@ -99,6 +170,268 @@ impl Chorus {
     }
 }
 
+// ============================================================================
+// PHASER EFFECT
+// ============================================================================
+
+/// Stage counts the phaser can run.
+pub const PHASER_STAGES: [usize; 2] = [4, 8];
+const PHASER_MAX_STAGES: usize = 8;
+/// Range the all-pass corners sweep over at full depth.
+const PHASER_MIN_HZ: f32 = 200.0;
+const PHASER_MAX_HZ: f32 = 4_000.0;
+pub const PHASER_MAX_FEEDBACK: f32 = 0.9;
+
+/// One set of phaser controls, for the built-in starting points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaserSettings {
+    pub stages: usize,
+    pub rate: f32,
+    pub depth: f32,
+    pub feedback: f32,
+    pub mix: f32,
+}
+
+/// The slow four-stage sweep so many DX7 electric pianos were recorded
+/// through (a Small Stone / Phase 90 style box in front of the amp).
+pub const PHASER_VINTAGE_EP: PhaserSettings = PhaserSettings {
+    stages: 4,
+    rate: 0.4,
+    depth: 0.7,
+    feedback: 0.3,
+    mix: 0.5,
+};
+
+/// Stomp-box phaser: a chain of first-order all-pass stages whose corner
+/// an LFO sweeps, mixed back with the dry signal so the phase shifts turn
+/// into moving notches. The right channel's LFO runs a quarter cycle ahead.
+pub struct Phaser {
+    pub enabled: bool,
+    stages: usize,
+    pub rate: f32,     // LFO rate in Hz (0.05 - 5.0)
+    pub depth: f32,    // Share of the sweep range covered (0.0 - 1.0)
+    pub feedback: f32, // Last stage back into the first (0.0 - PHASER_MAX_FEEDBACK)
+    pub mix: f32,      // Wet/dry mix (0.0 - 1.0); 0.5 gives the deepest notches
+    lfo_phase: f32,
+    sample_rate: f32,
+    state: [[f32; PHASER_MAX_STAGES]; 2], // [channel][stage]
+    last: [f32; 2],
+}
+
+impl Phaser {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            enabled: false,
+            stages: PHASER_VINTAGE_EP.stages,
+            rate: PHASER_VINTAGE_EP.rate,
+            depth: PHASER_VINTAGE_EP.depth,
+            feedback: PHASER_VINTAGE_EP.feedback,
+            mix: PHASER_VINTAGE_EP.mix,
+            lfo_phase: 0.0,
+            sample_rate,
+            state: [[0.0; PHASER_MAX_STAGES]; 2],
+            last: [0.0; 2],
+        }
+    }
+
+    /// Take every control from `settings`; `enabled` is left alone.
+    pub fn apply(&mut self, settings: PhaserSettings) {
+        self.set_stages(settings.stages);
+        self.rate = settings.rate;
+        self.depth = settings.depth;
+        self.feedback = settings.feedback.clamp(0.0, PHASER_MAX_FEEDBACK);
+        self.mix = settings.mix;
+    }
+
+    pub fn stages(&self) -> usize {
+        self.stages
+    }
+
+    /// 4 or 8 all-pass stages; anything above 4 picks 8.
+    pub fn set_stages(&mut self, stages: usize) {
+        self.stages = if stages > PHASER_STAGES[0] {
+            PHASER_STAGES[1]
+        } else {
+            PHASER_STAGES[0]
+        };
+    }
+
+    /// Only the sweep and corner math depend on the rate, so the LFO keeps
+    /// its position.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.clear();
+    }
+
+    /// Forget the all-pass and feedback history.
+    pub fn clear(&mut self) {
+        self.state = [[0.0; PHASER_MAX_STAGES]; 2];
+        self.last = [0.0; 2];
+    }
+
+    /// All-pass coefficient for an LFO value in -1..1.
+    fn coefficient(&self, lfo: f32) -> f32 {
+        let sweep = self.depth.clamp(0.0, 1.0) * (lfo + 1.0) * 0.5;
+        let hz = PHASER_MIN_HZ * (PHASER_MAX_HZ / PHASER_MIN_HZ).powf(sweep);
+        let t = (PI * hz.min(self.sample_rate * 0.45) / self.sample_rate).tan();
+        (t - 1.0) / (t + 1.0)
+    }
+
+    fn process_channel(&mut self, channel: usize, input: f32, a: f32) -> f32 {
+        let mut x = input + self.last[channel] * self.feedback;
+        for s in &mut self.state[channel][..self.stages] {
+            let w = x - a * *s;
+            x = a * w + *s;
+            *s = flush_denormal(w);
+        }
+        self.last[channel] = flush_denormal(x);
+        input * (1.0 - self.mix) + x * self.mix
+    }
+
+    pub fn process(&mut self, l: f32, r: f32) -> (f32, f32) {
+        if !self.enabled {
+            return (l, r);
+        }
+
+        let lfo_l = (self.lfo_phase * 2.0 * PI).sin();
+        let lfo_r = ((self.lfo_phase + 0.25) * 2.0 * PI).sin();
+        let (a_l, a_r) = (self.coefficient(lfo_l), self.coefficient(lfo_r));
+
+        self.lfo_phase += self.rate / self.sample_rate;
+        if self.lfo_phase >= 1.0 {
+            self.lfo_phase -= 1.0;
+        }
+
+        (
+            self.process_channel(0, l, a_l),
+            self.process_channel(1, r, a_r),
+        )
+    }
+}
+
+// ============================================================================
+// RESONANT FILTER (auto-wah; an extension, the DX7 had no filter)
+// ============================================================================
+
+/// Cutoff range of the resonant filter, Hz.
+pub const FILTER_MIN_HZ: f32 = 20.0;
+pub const FILTER_MAX_HZ: f32 = 20_000.0;
+/// Cutoff sweep of a full-scale input at envelope amount ±1, octaves.
+const FILTER_ENV_RANGE_OCTAVES: f32 = 5.0;
+/// Cutoff swing at LFO depth 1, octaves either way.
+const FILTER_LFO_RANGE_OCTAVES: f32 = 3.0;
+/// Envelope follower times: quick enough to catch a pick attack, slow
+/// enough that the sweep reads as a wah rather than distortion.
+const FILTER_FOLLOW_ATTACK_MS: f32 = 5.0;
+const FILTER_FOLLOW_RELEASE_MS: f32 = 150.0;
+
+/// Resonant low-pass on the bus: a state-variable filter whose cutoff the
+/// input level (envelope follower) and its own sine LFO push around, which
+/// makes it an auto-wah when the envelope amount is up. Runs only while
+/// `EffectsChain::extensions` is on.
+pub struct ResonantFilter {
+    pub enabled: bool,
+    pub cutoff_hz: f32,  // Base cutoff (FILTER_MIN_HZ - FILTER_MAX_HZ)
+    pub resonance: f32,  // 0.0 (no peak) - 1.0 (close to self-oscillation)
+    pub env_amount: f32, // Envelope follower sweep (-1.0 - 1.0); negative closes
+    pub lfo_rate: f32,   // LFO rate in Hz (0.05 - 10.0)
+    pub lfo_depth: f32,  // LFO sweep (0.0 - 1.0)
+    pub mix: f32,        // Wet/dry mix (0.0 - 1.0)
+    lfo_phase: f32,
+    sample_rate: f32,
+    follower: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    state: [[f32; 2]; 2], // [channel][integrator]
+}
+
+impl ResonantFilter {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut filter = Self {
+            enabled: false,
+            cutoff_hz: 800.0,
+            resonance: 0.6,
+            env_amount: 0.5,
+            lfo_rate: 0.5,
+            lfo_depth: 0.0,
+            mix: 1.0,
+            lfo_phase: 0.0,
+            sample_rate,
+            follower: 0.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            state: [[0.0; 2]; 2],
+        };
+        filter.set_sample_rate(sample_rate);
+        filter
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        let coeff = |ms: f32| 1.0 - (-1.0 / (ms * 0.001 * sample_rate)).exp();
+        self.attack_coeff = coeff(FILTER_FOLLOW_ATTACK_MS);
+        self.release_coeff = coeff(FILTER_FOLLOW_RELEASE_MS);
+        self.clear();
+    }
+
+    /// Forget the integrator and follower history.
+    pub fn clear(&mut self) {
+        self.state = [[0.0; 2]; 2];
+        self.follower = 0.0;
+    }
+
+    /// Cutoff for the current follower level and an LFO value in -1..1.
+    fn cutoff(&self, lfo: f32) -> f32 {
+        let octaves =
+            self.env_amount.clamp(-1.0, 1.0) * FILTER_ENV_RANGE_OCTAVES * self.follower.min(1.0)
+                + self.lfo_depth.clamp(0.0, 1.0) * FILTER_LFO_RANGE_OCTAVES * lfo;
+        (self.cutoff_hz * 2.0_f32.powf(octaves))
+            .clamp(FILTER_MIN_HZ, FILTER_MAX_HZ.min(self.sample_rate * 0.45))
+    }
+
+    /// One sample of the trapezoidal state-variable filter's low-pass output.
+    fn process_channel(&mut self, channel: usize, input: f32, g: f32, k: f32) -> f32 {
+        let [ic1, ic2] = self.state[channel];
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let v3 = input - ic2;
+        let v1 = a1 * ic1 + g * a1 * v3;
+        let v2 = ic2 + g * v1;
+        self.state[channel] = [
+            flush_denormal(2.0 * v1 - ic1),
+            flush_denormal(2.0 * v2 - ic2),
+        ];
+        input * (1.0 - self.mix) + v2 * self.mix
+    }
+
+    pub fn process(&mut self, l: f32, r: f32) -> (f32, f32) {
+        if !self.enabled {
+            return (l, r);
+        }
+
+        let level = l.abs().max(r.abs());
+        let coeff = if level > self.follower {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.follower = flush_denormal(self.follower + (level - self.follower) * coeff);
+
+        let lfo = (self.lfo_phase * 2.0 * PI).sin();
+        self.lfo_phase += self.lfo_rate / self.sample_rate;
+        if self.lfo_phase >= 1.0 {
+            self.lfo_phase -= 1.0;
+        }
+
+        let g = (PI * self.cutoff(lfo) / self.sample_rate).tan();
+        // Damping 2 is a flat Q of 0.5; near 0 the peak rings.
+        let k = 2.0 - 1.98 * self.resonance.clamp(0.0, 1.0);
+        (
+            self.process_channel(0, l, g, k),
+            self.process_channel(1, r, g, k),
+        )
+    }
+}
+
 // ============================================================================
 // DELAY EFFECT
 // ============================================================================
@@ -111,17 +444,30 @@ pub struct Delay {
 
     // Parameters
     pub enabled: bool,
-    pub time_ms: f32,    // Delay time in ms (0 - 1000)
-    pub feedback: f32,   // Feedback amount (0.0 - 0.9)
-    pub mix: f32,        // Wet/dry mix (0.0 - 1.0)
-    pub ping_pong: bool, // Ping-pong stereo mode
+    pub time_ms: f32,               // Delay time in ms (0 - 1000)
+    pub feedback: f32,              // Feedback amount (0.0 - 0.9)
+    pub mix: f32,                   // Wet/dry mix (0.0 - 1.0)
+    pub ping_pong: bool,            // Ping-pong stereo mode
+    pub sync: Option<NoteDivision>, // Tempo sync: time follows the division instead of `time_ms`
+    mix_mod: f32,                   // Mod-matrix offset on `mix`
+    feedback_mod: f32,              // Mod-matrix offset on `feedback`
+    wet_only: bool,                 // Wet-solo audition: dry path muted
+    bpm: f32,
+    // One-pole filters on the feedback path (analog-style darker, thinner repeats)
+    high_cut_hz: f32,
+    low_cut_hz: f32,
+    high_cut_coeff: f32,
+    low_cut_coeff: f32,
+    lp_state: [f32; 2],
+    hp_state: [f32; 2],
 }
 
 impl Delay {
     pub fn new(sample_rate: f32) -> Self {
+        let buffer_size = Self::buffer_size_for(sample_rate);
         Self {
-            buffer_l: vec![0.0; MAX_DELAY_SAMPLES],
-            buffer_r: vec![0.0; MAX_DELAY_SAMPLES],
+            buffer_l: vec![0.0; buffer_size],
+            buffer_r: vec![0.0; buffer_size],
             write_pos: 0,
             sample_rate,
             enabled: false,
@@ -129,7 +475,107 @@ impl Delay {
             feedback: 0.4,
             mix: 0.3,
             ping_pong: true,
+            sync: None,
+            mix_mod: 0.0,
+            feedback_mod: 0.0,
+            wet_only: false,
+            bpm: DEFAULT_BPM,
+            high_cut_hz: DELAY_HIGH_CUT_MAX_HZ,
+            low_cut_hz: DELAY_LOW_CUT_MIN_HZ,
+            high_cut_coeff: 1.0,
+            low_cut_coeff: 0.0,
+            lp_state: [0.0; 2],
+            hp_state: [0.0; 2],
+        }
+    }
+
+    fn buffer_size_for(sample_rate: f32) -> usize {
+        (sample_rate * MAX_DELAY_SECONDS) as usize
+    }
+
+    pub fn set_tempo(&mut self, bpm: f32) {
+        self.bpm = clamp_bpm(bpm);
+    }
+
+    /// Delay time actually used: the synced division at the current tempo,
+    /// or `time_ms`. Capped by the buffer length.
+    pub fn effective_time_ms(&self) -> f32 {
+        let ms = match self.sync {
+            Some(division) => division.seconds(self.bpm) * 1000.0,
+            None => self.time_ms,
+        };
+        ms.min(MAX_DELAY_SECONDS * 1000.0)
+    }
+
+    /// Low-pass on the repeats; `DELAY_HIGH_CUT_MAX_HZ` turns it off.
+    pub fn set_high_cut(&mut self, hz: f32) {
+        self.high_cut_hz = hz.clamp(DELAY_LOW_CUT_MIN_HZ, DELAY_HIGH_CUT_MAX_HZ);
+        self.update_filter_coeffs();
+    }
+
+    /// High-pass on the repeats; `DELAY_LOW_CUT_MIN_HZ` turns it off.
+    pub fn set_low_cut(&mut self, hz: f32) {
+        self.low_cut_hz = hz.clamp(DELAY_LOW_CUT_MIN_HZ, DELAY_HIGH_CUT_MAX_HZ);
+        self.update_filter_coeffs();
+    }
+
+    pub fn high_cut(&self) -> f32 {
+        self.high_cut_hz
+    }
+
+    pub fn low_cut(&self) -> f32 {
+        self.low_cut_hz
+    }
+
+    fn update_filter_coeffs(&mut self) {
+        let one_pole = |hz: f32| 1.0 - (-2.0 * PI * hz / self.sample_rate).exp();
+        self.high_cut_coeff = if self.high_cut_hz >= DELAY_HIGH_CUT_MAX_HZ {
+            1.0
+        } else {
+            one_pole(self.high_cut_hz)
+        };
+        self.low_cut_coeff = if self.low_cut_hz <= DELAY_LOW_CUT_MIN_HZ {
+            0.0
+        } else {
+            one_pole(self.low_cut_hz)
+        };
+    }
+
+    /// Run one channel's feedback signal through the high-cut then low-cut.
+    fn filter_feedback(&mut self, channel: usize, x: f32) -> f32 {
+        let mut y = x;
+        if self.high_cut_coeff < 1.0 {
+            let lp = &mut self.lp_state[channel];
+            *lp = flush_denormal(*lp + self.high_cut_coeff * (y - *lp));
+            y = *lp;
         }
+        if self.low_cut_coeff > 0.0 {
+            let hp = &mut self.hp_state[channel];
+            *hp = flush_denormal(*hp + self.low_cut_coeff * (y - *hp));
+            y -= *hp;
+        }
+        y
+    }
+
+    /// Reallocate the delay lines so they still hold `MAX_DELAY_SECONDS` at
+    /// the new rate. Pending echoes are dropped.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        let buffer_size = Self::buffer_size_for(sample_rate);
+        self.buffer_l = vec![0.0; buffer_size];
+        self.buffer_r = vec![0.0; buffer_size];
+        self.write_pos = 0;
+        self.sample_rate = sample_rate;
+        self.lp_state = [0.0; 2];
+        self.hp_state = [0.0; 2];
+        self.update_filter_coeffs();
+    }
+
+    /// Drop pending echoes without reallocating the delay lines.
+    pub fn clear(&mut self) {
+        self.buffer_l.fill(0.0);
+        self.buffer_r.fill(0.0);
+        self.lp_state = [0.0; 2];
+        self.hp_state = [0.0; 2];
     }
 
     pub fn process(&mut self, input_l: f32, input_r: f32) -> (f32, f32) {
@@ -137,29 +583,38 @@ impl Delay {
             return (input_l, input_r);
         }
 
+        let buffer_size = self.buffer_l.len();
         let delay_samples =
-            ((self.time_ms * self.sample_rate / 1000.0) as usize).min(MAX_DELAY_SAMPLES - 1);
-        let read_pos = (self.write_pos + MAX_DELAY_SAMPLES - delay_samples) % MAX_DELAY_SAMPLES;
+            ((self.effective_time_ms() * self.sample_rate / 1000.0) as usize).min(buffer_size - 1);
+        let read_pos = (self.write_pos + buffer_size - delay_samples) % buffer_size;
 
         let delayed_l = self.buffer_l[read_pos];
         let delayed_r = self.buffer_r[read_pos];
 
-        // Write to buffers
-        if self.ping_pong {
+        // Feedback, filtered; the dry-path echo (`delayed_*`) keeps its tone
+        // and each further repeat loses a little more.
+        let (fb_l, fb_r) = if self.ping_pong {
             // Ping-pong: left feeds right, right feeds left
-            self.buffer_l[self.write_pos] = input_l + delayed_r * self.feedback;
-            self.buffer_r[self.write_pos] = input_r + delayed_l * self.feedback;
+            (delayed_r, delayed_l)
         } else {
-            // Normal stereo delay
-            self.buffer_l[self.write_pos] = input_l + delayed_l * self.feedback;
-            self.buffer_r[self.write_pos] = input_r + delayed_r * self.feedback;
-        }
+            (delayed_l, delayed_r)
+        };
+        let fb_l = self.filter_feedback(0, fb_l);
+        let fb_r = self.filter_feedback(1, fb_r);
+        let feedback = (self.feedback + self.feedback_mod).clamp(0.0, DELAY_MAX_FEEDBACK);
+        self.buffer_l[self.write_pos] = flush_denormal(input_l + fb_l * feedback);
+        self.buffer_r[self.write_pos] = flush_denormal(input_r + fb_r * feedback);
 
-        self.write_pos = (self.write_pos + 1) % MAX_DELAY_SAMPLES;
+        self.write_pos = (self.write_pos + 1) % buffer_size;
 
         // Mix
-        let out_l = input_l * (1.0 - self.mix) + delayed_l * self.mix;
-        let out_r = input_r * (1.0 - self.mix) + delayed_r * self.mix;
+        let mix = if self.wet_only {
+            1.0
+        } else {
+            (self.mix + self.mix_mod).clamp(0.0, 1.0)
+        };
+        let out_l = input_l * (1.0 - mix) + delayed_l * mix;
+        let out_r = input_r * (1.0 - mix) + delayed_r * mix;
 
         (out_l, out_r)
     }
@@ -169,8 +624,74 @@ impl Delay {
 // REVERB EFFECT (Schroeder-style)
 // ============================================================================
 
+/// Longest pre-delay the reverb allocates for.
+pub const REVERB_MAX_PRE_DELAY_MS: f32 = 200.0;
+/// Extra samples between the left and right comb lengths (at 44.1kHz).
+const REVERB_STEREO_SPREAD: f32 = 23.0;
+/// Modulated all-pass: base length, sweep either side of it (samples at
+/// 44.1kHz) and LFO rate. Half a millisecond of slow wobble is enough to
+/// stop the combs' fixed resonances from ringing without audible chorus.
+const REVERB_MOD_ALLPASS_SIZE: f32 = 347.0;
+const REVERB_MOD_EXCURSION: f32 = 12.0;
+const REVERB_MOD_RATE_HZ: f32 = 0.7;
+
+/// Comb tuning set the reverb runs: how big the space sounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReverbType {
+    /// Short, dense lines: quick build-up, bright and smooth.
+    Plate,
+    /// The classic Freeverb tunings, the longest set.
+    #[default]
+    Hall,
+    /// Small space: short lines, early reflections close together.
+    Room,
+}
+
+impl ReverbType {
+    pub fn all() -> [ReverbType; 3] {
+        [ReverbType::Plate, ReverbType::Hall, ReverbType::Room]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReverbType::Plate => "PLATE",
+            ReverbType::Hall => "HALL",
+            ReverbType::Room => "ROOM",
+        }
+    }
+
+    pub fn to_code(self) -> u8 {
+        match self {
+            ReverbType::Plate => 0,
+            ReverbType::Hall => 1,
+            ReverbType::Room => 2,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            0 => ReverbType::Plate,
+            2 => ReverbType::Room,
+            _ => ReverbType::Hall,
+        }
+    }
+
+    /// Left comb lengths in samples at 44.1kHz. Mutually prime within each
+    /// set so the echoes don't pile up on common multiples.
+    fn comb_tuning(self) -> [f32; 4] {
+        match self {
+            ReverbType::Plate => [743.0, 797.0, 863.0, 929.0],
+            ReverbType::Hall => [1116.0, 1188.0, 1277.0, 1356.0],
+            ReverbType::Room => [557.0, 613.0, 659.0, 701.0],
+        }
+    }
+}
+
 struct CombFilter {
     buffer: Vec<f32>,
+    /// Active loop length; the buffer is sized for the longest tuning so a
+    /// room type switch only moves this.
+    len: usize,
     write_pos: usize,
     feedback: f32,
     damp: f32,
@@ -178,9 +699,10 @@ struct CombFilter {
 }
 
 impl CombFilter {
-    fn new(size: usize, feedback: f32, damp: f32) -> Self {
+    fn new(capacity: usize, len: usize, feedback: f32, damp: f32) -> Self {
         Self {
-            buffer: vec![0.0; size],
+            buffer: vec![0.0; capacity],
+            len: len.clamp(1, capacity),
             write_pos: 0,
             feedback,
             damp,
@@ -188,14 +710,33 @@ impl CombFilter {
         }
     }
 
+    fn resize(&mut self, capacity: usize, len: usize) {
+        self.buffer = vec![0.0; capacity];
+        self.len = len.clamp(1, capacity);
+        self.write_pos = 0;
+        self.damp_state = 0.0;
+    }
+
+    /// Change the loop length without reallocating. The part of the old
+    /// tail that falls inside the new length keeps ringing.
+    fn set_len(&mut self, len: usize) {
+        self.len = len.clamp(1, self.buffer.len());
+        self.write_pos %= self.len;
+    }
+
+    fn clear(&mut self) {
+        self.buffer.fill(0.0);
+        self.damp_state = 0.0;
+    }
+
     fn process(&mut self, input: f32) -> f32 {
         let output = self.buffer[self.write_pos];
 
         // Low-pass filter in feedback loop for natural decay
-        self.damp_state = output * (1.0 - self.damp) + self.damp_state * self.damp;
+        self.damp_state = flush_denormal(output * (1.0 - self.damp) + self.damp_state * self.damp);
 
-        self.buffer[self.write_pos] = input + self.damp_state * self.feedback;
-        self.write_pos = (self.write_pos + 1) % self.buffer.len();
+        self.buffer[self.write_pos] = flush_denormal(input + self.damp_state * self.feedback);
+        self.write_pos = (self.write_pos + 1) % self.len;
 
         output
     }
@@ -216,70 +757,174 @@ impl AllPassFilter {
         }
     }
 
+    fn resize(&mut self, size: usize) {
+        self.buffer = vec![0.0; size];
+        self.write_pos = 0;
+    }
+
+    fn clear(&mut self) {
+        self.buffer.fill(0.0);
+    }
+
     fn process(&mut self, input: f32) -> f32 {
         let delayed = self.buffer[self.write_pos];
         let output = -input + delayed;
 
-        self.buffer[self.write_pos] = input + delayed * self.feedback;
+        self.buffer[self.write_pos] = flush_denormal(input + delayed * self.feedback);
         self.write_pos = (self.write_pos + 1) % self.buffer.len();
 
         output
     }
 }
 
+/// All-pass whose delay an LFO sweeps by a few samples. The moving delay
+/// smears the combs' fixed resonances, which is what takes the metallic
+/// ring out of long sustained tails.
+struct ModulatedAllPass {
+    buffer: Vec<f32>,
+    write_pos: usize,
+    base: f32,      // Centre delay in samples
+    excursion: f32, // Sweep either side of `base`, in samples
+    feedback: f32,
+    lfo_phase: f32, // 0.0 - 1.0
+    lfo_inc: f32,   // Phase step per sample
+}
+
+impl ModulatedAllPass {
+    fn new(sample_rate: f32, base: f32, lfo_phase: f32) -> Self {
+        let mut allpass = Self {
+            buffer: Vec::new(),
+            write_pos: 0,
+            base: 0.0,
+            excursion: 0.0,
+            feedback: 0.5,
+            lfo_phase,
+            lfo_inc: 0.0,
+        };
+        allpass.resize(sample_rate, base);
+        allpass
+    }
+
+    /// Size the line for `sample_rate`; `base` is in samples at 44.1kHz.
+    fn resize(&mut self, sample_rate: f32, base: f32) {
+        let scale = sample_rate / 44100.0;
+        self.base = base * scale;
+        self.excursion = REVERB_MOD_EXCURSION * scale;
+        self.lfo_inc = REVERB_MOD_RATE_HZ / sample_rate;
+        self.buffer = vec![0.0; (self.base + self.excursion) as usize + 3];
+        self.write_pos = 0;
+    }
+
+    fn clear(&mut self) {
+        self.buffer.fill(0.0);
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let size = self.buffer.len();
+        let delay = self.base + self.excursion * (2.0 * PI * self.lfo_phase).sin();
+        self.lfo_phase += self.lfo_inc;
+        if self.lfo_phase >= 1.0 {
+            self.lfo_phase -= 1.0;
+        }
+
+        let delay_int = delay as usize;
+        let frac = delay - delay_int as f32;
+        let s0 = self.buffer[(self.write_pos + size - delay_int) % size];
+        let s1 = self.buffer[(self.write_pos + size - delay_int - 1) % size];
+        let delayed = s0 + frac * (s1 - s0);
+
+        // w[n] = x[n] + g*w[n-D]; y[n] = w[n-D] - g*w[n]
+        let w = flush_denormal(input + delayed * self.feedback);
+        self.buffer[self.write_pos] = w;
+        self.write_pos = (self.write_pos + 1) % size;
+
+        delayed - w * self.feedback
+    }
+}
+
+/// Mono delay line in front of the combs, so the first reflections arrive
+/// a beat after the dry note instead of smearing into its attack.
+struct PreDelay {
+    buffer: Vec<f32>,
+    write_pos: usize,
+}
+
+impl PreDelay {
+    fn new(sample_rate: f32) -> Self {
+        Self {
+            buffer: vec![0.0; Self::capacity(sample_rate)],
+            write_pos: 0,
+        }
+    }
+
+    fn capacity(sample_rate: f32) -> usize {
+        (REVERB_MAX_PRE_DELAY_MS * 0.001 * sample_rate) as usize + 1
+    }
+
+    fn resize(&mut self, sample_rate: f32) {
+        self.buffer = vec![0.0; Self::capacity(sample_rate)];
+        self.write_pos = 0;
+    }
+
+    fn clear(&mut self) {
+        self.buffer.fill(0.0);
+    }
+
+    /// Push `input` and return what went in `delay` samples ago.
+    fn process(&mut self, input: f32, delay: usize) -> f32 {
+        let size = self.buffer.len();
+        self.buffer[self.write_pos] = input;
+        let output = self.buffer[(self.write_pos + size - delay.min(size - 1)) % size];
+        self.write_pos = (self.write_pos + 1) % size;
+        output
+    }
+}
+
 pub struct Reverb {
+    pre_delay: PreDelay,
     // 8 parallel comb filters (4 per channel for stereo)
     combs_l: [CombFilter; 4],
     combs_r: [CombFilter; 4],
-    // 2 series allpass filters per channel
+    // One modulated allpass, then 2 series allpass filters per channel
+    mod_allpass_l: ModulatedAllPass,
+    mod_allpass_r: ModulatedAllPass,
     allpasses_l: [AllPassFilter; 2],
     allpasses_r: [AllPassFilter; 2],
+    reverb_type: ReverbType,
+    sample_rate: f32,
 
     // Parameters
     pub enabled: bool,
-    pub room_size: f32, // 0.0 - 1.0
-    pub damping: f32,   // 0.0 - 1.0
-    pub mix: f32,       // Wet/dry mix (0.0 - 1.0)
-    pub width: f32,     // Stereo width (0.0 - 1.0)
+    pub room_size: f32,    // 0.0 - 1.0
+    pub damping: f32,      // 0.0 - 1.0
+    pub mix: f32,          // Wet/dry mix (0.0 - 1.0)
+    pub width: f32,        // Stereo width (0.0 - 1.0)
+    pub pre_delay_ms: f32, // 0.0 - REVERB_MAX_PRE_DELAY_MS
+    mix_mod: f32,          // Mod-matrix offset on `mix`
+    wet_only: bool,        // Wet-solo audition: dry path muted
 }
 
 impl Reverb {
     pub fn new(sample_rate: f32) -> Self {
-        // Comb filter delay times (in samples at 44.1kHz, scaled for actual sample rate)
-        let scale = sample_rate / 44100.0;
-        let comb_sizes_l: [usize; 4] = [
-            (1116.0 * scale) as usize,
-            (1188.0 * scale) as usize,
-            (1277.0 * scale) as usize,
-            (1356.0 * scale) as usize,
-        ];
-        let comb_sizes_r: [usize; 4] = [
-            (1139.0 * scale) as usize, // Slightly different for stereo
-            (1211.0 * scale) as usize,
-            (1300.0 * scale) as usize,
-            (1379.0 * scale) as usize,
-        ];
-
-        // Allpass filter delay times
-        let allpass_sizes: [usize; 2] = [(556.0 * scale) as usize, (441.0 * scale) as usize];
+        let reverb_type = ReverbType::default();
+        let capacity = Self::comb_capacity(sample_rate);
+        let (comb_sizes_l, comb_sizes_r, allpass_sizes) =
+            Self::filter_sizes(sample_rate, reverb_type);
 
         let feedback = 0.84;
         let damp = 0.2;
         let allpass_feedback = 0.5;
 
         Self {
-            combs_l: [
-                CombFilter::new(comb_sizes_l[0], feedback, damp),
-                CombFilter::new(comb_sizes_l[1], feedback, damp),
-                CombFilter::new(comb_sizes_l[2], feedback, damp),
-                CombFilter::new(comb_sizes_l[3], feedback, damp),
-            ],
-            combs_r: [
-                CombFilter::new(comb_sizes_r[0], feedback, damp),
-                CombFilter::new(comb_sizes_r[1], feedback, damp),
-                CombFilter::new(comb_sizes_r[2], feedback, damp),
-                CombFilter::new(comb_sizes_r[3], feedback, damp),
-            ],
+            pre_delay: PreDelay::new(sample_rate),
+            combs_l: [0, 1, 2, 3]
+                .map(|i| CombFilter::new(capacity[0][i], comb_sizes_l[i], feedback, damp)),
+            combs_r: [0, 1, 2, 3]
+                .map(|i| CombFilter::new(capacity[1][i], comb_sizes_r[i], feedback, damp)),
+            // Right line a little longer and a quarter cycle ahead, so the
+            // two sides never wobble in step.
+            mod_allpass_l: ModulatedAllPass::new(sample_rate, REVERB_MOD_ALLPASS_SIZE, 0.0),
+            mod_allpass_r: ModulatedAllPass::new(sample_rate, REVERB_MOD_ALLPASS_SIZE + 19.0, 0.25),
             allpasses_l: [
                 AllPassFilter::new(allpass_sizes[0], allpass_feedback),
                 AllPassFilter::new(allpass_sizes[1], allpass_feedback),
@@ -288,11 +933,112 @@ impl Reverb {
                 AllPassFilter::new(allpass_sizes[0] + 23, allpass_feedback),
                 AllPassFilter::new(allpass_sizes[1] + 17, allpass_feedback),
             ],
+            reverb_type,
+            sample_rate,
             enabled: false,
             room_size: 0.7,
             damping: 0.5,
             mix: 0.25,
             width: 1.0,
+            pre_delay_ms: 0.0,
+            mix_mod: 0.0,
+            wet_only: false,
+        }
+    }
+
+    /// Comb and allpass lengths for `sample_rate` and `reverb_type`. The
+    /// tunings are sample counts at 44.1kHz, scaled so the room keeps the
+    /// same size in milliseconds at any rate.
+    fn filter_sizes(
+        sample_rate: f32,
+        reverb_type: ReverbType,
+    ) -> ([usize; 4], [usize; 4], [usize; 2]) {
+        let scale = sample_rate / 44100.0;
+        let tuning = reverb_type.comb_tuning();
+        let comb_sizes_l = tuning.map(|t| (t * scale) as usize);
+        // Slightly different for stereo
+        let comb_sizes_r = tuning.map(|t| ((t + REVERB_STEREO_SPREAD) * scale) as usize);
+
+        // Allpass filter delay times
+        let allpass_sizes: [usize; 2] = [(556.0 * scale) as usize, (441.0 * scale) as usize];
+
+        (comb_sizes_l, comb_sizes_r, allpass_sizes)
+    }
+
+    /// Per-comb buffer sizes ([left, right]) that fit every room type.
+    fn comb_capacity(sample_rate: f32) -> [[usize; 4]; 2] {
+        let mut capacity = [[0; 4]; 2];
+        for reverb_type in ReverbType::all() {
+            let (l, r, _) = Self::filter_sizes(sample_rate, reverb_type);
+            for i in 0..4 {
+                capacity[0][i] = capacity[0][i].max(l[i]);
+                capacity[1][i] = capacity[1][i].max(r[i]);
+            }
+        }
+        capacity
+    }
+
+    pub fn reverb_type(&self) -> ReverbType {
+        self.reverb_type
+    }
+
+    /// Switch comb tuning set. The lines are preallocated for the longest
+    /// set, so this is safe on the audio thread.
+    pub fn set_reverb_type(&mut self, reverb_type: ReverbType) {
+        if reverb_type == self.reverb_type {
+            return;
+        }
+        self.reverb_type = reverb_type;
+        let (comb_sizes_l, comb_sizes_r, _) = Self::filter_sizes(self.sample_rate, reverb_type);
+        for (comb, len) in self.combs_l.iter_mut().zip(comb_sizes_l) {
+            comb.set_len(len);
+        }
+        for (comb, len) in self.combs_r.iter_mut().zip(comb_sizes_r) {
+            comb.set_len(len);
+        }
+    }
+
+    /// Resize every delay line for a new sample rate. The tail is cleared;
+    /// room type, size, damping, mix, width and pre-delay are kept.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        let capacity = Self::comb_capacity(sample_rate);
+        let (comb_sizes_l, comb_sizes_r, allpass_sizes) =
+            Self::filter_sizes(sample_rate, self.reverb_type);
+        self.pre_delay.resize(sample_rate);
+        for (i, comb) in self.combs_l.iter_mut().enumerate() {
+            comb.resize(capacity[0][i], comb_sizes_l[i]);
+        }
+        for (i, comb) in self.combs_r.iter_mut().enumerate() {
+            comb.resize(capacity[1][i], comb_sizes_r[i]);
+        }
+        self.mod_allpass_l
+            .resize(sample_rate, REVERB_MOD_ALLPASS_SIZE);
+        self.mod_allpass_r
+            .resize(sample_rate, REVERB_MOD_ALLPASS_SIZE + 19.0);
+        for (allpass, size) in self.allpasses_l.iter_mut().zip(allpass_sizes) {
+            allpass.resize(size);
+        }
+        // Same stereo offsets as in `new`
+        for (allpass, size) in self
+            .allpasses_r
+            .iter_mut()
+            .zip([allpass_sizes[0] + 23, allpass_sizes[1] + 17])
+        {
+            allpass.resize(size);
+        }
+    }
+
+    /// Cut the tail without reallocating any delay line.
+    pub fn clear(&mut self) {
+        self.pre_delay.clear();
+        for comb in self.combs_l.iter_mut().chain(&mut self.combs_r) {
+            comb.clear();
+        }
+        self.mod_allpass_l.clear();
+        self.mod_allpass_r.clear();
+        for allpass in self.allpasses_l.iter_mut().chain(&mut self.allpasses_r) {
+            allpass.clear();
         }
     }
 
@@ -305,8 +1051,11 @@ impl Reverb {
         let feedback = 0.7 + self.room_size * 0.28; // 0.7 to 0.98
         let damp = self.damping * 0.4; // 0 to 0.4
 
-        // Process through parallel comb filters
-        let input_mono = (input_l + input_r) * 0.5;
+        // Pre-delay, then the parallel comb filters
+        let pre_delay = (self.pre_delay_ms.clamp(0.0, REVERB_MAX_PRE_DELAY_MS)
+            * 0.001
+            * self.sample_rate) as usize;
+        let input_mono = self.pre_delay.process((input_l + input_r) * 0.5, pre_delay);
         let mut wet_l = 0.0;
         let mut wet_r = 0.0;
 
@@ -326,7 +1075,9 @@ impl Reverb {
         wet_l *= 0.25;
         wet_r *= 0.25;
 
-        // Process through series allpass filters
+        // Modulated allpass, then the fixed series allpass filters
+        wet_l = self.mod_allpass_l.process(wet_l);
+        wet_r = self.mod_allpass_r.process(wet_r);
         for allpass in &mut self.allpasses_l {
             wet_l = allpass.process(wet_l);
         }
@@ -340,8 +1091,13 @@ impl Reverb {
         wet_r = wet_mono + (wet_r - wet_mono) * self.width;
 
         // Mix dry and wet
-        let out_l = input_l * (1.0 - self.mix) + wet_l * self.mix;
-        let out_r = input_r * (1.0 - self.mix) + wet_r * self.mix;
+        let mix = if self.wet_only {
+            1.0
+        } else {
+            (self.mix + self.mix_mod).clamp(0.0, 1.0)
+        };
+        let out_l = input_l * (1.0 - mix) + wet_l * mix;
+        let out_r = input_r * (1.0 - mix) + wet_r * mix;
 
         (out_l, out_r)
     }
@@ -376,6 +1132,12 @@ impl AutoPan {
         }
     }
 
+    /// Only the phase increment depends on the rate, so the sweep position
+    /// is kept across the switch.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
     pub fn process(&mut self, l: f32, r: f32) -> (f32, f32) {
         if !self.enabled || self.depth <= 0.0 {
             return (l, r);
@@ -396,7 +1158,363 @@ impl AutoPan {
             self.phase -= 1.0;
         }
 
-        (l * l_gain, r * r_gain)
+        (l * l_gain, r * r_gain)
+    }
+}
+
+/// Where each new note sits in the stereo field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VoiceSpread {
+    /// Every voice in the centre.
+    #[default]
+    Center,
+    /// Successive notes alternate left / right, outer positions first.
+    RoundRobin,
+    /// Low keys to the left, high keys to the right, like sitting at a piano.
+    KeyFollow,
+}
+
+impl VoiceSpread {
+    const ROUND_ROBIN_POSITIONS: [f32; 4] = [-1.0, 1.0, -0.5, 0.5];
+
+    pub fn all() -> [VoiceSpread; 3] {
+        [
+            VoiceSpread::Center,
+            VoiceSpread::RoundRobin,
+            VoiceSpread::KeyFollow,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VoiceSpread::Center => "CENTER",
+            VoiceSpread::RoundRobin => "ROUND ROBIN",
+            VoiceSpread::KeyFollow => "KEY FOLLOW",
+        }
+    }
+
+    pub fn to_code(self) -> u8 {
+        match self {
+            VoiceSpread::Center => 0,
+            VoiceSpread::RoundRobin => 1,
+            VoiceSpread::KeyFollow => 2,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            1 => VoiceSpread::RoundRobin,
+            2 => VoiceSpread::KeyFollow,
+            _ => VoiceSpread::Center,
+        }
+    }
+
+    /// Position (-1 = left, +1 = right) for the `count`-th note, `note`.
+    /// Key follow spans three octaves either side of middle C.
+    pub fn position(self, note: u8, count: u64) -> f32 {
+        match self {
+            VoiceSpread::Center => 0.0,
+            VoiceSpread::RoundRobin => {
+                Self::ROUND_ROBIN_POSITIONS
+                    [(count % Self::ROUND_ROBIN_POSITIONS.len() as u64) as usize]
+            }
+            VoiceSpread::KeyFollow => ((note as f32 - 60.0) / 36.0).clamp(-1.0, 1.0),
+        }
+    }
+}
+
+/// Per-voice tremolo / auto-pan, run on each voice before the voices are
+/// summed into the effects chain so every note moves on its own clock.
+/// Left and right gains follow one LFO, offset by `stereo_phase`: 0 moves
+/// both sides together (tremolo), 1 puts them half a cycle apart (auto-pan),
+/// values in between blend the two. Gains dip from 1.0 to `1 - depth`.
+///
+/// On top of that the voice has a fixed stereo position (`set_position`),
+/// using the same unity-at-centre equal-power law as [`AutoPan`].
+#[derive(Clone)]
+pub struct VoicePan {
+    pub rate_hz: f32,      // LFO rate (0.1 .. 10.0 Hz)
+    pub depth: f32,        // 0.0 = bypass, 1.0 = gain reaches silence at the trough
+    pub stereo_phase: f32, // 0.0 = tremolo, 1.0 = L/R 180° apart
+    phase: f32,
+    sample_rate: f32,
+    position: f32,
+    place: (f32, f32), // (left, right) gains of `position`
+}
+
+impl VoicePan {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            rate_hz: 4.0,
+            depth: 0.0,
+            stereo_phase: 1.0,
+            phase: 0.0,
+            sample_rate,
+            position: 0.0,
+            place: (1.0, 1.0),
+        }
+    }
+
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// Fixed stereo position, -1 (left) .. +1 (right). The centre is an
+    /// exact pass-through.
+    pub fn set_position(&mut self, position: f32) {
+        self.position = position.clamp(-1.0, 1.0);
+        self.place = if self.position == 0.0 {
+            (1.0, 1.0)
+        } else {
+            let theta = (self.position + 1.0) * std::f32::consts::FRAC_PI_4;
+            (
+                theta.cos() * std::f32::consts::SQRT_2,
+                theta.sin() * std::f32::consts::SQRT_2,
+            )
+        };
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Restart the LFO at the top of its cycle (called on note-on).
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+
+    /// (left, right) gains for the next sample.
+    pub fn next_gains(&mut self) -> (f32, f32) {
+        if self.depth <= 0.0 {
+            return self.place;
+        }
+
+        let depth = self.depth.min(1.0);
+        // Start at the crest (phase 0 → cos = 1) so note-on is never ducked.
+        let left = (self.phase * 2.0 * PI).cos();
+        let right = ((self.phase + self.stereo_phase.clamp(0.0, 1.0) * 0.5) * 2.0 * PI).cos();
+        let gain = |m: f32| 1.0 - depth * 0.5 * (1.0 - m);
+
+        self.phase += self.rate_hz / self.sample_rate;
+        if self.phase >= 1.0 {
+            self.phase -= self.phase.floor();
+        }
+
+        (gain(left) * self.place.0, gain(right) * self.place.1)
+    }
+}
+
+// ============================================================================
+// EQUALIZER (3-band tone shaping)
+// ============================================================================
+
+/// Boost/cut range of every EQ band, in dB either way.
+pub const EQ_MAX_GAIN_DB: f32 = 15.0;
+
+const EQ_LOW: usize = 0;
+const EQ_MID: usize = 1;
+const EQ_HIGH: usize = 2;
+
+/// One EQ band. `q` only shapes the mid (peaking) band; the shelves use a
+/// fixed maximally-flat slope.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqBand {
+    pub freq_hz: f32,
+    pub gain_db: f32,
+    pub q: f32,
+}
+
+/// Biquad coefficients, normalised so a0 = 1 (RBJ audio-EQ cookbook).
+#[derive(Debug, Clone, Copy, Default)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl Biquad {
+    fn new(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+
+    /// Transposed direct form II; `z` is the channel's two-sample state.
+    fn process(&self, x: f32, z: &mut [f32; 2]) -> f32 {
+        let y = self.b0 * x + z[0];
+        z[0] = flush_denormal(self.b1 * x - self.a1 * y + z[1]);
+        z[1] = flush_denormal(self.b2 * x - self.a2 * y);
+        y
+    }
+}
+
+/// Three-band EQ: low shelf, parametric mid, high shelf. Mostly for taming
+/// the harsh upper partials of bright FM patches. A band at 0 dB is skipped
+/// outright, so a flat EQ is an exact pass-through.
+pub struct Equalizer {
+    pub enabled: bool,
+    bands: [EqBand; 3],
+    filters: [Biquad; 3],
+    state: [[[f32; 2]; 2]; 3], // [band][channel]
+    sample_rate: f32,
+}
+
+impl Equalizer {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut eq = Self {
+            enabled: false,
+            bands: [
+                EqBand {
+                    freq_hz: 100.0,
+                    gain_db: 0.0,
+                    q: 0.7,
+                },
+                EqBand {
+                    freq_hz: 1_000.0,
+                    gain_db: 0.0,
+                    q: 0.7,
+                },
+                EqBand {
+                    freq_hz: 8_000.0,
+                    gain_db: 0.0,
+                    q: 0.7,
+                },
+            ],
+            filters: [Biquad::default(); 3],
+            state: [[[0.0; 2]; 2]; 3],
+            sample_rate,
+        };
+        eq.update_filters();
+        eq
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.state = [[[0.0; 2]; 2]; 3];
+        self.update_filters();
+    }
+
+    /// Forget the filter history.
+    pub fn clear(&mut self) {
+        self.state = [[[0.0; 2]; 2]; 3];
+    }
+
+    pub fn low(&self) -> EqBand {
+        self.bands[EQ_LOW]
+    }
+
+    pub fn mid(&self) -> EqBand {
+        self.bands[EQ_MID]
+    }
+
+    pub fn high(&self) -> EqBand {
+        self.bands[EQ_HIGH]
+    }
+
+    /// Low shelf corner, 20 Hz – 1 kHz.
+    pub fn set_low_freq(&mut self, hz: f32) {
+        self.bands[EQ_LOW].freq_hz = hz.clamp(20.0, 1_000.0);
+        self.update_filters();
+    }
+
+    pub fn set_low_gain(&mut self, db: f32) {
+        self.bands[EQ_LOW].gain_db = db.clamp(-EQ_MAX_GAIN_DB, EQ_MAX_GAIN_DB);
+        self.update_filters();
+    }
+
+    /// Mid band centre, 100 Hz – 10 kHz.
+    pub fn set_mid_freq(&mut self, hz: f32) {
+        self.bands[EQ_MID].freq_hz = hz.clamp(100.0, 10_000.0);
+        self.update_filters();
+    }
+
+    pub fn set_mid_gain(&mut self, db: f32) {
+        self.bands[EQ_MID].gain_db = db.clamp(-EQ_MAX_GAIN_DB, EQ_MAX_GAIN_DB);
+        self.update_filters();
+    }
+
+    /// Mid band width, 0.3 (wide) – 10 (narrow).
+    pub fn set_mid_q(&mut self, q: f32) {
+        self.bands[EQ_MID].q = q.clamp(0.3, 10.0);
+        self.update_filters();
+    }
+
+    /// High shelf corner, 1 kHz – 20 kHz.
+    pub fn set_high_freq(&mut self, hz: f32) {
+        self.bands[EQ_HIGH].freq_hz = hz.clamp(1_000.0, 20_000.0);
+        self.update_filters();
+    }
+
+    pub fn set_high_gain(&mut self, db: f32) {
+        self.bands[EQ_HIGH].gain_db = db.clamp(-EQ_MAX_GAIN_DB, EQ_MAX_GAIN_DB);
+        self.update_filters();
+    }
+
+    fn update_filters(&mut self) {
+        for (i, band) in self.bands.iter().enumerate() {
+            let a = 10.0_f32.powf(band.gain_db / 40.0);
+            // Keep the corner below Nyquist at low sample rates.
+            let w0 = 2.0 * PI * band.freq_hz.min(self.sample_rate * 0.45) / self.sample_rate;
+            let (sin, cos) = w0.sin_cos();
+            self.filters[i] = if i == EQ_MID {
+                let alpha = sin / (2.0 * band.q);
+                Biquad::new(
+                    1.0 + alpha * a,
+                    -2.0 * cos,
+                    1.0 - alpha * a,
+                    1.0 + alpha / a,
+                    -2.0 * cos,
+                    1.0 - alpha / a,
+                )
+            } else {
+                // Shelf slope S = 1.
+                let k = 2.0 * a.sqrt() * sin / std::f32::consts::SQRT_2;
+                let (ap, am) = (a + 1.0, a - 1.0);
+                if i == EQ_LOW {
+                    Biquad::new(
+                        a * (ap - am * cos + k),
+                        2.0 * a * (am - ap * cos),
+                        a * (ap - am * cos - k),
+                        ap + am * cos + k,
+                        -2.0 * (am + ap * cos),
+                        ap + am * cos - k,
+                    )
+                } else {
+                    Biquad::new(
+                        a * (ap + am * cos + k),
+                        -2.0 * a * (am + ap * cos),
+                        a * (ap + am * cos - k),
+                        ap - am * cos + k,
+                        2.0 * (am - ap * cos),
+                        ap - am * cos - k,
+                    )
+                }
+            };
+        }
+    }
+
+    pub fn process(&mut self, l: f32, r: f32) -> (f32, f32) {
+        if !self.enabled {
+            return (l, r);
+        }
+
+        let mut out = [l, r];
+        for band in 0..3 {
+            if self.bands[band].gain_db == 0.0 {
+                continue;
+            }
+            let filter = self.filters[band];
+            for (ch, sample) in out.iter_mut().enumerate() {
+                *sample = filter.process(*sample, &mut self.state[band][ch]);
+            }
+        }
+        (out[0], out[1])
     }
 }
 
@@ -404,39 +1522,349 @@ impl AutoPan {
 // EFFECTS CHAIN
 // ============================================================================
 
+/// One position in the effects chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectSlot {
+    Eq,
+    Filter,
+    Phaser,
+    Chorus,
+    AutoPan,
+    Delay,
+    Reverb,
+}
+
+/// Number of effect slots in the chain.
+pub const EFFECT_SLOTS: usize = 7;
+/// Per-effect output trim range, either way.
+pub const EFFECT_MAX_GAIN_DB: f32 = 12.0;
+
+impl EffectSlot {
+    /// Factory order: EQ → Filter → Phaser → Chorus → AutoPan → Delay →
+    /// Reverb. The EQ goes first so it shapes the dry tone rather than the
+    /// echoes; the filter and phaser sit where the pedals did, between the
+    /// keyboard and the amp.
+    pub const DEFAULT_ORDER: [EffectSlot; EFFECT_SLOTS] = [
+        EffectSlot::Eq,
+        EffectSlot::Filter,
+        EffectSlot::Phaser,
+        EffectSlot::Chorus,
+        EffectSlot::AutoPan,
+        EffectSlot::Delay,
+        EffectSlot::Reverb,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EffectSlot::Eq => "EQ",
+            EffectSlot::Filter => "FILTER",
+            EffectSlot::Phaser => "PHASER",
+            EffectSlot::Chorus => "CHORUS",
+            EffectSlot::AutoPan => "AUTOPAN",
+            EffectSlot::Delay => "DELAY",
+            EffectSlot::Reverb => "REVERB",
+        }
+    }
+
+    /// Position in `DEFAULT_ORDER`, for per-slot tables.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Whether the slot has a wet signal worth soloing. The phaser's wet
+    /// path alone is a flat all-pass (the notches only appear against the
+    /// dry), and the EQ and autopan have no wet/dry split at all.
+    pub fn has_wet_solo(self) -> bool {
+        matches!(
+            self,
+            EffectSlot::Chorus | EffectSlot::Delay | EffectSlot::Reverb
+        )
+    }
+}
+
+/// `order` with the slot at `from` moved to `to`, the others shifting to
+/// make room (drag-to-reorder). Out-of-range indices return it unchanged.
+pub fn move_effect_slot(
+    mut order: [EffectSlot; EFFECT_SLOTS],
+    from: usize,
+    to: usize,
+) -> [EffectSlot; EFFECT_SLOTS] {
+    if from < EFFECT_SLOTS && to < EFFECT_SLOTS {
+        if from < to {
+            order[from..=to].rotate_left(1);
+        } else {
+            order[to..=from].rotate_right(1);
+        }
+    }
+    order
+}
+
 pub struct EffectsChain {
+    pub eq: Equalizer,
+    pub filter: ResonantFilter,
+    pub phaser: Phaser,
     pub chorus: Chorus,
     pub auto_pan: AutoPan,
     pub delay: Delay,
     pub reverb: Reverb,
+    /// Processing order; always a permutation of `EffectSlot::DEFAULT_ORDER`.
+    order: [EffectSlot; EFFECT_SLOTS],
+    /// Skip every effect; the sub bus is still mixed in dry.
+    pub bypass: bool,
+    /// Allow the processing the DX7 never had (the resonant filter). Off
+    /// keeps the authentic signal path whatever those effects are set to.
+    pub extensions: bool,
+    /// Output trim per slot (by `EffectSlot::index`), in dB and as a gain.
+    /// Applied only while the effect is on.
+    gains_db: [f32; EFFECT_SLOTS],
+    gains: [f32; EFFECT_SLOTS],
+    /// Slot heard on its own, fully wet; the slots after it and the dry sub
+    /// bus are skipped.
+    wet_solo: Option<EffectSlot>,
 }
 
 impl EffectsChain {
     pub fn new(sample_rate: f32) -> Self {
         Self {
+            eq: Equalizer::new(sample_rate),
+            filter: ResonantFilter::new(sample_rate),
+            phaser: Phaser::new(sample_rate),
             chorus: Chorus::new(sample_rate),
             auto_pan: AutoPan::new(sample_rate),
             delay: Delay::new(sample_rate),
             reverb: Reverb::new(sample_rate),
+            order: EffectSlot::DEFAULT_ORDER,
+            bypass: false,
+            extensions: false,
+            gains_db: [0.0; EFFECT_SLOTS],
+            gains: [1.0; EFFECT_SLOTS],
+            wet_solo: None,
+        }
+    }
+
+    pub fn order(&self) -> [EffectSlot; EFFECT_SLOTS] {
+        self.order
+    }
+
+    /// Reorder the chain. Ignored unless every slot appears exactly once.
+    pub fn set_order(&mut self, order: [EffectSlot; EFFECT_SLOTS]) {
+        if EffectSlot::DEFAULT_ORDER
+            .iter()
+            .all(|slot| order.iter().filter(|&s| s == slot).count() == 1)
+        {
+            self.order = order;
+        }
+    }
+
+    pub fn output_gain_db(&self, slot: EffectSlot) -> f32 {
+        self.gains_db[slot.index()]
+    }
+
+    /// Trim one effect's output, within ±`EFFECT_MAX_GAIN_DB`.
+    pub fn set_output_gain_db(&mut self, slot: EffectSlot, db: f32) {
+        let db = db.clamp(-EFFECT_MAX_GAIN_DB, EFFECT_MAX_GAIN_DB);
+        self.gains_db[slot.index()] = db;
+        self.gains[slot.index()] = 10.0_f32.powf(db / 20.0);
+    }
+
+    pub fn wet_solo(&self) -> Option<EffectSlot> {
+        self.wet_solo
+    }
+
+    /// Audition one effect's wet signal alone, or `None` for the normal
+    /// mix. Slots without a wet path (`has_wet_solo`) are ignored.
+    pub fn set_wet_solo(&mut self, slot: Option<EffectSlot>) {
+        if slot.is_some_and(|slot| !slot.has_wet_solo()) {
+            return;
+        }
+        self.wet_solo = slot;
+        self.chorus.wet_only = slot == Some(EffectSlot::Chorus);
+        self.delay.wet_only = slot == Some(EffectSlot::Delay);
+        self.reverb.wet_only = slot == Some(EffectSlot::Reverb);
+    }
+
+    fn is_enabled(&self, slot: EffectSlot) -> bool {
+        match slot {
+            EffectSlot::Eq => self.eq.enabled,
+            EffectSlot::Filter => self.filter.enabled && self.extensions,
+            EffectSlot::Phaser => self.phaser.enabled,
+            EffectSlot::Chorus => self.chorus.enabled,
+            EffectSlot::AutoPan => self.auto_pan.enabled,
+            EffectSlot::Delay => self.delay.enabled,
+            EffectSlot::Reverb => self.reverb.enabled,
+        }
+    }
+
+    /// Apply the mod matrix's effect destinations until the next call.
+    pub fn set_modulation(&mut self, modulation: EffectModulation) {
+        self.chorus.mix_mod = modulation.mix;
+        self.chorus.depth_mod = modulation.chorus_depth_ms;
+        self.delay.mix_mod = modulation.mix;
+        self.delay.feedback_mod = modulation.delay_feedback;
+        self.reverb.mix_mod = modulation.mix + modulation.reverb_mix;
+    }
+
+    /// Retune every effect for a new output sample rate in place. Parameters
+    /// survive; delay lines are reallocated and start out silent. This
+    /// allocates, so call it from the control side, not mid-callback.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.eq.set_sample_rate(sample_rate);
+        self.filter.set_sample_rate(sample_rate);
+        self.phaser.set_sample_rate(sample_rate);
+        self.chorus.set_sample_rate(sample_rate);
+        self.auto_pan.set_sample_rate(sample_rate);
+        self.delay.set_sample_rate(sample_rate);
+        self.reverb.set_sample_rate(sample_rate);
+    }
+
+    /// Every control back to the value `new` gives it, and the lines
+    /// silenced. Nothing is allocated, so INIT VOICE can run it on the
+    /// audio thread.
+    pub fn reset_settings(&mut self) {
+        self.eq.enabled = false;
+        for (band, freq_hz) in self.eq.bands.iter_mut().zip([100.0, 1_000.0, 8_000.0]) {
+            *band = EqBand {
+                freq_hz,
+                gain_db: 0.0,
+                q: 0.7,
+            };
         }
+        self.eq.update_filters();
+
+        let filter = &mut self.filter;
+        filter.enabled = false;
+        filter.cutoff_hz = 800.0;
+        filter.resonance = 0.6;
+        filter.env_amount = 0.5;
+        filter.lfo_rate = 0.5;
+        filter.lfo_depth = 0.0;
+        filter.mix = 1.0;
+        filter.lfo_phase = 0.0;
+
+        self.phaser.enabled = false;
+        self.phaser.apply(PHASER_VINTAGE_EP);
+
+        let chorus = &mut self.chorus;
+        chorus.enabled = false;
+        chorus.rate = 1.5;
+        chorus.depth = 3.0;
+        chorus.mix = 0.5;
+        chorus.feedback = 0.2;
+
+        self.auto_pan.enabled = false;
+        self.auto_pan.rate_hz = 5.0;
+        self.auto_pan.depth = 0.5;
+
+        let delay = &mut self.delay;
+        delay.enabled = false;
+        delay.time_ms = 300.0;
+        delay.feedback = 0.4;
+        delay.mix = 0.3;
+        delay.ping_pong = true;
+        delay.sync = None;
+        delay.high_cut_hz = DELAY_HIGH_CUT_MAX_HZ;
+        delay.low_cut_hz = DELAY_LOW_CUT_MIN_HZ;
+        delay.update_filter_coeffs();
+
+        let reverb = &mut self.reverb;
+        reverb.enabled = false;
+        reverb.set_reverb_type(ReverbType::default());
+        reverb.room_size = 0.7;
+        reverb.damping = 0.5;
+        reverb.mix = 0.25;
+        reverb.width = 1.0;
+        reverb.pre_delay_ms = 0.0;
+
+        self.order = EffectSlot::DEFAULT_ORDER;
+        self.bypass = false;
+        for slot in EffectSlot::DEFAULT_ORDER {
+            self.set_output_gain_db(slot, 0.0);
+        }
+        self.set_wet_solo(None);
+        self.set_modulation(EffectModulation::default());
+        // `extensions` is a preference, not part of the sound: INIT VOICE
+        // leaves it where the player set it.
+        self.clear();
+    }
+
+    /// Silence every delay line and filter state in place, e.g. after a
+    /// NaN got into a feedback loop, where it would otherwise circulate for
+    /// good. Parameters are kept and nothing is allocated.
+    pub fn clear(&mut self) {
+        self.eq.clear();
+        self.filter.clear();
+        self.phaser.clear();
+        self.chorus.clear();
+        self.delay.clear();
+        self.reverb.clear();
     }
 
     pub fn process(&mut self, input: f32) -> (f32, f32) {
-        // Chorus first (mono to stereo)
-        let (l, r) = self.chorus.process(input);
+        self.process_with_sends(input, 0.0, 0.0, 0.0)
+    }
+
+    /// Run `input` through the chain while mixing in a secondary bus. The
+    /// sub signal skips chorus/autopan and is heard dry at the output; the
+    /// sends add extra copies of it into the delay and reverb inputs.
+    pub fn process_with_sends(
+        &mut self,
+        input: f32,
+        sub: f32,
+        delay_send: f32,
+        reverb_send: f32,
+    ) -> (f32, f32) {
+        self.process_mid_side_with_sends(input, 0.0, sub, delay_send, reverb_send)
+    }
 
-        // AutoPan after chorus: the Suitcase tremolo sits in the amp stage,
-        // *after* the pickup-side modulation. Putting it here lets the
-        // chorus widen the image first, then the autopan sways the whole
-        // stereo field — exactly what you hear on a real Rhodes through a
-        // Suitcase amp.
-        let (l, r) = self.auto_pan.process(l, r);
+    /// [`Self::process_with_sends`] for a stereo input given as mid/side.
+    /// The side carries the voices' own panning and skips the chorus (which
+    /// only modulates the mid), so `side == 0` is exactly the mono path.
+    ///
+    /// The effects run in `order`. The default (EQ → Filter → Phaser →
+    /// Chorus → AutoPan → Delay → Reverb) puts the Suitcase-style autopan in the amp stage, *after*
+    /// the pickup-side chorus has widened the image — what you hear on a
+    /// real Rhodes through a Suitcase amp. The sends always feed the delay
+    /// and reverb inputs, wherever those sit.
+    pub fn process_mid_side_with_sends(
+        &mut self,
+        mid: f32,
+        side: f32,
+        sub: f32,
+        delay_send: f32,
+        reverb_send: f32,
+    ) -> (f32, f32) {
+        let (mut l, mut r) = (mid + side, mid - side);
+        if self.bypass {
+            return (l + sub, r + sub);
+        }
 
-        // Then delay (stereo)
-        let (l, r) = self.delay.process(l, r);
+        for slot in self.order {
+            (l, r) = match slot {
+                EffectSlot::Eq => self.eq.process(l, r),
+                EffectSlot::Filter if self.extensions => self.filter.process(l, r),
+                EffectSlot::Filter => (l, r),
+                EffectSlot::Phaser => self.phaser.process(l, r),
+                EffectSlot::Chorus => self.chorus.process_stereo(l, r),
+                EffectSlot::AutoPan => self.auto_pan.process(l, r),
+                EffectSlot::Delay => {
+                    let to_delay = sub * delay_send;
+                    self.delay.process(l + to_delay, r + to_delay)
+                }
+                EffectSlot::Reverb => {
+                    let to_reverb = sub * reverb_send;
+                    self.reverb.process(l + to_reverb, r + to_reverb)
+                }
+            };
+            if self.is_enabled(slot) {
+                let gain = self.gains[slot.index()];
+                (l, r) = (l * gain, r * gain);
+            }
+            if self.wet_solo == Some(slot) {
+                return (l, r);
+            }
+        }
 
-        // Finally reverb (stereo)
-        self.reverb.process(l, r)
+        (l + sub, r + sub)
     }
 }
 
@@ -516,6 +1944,24 @@ mod tests {
         assert_eq!(r, 0.7);
     }
 
+    #[test]
+    fn delay_repeats_end_at_exact_zero_instead_of_denormals() {
+        let mut d = Delay::new(SR);
+        d.enabled = true;
+        d.time_ms = 20.0;
+        d.feedback = 0.5;
+        d.mix = 1.0;
+        d.set_high_cut(5_000.0);
+        d.process(1.0, 1.0);
+        let mut last = (1.0, 1.0);
+        for _ in 0..(SR as usize * 2) {
+            last = d.process(0.0, 0.0);
+            assert!(!last.0.is_subnormal() && !last.1.is_subnormal());
+        }
+        assert_eq!(last, (0.0, 0.0));
+        assert_eq!(d.lp_state, [0.0; 2]);
+    }
+
     #[test]
     fn delay_enabled_emits_delayed_signal() {
         let mut d = Delay::new(SR);
@@ -561,6 +2007,70 @@ mod tests {
         }
     }
 
+    fn first_echo_at(d: &mut Delay) -> Option<usize> {
+        d.process(1.0, 1.0);
+        (1..(SR * MAX_DELAY_SECONDS) as usize).find(|_| d.process(0.0, 0.0).0 > 0.5)
+    }
+
+    #[test]
+    fn synced_delay_follows_the_tempo() {
+        use crate::tempo::{NoteFeel, NoteValue};
+        let mut d = Delay::new(SR);
+        d.enabled = true;
+        d.feedback = 0.0;
+        d.mix = 1.0;
+        d.sync = Some(NoteDivision::new(NoteValue::Eighth, NoteFeel::Straight));
+        d.set_tempo(120.0);
+        assert_eq!(d.effective_time_ms(), 250.0);
+        assert_eq!(first_echo_at(&mut d), Some((SR * 0.25) as usize));
+
+        d.set_tempo(150.0);
+        assert_eq!(d.effective_time_ms(), 200.0);
+        // Too long for the buffer: capped.
+        d.sync = Some(NoteDivision::new(NoteValue::Whole, NoteFeel::Dotted));
+        d.set_tempo(30.0);
+        assert_eq!(d.effective_time_ms(), MAX_DELAY_SECONDS * 1000.0);
+        d.sync = None;
+        assert_eq!(d.effective_time_ms(), d.time_ms);
+    }
+
+    #[test]
+    fn delay_feedback_filters_shape_only_the_repeats() {
+        let second_echo_peak = |high_cut: f32, low_cut: f32| {
+            let mut d = Delay::new(SR);
+            d.enabled = true;
+            d.ping_pong = false;
+            d.time_ms = 10.0;
+            d.feedback = 0.9;
+            d.mix = 1.0;
+            d.set_high_cut(high_cut);
+            d.set_low_cut(low_cut);
+            let delay = (SR * 0.01) as usize;
+            d.process(1.0, 1.0);
+            let out: Vec<f32> = (1..3 * delay).map(|_| d.process(0.0, 0.0).0).collect();
+            let first = out[delay - 1];
+            let second = out[delay..].iter().fold(0.0_f32, |m, s| m.max(s.abs()));
+            (first, second)
+        };
+        let (first_open, second_open) =
+            second_echo_peak(DELAY_HIGH_CUT_MAX_HZ, DELAY_LOW_CUT_MIN_HZ);
+        assert_eq!(first_open, 1.0);
+        assert!((second_open - 0.9).abs() < 1e-6);
+        // High cut: the first echo is untouched, the repeat is smeared.
+        let (first, second) = second_echo_peak(1_000.0, DELAY_LOW_CUT_MIN_HZ);
+        assert_eq!(first, 1.0);
+        assert!(second < 0.5 * second_open);
+        // Low cut blocks DC in the loop.
+        let mut d = Delay::new(SR);
+        d.enabled = true;
+        d.time_ms = 5.0;
+        d.feedback = 0.9;
+        d.mix = 1.0;
+        d.set_low_cut(500.0);
+        let tail = (0..(SR as usize)).map(|_| d.process(0.1, 0.1).0).last();
+        assert!(tail.unwrap().abs() < 0.12);
+    }
+
     // -----------------------------------------------------------------------
     // Reverb
     // -----------------------------------------------------------------------
@@ -595,6 +2105,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn reverb_tail_ends_at_exact_zero_instead_of_denormals() {
+        let mut r = Reverb::new(SR);
+        r.enabled = true;
+        r.room_size = 0.0;
+        r.mix = 1.0;
+        for _ in 0..(SR as usize / 20) {
+            r.process(0.5, 0.5);
+        }
+        let mut last = (1.0, 1.0);
+        for _ in 0..(SR as usize * 5) {
+            last = r.process(0.0, 0.0);
+            assert!(!last.0.is_subnormal() && !last.1.is_subnormal());
+        }
+        assert_eq!(last, (0.0, 0.0));
+    }
+
+    #[test]
+    fn clear_flushes_a_nan_out_of_the_feedback_loops() {
+        let mut chain = EffectsChain::new(SR);
+        chain.delay.enabled = true;
+        chain.reverb.enabled = true;
+        chain.process(f32::NAN);
+        let poisoned = (0..SR as usize / 10).any(|_| chain.process(0.0).0.is_nan());
+        assert!(poisoned);
+        chain.clear();
+        for _ in 0..(SR as usize) {
+            let (l, r) = chain.process(0.0);
+            assert!(l.is_finite() && r.is_finite());
+        }
+    }
+
     #[test]
     fn reverb_room_size_changes_feedback() {
         let mut r = Reverb::new(SR);
@@ -620,6 +2162,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn reverb_pre_delay_holds_back_the_first_reflections() {
+        let onset = |pre_delay_ms: f32| {
+            let mut r = Reverb::new(SR);
+            r.enabled = true;
+            r.mix = 1.0;
+            r.pre_delay_ms = pre_delay_ms;
+            r.process(1.0, 1.0);
+            (1..SR as usize)
+                .find(|_| r.process(0.0, 0.0).0.abs() > 1e-6)
+                .unwrap()
+        };
+        let dry = onset(0.0);
+        let delayed = onset(100.0);
+        let expected = (0.1 * SR) as usize;
+        assert!(
+            delayed.abs_diff(dry + expected) <= 1,
+            "onset {dry} -> {delayed}, expected +{expected} samples"
+        );
+    }
+
+    #[test]
+    fn reverb_type_switch_retunes_combs_without_reallocating() {
+        let mut r = Reverb::new(SR);
+        assert_eq!(r.reverb_type(), ReverbType::Hall);
+        let buffer = r.combs_l[0].buffer.as_ptr();
+        for reverb_type in ReverbType::all() {
+            r.set_reverb_type(reverb_type);
+            let (combs_l, combs_r, _) = Reverb::filter_sizes(SR, reverb_type);
+            assert_eq!(r.combs_l[0].len, combs_l[0]);
+            assert_eq!(r.combs_r[3].len, combs_r[3]);
+            assert_eq!(r.combs_l[0].buffer.as_ptr(), buffer);
+        }
+        assert_eq!(
+            ReverbType::from_code(ReverbType::Room.to_code()),
+            ReverbType::Room
+        );
+    }
+
+    #[test]
+    fn reverb_modulated_allpass_keeps_the_tail_bounded() {
+        let mut r = Reverb::new(SR);
+        r.enabled = true;
+        r.mix = 1.0;
+        r.room_size = 1.0;
+        r.set_reverb_type(ReverbType::Plate);
+        let mut peak = 0.0_f32;
+        for i in 0..(SR as usize * 2) {
+            let x = if i < SR as usize / 2 {
+                (i as f32 * 0.05).sin() * 0.5
+            } else {
+                0.0
+            };
+            let (l, rr) = r.process(x, x);
+            assert!(l.is_finite() && rr.is_finite());
+            peak = peak.max(l.abs()).max(rr.abs());
+        }
+        assert!(peak > 0.0 && peak < 4.0, "peak {peak}");
+    }
+
     // -----------------------------------------------------------------------
     // EffectsChain
     // -----------------------------------------------------------------------
@@ -647,6 +2249,113 @@ mod tests {
         assert_eq!(r, 0.42);
     }
 
+    #[test]
+    fn effects_chain_sub_bus_bypasses_chorus_and_feeds_sends() {
+        let mut chain = EffectsChain::new(SR);
+        chain.chorus.enabled = true;
+        chain.chorus.mix = 1.0;
+        // No main input: the sub bus alone is heard dry, untouched by chorus.
+        let (l, r) = chain.process_with_sends(0.0, 0.3, 0.0, 0.0);
+        assert_eq!((l, r), (0.3, 0.3));
+
+        chain.delay.enabled = true;
+        chain.delay.time_ms = 10.0;
+        chain.delay.mix = 1.0;
+        chain.process_with_sends(0.0, 1.0, 1.0, 0.0);
+        let mut echo = 0.0_f32;
+        for _ in 0..2048 {
+            let (l, _) = chain.process_with_sends(0.0, 0.0, 1.0, 0.0);
+            echo = echo.max(l.abs());
+        }
+        assert!(echo > 0.0, "delay send should produce an echo");
+    }
+
+    #[test]
+    fn effects_chain_set_sample_rate_rescales_every_buffer() {
+        let mut chain = EffectsChain::new(SR);
+        chain.delay.time_ms = 450.0;
+        chain.reverb.room_size = 0.3;
+        chain.set_sample_rate(96_000.0);
+
+        assert_eq!(chain.chorus.buffer_l.len(), (96_000.0 * 0.05) as usize);
+        assert_eq!(chain.delay.buffer_l.len(), 192_000);
+        let (combs_l, _, allpasses) = Reverb::filter_sizes(96_000.0, ReverbType::Hall);
+        assert_eq!(combs_l[0], 2429);
+        assert_eq!(chain.reverb.combs_l[0].buffer.len(), combs_l[0]);
+        assert_eq!(chain.reverb.allpasses_r[1].buffer.len(), allpasses[1] + 17);
+        assert_eq!(chain.auto_pan.sample_rate, 96_000.0);
+        // Parameters survive the switch.
+        assert_eq!(chain.delay.time_ms, 450.0);
+        assert_eq!(chain.reverb.room_size, 0.3);
+    }
+
+    #[test]
+    fn effects_chain_output_gain_trims_only_enabled_effects() {
+        let mut chain = EffectsChain::new(SR);
+        chain.set_output_gain_db(EffectSlot::Eq, -6.0);
+        assert_eq!(chain.process(0.5), (0.5, 0.5));
+
+        chain.eq.enabled = true;
+        let (l, r) = chain.process(0.5);
+        let expected = 0.5 * 10.0_f32.powf(-6.0 / 20.0);
+        assert!((l - expected).abs() < 1e-4 && (r - expected).abs() < 1e-4);
+
+        chain.set_output_gain_db(EffectSlot::Eq, 40.0);
+        assert_eq!(chain.output_gain_db(EffectSlot::Eq), EFFECT_MAX_GAIN_DB);
+    }
+
+    #[test]
+    fn effects_chain_wet_solo_mutes_dry_and_later_slots() {
+        let mut chain = EffectsChain::new(SR);
+        chain.delay.enabled = true;
+        chain.delay.time_ms = 10.0;
+        chain.delay.mix = 0.3;
+        chain.reverb.enabled = true;
+        chain.set_wet_solo(Some(EffectSlot::Delay));
+        assert!(chain.delay.wet_only && !chain.reverb.wet_only);
+
+        // Before the first echo only the (muted) dry would be heard, the
+        // sub bus included.
+        assert_eq!(chain.process_with_sends(1.0, 0.5, 0.0, 0.0), (0.0, 0.0));
+        let echo = (0..SR as usize / 50)
+            .map(|_| chain.process(0.0).0.abs())
+            .fold(0.0, f32::max);
+        assert!(
+            (echo - 1.0).abs() < 1e-3,
+            "the echo is heard at full wet, unreverbed: {echo}"
+        );
+
+        // Slots without a wet path can't be soloed; None restores the mix.
+        chain.set_wet_solo(Some(EffectSlot::Eq));
+        assert_eq!(chain.wet_solo(), Some(EffectSlot::Delay));
+        chain.set_wet_solo(None);
+        assert!(!chain.delay.wet_only);
+        chain.reverb.enabled = false;
+        chain.delay.enabled = false;
+        assert_eq!(chain.process(0.25), (0.25, 0.25));
+    }
+
+    #[test]
+    fn delay_echo_time_is_preserved_after_rate_change() {
+        let mut d = Delay::new(SR);
+        d.set_sample_rate(48_000.0);
+        d.enabled = true;
+        d.time_ms = 100.0;
+        d.feedback = 0.0;
+        d.mix = 1.0;
+
+        d.process(1.0, 1.0);
+        let mut echo_at = None;
+        for i in 1..10_000 {
+            let (l, _) = d.process(0.0, 0.0);
+            if l > 0.5 {
+                echo_at = Some(i);
+                break;
+            }
+        }
+        assert_eq!(echo_at, Some(4800));
+    }
+
     // -----------------------------------------------------------------------
     // AutoPan
     // -----------------------------------------------------------------------
@@ -745,4 +2454,455 @@ mod tests {
         assert!(peak_l > 0.5);
         assert!(peak_r > 0.5);
     }
+
+    // -----------------------------------------------------------------------
+    // VoicePan
+    // -----------------------------------------------------------------------
+
+    fn voice_pan_gains(stereo_phase: f32, samples: usize) -> Vec<(f32, f32)> {
+        let mut pan = VoicePan::new(SR);
+        pan.rate_hz = 5.0;
+        pan.depth = 1.0;
+        pan.stereo_phase = stereo_phase;
+        (0..samples).map(|_| pan.next_gains()).collect()
+    }
+
+    #[test]
+    fn voice_pan_zero_depth_is_unity() {
+        let mut pan = VoicePan::new(SR);
+        assert_eq!(pan.depth, 0.0);
+        for _ in 0..1000 {
+            assert_eq!(pan.next_gains(), (1.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn voice_pan_phase_zero_is_tremolo() {
+        let gains = voice_pan_gains(0.0, SR as usize / 5);
+        assert!(gains.iter().all(|(l, r)| (l - r).abs() < 1e-6));
+        let min = gains.iter().fold(1.0_f32, |m, g| m.min(g.0));
+        assert!(min < 0.01, "full depth should reach silence, got {min}");
+    }
+
+    #[test]
+    fn voice_pan_phase_one_is_auto_pan() {
+        let gains = voice_pan_gains(1.0, SR as usize / 5);
+        // Starts at the crest on L and the trough on R.
+        assert!((gains[0].0 - 1.0).abs() < 1e-6);
+        assert!(gains[0].1.abs() < 1e-6);
+        // Opposite phases: the two gains always sum to one.
+        assert!(gains.iter().all(|(l, r)| (l + r - 1.0).abs() < 1e-5));
+    }
+
+    #[test]
+    fn voice_pan_reset_restarts_at_crest() {
+        let mut pan = VoicePan::new(SR);
+        pan.depth = 0.8;
+        pan.stereo_phase = 0.0;
+        for _ in 0..3000 {
+            pan.next_gains();
+        }
+        pan.reset();
+        assert_eq!(pan.next_gains(), (1.0, 1.0));
+    }
+
+    #[test]
+    fn voice_pan_position_is_equal_power_and_stacks_with_the_lfo() {
+        let mut pan = VoicePan::new(SR);
+        pan.set_position(-1.0);
+        let (l, r) = pan.next_gains();
+        assert!((l - std::f32::consts::SQRT_2).abs() < 1e-6 && r.abs() < 1e-6);
+        pan.set_position(0.5);
+        let (l, r) = pan.next_gains();
+        assert!(r > l && (l * l + r * r - 2.0).abs() < 1e-5);
+
+        // The tremolo crest at reset leaves the placement gains as they are.
+        pan.depth = 1.0;
+        pan.stereo_phase = 0.0;
+        pan.reset();
+        assert_eq!(pan.next_gains(), (l, r));
+
+        pan.set_position(0.0);
+        pan.depth = 0.0;
+        assert_eq!(pan.next_gains(), (1.0, 1.0));
+    }
+
+    #[test]
+    fn voice_spread_positions() {
+        assert_eq!(VoiceSpread::Center.position(100, 3), 0.0);
+        let rr: Vec<_> = (0..5)
+            .map(|n| VoiceSpread::RoundRobin.position(60, n))
+            .collect();
+        assert_eq!(rr, [-1.0, 1.0, -0.5, 0.5, -1.0]);
+        assert_eq!(VoiceSpread::KeyFollow.position(60, 0), 0.0);
+        assert_eq!(VoiceSpread::KeyFollow.position(24, 0), -1.0);
+        assert_eq!(VoiceSpread::KeyFollow.position(127, 0), 1.0);
+        for spread in VoiceSpread::all() {
+            assert_eq!(VoiceSpread::from_code(spread.to_code()), spread);
+        }
+    }
+
+    #[test]
+    fn effect_slots_move_and_only_permutations_are_accepted() {
+        use EffectSlot::*;
+        let order = EffectSlot::DEFAULT_ORDER;
+        assert_eq!(
+            move_effect_slot(order, 6, 3),
+            [Eq, Filter, Phaser, Reverb, Chorus, AutoPan, Delay]
+        );
+        assert_eq!(
+            move_effect_slot(order, 3, 5),
+            [Eq, Filter, Phaser, AutoPan, Delay, Chorus, Reverb]
+        );
+        assert_eq!(move_effect_slot(order, 3, 9), order);
+
+        let mut chain = EffectsChain::new(SR);
+        chain.set_order([Eq, Delay, Delay, Chorus, Reverb, Phaser, Filter]);
+        assert_eq!(chain.order(), order);
+        chain.set_order([Reverb, Delay, AutoPan, Chorus, Phaser, Filter, Eq]);
+        assert_eq!(
+            chain.order(),
+            [Reverb, Delay, AutoPan, Chorus, Phaser, Filter, Eq]
+        );
+    }
+
+    #[test]
+    fn chain_order_changes_the_sound_and_bypass_skips_everything() {
+        let run = |setup: &dyn Fn(&mut EffectsChain)| {
+            let mut chain = EffectsChain::new(SR);
+            chain.chorus.enabled = true;
+            chain.delay.enabled = true;
+            chain.delay.time_ms = 5.0;
+            chain.reverb.enabled = true;
+            setup(&mut chain);
+            (0..2048)
+                .map(|i| {
+                    let x = (2.0 * PI * 330.0 * i as f32 / SR).sin();
+                    chain.process_mid_side_with_sends(x, 0.1 * x, 0.05, 0.5, 0.5)
+                })
+                .collect::<Vec<_>>()
+        };
+        let default = run(&|_| {});
+        let reversed = run(&|c| {
+            let mut order = c.order();
+            order.reverse();
+            c.set_order(order);
+        });
+        assert_ne!(default, reversed);
+
+        let bypassed = run(&|c| c.bypass = true);
+        for (i, (l, r)) in bypassed.into_iter().enumerate() {
+            let x = (2.0 * PI * 330.0 * i as f32 / SR).sin();
+            assert_eq!((l, r), (x + 0.1 * x + 0.05, x - 0.1 * x + 0.05));
+        }
+    }
+
+    #[test]
+    fn phaser_disabled_passes_through() {
+        let mut p = Phaser::new(SR);
+        assert_eq!(p.process(0.3, -0.7), (0.3, -0.7));
+    }
+
+    #[test]
+    fn phaser_notches_move_with_the_sweep() {
+        // A steady tone through a half-wet phaser comes out at a level that
+        // rises and falls as the notches sweep past it. Without feedback
+        // there are no resonant peaks, so nothing is boosted.
+        let mut p = Phaser::new(SR);
+        p.enabled = true;
+        p.rate = 2.0;
+        p.depth = 1.0;
+        p.feedback = 0.0;
+        let block = (SR / 50.0) as usize;
+        let levels: Vec<f32> = (0..50)
+            .map(|b| {
+                let mut peak = 0.0_f32;
+                for i in 0..block {
+                    let t = (b * block + i) as f32 / SR;
+                    let (l, _) = p.process((2.0 * PI * 800.0 * t).sin(), 0.0);
+                    peak = peak.max(l.abs());
+                }
+                peak
+            })
+            .collect();
+        let min = levels.iter().copied().fold(f32::MAX, f32::min);
+        let max = levels.iter().copied().fold(0.0, f32::max);
+        assert!(max <= 1.05, "phaser should not boost: {max}");
+        assert!(max - min > 0.3, "sweep too shallow: {min}..{max}");
+    }
+
+    #[test]
+    fn phaser_stages_are_four_or_eight_and_decay_to_zero() {
+        let mut p = Phaser::new(SR);
+        for (asked, got) in [(0, 4), (4, 4), (6, 8), (8, 8), (12, 8)] {
+            p.set_stages(asked);
+            assert_eq!(p.stages(), got);
+        }
+        p.enabled = true;
+        p.apply(PhaserSettings {
+            feedback: 5.0,
+            ..PHASER_VINTAGE_EP
+        });
+        assert_eq!(p.feedback, PHASER_MAX_FEEDBACK);
+        assert_eq!(p.stages(), 4);
+        p.process(1.0, 1.0);
+        let mut last = (1.0, 1.0);
+        for _ in 0..SR as usize {
+            last = p.process(0.0, 0.0);
+        }
+        assert_eq!(last, (0.0, 0.0));
+    }
+
+    /// RMS of the second half of `freq` Hz at `amplitude` through `filter`.
+    fn filter_rms(filter: &mut ResonantFilter, freq: f32, amplitude: f32) -> f32 {
+        let n = 16_384;
+        let mut sum = 0.0;
+        for i in 0..n {
+            let x = amplitude * (2.0 * PI * freq * i as f32 / SR).sin();
+            let (l, _) = filter.process(x, x);
+            if i >= n / 2 {
+                sum += l * l;
+            }
+        }
+        (sum / (n / 2) as f32).sqrt() / amplitude
+    }
+
+    #[test]
+    fn resonant_filter_passes_lows_rings_at_cutoff_and_cuts_highs() {
+        let fresh = || {
+            let mut f = ResonantFilter::new(SR);
+            f.enabled = true;
+            f.env_amount = 0.0;
+            f.cutoff_hz = 1_000.0;
+            f.resonance = 0.8;
+            f
+        };
+        let low = filter_rms(&mut fresh(), 100.0, 0.5);
+        let peak = filter_rms(&mut fresh(), 1_000.0, 0.5);
+        let high = filter_rms(&mut fresh(), 8_000.0, 0.5);
+        let unity = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((db(low / unity)).abs() < 0.5, "lows pass: {low}");
+        assert!(db(peak / unity) > 6.0, "resonance peaks: {peak}");
+        assert!(db(high / unity) < -24.0, "highs cut: {high}");
+
+        let mut off = fresh();
+        off.enabled = false;
+        assert_eq!(off.process(0.3, -0.7), (0.3, -0.7));
+    }
+
+    #[test]
+    fn resonant_filter_opens_with_the_input_level() {
+        // The envelope follower raises the cutoff: a loud 4 kHz tone gets
+        // through a 500 Hz filter far better than a quiet one.
+        let mut f = ResonantFilter::new(SR);
+        f.enabled = true;
+        f.cutoff_hz = 500.0;
+        f.resonance = 0.0;
+        f.env_amount = 1.0;
+        let quiet = filter_rms(&mut f, 4_000.0, 0.01);
+        f.clear();
+        let loud = filter_rms(&mut f, 4_000.0, 1.0);
+        assert!(db(loud / quiet) > 20.0, "quiet {quiet}, loud {loud}");
+    }
+
+    #[test]
+    fn resonant_filter_runs_only_with_extensions_on() {
+        let run = |extensions: bool| {
+            let mut chain = EffectsChain::new(SR);
+            chain.filter.enabled = true;
+            chain.filter.cutoff_hz = 200.0;
+            chain.extensions = extensions;
+            (0..512)
+                .map(|i| chain.process((2.0 * PI * 5_000.0 * i as f32 / SR).sin()))
+                .collect::<Vec<_>>()
+        };
+        let authentic = run(false);
+        for (i, (l, r)) in authentic.iter().enumerate() {
+            let x = (2.0 * PI * 5_000.0 * i as f32 / SR).sin();
+            assert_eq!((*l, *r), (x, x));
+        }
+        assert_ne!(run(true), authentic);
+
+        let mut chain = EffectsChain::new(SR);
+        chain.filter.enabled = true;
+        assert!(!chain.is_enabled(EffectSlot::Filter));
+        chain.extensions = true;
+        assert!(chain.is_enabled(EffectSlot::Filter));
+    }
+
+    /// RMS of the second half of `freq` Hz through `eq` (first half settles).
+    fn eq_rms(eq: &mut Equalizer, freq: f32) -> f32 {
+        let n = 16_384;
+        let mut sum = 0.0;
+        for i in 0..n {
+            let x = (2.0 * PI * freq * i as f32 / SR).sin();
+            let (l, _) = eq.process(x, x);
+            if i >= n / 2 {
+                sum += l * l;
+            }
+        }
+        (sum / (n / 2) as f32).sqrt()
+    }
+
+    fn db(ratio: f32) -> f32 {
+        20.0 * ratio.log10()
+    }
+
+    #[test]
+    fn flat_eq_passes_through_exactly() {
+        let mut eq = Equalizer::new(SR);
+        eq.enabled = true;
+        eq.set_mid_q(4.0);
+        for i in 0..512 {
+            let x = (i as f32 * 0.37).sin();
+            assert_eq!(eq.process(x, -x), (x, -x));
+        }
+    }
+
+    #[test]
+    fn eq_bands_boost_and_cut_their_own_region() {
+        let dry = std::f32::consts::FRAC_1_SQRT_2;
+
+        let mut eq = Equalizer::new(SR);
+        eq.enabled = true;
+        eq.set_low_freq(300.0);
+        eq.set_low_gain(12.0);
+        assert!((db(eq_rms(&mut eq, 40.0) / dry) - 12.0).abs() < 1.0);
+        assert!(db(eq_rms(&mut eq, 5_000.0) / dry).abs() < 0.5);
+
+        let mut eq = Equalizer::new(SR);
+        eq.enabled = true;
+        eq.set_high_freq(4_000.0);
+        eq.set_high_gain(-12.0);
+        assert!((db(eq_rms(&mut eq, 16_000.0) / dry) + 12.0).abs() < 1.0);
+        assert!(db(eq_rms(&mut eq, 200.0) / dry).abs() < 0.5);
+
+        let mut eq = Equalizer::new(SR);
+        eq.enabled = true;
+        eq.set_mid_freq(2_000.0);
+        eq.set_mid_q(2.0);
+        eq.set_mid_gain(-9.0);
+        assert!((db(eq_rms(&mut eq, 2_000.0) / dry) + 9.0).abs() < 0.5);
+        assert!(db(eq_rms(&mut eq, 100.0) / dry).abs() < 0.5);
+
+        eq.set_mid_gain(100.0);
+        assert_eq!(eq.mid().gain_db, EQ_MAX_GAIN_DB);
+    }
+
+    #[test]
+    fn chorus_stereo_input_with_equal_channels_matches_mono() {
+        let mut a = Chorus::new(SR);
+        let mut b = Chorus::new(SR);
+        a.enabled = true;
+        b.enabled = true;
+        for i in 0..2048 {
+            let x = (2.0 * PI * 220.0 * i as f32 / SR).sin();
+            assert_eq!(a.process(x), b.process_stereo(x, x));
+        }
+    }
+
+    #[test]
+    fn chain_mid_side_with_zero_side_matches_mono_path() {
+        let mut a = EffectsChain::new(SR);
+        let mut b = EffectsChain::new(SR);
+        for i in 0..2048 {
+            let x = (2.0 * PI * 330.0 * i as f32 / SR).sin();
+            assert_eq!(
+                a.process_with_sends(x, 0.1, 0.2, 0.3),
+                b.process_mid_side_with_sends(x, 0.0, 0.1, 0.2, 0.3)
+            );
+        }
+    }
+
+    #[test]
+    fn modulation_offsets_the_parameters_without_storing() {
+        // Impulse through a 10 ms delay with no dry path and ping-pong off:
+        // returns the (echo, second echo) peaks.
+        let echoes = |modulation: EffectModulation| {
+            let mut chain = EffectsChain::new(SR);
+            chain.delay.enabled = true;
+            chain.delay.time_ms = 10.0;
+            chain.delay.feedback = 0.5;
+            chain.delay.mix = 1.0;
+            chain.delay.ping_pong = false;
+            chain.set_modulation(modulation);
+            let out: Vec<f32> = (0..1000)
+                .map(|i| chain.delay.process(if i == 0 { 1.0 } else { 0.0 }, 0.0).0)
+                .collect();
+            assert_eq!(chain.delay.feedback, 0.5);
+            (out[441].abs(), out[882].abs())
+        };
+        let (first, second) = echoes(EffectModulation::default());
+        assert!((first - 1.0).abs() < 1e-6 && (second - 0.5).abs() < 1e-6);
+        let (first, second) = echoes(EffectModulation {
+            delay_feedback: -1.0,
+            ..EffectModulation::default()
+        });
+        assert!((first - 1.0).abs() < 1e-6 && second == 0.0);
+        let (_, second) = echoes(EffectModulation {
+            delay_feedback: 5.0,
+            ..EffectModulation::default()
+        });
+        assert!((second - DELAY_MAX_FEEDBACK).abs() < 1e-6);
+
+        let mut chain = EffectsChain::new(SR);
+        chain.set_modulation(EffectModulation {
+            mix: 0.1,
+            chorus_depth_ms: 20.0,
+            delay_feedback: 0.0,
+            reverb_mix: 0.2,
+        });
+        assert_eq!(chain.chorus.depth, 3.0);
+        assert_eq!(chain.chorus.depth_mod, 20.0);
+        assert!((chain.reverb.mix_mod - 0.3).abs() < 1e-6);
+        chain.reset_settings();
+        assert_eq!(chain.chorus.depth_mod, 0.0);
+        assert_eq!(chain.reverb.mix_mod, 0.0);
+    }
+
+    #[test]
+    fn reset_settings_matches_a_new_chain() {
+        use crate::tempo::{NoteFeel, NoteValue};
+        let fresh = EffectsChain::new(SR);
+        let mut chain = EffectsChain::new(SR);
+        chain.eq.set_mid_gain(6.0);
+        chain.filter.cutoff_hz = 3_000.0;
+        chain.extensions = true;
+        chain.phaser.set_stages(8);
+        chain.chorus.mix = 0.9;
+        chain.auto_pan.enabled = true;
+        chain.delay.sync = Some(NoteDivision::new(NoteValue::Eighth, NoteFeel::Straight));
+        chain.delay.set_high_cut(2_000.0);
+        chain.reverb.set_reverb_type(ReverbType::Plate);
+        chain.reverb.room_size = 0.1;
+        chain.set_order([
+            EffectSlot::Reverb,
+            EffectSlot::Delay,
+            EffectSlot::AutoPan,
+            EffectSlot::Chorus,
+            EffectSlot::Phaser,
+            EffectSlot::Filter,
+            EffectSlot::Eq,
+        ]);
+        chain.set_output_gain_db(EffectSlot::Delay, -6.0);
+        chain.set_wet_solo(Some(EffectSlot::Reverb));
+        chain.bypass = true;
+        chain.process(1.0);
+
+        chain.reset_settings();
+        assert_eq!(chain.eq.bands[1].gain_db, fresh.eq.bands[1].gain_db);
+        assert_eq!(chain.filter.cutoff_hz, fresh.filter.cutoff_hz);
+        assert!(chain.extensions);
+        assert_eq!(chain.phaser.stages(), fresh.phaser.stages());
+        assert_eq!(chain.chorus.mix, fresh.chorus.mix);
+        assert!(!chain.auto_pan.enabled);
+        assert_eq!(chain.delay.sync, None);
+        assert_eq!(chain.delay.high_cut(), fresh.delay.high_cut());
+        assert_eq!(chain.reverb.reverb_type(), fresh.reverb.reverb_type());
+        assert_eq!(chain.reverb.room_size, fresh.reverb.room_size);
+        assert_eq!(chain.order(), EffectSlot::DEFAULT_ORDER);
+        assert_eq!(chain.output_gain_db(EffectSlot::Delay), 0.0);
+        assert_eq!(chain.wet_solo(), None);
+        assert!(!chain.bypass);
+    }
 }
@@ -1,4 +1,8 @@
-use crate::optimization::dx7_rate_to_multiplier;
+use crate::optimization::{dx7_rate_to_multiplier, dx7_rate_to_time};
+use crate::vintage::{quantize_envelope, VintageConfig};
+
+/// A segment ends once the level is this close to its target.
+const SNAP_DISTANCE: f32 = 0.001;
 
 #[derive(Debug, Clone)]
 pub struct Envelope {
@@ -24,17 +28,33 @@ pub struct Envelope {
     rate_smoother: f32,
     target_rate: f32,
     smoothing_samples: f32,
+    vintage: VintageConfig,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum EnvelopeStage {
+pub enum EnvelopeStage {
     Idle,
     Stage1,
     Stage2,
     Stage3,
+    /// Release, after note-off.
     Stage4,
 }
 
+impl EnvelopeStage {
+    /// Short name for debug reports: the DX7's rate numbers, REL for the
+    /// release segment.
+    pub fn label(self) -> &'static str {
+        match self {
+            EnvelopeStage::Idle => "IDLE",
+            EnvelopeStage::Stage1 => "R1",
+            EnvelopeStage::Stage2 => "R2",
+            EnvelopeStage::Stage3 => "R3",
+            EnvelopeStage::Stage4 => "REL",
+        }
+    }
+}
+
 impl Envelope {
     pub fn new(sample_rate: f32) -> Self {
         Self {
@@ -60,9 +80,25 @@ impl Envelope {
             rate_smoother: 0.0,
             target_rate: 0.0,
             smoothing_samples: sample_rate * 0.002, // 2ms smoothing time for crystalline attacks
+            vintage: VintageConfig::CLEAN,
         }
     }
 
+    pub fn set_vintage(&mut self, vintage: VintageConfig) {
+        self.vintage = vintage;
+    }
+
+    /// Switch to a new sample rate. Per-sample rates of the stage in flight
+    /// are rescaled so it keeps its duration in seconds.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        let scale = self.sample_rate / sample_rate;
+        self.rate *= scale;
+        self.target_rate *= scale;
+        self.rate_smoother *= scale;
+        self.smoothing_samples = sample_rate * 0.002;
+        self.sample_rate = sample_rate;
+    }
+
     pub fn trigger_with_key_scale(&mut self, velocity: f32, key_scale_factor: f32) {
         self.velocity = velocity;
         self.key_scale_factor = key_scale_factor;
@@ -110,8 +146,9 @@ impl Envelope {
             let approach_factor = (self.rate * 6.908).clamp(0.0000001, 0.5);
             self.current_level += distance * approach_factor;
 
-            // Check if we're close enough to target to advance stage
-            if distance.abs() < 0.001 {
+            // Check if we're close enough to target to advance stage. The
+            // snap also means a release tail never decays into denormals.
+            if distance.abs() < SNAP_DISTANCE {
                 self.current_level = self.target_level;
                 self.advance_stage();
             }
@@ -121,7 +158,12 @@ impl Envelope {
         }
 
         // Apply velocity scaling
-        self.current_level * self.velocity
+        let output = self.current_level * self.velocity;
+        if self.vintage.envelope_quantization {
+            quantize_envelope(output)
+        } else {
+            output
+        }
     }
 
     fn advance_stage(&mut self) {
@@ -165,6 +207,10 @@ impl Envelope {
         self.stage != EnvelopeStage::Idle
     }
 
+    pub fn stage(&self) -> EnvelopeStage {
+        self.stage
+    }
+
     /// Live envelope output: `level * velocity`, in 0..=1.
     pub fn current_output(&self) -> f32 {
         self.current_level * self.velocity
@@ -205,6 +251,56 @@ impl Envelope {
     }
 }
 
+/// Seconds a segment at DX7 `rate` takes to move `distance` (a fraction of
+/// full scale) on a key whose rate scaling speeds envelopes up by
+/// `key_scale_factor`. None for rate 0, which never gets there.
+///
+/// The level approaches its target exponentially and a full-scale segment
+/// lasts `dx7_rate_to_time`, so a shorter move takes proportionally less of
+/// the same log curve.
+pub fn segment_seconds(rate: f32, distance: f32, key_scale_factor: f32) -> Option<f32> {
+    let distance = distance.abs();
+    if distance < SNAP_DISTANCE {
+        return Some(0.0);
+    }
+    if rate == 0.0 {
+        return None;
+    }
+    let full_scale = dx7_rate_to_time(rate as u8);
+    Some(
+        full_scale * (distance / SNAP_DISTANCE).ln()
+            / (1.0 / SNAP_DISTANCE).ln()
+            / key_scale_factor,
+    )
+}
+
+/// How long an envelope takes on one key, in seconds; None where a rate 0
+/// segment holds it forever.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvelopeTimes {
+    /// Key-on to the sustain level: R1, R2 and R3.
+    pub to_sustain: Option<f32>,
+    /// Key-off from the sustain level down to L4.
+    pub release: Option<f32>,
+}
+
+/// Times for the envelope with DX7 `rates` and `levels` (0-99) on a key
+/// whose rate scaling factor is `key_scale_factor`. The envelope starts
+/// from silence.
+pub fn envelope_times(rates: [f32; 4], levels: [f32; 4], key_scale_factor: f32) -> EnvelopeTimes {
+    let mut from = 0.0;
+    let mut to_sustain = Some(0.0);
+    for (&rate, &level) in rates.iter().zip(&levels).take(3) {
+        let segment = segment_seconds(rate, (level - from) / 99.0, key_scale_factor);
+        to_sustain = to_sustain.zip(segment).map(|(total, s)| total + s);
+        from = level;
+    }
+    EnvelopeTimes {
+        to_sustain,
+        release: segment_seconds(rates[3], (levels[3] - levels[2]) / 99.0, key_scale_factor),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -416,4 +512,42 @@ mod tests {
         }
         assert!(!env.is_active());
     }
+
+    #[test]
+    fn computed_times_match_the_running_envelope() {
+        // The attack runs at exactly its rate from silence, so it checks the
+        // timing law; later segments ease between rates.
+        for (rate, level, key_scale_factor) in [(50.0, 99.0, 1.0), (60.0, 60.0, 2.0)] {
+            let mut env = Envelope::new(SR);
+            env.rate1 = rate;
+            env.level1 = level;
+            env.trigger_with_key_scale(1.0, key_scale_factor);
+            let mut samples = 0;
+            while env.stage() == EnvelopeStage::Stage1 {
+                env.process();
+                samples += 1;
+            }
+            let measured = samples as f32 / SR;
+            let computed = segment_seconds(rate, level / 99.0, key_scale_factor).unwrap();
+            assert!(
+                (measured / computed - 1.0).abs() < 0.02,
+                "{measured} vs {computed}"
+            );
+        }
+
+        let rates = [99.0, 50.0, 50.0, 50.0];
+        let levels = [99.0, 99.0, 0.0, 99.0];
+        let times = envelope_times(rates, levels, 1.0);
+        let full = dx7_rate_to_time(50);
+        assert!((times.to_sustain.unwrap() - dx7_rate_to_time(99) - full).abs() < 1e-3);
+        assert!((times.release.unwrap() - full).abs() < 1e-3);
+        let faster = envelope_times(rates, levels, 2.0);
+        assert!((faster.release.unwrap() - full / 2.0).abs() < 1e-3);
+
+        // A rate 0 segment that has somewhere to go holds forever.
+        let held = envelope_times([99.0, 0.0, 99.0, 99.0], [99.0, 50.0, 50.0, 0.0], 1.0);
+        assert_eq!(held.to_sustain, None);
+        assert!(held.release.is_some());
+        assert_eq!(segment_seconds(0.0, 0.0, 1.0), Some(0.0));
+    }
 }
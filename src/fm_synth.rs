@@ -1,22 +1,170 @@
+use crate::algorithm_matrix::AlgorithmMatrix;
 use crate::algorithms;
 use crate::command_queue::{
-    create_command_queue, CommandReceiver, CommandSender, EffectParam, EffectType, EnvelopeParam,
-    LfoParam, OperatorParam, PitchEgParam, SynthCommand,
+    create_command_queue, CommandReceiver, CommandSender, CompressorParam, EffectParam, EffectType,
+    EnvelopeParam, LfoParam, OperatorParam, PitchEgParam, SynthCommand,
 };
+use crate::compressor::MasterCompressor;
 use crate::dc_blocker::DcBlocker;
-use crate::effects::EffectsChain;
+use crate::debug_dump::{EngineDump, OperatorDump, VoiceDump};
+use crate::effects::{
+    EffectSlot, EffectsChain, ReverbType, VoicePan, VoiceSpread, EFFECT_SLOTS, FILTER_MAX_HZ,
+    FILTER_MIN_HZ, PHASER_MAX_FEEDBACK, REVERB_MAX_PRE_DELAY_MS,
+};
+use crate::envelope::EnvelopeStage;
+use crate::level_meter::{OperatorMeter, OutputMeter};
 use crate::lfo::{LFOWaveform, LFO};
+use crate::lock_free::ScopeBuffer;
+use crate::mod_matrix::{ModAmounts, ModMatrix, ModRoute, ModSources};
 use crate::operator::{KeyScaleCurve, Operator};
-use crate::optimization::{midi_to_hz, voice_scale};
+use crate::optimization::{midi_to_hz, voice_scale, OperatorWaveform};
+use crate::performance::Performance;
 use crate::pitch_eg::PitchEg;
 use crate::presets::Dx7Preset;
+use crate::render::NoteEvent;
+use crate::sequencer::{AutomationEvent, AutomationRecorder, PhraseRecorder};
+use crate::smoothing::Smoothed;
 use crate::state_snapshot::{
-    create_snapshot_channel, AutoPanSnapshot, ChorusSnapshot, DelaySnapshot, OperatorSnapshot,
-    PitchEgSnapshot, ReverbSnapshot, SnapshotReceiver, SnapshotSender, SynthSnapshot, VoiceMode,
+    create_snapshot_channel, AudioStats, AutoPanSnapshot, ChorusSnapshot, CompressorSnapshot,
+    DelaySnapshot, EqSnapshot, ExpressionSource, FilterSnapshot, Lfo2Snapshot, NotePriority,
+    OperatorSnapshot, PatchChangeMode, PhaserSnapshot, PitchEgSnapshot, PortamentoMode,
+    ReverbSnapshot, SameNoteMode, SnapshotReceiver, SnapshotSender, SnapshotWatcher,
+    SubBusSnapshot, SynthSnapshot, VoiceMode, VoicePanSnapshot,
 };
+use crate::tempo::{clamp_bpm, NoteDivision, DEFAULT_BPM};
+use crate::tuning::{Tuning, LIVE_TUNING};
+use crate::vintage::{dac_compand, NoiseFloor, OutputDac, VintageConfig};
 use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Voices allocated up front. `SetPolyphony` only moves the allocation
+/// limit, so changing it never allocates on the audio thread.
+pub const MAX_POLYPHONY: usize = 64;
+/// Playable voices at startup, as on the DX7.
+pub const DEFAULT_POLYPHONY: usize = 16;
+/// Voice counts offered by the GUI selector.
+pub const POLYPHONY_CHOICES: [usize; 4] = [8, 16, 32, 64];
+/// The newest notes are only stolen when every other voice is newer still.
+const PROTECTED_RECENT_NOTES: u64 = 2;
+/// Output samples kept for the oscilloscope (~90 ms at 44.1 kHz).
+pub const SCOPE_CAPACITY: usize = 4096;
+
+/// Default crossfade when a voice is stolen, in ms.
+const DEFAULT_STEAL_FADE_MS: f32 = 2.0;
+/// Default anti-click fade-in on every note-on, in ms.
+const DEFAULT_ATTACK_FADE_MS: f32 = 5.0;
+/// Upper bound for both anti-click fades, in ms.
+const MAX_FADE_MS: f32 = 50.0;
+
+/// Default time for the bend to catch up with the wheel, in ms: enough to
+/// hide the steps of a 7-bit controller without feeling late.
+const DEFAULT_BEND_SMOOTHING_MS: f32 = 5.0;
+/// Upper bound for the bend smoothing, in ms.
+pub const MAX_BEND_SMOOTHING_MS: f32 = 100.0;
+
+/// A 14-bit pitch bend (-8192..=8191) as -1..=1. Each side is scaled by
+/// its own span, so full-scale up reaches +1 and every step stays distinct.
+fn bend_from_14bit(value: i16) -> f32 {
+    let value = value.clamp(-8192, 8191) as f32;
+    if value >= 0.0 {
+        value / 8191.0
+    } else {
+        value / 8192.0
+    }
+}
+
+/// How long a voice may keep sounding with no key or pedal holding it
+/// before the stuck-note watchdog releases it.
+const STUCK_VOICE_GRACE_SECONDS: f32 = 0.5;
+/// The watchdog looks at the voices this often rather than every sample.
+const WATCHDOG_INTERVAL_SECONDS: f32 = 0.01;
+/// Longest voice timeout accepted, in seconds.
+pub const MAX_VOICE_TIMEOUT_SECONDS: f32 = 600.0;
+
+/// Corner of the output DC blocker.
+const DC_BLOCKER_CUTOFF_HZ: f32 = 5.0;
+
+/// Most operator stacks one unison voice plays.
+pub const MAX_UNISON: usize = 4;
+/// Widest unison detune, in cents either side of the note.
+pub const MAX_UNISON_DETUNE: f32 = 50.0;
+
+/// Samples between control-rate updates in `process_block` (LFO, pitch EG,
+/// controller routing, operator retuning): ~0.36 ms at 44.1 kHz.
+pub const CONTROL_BLOCK: usize = 16;
+
+/// Engine-wide modulation shared by every voice for one control block.
+#[derive(Clone, Copy, Debug, Default)]
+struct ControlSignals {
+    pitch_bend: f32,
+    pitch_bend_range: f32,
+    portamento_time: f32,
+    glissando: bool,
+    lfo_pitch_mod: f32,
+    lfo_amp_mod: f32,
+    pitch_eg_semitones: f32,
+    eg_bias_amount: f32,
+    pitch_bias_semitones: f32,
+    /// Unscaled LFO output and the channel-pressure routing, so a voice with
+    /// stronger poly pressure can add its own share on top.
+    lfo_pitch_raw: f32,
+    lfo_amp_raw: f32,
+    aftertouch: f32,
+    /// PITCH, AMP, EG-BIAS, PITCH-BIAS sensitivities (0..7).
+    aftertouch_sens: [u8; 4],
+    /// Mod matrix and its global source values; each voice evaluates it
+    /// again with its own velocity for the pitch and operator-level routes.
+    mod_matrix: ModMatrix,
+    mod_sources: ModSources,
+    /// Global PMS depth and the controller routes into the LFO depths, so
+    /// an operator with its own PMS or on LFO 2 can rebuild its share.
+    pms_scale: f32,
+    pitch_route: f32,
+    amp_route: f32,
+    /// Second LFO, unscaled; only operators switched to it follow it.
+    lfo2_enabled: bool,
+    lfo2_pitch_raw: f32,
+    lfo2_amp_raw: f32,
+    /// Expression input (0..1) for operators with `expression` set.
+    expression: f32,
+}
+
+/// Unison settings shared by every voice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Unison {
+    /// Stacks per note, 1 (off) to `MAX_UNISON`.
+    stacks: usize,
+    /// Cents between the centre and the outermost stacks.
+    detune: f32,
+    /// Stereo width of the stacks, 0..1.
+    spread: f32,
+}
+
+impl Default for Unison {
+    fn default() -> Self {
+        Self {
+            stacks: 1,
+            detune: 10.0,
+            spread: 0.5,
+        }
+    }
+}
 
-const MAX_VOICES: usize = 16;
+impl Unison {
+    /// Pitch ratio and pan position (-1 = left, +1 = right) of stack `k`
+    /// out of `stacks`, spaced evenly from the lowest-left to the
+    /// highest-right.
+    fn placement(&self, k: usize, stacks: usize) -> (f32, f32) {
+        if stacks < 2 {
+            return (1.0, 0.0);
+        }
+        let position = 2.0 * k as f32 / (stacks - 1) as f32 - 1.0;
+        (
+            2.0_f32.powf(position * self.detune / 1200.0),
+            position * self.spread,
+        )
+    }
+}
 
 #[derive(Clone)]
 pub struct Voice {
@@ -32,6 +180,39 @@ pub struct Voice {
     fade_gain: f32,
     fade_rate: f32,
     note_on_id: u64,
+    /// Polyphonic key pressure (0xA0) on this voice's key, 0..1.
+    pressure: f32,
+    /// Operators of the note being stolen, kept running while the new note
+    /// crossfades in.
+    tail: [Operator; 6],
+    /// Fade gain of the stolen note at the moment it was stolen.
+    tail_level: f32,
+    steal_fade_ms: f32,
+    attack_fade_ms: f32,
+    /// Per-voice tremolo / auto-pan, applied before the global effects.
+    pub pan: VoicePan,
+    /// Stereo slot picked by the voice spread at note-on, before the spread
+    /// width and master pan are applied.
+    spread_slot: f32,
+    /// Extra detuned copies of `operators` for unison, cloned from it at
+    /// note-on: they take the settings the note started with. Stack 0 is
+    /// `operators` itself, so only the first `stacks - 1` are used.
+    unison_ops: [[Operator; 6]; MAX_UNISON - 1],
+    /// Copies of the note being stolen, crossfaded out with `tail`.
+    unison_tail: [[Operator; 6]; MAX_UNISON - 1],
+    unison: Unison,
+    /// Stacks of the current note and of the stolen one, latched at
+    /// note-on / steal.
+    stacks: usize,
+    tail_stacks: usize,
+    /// Soloed operators (bit `i` = OP `i + 1`), heard as carriers instead
+    /// of the algorithm's own; 0 = normal output.
+    solo: u8,
+    /// Samples since the voice last got a key (note-on or mono retarget),
+    /// and samples it has sounded unreleased with no key holding it. Both
+    /// are counted by the engine's watchdog, and only while it runs.
+    age: u32,
+    unheld: u32,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -39,6 +220,9 @@ enum VoiceFadeState {
     Normal,
     FadeOut,
     FadeIn,
+    /// Equal-power crossfade from `tail` into the new note; `fade_gain`
+    /// holds the 0..1 crossfade position.
+    Crossfade,
 }
 
 impl Voice {
@@ -68,6 +252,9 @@ impl Voice {
         }
 
         Self {
+            tail: operators.clone(),
+            unison_ops: std::array::from_fn(|_| operators.clone()),
+            unison_tail: std::array::from_fn(|_| operators.clone()),
             operators,
             note: 0,
             frequency: 0.0,
@@ -80,12 +267,99 @@ impl Voice {
             fade_gain: 1.0,
             fade_rate: 0.001,
             note_on_id: 0,
+            pressure: 0.0,
+            tail_level: 0.0,
+            steal_fade_ms: DEFAULT_STEAL_FADE_MS,
+            attack_fade_ms: DEFAULT_ATTACK_FADE_MS,
+            pan: VoicePan::new(sample_rate),
+            spread_slot: 0.0,
+            unison: Unison::default(),
+            stacks: 1,
+            tail_stacks: 1,
+            solo: 0,
+            age: 0,
+            unheld: 0,
+        }
+    }
+
+    /// Every operator this voice may be running: its own, the unison
+    /// copies and both crossfade tails.
+    fn all_operators_mut(&mut self) -> impl Iterator<Item = &mut Operator> {
+        self.operators
+            .iter_mut()
+            .chain(self.tail.iter_mut())
+            .chain(self.unison_ops.iter_mut().flatten())
+            .chain(self.unison_tail.iter_mut().flatten())
+    }
+
+    /// Unison stacks for the next note (clamped to 1..=`MAX_UNISON`),
+    /// detune in cents (0..=`MAX_UNISON_DETUNE`) and stereo spread 0..1.
+    /// Detune and spread also move a sounding note.
+    pub fn set_unison(&mut self, stacks: usize, detune: f32, spread: f32) {
+        self.unison = Unison {
+            stacks: stacks.clamp(1, MAX_UNISON),
+            detune: detune.clamp(0.0, MAX_UNISON_DETUNE),
+            spread: spread.clamp(0.0, 1.0),
+        };
+    }
+
+    /// Set the anti-click fade lengths in ms: `steal` is the crossfade
+    /// between a stolen note's tail and the new attack, `attack` the fade-in
+    /// applied to every other note-on. Both clamp to 0..=`MAX_FADE_MS`.
+    pub fn set_fade_times(&mut self, steal_ms: f32, attack_ms: f32) {
+        self.steal_fade_ms = steal_ms.clamp(0.0, MAX_FADE_MS);
+        self.attack_fade_ms = attack_ms.clamp(0.0, MAX_FADE_MS);
+    }
+
+    pub fn set_vintage(&mut self, vintage: VintageConfig) {
+        for op in self.all_operators_mut() {
+            op.set_vintage(vintage);
         }
     }
 
+    /// Retune the voice for a new output sample rate without cutting it off.
+    /// A fade already in progress keeps its per-sample step until it ends.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        for op in self.all_operators_mut() {
+            op.set_sample_rate(sample_rate);
+        }
+        self.pan.set_sample_rate(sample_rate);
+    }
+
+    /// Per-sample increment that covers `ms` (at least one sample).
+    fn fade_rate_for(&self, ms: f32) -> f32 {
+        1.0 / (self.sample_rate * ms / 1000.0).max(1.0)
+    }
+
+    /// Start fading the current note out. If `trigger` follows before the
+    /// fade completes, the old note keeps sounding from a copy of its
+    /// operators and is crossfaded against the new attack.
     pub fn steal_voice(&mut self) {
+        self.tail.clone_from(&self.operators);
+        let copies = self.stacks - 1;
+        self.unison_tail[..copies].clone_from_slice(&self.unison_ops[..copies]);
+        self.tail_stacks = self.stacks;
+        self.tail_level = match self.fade_state {
+            VoiceFadeState::Normal => 1.0,
+            VoiceFadeState::Crossfade => (self.fade_gain * std::f32::consts::FRAC_PI_2).sin(),
+            VoiceFadeState::FadeIn | VoiceFadeState::FadeOut => self.fade_gain,
+        };
         self.fade_state = VoiceFadeState::FadeOut;
-        self.fade_rate = 1.0 / (self.sample_rate * 0.002);
+        self.fade_rate = self.fade_rate_for(self.steal_fade_ms);
+    }
+
+    /// Keep the current sound running from a copy of its operators and
+    /// crossfade it, at the steal rate, into whatever the operators are
+    /// changed to next: a preset switch under held notes then morphs
+    /// instead of clicking.
+    pub fn crossfade_to_new_settings(&mut self) {
+        if !self.active {
+            return;
+        }
+        self.steal_voice();
+        self.fade_state = VoiceFadeState::Crossfade;
+        self.fade_gain = 0.0;
     }
 
     pub fn trigger(&mut self, note: u8, velocity: f32, master_tune: f32, portamento_enable: bool) {
@@ -108,27 +382,91 @@ impl Voice {
         }
 
         self.velocity = velocity;
+        self.pressure = 0.0;
+        self.age = 0;
+        self.unheld = 0;
+        if self.active && self.fade_state == VoiceFadeState::FadeOut {
+            // Stolen voice: keep the steal rate and crossfade from the tail.
+            self.fade_state = VoiceFadeState::Crossfade;
+        } else {
+            self.fade_state = VoiceFadeState::FadeIn;
+            self.fade_rate = self.fade_rate_for(self.attack_fade_ms);
+        }
         self.active = true;
-        self.fade_state = VoiceFadeState::FadeIn;
         self.fade_gain = 0.0;
-        self.fade_rate = 1.0 / (self.sample_rate * 0.005);
+        self.pan.reset();
 
         for op in &mut self.operators {
             op.trigger(new_frequency, velocity, note);
         }
+
+        self.stacks = self.unison.stacks;
+        let copies = self.stacks - 1;
+        for (k, stack) in self.unison_ops[..copies].iter_mut().enumerate() {
+            stack.clone_from(&self.operators);
+            let (ratio, _) = self.unison.placement(k + 1, self.stacks);
+            for op in stack.iter_mut() {
+                op.trigger(new_frequency * ratio, velocity, note);
+            }
+        }
+    }
+
+    /// Start the pitch at `frequency` and glide from there to the note just
+    /// triggered: poly portamento from the previous note.
+    pub fn glide_from(&mut self, frequency: f32) {
+        if frequency > 0.0 && (frequency - self.target_frequency).abs() > 0.1 {
+            self.current_frequency = frequency;
+        }
     }
 
     pub fn release(&mut self) {
-        for op in &mut self.operators {
+        let copies = self.stacks - 1;
+        let stacks = self.unison_ops[..copies].iter_mut().flatten();
+        for op in self.operators.iter_mut().chain(stacks) {
             op.release();
         }
     }
 
+    /// Stop dead and clear every operator's state, stolen tail and unison
+    /// stacks included.
+    fn reset(&mut self) {
+        self.active = false;
+        let stacks = self.unison_ops.iter_mut().flatten();
+        for op in self
+            .operators
+            .iter_mut()
+            .chain(&mut self.tail)
+            .chain(stacks)
+        {
+            op.reset();
+        }
+    }
+
+    /// Every operator is in its release segment or done.
+    fn released(&self) -> bool {
+        self.operators.iter().all(|op| {
+            matches!(
+                op.envelope.stage(),
+                EnvelopeStage::Stage4 | EnvelopeStage::Idle
+            )
+        })
+    }
+
+    /// Loudest operator envelope right now; the allocator steals the
+    /// quietest released voice first.
+    fn level(&self) -> f32 {
+        self.operators
+            .iter()
+            .map(|op| op.envelope.current_output())
+            .fold(0.0, f32::max)
+    }
+
     /// Retarget the active voice to a new MIDI note without re-triggering envelopes.
     /// Used by mono-legato to glide back to a held note when the topmost note is released.
     /// Honours portamento when `portamento` is true.
     pub fn retarget(&mut self, note: u8, master_tune: f32, portamento: bool) {
         self.note = note;
+        self.age = 0;
         let base_frequency = midi_to_hz(note);
         let new_frequency = base_frequency * 2.0_f32.powf((master_tune / 100.0) / 12.0);
         self.frequency = new_frequency;
@@ -142,11 +480,14 @@ impl Voice {
 
     pub fn stop(&mut self) {
         self.active = false;
-        for op in &mut self.operators {
+        let copies = self.stacks - 1;
+        let stacks = self.unison_ops[..copies].iter_mut().flatten();
+        for op in self.operators.iter_mut().chain(stacks) {
             op.reset();
         }
     }
 
+    /// Render one sample, updating pitch and modulation first.
     #[allow(clippy::too_many_arguments)]
     pub fn process(
         &mut self,
@@ -160,57 +501,175 @@ impl Voice {
         pitch_eg_semitones: f32,
         eg_bias_amount: f32,
         pitch_bias_semitones: f32,
-    ) -> f32 {
+    ) -> (f32, f32) {
         if !self.active {
-            return 0.0;
+            return (0.0, 0.0);
         }
+        let control = ControlSignals {
+            pitch_bend,
+            pitch_bend_range,
+            portamento_time,
+            glissando,
+            lfo_pitch_mod,
+            lfo_amp_mod,
+            pitch_eg_semitones,
+            eg_bias_amount,
+            pitch_bias_semitones,
+            ..ControlSignals::default()
+        };
+        self.update_control(&control, 1);
+        let (output, _, sub) = self.render(algorithm_number, None);
+        (output, sub)
+    }
 
+    /// Control-rate half of `process`: advance portamento by `samples` and
+    /// retune the operators for the block that follows.
+    fn update_control(&mut self, control: &ControlSignals, samples: usize) {
         if self.current_frequency != self.target_frequency {
-            let portamento_rate = if portamento_time > 0.0 {
-                // Authentic DX7-style portamento: 5ms to 2.5s range
-                let time_seconds = 0.005 + (portamento_time / 99.0).powf(2.0) * 2.5;
-                let samples_for_transition = time_seconds * self.sample_rate;
-                1.0 / samples_for_transition.max(1.0)
-            } else {
-                1.0
-            };
-
-            let freq_ratio = self.target_frequency / self.current_frequency.max(0.001);
-            let log_ratio = freq_ratio.ln();
-            let step = log_ratio * portamento_rate;
-            self.current_frequency *= (1.0 + step).clamp(0.5, 2.0);
-
-            if (self.target_frequency - self.current_frequency).abs() < 0.1 {
+            // Constant speed in semitones, so a wider interval takes longer,
+            // as on the DX7.
+            let distance =
+                12.0 * (self.target_frequency / self.current_frequency.max(0.001)).log2();
+            let step = portamento_semitones_per_second(control.portamento_time) * samples as f32
+                / self.sample_rate;
+            if distance.abs() <= step {
                 self.current_frequency = self.target_frequency;
+            } else {
+                self.current_frequency *= 2.0_f32.powf(step.copysign(distance) / 12.0);
             }
         }
 
         // Glissando quantises the live pitch to the nearest semitone, producing
         // a stepped glide instead of a continuous one.
-        let played_frequency = if glissando {
+        let played_frequency = if control.glissando {
             quantize_to_semitone(self.current_frequency)
         } else {
             self.current_frequency
         };
 
-        let bend_semitones = pitch_bend * pitch_bend_range;
+        // Channel and poly pressure feed the same routes; each voice follows
+        // whichever is stronger, so only the excess over channel pressure is added.
+        let extra_pressure = (self.pressure - control.aftertouch).max(0.0);
+        let [pitch_sens, amp_sens, eg_bias_sens, pitch_bias_sens] = control.aftertouch_sens;
+        let pitch_route = control.pitch_route + route_amount(extra_pressure, pitch_sens);
+        let amp_route = control.amp_route + route_amount(extra_pressure, amp_sens);
+        let lfo_pitch_mod = control.lfo_pitch_mod
+            + control.lfo_pitch_raw * route_amount(extra_pressure, pitch_sens);
+        let lfo_amp_mod =
+            control.lfo_amp_mod + control.lfo_amp_raw * route_amount(extra_pressure, amp_sens);
+        let eg_bias_amount = control.eg_bias_amount + route_amount(extra_pressure, eg_bias_sens);
+        let pitch_bias_semitones =
+            control.pitch_bias_semitones + route_amount(extra_pressure, pitch_bias_sens) * 2.0;
+
+        let matrix = if control.mod_matrix.is_empty() {
+            ModAmounts::default()
+        } else {
+            control.mod_matrix.evaluate(&ModSources {
+                velocity: self.velocity,
+                ..control.mod_sources
+            })
+        };
+
+        let bend_semitones = control.pitch_bend * control.pitch_bend_range;
         let bent_frequency = played_frequency * 2.0_f32.powf(bend_semitones / 12.0);
         let lfo_pitch_semitones = lfo_pitch_mod * 0.5;
         // Pitch Bias is the static, mod-wheel-driven counterpart of LFO pitch mod —
         // a constant offset rather than an oscillation. Sums into the same destination.
-        let total_pitch_offset = lfo_pitch_semitones + pitch_eg_semitones + pitch_bias_semitones;
+        let total_pitch_offset =
+            lfo_pitch_semitones + control.pitch_eg_semitones + pitch_bias_semitones + matrix.pitch;
         let final_frequency = bent_frequency * 2.0_f32.powf(total_pitch_offset / 12.0);
 
-        for op in &mut self.operators {
-            op.update_frequency_only(final_frequency);
-            op.set_lfo_amp_mod(lfo_amp_mod);
-            op.set_eg_bias(eg_bias_amount);
+        let mut ratios = [1.0; MAX_UNISON];
+        for (k, ratio) in ratios.iter_mut().enumerate().take(self.stacks) {
+            *ratio = self.unison.placement(k, self.stacks).0;
+        }
+        let copies = self.stacks - 1;
+
+        for (i, op) in self.operators.iter_mut().enumerate() {
+            // Operators with their own PMS or on LFO 2 get their LFO share
+            // rebuilt; the rest share the voice's.
+            let (op_lfo_pitch, op_lfo_amp) = if control.lfo2_enabled && op.lfo2 {
+                let pms = op.pm_sensitivity.map_or(control.pms_scale, pms_scale);
+                (
+                    control.lfo2_pitch_raw * (pms + pitch_route),
+                    control.lfo2_amp_raw * (1.0 + amp_route),
+                )
+            } else if let Some(pms) = op.pm_sensitivity {
+                (
+                    lfo_pitch_mod + control.lfo_pitch_raw * (pms_scale(pms) - control.pms_scale),
+                    lfo_amp_mod,
+                )
+            } else {
+                (lfo_pitch_mod, lfo_amp_mod)
+            };
+            let frequency = if op_lfo_pitch == lfo_pitch_mod {
+                final_frequency
+            } else {
+                let offset = total_pitch_offset - lfo_pitch_semitones + op_lfo_pitch * 0.5;
+                bent_frequency * 2.0_f32.powf(offset / 12.0)
+            };
+            let unison_ops = self.unison_ops[..copies]
+                .iter_mut()
+                .map(|stack| &mut stack[i]);
+            for (op, ratio) in std::iter::once(op).chain(unison_ops).zip(ratios) {
+                op.update_frequency_only(frequency * ratio);
+                op.set_lfo_amp_mod(op_lfo_amp);
+                op.set_eg_bias(eg_bias_amount);
+                op.set_expression(control.expression);
+                op.set_level_mod(matrix.operator_gain(i));
+            }
         }
+    }
 
-        let output = algorithms::process_algorithm(algorithm_number, &mut self.operators);
+    /// Audio-rate half of `process`: run the algorithm (or the `custom`
+    /// routing when set) and the anti-click fades for one sample at the
+    /// current control values.
+    ///
+    /// Returns (mid, side, sub): unison stacks are panned inside the voice,
+    /// so it has a stereo side part of its own.
+    fn render(
+        &mut self,
+        algorithm_number: u8,
+        custom: Option<&AlgorithmMatrix>,
+    ) -> (f32, f32, f32) {
+        let solo = self.solo;
+        let route = |ops: &mut [Operator; 6]| {
+            let buses = match custom {
+                Some(matrix) => matrix.process_split(ops),
+                None => algorithms::process_algorithm_split(algorithm_number, ops),
+            };
+            if solo == 0 {
+                buses
+            } else {
+                (algorithms::solo_output(solo, ops), 0.0)
+            }
+        };
+        let unison = self.unison;
+        // Sum `stacks` stacks, the first being `main`, at 1/√n so unison
+        // does not change the loudness much.
+        let mix = |main: &mut [Operator; 6], copies: &mut [[Operator; 6]], stacks: usize| {
+            let (mut mid, mut sub) = route(main);
+            if stacks < 2 {
+                return (mid, 0.0, sub);
+            }
+            // Side is positive to the left, as in `VoicePan`.
+            let mut side = -mid * unison.placement(0, stacks).1;
+            for (k, stack) in copies[..stacks - 1].iter_mut().enumerate() {
+                let (out, stack_sub) = route(stack);
+                mid += out;
+                side -= out * unison.placement(k + 1, stacks).1;
+                sub += stack_sub;
+            }
+            let gain = (stacks as f32).sqrt().recip();
+            (mid * gain, side * gain, sub * gain)
+        };
+        let (output, side, sub) = mix(&mut self.operators, &mut self.unison_ops[..], self.stacks);
 
         let all_inactive = self.operators.iter().all(|op| !op.is_active());
-        if all_inactive && self.fade_state != VoiceFadeState::FadeOut {
+        if all_inactive
+            && self.fade_state != VoiceFadeState::FadeOut
+            && self.fade_state != VoiceFadeState::Crossfade
+        {
             self.active = false;
         }
 
@@ -221,7 +680,11 @@ impl Voice {
                     self.fade_gain = 1.0;
                     self.fade_state = VoiceFadeState::Normal;
                 }
-                output * self.fade_gain
+                (
+                    output * self.fade_gain,
+                    side * self.fade_gain,
+                    sub * self.fade_gain,
+                )
             }
             VoiceFadeState::FadeOut => {
                 self.fade_gain -= self.fade_rate;
@@ -229,9 +692,81 @@ impl Voice {
                     self.fade_gain = 0.0;
                     self.active = false;
                 }
-                output * self.fade_gain
+                (
+                    output * self.fade_gain,
+                    side * self.fade_gain,
+                    sub * self.fade_gain,
+                )
             }
-            VoiceFadeState::Normal => output,
+            VoiceFadeState::Crossfade => {
+                let (tail, tail_side, tail_sub) =
+                    mix(&mut self.tail, &mut self.unison_tail[..], self.tail_stacks);
+                self.fade_gain = (self.fade_gain + self.fade_rate).min(1.0);
+                let angle = self.fade_gain * std::f32::consts::FRAC_PI_2;
+                let (new_gain, tail_gain) = (angle.sin(), self.tail_level * angle.cos());
+                if self.fade_gain >= 1.0 {
+                    self.fade_state = VoiceFadeState::Normal;
+                }
+                (
+                    output * new_gain + tail * tail_gain,
+                    side * new_gain + tail_side * tail_gain,
+                    sub * new_gain + tail_sub * tail_gain,
+                )
+            }
+            VoiceFadeState::Normal => (output, side, sub),
+        }
+    }
+}
+
+/// Commands part B of a performance gets as well: note-offs (a key may
+/// have been routed to either part), performance controllers and the master
+/// volume and tempo. None of them carry heap data, so the clone is free.
+fn shared_with_part_b(cmd: &SynthCommand) -> bool {
+    matches!(
+        cmd,
+        SynthCommand::NoteOff { .. }
+            | SynthCommand::PitchBend(_)
+            | SynthCommand::SetPitchBendSmoothing(_)
+            | SynthCommand::ModWheel(_)
+            | SynthCommand::SustainPedal(_)
+            | SynthCommand::Aftertouch(_)
+            | SynthCommand::PolyAftertouch { .. }
+            | SynthCommand::BreathController(_)
+            | SynthCommand::FootController(_)
+            | SynthCommand::Expression(_)
+            | SynthCommand::SetExpressionSource(_)
+            | SynthCommand::SetMasterVolume(_)
+            | SynthCommand::SetTempo(_)
+            | SynthCommand::SetVoiceWatchdog(_)
+            | SynthCommand::SetVoiceTimeout(_)
+            | SynthCommand::SetPatchChangeMode(_)
+            | SynthCommand::AllNotesOff
+            | SynthCommand::ResetControllers
+            | SynthCommand::Panic
+    )
+}
+
+/// Apply an `LfoParam` to either LFO.
+fn apply_lfo_param(lfo: &mut LFO, param: LfoParam, value: f32) {
+    match param {
+        LfoParam::Rate => lfo.set_rate(value),
+        LfoParam::Delay => lfo.set_delay(value),
+        LfoParam::PitchDepth => lfo.set_pitch_depth(value),
+        LfoParam::AmpDepth => lfo.set_amp_depth(value),
+        LfoParam::Waveform(w) => {
+            let waveform = match w {
+                0 => LFOWaveform::Triangle,
+                1 => LFOWaveform::SawDown,
+                2 => LFOWaveform::SawUp,
+                3 => LFOWaveform::Square,
+                4 => LFOWaveform::Sine,
+                _ => LFOWaveform::SampleHold,
+            };
+            lfo.set_waveform(waveform);
+        }
+        LfoParam::KeySync => lfo.set_key_sync(value > 0.5),
+        LfoParam::SyncDivision => {
+            lfo.set_sync((value >= 0.0).then(|| NoteDivision::from_code(value as u8)))
         }
     }
 }
@@ -244,6 +779,36 @@ fn route_amount(value: f32, sens: u8) -> f32 {
     value * (sens.min(7) as f32 / 7.0)
 }
 
+/// PMS (Pitch Mod Sensitivity) ROM lookup. Source: `pitchmodsenstab[8]`
+/// in MSFA / Dexed `dx7note.cc` = {0, 10, 20, 33, 55, 92, 153, 255},
+/// normalised by 255 then scaled to our depth domain. The `* 2.0`
+/// factor preserves the previous max swing of ~2 semitones at PMS=7
+/// (downstream code applies `* 0.5` to convert back to semitones).
+const PMS_TABLE: [f32; 8] = [
+    0.0,
+    (10.0 / 255.0) * 2.0,  // 0.0784
+    (20.0 / 255.0) * 2.0,  // 0.1569
+    (33.0 / 255.0) * 2.0,  // 0.2588
+    (55.0 / 255.0) * 2.0,  // 0.4314
+    (92.0 / 255.0) * 2.0,  // 0.7216
+    (153.0 / 255.0) * 2.0, // 1.2000
+    2.0,
+];
+
+/// LFO pitch depth for a 0..7 PMS setting, clamped like `route_amount`.
+#[inline]
+fn pms_scale(pms: u8) -> f32 {
+    PMS_TABLE[pms.min(7) as usize]
+}
+
+/// Portamento speed in semitones per second for a PORTAMENTO TIME of 0-99.
+/// The DX7's curve as measured for msfa (the engine in Dexed): exponential,
+/// from 350 st/s at 0 (an octave in ~34 ms) down to ~5 st/s at 99 (an
+/// octave in ~2.4 s), halving every 16 steps.
+pub fn portamento_semitones_per_second(time: f32) -> f32 {
+    350.0 * 2.0_f32.powf(-0.062 * time.clamp(0.0, 99.0))
+}
+
 /// Round a frequency to the nearest equal-tempered semitone (relative to A4 = 440 Hz).
 fn quantize_to_semitone(freq: f32) -> f32 {
     if freq <= 0.0 {
@@ -254,28 +819,92 @@ fn quantize_to_semitone(freq: f32) -> f32 {
     440.0 * 2.0_f32.powf(rounded / 12.0)
 }
 
+/// Effect settings the engine boots with, and INIT VOICE returns to.
+///
+/// The DX7 itself shipped without on-board effects, but its iconic sound on
+/// every record from 1983-89 came through external chorus + reverb. Boot
+/// with both ON at modest mix levels so factory presets sound like the user
+/// remembers them; users wanting the bone-dry signal can flip them off.
+/// AutoPan is also ON by default at moderate depth: countless DX7 patches
+/// (Rhodes, Clavinet, e-pianos, soft pads) were tracked through a Suitcase
+/// amp or Leslie-style pan, and patches like `mark/rhodes.json` that omit
+/// LFO amp modulation rely on this post-FM movement to sound alive.
+/// Delay stays OFF on purpose — it's a creative effect, not part of the
+/// canonical "DX7 → outboard" flavour.
+fn boot_effects(effects: &mut EffectsChain) {
+    effects.chorus.enabled = true;
+    effects.chorus.mix = 0.15;
+    effects.auto_pan.enabled = true;
+    effects.auto_pan.rate_hz = 5.0;
+    effects.auto_pan.depth = 0.35;
+    effects.reverb.enabled = true;
+    effects.reverb.mix = 0.22;
+}
+
 /// SynthEngine - runs on the audio thread, processes commands and generates audio
 pub struct SynthEngine {
     voices: Vec<Voice>,
     held_notes: HashMap<u8, usize>,
+    /// (key, voice) for the earlier voices of a stacked key; `held_notes`
+    /// keeps only the newest. Released together with it on note-off.
+    stacked_voices: Vec<(u8, usize)>,
+    same_note_mode: SameNoteMode,
+    patch_change_mode: PatchChangeMode,
     /// Order in which currently-held notes were pressed (front = oldest, back = newest).
     /// Used by mono modes to fall back to the previous held note when the active one is released.
     mono_held_order: Vec<u8>,
+    note_priority: NotePriority,
+    /// Assignable routes on top of the fixed DX7S controller assignments.
+    mod_matrix: ModMatrix,
+    /// Velocity of the latest note-on (0..1): the VELOCITY source for the
+    /// matrix's global destinations (LFO, effects).
+    last_velocity: f32,
+    /// Key of the latest note-on, for the operator frequency read-out.
+    last_note: Option<u8>,
+    /// Voice of the latest note-on: where poly portamento glides from.
+    last_voice: Option<usize>,
     pub preset_name: String,
     lfo: LFO,
+    /// Optional second LFO for operators switched to it (`Operator::lfo2`).
+    lfo2: LFO,
+    lfo2_enabled: bool,
+    /// Tempo for everything that can sync to note divisions.
+    tempo_bpm: f32,
     pub pitch_eg: PitchEg,
     pub effects: EffectsChain,
+    /// Stereo placement of new notes, scaled by `voice_spread_width` and
+    /// shifted by `master_pan`.
+    voice_spread: VoiceSpread,
+    voice_spread_width: f32,
+    master_pan: f32,
+    /// Copied into every voice; see `Voice::set_unison`.
+    unison: Unison,
     command_rx: CommandReceiver,
     snapshot_tx: SnapshotSender,
+    /// Recent output samples for the GUI oscilloscope.
+    scope: Arc<ScopeBuffer>,
     note_counter: u64,
     // Cached parameters for real-time access
     algorithm: u8,
+    /// User routing run instead of `algorithm` while `custom_algorithm` is on.
+    algorithm_matrix: AlgorithmMatrix,
+    custom_algorithm: bool,
     master_volume: f32,
+    /// `master_volume` as applied to the output, ramped so that volume moves
+    /// do not zipper.
+    master_gain: Smoothed,
+    /// Wheel position (-1..1) and the bend actually applied, which glides
+    /// to it over `bend_smoothing_ms`.
     pitch_bend: f32,
+    smoothed_bend: f32,
+    bend_smoothing_ms: f32,
     mod_wheel: f32,
     master_tune: f32,
+    /// Per-key offsets on top of `master_tune`.
+    tuning: Tuning,
     pitch_bend_range: f32,
     portamento_enable: bool,
+    portamento_mode: PortamentoMode,
     portamento_time: f32,
     portamento_glissando: bool,
     voice_mode: VoiceMode,
@@ -301,66 +930,146 @@ pub struct SynthEngine {
     foot_pitch_sens: u8,
     foot_amp_sens: u8,
     foot_eg_bias_sens: u8,
-    /// MIDI Expression (CC11): generic 0..1 attenuator multiplied into the master output.
+    /// MIDI Expression (CC11): generic 0..1 attenuator multiplied into the
+    /// master output, unless it drives the per-operator expression input.
     expression: f32,
+    /// Controller that scales operators flagged for expression.
+    expression_source: ExpressionSource,
     /// MIDI Bank Select MSB (CC0) — top 7 bits of the bank index.
     bank_msb: u8,
     /// MIDI Bank Select LSB (CC32) — low 7 bits of the bank index.
     bank_lsb: u8,
     sustain_pedal: bool,
-    #[allow(dead_code)]
+    /// Keys lifted while the pedal was down (bit = MIDI note), released when
+    /// it comes back up.
+    sustained_notes: u128,
+    /// Anti-click fade lengths in ms, mirrored into every voice.
+    steal_fade_ms: f32,
+    attack_fade_ms: f32,
+    /// Secondary output bus fed by operators tagged `sub_bus`: its own level
+    /// plus sends into the delay and reverb stages (0..1 each).
+    sub_bus_level: f32,
+    sub_bus_delay_send: f32,
+    sub_bus_reverb_send: f32,
+    /// Hardware quirks mirrored into every operator, envelope and the LFO;
+    /// the DAC flag is applied here at the output.
+    vintage: VintageConfig,
+    /// DX7 converter and output filter, last on the master bus.
+    output_dac: OutputDac,
+    /// Hiss and hum added to the mix ahead of the effects.
+    noise_floor: NoiseFloor,
+    /// How many of the preallocated voices Poly mode may allocate notes to.
+    polyphony: usize,
     sample_rate: f32,
     dc_blocker_l: DcBlocker,
     dc_blocker_r: DcBlocker,
+    /// Master bus dynamics, ahead of the final safety `tanh`.
+    pub compressor: MasterCompressor,
+    /// Peak / RMS / clip metering of what reaches the output saturator.
+    output_meter: OutputMeter,
+    /// Peak output of each operator across the sounding voices.
+    operator_meter: OperatorMeter,
+    /// Soloed operators (bit `i` = OP `i + 1`), pushed to every voice.
+    operator_solo: u8,
+    /// Callback statistics handed in by the audio backend for the snapshot.
+    audio_stats: AudioStats,
     // Preset storage for MIDI program change
     presets: Vec<Dx7Preset>,
     current_preset_index: usize,
+    /// Preset each Program Change number loads in place of the bank
+    /// position, indexed by program; empty when nothing is mapped.
+    program_map: Vec<Option<Dx7Preset>>,
+    /// Dual / split setup. This engine plays part A.
+    performance: Performance,
+    /// Engine playing part B, allocated up front so switching modes never
+    /// allocates on the audio thread. Only its voices and control-rate
+    /// modulation run; they mix into this engine's effects and master stage.
+    /// None inside part B itself.
+    part_b: Option<Box<SynthEngine>>,
+    /// Part B renders this control block: in use, or still ringing out.
+    part_b_running: bool,
+    /// This engine's part volume (under `master_volume`) and key shift (on
+    /// top of `transpose_semitones`) in the performance.
+    part_volume: f32,
+    part_transpose: i8,
+    /// Attach `debug_dump()` to the next snapshot, once.
+    debug_dump_requested: bool,
+    /// Stuck-note watchdog: release voices no key or pedal has held for
+    /// `STUCK_VOICE_GRACE_SECONDS`, and, when `voice_timeout` (seconds) is
+    /// non-zero, any voice that long past its last note-on.
+    voice_watchdog: bool,
+    voice_timeout: f32,
+    /// Samples rendered since the watchdog last ran.
+    watchdog_elapsed: usize,
+    /// Voices the watchdog has released so far.
+    watchdog_releases: u32,
 }
 
 impl SynthEngine {
     pub fn new(sample_rate: f32, command_rx: CommandReceiver, snapshot_tx: SnapshotSender) -> Self {
-        let mut voices = Vec::with_capacity(MAX_VOICES);
-        for _ in 0..MAX_VOICES {
+        let (_, part_b_commands) = create_command_queue();
+        let (part_b_snapshots, _) = create_snapshot_channel();
+        let mut engine = Self::new_part(sample_rate, command_rx, snapshot_tx);
+        engine.part_b = Some(Box::new(Self::new_part(
+            sample_rate,
+            part_b_commands,
+            part_b_snapshots,
+        )));
+        engine
+    }
+
+    /// One engine without a part B, booted into INIT VOICE.
+    fn new_part(
+        sample_rate: f32,
+        command_rx: CommandReceiver,
+        snapshot_tx: SnapshotSender,
+    ) -> Self {
+        let mut voices = Vec::with_capacity(MAX_POLYPHONY);
+        for _ in 0..MAX_POLYPHONY {
             voices.push(Voice::new_with_sample_rate(sample_rate));
         }
 
-        // The DX7 itself shipped without on-board effects, but its iconic sound on
-        // every record from 1983-89 came through external chorus + reverb. Boot
-        // with both ON at modest mix levels so factory presets sound like the user
-        // remembers them; users wanting the bone-dry signal can flip them off.
-        // AutoPan is also ON by default at moderate depth: countless DX7 patches
-        // (Rhodes, Clavinet, e-pianos, soft pads) were tracked through a Suitcase
-        // amp or Leslie-style pan, and patches like `mark/rhodes.json` that omit
-        // LFO amp modulation rely on this post-FM movement to sound alive.
-        // Delay stays OFF on purpose — it's a creative effect, not part of the
-        // canonical "DX7 → outboard" flavour.
-        let mut effects = EffectsChain::new(sample_rate);
-        effects.chorus.enabled = true;
-        effects.chorus.mix = 0.15;
-        effects.auto_pan.enabled = true;
-        effects.auto_pan.rate_hz = 5.0;
-        effects.auto_pan.depth = 0.35;
-        effects.reverb.enabled = true;
-        effects.reverb.mix = 0.22;
-
-        Self {
+        let mut engine = Self {
             voices,
             held_notes: HashMap::new(),
+            stacked_voices: Vec::with_capacity(MAX_POLYPHONY),
+            same_note_mode: SameNoteMode::Retrigger,
+            patch_change_mode: PatchChangeMode::Crossfade,
             mono_held_order: Vec::with_capacity(8),
+            note_priority: NotePriority::Last,
+            mod_matrix: ModMatrix::default(),
+            last_velocity: 0.0,
+            last_note: None,
+            last_voice: None,
             preset_name: "Init Voice".to_string(),
             lfo: LFO::new(sample_rate),
+            lfo2: LFO::new(sample_rate),
+            lfo2_enabled: false,
+            tempo_bpm: DEFAULT_BPM,
             pitch_eg: PitchEg::new(sample_rate),
-            effects,
+            effects: EffectsChain::new(sample_rate),
+            voice_spread: VoiceSpread::Center,
+            voice_spread_width: 1.0,
+            master_pan: 0.0,
+            unison: Unison::default(),
             command_rx,
             snapshot_tx,
+            scope: Arc::new(ScopeBuffer::new(SCOPE_CAPACITY)),
             note_counter: 0,
             algorithm: 1,
+            algorithm_matrix: AlgorithmMatrix::default(),
+            custom_algorithm: false,
             master_volume: 0.7,
+            master_gain: Smoothed::new(0.7, sample_rate),
             pitch_bend: 0.0,
+            smoothed_bend: 0.0,
+            bend_smoothing_ms: DEFAULT_BEND_SMOOTHING_MS,
             mod_wheel: 0.0,
             master_tune: 0.0,
+            tuning: Tuning::default(),
             pitch_bend_range: 2.0,
             portamento_enable: false,
+            portamento_mode: PortamentoMode::FullTime,
             portamento_time: 50.0,
             portamento_glissando: false,
             voice_mode: VoiceMode::Poly,
@@ -384,14 +1093,109 @@ impl SynthEngine {
             foot_amp_sens: 0,
             foot_eg_bias_sens: 0,
             expression: 1.0,
+            expression_source: ExpressionSource::Breath,
             bank_msb: 0,
             bank_lsb: 0,
             sustain_pedal: false,
+            sustained_notes: 0,
+            steal_fade_ms: DEFAULT_STEAL_FADE_MS,
+            attack_fade_ms: DEFAULT_ATTACK_FADE_MS,
+            sub_bus_level: 1.0,
+            sub_bus_delay_send: 0.0,
+            sub_bus_reverb_send: 0.0,
+            vintage: VintageConfig::CLEAN,
+            output_dac: OutputDac::new(sample_rate),
+            noise_floor: NoiseFloor::new(sample_rate),
+            polyphony: DEFAULT_POLYPHONY,
             sample_rate,
-            dc_blocker_l: DcBlocker::new(sample_rate, 5.0),
-            dc_blocker_r: DcBlocker::new(sample_rate, 5.0),
+            dc_blocker_l: DcBlocker::new(sample_rate, DC_BLOCKER_CUTOFF_HZ),
+            dc_blocker_r: DcBlocker::new(sample_rate, DC_BLOCKER_CUTOFF_HZ),
+            compressor: MasterCompressor::new(sample_rate),
+            output_meter: OutputMeter::new(sample_rate),
+            operator_meter: OperatorMeter::new(sample_rate),
+            operator_solo: 0,
+            audio_stats: AudioStats::default(),
             presets: Vec::new(),
             current_preset_index: 0,
+            program_map: Vec::new(),
+            performance: Performance::default(),
+            part_b: None,
+            part_b_running: false,
+            part_volume: 1.0,
+            part_transpose: 0,
+            debug_dump_requested: false,
+            voice_watchdog: false,
+            voice_timeout: 0.0,
+            watchdog_elapsed: 0,
+            watchdog_releases: 0,
+        };
+        // Boot and the INIT button share one definition of the init voice.
+        engine.voice_initialize();
+        engine
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    pub fn polyphony(&self) -> usize {
+        self.polyphony
+    }
+
+    /// Set how many voices notes may be allocated to (1..=`MAX_POLYPHONY`).
+    /// Shrinking releases any voice above the new limit.
+    pub fn set_polyphony(&mut self, voices: usize) {
+        let limit = voices.clamp(1, MAX_POLYPHONY);
+        self.polyphony = limit;
+        for voice in &mut self.voices[limit..] {
+            if voice.active {
+                voice.release();
+            }
+        }
+        self.held_notes.retain(|_, &mut idx| idx < limit);
+        self.stacked_voices.retain(|&(_, idx)| idx < limit);
+    }
+
+    /// Seed the random draws (LFO S&H, vintage rate jitter and the noise
+    /// floor) of both parts, so the same notes render the same samples
+    /// every run.
+    pub fn seed_random(&mut self, seed: u32) {
+        self.lfo.seed(seed);
+        self.lfo2.seed(seed.rotate_left(16));
+        self.noise_floor.seed(seed.rotate_left(8));
+        if let Some(part_b) = self.part_b.as_deref_mut() {
+            part_b.seed_random(!seed);
+        }
+    }
+
+    /// Retune the whole engine for a new output sample rate: operator phase
+    /// increments, envelopes, LFOs and effect buffers. Sounding notes carry
+    /// on at the same pitch. Effect lines are reallocated, so call this from
+    /// the control side while the audio stream is stopped.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        if sample_rate == self.sample_rate {
+            return;
+        }
+        self.sample_rate = sample_rate;
+        for voice in &mut self.voices {
+            voice.set_sample_rate(sample_rate);
+        }
+        self.lfo.set_sample_rate(sample_rate);
+        self.lfo2.set_sample_rate(sample_rate);
+        self.pitch_eg.set_sample_rate(sample_rate);
+        self.master_gain.set_sample_rate(sample_rate);
+        self.effects.set_sample_rate(sample_rate);
+        self.dc_blocker_l
+            .set_sample_rate(sample_rate, DC_BLOCKER_CUTOFF_HZ);
+        self.dc_blocker_r
+            .set_sample_rate(sample_rate, DC_BLOCKER_CUTOFF_HZ);
+        self.compressor.set_sample_rate(sample_rate);
+        self.output_dac.set_sample_rate(sample_rate);
+        self.noise_floor.set_sample_rate(sample_rate);
+        self.output_meter.set_sample_rate(sample_rate);
+        self.operator_meter.set_sample_rate(sample_rate);
+        if let Some(part_b) = self.part_b.as_deref_mut() {
+            part_b.set_sample_rate(sample_rate);
         }
     }
 
@@ -403,12 +1207,37 @@ impl SynthEngine {
     }
 
     fn handle_command(&mut self, cmd: SynthCommand) {
+        if let Some(part_b) = self.part_b.as_deref_mut() {
+            if shared_with_part_b(&cmd) {
+                part_b.handle_command(cmd.clone());
+            }
+        }
         match cmd {
-            SynthCommand::NoteOn { note, velocity } => self.note_on(note, velocity),
+            SynthCommand::NoteOn { note, velocity } => {
+                let (part_a, part_b) = self.performance.route(note);
+                if part_a {
+                    self.note_on(note, velocity);
+                }
+                if let Some(engine) = self.part_b.as_deref_mut().filter(|_| part_b) {
+                    engine.note_on(note, velocity);
+                }
+            }
             SynthCommand::NoteOff { note } => self.note_off(note),
             SynthCommand::SetAlgorithm(alg) => {
-                if (1..=32).contains(&alg) {
-                    self.algorithm = alg;
+                if (1..=32).contains(&alg) && (alg != self.algorithm || self.custom_algorithm) {
+                    self.change_patch(|engine| engine.set_algorithm(alg));
+                }
+            }
+            SynthCommand::SetCustomAlgorithm(on) => {
+                if on != self.custom_algorithm {
+                    self.change_patch(|engine| engine.custom_algorithm = on);
+                }
+            }
+            SynthCommand::SetAlgorithmMatrix(matrix) => {
+                if self.custom_algorithm {
+                    self.change_patch(|engine| engine.algorithm_matrix = matrix);
+                } else {
+                    self.algorithm_matrix = matrix;
                 }
             }
             SynthCommand::SetMasterVolume(vol) => {
@@ -417,6 +1246,8 @@ impl SynthEngine {
             SynthCommand::SetMasterTune(cents) => {
                 self.master_tune = cents.clamp(-150.0, 150.0);
             }
+            SynthCommand::SetTuning(tuning) => self.tuning = *tuning,
+            SynthCommand::RetuneNotes(changes) => self.retune_notes(&changes),
             SynthCommand::SetVoiceMode(mode) => {
                 let new_mode = match mode {
                     1 => VoiceMode::Mono,
@@ -437,24 +1268,159 @@ impl SynthEngine {
                         }
                     }
                     self.held_notes.clear();
+                    self.stacked_voices.clear();
                     self.mono_held_order.clear();
                 }
             }
+            SynthCommand::SetNotePriority(priority) => {
+                self.note_priority = match priority {
+                    1 => NotePriority::Low,
+                    2 => NotePriority::High,
+                    _ => NotePriority::Last,
+                };
+            }
+            SynthCommand::SetSameNoteMode(mode) => {
+                self.same_note_mode = match mode {
+                    1 => SameNoteMode::Stack,
+                    _ => SameNoteMode::Retrigger,
+                };
+            }
+            SynthCommand::SetPatchChangeMode(mode) => {
+                self.patch_change_mode = match mode {
+                    1 => PatchChangeMode::Retrigger,
+                    _ => PatchChangeMode::Crossfade,
+                };
+            }
+            SynthCommand::SetPitchBendSmoothing(ms) => {
+                self.bend_smoothing_ms = ms.clamp(0.0, MAX_BEND_SMOOTHING_MS);
+            }
             SynthCommand::SetPitchBendRange(range) => {
                 self.pitch_bend_range = range.clamp(0.0, 12.0);
             }
             SynthCommand::SetPortamentoEnable(enable) => {
                 self.portamento_enable = enable;
             }
+            SynthCommand::SetPortamentoMode(mode) => {
+                self.portamento_mode = match mode {
+                    1 => PortamentoMode::Fingered,
+                    _ => PortamentoMode::FullTime,
+                };
+            }
             SynthCommand::SetPortamentoTime(time) => {
                 self.portamento_time = time.clamp(0.0, 99.0);
             }
             SynthCommand::SetPortamentoGlissando(on) => {
                 self.portamento_glissando = on;
             }
+            SynthCommand::SetOscKeySync(on) => {
+                for voice in &mut self.voices {
+                    for op in &mut voice.operators {
+                        op.oscillator_key_sync = on;
+                    }
+                }
+            }
             SynthCommand::SetTranspose(st) => {
                 self.transpose_semitones = st.clamp(-24, 24);
             }
+            SynthCommand::SetStealFadeTime(ms) => {
+                self.steal_fade_ms = ms.clamp(0.0, MAX_FADE_MS);
+                self.apply_fade_times();
+            }
+            SynthCommand::SetAttackFadeTime(ms) => {
+                self.attack_fade_ms = ms.clamp(0.0, MAX_FADE_MS);
+                self.apply_fade_times();
+            }
+            SynthCommand::SetVoiceWatchdog(on) => {
+                self.voice_watchdog = on;
+            }
+            SynthCommand::SetVoiceTimeout(seconds) => {
+                self.voice_timeout = if seconds.is_finite() {
+                    seconds.clamp(0.0, MAX_VOICE_TIMEOUT_SECONDS)
+                } else {
+                    0.0
+                };
+            }
+            SynthCommand::SetVoicePanRate(hz) => {
+                for voice in &mut self.voices {
+                    voice.pan.rate_hz = hz.clamp(0.1, 10.0);
+                }
+            }
+            SynthCommand::SetVoicePanDepth(depth) => {
+                for voice in &mut self.voices {
+                    voice.pan.depth = depth.clamp(0.0, 1.0);
+                }
+            }
+            SynthCommand::SetVoicePanStereoPhase(phase) => {
+                for voice in &mut self.voices {
+                    voice.pan.stereo_phase = phase.clamp(0.0, 1.0);
+                }
+            }
+            SynthCommand::SetVoiceSpread(code) => {
+                // Applies from the next note; sounding notes keep their place.
+                self.voice_spread = VoiceSpread::from_code(code);
+            }
+            SynthCommand::SetVoiceSpreadWidth(width) => {
+                self.voice_spread_width = width.clamp(0.0, 1.0);
+                self.place_voices();
+            }
+            SynthCommand::SetMasterPan(pan) => {
+                self.master_pan = pan.clamp(-1.0, 1.0);
+                self.place_voices();
+            }
+            SynthCommand::SetUnisonVoices(stacks) => {
+                self.unison.stacks = (stacks as usize).clamp(1, MAX_UNISON);
+                self.apply_unison();
+            }
+            SynthCommand::SetUnisonDetune(cents) => {
+                self.unison.detune = cents.clamp(0.0, MAX_UNISON_DETUNE);
+                self.apply_unison();
+            }
+            SynthCommand::SetUnisonSpread(spread) => {
+                self.unison.spread = spread.clamp(0.0, 1.0);
+                self.apply_unison();
+            }
+            SynthCommand::SetVintageMode(on) => {
+                self.vintage = VintageConfig::from_enabled(on);
+                self.apply_vintage();
+            }
+            SynthCommand::SetOutputDac(on) => {
+                if on && !self.output_dac.enabled {
+                    self.output_dac.reset();
+                }
+                self.output_dac.enabled = on;
+            }
+            SynthCommand::SetOutputDacIntensity(intensity) => {
+                self.output_dac.set_intensity(intensity);
+            }
+            SynthCommand::SetNoiseFloor(on) => self.noise_floor.enabled = on,
+            SynthCommand::SetNoiseLevel(db) => self.noise_floor.set_level_db(db),
+            SynthCommand::SetHumFrequency(hz) => self.noise_floor.set_hum_hz(hz),
+            SynthCommand::SetCompressorParam { param, value } => {
+                let c = &mut self.compressor;
+                match param {
+                    CompressorParam::Enabled => c.enabled = value > 0.5,
+                    CompressorParam::Threshold => c.set_threshold_db(value),
+                    CompressorParam::Ratio => c.set_ratio(value),
+                    CompressorParam::Attack => c.set_attack_ms(value),
+                    CompressorParam::Release => c.set_release_ms(value),
+                    CompressorParam::Makeup => c.set_makeup_db(value),
+                }
+            }
+            SynthCommand::SetModRoute { slot, route } => {
+                self.mod_matrix.set_route(slot as usize, route);
+            }
+            SynthCommand::SetPolyphony(voices) => {
+                self.set_polyphony(voices as usize);
+            }
+            SynthCommand::SetSubBusLevel(level) => {
+                self.sub_bus_level = level.clamp(0.0, 1.0);
+            }
+            SynthCommand::SetSubBusDelaySend(send) => {
+                self.sub_bus_delay_send = send.clamp(0.0, 1.0);
+            }
+            SynthCommand::SetSubBusReverbSend(send) => {
+                self.sub_bus_reverb_send = send.clamp(0.0, 1.0);
+            }
             SynthCommand::SetPitchModSensitivity(pms) => {
                 self.pitch_mod_sensitivity = pms.min(7);
             }
@@ -479,6 +1445,23 @@ impl SynthEngine {
             SynthCommand::Aftertouch(value) => {
                 self.aftertouch = value.clamp(0.0, 1.0);
             }
+            SynthCommand::PolyAftertouch { note, value } => {
+                let value = value.clamp(0.0, 1.0);
+                let stacked = self
+                    .stacked_voices
+                    .iter()
+                    .filter(|&&(key, _)| key == note)
+                    .map(|&(_, idx)| idx);
+                for idx in self
+                    .held_notes
+                    .get(&note)
+                    .copied()
+                    .into_iter()
+                    .chain(stacked)
+                {
+                    self.voices[idx].pressure = value;
+                }
+            }
             SynthCommand::SetBreathPitchSens(s) => {
                 self.breath_pitch_sens = s.min(7);
             }
@@ -512,6 +1495,9 @@ impl SynthEngine {
             SynthCommand::Expression(value) => {
                 self.expression = value.clamp(0.0, 1.0);
             }
+            SynthCommand::SetExpressionSource(source) => {
+                self.expression_source = source;
+            }
             SynthCommand::SetBankSelectMsb(v) => {
                 self.bank_msb = v & 0x7F;
             }
@@ -519,19 +1505,31 @@ impl SynthEngine {
                 self.bank_lsb = v & 0x7F;
             }
             SynthCommand::ProgramChange(program) => {
-                let absolute = ((self.bank_msb as usize) << 14)
-                    | ((self.bank_lsb as usize) << 7)
-                    | (program as usize & 0x7F);
-                self.load_preset(absolute);
+                // Taken out for the call so the preset is borrowed, not cloned.
+                let map = std::mem::take(&mut self.program_map);
+                if let Some(Some(preset)) = map.get(program as usize & 0x7F) {
+                    self.switch_preset(preset);
+                    log::debug!("Program {} mapped to {}", program, preset.name);
+                } else {
+                    let absolute = ((self.bank_msb as usize) << 14)
+                        | ((self.bank_lsb as usize) << 7)
+                        | (program as usize & 0x7F);
+                    self.load_preset(absolute);
+                }
+                self.program_map = map;
+            }
+            SynthCommand::SetProgramMap(map) => {
+                let old = std::mem::replace(&mut self.program_map, map);
+                self.command_rx.hand_back(SynthCommand::SetProgramMap(old));
             }
             SynthCommand::PitchBend(value) => {
-                self.pitch_bend = value as f32 / 8192.0;
+                self.pitch_bend = bend_from_14bit(value);
             }
             SynthCommand::ModWheel(value) => {
                 self.mod_wheel = value;
             }
             SynthCommand::SustainPedal(pressed) => {
-                self.sustain_pedal = pressed;
+                self.set_sustain_pedal(pressed);
             }
             SynthCommand::SetOperatorParam {
                 operator,
@@ -540,6 +1538,12 @@ impl SynthEngine {
             } => {
                 self.set_operator_param(operator as usize, param, value);
             }
+            SynthCommand::SetOperatorSolo(mask) => {
+                self.operator_solo = mask & 0x3F;
+                for voice in &mut self.voices {
+                    voice.solo = self.operator_solo;
+                }
+            }
             SynthCommand::SetEnvelopeParam {
                 operator,
                 param,
@@ -551,7 +1555,19 @@ impl SynthEngine {
                 self.set_pitch_eg_param(param, value);
             }
             SynthCommand::SetLfoParam { param, value } => {
-                self.set_lfo_param(param, value);
+                apply_lfo_param(&mut self.lfo, param, value);
+            }
+            SynthCommand::SetLfo2Param { param, value } => {
+                apply_lfo_param(&mut self.lfo2, param, value);
+            }
+            SynthCommand::SetTempo(bpm) => {
+                self.tempo_bpm = clamp_bpm(bpm);
+                self.lfo.set_tempo(self.tempo_bpm);
+                self.lfo2.set_tempo(self.tempo_bpm);
+                self.effects.delay.set_tempo(self.tempo_bpm);
+            }
+            SynthCommand::SetLfo2Enabled(enabled) => {
+                self.lfo2_enabled = enabled;
             }
             SynthCommand::SetEffectParam {
                 effect,
@@ -560,30 +1576,75 @@ impl SynthEngine {
             } => {
                 self.set_effect_param(effect, param, value);
             }
+            SynthCommand::SetEffectOrder(order) => {
+                self.effects.set_order(order);
+            }
+            SynthCommand::SetEffectsBypass(bypass) => {
+                self.effects.bypass = bypass;
+            }
+            SynthCommand::SetExtensions(extensions) => {
+                self.effects.extensions = extensions;
+            }
             SynthCommand::LoadPreset(preset_idx) => {
                 self.load_preset(preset_idx);
             }
-            SynthCommand::LoadSysExSingleVoice(preset) => {
-                preset.apply_to_synth(self);
+            SynthCommand::ApplyPreset(preset) | SynthCommand::LoadSysExSingleVoice(preset) => {
+                self.switch_preset(&preset);
             }
             SynthCommand::LoadSysExBulk(presets) => {
                 if let Some(first) = presets.first().cloned() {
-                    first.apply_to_synth(self);
+                    self.switch_preset(&first);
                 }
                 self.set_presets(presets);
             }
+            SynthCommand::SetPerformance(performance) => self.set_performance(performance),
+            SynthCommand::RequestDebugDump => self.debug_dump_requested = true,
+            SynthCommand::LoadPartB(preset) => {
+                if let Some(part_b) = self.part_b.as_deref_mut() {
+                    part_b.switch_preset(&preset);
+                }
+            }
+            SynthCommand::FunctionInitialize => self.function_initialize(),
             SynthCommand::VoiceInitialize => {
                 self.voice_initialize();
             }
+            SynthCommand::AllNotesOff => {
+                self.all_notes_off();
+            }
+            SynthCommand::ResetControllers => {
+                self.reset_controllers();
+            }
             SynthCommand::Panic => {
                 self.panic();
             }
         }
     }
 
+    fn set_sustain_pedal(&mut self, pressed: bool) {
+        self.sustain_pedal = pressed;
+        if !pressed {
+            let lifted = std::mem::take(&mut self.sustained_notes);
+            for note in (0..128u8).filter(|&n| lifted & (1 << n) != 0) {
+                self.note_off(note);
+            }
+        }
+    }
+
     fn note_on(&mut self, note: u8, velocity: u8) {
+        // Played again under the pedal: the key holds it now.
+        self.sustained_notes &= !(1 << (note & 0x7F));
         let velocity_f = velocity as f32 / 127.0;
         self.note_counter = self.note_counter.wrapping_add(1);
+        self.last_velocity = velocity_f;
+        self.last_note = Some(note);
+
+        // Mono modes: a key that loses on note priority only joins the held
+        // stack; the sounding note carries on untouched.
+        if self.voice_mode != VoiceMode::Poly && !self.mono_note_wins(note) {
+            self.mono_held_order.retain(|&n| n != note);
+            self.mono_held_order.push(note);
+            return;
+        }
 
         // Mono-Legato suppresses LFO/PEG retrigger while another note is held —
         // matching DX7 behaviour where a tied note keeps the previous envelope alive.
@@ -591,6 +1652,7 @@ impl SynthEngine {
             self.voice_mode == VoiceMode::MonoLegato && !self.mono_held_order.is_empty();
         if !suppress_retrigger {
             self.lfo.trigger();
+            self.lfo2.trigger();
             self.pitch_eg.trigger();
         }
 
@@ -598,8 +1660,12 @@ impl SynthEngine {
 
         match self.voice_mode {
             VoiceMode::Mono => {
-                // Full portamento: glide from previous note whenever portamento is enabled.
-                self.mono_trigger(note, effective_note, velocity_f, self.portamento_enable);
+                // Full time glides from the previous note on every key;
+                // fingered only while another key is down.
+                let glide = self.portamento_enable
+                    && (self.portamento_mode == PortamentoMode::FullTime
+                        || !self.mono_held_order.is_empty());
+                self.mono_trigger(note, effective_note, velocity_f, glide);
             }
             VoiceMode::MonoLegato => {
                 // Legato portamento: only glide if there is a previous note still held.
@@ -610,52 +1676,122 @@ impl SynthEngine {
                     self.mono_held_order.push(note);
                     self.held_notes.clear();
                     self.held_notes.insert(note, 0);
-                    self.voices[0].retarget(effective_note, self.master_tune, legato);
+                    let tune = self.note_tune(effective_note);
+                    self.voices[0].retarget(effective_note, tune, legato);
                     self.voices[0].note_on_id = self.note_counter;
                     return;
                 }
                 self.mono_trigger(note, effective_note, velocity_f, legato);
             }
             VoiceMode::Poly => {
-                if let Some(&voice_idx) = self.held_notes.get(&note) {
-                    self.voices[voice_idx].trigger(
-                        effective_note,
-                        velocity_f,
-                        self.master_tune,
-                        false,
-                    );
-                    self.voices[voice_idx].note_on_id = self.note_counter;
-                    return;
-                }
-
-                for (i, voice) in self.voices.iter_mut().enumerate() {
-                    if !voice.active {
-                        voice.trigger(effective_note, velocity_f, self.master_tune, false);
-                        voice.note_on_id = self.note_counter;
-                        self.held_notes.insert(note, i);
+                if self.same_note_mode == SameNoteMode::Retrigger {
+                    if let Some(&voice_idx) = self.held_notes.get(&note) {
+                        let tune = self.note_tune(effective_note);
+                        self.voices[voice_idx].trigger(effective_note, velocity_f, tune, false);
+                        self.voices[voice_idx].note_on_id = self.note_counter;
+                        self.place_voice(voice_idx, effective_note);
+                        self.last_voice = Some(voice_idx);
                         return;
                     }
                 }
 
-                let oldest_voice = self
-                    .voices
-                    .iter()
-                    .enumerate()
-                    .min_by_key(|(_, v)| v.note_on_id)
-                    .map(|(i, _)| i)
-                    .unwrap_or(0);
+                let glide_from = self.poly_glide_source(note);
+                let voice_idx = self.allocate_voice();
+                let tune = self.note_tune(effective_note);
+                let voice = &mut self.voices[voice_idx];
+                if voice.active {
+                    voice.steal_voice();
+                }
+                voice.trigger(effective_note, velocity_f, tune, false);
+                if let Some(frequency) = glide_from {
+                    voice.glide_from(frequency);
+                }
+                voice.note_on_id = self.note_counter;
+                self.place_voice(voice_idx, effective_note);
+                self.last_voice = Some(voice_idx);
+
+                self.held_notes.retain(|_, &mut v| v != voice_idx);
+                self.stacked_voices.retain(|&(_, v)| v != voice_idx);
+                if let Some(previous) = self.held_notes.insert(note, voice_idx) {
+                    // Stack mode: the earlier voice keeps sounding until note-off.
+                    self.stacked_voices.push((note, previous));
+                }
+            }
+        }
+    }
+
+    /// Pitch a new Poly note glides from: where the last note's voice is
+    /// now, so a run of notes chains glide into glide. None when portamento
+    /// is off, or fingered and no other key is physically down.
+    fn poly_glide_source(&self, note: u8) -> Option<f32> {
+        if !self.portamento_enable {
+            return None;
+        }
+        let legato = self
+            .held_notes
+            .keys()
+            .any(|&key| key != note && self.sustained_notes & (1 << (key & 0x7F)) == 0);
+        if self.portamento_mode == PortamentoMode::Fingered && !legato {
+            return None;
+        }
+        self.last_voice.map(|i| self.voices[i].current_frequency)
+    }
+
+    /// Some key (or the sustain pedal, which keeps lifted keys in
+    /// `held_notes`) holds voice `i`.
+    fn voice_is_held(&self, i: usize) -> bool {
+        self.held_notes.values().any(|&v| v == i)
+            || self.stacked_voices.iter().any(|&(_, v)| v == i)
+    }
+
+    /// Pick the voice for a new Poly note: a free one if any, else the
+    /// quietest voice whose key is already up, else the oldest held note.
+    /// The `PROTECTED_RECENT_NOTES` newest notes are skipped in both passes.
+    fn allocate_voice(&self) -> usize {
+        let pool = &self.voices[..self.polyphony];
+        if let Some(free) = pool.iter().position(|v| !v.active) {
+            return free;
+        }
+
+        let is_held = |i: usize| self.voice_is_held(i);
+        // `note_counter` already counts the note being allocated.
+        let protected_from = self.note_counter.saturating_sub(PROTECTED_RECENT_NOTES);
+        let candidates = || {
+            pool.iter()
+                .enumerate()
+                .filter(|(_, v)| v.note_on_id < protected_from)
+        };
+
+        let quietest_released = candidates()
+            .filter(|&(i, _)| !is_held(i))
+            .min_by(|(_, a), (_, b)| a.level().total_cmp(&b.level()));
+        let oldest_held = || candidates().min_by_key(|(_, v)| v.note_on_id);
+        let oldest = || pool.iter().enumerate().min_by_key(|(_, v)| v.note_on_id);
+        quietest_released
+            .or_else(oldest_held)
+            .or_else(oldest)
+            .map_or(0, |(i, _)| i)
+    }
 
-                self.voices[oldest_voice].steal_voice();
-                self.voices[oldest_voice].trigger(
-                    effective_note,
-                    velocity_f,
-                    self.master_tune,
-                    false,
-                );
-                self.voices[oldest_voice].note_on_id = self.note_counter;
+    /// MASTER TUNE plus the tuning table's offset for `note`, in cents.
+    fn note_tune(&self, note: u8) -> f32 {
+        self.master_tune + self.tuning.offset_cents(note)
+    }
 
-                self.held_notes.retain(|_, &mut v| v != oldest_voice);
-                self.held_notes.insert(note, oldest_voice);
+    /// Apply MTS single-note changes, moving sounding voices on those keys
+    /// straight to the new pitch.
+    fn retune_notes(&mut self, changes: &[(u8, f32)]) {
+        for &(note, cents) in changes {
+            self.tuning.set_offset_cents(note, cents);
+        }
+        // Renamed once, not on every message: this runs on the audio thread.
+        if self.tuning.name != LIVE_TUNING {
+            self.tuning.name = LIVE_TUNING.to_string();
+        }
+        for voice in self.voices.iter_mut().filter(|v| v.active) {
+            if changes.iter().any(|&(note, _)| note == voice.note) {
+                let tune = self.master_tune + self.tuning.offset_cents(voice.note);
+                voice.retarget(voice.note, tune, false);
             }
         }
     }
@@ -667,24 +1803,85 @@ impl SynthEngine {
         self.held_notes.clear();
         self.held_notes.insert(note, 0);
 
-        self.voices[0].trigger(effective_note, velocity_f, self.master_tune, portamento);
+        let tune = self.note_tune(effective_note);
+        self.voices[0].trigger(effective_note, velocity_f, tune, portamento);
         self.voices[0].note_on_id = self.note_counter;
+        self.place_voice(0, effective_note);
+        self.last_voice = Some(0);
+    }
+
+    /// Pick the stereo slot of a voice starting `note`, then position it.
+    fn place_voice(&mut self, voice_idx: usize, note: u8) {
+        // `note_counter` already counts this note; the first one takes slot 0.
+        let count = self.note_counter.wrapping_sub(1);
+        let slot = self.voice_spread.position(note, count);
+        let position = slot * self.voice_spread_width + self.master_pan;
+        let voice = &mut self.voices[voice_idx];
+        voice.spread_slot = slot;
+        voice.pan.set_position(position);
+    }
+
+    /// Re-apply the spread width and master pan to every voice's slot.
+    fn place_voices(&mut self) {
+        for voice in &mut self.voices {
+            voice
+                .pan
+                .set_position(voice.spread_slot * self.voice_spread_width + self.master_pan);
+        }
+    }
+
+    fn apply_unison(&mut self) {
+        let Unison {
+            stacks,
+            detune,
+            spread,
+        } = self.unison;
+        for voice in &mut self.voices {
+            voice.set_unison(stacks, detune, spread);
+        }
+    }
+
+    /// Whether a newly pressed `note` takes over the mono voice under the
+    /// current note priority.
+    fn mono_note_wins(&self, note: u8) -> bool {
+        let mut others = self.mono_held_order.iter().filter(|&&n| n != note);
+        match self.note_priority {
+            NotePriority::Last => true,
+            NotePriority::Low => others.all(|&n| note < n),
+            NotePriority::High => others.all(|&n| note > n),
+        }
+    }
+
+    /// The held key the mono voice should play, if any key is still down.
+    fn mono_priority_note(&self) -> Option<u8> {
+        let held = self.mono_held_order.iter().copied();
+        match self.note_priority {
+            NotePriority::Last => self.mono_held_order.last().copied(),
+            NotePriority::Low => held.min(),
+            NotePriority::High => held.max(),
+        }
     }
 
     fn note_off(&mut self, note: u8) {
         if self.sustain_pedal {
+            self.sustained_notes |= 1 << (note & 0x7F);
             return;
         }
         match self.voice_mode {
             VoiceMode::Mono | VoiceMode::MonoLegato => {
                 self.mono_held_order.retain(|&n| n != note);
-                if let Some(&prev) = self.mono_held_order.last() {
-                    // Re-target voice 0 to the most recently held note still pressed.
+                if !self.held_notes.contains_key(&note) {
+                    // A waiting key was lifted; the sounding note is unaffected.
+                    return;
+                }
+                if let Some(prev) = self.mono_priority_note() {
+                    // Re-target voice 0 to the held note with the highest priority.
                     // Both Mono and MonoLegato glide here when portamento is on:
                     // there's always at least one prior held note (`prev`).
                     let prev_eff = self.apply_transpose(prev);
                     let portamento = self.portamento_enable;
-                    self.voices[0].retarget(prev_eff, self.master_tune, portamento);
+                    let tune = self.note_tune(prev_eff);
+                    self.voices[0].retarget(prev_eff, tune, portamento);
                     self.held_notes.clear();
                     self.held_notes.insert(prev, 0);
                 } else if let Some(&voice_idx) = self.held_notes.get(&note) {
@@ -694,6 +1891,12 @@ impl SynthEngine {
                 }
             }
             VoiceMode::Poly => {
+                for &(key, voice_idx) in &self.stacked_voices {
+                    if key == note {
+                        self.voices[voice_idx].release();
+                    }
+                }
+                self.stacked_voices.retain(|&(key, _)| key != note);
                 if let Some(&voice_idx) = self.held_notes.get(&note) {
                     self.voices[voice_idx].release();
                     self.held_notes.remove(&note);
@@ -705,8 +1908,22 @@ impl SynthEngine {
         }
     }
 
+    fn apply_fade_times(&mut self) {
+        for voice in &mut self.voices {
+            voice.set_fade_times(self.steal_fade_ms, self.attack_fade_ms);
+        }
+    }
+
+    fn apply_vintage(&mut self) {
+        for voice in &mut self.voices {
+            voice.set_vintage(self.vintage);
+        }
+        self.lfo.set_vintage(self.vintage);
+        self.lfo2.set_vintage(self.vintage);
+    }
+
     fn apply_transpose(&self, note: u8) -> u8 {
-        let shifted = note as i32 + self.transpose_semitones as i32;
+        let shifted = note as i32 + self.transpose_semitones as i32 + self.part_transpose as i32;
         shifted.clamp(0, 127) as u8
     }
 
@@ -718,6 +1935,9 @@ impl SynthEngine {
             let op = &mut voice.operators[op_index];
             match param {
                 OperatorParam::Ratio => op.set_frequency_ratio(value),
+                OperatorParam::Coarse => op.set_coarse_fine(value as u8, op.fine),
+                OperatorParam::Fine => op.set_coarse_fine(op.coarse, value as u8),
+                OperatorParam::ExtendedRatio => op.set_extended_ratio(value > 0.5),
                 OperatorParam::Level => op.set_output_level(value),
                 OperatorParam::Detune => op.set_detune(value),
                 OperatorParam::Feedback => op.set_feedback(value),
@@ -736,6 +1956,14 @@ impl SynthEngine {
                 }
                 OperatorParam::AmSensitivity => op.set_am_sensitivity(value.clamp(0.0, 3.0) as u8),
                 OperatorParam::OscillatorKeySync => op.oscillator_key_sync = value > 0.5,
+                OperatorParam::SubBus => op.sub_bus = value > 0.5,
+                OperatorParam::Expression => op.expression = value > 0.5,
+                OperatorParam::Waveform => op.waveform = OperatorWaveform::from_code(value as u8),
+                OperatorParam::PhaseOffset => op.set_phase_offset(value),
+                OperatorParam::PmSensitivity => {
+                    op.set_pm_sensitivity((value >= 0.0).then_some(value.min(7.0) as u8))
+                }
+                OperatorParam::Lfo2 => op.lfo2 = value > 0.5,
                 OperatorParam::FixedFrequency => {
                     op.fixed_frequency = value > 0.5;
                     op.update_frequency();
@@ -781,29 +2009,64 @@ impl SynthEngine {
         }
     }
 
-    fn set_lfo_param(&mut self, param: LfoParam, value: f32) {
+    fn set_effect_param(&mut self, effect: EffectType, param: EffectParam, value: f32) {
+        // Chain-level controls, the same for every effect
+        let slot = effect.slot();
         match param {
-            LfoParam::Rate => self.lfo.set_rate(value),
-            LfoParam::Delay => self.lfo.set_delay(value),
-            LfoParam::PitchDepth => self.lfo.set_pitch_depth(value),
-            LfoParam::AmpDepth => self.lfo.set_amp_depth(value),
-            LfoParam::Waveform(w) => {
-                let waveform = match w {
-                    0 => LFOWaveform::Triangle,
-                    1 => LFOWaveform::SawDown,
-                    2 => LFOWaveform::SawUp,
-                    3 => LFOWaveform::Square,
-                    4 => LFOWaveform::Sine,
-                    _ => LFOWaveform::SampleHold,
-                };
-                self.lfo.set_waveform(waveform);
+            EffectParam::OutputGain => return self.effects.set_output_gain_db(slot, value),
+            EffectParam::SoloWet => {
+                if value > 0.5 {
+                    self.effects.set_wet_solo(Some(slot));
+                } else if self.effects.wet_solo() == Some(slot) {
+                    self.effects.set_wet_solo(None);
+                }
+                return;
             }
-            LfoParam::KeySync => self.lfo.set_key_sync(value > 0.5),
+            _ => {}
         }
-    }
-
-    fn set_effect_param(&mut self, effect: EffectType, param: EffectParam, value: f32) {
         match effect {
+            EffectType::Eq => match param {
+                EffectParam::Enabled => self.effects.eq.enabled = value > 0.5,
+                EffectParam::EqLowGain => self.effects.eq.set_low_gain(value),
+                EffectParam::EqLowFreq => self.effects.eq.set_low_freq(value),
+                EffectParam::EqMidGain => self.effects.eq.set_mid_gain(value),
+                EffectParam::EqMidFreq => self.effects.eq.set_mid_freq(value),
+                EffectParam::EqMidQ => self.effects.eq.set_mid_q(value),
+                EffectParam::EqHighGain => self.effects.eq.set_high_gain(value),
+                EffectParam::EqHighFreq => self.effects.eq.set_high_freq(value),
+                _ => {}
+            },
+            EffectType::Filter => match param {
+                EffectParam::Enabled => self.effects.filter.enabled = value > 0.5,
+                EffectParam::Mix => self.effects.filter.mix = value.clamp(0.0, 1.0),
+                EffectParam::FilterCutoff => {
+                    self.effects.filter.cutoff_hz = value.clamp(FILTER_MIN_HZ, FILTER_MAX_HZ)
+                }
+                EffectParam::FilterResonance => {
+                    self.effects.filter.resonance = value.clamp(0.0, 1.0)
+                }
+                EffectParam::FilterEnvAmount => {
+                    self.effects.filter.env_amount = value.clamp(-1.0, 1.0)
+                }
+                EffectParam::FilterLfoRate => {
+                    self.effects.filter.lfo_rate = value.clamp(0.05, 10.0)
+                }
+                EffectParam::FilterLfoDepth => {
+                    self.effects.filter.lfo_depth = value.clamp(0.0, 1.0)
+                }
+                _ => {}
+            },
+            EffectType::Phaser => match param {
+                EffectParam::Enabled => self.effects.phaser.enabled = value > 0.5,
+                EffectParam::Mix => self.effects.phaser.mix = value.clamp(0.0, 1.0),
+                EffectParam::PhaserStages => self.effects.phaser.set_stages(value as usize),
+                EffectParam::PhaserRate => self.effects.phaser.rate = value.clamp(0.05, 5.0),
+                EffectParam::PhaserDepth => self.effects.phaser.depth = value.clamp(0.0, 1.0),
+                EffectParam::PhaserFeedback => {
+                    self.effects.phaser.feedback = value.clamp(0.0, PHASER_MAX_FEEDBACK)
+                }
+                _ => {}
+            },
             EffectType::Chorus => match param {
                 EffectParam::Enabled => self.effects.chorus.enabled = value > 0.5,
                 EffectParam::Mix => self.effects.chorus.mix = value,
@@ -824,6 +2087,12 @@ impl SynthEngine {
                 EffectParam::DelayTime => self.effects.delay.time_ms = value,
                 EffectParam::DelayFeedback => self.effects.delay.feedback = value,
                 EffectParam::DelayPingPong => self.effects.delay.ping_pong = value > 0.5,
+                EffectParam::DelaySync => {
+                    self.effects.delay.sync =
+                        (value >= 0.0).then(|| NoteDivision::from_code(value as u8))
+                }
+                EffectParam::DelayHighCut => self.effects.delay.set_high_cut(value),
+                EffectParam::DelayLowCut => self.effects.delay.set_low_cut(value),
                 _ => {}
             },
             EffectType::Reverb => match param {
@@ -832,22 +2101,36 @@ impl SynthEngine {
                 EffectParam::ReverbRoomSize => self.effects.reverb.room_size = value,
                 EffectParam::ReverbDamping => self.effects.reverb.damping = value,
                 EffectParam::ReverbWidth => self.effects.reverb.width = value,
+                EffectParam::ReverbPreDelay => {
+                    self.effects.reverb.pre_delay_ms = value.clamp(0.0, REVERB_MAX_PRE_DELAY_MS)
+                }
+                EffectParam::ReverbType => self
+                    .effects
+                    .reverb
+                    .set_reverb_type(ReverbType::from_code(value as u8)),
                 _ => {}
             },
         }
     }
 
+    /// INIT VOICE as the DX7 does it: algorithm 1 with only OP1 sounding,
+    /// every EG at full rate, LFO and pitch EG neutral. The extensions this
+    /// engine adds on top of the voice (custom algorithm, LFO 2, unison,
+    /// voice pan and spread, sub bus and the effects) go back to how the
+    /// engine boots. Function settings are left to `function_initialize`.
     fn voice_initialize(&mut self) {
         self.preset_name = "Init Voice".to_string();
         self.algorithm = 1;
+        self.custom_algorithm = false;
 
         for voice in &mut self.voices {
             voice.stop();
         }
         self.held_notes.clear();
+        self.stacked_voices.clear();
         self.mono_held_order.clear();
         self.transpose_semitones = 0;
-        self.pitch_mod_sensitivity = 0;
+        self.pitch_mod_sensitivity = 3;
         self.eg_bias_sensitivity = 0;
         self.pitch_bias_sensitivity = 0;
         // Init Voice clears the patch-side routing for every external controller
@@ -866,12 +2149,43 @@ impl SynthEngine {
         self.foot_amp_sens = 0;
         self.foot_eg_bias_sens = 0;
         self.pitch_eg.enabled = false;
+        self.pitch_eg.rate1 = 99.0;
+        self.pitch_eg.rate2 = 99.0;
+        self.pitch_eg.rate3 = 99.0;
+        self.pitch_eg.rate4 = 99.0;
+        self.pitch_eg.level1 = 50.0;
+        self.pitch_eg.level2 = 50.0;
+        self.pitch_eg.level3 = 50.0;
+        self.pitch_eg.level4 = 50.0;
         self.pitch_eg.reset();
 
+        self.lfo.set_waveform(LFOWaveform::Triangle);
+        self.lfo.set_rate(35.0);
+        self.lfo.set_delay(0.0);
+        self.lfo.set_pitch_depth(0.0);
+        self.lfo.set_amp_depth(0.0);
+        self.lfo.set_key_sync(true);
+        self.lfo.set_sync(None);
+        self.lfo2_enabled = false;
+
+        self.unison = Unison::default();
+        self.apply_unison();
+        self.voice_spread = VoiceSpread::Center;
+        self.voice_spread_width = 1.0;
+        self.place_voices();
+        self.sub_bus_level = 1.0;
+        self.sub_bus_delay_send = 0.0;
+        self.sub_bus_reverb_send = 0.0;
+        self.effects.reset_settings();
+        boot_effects(&mut self.effects);
+
         for voice in &mut self.voices {
-            for op in voice.operators.iter_mut() {
-                op.frequency_ratio = 1.0;
-                op.output_level = 99.0;
+            voice.pan.rate_hz = 4.0;
+            voice.pan.depth = 0.0;
+            voice.pan.stereo_phase = 1.0;
+            for (i, op) in voice.operators.iter_mut().enumerate() {
+                op.set_coarse_fine(1, 0);
+                op.output_level = if i == 0 { 99.0 } else { 0.0 };
                 op.detune = 0.0;
                 op.feedback = 0.0;
                 op.velocity_sensitivity = 0.0;
@@ -882,21 +2196,44 @@ impl SynthEngine {
                 op.key_scale_left_depth = 0.0;
                 op.key_scale_right_depth = 0.0;
                 op.am_sensitivity = 0;
+                op.pm_sensitivity = None;
+                op.lfo2 = false;
                 op.oscillator_key_sync = true;
+                op.sub_bus = false;
+                op.expression = false;
+                op.waveform = OperatorWaveform::Sine;
+                op.phase_offset = 0.0;
                 op.fixed_frequency = false;
                 op.fixed_freq_hz = 440.0;
                 op.envelope.rate1 = 99.0;
-                op.envelope.rate2 = 50.0;
-                op.envelope.rate3 = 50.0;
-                op.envelope.rate4 = 50.0;
+                op.envelope.rate2 = 99.0;
+                op.envelope.rate3 = 99.0;
+                op.envelope.rate4 = 99.0;
                 op.envelope.level1 = 99.0;
-                op.envelope.level2 = 75.0;
-                op.envelope.level3 = 50.0;
+                op.envelope.level2 = 99.0;
+                op.envelope.level3 = 99.0;
                 op.envelope.level4 = 0.0;
+                op.invalidate_cache();
             }
         }
     }
 
+    /// INIT FUNCTION: the playing setup back to how the engine boots —
+    /// poly, last-note priority, 2-semitone bend, portamento off, master
+    /// tune centred, no assignable mod routes. The voice is left alone.
+    fn function_initialize(&mut self) {
+        self.apply_command(SynthCommand::SetVoiceMode(0));
+        self.note_priority = NotePriority::Last;
+        self.same_note_mode = SameNoteMode::Retrigger;
+        self.pitch_bend_range = 2.0;
+        self.portamento_enable = false;
+        self.portamento_time = 50.0;
+        self.portamento_glissando = false;
+        self.master_tune = 0.0;
+        self.mod_matrix = ModMatrix::default();
+        self.expression_source = ExpressionSource::Breath;
+    }
+
     /// Load a preset by index (for MIDI program change)
     fn load_preset(&mut self, index: usize) {
         if index >= self.presets.len() {
@@ -905,47 +2242,289 @@ impl SynthEngine {
 
         // Avoid double-borrow by cloning the preset (cheap: ~6 ops + 6 envs + Option fields).
         let preset = self.presets[index].clone();
-        preset.apply_to_synth(self);
+        self.switch_preset(&preset);
         self.current_preset_index = index;
         log::debug!("Loaded preset {}: {}", index, preset.name);
     }
 
-    fn panic(&mut self) {
+    /// Apply `preset` in one go at this block boundary; see `change_patch`.
+    fn switch_preset(&mut self, preset: &Dx7Preset) {
+        self.change_patch(|engine| preset.apply_to_synth(engine));
+    }
+
+    /// Run `change` on the sounding patch without a click. Sounding voices
+    /// keep their old sound running from a copy of their operators and
+    /// fade it out at the steal rate. In `PatchChangeMode::Crossfade` they
+    /// fade into the new settings mid-envelope; in `Retrigger` the voices
+    /// a key still holds restart on the new patch with fresh envelopes
+    /// (and LFO / pitch EG), released ones crossfade as they decay.
+    fn change_patch(&mut self, change: impl FnOnce(&mut Self)) {
+        let retrigger = self.patch_change_mode == PatchChangeMode::Retrigger;
+        for i in 0..self.voices.len() {
+            if retrigger && self.voices[i].active && self.voice_is_held(i) {
+                self.voices[i].steal_voice();
+            } else {
+                self.voices[i].crossfade_to_new_settings();
+            }
+        }
+        change(self);
+        if retrigger {
+            self.retrigger_held_voices();
+        }
+    }
+
+    /// Restart every held voice on its key, at its last velocity.
+    fn retrigger_held_voices(&mut self) {
+        let mut any = false;
+        for i in 0..self.voices.len() {
+            let Some(key) = self.held_key(i) else {
+                continue;
+            };
+            if !self.voices[i].active {
+                continue;
+            }
+            let note = self.apply_transpose(key);
+            let tune = self.note_tune(note);
+            let voice = &mut self.voices[i];
+            let velocity = voice.velocity;
+            voice.trigger(note, velocity, tune, false);
+            any = true;
+        }
+        if any {
+            self.lfo.trigger();
+            self.lfo2.trigger();
+            self.pitch_eg.trigger();
+        }
+    }
+
+    /// Key holding voice `i`, if any.
+    fn held_key(&self, i: usize) -> Option<u8> {
+        self.held_notes
+            .iter()
+            .map(|(&key, &v)| (key, v))
+            .chain(self.stacked_voices.iter().copied())
+            .find(|&(_, v)| v == i)
+            .map(|(key, _)| key)
+    }
+
+    fn set_performance(&mut self, performance: Performance) {
+        let performance = performance.clamped();
+        self.performance = performance;
+        let part_a = performance.part(0);
+        self.part_volume = part_a.volume;
+        self.part_transpose = part_a.transpose;
+        if let Some(part_b) = self.part_b.as_deref_mut() {
+            let settings = performance.parts[1];
+            part_b.part_volume = settings.volume;
+            part_b.part_transpose = settings.transpose;
+            if !performance.uses_part_b() {
+                part_b.release_all();
+            }
+        }
+    }
+
+    /// Release every sounding voice, as if all keys and the pedal came up.
+    fn release_all(&mut self) {
         for voice in &mut self.voices {
-            voice.active = false;
-            for op in &mut voice.operators {
-                op.reset();
+            if voice.active {
+                voice.release();
             }
         }
         self.held_notes.clear();
+        self.stacked_voices.clear();
+        self.mono_held_order.clear();
+        self.sustained_notes = 0;
+        self.pitch_eg.release();
+    }
+
+    /// MIDI All Notes Off (CC123): like a note-off for every key, so the
+    /// notes ring out through their release, or stay under the pedal
+    /// until it comes up.
+    fn all_notes_off(&mut self) {
+        if !self.sustain_pedal {
+            self.release_all();
+            return;
+        }
+        let stacked = self.stacked_voices.iter().map(|(note, _)| note);
+        for &note in self
+            .held_notes
+            .keys()
+            .chain(stacked)
+            .chain(&self.mono_held_order)
+        {
+            self.sustained_notes |= 1 << (note & 0x7F);
+        }
+    }
+
+    /// MIDI Reset All Controllers (CC121), per RP-015: bend centred, mod
+    /// wheel and channel / key pressure at 0, expression at full and the
+    /// sustain pedal up. Breath and foot are left where they are.
+    fn reset_controllers(&mut self) {
+        self.pitch_bend = 0.0;
+        self.mod_wheel = 0.0;
+        self.aftertouch = 0.0;
+        for voice in &mut self.voices {
+            voice.pressure = 0.0;
+        }
+        self.expression = 1.0;
+        self.set_sustain_pedal(false);
+    }
+
+    /// All Sound Off (CC120), system reset and the PANIC button: every
+    /// voice stops dead, with no release.
+    fn panic(&mut self) {
+        for voice in &mut self.voices {
+            voice.reset();
+        }
+        self.held_notes.clear();
+        self.stacked_voices.clear();
         self.mono_held_order.clear();
+        self.sustained_notes = 0;
         self.pitch_eg.reset();
     }
 
     /// Process one sample of audio (mono). Output is **unsaturated** — the
     /// final `tanh` happens once, post-effects, in [`Self::process_stereo`].
+    /// The sub bus is folded into the main signal here; only the stereo path
+    /// keeps it separate for its effect sends.
     pub fn process(&mut self) -> f32 {
-        let mut output = 0.0;
-        let mut active_voice_count = 0;
+        let (main, _side, sub) = self.process_buses();
+        main + sub
+    }
 
-        let (lfo_pitch_mod_raw, lfo_amp_mod_raw) = self.lfo.process(self.mod_wheel);
-
-        // PMS (Pitch Mod Sensitivity) ROM lookup. Source: `pitchmodsenstab[8]`
-        // in MSFA / Dexed `dx7note.cc` = {0, 10, 20, 33, 55, 92, 153, 255},
-        // normalised by 255 then scaled to our depth domain. The `* 2.0`
-        // factor preserves the previous max swing of ~2 semitones at PMS=7
-        // (downstream code applies `* 0.5` to convert back to semitones).
-        const PMS_TABLE: [f32; 8] = [
-            0.0,
-            (10.0 / 255.0) * 2.0,  // 0.0784
-            (20.0 / 255.0) * 2.0,  // 0.1569
-            (33.0 / 255.0) * 2.0,  // 0.2588
-            (55.0 / 255.0) * 2.0,  // 0.4314
-            (92.0 / 255.0) * 2.0,  // 0.7216
-            (153.0 / 255.0) * 2.0, // 1.2000
-            2.0,
-        ];
-        let pms_scale = PMS_TABLE[self.pitch_mod_sensitivity.min(7) as usize];
+    /// Render one sample as (main, side, sub) bus values. The main bus is
+    /// split into mid (`main`) and side by each voice's tremolo / auto-pan;
+    /// side is 0 while no voice pans. All buses share voice scaling and
+    /// master gain; the sub bus additionally gets its own level.
+    fn process_buses(&mut self) -> (f32, f32, f32) {
+        self.update_voice_controls(1);
+        self.mix_parts()
+    }
+
+    /// Retune every active voice for the next `samples` samples, part B's
+    /// included while it renders.
+    fn update_voice_controls(&mut self, samples: usize) {
+        self.run_watchdog(samples);
+        let control = self.control_signals(samples);
+        for voice in &mut self.voices {
+            if voice.active {
+                voice.update_control(&control, samples);
+            }
+        }
+        let uses_part_b = self.performance.uses_part_b();
+        self.part_b_running = match self.part_b.as_deref_mut() {
+            Some(part_b) if uses_part_b || part_b.voices.iter().any(|v| v.active) => {
+                part_b.update_voice_controls(samples);
+                true
+            }
+            _ => false,
+        };
+    }
+
+    /// Stuck-note watchdog, every `WATCHDOG_INTERVAL_SECONDS` of audio:
+    /// release voices still short of their release segment that no key has
+    /// held for the grace time (a note-off lost to a MIDI hiccup), and
+    /// voices past the timeout, and forget the keys they were held by.
+    fn run_watchdog(&mut self, samples: usize) {
+        if !self.voice_watchdog && self.voice_timeout <= 0.0 {
+            return;
+        }
+        self.watchdog_elapsed += samples;
+        if (self.watchdog_elapsed as f32) < WATCHDOG_INTERVAL_SECONDS * self.sample_rate {
+            return;
+        }
+        let elapsed = std::mem::take(&mut self.watchdog_elapsed) as u32;
+        let grace = (STUCK_VOICE_GRACE_SECONDS * self.sample_rate) as u32;
+        let timeout = (self.voice_timeout * self.sample_rate) as u32;
+        for i in 0..self.voices.len() {
+            if !self.voices[i].active || self.voices[i].released() {
+                continue;
+            }
+            let held = self.voice_is_held(i);
+            let voice = &mut self.voices[i];
+            voice.age = voice.age.saturating_add(elapsed);
+            voice.unheld = if held {
+                0
+            } else {
+                voice.unheld.saturating_add(elapsed)
+            };
+            let stuck = self.voice_watchdog && voice.unheld >= grace;
+            let expired = timeout > 0 && voice.age >= timeout;
+            if !(stuck || expired) {
+                continue;
+            }
+            voice.release();
+            self.held_notes.retain(|_, &mut v| v != i);
+            self.stacked_voices.retain(|&(_, v)| v != i);
+            if self.voice_mode != VoiceMode::Poly {
+                self.mono_held_order.clear();
+            }
+            if self.held_notes.is_empty() {
+                self.pitch_eg.release();
+            }
+            self.watchdog_releases = self.watchdog_releases.saturating_add(1);
+        }
+    }
+
+    /// `mix_voices` plus part B's voices while it renders.
+    fn mix_parts(&mut self) -> (f32, f32, f32) {
+        let (main, side, sub) = self.mix_voices();
+        match self.part_b.as_deref_mut() {
+            Some(part_b) if self.part_b_running => {
+                let (b_main, b_side, b_sub) = part_b.mix_voices();
+                (main + b_main, side + b_side, sub + b_sub)
+            }
+            _ => (main, side, sub),
+        }
+    }
+
+    /// Move the applied bend towards the wheel: a one-pole glide with a
+    /// `bend_smoothing_ms` time constant, run at control rate.
+    fn advance_bend(&mut self, samples: usize) {
+        let distance = self.pitch_bend - self.smoothed_bend;
+        // 1e-4 of a 12-semitone range is about a tenth of a cent.
+        if distance.abs() < 1e-4 || self.bend_smoothing_ms <= 0.0 {
+            self.smoothed_bend = self.pitch_bend;
+            return;
+        }
+        let time_constant = self.bend_smoothing_ms * 0.001 * self.sample_rate;
+        self.smoothed_bend += distance * (1.0 - (-(samples as f32) / time_constant).exp());
+    }
+
+    /// Engine-wide modulation for the next `samples` samples. The LFO and
+    /// pitch EG are read at the start of the span and advanced past it.
+    fn control_signals(&mut self, samples: usize) -> ControlSignals {
+        // Mod matrix, global pass. The LFO source is the previous block's
+        // value, since the LFO rate is itself a destination.
+        let mod_sources = ModSources {
+            mod_wheel: self.mod_wheel,
+            breath: self.breath,
+            foot: self.foot,
+            aftertouch: self.aftertouch,
+            velocity: self.last_velocity,
+            lfo: self.lfo.value(),
+            lfo2: if self.lfo2_enabled {
+                self.lfo2.value()
+            } else {
+                0.0
+            },
+        };
+        let matrix = self.mod_matrix.evaluate(&mod_sources);
+        self.lfo.set_rate_scale(matrix.lfo_rate_scale());
+        self.effects.set_modulation(matrix.effect_modulation());
+
+        let (lfo_pitch_mod_raw, lfo_amp_mod_raw) =
+            self.lfo.process_samples(self.mod_wheel, samples);
+        let lfo_depth_scale = matrix.lfo_depth_scale();
+        let lfo_pitch_mod_raw = lfo_pitch_mod_raw * lfo_depth_scale;
+        let lfo_amp_mod_raw = lfo_amp_mod_raw * lfo_depth_scale;
+        let (lfo2_pitch_raw, lfo2_amp_raw) = if self.lfo2_enabled {
+            self.lfo2.process_samples(self.mod_wheel, samples)
+        } else {
+            (0.0, 0.0)
+        };
+
+        let pms_scale = pms_scale(self.pitch_mod_sensitivity);
 
         // Each external controller (Aftertouch / Breath / Foot) routes to four
         // destinations. PITCH and AMP further scale the LFO pitch/amp depth on
@@ -968,7 +2547,8 @@ impl SynthEngine {
         let lfo_pitch_mod = lfo_pitch_mod_raw * (pms_scale + pitch_route_total);
         let lfo_amp_mod = lfo_amp_mod_raw * (1.0 + amp_route_total);
 
-        let pitch_eg_semitones = self.pitch_eg.process();
+        let pitch_eg_semitones = self.pitch_eg.process_samples(samples);
+        self.advance_bend(samples);
 
         // EG Bias: static controller-driven offset (mod wheel × sensitivity).
         // 0..1 amount; the per-operator AMS gates how strongly each op responds.
@@ -979,25 +2559,73 @@ impl SynthEngine {
             + pitch_bias_route_total)
             * 2.0;
 
+        ControlSignals {
+            pitch_bend: self.smoothed_bend,
+            pitch_bend_range: self.pitch_bend_range,
+            portamento_time: self.portamento_time,
+            glissando: self.portamento_glissando,
+            lfo_pitch_mod,
+            lfo_amp_mod,
+            pitch_eg_semitones,
+            eg_bias_amount,
+            pitch_bias_semitones,
+            lfo_pitch_raw: lfo_pitch_mod_raw,
+            lfo_amp_raw: lfo_amp_mod_raw,
+            aftertouch: self.aftertouch,
+            aftertouch_sens: [
+                self.aftertouch_pitch_sens,
+                self.aftertouch_amp_sens,
+                self.aftertouch_eg_bias_sens,
+                self.aftertouch_pitch_bias_sens,
+            ],
+            mod_matrix: self.mod_matrix,
+            mod_sources,
+            pms_scale,
+            pitch_route: pitch_route_total,
+            amp_route: amp_route_total,
+            lfo2_enabled: self.lfo2_enabled,
+            lfo2_pitch_raw,
+            lfo2_amp_raw,
+            expression: match self.expression_source {
+                ExpressionSource::Breath => self.breath,
+                ExpressionSource::Expression => self.expression,
+            },
+        }
+    }
+
+    /// One sample of every active voice, mixed into (main, side, sub).
+    fn mix_voices(&mut self) -> (f32, f32, f32) {
+        let mut output = 0.0;
+        let mut side_output = 0.0;
+        let mut sub_output = 0.0;
+        let mut active_voice_count = 0;
+
+        let custom = self.custom_algorithm.then_some(&self.algorithm_matrix);
+        let mut operator_levels = [0.0_f32; 6];
         for voice in &mut self.voices {
             if voice.active {
-                let voice_output = voice.process(
-                    self.algorithm,
-                    self.pitch_bend,
-                    self.pitch_bend_range,
-                    self.portamento_time,
-                    self.portamento_glissando,
-                    lfo_pitch_mod,
-                    lfo_amp_mod,
-                    pitch_eg_semitones,
-                    eg_bias_amount,
-                    pitch_bias_semitones,
-                );
-                output += voice_output;
+                let (voice_output, voice_side, voice_sub) = voice.render(self.algorithm, custom);
+                // Debug builds drop a voice that went NaN or infinite, so
+                // the note that broke is easy to spot in the log.
+                if cfg!(debug_assertions) && !(voice_output + voice_side + voice_sub).is_finite() {
+                    log::warn!("Voice on note {} went non-finite; reset", voice.note);
+                    voice.reset();
+                    continue;
+                }
+                for (level, op) in operator_levels.iter_mut().zip(&voice.operators) {
+                    *level = level.max(op.current_output().abs());
+                }
+                let (gain_l, gain_r) = voice.pan.next_gains();
+                let (sum, diff) = ((gain_l + gain_r) * 0.5, (gain_l - gain_r) * 0.5);
+                output += voice_output * sum + voice_side * diff;
+                side_output += voice_output * diff + voice_side * sum;
+                sub_output += voice_sub;
                 active_voice_count += 1;
             }
         }
 
+        self.operator_meter.process(operator_levels);
+
         let voice_scaling = voice_scale(active_voice_count);
 
         // Foot Controller VOLUME (DX7S): when sensitivity > 0, the foot pedal acts
@@ -1011,7 +2639,21 @@ impl SynthEngine {
             1.0
         };
 
-        output * voice_scaling * self.master_volume * foot_volume_factor * self.expression
+        self.master_gain
+            .set_target(self.master_volume * self.part_volume);
+        let master = self.master_gain.advance();
+        // CC11 driving the operators' expression input no longer scales the
+        // whole output as well.
+        let expression = match self.expression_source {
+            ExpressionSource::Breath => self.expression,
+            ExpressionSource::Expression => 1.0,
+        };
+        let gain = voice_scaling * master * foot_volume_factor * expression;
+        (
+            output * gain,
+            side_output * gain,
+            sub_output * gain * self.sub_bus_level,
+        )
     }
 
     /// Process audio with effects, returns stereo pair (left, right).
@@ -1019,21 +2661,146 @@ impl SynthEngine {
     /// Saturation lives only here, *after* the effects chain: feeding a
     /// pre-saturated mono into Chorus/Reverb crushes transients (the Rhodes
     /// "bell tone", marimba peaks) before the reverb sees them, and makes
-    /// the wet path sound dull. DC blockers run before the final `tanh`
-    /// so any feedback-induced offset (algorithms 4/6 cross-feedback,
-    /// asymmetric voice sums) is removed *before* it biases the saturator.
+    /// the wet path sound dull. DC blockers run before the master
+    /// compressor and the final `tanh` so any feedback-induced offset
+    /// (algorithms 4/6 cross-feedback, asymmetric voice sums) is removed
+    /// *before* it biases the detector or the saturator. The compressor
+    /// does the level control; the `tanh` only catches what slips past its
+    /// attack.
     pub fn process_stereo(&mut self) -> (f32, f32) {
-        let mono = self.process();
-        let (left, right) = self.effects.process(mono);
-        let l = Self::soft_clip(self.dc_blocker_l.process(left));
-        let r = Self::soft_clip(self.dc_blocker_r.process(right));
+        let (main, side, sub) = self.process_buses();
+        self.output_stage(main, side, sub)
+    }
+
+    /// Render a block of stereo output into `left` and `right`.
+    ///
+    /// Pending commands are applied once at the start of the block, and the
+    /// control-rate work (LFO, pitch EG, controller routing, operator
+    /// retuning) runs every `CONTROL_BLOCK` samples instead of every sample.
+    /// Operators, envelopes and effects still run per sample.
+    pub fn process_block(&mut self, left: &mut [f32], right: &mut [f32]) {
+        debug_assert_eq!(left.len(), right.len());
+        self.process_commands();
+        for (left, right) in left
+            .chunks_mut(CONTROL_BLOCK)
+            .zip(right.chunks_mut(CONTROL_BLOCK))
+        {
+            self.update_voice_controls(left.len());
+            for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+                let (main, side, sub) = self.mix_parts();
+                (*l, *r) = self.output_stage(main, side, sub);
+            }
+        }
+    }
+
+    /// Noise floor, effects, DC blocking, master compressor, saturation,
+    /// the vintage DAC and the output stage; also feeds the output meter
+    /// and the scope tap.
+    fn output_stage(&mut self, main: f32, side: f32, sub: f32) -> (f32, f32) {
+        let (left, right) = self.effects.process_mid_side_with_sends(
+            main + self.noise_floor.next_sample(),
+            side,
+            sub,
+            self.sub_bus_delay_send,
+            self.sub_bus_reverb_send,
+        );
+        // A NaN would circulate in the delay and reverb loops for good:
+        // clear them and drop this sample instead.
+        if !(left.is_finite() && right.is_finite()) {
+            self.effects.clear();
+            self.dc_blocker_l.reset();
+            self.dc_blocker_r.reset();
+            return (0.0, 0.0);
+        }
+        let (left, right) = self.compressor.process(
+            self.dc_blocker_l.process(left),
+            self.dc_blocker_r.process(right),
+        );
+        self.output_meter.process(left, right);
+        let mut l = Self::soft_clip(left);
+        let mut r = Self::soft_clip(right);
+        if self.vintage.dac_companding {
+            l = dac_compand(l);
+            r = dac_compand(r);
+        }
+        let (l, r) = self.output_dac.process(l, r);
+        self.scope.push((l + r) * 0.5);
         (l, r)
     }
 
+    /// Shared handle to the oscilloscope ring fed by `process_stereo`.
+    pub fn scope(&self) -> Arc<ScopeBuffer> {
+        self.scope.clone()
+    }
+
+    /// Record the audio callback statistics published with the next snapshot.
+    pub fn set_audio_stats(&mut self, stats: AudioStats) {
+        self.audio_stats = stats;
+    }
+
+    /// Keys currently down, as the snapshot's `held_keys` bit set. Keys the
+    /// sustain pedal is holding have been let go, so they are left out.
+    fn held_keys(&self) -> u128 {
+        let part_b = self.part_b.as_ref().map_or(0, |part_b| part_b.held_keys());
+        self.held_notes
+            .keys()
+            .chain(&self.mono_held_order)
+            .fold(0u128, |keys, &note| keys | 1 << (note & 0x7F))
+            & !self.sustained_notes
+            | part_b
+    }
+
+    /// What the engine is doing right now: sounding voices with their
+    /// envelope stages, the keys it thinks are down and the command queue
+    /// depth. For chasing stuck notes; allocates, so keep it off the audio
+    /// thread's hot path.
+    pub fn debug_dump(&self) -> EngineDump {
+        let dump_voices = |part: char, voices: &[Voice]| -> Vec<VoiceDump> {
+            voices
+                .iter()
+                .enumerate()
+                .filter(|(_, voice)| voice.active)
+                .map(|(index, voice)| VoiceDump {
+                    part,
+                    index,
+                    note: voice.note,
+                    frequency: voice.current_frequency,
+                    velocity: voice.velocity,
+                    operators: std::array::from_fn(|i| OperatorDump {
+                        stage: voice.operators[i].envelope.stage().label(),
+                        level: voice.operators[i].envelope.current_output(),
+                    }),
+                })
+                .collect()
+        };
+        let mut voices = dump_voices('A', &self.voices);
+        if let Some(part_b) = &self.part_b {
+            voices.extend(dump_voices('B', &part_b.voices));
+        }
+        let mut held_notes: Vec<(u8, usize)> =
+            self.held_notes.iter().map(|(&k, &v)| (k, v)).collect();
+        held_notes.sort_unstable();
+        EngineDump {
+            voices,
+            polyphony: self.polyphony,
+            voice_mode: format!("{:?}", self.voice_mode),
+            held_notes,
+            stacked_voices: self.stacked_voices.clone(),
+            sustained_notes: (0..128u8)
+                .filter(|&note| self.sustained_notes & 1 << note != 0)
+                .collect(),
+            mono_held_order: self.mono_held_order.clone(),
+            sustain_pedal: self.sustain_pedal,
+            pending_commands: self.command_rx.pending(),
+            command_capacity: self.command_rx.capacity(),
+        }
+    }
+
     /// Update and send snapshot to GUI
-    pub fn update_snapshot(&self) {
+    pub fn update_snapshot(&mut self) {
+        let part_b_voices = self.part_b.iter().flat_map(|part_b| &part_b.voices);
         let mut active_voices = 0u8;
-        for voice in &self.voices {
+        for voice in self.voices.iter().chain(part_b_voices) {
             if voice.active {
                 active_voices += 1;
             }
@@ -1042,18 +2809,75 @@ impl SynthEngine {
         let snapshot = SynthSnapshot {
             preset_name: self.preset_name.clone(),
             algorithm: self.algorithm,
+            custom_algorithm: self.custom_algorithm,
+            algorithm_matrix: self.algorithm_matrix,
             active_voices,
+            held_keys: self.held_keys(),
+            last_note: self.last_note,
+            last_note_hz: self.last_note.map(|note| {
+                let note = self.apply_transpose(note);
+                midi_to_hz(note) * 2.0_f32.powf(self.note_tune(note) / 1200.0)
+            }),
             master_volume: self.master_volume,
             master_tune: self.master_tune,
+            tuning_name: self.tuning.name.clone(),
             voice_mode: self.voice_mode,
             portamento_enable: self.portamento_enable,
+            portamento_mode: self.portamento_mode,
             portamento_time: self.portamento_time,
             portamento_glissando: self.portamento_glissando,
+            // Same collapse as the VCED encoder: the voice counts as synced
+            // if any operator is.
+            osc_key_sync: self
+                .voices
+                .first()
+                .is_some_and(|v| v.operators.iter().any(|op| op.oscillator_key_sync)),
             pitch_bend_range: self.pitch_bend_range,
+            pitch_bend_smoothing_ms: self.bend_smoothing_ms,
             transpose_semitones: self.transpose_semitones,
             pitch_mod_sensitivity: self.pitch_mod_sensitivity,
             eg_bias_sensitivity: self.eg_bias_sensitivity,
             pitch_bias_sensitivity: self.pitch_bias_sensitivity,
+            steal_fade_ms: self.steal_fade_ms,
+            attack_fade_ms: self.attack_fade_ms,
+            voice_watchdog: self.voice_watchdog,
+            voice_timeout: self.voice_timeout,
+            watchdog_releases: self.watchdog_releases
+                + self
+                    .part_b
+                    .as_ref()
+                    .map_or(0, |part_b| part_b.watchdog_releases),
+            sub_bus: SubBusSnapshot {
+                level: self.sub_bus_level,
+                delay_send: self.sub_bus_delay_send,
+                reverb_send: self.sub_bus_reverb_send,
+            },
+            voice_pan: self
+                .voices
+                .first()
+                .map(|v| VoicePanSnapshot {
+                    rate: v.pan.rate_hz,
+                    depth: v.pan.depth,
+                    stereo_phase: v.pan.stereo_phase,
+                    spread: self.voice_spread,
+                    spread_width: self.voice_spread_width,
+                    master_pan: self.master_pan,
+                })
+                .unwrap_or_default(),
+            vintage_mode: self.vintage.is_enabled(),
+            output_dac: self.output_dac.enabled,
+            output_dac_intensity: self.output_dac.intensity(),
+            noise_floor: self.noise_floor.enabled,
+            noise_level_db: self.noise_floor.level_db(),
+            hum_hz: self.noise_floor.hum_hz(),
+            unison_voices: self.unison.stacks as u8,
+            unison_detune: self.unison.detune,
+            unison_spread: self.unison.spread,
+            polyphony: self.polyphony as u8,
+            same_note_mode: self.same_note_mode,
+            patch_change_mode: self.patch_change_mode,
+            note_priority: self.note_priority,
+            mod_matrix: self.mod_matrix,
             pitch_bend: self.pitch_bend,
             mod_wheel: self.mod_wheel,
             sustain_pedal: self.sustain_pedal,
@@ -1061,6 +2885,7 @@ impl SynthEngine {
             breath: self.breath,
             foot: self.foot,
             expression: self.expression,
+            expression_source: self.expression_source,
             aftertouch_pitch_sens: self.aftertouch_pitch_sens,
             aftertouch_amp_sens: self.aftertouch_amp_sens,
             aftertouch_eg_bias_sens: self.aftertouch_eg_bias_sens,
@@ -1081,6 +2906,19 @@ impl SynthEngine {
             lfo_key_sync: self.lfo.key_sync,
             lfo_frequency_hz: self.lfo.get_frequency_hz(),
             lfo_delay_seconds: self.lfo.get_delay_seconds(),
+            lfo_sync: self.lfo.sync,
+            tempo_bpm: self.tempo_bpm,
+            lfo2: Lfo2Snapshot {
+                enabled: self.lfo2_enabled,
+                rate: self.lfo2.rate,
+                delay: self.lfo2.delay,
+                pitch_depth: self.lfo2.pitch_depth,
+                amp_depth: self.lfo2.amp_depth,
+                waveform: self.lfo2.waveform,
+                key_sync: self.lfo2.key_sync,
+                sync: self.lfo2.sync,
+                frequency_hz: self.lfo2.get_frequency_hz(),
+            },
             pitch_eg: PitchEgSnapshot {
                 enabled: self.pitch_eg.enabled,
                 rate1: self.pitch_eg.rate1,
@@ -1092,6 +2930,29 @@ impl SynthEngine {
                 level3: self.pitch_eg.level3,
                 level4: self.pitch_eg.level4,
             },
+            eq: EqSnapshot {
+                enabled: self.effects.eq.enabled,
+                low: self.effects.eq.low(),
+                mid: self.effects.eq.mid(),
+                high: self.effects.eq.high(),
+            },
+            filter: FilterSnapshot {
+                enabled: self.effects.filter.enabled,
+                cutoff_hz: self.effects.filter.cutoff_hz,
+                resonance: self.effects.filter.resonance,
+                env_amount: self.effects.filter.env_amount,
+                lfo_rate: self.effects.filter.lfo_rate,
+                lfo_depth: self.effects.filter.lfo_depth,
+                mix: self.effects.filter.mix,
+            },
+            phaser: PhaserSnapshot {
+                enabled: self.effects.phaser.enabled,
+                stages: self.effects.phaser.stages() as u8,
+                rate: self.effects.phaser.rate,
+                depth: self.effects.phaser.depth,
+                feedback: self.effects.phaser.feedback,
+                mix: self.effects.phaser.mix,
+            },
             chorus: ChorusSnapshot {
                 enabled: self.effects.chorus.enabled,
                 rate: self.effects.chorus.rate,
@@ -1110,6 +2971,10 @@ impl SynthEngine {
                 feedback: self.effects.delay.feedback,
                 mix: self.effects.delay.mix,
                 ping_pong: self.effects.delay.ping_pong,
+                sync: self.effects.delay.sync,
+                effective_time_ms: self.effects.delay.effective_time_ms(),
+                high_cut_hz: self.effects.delay.high_cut(),
+                low_cut_hz: self.effects.delay.low_cut(),
             },
             reverb: ReverbSnapshot {
                 enabled: self.effects.reverb.enabled,
@@ -1117,7 +2982,34 @@ impl SynthEngine {
                 damping: self.effects.reverb.damping,
                 mix: self.effects.reverb.mix,
                 width: self.effects.reverb.width,
+                pre_delay_ms: self.effects.reverb.pre_delay_ms,
+                reverb_type: self.effects.reverb.reverb_type(),
+            },
+            effect_order: self.effects.order(),
+            effects_bypass: self.effects.bypass,
+            extensions: self.effects.extensions,
+            effect_gains_db: EffectSlot::DEFAULT_ORDER
+                .map(|slot| self.effects.output_gain_db(slot)),
+            wet_solo: self.effects.wet_solo(),
+            compressor: CompressorSnapshot {
+                enabled: self.compressor.enabled,
+                threshold_db: self.compressor.threshold_db(),
+                ratio: self.compressor.ratio(),
+                attack_ms: self.compressor.attack_ms(),
+                release_ms: self.compressor.release_ms(),
+                makeup_db: self.compressor.makeup_db(),
+                gain_reduction_db: self.compressor.gain_reduction_db(),
             },
+            output_levels: self.output_meter.levels(),
+            operator_solo: self.operator_solo,
+            audio_stats: self.audio_stats,
+            performance: self.performance,
+            part_b_name: self
+                .part_b
+                .as_ref()
+                .map(|part_b| part_b.preset_name.clone())
+                .unwrap_or_default(),
+            debug_dump: std::mem::take(&mut self.debug_dump_requested).then(|| self.debug_dump()),
             operators: self.get_operator_snapshots(),
         };
 
@@ -1131,6 +3023,9 @@ impl SynthEngine {
                 snapshots[i] = OperatorSnapshot {
                     enabled: op.enabled,
                     frequency_ratio: op.frequency_ratio,
+                    coarse: op.coarse,
+                    fine: op.fine,
+                    extended_ratio: op.extended_ratio,
                     output_level: op.output_level,
                     detune: op.detune,
                     feedback: op.feedback,
@@ -1142,7 +3037,13 @@ impl SynthEngine {
                     key_scale_left_depth: op.key_scale_left_depth,
                     key_scale_right_depth: op.key_scale_right_depth,
                     am_sensitivity: op.am_sensitivity,
+                    pm_sensitivity: op.pm_sensitivity,
+                    lfo2: op.lfo2,
                     oscillator_key_sync: op.oscillator_key_sync,
+                    sub_bus: op.sub_bus,
+                    expression: op.expression,
+                    waveform: op.waveform,
+                    phase_offset: op.phase_offset,
                     fixed_frequency: op.fixed_frequency,
                     fixed_freq_hz: op.fixed_freq_hz,
                     rate1: op.envelope.rate1,
@@ -1154,6 +3055,9 @@ impl SynthEngine {
                     level3: op.envelope.level3,
                     level4: op.envelope.level4,
                     live_level: 0.0,
+                    recent_level: 0.0,
+                    recent_phase: 0.0,
+                    output_peak: self.operator_meter.levels()[i],
                 };
             }
 
@@ -1169,6 +3073,18 @@ impl SynthEngine {
                 }
             }
 
+            let recent = self
+                .voices
+                .iter()
+                .filter(|v| v.active)
+                .max_by_key(|v| v.note_on_id);
+            if let Some(recent) = recent {
+                for (snapshot, op) in snapshots.iter_mut().zip(&recent.operators) {
+                    snapshot.recent_level = op.envelope.current_output();
+                    snapshot.recent_phase = op.phase_degrees();
+                }
+            }
+
             snapshots
         } else {
             [OperatorSnapshot::default(); 6]
@@ -1176,7 +3092,8 @@ impl SynthEngine {
     }
 
     /// Soft saturation analogous to the DX7's μ-law-companded 12-bit DAC.
-    /// `tanh` gives smooth, symmetric, asymptotic compression toward ±1.0.
+    /// `tanh` gives smooth, symmetric, asymptotic compression toward ±1.0;
+    /// after the master compressor it is only a safety ceiling.
     fn soft_clip(sample: f32) -> f32 {
         sample.tanh()
     }
@@ -1190,9 +3107,12 @@ impl SynthEngine {
         self.preset_name = name;
     }
 
+    /// Select DX7 algorithm `alg` (1–32). This also leaves custom-algorithm
+    /// mode, so picking an algorithm or loading a preset is always heard.
     pub fn set_algorithm(&mut self, alg: u8) {
         if (1..=32).contains(&alg) {
             self.algorithm = alg;
+            self.custom_algorithm = false;
         }
     }
 
@@ -1204,8 +3124,10 @@ impl SynthEngine {
         self.pitch_mod_sensitivity = pms.min(7);
     }
 
-    pub fn set_pitch_bend_range(&mut self, range: f32) {
-        self.pitch_bend_range = range.clamp(0.0, 12.0);
+    /// Run `cmd` immediately instead of through the queue. Preset loading uses
+    /// this so function parameters take the same path as the GUI controls.
+    pub fn apply_command(&mut self, cmd: SynthCommand) {
+        self.handle_command(cmd);
     }
 
     pub fn pitch_eg_mut(&mut self) -> &mut PitchEg {
@@ -1317,22 +3239,62 @@ impl SynthEngine {
     pub fn voices(&self) -> &Vec<Voice> {
         &self.voices
     }
+
+    /// True when a block would render silence: no voice sounding in either
+    /// part and the effects bypassed, so there is no tail to ring out.
+    /// Running effects always count as busy.
+    pub fn is_idle(&self) -> bool {
+        self.effects.bypass
+            && !self.voices.iter().any(|v| v.active)
+            && self.part_b.as_deref().is_none_or(SynthEngine::is_idle)
+    }
 }
 
 /// SynthController - interface for GUI/MIDI threads to control the synthesizer
 pub struct SynthController {
     command_tx: CommandSender,
     snapshot_rx: SnapshotReceiver,
+    scope: Arc<ScopeBuffer>,
+    sample_rate: f32,
+    /// Live notes (MIDI input, computer keyboard) being captured as a phrase.
+    phrase_recorder: Option<PhraseRecorder>,
+    /// Parameter moves being captured for replay with the player.
+    automation_recorder: Option<AutomationRecorder>,
 }
 
 impl SynthController {
-    pub fn new(command_tx: CommandSender, snapshot_rx: SnapshotReceiver) -> Self {
+    pub fn new(
+        command_tx: CommandSender,
+        snapshot_rx: SnapshotReceiver,
+        scope: Arc<ScopeBuffer>,
+        sample_rate: f32,
+    ) -> Self {
         Self {
             command_tx,
             snapshot_rx,
+            scope,
+            sample_rate,
+            phrase_recorder: None,
+            automation_recorder: None,
         }
     }
 
+    /// Copy the most recent output samples (mono, post-clip) for display.
+    pub fn scope_latest(&self, out: &mut [f32]) {
+        self.scope.copy_latest(out);
+    }
+
+    /// Sample rate of the engine feeding the scope tap.
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Record the rate the engine was switched to, so the spectrum axis
+    /// follows an audio device change.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
     /// Get the latest snapshot from the audio thread (reference)
     #[allow(dead_code)]
     pub fn get_snapshot(&self) -> &SynthSnapshot {
@@ -1344,24 +3306,90 @@ impl SynthController {
         self.snapshot_rx.get().clone()
     }
 
+    /// Snapshots published so far; unchanged means `snapshot()` would
+    /// return the same data as last time.
+    pub fn snapshot_sequence(&self) -> u64 {
+        self.snapshot_rx.sequence()
+    }
+
+    /// Handle for another thread to wait on new snapshots.
+    pub fn snapshot_watcher(&self) -> SnapshotWatcher {
+        self.snapshot_rx.watcher()
+    }
+
     /// Send a command to the audio thread
     pub fn send(&mut self, command: SynthCommand) -> bool {
+        if let Some(recorder) = &mut self.automation_recorder {
+            recorder.record(&command);
+        }
+        self.command_tx.send(command)
+    }
+
+    /// Send a recorded parameter move back out, without capturing it again.
+    pub fn replay(&mut self, command: SynthCommand) -> bool {
         self.command_tx.send(command)
     }
 
     // Convenience methods for common operations
     pub fn note_on(&mut self, note: u8, velocity: u8) {
+        if let Some(recorder) = &mut self.phrase_recorder {
+            recorder.note_on(note, velocity);
+        }
         self.send(SynthCommand::NoteOn { note, velocity });
     }
 
     pub fn note_off(&mut self, note: u8) {
+        if let Some(recorder) = &mut self.phrase_recorder {
+            recorder.note_off(note);
+        }
         self.send(SynthCommand::NoteOff { note });
     }
 
+    /// Start capturing the notes played through `note_on` / `note_off`,
+    /// discarding any capture in progress.
+    pub fn start_phrase_recording(&mut self) {
+        self.phrase_recorder = Some(PhraseRecorder::new());
+    }
+
+    pub fn is_recording_phrase(&self) -> bool {
+        self.phrase_recorder.is_some()
+    }
+
+    /// Stop capturing. Returns the phrase and its loop length in seconds,
+    /// or None if nothing was played.
+    pub fn finish_phrase_recording(&mut self) -> Option<(Vec<NoteEvent>, f32)> {
+        self.phrase_recorder.take()?.finish()
+    }
+
+    /// Start capturing parameter moves sent through this controller,
+    /// discarding any capture in progress.
+    pub fn start_automation_recording(&mut self) {
+        self.automation_recorder = Some(AutomationRecorder::new());
+    }
+
+    pub fn is_recording_automation(&self) -> bool {
+        self.automation_recorder.is_some()
+    }
+
+    /// Stop capturing. Returns the moves, timed from when recording began.
+    pub fn finish_automation_recording(&mut self) -> Vec<AutomationEvent> {
+        self.automation_recorder
+            .take()
+            .map_or_else(Vec::new, AutomationRecorder::finish)
+    }
+
     pub fn set_algorithm(&mut self, algorithm: u8) {
         self.send(SynthCommand::SetAlgorithm(algorithm));
     }
 
+    pub fn set_custom_algorithm(&mut self, on: bool) {
+        self.send(SynthCommand::SetCustomAlgorithm(on));
+    }
+
+    pub fn set_algorithm_matrix(&mut self, matrix: AlgorithmMatrix) {
+        self.send(SynthCommand::SetAlgorithmMatrix(matrix));
+    }
+
     pub fn set_master_volume(&mut self, volume: f32) {
         self.send(SynthCommand::SetMasterVolume(volume));
     }
@@ -1370,6 +3398,17 @@ impl SynthController {
         self.send(SynthCommand::SetMasterTune(cents));
     }
 
+    /// Replace the per-key tuning table. `Tuning::default()` restores
+    /// equal temperament.
+    pub fn set_tuning(&mut self, tuning: Tuning) {
+        self.send(SynthCommand::SetTuning(Box::new(tuning)));
+    }
+
+    /// Retune single keys (MTS single note tuning change).
+    pub fn retune_notes(&mut self, changes: Vec<(u8, f32)>) {
+        self.send(SynthCommand::RetuneNotes(changes));
+    }
+
     pub fn set_voice_mode(&mut self, mode: VoiceMode) {
         let code = match mode {
             VoiceMode::Poly => 0,
@@ -1383,7 +3422,12 @@ impl SynthController {
         self.send(SynthCommand::SetPortamentoGlissando(on));
     }
 
-    #[allow(dead_code)]
+    pub fn set_osc_key_sync(&mut self, on: bool) {
+        self.send(SynthCommand::SetOscKeySync(on));
+    }
+
+    /// KEY TRANSPOSE in semitones (clamped to ±24), applied to every note
+    /// before its pitch is looked up; master tune is separate.
     pub fn set_transpose(&mut self, semitones: i8) {
         self.send(SynthCommand::SetTranspose(semitones));
     }
@@ -1393,126 +3437,292 @@ impl SynthController {
         self.send(SynthCommand::SetPitchModSensitivity(pms));
     }
 
-    pub fn set_eg_bias_sensitivity(&mut self, sens: u8) {
-        self.send(SynthCommand::SetEgBiasSensitivity(sens));
+    /// Bend glide time in ms, 0 (instant) to `MAX_BEND_SMOOTHING_MS`.
+    pub fn set_pitch_bend_smoothing(&mut self, ms: f32) {
+        self.send(SynthCommand::SetPitchBendSmoothing(ms));
     }
 
-    pub fn set_pitch_bias_sensitivity(&mut self, sens: u8) {
-        self.send(SynthCommand::SetPitchBiasSensitivity(sens));
+    pub fn set_steal_fade_time(&mut self, ms: f32) {
+        self.send(SynthCommand::SetStealFadeTime(ms));
     }
 
-    pub fn aftertouch(&mut self, value: f32) {
-        self.send(SynthCommand::Aftertouch(value));
+    pub fn set_voice_pan_rate(&mut self, hz: f32) {
+        self.send(SynthCommand::SetVoicePanRate(hz));
     }
 
-    pub fn set_aftertouch_pitch_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetAftertouchPitchSens(sens));
+    pub fn set_voice_pan_depth(&mut self, depth: f32) {
+        self.send(SynthCommand::SetVoicePanDepth(depth));
     }
 
-    pub fn set_aftertouch_amp_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetAftertouchAmpSens(sens));
+    pub fn set_voice_pan_stereo_phase(&mut self, phase: f32) {
+        self.send(SynthCommand::SetVoicePanStereoPhase(phase));
     }
 
-    pub fn set_aftertouch_eg_bias_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetAftertouchEgBiasSens(sens));
+    pub fn set_voice_spread(&mut self, spread: VoiceSpread) {
+        self.send(SynthCommand::SetVoiceSpread(spread.to_code()));
     }
 
-    pub fn set_aftertouch_pitch_bias_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetAftertouchPitchBiasSens(sens));
+    pub fn set_voice_spread_width(&mut self, width: f32) {
+        self.send(SynthCommand::SetVoiceSpreadWidth(width));
     }
 
-    pub fn breath_controller(&mut self, value: f32) {
-        self.send(SynthCommand::BreathController(value));
+    pub fn set_master_pan(&mut self, pan: f32) {
+        self.send(SynthCommand::SetMasterPan(pan));
     }
 
-    pub fn set_breath_pitch_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetBreathPitchSens(sens));
+    /// Unison stacks per note, 1 (off) to `MAX_UNISON`. Applies from the
+    /// next note-on.
+    pub fn set_unison_voices(&mut self, stacks: u8) {
+        self.send(SynthCommand::SetUnisonVoices(stacks));
     }
 
-    pub fn set_breath_amp_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetBreathAmpSens(sens));
+    /// Unison detune in cents, 0..=`MAX_UNISON_DETUNE`.
+    pub fn set_unison_detune(&mut self, cents: f32) {
+        self.send(SynthCommand::SetUnisonDetune(cents));
     }
 
-    pub fn set_breath_eg_bias_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetBreathEgBiasSens(sens));
+    /// Stereo width of the unison stacks, 0..1.
+    pub fn set_unison_spread(&mut self, spread: f32) {
+        self.send(SynthCommand::SetUnisonSpread(spread));
     }
 
-    pub fn set_breath_pitch_bias_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetBreathPitchBiasSens(sens));
+    pub fn set_vintage_mode(&mut self, on: bool) {
+        self.send(SynthCommand::SetVintageMode(on));
     }
 
-    pub fn foot_controller(&mut self, value: f32) {
-        self.send(SynthCommand::FootController(value));
+    pub fn set_output_dac(&mut self, on: bool) {
+        self.send(SynthCommand::SetOutputDac(on));
     }
 
-    pub fn set_foot_volume_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetFootVolumeSens(sens));
+    /// Share of the emulated output stage in the mix, 0..1.
+    pub fn set_output_dac_intensity(&mut self, intensity: f32) {
+        self.send(SynthCommand::SetOutputDacIntensity(intensity));
     }
 
-    pub fn set_foot_pitch_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetFootPitchSens(sens));
+    pub fn set_noise_floor(&mut self, on: bool) {
+        self.send(SynthCommand::SetNoiseFloor(on));
     }
 
-    pub fn set_foot_amp_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetFootAmpSens(sens));
+    /// Noise floor level in dBFS, `MIN_NOISE_DB` to `MAX_NOISE_DB`.
+    pub fn set_noise_level(&mut self, db: f32) {
+        self.send(SynthCommand::SetNoiseLevel(db));
     }
 
-    pub fn set_foot_eg_bias_sens(&mut self, sens: u8) {
-        self.send(SynthCommand::SetFootEgBiasSens(sens));
+    /// Mains hum at 50 or 60 Hz.
+    pub fn set_hum_frequency(&mut self, hz: f32) {
+        self.send(SynthCommand::SetHumFrequency(hz));
     }
 
-    pub fn expression(&mut self, value: f32) {
-        self.send(SynthCommand::Expression(value));
+    pub fn set_compressor_param(&mut self, param: CompressorParam, value: f32) {
+        self.send(SynthCommand::SetCompressorParam { param, value });
     }
 
-    pub fn set_bank_msb(&mut self, value: u8) {
-        self.send(SynthCommand::SetBankSelectMsb(value));
+    pub fn set_polyphony(&mut self, voices: u8) {
+        self.send(SynthCommand::SetPolyphony(voices));
     }
 
-    pub fn set_bank_lsb(&mut self, value: u8) {
-        self.send(SynthCommand::SetBankSelectLsb(value));
+    /// Fill (`Some`) or clear (`None`) one mod-matrix slot.
+    pub fn set_mod_route(&mut self, slot: u8, route: Option<ModRoute>) {
+        self.send(SynthCommand::SetModRoute { slot, route });
     }
 
-    pub fn program_change(&mut self, program: u8) {
-        self.send(SynthCommand::ProgramChange(program));
+    pub fn set_note_priority(&mut self, priority: NotePriority) {
+        let code = match priority {
+            NotePriority::Last => 0,
+            NotePriority::Low => 1,
+            NotePriority::High => 2,
+        };
+        self.send(SynthCommand::SetNotePriority(code));
     }
 
-    pub fn set_pitch_eg_param(&mut self, param: PitchEgParam, value: f32) {
-        self.send(SynthCommand::SetPitchEgParam { param, value });
+    pub fn set_same_note_mode(&mut self, mode: SameNoteMode) {
+        let code = match mode {
+            SameNoteMode::Retrigger => 0,
+            SameNoteMode::Stack => 1,
+        };
+        self.send(SynthCommand::SetSameNoteMode(code));
     }
 
-    pub fn set_pitch_bend_range(&mut self, range: f32) {
-        self.send(SynthCommand::SetPitchBendRange(range));
+    pub fn set_patch_change_mode(&mut self, mode: PatchChangeMode) {
+        let code = match mode {
+            PatchChangeMode::Crossfade => 0,
+            PatchChangeMode::Retrigger => 1,
+        };
+        self.send(SynthCommand::SetPatchChangeMode(code));
     }
 
-    pub fn set_portamento_enable(&mut self, enable: bool) {
-        self.send(SynthCommand::SetPortamentoEnable(enable));
+    pub fn set_sub_bus_level(&mut self, level: f32) {
+        self.send(SynthCommand::SetSubBusLevel(level));
     }
 
-    pub fn set_portamento_time(&mut self, time: f32) {
-        self.send(SynthCommand::SetPortamentoTime(time));
+    pub fn set_sub_bus_delay_send(&mut self, send: f32) {
+        self.send(SynthCommand::SetSubBusDelaySend(send));
     }
 
-    pub fn pitch_bend(&mut self, value: i16) {
-        self.send(SynthCommand::PitchBend(value));
+    pub fn set_sub_bus_reverb_send(&mut self, send: f32) {
+        self.send(SynthCommand::SetSubBusReverbSend(send));
     }
 
-    pub fn mod_wheel(&mut self, value: f32) {
-        self.send(SynthCommand::ModWheel(value));
+    pub fn set_attack_fade_time(&mut self, ms: f32) {
+        self.send(SynthCommand::SetAttackFadeTime(ms));
     }
 
-    pub fn sustain_pedal(&mut self, pressed: bool) {
-        self.send(SynthCommand::SustainPedal(pressed));
+    pub fn set_voice_watchdog(&mut self, on: bool) {
+        self.send(SynthCommand::SetVoiceWatchdog(on));
     }
 
-    pub fn set_operator_param(&mut self, operator: u8, param: OperatorParam, value: f32) {
-        self.send(SynthCommand::SetOperatorParam {
+    /// Seconds after its note-on at which any voice is released; 0 = never.
+    pub fn set_voice_timeout(&mut self, seconds: f32) {
+        self.send(SynthCommand::SetVoiceTimeout(seconds));
+    }
+
+    pub fn set_eg_bias_sensitivity(&mut self, sens: u8) {
+        self.send(SynthCommand::SetEgBiasSensitivity(sens));
+    }
+
+    pub fn set_pitch_bias_sensitivity(&mut self, sens: u8) {
+        self.send(SynthCommand::SetPitchBiasSensitivity(sens));
+    }
+
+    pub fn aftertouch(&mut self, value: f32) {
+        self.send(SynthCommand::Aftertouch(value));
+    }
+
+    pub fn poly_aftertouch(&mut self, note: u8, value: f32) {
+        self.send(SynthCommand::PolyAftertouch { note, value });
+    }
+
+    pub fn set_aftertouch_pitch_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetAftertouchPitchSens(sens));
+    }
+
+    pub fn set_aftertouch_amp_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetAftertouchAmpSens(sens));
+    }
+
+    pub fn set_aftertouch_eg_bias_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetAftertouchEgBiasSens(sens));
+    }
+
+    pub fn set_aftertouch_pitch_bias_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetAftertouchPitchBiasSens(sens));
+    }
+
+    pub fn breath_controller(&mut self, value: f32) {
+        self.send(SynthCommand::BreathController(value));
+    }
+
+    pub fn set_breath_pitch_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetBreathPitchSens(sens));
+    }
+
+    pub fn set_breath_amp_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetBreathAmpSens(sens));
+    }
+
+    pub fn set_breath_eg_bias_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetBreathEgBiasSens(sens));
+    }
+
+    pub fn set_breath_pitch_bias_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetBreathPitchBiasSens(sens));
+    }
+
+    pub fn foot_controller(&mut self, value: f32) {
+        self.send(SynthCommand::FootController(value));
+    }
+
+    pub fn set_foot_volume_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetFootVolumeSens(sens));
+    }
+
+    pub fn set_foot_pitch_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetFootPitchSens(sens));
+    }
+
+    pub fn set_foot_amp_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetFootAmpSens(sens));
+    }
+
+    pub fn set_foot_eg_bias_sens(&mut self, sens: u8) {
+        self.send(SynthCommand::SetFootEgBiasSens(sens));
+    }
+
+    pub fn expression(&mut self, value: f32) {
+        self.send(SynthCommand::Expression(value));
+    }
+
+    /// Pick the controller that scales operators flagged for expression.
+    pub fn set_expression_source(&mut self, source: ExpressionSource) {
+        self.send(SynthCommand::SetExpressionSource(source));
+    }
+
+    pub fn set_bank_msb(&mut self, value: u8) {
+        self.send(SynthCommand::SetBankSelectMsb(value));
+    }
+
+    pub fn set_bank_lsb(&mut self, value: u8) {
+        self.send(SynthCommand::SetBankSelectLsb(value));
+    }
+
+    pub fn program_change(&mut self, program: u8) {
+        self.send(SynthCommand::ProgramChange(program));
+    }
+
+    /// Presets Program Change numbers load, from `ProgramMap::resolve`.
+    pub fn set_program_map(&mut self, map: Vec<Option<Dx7Preset>>) {
+        self.send(SynthCommand::SetProgramMap(map));
+    }
+
+    pub fn set_pitch_eg_param(&mut self, param: PitchEgParam, value: f32) {
+        self.send(SynthCommand::SetPitchEgParam { param, value });
+    }
+
+    pub fn set_pitch_bend_range(&mut self, range: f32) {
+        self.send(SynthCommand::SetPitchBendRange(range));
+    }
+
+    pub fn set_portamento_mode(&mut self, mode: PortamentoMode) {
+        let code = match mode {
+            PortamentoMode::FullTime => 0,
+            PortamentoMode::Fingered => 1,
+        };
+        self.send(SynthCommand::SetPortamentoMode(code));
+    }
+
+    pub fn set_portamento_enable(&mut self, enable: bool) {
+        self.send(SynthCommand::SetPortamentoEnable(enable));
+    }
+
+    pub fn set_portamento_time(&mut self, time: f32) {
+        self.send(SynthCommand::SetPortamentoTime(time));
+    }
+
+    pub fn pitch_bend(&mut self, value: i16) {
+        self.send(SynthCommand::PitchBend(value));
+    }
+
+    pub fn mod_wheel(&mut self, value: f32) {
+        self.send(SynthCommand::ModWheel(value));
+    }
+
+    pub fn sustain_pedal(&mut self, pressed: bool) {
+        self.send(SynthCommand::SustainPedal(pressed));
+    }
+
+    pub fn set_operator_param(&mut self, operator: u8, param: OperatorParam, value: f32) {
+        self.send(SynthCommand::SetOperatorParam {
             operator,
             param,
             value,
         });
     }
 
+    /// Solo operators for auditioning: bit `i` = OP `i + 1`, 0 = off.
+    pub fn set_operator_solo(&mut self, mask: u8) {
+        self.send(SynthCommand::SetOperatorSolo(mask));
+    }
+
     pub fn set_envelope_param(&mut self, operator: u8, param: EnvelopeParam, value: f32) {
         self.send(SynthCommand::SetEnvelopeParam {
             operator,
@@ -1521,10 +3731,97 @@ impl SynthController {
         });
     }
 
+    /// Write copied operator settings onto `operator` as one batch of
+    /// parameter commands. `envelope_only` pastes just the EG rates and
+    /// levels. The ON switch is never pasted.
+    pub fn paste_operator(&mut self, operator: u8, params: &OperatorSnapshot, envelope_only: bool) {
+        let envelope = [
+            (EnvelopeParam::Rate1, params.rate1),
+            (EnvelopeParam::Rate2, params.rate2),
+            (EnvelopeParam::Rate3, params.rate3),
+            (EnvelopeParam::Rate4, params.rate4),
+            (EnvelopeParam::Level1, params.level1),
+            (EnvelopeParam::Level2, params.level2),
+            (EnvelopeParam::Level3, params.level3),
+            (EnvelopeParam::Level4, params.level4),
+        ];
+        for (param, value) in envelope {
+            self.set_envelope_param(operator, param, value);
+        }
+        if envelope_only {
+            return;
+        }
+
+        let flag = |on: bool| if on { 1.0 } else { 0.0 };
+        let operator_params = [
+            (OperatorParam::Ratio, params.frequency_ratio),
+            (OperatorParam::ExtendedRatio, flag(params.extended_ratio)),
+            (OperatorParam::Level, params.output_level),
+            (OperatorParam::Detune, params.detune),
+            (OperatorParam::Feedback, params.feedback),
+            (
+                OperatorParam::VelocitySensitivity,
+                params.velocity_sensitivity,
+            ),
+            (OperatorParam::KeyScaleRate, params.key_scale_rate),
+            (
+                OperatorParam::KeyScaleBreakpoint,
+                params.key_scale_breakpoint as f32,
+            ),
+            (
+                OperatorParam::KeyScaleLeftDepth,
+                params.key_scale_left_depth,
+            ),
+            (
+                OperatorParam::KeyScaleRightDepth,
+                params.key_scale_right_depth,
+            ),
+            (
+                OperatorParam::KeyScaleLeftCurve,
+                params.key_scale_left_curve.to_dx7_code() as f32,
+            ),
+            (
+                OperatorParam::KeyScaleRightCurve,
+                params.key_scale_right_curve.to_dx7_code() as f32,
+            ),
+            (OperatorParam::AmSensitivity, params.am_sensitivity as f32),
+            (
+                OperatorParam::PmSensitivity,
+                params.pm_sensitivity.map_or(-1.0, f32::from),
+            ),
+            (OperatorParam::Lfo2, flag(params.lfo2)),
+            (
+                OperatorParam::OscillatorKeySync,
+                flag(params.oscillator_key_sync),
+            ),
+            (OperatorParam::SubBus, flag(params.sub_bus)),
+            (OperatorParam::Expression, flag(params.expression)),
+            (OperatorParam::Waveform, params.waveform.to_code() as f32),
+            (OperatorParam::PhaseOffset, params.phase_offset),
+            (OperatorParam::FixedFreqHz, params.fixed_freq_hz),
+            (OperatorParam::FixedFrequency, flag(params.fixed_frequency)),
+        ];
+        for (param, value) in operator_params {
+            self.set_operator_param(operator, param, value);
+        }
+    }
+
     pub fn set_lfo_param(&mut self, param: LfoParam, value: f32) {
         self.send(SynthCommand::SetLfoParam { param, value });
     }
 
+    pub fn set_lfo2_param(&mut self, param: LfoParam, value: f32) {
+        self.send(SynthCommand::SetLfo2Param { param, value });
+    }
+
+    pub fn set_lfo2_enabled(&mut self, enabled: bool) {
+        self.send(SynthCommand::SetLfo2Enabled(enabled));
+    }
+
+    pub fn set_tempo(&mut self, bpm: f32) {
+        self.send(SynthCommand::SetTempo(bpm));
+    }
+
     pub fn set_effect_param(&mut self, effect: EffectType, param: EffectParam, value: f32) {
         self.send(SynthCommand::SetEffectParam {
             effect,
@@ -1533,21 +3830,67 @@ impl SynthController {
         });
     }
 
+    pub fn set_effect_order(&mut self, order: [EffectSlot; EFFECT_SLOTS]) {
+        self.send(SynthCommand::SetEffectOrder(order));
+    }
+
+    pub fn set_effects_bypass(&mut self, bypass: bool) {
+        self.send(SynthCommand::SetEffectsBypass(bypass));
+    }
+
+    pub fn set_extensions(&mut self, extensions: bool) {
+        self.send(SynthCommand::SetExtensions(extensions));
+    }
+
     pub fn voice_initialize(&mut self) {
         self.send(SynthCommand::VoiceInitialize);
     }
 
+    pub fn function_initialize(&mut self) {
+        self.send(SynthCommand::FunctionInitialize);
+    }
+
     pub fn panic(&mut self) {
         self.send(SynthCommand::Panic);
     }
 
-    /// Load a preset by index (for MIDI program change 0xC0).
-    /// MIDI now goes through `program_change`; this remains for the GUI / direct callers.
-    #[allow(dead_code)]
+    /// Release every note normally (MIDI CC123); `panic` cuts them.
+    pub fn all_notes_off(&mut self) {
+        self.send(SynthCommand::AllNotesOff);
+    }
+
+    /// Centre bend and zero the mod wheel, pressure and sustain (MIDI CC121).
+    pub fn reset_controllers(&mut self) {
+        self.send(SynthCommand::ResetControllers);
+    }
+
+    /// Load a preset by index. MIDI goes through `program_change` (which adds
+    /// the bank offset); the stdin RPC and direct callers use this.
     pub fn load_preset(&mut self, index: usize) {
         self.send(SynthCommand::LoadPreset(index));
     }
 
+    /// Apply `preset` as the live edit buffer; the bank is not changed.
+    pub fn apply_preset(&mut self, preset: Dx7Preset) {
+        self.send(SynthCommand::ApplyPreset(Box::new(preset)));
+    }
+
+    /// Switch between single, dual and split play and set the parts'
+    /// volume and transpose.
+    pub fn set_performance(&mut self, performance: Performance) {
+        self.send(SynthCommand::SetPerformance(performance));
+    }
+
+    /// Load the voice part B of a dual / split performance plays.
+    pub fn load_part_b(&mut self, preset: Dx7Preset) {
+        self.send(SynthCommand::LoadPartB(Box::new(preset)));
+    }
+
+    /// Ask for an engine debug dump with the next snapshot.
+    pub fn request_debug_dump(&mut self) {
+        self.send(SynthCommand::RequestDebugDump);
+    }
+
     /// Apply a SysEx-parsed single voice as the live edit buffer.
     pub fn load_sysex_single_voice(&mut self, preset: Dx7Preset) {
         self.send(SynthCommand::LoadSysExSingleVoice(Box::new(preset)));
@@ -1565,7 +3908,20 @@ pub fn create_synth(sample_rate: f32) -> (SynthEngine, SynthController) {
     let (snapshot_tx, snapshot_rx) = create_snapshot_channel();
 
     let engine = SynthEngine::new(sample_rate, command_rx, snapshot_tx);
-    let controller = SynthController::new(command_tx, snapshot_rx);
+    let controller = SynthController::new(command_tx, snapshot_rx, engine.scope(), sample_rate);
+
+    (engine, controller)
+}
+
+/// A lighter engine for auditioning presets next to the main one: no part B
+/// and the effects chain bypassed, so it renders nothing once its notes end.
+pub fn create_preview_synth(sample_rate: f32) -> (SynthEngine, SynthController) {
+    let (command_tx, command_rx) = create_command_queue();
+    let (snapshot_tx, snapshot_rx) = create_snapshot_channel();
+
+    let mut engine = SynthEngine::new_part(sample_rate, command_rx, snapshot_tx);
+    engine.effects.bypass = true;
+    let controller = SynthController::new(command_tx, snapshot_rx, engine.scope(), sample_rate);
 
     (engine, controller)
 }
@@ -1573,7 +3929,9 @@ pub fn create_synth(sample_rate: f32) -> (SynthEngine, SynthController) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::performance::PerformanceMode;
     use crate::presets::{PresetLfo, PresetOperator, PresetPitchEg};
+    use crate::tempo::{NoteFeel, NoteValue};
 
     const SR: f32 = 44_100.0;
 
@@ -1739,7 +4097,7 @@ mod tests {
     fn voice_inactive_returns_zero_output() {
         let mut v = Voice::new_with_sample_rate(SR);
         let s = v.process(1, 0.0, 2.0, 0.0, false, 0.0, 0.0, 0.0, 0.0, 0.0);
-        assert_eq!(s, 0.0);
+        assert_eq!(s, (0.0, 0.0));
     }
 
     #[test]
@@ -1780,6 +4138,107 @@ mod tests {
         );
     }
 
+    #[test]
+    fn voice_steal_then_trigger_crossfades_from_tail() {
+        let mut v = Voice::new_with_sample_rate(SR);
+        v.trigger(69, 1.0, 0.0, false);
+        let mut last = 0.0;
+        for _ in 0..2048 {
+            last = v
+                .process(1, 0.0, 2.0, 0.0, false, 0.0, 0.0, 0.0, 0.0, 0.0)
+                .0;
+        }
+        assert!(last.abs() > 0.0);
+
+        v.steal_voice();
+        v.trigger(72, 1.0, 0.0, false);
+        assert_eq!(v.fade_state, VoiceFadeState::Crossfade);
+        // The old note carries on at full level for the first sample instead of
+        // dropping to silence; a plain fade-in would start at exactly 0.
+        let (first, _) = v.process(1, 0.0, 2.0, 0.0, false, 0.0, 0.0, 0.0, 0.0, 0.0);
+        assert!(first.abs() > 0.0);
+
+        let steal_samples = (SR * DEFAULT_STEAL_FADE_MS / 1000.0) as usize + 2;
+        for _ in 0..steal_samples {
+            v.process(1, 0.0, 2.0, 0.0, false, 0.0, 0.0, 0.0, 0.0, 0.0);
+        }
+        assert_eq!(v.fade_state, VoiceFadeState::Normal);
+        assert!(v.active);
+    }
+
+    #[test]
+    fn unison_stacks_are_detuned_and_spread_inside_one_voice() {
+        let render_side = |stacks: usize| {
+            let mut v = Voice::new_with_sample_rate(SR);
+            v.set_unison(stacks, 20.0, 1.0);
+            v.trigger(69, 1.0, 0.0, false);
+            let mut side_energy = 0.0;
+            for _ in 0..2048 {
+                v.update_control(&ControlSignals::default(), 1);
+                let (mid, side, _) = v.render(1, None);
+                assert!(mid.is_finite() && side.is_finite());
+                side_energy += side * side;
+            }
+            (v, side_energy)
+        };
+        let (mono, mono_side) = render_side(1);
+        assert_eq!(mono.stacks, 1);
+        assert_eq!(mono_side, 0.0);
+
+        let (wide, wide_side) = render_side(3);
+        assert_eq!(wide.stacks, 3);
+        assert!(wide_side > 0.0);
+        let cents = |ratio: f32| 1200.0 * ratio.log2();
+        assert!((cents(wide.unison.placement(0, 3).0) + 20.0).abs() < 1e-3);
+        assert_eq!(wide.unison.placement(1, 3), (1.0, 0.0));
+        assert!((cents(wide.unison.placement(2, 3).0) - 20.0).abs() < 1e-3);
+
+        let (clamped, _) = render_side(MAX_UNISON + 3);
+        assert_eq!(clamped.stacks, MAX_UNISON);
+    }
+
+    #[test]
+    fn engine_unison_uses_one_voice_per_note() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_unison_voices(4);
+        ctrl.set_unison_detune(80.0); // clamped
+        ctrl.set_unison_spread(1.0);
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        let mut side = 0.0;
+        for _ in 0..2048 {
+            side += engine.mix_voices().1.abs();
+        }
+        assert!(side > 0.0);
+
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.active_voices, 1);
+        assert_eq!(snap.unison_voices, 4);
+        assert_eq!(snap.unison_detune, MAX_UNISON_DETUNE);
+        assert_eq!(snap.unison_spread, 1.0);
+    }
+
+    #[test]
+    fn voice_fresh_trigger_uses_attack_fade() {
+        let mut v = Voice::new_with_sample_rate(SR);
+        v.set_fade_times(2.0, 10.0);
+        v.trigger(69, 1.0, 0.0, false);
+        assert_eq!(v.fade_state, VoiceFadeState::FadeIn);
+        assert!((v.fade_rate - 1.0 / (SR * 0.010)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn voice_set_fade_times_clamps_and_zero_is_instant() {
+        let mut v = Voice::new_with_sample_rate(SR);
+        v.set_fade_times(-1.0, 500.0);
+        assert_eq!(v.steal_fade_ms, 0.0);
+        assert_eq!(v.attack_fade_ms, MAX_FADE_MS);
+        v.set_fade_times(0.0, 0.0);
+        v.trigger(69, 1.0, 0.0, false);
+        assert_eq!(v.fade_rate, 1.0);
+    }
+
     #[test]
     fn voice_retarget_changes_note_without_envelope_retrigger() {
         let mut v = Voice::new_with_sample_rate(SR);
@@ -1802,8 +4261,7 @@ mod tests {
         v.trigger(72, 1.0, 0.0, true);
         assert_ne!(v.target_frequency, initial);
         let target = v.target_frequency;
-        // Asymptotic glide: at portamento_time=10 the half-life is ~30ms, so
-        // SR/2 (~500ms) gets us deep into the convergence tail.
+        // At portamento_time=10 the octave takes ~52ms, well inside SR/2.
         for _ in 0..(SR as usize / 2) {
             v.process(1, 0.0, 2.0, 10.0, false, 0.0, 0.0, 0.0, 0.0, 0.0);
             if (v.current_frequency - target).abs() < 1.0 {
@@ -1817,6 +4275,50 @@ mod tests {
         assert!(v.current_frequency <= target * 1.01, "should not overshoot");
     }
 
+    /// Seconds a voice takes to glide `semitones` up at portamento `time`.
+    fn glide_seconds(time: f32, semitones: u8) -> f32 {
+        let mut v = Voice::new_with_sample_rate(SR);
+        v.trigger(48, 1.0, 0.0, true);
+        v.trigger(48 + semitones, 1.0, 0.0, true);
+        let control = ControlSignals {
+            portamento_time: time,
+            pitch_bend_range: 2.0,
+            ..ControlSignals::default()
+        };
+        let mut blocks = 0;
+        while v.current_frequency != v.target_frequency {
+            v.update_control(&control, CONTROL_BLOCK);
+            blocks += 1;
+            assert!(blocks < 100_000, "glide never arrives");
+        }
+        (blocks * CONTROL_BLOCK) as f32 / SR
+    }
+
+    #[test]
+    fn portamento_follows_the_dx7_time_curve() {
+        assert_eq!(portamento_semitones_per_second(0.0), 350.0);
+        // Halves every 1 / 0.062 ≈ 16 steps.
+        let ratio = portamento_semitones_per_second(50.0) / portamento_semitones_per_second(66.13);
+        assert!((ratio - 2.0).abs() < 0.01, "ratio {ratio}");
+        assert_eq!(
+            portamento_semitones_per_second(150.0),
+            portamento_semitones_per_second(99.0)
+        );
+
+        // An octave: ~34 ms at 0, ~290 ms at 50, ~2.4 s at 99.
+        for (time, expected) in [(0.0, 0.0343), (50.0, 0.2940), (99.0, 2.4145)] {
+            let seconds = glide_seconds(time, 12);
+            assert!(
+                (seconds - expected).abs() < expected * 0.02 + 0.001,
+                "time {time}: {seconds}s, expected {expected}s"
+            );
+        }
+        // Constant speed: twice the interval takes twice as long.
+        let one = glide_seconds(40.0, 12);
+        let two = glide_seconds(40.0, 24);
+        assert!((two / one - 2.0).abs() < 0.02, "{one}s vs {two}s");
+    }
+
     #[test]
     fn voice_stop_resets_state() {
         let mut v = Voice::new_with_sample_rate(SR);
@@ -1834,6 +4336,33 @@ mod tests {
         let (engine, _ctrl) = make_engine();
         assert_eq!(engine.preset_name, "Init Voice");
         assert_eq!(engine.algorithm, 1);
+        assert_eq!(engine.pitch_mod_sensitivity, 3);
+        for voice in &engine.voices {
+            assert_eq!(voice.operators[0].output_level, 99.0);
+            assert!(voice.operators[1..].iter().all(|op| op.output_level == 0.0));
+        }
+    }
+
+    #[test]
+    fn preview_engine_has_no_part_b_and_idles_between_notes() {
+        let (mut engine, mut ctrl) = create_preview_synth(44_100.0);
+        assert!(engine.part_b.is_none());
+        assert!(engine.effects.bypass);
+        assert!(engine.is_idle());
+
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        assert!(!engine.is_idle());
+        ctrl.note_off(60);
+        engine.process_commands();
+        for _ in 0..44_100 {
+            engine.process_stereo();
+        }
+        assert!(engine.is_idle());
+
+        // The main engine boots with chorus and reverb running: never idle.
+        let (main, _ctrl) = make_engine();
+        assert!(!main.is_idle());
     }
 
     #[test]
@@ -1850,6 +4379,38 @@ mod tests {
         assert_eq!(engine.algorithm, 7);
     }
 
+    #[test]
+    fn engine_custom_algorithm_replaces_the_dx7_routing() {
+        let render = |setup: &dyn Fn(&mut SynthController)| {
+            let (mut engine, mut ctrl) = make_engine();
+            setup(&mut ctrl);
+            ctrl.note_on(60, 100);
+            engine.process_commands();
+            (0..1024).map(|_| engine.process()).collect::<Vec<_>>()
+        };
+        let dx7 = render(&|ctrl| ctrl.set_algorithm(32));
+        let custom = render(&|ctrl| {
+            ctrl.set_algorithm_matrix(AlgorithmMatrix::from_algorithm(32, 0.0));
+            ctrl.set_custom_algorithm(true);
+        });
+        let plain = render(&|_| {});
+        assert!(dx7.iter().zip(&custom).all(|(a, b)| (a - b).abs() < 1e-5));
+        assert!(dx7.iter().zip(&plain).any(|(a, b)| (a - b).abs() > 1e-3));
+
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_custom_algorithm(true);
+        engine.process_commands();
+        engine.update_snapshot();
+        assert!(ctrl.snapshot().custom_algorithm);
+        ctrl.set_algorithm(5);
+        engine.process_commands();
+        engine.update_snapshot();
+        assert!(
+            !ctrl.snapshot().custom_algorithm,
+            "picking an algorithm leaves custom mode"
+        );
+    }
+
     #[test]
     fn engine_set_master_volume_clamps_to_zero_one() {
         let (mut engine, mut ctrl) = make_engine();
@@ -1873,16 +4434,640 @@ mod tests {
     }
 
     #[test]
-    fn engine_set_pitch_bend_range_clamps() {
+    fn tuning_table_offsets_new_and_sounding_notes() {
         let (mut engine, mut ctrl) = make_engine();
-        ctrl.set_pitch_bend_range(50.0);
+        let mut tuning = Tuning::default();
+        tuning.set_offset_cents(69, 1200.0);
+        tuning.name = "Octave A".to_string();
+        ctrl.set_tuning(tuning);
+        ctrl.set_master_tune(-100.0);
+        ctrl.note_on(69, 100);
+        ctrl.note_on(60, 100);
         engine.process_commands();
-        assert_eq!(engine.pitch_bend_range, 12.0);
-        ctrl.set_pitch_bend_range(-1.0);
+        let freq = |engine: &SynthEngine, note: u8| {
+            let voice = engine.voices.iter().find(|v| v.active && v.note == note);
+            voice.expect("voice").target_frequency
+        };
+        // +1200 from the table, -100 master tune: G#5.
+        assert!((freq(&engine, 69) - midi_to_hz(80)).abs() < 0.05);
+        assert!((freq(&engine, 60) - midi_to_hz(59)).abs() < 0.05);
+
+        // A live retune moves the held C at once.
+        ctrl.retune_notes(vec![(60, 300.0)]);
+        engine.process_commands();
+        assert!((freq(&engine, 60) - midi_to_hz(62)).abs() < 0.05);
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().tuning_name, LIVE_TUNING);
+
+        ctrl.set_tuning(Tuning::default());
+        engine.process_commands();
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().tuning_name, "12-TET");
+    }
+
+    #[test]
+    fn engine_set_pitch_bend_range_clamps() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_pitch_bend_range(50.0);
+        engine.process_commands();
+        assert_eq!(engine.pitch_bend_range, 12.0);
+        ctrl.set_pitch_bend_range(-1.0);
         engine.process_commands();
         assert_eq!(engine.pitch_bend_range, 0.0);
     }
 
+    #[test]
+    fn engine_fade_time_commands_reach_voices_and_snapshot() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_steal_fade_time(12.0);
+        ctrl.set_attack_fade_time(99.0);
+        engine.process_commands();
+        assert!(engine
+            .voices
+            .iter()
+            .all(|v| v.steal_fade_ms == 12.0 && v.attack_fade_ms == MAX_FADE_MS));
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.steal_fade_ms, 12.0);
+        assert_eq!(snap.attack_fade_ms, MAX_FADE_MS);
+    }
+
+    #[test]
+    fn process_stereo_feeds_scope_visible_to_controller() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.note_on(69, 127);
+        engine.process_commands();
+        for _ in 0..SCOPE_CAPACITY {
+            engine.process_stereo();
+        }
+        let mut out = vec![0.0; 512];
+        ctrl.scope_latest(&mut out);
+        assert!(out.iter().any(|s| s.abs() > 0.01));
+        assert!(out.iter().all(|s| s.abs() <= 1.0));
+    }
+
+    #[test]
+    fn engine_sub_bus_commands_clamp_and_reach_snapshot() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_sub_bus_level(2.0);
+        ctrl.set_sub_bus_delay_send(0.4);
+        ctrl.set_sub_bus_reverb_send(-1.0);
+        ctrl.set_operator_param(5, OperatorParam::SubBus, 1.0);
+        engine.process_commands();
+        assert_eq!(engine.sub_bus_level, 1.0);
+        assert_eq!(engine.sub_bus_delay_send, 0.4);
+        assert_eq!(engine.sub_bus_reverb_send, 0.0);
+        assert!(engine.voices.iter().all(|v| v.operators[5].sub_bus));
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.sub_bus.level, 1.0);
+        assert_eq!(snap.sub_bus.delay_send, 0.4);
+        assert!(snap.operators[5].sub_bus);
+        assert!(!snap.operators[0].sub_bus);
+    }
+
+    #[test]
+    fn operator_waveform_reaches_voices_snapshot_and_presets() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_operator_param(0, OperatorParam::Waveform, 4.0);
+        ctrl.set_operator_param(1, OperatorParam::Waveform, 99.0);
+        engine.process_commands();
+        assert!(engine
+            .voices
+            .iter()
+            .all(|v| v.operators[0].waveform == OperatorWaveform::DoubleSine));
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.operators[0].waveform, OperatorWaveform::DoubleSine);
+        assert_eq!(
+            snap.operators[1].waveform,
+            OperatorWaveform::DoubleAbsSquaredSine
+        );
+        assert_eq!(snap.operators[2].waveform, OperatorWaveform::Sine);
+
+        let preset = Dx7Preset::from_snapshot(&snap);
+        assert_eq!(preset.operators[0].waveform, OperatorWaveform::DoubleSine);
+    }
+
+    #[test]
+    fn phase_offset_sets_the_blend_of_same_ratio_carriers() {
+        // Algorithm 32 with OP1 and OP2 alone at the same ratio: in phase
+        // they add, half a cycle apart they cancel.
+        let peak = |offset: f32| {
+            let (mut engine, mut ctrl) = make_engine();
+            ctrl.set_algorithm(32);
+            ctrl.set_operator_param(1, OperatorParam::Level, 99.0);
+            ctrl.set_operator_param(1, OperatorParam::PhaseOffset, offset);
+            ctrl.note_on(69, 100);
+            engine.process_commands();
+            (0..2048)
+                .map(|_| engine.process_stereo().0.abs())
+                .fold(0.0_f32, f32::max)
+        };
+        let together = peak(0.0);
+        let opposed = peak(180.0);
+        assert!(together > 0.1, "together={together}");
+        assert!(opposed < together * 0.01, "opposed={opposed}");
+    }
+
+    #[test]
+    fn phase_offset_reaches_snapshot_and_presets() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_operator_param(2, OperatorParam::PhaseOffset, 90.0);
+        ctrl.set_operator_param(3, OperatorParam::PhaseOffset, 720.0);
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.operators[2].phase_offset, 90.0);
+        assert_eq!(snap.operators[3].phase_offset, 360.0);
+        // Read right after the note-on, before any sample advanced it.
+        assert!((snap.operators[2].recent_phase - 90.0).abs() < 1e-3);
+
+        let preset = Dx7Preset::from_snapshot(&snap);
+        assert_eq!(preset.operators[2].phase_offset, 90.0);
+        let (mut other, _ctrl) = make_engine();
+        preset.apply_to_synth(&mut other);
+        assert_eq!(other.voices[0].operators[2].phase_offset, 90.0);
+    }
+
+    #[test]
+    fn engine_sub_bus_level_scales_only_tagged_carriers() {
+        let render = |level: f32, tag: bool| {
+            let (mut engine, mut ctrl) = make_engine();
+            ctrl.set_algorithm(32);
+            if tag {
+                ctrl.set_operator_param(0, OperatorParam::SubBus, 1.0);
+            }
+            ctrl.set_sub_bus_level(level);
+            ctrl.note_on(60, 100);
+            engine.process_commands();
+            (0..2048).map(|_| engine.process().abs()).sum::<f32>()
+        };
+        let untagged = render(0.0, false);
+        let muted_sub = render(0.0, true);
+        let full_sub = render(1.0, true);
+        assert!(muted_sub < untagged, "{muted_sub} vs {untagged}");
+        assert!((full_sub - untagged).abs() < untagged * 1e-3);
+    }
+
+    #[test]
+    fn engine_voice_pan_commands_reach_every_voice_and_snapshot() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_voice_pan_rate(50.0);
+        ctrl.set_voice_pan_depth(0.6);
+        ctrl.set_voice_pan_stereo_phase(-1.0);
+        engine.process_commands();
+        assert!(engine
+            .voices
+            .iter()
+            .all(|v| v.pan.rate_hz == 10.0 && v.pan.depth == 0.6 && v.pan.stereo_phase == 0.0));
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.voice_pan.rate, 10.0);
+        assert_eq!(snap.voice_pan.depth, 0.6);
+        assert_eq!(snap.voice_pan.stereo_phase, 0.0);
+    }
+
+    #[test]
+    fn engine_voice_pan_moves_signal_into_side_only_when_panning() {
+        let side_energy = |depth: f32, stereo_phase: f32| {
+            let (mut engine, mut ctrl) = make_engine();
+            ctrl.set_voice_pan_rate(8.0);
+            ctrl.set_voice_pan_depth(depth);
+            ctrl.set_voice_pan_stereo_phase(stereo_phase);
+            ctrl.note_on(60, 100);
+            engine.process_commands();
+            (0..4096)
+                .map(|_| engine.process_buses().1.abs())
+                .sum::<f32>()
+        };
+        assert_eq!(side_energy(0.0, 1.0), 0.0);
+        assert_eq!(side_energy(1.0, 0.0), 0.0);
+        assert!(side_energy(1.0, 1.0) > 1.0);
+    }
+
+    #[test]
+    fn engine_compressor_tames_loud_patches_and_reports_gain_reduction() {
+        let peak_and_gr = |enabled: bool| {
+            let (mut engine, mut ctrl) = make_engine();
+            ctrl.set_algorithm(32); // six carriers
+            ctrl.set_master_volume(1.0);
+            ctrl.set_compressor_param(CompressorParam::Enabled, if enabled { 1.0 } else { 0.0 });
+            ctrl.set_compressor_param(CompressorParam::Threshold, -30.0);
+            for note in [48, 55, 60, 64, 67, 72] {
+                ctrl.note_on(note, 127);
+            }
+            engine.process_commands();
+            let peak = (0..8192)
+                .map(|_| engine.process_stereo().0.abs())
+                .fold(0.0_f32, f32::max);
+            engine.update_snapshot();
+            (peak, ctrl.snapshot().compressor.gain_reduction_db)
+        };
+        let (raw_peak, raw_gr) = peak_and_gr(false);
+        let (limited_peak, gr) = peak_and_gr(true);
+        assert_eq!(raw_gr, 0.0);
+        assert!(gr > 0.0);
+        assert!(limited_peak < raw_peak, "{limited_peak} vs {raw_peak}");
+    }
+
+    #[test]
+    fn engine_output_meter_follows_the_output() {
+        let (mut engine, mut ctrl) = make_engine();
+        engine.update_snapshot();
+        assert_eq!(
+            ctrl.snapshot().output_levels,
+            crate::level_meter::MeterLevels::default()
+        );
+
+        // Full volume takes a note past full scale; keep it in range here.
+        ctrl.set_master_volume(0.5);
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        for _ in 0..4096 {
+            engine.process_stereo();
+        }
+        engine.update_snapshot();
+        let levels = ctrl.snapshot().output_levels;
+        assert!(levels.peak.iter().all(|&p| p > 0.0));
+        assert!(levels.rms[0] > 0.0 && levels.rms[0] <= levels.peak[0]);
+        assert!(!levels.clip);
+    }
+
+    #[test]
+    fn operator_solo_auditions_a_modulator_and_meters_each_operator() {
+        // OP2 only modulates in algorithm 1; soloed, it is all that is heard.
+        let run = |solo: u8, op2_level: f32| {
+            let (mut engine, mut ctrl) = make_engine();
+            ctrl.set_algorithm(1);
+            ctrl.set_master_volume(1.0);
+            ctrl.set_operator_param(1, OperatorParam::Level, op2_level);
+            ctrl.set_operator_solo(solo);
+            ctrl.note_on(60, 100);
+            engine.process_commands();
+            let energy: f32 = (0..4096).map(|_| engine.process_stereo().0.powi(2)).sum();
+            engine.update_snapshot();
+            (energy, ctrl.snapshot())
+        };
+
+        let (silent, snap) = run(0b10, 0.0);
+        assert_eq!(silent, 0.0);
+        assert_eq!(snap.operator_solo, 0b10);
+        assert!(snap.operators[0].output_peak > 0.0, "OP1 still runs");
+        assert_eq!(snap.operators[1].output_peak, 0.0);
+
+        let (soloed, snap) = run(0b10, 99.0);
+        let (normal, _) = run(0, 99.0);
+        assert!(soloed > 0.0);
+        assert_ne!(soloed, normal);
+        assert!(snap.operators[1].output_peak > 0.0);
+        assert!(snap.operators[1].output_peak <= 1.0);
+    }
+
+    #[test]
+    fn engine_snapshot_reports_the_latest_voice_operator_levels() {
+        let (mut engine, mut ctrl) = make_engine();
+        engine.update_snapshot();
+        assert!(ctrl
+            .snapshot()
+            .operators
+            .iter()
+            .all(|op| op.recent_level == 0.0));
+
+        ctrl.note_on(60, 127);
+        engine.process_commands();
+        for _ in 0..2048 {
+            engine.process_stereo();
+        }
+        // A soft second note: the meters follow it, not the louder first one.
+        ctrl.note_on(64, 10);
+        engine.process_commands();
+        for _ in 0..2048 {
+            engine.process_stereo();
+        }
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        let latest = engine.voices.iter().max_by_key(|v| v.note_on_id).unwrap();
+        for (i, op) in snap.operators.iter().enumerate() {
+            assert_eq!(
+                op.recent_level,
+                latest.operators[i].envelope.current_output()
+            );
+            assert!(op.recent_level <= op.live_level);
+        }
+        assert!(snap.operators[0].recent_level < snap.operators[0].live_level);
+    }
+
+    #[test]
+    fn coarse_fine_commands_set_the_ratio_and_leave_extended_mode() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_operator_param(0, OperatorParam::Ratio, 1.414);
+        ctrl.set_operator_param(1, OperatorParam::Coarse, 0.0);
+        ctrl.set_operator_param(1, OperatorParam::Fine, 50.0);
+        ctrl.set_operator_param(2, OperatorParam::Coarse, 40.0);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert!(snap.operators[0].extended_ratio);
+        assert_eq!(snap.operators[0].frequency_ratio, 1.414);
+        assert_eq!((snap.operators[1].coarse, snap.operators[1].fine), (0, 50));
+        assert_eq!(snap.operators[1].frequency_ratio, 0.75);
+        assert_eq!(snap.operators[2].coarse, 31);
+
+        ctrl.set_operator_param(0, OperatorParam::ExtendedRatio, 0.0);
+        engine.process_commands();
+        engine.update_snapshot();
+        let op = ctrl.snapshot().operators[0];
+        assert!(!op.extended_ratio);
+        assert_eq!((op.coarse, op.fine), (1, 41));
+    }
+
+    #[test]
+    fn controller_pastes_whole_operators_or_just_envelopes() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_operator_param(0, OperatorParam::Ratio, 3.0);
+        ctrl.set_operator_param(0, OperatorParam::Detune, 2.0);
+        ctrl.set_operator_param(0, OperatorParam::KeyScaleLeftCurve, 2.0);
+        ctrl.set_operator_param(0, OperatorParam::PmSensitivity, 5.0);
+        ctrl.set_operator_param(0, OperatorParam::Enabled, 0.0);
+        ctrl.set_envelope_param(0, EnvelopeParam::Rate1, 12.0);
+        ctrl.set_envelope_param(0, EnvelopeParam::Level3, 77.0);
+        engine.process_commands();
+        engine.update_snapshot();
+        let source = ctrl.snapshot().operators[0];
+
+        ctrl.paste_operator(1, &source, true);
+        ctrl.paste_operator(2, &source, false);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+
+        let eg_only = snap.operators[1];
+        assert_eq!((eg_only.rate1, eg_only.level3), (12.0, 77.0));
+        assert_eq!(eg_only.frequency_ratio, 1.0);
+
+        let full = snap.operators[2];
+        assert_eq!((full.rate1, full.level3), (12.0, 77.0));
+        assert_eq!(full.frequency_ratio, source.frequency_ratio);
+        assert_eq!(full.detune, 2.0);
+        assert_eq!(full.key_scale_left_curve, KeyScaleCurve::PosExp);
+        assert_eq!(full.pm_sensitivity, Some(5));
+        assert!(full.enabled, "the ON switch is not pasted");
+    }
+
+    #[test]
+    fn engine_voice_spread_places_notes_and_master_pan_shifts_them() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_voice_spread(VoiceSpread::RoundRobin);
+        ctrl.set_voice_spread_width(0.5);
+        ctrl.note_on(60, 100);
+        ctrl.note_on(64, 100);
+        engine.process_commands();
+        let mut positions: Vec<_> = engine
+            .voices
+            .iter()
+            .filter(|v| v.active)
+            .map(|v| v.pan.position())
+            .collect();
+        positions.sort_by(f32::total_cmp);
+        assert_eq!(positions, [-0.5, 0.5]);
+
+        // Hard left: everything lands in L, so side equals mid.
+        ctrl.set_master_pan(-3.0);
+        ctrl.set_voice_spread_width(0.0);
+        engine.process_commands();
+        for _ in 0..256 {
+            let (main, side, _) = engine.process_buses();
+            assert_eq!(main, side);
+        }
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.voice_pan.spread, VoiceSpread::RoundRobin);
+        assert_eq!(snap.voice_pan.master_pan, -1.0);
+    }
+
+    #[test]
+    fn engine_set_sample_rate_matches_an_engine_built_at_that_rate() {
+        let render = |engine: &mut SynthEngine, ctrl: &mut SynthController| {
+            ctrl.note_on(69, 100);
+            engine.process_commands();
+            (0..2048)
+                .map(|_| engine.process_stereo())
+                .collect::<Vec<_>>()
+        };
+        let (mut switched, mut switched_ctrl) = make_engine();
+        switched.set_sample_rate(96_000.0);
+        let (mut fresh, mut fresh_ctrl) = create_synth(96_000.0);
+
+        assert_eq!(switched.sample_rate(), 96_000.0);
+        let a = render(&mut switched, &mut switched_ctrl);
+        let b = render(&mut fresh, &mut fresh_ctrl);
+        for ((al, ar), (bl, br)) in a.iter().zip(&b) {
+            assert!((al - bl).abs() < 1e-4 && (ar - br).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn engine_vintage_mode_changes_output_and_reports_in_snapshot() {
+        let render = |vintage: bool| {
+            let (mut engine, mut ctrl) = make_engine();
+            ctrl.set_vintage_mode(vintage);
+            ctrl.note_on(60, 100);
+            engine.process_commands();
+            let out: Vec<_> = (0..2048).map(|_| engine.process_stereo()).collect();
+            engine.update_snapshot();
+            assert_eq!(ctrl.snapshot().vintage_mode, vintage);
+            out
+        };
+        let clean = render(false);
+        let vintage = render(true);
+        assert!(clean.iter().zip(&vintage).any(|(a, b)| a != b));
+        // The bundle colours the sound; it must not change level or pitch.
+        let peak = |s: &[(f32, f32)]| s.iter().fold(0.0_f32, |m, (l, _)| m.max(l.abs()));
+        assert!((peak(&clean) - peak(&vintage)).abs() < 0.05 * peak(&clean));
+    }
+
+    #[test]
+    fn engine_output_dac_darkens_the_output_by_its_intensity() {
+        let render = |on: bool, intensity: f32| {
+            let (mut engine, mut ctrl) = make_engine();
+            ctrl.set_output_dac(on);
+            ctrl.set_output_dac_intensity(intensity);
+            ctrl.note_on(84, 127);
+            engine.process_commands();
+            let out: Vec<_> = (0..2048).map(|_| engine.process_stereo()).collect();
+            engine.update_snapshot();
+            let snapshot = ctrl.snapshot();
+            assert_eq!(snapshot.output_dac, on);
+            assert_eq!(snapshot.output_dac_intensity, intensity);
+            out
+        };
+        // Sample-to-sample change tracks the high-frequency content.
+        let roughness =
+            |s: &[(f32, f32)]| s.windows(2).map(|w| (w[1].0 - w[0].0).abs()).sum::<f32>();
+        let clean = render(false, 1.0);
+        let full = render(true, 1.0);
+        let half = render(true, 0.5);
+        assert_eq!(clean, render(true, 0.0));
+        assert!(roughness(&full) < roughness(&half));
+        assert!(roughness(&half) < roughness(&clean));
+    }
+
+    #[test]
+    fn engine_noise_floor_sounds_under_silence_only_when_on() {
+        let (mut engine, mut ctrl) = make_engine();
+        let peak = |engine: &mut SynthEngine| {
+            (0..4800)
+                .map(|_| engine.process_stereo().0.abs())
+                .fold(0.0_f32, f32::max)
+        };
+        assert_eq!(peak(&mut engine), 0.0);
+
+        ctrl.set_noise_floor(true);
+        ctrl.set_noise_level(-50.0);
+        ctrl.set_hum_frequency(60.0);
+        engine.process_commands();
+        let noisy = peak(&mut engine);
+        assert!(noisy > 1e-4 && noisy < 0.02, "{noisy}");
+        engine.update_snapshot();
+        let snapshot = ctrl.snapshot();
+        assert!(snapshot.noise_floor);
+        assert_eq!(snapshot.noise_level_db, -50.0);
+        assert_eq!(snapshot.hum_hz, 60.0);
+    }
+
+    #[test]
+    fn engine_polyphony_limits_voice_allocation() {
+        let (mut engine, mut ctrl) = make_engine();
+        assert_eq!(engine.polyphony(), DEFAULT_POLYPHONY);
+        ctrl.set_polyphony(32);
+        for note in 30..62 {
+            ctrl.note_on(note, 100);
+        }
+        engine.process_commands();
+        assert_eq!(engine.voices().iter().filter(|v| v.active).count(), 32);
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().polyphony, 32);
+
+        // A 33rd note steals instead of growing past the limit.
+        ctrl.note_on(90, 100);
+        engine.process_commands();
+        assert!(!engine.voices()[32..].iter().any(|v| v.active));
+    }
+
+    #[test]
+    fn engine_shrinking_polyphony_releases_voices_above_the_limit() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_polyphony(64);
+        for note in 40..60 {
+            ctrl.note_on(note, 100);
+        }
+        engine.process_commands();
+        ctrl.set_polyphony(8);
+        engine.process_commands();
+        assert_eq!(engine.polyphony(), 8);
+        assert_eq!(engine.held_notes.len(), 8);
+        assert!(engine.held_notes.values().all(|&idx| idx < 8));
+        // Notes that lost their voice are no longer tracked; their note-off is a no-op.
+        ctrl.note_off(59);
+        engine.process_commands();
+        assert_eq!(engine.held_notes.len(), 8);
+
+        ctrl.set_polyphony(0);
+        engine.process_commands();
+        assert_eq!(engine.polyphony(), 1);
+    }
+
+    #[test]
+    fn engine_steals_a_released_voice_before_an_older_held_one() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_polyphony(4);
+        for note in 60..64 {
+            ctrl.note_on(note, 100);
+        }
+        ctrl.note_off(61);
+        ctrl.note_on(70, 100);
+        engine.process_commands();
+        assert_eq!(engine.voices()[1].note, 70);
+        assert_eq!(engine.voices()[0].note, 60);
+    }
+
+    #[test]
+    fn engine_does_not_steal_the_newest_notes() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_polyphony(4);
+        for note in 60..64 {
+            ctrl.note_on(note, 100);
+        }
+        // A staccato last note is released and quiet, but still protected.
+        ctrl.note_off(63);
+        ctrl.note_on(70, 100);
+        engine.process_commands();
+        assert_eq!(engine.voices()[0].note, 70);
+        assert_eq!(engine.voices()[3].note, 63);
+    }
+
+    #[test]
+    fn engine_same_note_mode_retriggers_or_stacks() {
+        let count_active =
+            |engine: &SynthEngine| engine.voices().iter().filter(|v| v.active).count();
+
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.note_on(60, 100);
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        assert_eq!(count_active(&engine), 1);
+
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_same_note_mode(SameNoteMode::Stack);
+        ctrl.note_on(60, 100);
+        ctrl.note_on(60, 100);
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        assert_eq!(count_active(&engine), 3);
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().same_note_mode, SameNoteMode::Stack);
+
+        // One note-off releases the whole stack.
+        ctrl.note_off(60);
+        engine.process_commands();
+        assert!(engine.held_notes.is_empty());
+        assert!(engine.stacked_voices.is_empty());
+    }
+
+    #[test]
+    fn process_block_matches_per_sample_output_without_modulation() {
+        let (mut per_sample, mut ctrl_a) = make_engine();
+        let (mut block, mut ctrl_b) = make_engine();
+        ctrl_a.note_on(60, 100);
+        ctrl_b.note_on(60, 100);
+        per_sample.process_commands();
+        let expected: Vec<_> = (0..1000).map(|_| per_sample.process_stereo()).collect();
+
+        // Uneven block length so the last control block is a partial one.
+        let mut left = vec![0.0; 1000];
+        let mut right = vec![0.0; 1000];
+        block.process_block(&mut left, &mut right);
+
+        assert!(expected.iter().any(|(l, _)| l.abs() > 0.01));
+        for (i, (l, r)) in expected.iter().enumerate() {
+            assert!((l - left[i]).abs() < 1e-4, "left differs at {}", i);
+            assert!((r - right[i]).abs() < 1e-4, "right differs at {}", i);
+        }
+    }
+
+    #[test]
+    fn process_block_applies_queued_commands() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.note_on(60, 100);
+        let mut left = vec![0.0; 256];
+        let mut right = vec![0.0; 256];
+        engine.process_block(&mut left, &mut right);
+        assert!(left.iter().any(|s| s.abs() > 0.01));
+        assert!(left.iter().chain(&right).all(|s| s.is_finite()));
+    }
+
     #[test]
     fn engine_set_voice_mode_changes_mode() {
         let (mut engine, mut ctrl) = make_engine();
@@ -1959,6 +5144,76 @@ mod tests {
         assert_eq!(active, 0);
     }
 
+    #[test]
+    fn voice_initialize_matches_the_dx7_init_voice() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_lfo_param(LfoParam::PitchDepth, 80.0);
+        ctrl.set_lfo_param(LfoParam::Waveform(4), 0.0);
+        ctrl.set_lfo2_enabled(true);
+        ctrl.set_unison_voices(3);
+        ctrl.set_effect_param(EffectType::Delay, EffectParam::Enabled, 1.0);
+        ctrl.set_effect_param(EffectType::Chorus, EffectParam::Mix, 0.9);
+        ctrl.set_effect_param(EffectType::Chorus, EffectParam::OutputGain, -6.0);
+        ctrl.set_sub_bus_level(0.2);
+        ctrl.set_pitch_bend_range(7.0);
+        ctrl.set_voice_mode(VoiceMode::Mono);
+        engine.process_commands();
+
+        ctrl.voice_initialize();
+        engine.process_commands();
+        assert_eq!(engine.lfo.pitch_depth, 0.0);
+        assert_eq!(engine.lfo.amp_depth, 0.0);
+        assert_eq!(engine.lfo.rate, 35.0);
+        assert_eq!(engine.lfo.waveform, LFOWaveform::Triangle);
+        assert!(engine.lfo.key_sync);
+        assert_eq!(engine.pitch_mod_sensitivity, 3);
+        assert!(!engine.lfo2_enabled);
+        assert_eq!(engine.unison.stacks, 1);
+        assert_eq!(engine.sub_bus_level, 1.0);
+        // Effects back to the boot flavour.
+        assert!(!engine.effects.delay.enabled);
+        assert!(engine.effects.chorus.enabled);
+        assert_eq!(engine.effects.chorus.mix, 0.15);
+        assert_eq!(engine.effects.output_gain_db(EffectSlot::Chorus), 0.0);
+        let ops = &engine.voices[0].operators;
+        assert_eq!(ops[0].output_level, 99.0);
+        assert!(ops[1..].iter().all(|op| op.output_level == 0.0));
+        assert_eq!(ops[0].envelope.rate2, 99.0);
+        assert_eq!(ops[0].envelope.level3, 99.0);
+        // Function settings are not part of the voice.
+        assert_eq!(engine.pitch_bend_range, 7.0);
+        assert_eq!(engine.voice_mode, VoiceMode::Mono);
+    }
+
+    #[test]
+    fn function_initialize_resets_the_playing_setup_only() {
+        use crate::mod_matrix::{ModDestination, ModSource};
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_algorithm(9);
+        ctrl.set_voice_mode(VoiceMode::MonoLegato);
+        ctrl.set_pitch_bend_range(12.0);
+        ctrl.set_portamento_enable(true);
+        ctrl.set_master_tune(40.0);
+        ctrl.set_mod_route(
+            0,
+            Some(ModRoute::new(
+                ModSource::Velocity,
+                ModDestination::Pitch,
+                0.5,
+            )),
+        );
+        engine.process_commands();
+
+        ctrl.function_initialize();
+        engine.process_commands();
+        assert_eq!(engine.voice_mode, VoiceMode::Poly);
+        assert_eq!(engine.pitch_bend_range, 2.0);
+        assert!(!engine.portamento_enable);
+        assert_eq!(engine.master_tune, 0.0);
+        assert_eq!(engine.mod_matrix, ModMatrix::default());
+        assert_eq!(engine.algorithm, 9);
+    }
+
     #[test]
     fn engine_process_produces_audio_when_note_pressed() {
         let (mut engine, mut ctrl) = make_engine();
@@ -2073,6 +5328,113 @@ mod tests {
         assert!(active >= 1);
     }
 
+    #[test]
+    fn poly_portamento_glides_new_voices_from_the_last_note() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_portamento_enable(true);
+        ctrl.set_portamento_time(20.0);
+        ctrl.note_on(57, 100);
+        engine.process_commands();
+        ctrl.note_off(57);
+        engine.process_commands();
+        let a3 = engine.voices[engine.last_voice.unwrap()].current_frequency;
+
+        // Full time: a detached note still glides from the last pitch.
+        ctrl.note_on(69, 100);
+        engine.process_commands();
+        let voice = &engine.voices[engine.held_notes[&69]];
+        assert_eq!(voice.current_frequency, a3);
+        assert!((voice.target_frequency - 440.0).abs() < 0.01);
+        drive(&mut engine, 44_100);
+        assert!((engine.voices[engine.held_notes[&69]].current_frequency - 440.0).abs() < 0.5);
+        ctrl.note_off(69);
+        engine.process_commands();
+
+        // Fingered: the detached note starts on pitch, a legato one glides.
+        ctrl.set_portamento_mode(PortamentoMode::Fingered);
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        let voice = &engine.voices[engine.held_notes[&60]];
+        assert_eq!(voice.current_frequency, voice.target_frequency);
+        ctrl.note_on(64, 100);
+        engine.process_commands();
+        let from = engine.voices[engine.held_notes[&60]].current_frequency;
+        let voice = &engine.voices[engine.held_notes[&64]];
+        assert_eq!(voice.current_frequency, from);
+        assert!(voice.target_frequency > from);
+
+        // Portamento off: no glide at all.
+        ctrl.set_portamento_enable(false);
+        ctrl.note_on(67, 100);
+        engine.process_commands();
+        let voice = &engine.voices[engine.held_notes[&67]];
+        assert_eq!(voice.current_frequency, voice.target_frequency);
+    }
+
+    #[test]
+    fn fingered_mono_portamento_glides_only_legato() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_voice_mode(crate::state_snapshot::VoiceMode::Mono);
+        ctrl.set_portamento_enable(true);
+        ctrl.set_portamento_mode(PortamentoMode::Fingered);
+        ctrl.note_on(60, 100);
+        ctrl.note_off(60);
+        ctrl.note_on(72, 100);
+        engine.process_commands();
+        let voice = &engine.voices[0];
+        assert_eq!(voice.current_frequency, voice.target_frequency);
+        ctrl.note_on(76, 100);
+        engine.process_commands();
+        let voice = &engine.voices[0];
+        assert!(voice.current_frequency < voice.target_frequency);
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().portamento_mode, PortamentoMode::Fingered);
+    }
+
+    #[test]
+    fn engine_mono_low_priority_keeps_the_lowest_key() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_voice_mode(crate::state_snapshot::VoiceMode::Mono);
+        ctrl.set_note_priority(NotePriority::Low);
+        ctrl.note_on(60, 100);
+        ctrl.note_on(64, 100);
+        engine.process_commands();
+        assert_eq!(engine.voices[0].note, 60);
+        ctrl.note_on(55, 100);
+        engine.process_commands();
+        assert_eq!(engine.voices[0].note, 55);
+
+        // Releasing the sounding key falls back to the next-lowest held key.
+        ctrl.note_off(55);
+        engine.process_commands();
+        assert_eq!(engine.voices[0].note, 60);
+        // Lifting a waiting key leaves the sounding note alone.
+        ctrl.note_off(64);
+        engine.process_commands();
+        assert_eq!(engine.voices[0].note, 60);
+        assert!(engine.held_notes.contains_key(&60));
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().note_priority, NotePriority::Low);
+    }
+
+    #[test]
+    fn engine_mono_high_priority_returns_to_the_highest_held_key() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_voice_mode(crate::state_snapshot::VoiceMode::MonoLegato);
+        ctrl.set_note_priority(NotePriority::High);
+        for note in [60, 72, 67] {
+            ctrl.note_on(note, 100);
+        }
+        engine.process_commands();
+        assert_eq!(engine.voices[0].note, 72);
+        ctrl.note_off(72);
+        engine.process_commands();
+        assert_eq!(engine.voices[0].note, 67);
+        ctrl.note_off(67);
+        engine.process_commands();
+        assert_eq!(engine.voices[0].note, 60);
+    }
+
     #[test]
     fn engine_sustain_pedal_holds_notes() {
         let (mut engine, mut ctrl) = make_engine();
@@ -2087,6 +5449,72 @@ mod tests {
         assert!(active_before_release >= 1);
     }
 
+    #[test]
+    fn snapshot_held_keys_follow_the_keys_that_are_down() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.note_on(60, 100);
+        ctrl.note_on(64, 100);
+        ctrl.note_off(64);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert!(snap.key_held(60));
+        assert!(!snap.key_held(64));
+
+        // Under the pedal the lifted key still sounds but is no longer held.
+        ctrl.sustain_pedal(true);
+        ctrl.note_off(60);
+        engine.process_commands();
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().held_keys, 0);
+
+        // Mono modes report the waiting keys as well as the sounding one.
+        ctrl.sustain_pedal(false);
+        ctrl.set_voice_mode(crate::state_snapshot::VoiceMode::Mono);
+        ctrl.note_on(48, 100);
+        ctrl.note_on(55, 100);
+        engine.process_commands();
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().held_keys, 1 << 48 | 1 << 55);
+    }
+
+    #[test]
+    fn snapshot_reports_the_pitch_of_the_last_note() {
+        let (mut engine, mut ctrl) = make_engine();
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().last_note_hz, None);
+
+        ctrl.set_transpose(12);
+        ctrl.note_on(57, 100);
+        ctrl.note_off(57);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.last_note, Some(57));
+        assert!((snap.last_note_hz.unwrap() - 440.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn engine_sustain_pedal_up_releases_the_lifted_keys() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.sustain_pedal(true);
+        ctrl.note_on(60, 100);
+        ctrl.note_on(64, 100);
+        ctrl.note_off(60);
+        ctrl.note_off(64);
+        // 64 is struck again and still down when the pedal comes up.
+        ctrl.note_on(64, 100);
+        ctrl.sustain_pedal(false);
+        drive(&mut engine, 44_100);
+        let sounding: Vec<u8> = engine
+            .voices
+            .iter()
+            .filter(|v| v.active)
+            .map(|v| v.note)
+            .collect();
+        assert_eq!(sounding, vec![64]);
+    }
+
     #[test]
     fn engine_set_operator_param_dispatches_to_voices() {
         let (mut engine, mut ctrl) = make_engine();
@@ -2111,6 +5539,26 @@ mod tests {
         // No assertion needed — we just exercise all branches.
     }
 
+    #[test]
+    fn engine_osc_key_sync_sets_every_operator_of_every_voice() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_osc_key_sync(false);
+        engine.process_commands();
+        assert!(engine
+            .voices
+            .iter()
+            .flat_map(|v| v.operators.iter())
+            .all(|op| !op.oscillator_key_sync));
+        engine.update_snapshot();
+        assert!(!ctrl.snapshot().osc_key_sync);
+
+        // One synced operator is enough for the voice-level flag.
+        ctrl.set_operator_param(3, OperatorParam::OscillatorKeySync, 1.0);
+        engine.process_commands();
+        engine.update_snapshot();
+        assert!(ctrl.snapshot().osc_key_sync);
+    }
+
     #[test]
     fn engine_set_envelope_param_dispatches_to_all_voices() {
         let (mut engine, mut ctrl) = make_engine();
@@ -2164,47 +5612,417 @@ mod tests {
     }
 
     #[test]
-    fn engine_set_effect_param_dispatches() {
+    fn engine_set_effect_param_dispatches() {
+        let (mut engine, mut ctrl) = make_engine();
+        // EQ
+        ctrl.set_effect_param(EffectType::Eq, EffectParam::Enabled, 1.0);
+        ctrl.set_effect_param(EffectType::Eq, EffectParam::EqLowGain, 3.0);
+        ctrl.set_effect_param(EffectType::Eq, EffectParam::EqLowFreq, 150.0);
+        ctrl.set_effect_param(EffectType::Eq, EffectParam::EqMidGain, -4.0);
+        ctrl.set_effect_param(EffectType::Eq, EffectParam::EqMidFreq, 2500.0);
+        ctrl.set_effect_param(EffectType::Eq, EffectParam::EqMidQ, 1.5);
+        ctrl.set_effect_param(EffectType::Eq, EffectParam::EqHighGain, -40.0); // clamped
+        ctrl.set_effect_param(EffectType::Eq, EffectParam::EqHighFreq, 6000.0);
+        // Filter
+        ctrl.set_effect_param(EffectType::Filter, EffectParam::Enabled, 1.0);
+        ctrl.set_effect_param(EffectType::Filter, EffectParam::FilterCutoff, 50_000.0); // clamped
+        ctrl.set_effect_param(EffectType::Filter, EffectParam::FilterResonance, 0.75);
+        ctrl.set_effect_param(EffectType::Filter, EffectParam::FilterEnvAmount, -0.5);
+        ctrl.set_effect_param(EffectType::Filter, EffectParam::FilterLfoRate, 2.0);
+        ctrl.set_effect_param(EffectType::Filter, EffectParam::FilterLfoDepth, 0.4);
+        ctrl.set_effect_param(EffectType::Filter, EffectParam::Mix, 0.8);
+        // Phaser
+        ctrl.set_effect_param(EffectType::Phaser, EffectParam::Enabled, 1.0);
+        ctrl.set_effect_param(EffectType::Phaser, EffectParam::PhaserStages, 8.0);
+        ctrl.set_effect_param(EffectType::Phaser, EffectParam::PhaserRate, 1.2);
+        ctrl.set_effect_param(EffectType::Phaser, EffectParam::PhaserDepth, 0.5);
+        ctrl.set_effect_param(EffectType::Phaser, EffectParam::PhaserFeedback, 2.0); // clamped
+        ctrl.set_effect_param(EffectType::Phaser, EffectParam::Mix, 0.25);
+        // Chorus
+        ctrl.set_effect_param(EffectType::Chorus, EffectParam::Enabled, 1.0);
+        ctrl.set_effect_param(EffectType::Chorus, EffectParam::Mix, 0.5);
+        ctrl.set_effect_param(EffectType::Chorus, EffectParam::ChorusRate, 2.0);
+        ctrl.set_effect_param(EffectType::Chorus, EffectParam::ChorusDepth, 5.0);
+        ctrl.set_effect_param(EffectType::Chorus, EffectParam::ChorusFeedback, 0.3);
+        // AutoPan
+        ctrl.set_effect_param(EffectType::AutoPan, EffectParam::Enabled, 1.0);
+        ctrl.set_effect_param(EffectType::AutoPan, EffectParam::AutoPanRate, 4.5);
+        ctrl.set_effect_param(EffectType::AutoPan, EffectParam::AutoPanDepth, 0.6);
+        // Delay
+        ctrl.set_effect_param(EffectType::Delay, EffectParam::Enabled, 1.0);
+        ctrl.set_effect_param(EffectType::Delay, EffectParam::Mix, 0.4);
+        ctrl.set_effect_param(EffectType::Delay, EffectParam::DelayTime, 200.0);
+        ctrl.set_effect_param(EffectType::Delay, EffectParam::DelayFeedback, 0.5);
+        ctrl.set_effect_param(EffectType::Delay, EffectParam::DelayPingPong, 1.0);
+        // Reverb
+        ctrl.set_effect_param(EffectType::Reverb, EffectParam::Enabled, 1.0);
+        ctrl.set_effect_param(EffectType::Reverb, EffectParam::Mix, 0.3);
+        ctrl.set_effect_param(EffectType::Reverb, EffectParam::ReverbRoomSize, 0.8);
+        ctrl.set_effect_param(EffectType::Reverb, EffectParam::ReverbDamping, 0.4);
+        ctrl.set_effect_param(EffectType::Reverb, EffectParam::ReverbWidth, 0.9);
+        ctrl.set_effect_param(EffectType::Reverb, EffectParam::ReverbPreDelay, 500.0);
+        ctrl.set_effect_param(
+            EffectType::Reverb,
+            EffectParam::ReverbType,
+            ReverbType::Plate.to_code() as f32,
+        );
+        engine.process_commands();
+        engine.update_snapshot();
+        let eq = ctrl.snapshot().eq;
+        assert!(eq.enabled);
+        assert_eq!((eq.low.freq_hz, eq.low.gain_db), (150.0, 3.0));
+        assert_eq!(
+            (eq.mid.freq_hz, eq.mid.gain_db, eq.mid.q),
+            (2500.0, -4.0, 1.5)
+        );
+        assert_eq!(eq.high.gain_db, -crate::effects::EQ_MAX_GAIN_DB);
+
+        // The effects panels read these back instead of the engine.
+        let snap = ctrl.snapshot();
+        let filter = snap.filter;
+        assert!(filter.enabled);
+        assert_eq!(filter.cutoff_hz, crate::effects::FILTER_MAX_HZ);
+        assert_eq!(
+            (
+                filter.resonance,
+                filter.env_amount,
+                filter.lfo_rate,
+                filter.lfo_depth,
+                filter.mix
+            ),
+            (0.75, -0.5, 2.0, 0.4, 0.8)
+        );
+        let phaser = snap.phaser;
+        assert!(phaser.enabled);
+        assert_eq!(
+            (phaser.stages, phaser.rate, phaser.depth, phaser.mix),
+            (8, 1.2, 0.5, 0.25)
+        );
+        assert_eq!(phaser.feedback, crate::effects::PHASER_MAX_FEEDBACK);
+        let chorus = snap.chorus;
+        assert!(chorus.enabled);
+        assert_eq!(
+            (chorus.mix, chorus.rate, chorus.depth, chorus.feedback),
+            (0.5, 2.0, 5.0, 0.3)
+        );
+        let delay = snap.delay;
+        assert!(delay.enabled && delay.ping_pong);
+        assert_eq!(
+            (delay.mix, delay.time_ms, delay.feedback),
+            (0.4, 200.0, 0.5)
+        );
+        let reverb = snap.reverb;
+        assert!(reverb.enabled);
+        assert_eq!(
+            (reverb.mix, reverb.room_size, reverb.damping, reverb.width),
+            (0.3, 0.8, 0.4, 0.9)
+        );
+        assert_eq!(reverb.pre_delay_ms, REVERB_MAX_PRE_DELAY_MS);
+        assert_eq!(reverb.reverb_type, ReverbType::Plate);
+    }
+
+    // -----------------------------------------------------------------------
+    // Controller routing & expression
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn engine_aftertouch_clamps_and_routes() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_aftertouch_pitch_sens(7);
+        ctrl.set_aftertouch_amp_sens(5);
+        ctrl.set_aftertouch_eg_bias_sens(3);
+        ctrl.set_aftertouch_pitch_bias_sens(2);
+        ctrl.aftertouch(2.0); // clamped to 1.0
+        engine.process_commands();
+        assert_eq!(engine.aftertouch, 1.0);
+    }
+
+    #[test]
+    fn engine_poly_aftertouch_targets_only_the_pressed_key() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.note_on(60, 100);
+        ctrl.note_on(64, 100);
+        ctrl.poly_aftertouch(64, 2.0); // clamped to 1.0
+        ctrl.poly_aftertouch(72, 1.0); // no voice on that key: ignored
+        engine.process_commands();
+        assert_eq!(engine.voices[engine.held_notes[&64]].pressure, 1.0);
+        assert_eq!(engine.voices[engine.held_notes[&60]].pressure, 0.0);
+
+        // A new note on the voice starts without pressure.
+        ctrl.note_off(64);
+        ctrl.note_on(64, 100);
+        engine.process_commands();
+        assert_eq!(engine.voices[engine.held_notes[&64]].pressure, 0.0);
+    }
+
+    #[test]
+    fn voice_poly_pressure_adds_only_the_excess_over_channel_pressure() {
+        let render = |pressure: f32, channel: f32| {
+            let mut voice = Voice::new_with_sample_rate(SR);
+            voice.trigger(60, 1.0, 0.0, false);
+            voice.pressure = pressure;
+            // P-BIAS at full sensitivity; the engine folds channel pressure in.
+            let control = ControlSignals {
+                pitch_bias_semitones: route_amount(channel, 7) * 2.0,
+                aftertouch: channel,
+                aftertouch_sens: [0, 0, 0, 7],
+                ..ControlSignals::default()
+            };
+            (0..512)
+                .map(|_| {
+                    voice.update_control(&control, 1);
+                    voice.render(1, None).0
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_ne!(render(0.0, 0.0), render(1.0, 0.0));
+        // Full poly pressure sounds like full channel pressure, and the two
+        // don't stack.
+        assert_eq!(render(1.0, 0.0), render(0.0, 1.0));
+        assert_eq!(render(1.0, 1.0), render(0.0, 1.0));
+    }
+
+    fn render_voice_with(setup: impl Fn(&mut Operator), control: ControlSignals) -> Vec<f32> {
+        let mut voice = Voice::new_with_sample_rate(SR);
+        voice.operators.iter_mut().for_each(setup);
+        voice.trigger(60, 1.0, 0.0, false);
+        (0..512)
+            .map(|_| {
+                voice.update_control(&control, 1);
+                voice.render(1, None).0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn voice_operator_pms_overrides_the_voice_pms() {
+        // LFO at its peak with PMS 7 on the voice.
+        let vibrato = ControlSignals {
+            lfo_pitch_mod: pms_scale(7),
+            lfo_pitch_raw: 1.0,
+            pms_scale: pms_scale(7),
+            ..ControlSignals::default()
+        };
+        let follow = render_voice_with(|_| {}, vibrato);
+        let dry = render_voice_with(|_| {}, ControlSignals::default());
+        assert_ne!(follow, dry);
+        assert_eq!(
+            render_voice_with(|op| op.set_pm_sensitivity(Some(7)), vibrato),
+            follow
+        );
+        assert_eq!(
+            render_voice_with(|op| op.set_pm_sensitivity(Some(0)), vibrato),
+            dry
+        );
+    }
+
+    #[test]
+    fn voice_operators_on_lfo2_ignore_the_main_lfo() {
+        let control = ControlSignals {
+            lfo_pitch_mod: pms_scale(7),
+            lfo_pitch_raw: 1.0,
+            pms_scale: pms_scale(7),
+            lfo2_enabled: true,
+            ..ControlSignals::default()
+        };
+        let dry = render_voice_with(|_| {}, ControlSignals::default());
+        let on_lfo2 = |op: &mut Operator| op.lfo2 = true;
+        assert_eq!(render_voice_with(on_lfo2, control), dry);
+        // LFO 2 swinging moves them again.
+        let lfo2_moving = ControlSignals {
+            lfo2_pitch_raw: 1.0,
+            ..control
+        };
+        assert_ne!(render_voice_with(on_lfo2, lfo2_moving), dry);
+        // With LFO 2 off the switch is ignored.
+        let lfo2_off = ControlSignals {
+            lfo2_enabled: false,
+            ..control
+        };
+        assert_eq!(
+            render_voice_with(on_lfo2, lfo2_off),
+            render_voice_with(|_| {}, lfo2_off)
+        );
+    }
+
+    #[test]
+    fn engine_tempo_drives_both_synced_lfos() {
+        let (mut engine, mut ctrl) = make_engine();
+        let eighth = NoteDivision::new(NoteValue::Eighth, NoteFeel::Straight);
+        ctrl.set_lfo_param(LfoParam::SyncDivision, eighth.to_code() as f32);
+        ctrl.set_lfo2_param(
+            LfoParam::SyncDivision,
+            NoteDivision::default().to_code() as f32,
+        );
+        ctrl.set_tempo(90.0);
+        engine.process_commands();
+        assert!((engine.lfo.get_frequency_hz() - 3.0).abs() < 1e-4);
+        assert!((engine.lfo2.get_frequency_hz() - 1.5).abs() < 1e-4);
+
+        ctrl.set_tempo(1000.0); // clamped
+        ctrl.set_lfo2_param(LfoParam::SyncDivision, -1.0);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.tempo_bpm, crate::tempo::MAX_BPM);
+        assert_eq!(snap.lfo_sync, Some(eighth));
+        assert_eq!(snap.lfo2.sync, None);
+    }
+
+    #[test]
+    fn engine_effect_order_and_bypass_reach_the_chain() {
+        let (mut engine, mut ctrl) = make_engine();
+        let reversed = [
+            EffectSlot::Reverb,
+            EffectSlot::Delay,
+            EffectSlot::AutoPan,
+            EffectSlot::Chorus,
+            EffectSlot::Phaser,
+            EffectSlot::Filter,
+            EffectSlot::Eq,
+        ];
+        ctrl.set_effect_order(reversed);
+        ctrl.set_effects_bypass(true);
+        ctrl.set_extensions(true);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.effect_order, reversed);
+        assert!(snap.effects_bypass);
+        assert!(snap.extensions);
+
+        // A repeated slot is not a valid order and is dropped.
+        ctrl.set_effect_order([EffectSlot::Delay; EFFECT_SLOTS]);
+        engine.process_commands();
+        assert_eq!(engine.effects.order(), reversed);
+    }
+
+    #[test]
+    fn engine_effect_output_gain_and_wet_solo_reach_the_snapshot() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_effect_param(EffectType::Delay, EffectParam::OutputGain, -3.0);
+        ctrl.set_effect_param(EffectType::Reverb, EffectParam::SoloWet, 1.0);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.effect_gains_db[EffectSlot::Delay.index()], -3.0);
+        assert_eq!(snap.wet_solo, Some(EffectSlot::Reverb));
+
+        // Un-soloing another effect leaves the reverb solo alone.
+        ctrl.set_effect_param(EffectType::Delay, EffectParam::SoloWet, 0.0);
+        engine.process_commands();
+        assert_eq!(engine.effects.wet_solo(), Some(EffectSlot::Reverb));
+        ctrl.set_effect_param(EffectType::Reverb, EffectParam::SoloWet, 0.0);
+        engine.process_commands();
+        assert_eq!(engine.effects.wet_solo(), None);
+    }
+
+    #[test]
+    fn engine_lfo2_and_operator_pms_reach_the_snapshot() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_lfo2_enabled(true);
+        ctrl.set_lfo2_param(LfoParam::Rate, 120.0);
+        ctrl.set_operator_param(2, OperatorParam::PmSensitivity, 9.0);
+        ctrl.set_operator_param(3, OperatorParam::PmSensitivity, 4.0);
+        ctrl.set_operator_param(3, OperatorParam::PmSensitivity, -1.0);
+        ctrl.set_operator_param(4, OperatorParam::Lfo2, 1.0);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert!(snap.lfo2.enabled);
+        assert_eq!(snap.lfo2.rate, 99.0);
+        assert_eq!(snap.operators[2].pm_sensitivity, Some(7));
+        assert_eq!(snap.operators[3].pm_sensitivity, None);
+        assert!(snap.operators[4].lfo2);
+        // The main LFO is untouched.
+        assert_eq!(snap.lfo_rate, engine.lfo.rate);
+        assert_ne!(engine.lfo.rate, 99.0);
+    }
+
+    fn render_with_route(route: Option<ModRoute>, mod_wheel: f32) -> Vec<f32> {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_mod_route(0, route);
+        ctrl.mod_wheel(mod_wheel);
+        ctrl.note_on(60, 100);
+        let mut left = vec![0.0; 2048];
+        let mut right = vec![0.0; 2048];
+        engine.process_block(&mut left, &mut right);
+        left
+    }
+
+    #[test]
+    fn engine_mod_route_is_applied_and_reported() {
+        use crate::mod_matrix::{ModDestination, ModSource};
         let (mut engine, mut ctrl) = make_engine();
-        // Chorus
-        ctrl.set_effect_param(EffectType::Chorus, EffectParam::Enabled, 1.0);
-        ctrl.set_effect_param(EffectType::Chorus, EffectParam::Mix, 0.5);
-        ctrl.set_effect_param(EffectType::Chorus, EffectParam::ChorusRate, 2.0);
-        ctrl.set_effect_param(EffectType::Chorus, EffectParam::ChorusDepth, 5.0);
-        ctrl.set_effect_param(EffectType::Chorus, EffectParam::ChorusFeedback, 0.3);
-        // AutoPan
-        ctrl.set_effect_param(EffectType::AutoPan, EffectParam::Enabled, 1.0);
-        ctrl.set_effect_param(EffectType::AutoPan, EffectParam::AutoPanRate, 4.5);
-        ctrl.set_effect_param(EffectType::AutoPan, EffectParam::AutoPanDepth, 0.6);
-        // Delay
-        ctrl.set_effect_param(EffectType::Delay, EffectParam::Enabled, 1.0);
-        ctrl.set_effect_param(EffectType::Delay, EffectParam::Mix, 0.4);
-        ctrl.set_effect_param(EffectType::Delay, EffectParam::DelayTime, 200.0);
-        ctrl.set_effect_param(EffectType::Delay, EffectParam::DelayFeedback, 0.5);
-        ctrl.set_effect_param(EffectType::Delay, EffectParam::DelayPingPong, 1.0);
-        // Reverb
-        ctrl.set_effect_param(EffectType::Reverb, EffectParam::Enabled, 1.0);
-        ctrl.set_effect_param(EffectType::Reverb, EffectParam::Mix, 0.3);
-        ctrl.set_effect_param(EffectType::Reverb, EffectParam::ReverbRoomSize, 0.8);
-        ctrl.set_effect_param(EffectType::Reverb, EffectParam::ReverbDamping, 0.4);
-        ctrl.set_effect_param(EffectType::Reverb, EffectParam::ReverbWidth, 0.9);
+        let route = ModRoute::new(ModSource::Velocity, ModDestination::Pitch, 0.5);
+        ctrl.set_mod_route(3, Some(route));
+        ctrl.set_mod_route(200, Some(route)); // out of range: ignored
+        engine.process_commands();
+        engine.update_snapshot();
+        let routes = *ctrl.snapshot().mod_matrix.routes();
+        assert_eq!(routes[3], Some(route));
+        assert_eq!(routes.iter().flatten().count(), 1);
+        ctrl.set_mod_route(3, None);
         engine.process_commands();
+        engine.update_snapshot();
+        assert!(ctrl.snapshot().mod_matrix.is_empty());
     }
 
-    // -----------------------------------------------------------------------
-    // Controller routing & expression
-    // -----------------------------------------------------------------------
+    #[test]
+    fn engine_mod_routes_change_the_output() {
+        use crate::mod_matrix::{ModDestination, ModSource};
+        let plain = render_with_route(None, 1.0);
+        let pitch = ModRoute::new(ModSource::Velocity, ModDestination::Pitch, 1.0);
+        assert_ne!(render_with_route(Some(pitch), 1.0), plain);
+        let mix = ModRoute::new(ModSource::ModWheel, ModDestination::EffectMix, 1.0);
+        assert_ne!(render_with_route(Some(mix), 1.0), plain);
+        // A route whose source sits at zero leaves the sound alone.
+        assert_eq!(
+            render_with_route(Some(mix), 0.0),
+            render_with_route(None, 0.0)
+        );
+    }
 
     #[test]
-    fn engine_aftertouch_clamps_and_routes() {
+    fn engine_lfo2_routes_modulate_effects_only_while_it_runs() {
+        use crate::mod_matrix::{ModDestination, ModSource};
+        let render = |lfo2_on: bool, route: Option<ModRoute>| {
+            let (mut engine, mut ctrl) = make_engine();
+            ctrl.set_lfo2_enabled(lfo2_on);
+            ctrl.set_lfo2_param(LfoParam::Rate, 99.0);
+            ctrl.set_mod_route(0, route);
+            ctrl.note_on(60, 100);
+            let mut left = vec![0.0; 4096];
+            let mut right = vec![0.0; 4096];
+            engine.process_block(&mut left, &mut right);
+            left
+        };
+        for destination in [ModDestination::ChorusDepth, ModDestination::ReverbMix] {
+            let route = Some(ModRoute::new(ModSource::Lfo2, destination, 1.0));
+            assert_ne!(render(true, route), render(true, None), "{:?}", destination);
+            assert_eq!(
+                render(false, route),
+                render(false, None),
+                "{:?}",
+                destination
+            );
+        }
+    }
+
+    #[test]
+    fn engine_mod_route_can_silence_operators() {
+        use crate::mod_matrix::{ModDestination, ModSource};
         let (mut engine, mut ctrl) = make_engine();
-        ctrl.set_aftertouch_pitch_sens(7);
-        ctrl.set_aftertouch_amp_sens(5);
-        ctrl.set_aftertouch_eg_bias_sens(3);
-        ctrl.set_aftertouch_pitch_bias_sens(2);
-        ctrl.aftertouch(2.0); // clamped to 1.0
-        engine.process_commands();
-        assert_eq!(engine.aftertouch, 1.0);
+        for op in 0..6 {
+            let route = ModRoute::new(ModSource::ModWheel, ModDestination::OperatorLevel(op), -1.0);
+            ctrl.set_mod_route(op, Some(route));
+        }
+        ctrl.mod_wheel(1.0);
+        ctrl.note_on(60, 100);
+        let mut left = vec![0.0; 512];
+        let mut right = vec![0.0; 512];
+        engine.process_block(&mut left, &mut right);
+        assert!(left.iter().chain(&right).all(|s| s.abs() < 1e-6));
     }
 
     #[test]
@@ -2243,6 +6061,187 @@ mod tests {
         assert_eq!(engine.expression, 0.0);
     }
 
+    #[test]
+    fn expression_input_scales_flagged_operators() {
+        let (mut engine, mut ctrl) = make_engine();
+        // Algorithm 1: OP1 and OP3 are the carriers.
+        ctrl.set_operator_param(0, OperatorParam::Expression, 1.0);
+        ctrl.set_operator_param(2, OperatorParam::Expression, 1.0);
+        ctrl.breath_controller(0.0);
+        ctrl.note_on(60, 100);
+        // Past the ramp from the operators' initial full level.
+        drive(&mut engine, 1024);
+        let peak = |engine: &mut SynthEngine| {
+            (0..2048).fold(0.0_f32, |peak, _| {
+                engine.process_commands();
+                peak.max(engine.process().abs())
+            })
+        };
+        let silent = peak(&mut engine);
+        assert!(silent < 1e-3, "no breath, no carrier: {silent}");
+
+        ctrl.breath_controller(1.0);
+        let breath = peak(&mut engine);
+        assert!(breath > 0.01, "breath opens it up: {breath}");
+
+        // With CC11 as the source, breath no longer matters and CC11 moves
+        // from the master to the operators.
+        ctrl.set_expression_source(ExpressionSource::Expression);
+        ctrl.breath_controller(0.0);
+        drive(&mut engine, 1024);
+        let cc11_up = peak(&mut engine);
+        assert!(cc11_up > 0.01, "{cc11_up}");
+        ctrl.expression(0.0);
+        drive(&mut engine, 1024);
+        let cc11_down = peak(&mut engine);
+        assert!(cc11_down < 1e-3, "{cc11_down}");
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.expression_source, ExpressionSource::Expression);
+        assert!(snap.operators[0].expression);
+        assert!(!snap.operators[1].expression);
+    }
+
+    #[test]
+    fn a_nan_voice_never_reaches_the_output() {
+        let (mut engine, mut ctrl) = make_engine();
+        engine.effects.reverb.enabled = true;
+        engine.effects.delay.enabled = true;
+        // A NaN envelope level turns OP1's output into NaN at note-on.
+        for voice in &mut engine.voices {
+            voice.operators[0].envelope.level1 = f32::NAN;
+        }
+        ctrl.note_on(60, 100);
+        for _ in 0..256 {
+            engine.process_commands();
+            let (l, r) = engine.process_stereo();
+            assert!(l.is_finite() && r.is_finite());
+        }
+        if cfg!(debug_assertions) {
+            assert!(engine.voices.iter().all(|v| !v.active));
+        }
+    }
+
+    #[test]
+    fn debug_dump_reports_voices_keys_and_queue() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.note_on(60, 100);
+        ctrl.note_on(64, 100);
+        ctrl.note_off(64);
+        engine.process_commands();
+        ctrl.note_on(67, 100);
+
+        let dump = engine.debug_dump();
+        assert_eq!(dump.voices.len(), 2);
+        assert_eq!(dump.held_notes.len(), 1);
+        assert_eq!(dump.held_notes[0].0, 60);
+        let released = dump.voices.iter().find(|v| v.note == 64).unwrap();
+        assert!(released.released());
+        assert!(dump.unheld_voices().is_empty());
+        assert_eq!(dump.pending_commands, 1);
+        assert!(dump.command_capacity >= 1024);
+
+        assert!(ctrl.snapshot().debug_dump.is_none());
+        ctrl.request_debug_dump();
+        engine.process_commands();
+        engine.update_snapshot();
+        let snapshot = ctrl.snapshot();
+        assert_eq!(snapshot.debug_dump.expect("dump").voices.len(), 3);
+        // One request, one dump: the audio thread doesn't keep building them.
+        engine.update_snapshot();
+        assert!(ctrl.snapshot().debug_dump.is_none());
+    }
+
+    #[test]
+    fn watchdog_releases_voices_that_lost_their_key() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.note_on(60, 100);
+        ctrl.note_on(64, 100);
+        engine.process_commands();
+        let lost = engine.held_notes.remove(&64).unwrap();
+        let kept = engine.held_notes[&60];
+
+        // Off by default: the lost note drones on.
+        drive(&mut engine, SR as usize);
+        assert!(!engine.voices[lost].released());
+
+        ctrl.set_voice_watchdog(true);
+        drive(&mut engine, (SR * 0.6) as usize);
+        assert!(engine.voices[lost].released());
+        assert!(!engine.voices[kept].released());
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().watchdog_releases, 1);
+    }
+
+    #[test]
+    fn voice_timeout_releases_even_held_notes() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_voice_timeout(1.0);
+        ctrl.sustain_pedal(true);
+        ctrl.note_on(60, 100);
+        ctrl.note_off(60);
+        drive(&mut engine, (SR * 0.5) as usize);
+        let voice = engine.held_notes[&60];
+        assert!(!engine.voices[voice].released());
+
+        drive(&mut engine, (SR * 0.6) as usize);
+        assert!(engine.voices[voice].released());
+        assert!(engine.held_notes.is_empty());
+        ctrl.set_voice_timeout(-3.0);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snapshot = ctrl.snapshot();
+        assert_eq!(snapshot.watchdog_releases, 1);
+        assert_eq!(snapshot.voice_timeout, 0.0);
+    }
+
+    #[test]
+    fn all_notes_off_releases_through_the_pedal() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.note_on(60, 100);
+        ctrl.all_notes_off();
+        engine.process_commands();
+        let voice = engine.voices.iter().find(|v| v.note == 60).unwrap();
+        assert!(voice.active && voice.released());
+        assert!(engine.held_notes.is_empty());
+
+        ctrl.sustain_pedal(true);
+        ctrl.note_on(64, 100);
+        ctrl.all_notes_off();
+        engine.process_commands();
+        let held = engine.held_notes[&64];
+        assert!(!engine.voices[held].released());
+        ctrl.sustain_pedal(false);
+        engine.process_commands();
+        assert!(engine.voices[held].released());
+    }
+
+    #[test]
+    fn reset_controllers_puts_performance_controls_at_rest() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.pitch_bend(4000);
+        ctrl.mod_wheel(0.8);
+        ctrl.aftertouch(0.5);
+        ctrl.expression(0.2);
+        ctrl.breath_controller(0.6);
+        ctrl.sustain_pedal(true);
+        ctrl.note_on(60, 100);
+        ctrl.note_off(60);
+        ctrl.reset_controllers();
+        engine.process_commands();
+        engine.update_snapshot();
+        let snapshot = ctrl.snapshot();
+        assert_eq!(snapshot.pitch_bend, 0.0);
+        assert_eq!(snapshot.mod_wheel, 0.0);
+        assert_eq!(snapshot.aftertouch, 0.0);
+        assert_eq!(snapshot.expression, 1.0);
+        assert!(!snapshot.sustain_pedal);
+        assert!((engine.breath - 0.6).abs() < 1e-6);
+        // The pedal coming up released the key it was holding, normally.
+        let voice = engine.voices.iter().find(|v| v.note == 60).unwrap();
+        assert!(voice.active && voice.released());
+    }
+
     #[test]
     fn engine_bank_select_combines_with_program_change() {
         let (mut engine, mut ctrl) = make_engine();
@@ -2254,6 +6253,29 @@ mod tests {
         assert_eq!(engine.bank_lsb, 2);
     }
 
+    #[test]
+    fn engine_program_map_overrides_the_bank_position() {
+        let (mut engine, mut ctrl) = make_engine();
+        engine.set_presets(vec![make_preset("FIRST", 1), make_preset("SECOND", 2)]);
+        let mut map = crate::program_map::ProgramMap::default();
+        map.assign(0, &make_preset("USER PAD", 9));
+        ctrl.set_program_map(map.resolve(&[make_preset("USER PAD", 9)]));
+        ctrl.program_change(0);
+        engine.process_commands();
+        assert_eq!(engine.preset_name, "USER PAD");
+        assert_eq!(engine.algorithm, 9);
+
+        // Unmapped numbers still load by position.
+        ctrl.program_change(1);
+        engine.process_commands();
+        assert_eq!(engine.preset_name, "SECOND");
+
+        ctrl.set_program_map(Vec::new());
+        ctrl.program_change(0);
+        engine.process_commands();
+        assert_eq!(engine.preset_name, "FIRST");
+    }
+
     #[test]
     fn engine_eg_bias_and_pitch_bias_sensitivities_clamp() {
         let (mut engine, mut ctrl) = make_engine();
@@ -2277,13 +6299,114 @@ mod tests {
         assert!(engine.portamento_glissando);
     }
 
+    // -----------------------------------------------------------------------
+    // Performance (dual / split)
+    // -----------------------------------------------------------------------
+
+    fn performance(mode: PerformanceMode) -> Performance {
+        Performance {
+            mode,
+            split_point: 60,
+            ..Performance::default()
+        }
+    }
+
+    fn part_b(engine: &SynthEngine) -> &SynthEngine {
+        engine.part_b.as_deref().expect("part B")
+    }
+
+    #[test]
+    fn single_mode_leaves_part_b_silent() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        assert!(engine.held_notes.contains_key(&60));
+        assert!(part_b(&engine).held_notes.is_empty());
+    }
+
+    #[test]
+    fn dual_mode_layers_both_parts_and_releases_both() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_performance(performance(PerformanceMode::Dual));
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        assert!(engine.held_notes.contains_key(&60));
+        assert!(part_b(&engine).held_notes.contains_key(&60));
+
+        ctrl.note_off(60);
+        engine.process_commands();
+        assert!(engine.held_notes.is_empty());
+        assert!(part_b(&engine).held_notes.is_empty());
+    }
+
+    #[test]
+    fn split_mode_routes_notes_by_zone() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_performance(performance(PerformanceMode::Split));
+        ctrl.note_on(59, 100);
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        assert_eq!(engine.held_notes.keys().collect::<Vec<_>>(), [&59]);
+        assert_eq!(part_b(&engine).held_notes.keys().collect::<Vec<_>>(), [&60]);
+
+        engine.update_snapshot();
+        let snap = ctrl.snapshot();
+        assert_eq!(snap.held_keys, 1 << 59 | 1 << 60);
+        assert_eq!(snap.active_voices, 2);
+        assert_eq!(snap.performance.mode, PerformanceMode::Split);
+    }
+
+    #[test]
+    fn part_b_plays_its_own_preset_volume_and_transpose() {
+        let (mut engine, mut ctrl) = make_engine();
+        let mut dual = performance(PerformanceMode::Dual);
+        dual.parts[0].volume = 0.0;
+        dual.parts[1].transpose = 12;
+        ctrl.set_performance(dual);
+        let mut preset = make_preset("PART B", 5);
+        preset.transpose_semitones = 0;
+        ctrl.load_part_b(preset);
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().part_b_name, "PART B");
+        assert_eq!(part_b(&engine).algorithm, 5);
+        assert_eq!(engine.algorithm, 1, "part A keeps its voice");
+        let b_voice = part_b(&engine).held_notes[&60];
+        assert_eq!(part_b(&engine).voices[b_voice].note, 72);
+
+        // Part A is muted, so everything heard comes from part B.
+        let ramp = (SR * crate::smoothing::PARAM_SMOOTHING_MS / 1000.0) as usize;
+        drive(&mut engine, ramp);
+        assert_eq!(engine.master_gain.current(), 0.0);
+        let (peak, _) = drive_stereo(&mut engine, 2048);
+        assert!(peak > 0.01, "part B is heard: {peak}");
+    }
+
+    #[test]
+    fn back_to_single_releases_part_b() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_performance(performance(PerformanceMode::Dual));
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        ctrl.set_performance(performance(PerformanceMode::Single));
+        ctrl.note_on(64, 100);
+        engine.process_commands();
+        assert!(part_b(&engine).held_notes.is_empty());
+        assert!(engine.held_notes.contains_key(&64));
+
+        // The released note still rings out through the mix.
+        drive(&mut engine, 1);
+        assert!(engine.part_b_running);
+    }
+
     // -----------------------------------------------------------------------
     // Snapshots & preset loading
     // -----------------------------------------------------------------------
 
     #[test]
     fn engine_update_snapshot_publishes_to_controller() {
-        let (engine, ctrl) = make_engine();
+        let (mut engine, ctrl) = make_engine();
         engine.update_snapshot();
         let snap = ctrl.snapshot();
         assert_eq!(snap.algorithm, 1);
@@ -2321,6 +6444,155 @@ mod tests {
         assert_eq!(engine.algorithm, 7);
     }
 
+    #[test]
+    fn apply_preset_sets_the_edit_buffer_and_keeps_the_bank() {
+        let (mut engine, mut ctrl) = make_engine();
+        engine.set_presets(vec![make_preset("BANK", 3)]);
+        ctrl.apply_preset(make_preset("BROWSED", 9));
+        engine.process_commands();
+        assert_eq!(engine.preset_name, "BROWSED");
+        assert_eq!(engine.algorithm, 9);
+        ctrl.load_preset(0);
+        engine.process_commands();
+        assert_eq!(engine.preset_name, "BANK");
+    }
+
+    #[test]
+    fn preset_switch_crossfades_held_voices_from_the_old_settings() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.note_on(69, 100);
+        engine.process_commands();
+        for _ in 0..256 {
+            engine.process_stereo();
+        }
+        let idle = engine.voices.iter().position(|v| !v.active).unwrap();
+        let held = engine.held_notes[&69];
+        let old_level = engine.voices[held].operators[0].output_level;
+
+        let mut preset = make_preset("SWITCHED", 5);
+        preset.operators[0].output_level = old_level - 20.0;
+        ctrl.apply_preset(preset);
+        engine.process_commands();
+
+        let voice = &engine.voices[held];
+        assert_eq!(voice.fade_state, VoiceFadeState::Crossfade);
+        assert_eq!(voice.tail[0].output_level, old_level);
+        assert_eq!(voice.operators[0].output_level, old_level - 20.0);
+        assert!(!engine.voices[idle].active, "idle voices stay silent");
+
+        let fade_samples = (SR * DEFAULT_STEAL_FADE_MS / 1000.0) as usize + 2;
+        for _ in 0..fade_samples {
+            engine.process_stereo();
+        }
+        assert_eq!(engine.voices[held].fade_state, VoiceFadeState::Normal);
+        assert!(engine.voices[held].active, "the held note keeps sounding");
+    }
+
+    #[test]
+    fn pitch_bend_keeps_14_bit_steps_and_reaches_full_scale() {
+        assert_eq!(bend_from_14bit(8191), 1.0);
+        assert_eq!(bend_from_14bit(-8192), -1.0);
+        assert_eq!(bend_from_14bit(0), 0.0);
+        assert!(bend_from_14bit(1) > 0.0);
+        assert!(bend_from_14bit(4097) > bend_from_14bit(4096));
+        assert_eq!(bend_from_14bit(i16::MAX), 1.0);
+    }
+
+    #[test]
+    fn pitch_bend_glides_to_the_wheel() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_pitch_bend_smoothing(10.0);
+        ctrl.pitch_bend(8191);
+        engine.process_commands();
+        let first = engine.control_signals(CONTROL_BLOCK).pitch_bend;
+        assert!(
+            first > 0.0 && first < 0.1,
+            "first block only starts the glide: {first}"
+        );
+        let settle = (SR * 0.1) as usize / CONTROL_BLOCK;
+        for _ in 0..settle {
+            engine.control_signals(CONTROL_BLOCK);
+        }
+        assert_eq!(engine.control_signals(CONTROL_BLOCK).pitch_bend, 1.0);
+
+        ctrl.set_pitch_bend_smoothing(0.0);
+        ctrl.pitch_bend(-8192);
+        engine.process_commands();
+        assert_eq!(engine.control_signals(CONTROL_BLOCK).pitch_bend, -1.0);
+        engine.update_snapshot();
+        assert_eq!(ctrl.snapshot().pitch_bend_smoothing_ms, 0.0);
+    }
+
+    #[test]
+    fn algorithm_change_crossfades_held_voices() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.note_on(60, 100);
+        engine.process_commands();
+        for _ in 0..256 {
+            engine.process_stereo();
+        }
+        let held = engine.held_notes[&60];
+        assert_eq!(engine.voices[held].fade_state, VoiceFadeState::Normal);
+
+        ctrl.set_algorithm(engine.algorithm);
+        engine.process_commands();
+        assert_eq!(
+            engine.voices[held].fade_state,
+            VoiceFadeState::Normal,
+            "re-selecting the same algorithm changes nothing"
+        );
+
+        ctrl.set_algorithm(17);
+        engine.process_commands();
+        assert_eq!(engine.algorithm, 17);
+        assert_eq!(engine.voices[held].fade_state, VoiceFadeState::Crossfade);
+    }
+
+    #[test]
+    fn retrigger_mode_restarts_held_notes_on_the_new_patch() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_patch_change_mode(PatchChangeMode::Retrigger);
+        ctrl.note_on(60, 100);
+        ctrl.note_on(64, 40);
+        engine.process_commands();
+        for _ in 0..2048 {
+            engine.process_stereo();
+        }
+        ctrl.note_off(64);
+        engine.process_commands();
+        let held = engine.held_notes[&60];
+        let released = engine
+            .voices
+            .iter()
+            .position(|v| v.active && v.note == 64)
+            .unwrap();
+        assert_ne!(
+            engine.voices[held].operators[0].envelope.stage(),
+            EnvelopeStage::Stage1
+        );
+
+        let mut preset = make_preset("RETRIGGERED", 5);
+        preset.transpose_semitones = 12;
+        ctrl.apply_preset(preset);
+        engine.process_commands();
+
+        let voice = &engine.voices[held];
+        assert_eq!(voice.note, 72, "restarted with the new transpose");
+        assert_eq!(voice.velocity, 100.0 / 127.0);
+        assert_eq!(voice.operators[0].envelope.stage(), EnvelopeStage::Stage1);
+        assert_eq!(voice.fade_state, VoiceFadeState::Crossfade);
+        // A released voice is not restarted; it crossfades as it decays.
+        let voice = &engine.voices[released];
+        assert_eq!(voice.note, 64);
+        assert_eq!(voice.operators[0].envelope.stage(), EnvelopeStage::Stage4);
+        assert_eq!(voice.fade_state, VoiceFadeState::Crossfade);
+        engine.update_snapshot();
+        assert_eq!(
+            ctrl.snapshot().patch_change_mode,
+            PatchChangeMode::Retrigger
+        );
+    }
+
     #[test]
     fn engine_load_sysex_bulk_applies_first_and_replaces_bank() {
         let (mut engine, mut ctrl) = make_engine();
@@ -2347,9 +6619,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn master_volume_changes_ramp_instead_of_stepping() {
+        let (mut engine, mut ctrl) = make_engine();
+        ctrl.set_master_volume(0.2);
+        engine.process_commands();
+        engine.process_stereo();
+        let gain = engine.master_gain.current();
+        assert!(
+            gain < 0.7 && gain > 0.69,
+            "first sample barely moves: {gain}"
+        );
+        assert_eq!(engine.master_volume, 0.2, "the snapshot shows the target");
+
+        let ramp = (SR * crate::smoothing::PARAM_SMOOTHING_MS / 1000.0) as usize;
+        for _ in 0..ramp {
+            engine.process_stereo();
+        }
+        assert_eq!(engine.master_gain.current(), 0.2);
+    }
+
     #[test]
     fn engine_get_snapshot_returns_clone() {
-        let (engine, ctrl) = make_engine();
+        let (mut engine, ctrl) = make_engine();
         engine.update_snapshot();
         let snap = ctrl.snapshot();
         let snap2 = ctrl.snapshot();
@@ -1,28 +1,77 @@
+use crate::algorithm_matrix::{AlgorithmMatrix, MAX_FEEDBACK};
 use crate::algorithms;
-use crate::audio_engine::AudioEngine;
+use crate::audio_engine::{
+    available_hosts, list_output_devices, play_test_tone, AudioEngine, AudioSettings,
+    OutputDeviceInfo, COMMON_BUFFER_SIZES, COMMON_SAMPLE_RATES,
+};
 use crate::command_queue::{
-    EffectParam, EffectType, EnvelopeParam, LfoParam, OperatorParam, PitchEgParam,
+    CompressorParam, EffectParam, EffectType, EnvelopeParam, LfoParam, OperatorParam, PitchEgParam,
+};
+use crate::compare::{CompareSlots, COMPARE_SLOTS, COMPARE_SLOT_NAMES};
+use crate::compressor::{LIMIT_RATIO, MAX_MAKEUP_DB, MIN_THRESHOLD_DB};
+use crate::data_entry::{DataEntryParam, OperatorField};
+use crate::debug_dump::EngineDump;
+use crate::dx7_frequency;
+use crate::effects::{
+    move_effect_slot, EffectSlot, EqBand, ReverbType, VoiceSpread, CHORUS_MAX_DEPTH_MS,
+    DELAY_HIGH_CUT_MAX_HZ, DELAY_LOW_CUT_MIN_HZ, DELAY_MAX_FEEDBACK, EFFECT_MAX_GAIN_DB,
+    EFFECT_SLOTS, EQ_MAX_GAIN_DB, FILTER_MAX_HZ, FILTER_MIN_HZ, PHASER_MAX_FEEDBACK, PHASER_STAGES,
+    PHASER_VINTAGE_EP, REVERB_MAX_PRE_DELAY_MS,
 };
-use crate::fm_synth::{SynthController, SynthEngine};
-use crate::midi_handler::MidiHandler;
-use crate::operator::KeyScaleCurve;
+use crate::envelope::{envelope_times, EnvelopeTimes};
+use crate::fm_synth::{
+    SynthController, MAX_BEND_SMOOTHING_MS, MAX_UNISON, MAX_UNISON_DETUNE, POLYPHONY_CHOICES,
+};
+use crate::keybindings::{KeyAction, KeyBindings, KeyboardLayout};
+use crate::lcd::{Lcd, LcdButton, LcdMode};
+use crate::librarian::{Library, LibraryFilter, CATEGORIES};
+use crate::midi_handler::{ClockSource, MidiHandler};
+use crate::midi_learn::MidiLearn;
+use crate::mod_matrix::{ModDestination, ModSource};
+use crate::operator::{key_rate_scale_factor, KeyScaleCurve};
+use crate::optimization::OperatorWaveform;
+use crate::performance::{PerformanceMode, PART_TRANSPOSE_RANGE};
+use crate::preset_preview::PresetPreview;
 use crate::presets::Dx7Preset;
-use crate::state_snapshot::SynthSnapshot;
+use crate::render::parse_midi_file;
+use crate::sequencer::{Sequencer, SequencerPlayer, MAX_TEMPO_SCALE, MIN_TEMPO_SCALE};
+use crate::settings::{AppSettings, Theme, OCTAVE_RANGE};
+use crate::spectrum::{self, SPECTRUM_FLOOR_DB};
+use crate::state_snapshot::SnapshotWatcher;
+use crate::state_snapshot::{
+    ExpressionSource, NotePriority, OperatorSnapshot, PatchChangeMode, PortamentoMode,
+    SameNoteMode, SynthSnapshot,
+};
+use crate::sysex::{BankFile, VoiceError};
+use crate::tempo::{NoteDivision, MAX_BPM, MIN_BPM};
+use crate::tuning::{Tuning, EQUAL_TEMPERAMENT};
+use crate::user_presets::{self, USER_COLLECTION};
+use crate::vintage::{MAX_NOISE_DB, MIN_NOISE_DB};
 use eframe::egui;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
 
 pub struct Dx7App {
-    engine: Arc<Mutex<SynthEngine>>,
     controller: Arc<Mutex<SynthController>>,
-    /// Owned to keep the audio stream alive. Optional so unit tests can
-    /// construct a `Dx7App` without a real audio device.
-    _audio_engine: Option<AudioEngine>,
+    /// Owns the audio stream, which the AUDIO page can rebuild. Optional so
+    /// unit tests can construct a `Dx7App` without a real audio device.
+    audio_engine: Option<AudioEngine>,
     _midi_handler: Option<MidiHandler>,
+    /// CC bindings and the learn in progress, shared with the MIDI input
+    /// callback. Right-clicking a tracked control arms learn for it.
+    midi_learn: Arc<Mutex<MidiLearn>>,
     selected_operator: usize,
     display_mode: DisplayMode,
     display_text: String,
     last_key_times: std::collections::HashMap<egui::Key, std::time::Instant>,
     current_octave: i32,
+    /// Hold key state: the sustain pedal is down while this is set.
+    keyboard_hold: bool,
+    /// Note held by the mouse on the on-screen piano.
+    piano_note: Option<u8>,
+    /// Key sounding while a RATE SCALING audition button is held.
+    rate_audition: Option<u8>,
     presets: Vec<Dx7Preset>,
     selected_preset: usize,
     /// Active collection filter; None = show all collections.
@@ -36,63 +85,299 @@ pub struct Dx7App {
     sysex_status: String,
     /// Cached MIDI channel selection: None = OMNI, Some(0..15) = specific channel.
     midi_channel_ui: Option<u8>,
+    key_bindings: KeyBindings,
+    /// Where the KEYS page saves bindings; None disables saving.
+    key_bindings_path: Option<std::path::PathBuf>,
+    /// Action waiting for the next key press in the KEYS editor.
+    capturing_key: Option<KeyAction>,
+    /// Last status line shown in the KEYS page (save feedback).
+    key_bindings_status: String,
+    /// Preview engine for auditioning presets; None disables the feature.
+    preview: Option<PresetPreview>,
+    /// Audition presets when hovered for `PREVIEW_HOVER_DELAY` or stepped
+    /// to with the preset keys.
+    preview_on_hover: bool,
+    /// Preset currently under the pointer and when the hover started.
+    preview_hover: Option<(usize, std::time::Instant)>,
+    /// Preset already auditioned for the current hover.
+    preview_played: Option<usize>,
+    /// Underrun count last seen in a snapshot, and how long the XRUN
+    /// warning stays up after it went up.
+    underruns_seen: u32,
+    xrun_warning_until: Option<std::time::Instant>,
+    /// Sequence of the snapshot in `snapshot`; a frame only copies a new
+    /// one when the audio thread has published since.
+    snapshot_seen: Option<u64>,
+    /// GUI frame-rate cap, shared with the repaint notifier.
+    gui_fps: Arc<AtomicU32>,
+    repaint_notifier: Option<RepaintNotifier>,
+    /// Keep the operator editor in a side panel on every page, so presets
+    /// can be browsed and tweaked without switching modes.
+    pin_operator_panel: bool,
+    /// Output devices shown on the AUDIO page; None until first listed.
+    audio_devices: Option<Vec<OutputDeviceInfo>>,
+    /// Device / rate / buffer picked on the AUDIO page, applied on APPLY.
+    audio_settings: AudioSettings,
+    /// Last status line shown in the AUDIO page (apply feedback).
+    audio_status: String,
+    /// Operator settings copied with COPY OP / COPY EG, waiting for PASTE.
+    operator_clipboard: Option<OperatorClipboard>,
+    /// MIDI file edited in the transport bar.
+    midi_file_path: String,
+    /// Player for the loaded MIDI file; None until one is loaded.
+    midi_player: Option<SequencerPlayer>,
+    /// Last status line of the transport bar (load feedback).
+    midi_file_status: String,
+    /// Play head position when AUTO recording started.
+    automation_start: f32,
+    /// Favorites and category tags shown on the LIBRARY page.
+    library: Library,
+    /// Where the LIBRARY page saves its index; None disables saving.
+    library_path: Option<std::path::PathBuf>,
+    /// Folder user presets are saved to; None disables saving.
+    user_presets_dir: Option<std::path::PathBuf>,
+    library_filter: LibraryFilter,
+    /// Presets in the A and B audition slots, and the slot heard last.
+    library_ab: [Option<usize>; 2],
+    library_ab_last: usize,
+    /// Voices of imported bank files that were clamped or missing, by
+    /// collection; listed on the LIBRARY page.
+    bank_errors: Vec<(String, VoiceError)>,
+    /// Edit states parked in the A-D compare slots.
+    compare: CompareSlots,
+    /// Last status line of the LIBRARY page (save feedback).
+    library_status: String,
+    /// MIDI port, volume, octave, theme, window size and program-change
+    /// map saved on exit.
+    settings: AppSettings,
+    /// Where the settings are saved on exit; None disables saving.
+    settings_path: Option<std::path::PathBuf>,
+    show_settings: bool,
+    /// Last status line of the Settings dialog (save feedback).
+    settings_status: String,
+    /// Program number and preset index the program-change map editor
+    /// assigns next.
+    program_map_pick: (u8, usize),
+    /// Wheel scroll over the algorithm diagram not yet worth a whole notch.
+    algorithm_scroll: f32,
+    /// Keyboard shortcut cheat sheet.
+    show_shortcuts: bool,
+    /// Front-panel LCD mode and page. Its page's parameter is what the
+    /// DATA ENTRY slider and YES / NO buttons edit; in MEMORY mode they
+    /// select the voice.
+    lcd: Lcd,
+    /// Hidden engine debug panel, toggled with Ctrl+Shift+D.
+    show_debug_panel: bool,
+    /// Raised by the SIGUSR1 handler: print the next engine dump to stderr.
+    dump_signal: Arc<AtomicBool>,
+    dump_to_stderr: bool,
+    /// Latest dump the engine sent, shown by the debug panel.
+    debug_dump: Option<EngineDump>,
+    /// When the engine was last asked for a dump.
+    debug_dump_requested_at: Option<std::time::Instant>,
+}
+
+/// Settings held by the operator COPY / PASTE buttons.
+struct OperatorClipboard {
+    source: usize,
+    params: OperatorSnapshot,
+    envelope_only: bool,
+}
+
+/// Scroll distance of one mouse-wheel notch, in points.
+const ALGORITHM_SCROLL_NOTCH: f32 = 40.0;
+
+/// Keys that select OP1..OP6 while Alt is held. Alone, 2, 3, 5 and 6 play
+/// the upper-octave sharps.
+const OPERATOR_KEYS: [egui::Key; 6] = [
+    egui::Key::Num1,
+    egui::Key::Num2,
+    egui::Key::Num3,
+    egui::Key::Num4,
+    egui::Key::Num5,
+    egui::Key::Num6,
+];
+
+/// How often the open debug panel asks the engine for a fresh dump.
+const DEBUG_DUMP_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Hover time before a preset is auditioned.
+const PREVIEW_HOVER_DELAY: std::time::Duration = std::time::Duration::from_millis(300);
+
+/// GUI frame-rate caps offered on the AUDIO page.
+pub const GUI_FPS_CHOICES: [u32; 4] = [15, 30, 60, 120];
+const DEFAULT_GUI_FPS: u32 = 30;
+
+fn frame_interval(fps: u32) -> std::time::Duration {
+    std::time::Duration::from_micros(1_000_000 / fps.max(1) as u64)
+}
+
+/// Wakes the GUI when the audio thread publishes a snapshot, at most `fps`
+/// times a second. The audio thread only bumps the snapshot counter; this
+/// thread turns that into `request_repaint`, so meters, scope and envelope
+/// dots animate without input events and egui sleeps when nothing changes.
+pub struct RepaintNotifier {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RepaintNotifier {
+    pub fn spawn(ctx: egui::Context, watcher: SnapshotWatcher, fps: Arc<AtomicU32>) -> Self {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let flag = shutdown.clone();
+        let handle = std::thread::Builder::new()
+            .name("gui-repaint".to_string())
+            .spawn(move || {
+                let mut seen = watcher.sequence();
+                while !flag.load(Ordering::Relaxed) {
+                    std::thread::sleep(frame_interval(fps.load(Ordering::Relaxed)));
+                    let sequence = watcher.sequence();
+                    if sequence != seen {
+                        seen = sequence;
+                        ctx.request_repaint();
+                    }
+                }
+            })
+            .map_err(|e| log::warn!("GUI repaint thread not started: {e}"))
+            .ok();
+        Self { shutdown, handle }
+    }
+}
+
+impl Drop for RepaintNotifier {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
 }
 
+/// How long the display shows the XRUN warning after an underrun.
+const XRUN_WARNING: std::time::Duration = std::time::Duration::from_secs(3);
+/// CPU load (render time / buffer time) above which the warning stays up.
+const CPU_LOAD_WARNING: f32 = 0.8;
+
+const LFO_SYNC_HINT: &str = "One LFO cycle per note division instead of Rate";
+
 #[derive(PartialEq)]
 #[allow(clippy::upper_case_acronyms)]
 enum DisplayMode {
     Voice,
+    Library,
     Operator,
     LFO,
     Effects,
     Midi,
+    Audio,
+    Keys,
+    Scope,
+    Spectrum,
+}
+
+impl DisplayMode {
+    fn next(&self) -> DisplayMode {
+        match self {
+            DisplayMode::Voice => DisplayMode::Library,
+            DisplayMode::Library => DisplayMode::Operator,
+            DisplayMode::Operator => DisplayMode::LFO,
+            DisplayMode::LFO => DisplayMode::Effects,
+            DisplayMode::Effects => DisplayMode::Midi,
+            DisplayMode::Midi => DisplayMode::Audio,
+            DisplayMode::Audio => DisplayMode::Keys,
+            DisplayMode::Keys => DisplayMode::Scope,
+            DisplayMode::Scope => DisplayMode::Spectrum,
+            DisplayMode::Spectrum => DisplayMode::Voice,
+        }
+    }
+
+    /// Main LCD line shown when entering the page.
+    fn title(&self) -> &'static str {
+        match self {
+            DisplayMode::Voice => "VOICE SELECT",
+            DisplayMode::Library => "PATCH LIBRARY",
+            DisplayMode::Operator => "OPERATOR",
+            DisplayMode::LFO => "LFO CONTROLS",
+            DisplayMode::Effects => "EFFECTS",
+            DisplayMode::Midi => "MIDI / CONTROLLERS",
+            DisplayMode::Audio => "AUDIO SETUP",
+            DisplayMode::Keys => "KEYBOARD SHORTCUTS",
+            DisplayMode::Scope => "OSCILLOSCOPE",
+            DisplayMode::Spectrum => "SPECTRUM ANALYZER",
+        }
+    }
 }
 
 impl Dx7App {
     pub fn new(
-        engine: Arc<Mutex<SynthEngine>>,
         controller: Arc<Mutex<SynthController>>,
         audio_engine: AudioEngine,
         midi_handler: Option<MidiHandler>,
+        midi_learn: Arc<Mutex<MidiLearn>>,
         presets: Vec<Dx7Preset>,
+        preview: Option<PresetPreview>,
     ) -> Self {
-        Self::build(
-            engine,
+        let mut app = Self::build(
             controller,
             Some(audio_engine),
             midi_handler,
+            midi_learn,
             presets,
-        )
+            KeyBindings::default_path(),
+        );
+        app.preview = preview;
+        app.audio_settings = AudioSettings::default_path()
+            .map(|path| AudioSettings::load_or_default(&path))
+            .unwrap_or_default();
+        app.library_path = Library::default_path();
+        app.user_presets_dir = user_presets::default_dir();
+        app.library = app
+            .library_path
+            .as_deref()
+            .map(Library::load_or_default)
+            .unwrap_or_default();
+        app.settings_path = AppSettings::default_path();
+        let settings = app
+            .settings_path
+            .as_deref()
+            .map(AppSettings::load_or_default)
+            .unwrap_or_default();
+        app.apply_settings(settings);
+        app
     }
 
     /// Test-only constructor: builds a `Dx7App` without a real audio engine.
     #[cfg(test)]
-    pub fn new_for_test(
-        engine: Arc<Mutex<SynthEngine>>,
-        controller: Arc<Mutex<SynthController>>,
-        presets: Vec<Dx7Preset>,
-    ) -> Self {
-        Self::build(engine, controller, None, None, presets)
+    pub fn new_for_test(controller: Arc<Mutex<SynthController>>, presets: Vec<Dx7Preset>) -> Self {
+        Self::build(controller, None, None, Arc::default(), presets, None)
     }
 
     fn build(
-        engine: Arc<Mutex<SynthEngine>>,
         controller: Arc<Mutex<SynthController>>,
         audio_engine: Option<AudioEngine>,
         midi_handler: Option<MidiHandler>,
+        midi_learn: Arc<Mutex<MidiLearn>>,
         presets: Vec<Dx7Preset>,
+        key_bindings_path: Option<std::path::PathBuf>,
     ) -> Self {
         let snapshot = controller.lock().map(|c| c.snapshot()).unwrap_or_default();
+        let key_bindings = key_bindings_path
+            .as_deref()
+            .map(KeyBindings::load_or_default)
+            .unwrap_or_default();
         Self {
-            engine,
             controller,
-            _audio_engine: audio_engine,
+            audio_engine,
             _midi_handler: midi_handler,
+            midi_learn,
             selected_operator: 0,
             display_mode: DisplayMode::Voice,
             display_text: "DX7 FM SYNTH".to_string(),
             last_key_times: std::collections::HashMap::new(),
             current_octave: 4,
+            keyboard_hold: false,
+            piano_note: None,
+            rate_audition: None,
             presets,
             selected_preset: 0,
             selected_collection: None,
@@ -101,14 +386,147 @@ impl Dx7App {
             sysex_path: String::from("voice.syx"),
             sysex_status: String::new(),
             midi_channel_ui: None,
+            key_bindings,
+            key_bindings_path,
+            capturing_key: None,
+            key_bindings_status: String::new(),
+            preview: None,
+            preview_on_hover: false,
+            preview_hover: None,
+            underruns_seen: 0,
+            xrun_warning_until: None,
+            snapshot_seen: None,
+            gui_fps: Arc::new(AtomicU32::new(DEFAULT_GUI_FPS)),
+            repaint_notifier: None,
+            preview_played: None,
+            pin_operator_panel: false,
+            audio_devices: None,
+            audio_settings: AudioSettings::default(),
+            audio_status: String::new(),
+            operator_clipboard: None,
+            midi_file_path: String::from("song.mid"),
+            midi_player: None,
+            midi_file_status: String::new(),
+            automation_start: 0.0,
+            library: Library::default(),
+            library_path: None,
+            user_presets_dir: None,
+            library_filter: LibraryFilter::default(),
+            library_ab: [None; 2],
+            library_ab_last: 0,
+            bank_errors: Vec::new(),
+            compare: CompareSlots::default(),
+            library_status: String::new(),
+            settings: AppSettings::default(),
+            settings_path: None,
+            show_settings: false,
+            settings_status: String::new(),
+            program_map_pick: (0, 0),
+            algorithm_scroll: 0.0,
+            show_shortcuts: false,
+            lcd: Lcd::default(),
+            show_debug_panel: false,
+            dump_signal: Arc::new(AtomicBool::new(false)),
+            dump_to_stderr: false,
+            debug_dump: None,
+            debug_dump_requested_at: None,
         }
     }
 
-    /// Update the cached snapshot from the audio thread (call once per frame)
+    /// Update the cached snapshot from the audio thread (call once per
+    /// frame); skips the copy when nothing new has been published.
     fn update_snapshot(&mut self) {
         if let Ok(ctrl) = self.controller.lock() {
-            self.snapshot = ctrl.snapshot();
+            let sequence = ctrl.snapshot_sequence();
+            if self.snapshot_seen != Some(sequence) {
+                self.snapshot = ctrl.snapshot();
+                self.snapshot_seen = Some(sequence);
+            }
+        }
+    }
+
+    /// Repaint whenever the engine publishes a snapshot, capped by the GUI
+    /// FPS setting. Call once with the context the app runs in.
+    pub fn start_repaint_notifier(&mut self, ctx: &egui::Context) {
+        let Ok(watcher) = self.lock_controller().map(|c| c.snapshot_watcher()) else {
+            return;
+        };
+        self.repaint_notifier = Some(RepaintNotifier::spawn(
+            ctx.clone(),
+            watcher,
+            self.gui_fps.clone(),
+        ));
+    }
+
+    fn frame_interval(&self) -> std::time::Duration {
+        frame_interval(self.gui_fps.load(Ordering::Relaxed))
+    }
+
+    /// Print an engine dump to stderr whenever the process receives SIGUSR1
+    /// (`kill -USR1 <pid>`), for stuck notes when the window is out of reach.
+    #[cfg(unix)]
+    pub fn listen_for_dump_signal(&mut self) {
+        let signal = signal_hook::consts::SIGUSR1;
+        if let Err(e) = signal_hook::flag::register(signal, self.dump_signal.clone()) {
+            log::warn!("SIGUSR1 dump handler not installed: {e}");
+        }
+    }
+
+    /// Ask the engine for a dump every `DEBUG_DUMP_INTERVAL` while the
+    /// debug panel is open or one is due on stderr, and print that one once
+    /// it arrives. Each request costs the audio thread one dump.
+    fn update_debug_dump(&mut self) {
+        if self.dump_signal.swap(false, Ordering::Relaxed) {
+            self.dump_to_stderr = true;
+            self.debug_dump_requested_at = None;
         }
+        if let Some(dump) = self.snapshot.debug_dump.take() {
+            if self.dump_to_stderr {
+                eprintln!("=== synth-fm-rs engine dump ===\n{dump}");
+                self.dump_to_stderr = false;
+            }
+            self.debug_dump = Some(dump);
+        }
+        if !self.show_debug_panel && !self.dump_to_stderr {
+            self.debug_dump_requested_at = None;
+            return;
+        }
+        let due = self
+            .debug_dump_requested_at
+            .is_none_or(|at| at.elapsed() >= DEBUG_DUMP_INTERVAL);
+        if due {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.request_debug_dump();
+            }
+            self.debug_dump_requested_at = Some(std::time::Instant::now());
+        }
+    }
+
+    fn draw_debug_panel(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_debug_panel;
+        egui::Window::new("Engine debug")
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                let Some(dump) = &self.debug_dump else {
+                    ui.label("Waiting for the engine…");
+                    return;
+                };
+                let text = dump.to_string();
+                ui.horizontal(|ui| {
+                    if ui.button("COPY").clicked() {
+                        ui.output_mut(|o| o.copied_text = text.clone());
+                    }
+                    if ui.button("PRINT TO STDERR").clicked() {
+                        eprintln!("=== synth-fm-rs engine dump ===\n{text}");
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.label(egui::RichText::new(text).monospace());
+                });
+            });
+        self.show_debug_panel = open;
     }
 
     /// Frame-independent rendering: drives one full GUI frame against the given
@@ -116,16 +534,56 @@ impl Dx7App {
     /// without constructing an `eframe::Frame`.
     pub(crate) fn render(&mut self, ctx: &egui::Context) {
         self.update_snapshot();
+        self.poll_midi_learn();
         self.handle_keyboard_input(ctx);
-        ctx.set_visuals(egui::Visuals::light());
+        self.update_debug_dump();
+        ctx.set_visuals(self.settings.theme.visuals());
+        if let Some(rect) = ctx.input(|i| i.viewport().inner_rect) {
+            self.settings.window_size = [rect.width(), rect.height()];
+        }
+        self.draw_settings_window(ctx);
+        self.draw_shortcuts_window(ctx);
+        self.draw_debug_panel(ctx);
+
+        egui::TopBottomPanel::bottom("piano").show(ctx, |ui| self.draw_piano(ui));
+
+        // The OPERATOR page already shows the editor; only pin it elsewhere.
+        if self.pin_operator_panel && self.display_mode != DisplayMode::Operator {
+            egui::SidePanel::right("pinned_operator_panel")
+                .resizable(true)
+                .default_width(560.0)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        self.draw_operator_selector_strip(ui);
+                        ui.add_space(4.0);
+                        self.draw_operator_full_panel(ui);
+                    });
+                });
+        }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.vertical_centered(|ui| {
                 ui.heading("DX7-STYLE DIGITAL FM SYNTHESIZER");
             });
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                self.draw_output_meter(ui);
+                if ui
+                    .selectable_label(self.show_settings, "⚙ SETTINGS")
+                    .clicked()
+                {
+                    self.show_settings = !self.show_settings;
+                }
+                if ui
+                    .selectable_label(self.show_shortcuts, "⌨ SHORTCUTS")
+                    .clicked()
+                {
+                    self.show_shortcuts = !self.show_shortcuts;
+                }
+            });
             ui.separator();
 
             self.draw_dx7_display(ui);
+            self.draw_data_entry(ui);
             ui.add_space(8.0);
             self.draw_global_controls(ui);
             ui.add_space(8.0);
@@ -133,7 +591,12 @@ impl Dx7App {
             ui.add_space(8.0);
 
             match self.display_mode {
-                DisplayMode::Voice => self.draw_preset_selector(ui),
+                DisplayMode::Voice => {
+                    self.draw_preset_selector(ui);
+                    self.draw_performance_section(ui);
+                    self.draw_advanced_settings(ui);
+                }
+                DisplayMode::Library => self.draw_library_panel(ui),
                 DisplayMode::Operator => {
                     ui.horizontal_top(|ui| {
                         self.draw_algorithm_diagram_compact(ui);
@@ -148,31 +611,48 @@ impl Dx7App {
                 DisplayMode::LFO => self.draw_lfo_panel(ui),
                 DisplayMode::Effects => self.draw_effects_panel(ui),
                 DisplayMode::Midi => self.draw_midi_panel(ui),
+                DisplayMode::Audio => self.draw_audio_panel(ui),
+                DisplayMode::Keys => self.draw_keys_panel(ui),
+                DisplayMode::Scope => self.draw_scope_panel(ui),
+                DisplayMode::Spectrum => self.draw_spectrum_panel(ui),
             }
 
             ui.separator();
-            ui.horizontal(|ui| {
-                ui.label("Keyboard: Z-M (lower octave), Q-U (upper octave)");
+            self.draw_midi_file_player(ui);
+            ui.separator();
+            ui.horizontal_wrapped(|ui| {
+                let kb = &self.key_bindings;
+                ui.label(format!(
+                    "Keyboard: {}-{} (lower octave), {}-{} (upper octave)",
+                    kb.key(KeyAction::Note(0)).name(),
+                    kb.key(KeyAction::Note(11)).name(),
+                    kb.key(KeyAction::Note(12)).name(),
+                    kb.key(KeyAction::Note(23)).name()
+                ));
                 ui.label(format!("| Octave: {}", self.current_octave));
-                ui.label("| Space: Panic");
-                ui.label("| Up/Down: Change octave");
+                ui.label(format!(
+                    "| Velocity: {} (Shift {}, Ctrl {})",
+                    kb.velocity.normal, kb.velocity.shift, kb.velocity.ctrl
+                ));
+                ui.label(format!(
+                    "| {}: Hold {}",
+                    kb.key(KeyAction::Hold).name(),
+                    if self.keyboard_hold { "ON" } else { "OFF" }
+                ));
+                ui.label(format!("| {}: Panic", kb.key(KeyAction::Panic).name()));
+                ui.label(format!(
+                    "| {}/{}: Change octave",
+                    kb.key(KeyAction::OctaveUp).name(),
+                    kb.key(KeyAction::OctaveDown).name()
+                ));
             });
         });
 
         if ctx.input(|i| !i.events.is_empty()) {
-            ctx.request_repaint_after(std::time::Duration::from_millis(16)); // ~60 FPS
+            ctx.request_repaint_after(self.frame_interval());
         }
     }
 
-    fn lock_engine(
-        &self,
-    ) -> Result<
-        std::sync::MutexGuard<'_, SynthEngine>,
-        std::sync::PoisonError<std::sync::MutexGuard<'_, SynthEngine>>,
-    > {
-        self.engine.lock()
-    }
-
     fn lock_controller(
         &self,
     ) -> Result<
@@ -182,6 +662,71 @@ impl Dx7App {
         self.controller.lock()
     }
 
+    /// Stereo peak / RMS bars (-60..0 dBFS) and a clip LED. Laid out
+    /// right to left, so the LED comes first.
+    fn draw_output_meter(&self, ui: &mut egui::Ui) {
+        const FLOOR_DB: f32 = -60.0;
+        let levels = self.snapshot.output_levels;
+        let fraction = |linear: f32| {
+            let db = 20.0 * linear.max(1e-6).log10();
+            ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0)
+        };
+
+        let (led_rect, _) = ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+        let led = if levels.clip {
+            egui::Color32::from_rgb(230, 30, 30)
+        } else {
+            egui::Color32::from_rgb(70, 30, 30)
+        };
+        ui.painter().circle_filled(led_rect.center(), 6.0, led);
+        ui.label("CLIP");
+
+        let (rect, response) =
+            ui.allocate_exact_size(egui::vec2(160.0, 14.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, egui::Color32::from_rgb(20, 24, 20));
+        let bar_height = (rect.height() - 3.0) / 2.0;
+        for ch in 0..2 {
+            let top = rect.top() + 1.0 + ch as f32 * (bar_height + 1.0);
+            let x_for = |linear: f32| rect.left() + fraction(linear) * rect.width();
+            let rms_x = x_for(levels.rms[ch]);
+            let peak_x = x_for(levels.peak[ch]);
+            let color = if levels.peak[ch] >= 1.0 {
+                egui::Color32::from_rgb(230, 60, 40)
+            } else if levels.peak[ch] >= 0.5 {
+                egui::Color32::from_rgb(230, 190, 40)
+            } else {
+                egui::Color32::from_rgb(80, 200, 100)
+            };
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(rect.left(), top),
+                    egui::pos2(rms_x, top + bar_height),
+                ),
+                0.0,
+                color,
+            );
+            painter.vline(
+                peak_x,
+                top..=top + bar_height,
+                egui::Stroke::new(2.0, egui::Color32::WHITE),
+            );
+        }
+        response.on_hover_text(format!(
+            "Peak L {:.1} / R {:.1} dBFS, RMS L {:.1} / R {:.1} dBFS",
+            20.0 * levels.peak[0].max(1e-6).log10(),
+            20.0 * levels.peak[1].max(1e-6).log10(),
+            20.0 * levels.rms[0].max(1e-6).log10(),
+            20.0 * levels.rms[1].max(1e-6).log10(),
+        ));
+        ui.label("OUT");
+
+        // Keep the bars falling back while anything is sounding.
+        if levels.clip || levels.peak.iter().any(|&p| p > 1e-4) {
+            ui.ctx().request_repaint_after(self.frame_interval());
+        }
+    }
+
     fn draw_dx7_display(&mut self, ui: &mut egui::Ui) {
         ui.group(|ui| {
             // Light background like classic LCD
@@ -198,11 +743,30 @@ impl Dx7App {
             ui.vertical_centered(|ui| {
                 ui.add_space(5.0);
 
-                let display_font = egui::FontId::new(16.0, egui::FontFamily::Monospace);
+                let lcd_font = egui::FontId::new(22.0, egui::FontFamily::Monospace);
+                let display_font = egui::FontId::new(14.0, egui::FontFamily::Monospace);
                 let small_font = egui::FontId::new(12.0, egui::FontFamily::Monospace);
                 let display_color = egui::Color32::from_rgb(30, 30, 30);
 
-                // Main display text (current mode)
+                // The 16x2 character LCD of the hardware.
+                let [top, bottom] = self.lcd.lines(&self.snapshot, self.selected_preset + 1);
+                egui::Frame::none()
+                    .fill(egui::Color32::from_rgb(168, 190, 110))
+                    .stroke(egui::Stroke::new(2.0, egui::Color32::from_rgb(60, 70, 40)))
+                    .inner_margin(egui::Margin::symmetric(10.0, 6.0))
+                    .show(ui, |ui| {
+                        for line in [top, bottom] {
+                            ui.label(
+                                egui::RichText::new(line)
+                                    .font(lcd_font.clone())
+                                    .color(egui::Color32::from_rgb(25, 35, 20)),
+                            );
+                        }
+                    });
+                self.draw_lcd_buttons(ui);
+                ui.add_space(4.0);
+
+                // Editor page (current mode)
                 ui.label(
                     egui::RichText::new(&self.display_text)
                         .font(display_font.clone())
@@ -217,6 +781,14 @@ impl Dx7App {
                             self.snapshot.preset_name, self.snapshot.algorithm
                         )
                     }
+                    DisplayMode::Library => {
+                        let favorites = self
+                            .presets
+                            .iter()
+                            .filter(|p| self.library.is_favorite(p))
+                            .count();
+                        format!("{} VOICES | {} FAVORITES", self.presets.len(), favorites)
+                    }
                     DisplayMode::Operator => {
                         format!("OP{} EDIT", self.selected_operator + 1)
                     }
@@ -230,6 +802,11 @@ impl Dx7App {
                         )
                     }
                     DisplayMode::Effects => {
+                        let phaser = if self.snapshot.phaser.enabled {
+                            "PHA"
+                        } else {
+                            "-"
+                        };
                         let chorus = if self.snapshot.chorus.enabled {
                             "CHO"
                         } else {
@@ -245,7 +822,13 @@ impl Dx7App {
                         } else {
                             "-"
                         };
-                        format!("EFFECTS: {} {} {}", chorus, delay, reverb)
+                        match self.snapshot.wet_solo {
+                            // A forgotten solo mutes the dry sound; say so.
+                            Some(slot) => format!("EFFECTS: SOLO {} WET", slot.name()),
+                            None => {
+                                format!("EFFECTS: {} {} {} {}", phaser, chorus, delay, reverb)
+                            }
+                        }
                     }
                     DisplayMode::Midi => {
                         let ch_text = match self.midi_channel_ui {
@@ -260,6 +843,27 @@ impl Dx7App {
                             self.snapshot.foot * 100.0
                         )
                     }
+                    DisplayMode::Audio => match &self.audio_engine {
+                        Some(audio) => {
+                            let info = audio.stream_info();
+                            match audio.buffer_latency() {
+                                Some((frames, ms)) => format!(
+                                    "OUTPUT: {} @ {} HZ | {} FR = {:.1} MS",
+                                    info.device, info.sample_rate, frames, ms
+                                ),
+                                None => {
+                                    format!("OUTPUT: {} @ {} HZ", info.device, info.sample_rate)
+                                }
+                            }
+                        }
+                        None => "NO AUDIO DEVICE".to_string(),
+                    },
+                    DisplayMode::Keys => match self.capturing_key {
+                        Some(action) => format!("PRESS KEY FOR: {}", action.label()),
+                        None => format!("OCTAVE: {}", self.current_octave),
+                    },
+                    DisplayMode::Scope => "OUTPUT: MONO SUM, POST-CLIP".to_string(),
+                    DisplayMode::Spectrum => format!("FFT {} PT, HANN WINDOW", SPECTRUM_SIZE),
                 };
 
                 ui.label(
@@ -282,6 +886,14 @@ impl Dx7App {
                 } else {
                     "NO MIDI"
                 };
+                let audio_text = match &self.audio_engine {
+                    Some(audio) if audio.is_stream_running() => match audio.buffer_latency() {
+                        Some((_, ms)) => format!("AUDIO OK {:.1}MS", ms),
+                        None => "AUDIO OK".to_string(),
+                    },
+                    Some(_) => "AUDIO STOPPED".to_string(),
+                    None => "NO AUDIO".to_string(),
+                };
 
                 let is_mono = self.snapshot.voice_mode != crate::state_snapshot::VoiceMode::Poly;
                 let status_line = if is_mono {
@@ -292,30 +904,245 @@ impl Dx7App {
                         "OFF"
                     };
                     format!(
-                        "VOICE: {} | ALG: {:02} | MODE: {} | PORTA: {} | {}",
+                        "VOICE: {} | ALG: {:02} | MODE: {} | PORTA: {} | {} | {}",
                         self.snapshot.preset_name,
                         self.snapshot.algorithm,
                         mode_text,
                         porta_text,
-                        midi_text
+                        midi_text,
+                        audio_text
                     )
                 } else {
                     // In POLY mode, don't show portamento
                     format!(
-                        "VOICE: {} | ALG: {:02} | MODE: {} | {}",
-                        self.snapshot.preset_name, self.snapshot.algorithm, mode_text, midi_text
+                        "VOICE: {} | ALG: {:02} | MODE: {} | {} | {}",
+                        self.snapshot.preset_name,
+                        self.snapshot.algorithm,
+                        mode_text,
+                        midi_text,
+                        audio_text
                     )
                 };
 
                 ui.label(
                     egui::RichText::new(status_line)
-                        .font(small_font)
+                        .font(small_font.clone())
                         .color(display_color),
                 );
+
+                if let Some(warning) = self.audio_warning() {
+                    let stats = self.snapshot.audio_stats;
+                    ui.label(
+                        egui::RichText::new(warning)
+                            .font(small_font)
+                            .color(egui::Color32::from_rgb(200, 40, 30)),
+                    )
+                    .on_hover_text(format!(
+                        "{} underruns / xruns since start, CPU load {:.0}% of the buffer \
+                         time. Raise the buffer size on the AUDIO page if this keeps \
+                         happening.",
+                        stats.underruns,
+                        stats.cpu_load * 100.0
+                    ));
+                    ui.ctx()
+                        .request_repaint_after(std::time::Duration::from_millis(250));
+                }
             });
         });
     }
 
+    /// EDIT / FUNCTION / MEMORY, page and OP SELECT buttons under the LCD.
+    fn draw_lcd_buttons(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mode = self.lcd.mode();
+            for (button, target, label) in [
+                (LcdButton::Edit, LcdMode::Edit, "EDIT"),
+                (LcdButton::Function, LcdMode::Function, "FUNCTION"),
+                (LcdButton::Memory, LcdMode::Memory, "MEMORY"),
+            ] {
+                if ui.selectable_label(mode == target, label).clicked() {
+                    self.press_lcd(button);
+                }
+            }
+            ui.separator();
+            let (prev_hint, next_hint) = if mode == LcdMode::Memory {
+                ("Previous voice", "Next voice")
+            } else {
+                ("Previous page", "Next page")
+            };
+            if ui.button("◀").on_hover_text(prev_hint).clicked() {
+                self.press_lcd(LcdButton::PagePrev);
+            }
+            if ui.button("▶").on_hover_text(next_hint).clicked() {
+                self.press_lcd(LcdButton::PageNext);
+            }
+            if ui
+                .add_enabled(mode == LcdMode::Edit, egui::Button::new("OP SELECT"))
+                .on_hover_text("Operator the EDIT operator pages show")
+                .clicked()
+            {
+                self.press_lcd(LcdButton::OpSelect);
+            }
+            if let Some((page, count)) = self.lcd.page() {
+                ui.label(
+                    egui::RichText::new(format!("PAGE {}/{}", page, count))
+                        .size(10.0)
+                        .color(egui::Color32::GRAY),
+                );
+            }
+        });
+    }
+
+    /// A panel button. In MEMORY mode the page buttons step through the
+    /// voices; OP SELECT also brings that operator up in the editor.
+    fn press_lcd(&mut self, button: LcdButton) {
+        let step_voice = self.lcd.mode() == LcdMode::Memory
+            && matches!(button, LcdButton::PagePrev | LcdButton::PageNext);
+        if step_voice {
+            if !self.presets.is_empty() {
+                let count = self.presets.len();
+                let index = if button == LcdButton::PagePrev {
+                    (self.selected_preset + count - 1) % count
+                } else {
+                    (self.selected_preset + 1) % count
+                };
+                self.select_preset(index);
+            }
+            return;
+        }
+        self.lcd.press(button);
+        if button == LcdButton::OpSelect {
+            self.selected_operator = self.lcd.operator() as usize;
+        }
+    }
+
+    /// Turn the LCD to `param`'s page once its widget is grabbed or
+    /// edited. Hands the response back for the caller's `changed()` check.
+    fn track(&mut self, param: DataEntryParam, response: egui::Response) -> egui::Response {
+        if response.drag_started() || response.changed() {
+            self.lcd.follow(param);
+        }
+        if response.secondary_clicked() {
+            self.toggle_midi_learn(param);
+        }
+        let (armed, cc) = self.midi_learn.lock().map_or((false, None), |learn| {
+            (learn.armed() == Some(param), learn.bindings().cc_for(param))
+        });
+        if armed {
+            // Outline the control waiting for a CC.
+            response.ctx.layer_painter(response.layer_id).rect_stroke(
+                response.rect.expand(2.0),
+                3.0,
+                egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 140, 0)),
+            );
+        }
+        match cc {
+            Some(cc) => response.on_hover_text(format!("CC {}", cc)),
+            None => response,
+        }
+    }
+
+    /// Arm MIDI learn for `param`, or cancel it when `param` is the one
+    /// already waiting.
+    fn toggle_midi_learn(&mut self, param: DataEntryParam) {
+        let Ok(mut learn) = self.midi_learn.lock() else {
+            return;
+        };
+        if learn.armed() == Some(param) {
+            learn.cancel();
+            self.display_text = "MIDI LEARN OFF".to_string();
+        } else {
+            learn.arm(param);
+            self.display_text = format!("LEARN {}: MOVE A CC", param.label());
+        }
+    }
+
+    /// Report a binding the MIDI input made since the last frame.
+    fn poll_midi_learn(&mut self) {
+        let learned = self
+            .midi_learn
+            .lock()
+            .ok()
+            .and_then(|mut learn| learn.take_learned());
+        if let Some((cc, param)) = learned {
+            self.display_text = format!("CC {} → {}", cc, param.label());
+        }
+    }
+
+    /// DATA ENTRY strip under the LCD: one long slider plus NO / YES
+    /// buttons for the LCD page's parameter, like the DX7 front panel. In
+    /// MEMORY mode they pick the voice.
+    fn draw_data_entry(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("DATA ENTRY").size(10.0).strong());
+            ui.spacing_mut().slider_width = 360.0;
+            let Some(param) = self.lcd.param() else {
+                self.draw_memory_entry(ui);
+                return;
+            };
+            let range = param.range();
+            let current = param.value(&self.snapshot);
+            let mut value = current;
+            ui.add(egui::Slider::new(&mut value, range.clone()).show_value(false));
+            if ui.button("NO  −1").clicked() {
+                value -= 1;
+            }
+            if ui.button("YES +1").clicked() {
+                value += 1;
+            }
+            let value = value.clamp(*range.start(), *range.end());
+            if value != current {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    param.set(&mut ctrl, value);
+                }
+            }
+        });
+    }
+
+    /// Data entry in MEMORY mode: the slider and YES / NO move through the
+    /// voice list, loading each voice as it comes up.
+    fn draw_memory_entry(&mut self, ui: &mut egui::Ui) {
+        let count = self.presets.len();
+        if count == 0 {
+            ui.add_enabled(false, egui::Slider::new(&mut 0, 0..=99).show_value(false));
+            return;
+        }
+        let current = self.selected_preset.min(count - 1) + 1;
+        let mut number = current;
+        ui.add(egui::Slider::new(&mut number, 1..=count).show_value(false));
+        if ui.button("NO  −1").clicked() {
+            number = number.saturating_sub(1);
+        }
+        if ui.button("YES +1").clicked() {
+            number += 1;
+        }
+        let number = number.clamp(1, count);
+        if number != current {
+            self.select_preset(number - 1);
+        }
+    }
+
+    /// Display-panel warning while the audio callback is struggling: for
+    /// `XRUN_WARNING` after the underrun count goes up, or while the CPU
+    /// load is above `CPU_LOAD_WARNING`.
+    fn audio_warning(&mut self) -> Option<String> {
+        let now = std::time::Instant::now();
+        let stats = self.snapshot.audio_stats;
+        if stats.underruns > self.underruns_seen {
+            self.xrun_warning_until = Some(now + XRUN_WARNING);
+        }
+        self.underruns_seen = stats.underruns;
+
+        let recent_xrun = self.xrun_warning_until.is_some_and(|until| now < until);
+        (recent_xrun || stats.cpu_load > CPU_LOAD_WARNING).then(|| {
+            format!(
+                "! AUDIO XRUN x{} | CPU {:.0}%",
+                stats.underruns,
+                stats.cpu_load * 100.0
+            )
+        })
+    }
+
     fn draw_global_controls(&mut self, ui: &mut egui::Ui) {
         ui.group(|ui| {
             // Light gray background for global panel
@@ -419,14 +1246,19 @@ impl Dx7App {
                                 }
                             });
 
+                            self.draw_transpose_controls(ui);
+
                             // Pitch Bend Range
                             ui.horizontal(|ui| {
                                 ui.label("PITCH BEND:");
                                 let mut pb_range = self.snapshot.pitch_bend_range;
-                                if ui
-                                    .add(
-                                        egui::Slider::new(&mut pb_range, 0.0..=12.0)
-                                            .show_value(false),
+                                if self
+                                    .track(
+                                        DataEntryParam::PitchBendRange,
+                                        ui.add(
+                                            egui::Slider::new(&mut pb_range, 0.0..=12.0)
+                                                .show_value(false),
+                                        ),
                                     )
                                     .changed()
                                 {
@@ -481,8 +1313,16 @@ impl Dx7App {
                                 }
                             });
 
-                            // Portamento (only visible in MONO modes)
+                            if !is_mono {
+                                self.draw_polyphony_selector(ui);
+                            }
+
                             if is_mono {
+                                self.draw_note_priority_selector(ui);
+                            }
+
+                            // Portamento: poly voices glide from the last note too.
+                            {
                                 ui.horizontal(|ui| {
                                     ui.label("PORTAMENTO:");
                                     let mut porta_on = porta_enable;
@@ -495,10 +1335,13 @@ impl Dx7App {
                                     if porta_enable {
                                         ui.label("TIME:");
                                         let mut pt = porta_time;
-                                        if ui
-                                            .add(
-                                                egui::Slider::new(&mut pt, 0.0..=99.0)
-                                                    .show_value(false),
+                                        if self
+                                            .track(
+                                                DataEntryParam::PortamentoTime,
+                                                ui.add(
+                                                    egui::Slider::new(&mut pt, 0.0..=99.0)
+                                                        .show_value(false),
+                                                ),
                                             )
                                             .changed()
                                         {
@@ -507,6 +1350,7 @@ impl Dx7App {
                                             }
                                         }
                                         ui.label(format!("{:.0}", porta_time));
+                                        self.draw_portamento_mode(ui);
                                     }
                                 });
 
@@ -534,12 +1378,28 @@ impl Dx7App {
                                     }
                                 }
 
-                                if ui.small_button("INIT").clicked() {
+                                if ui
+                                    .small_button("INIT")
+                                    .on_hover_text("INIT VOICE: a plain sine voice, effects as at startup")
+                                    .clicked()
+                                {
                                     if let Ok(mut ctrl) = self.lock_controller() {
                                         ctrl.voice_initialize();
                                     }
                                 }
+
+                                if ui
+                                    .small_button("INIT FN")
+                                    .on_hover_text("INIT FUNCTION: poly, bend 2, portamento off, tune centred, no mod routes")
+                                    .clicked()
+                                {
+                                    if let Ok(mut ctrl) = self.lock_controller() {
+                                        ctrl.function_initialize();
+                                    }
+                                }
                             });
+                            self.draw_vintage_toggle(ui);
+                            self.draw_clock_source_selector(ui);
                         });
                     });
                 });
@@ -583,8 +1443,16 @@ impl Dx7App {
             }
         });
 
-        // Portamento (only visible in MONO modes)
+        if !is_mono {
+            self.draw_polyphony_selector(ui);
+        }
+
         if is_mono {
+            self.draw_note_priority_selector(ui);
+        }
+
+        // Portamento: poly voices glide from the last note too.
+        {
             let porta_enable = self.snapshot.portamento_enable;
             let porta_time = self.snapshot.portamento_time;
             ui.horizontal(|ui| {
@@ -599,8 +1467,11 @@ impl Dx7App {
                 if porta_enable {
                     ui.label("TIME:");
                     let mut pt = porta_time;
-                    if ui
-                        .add(egui::Slider::new(&mut pt, 0.0..=99.0).show_value(false))
+                    if self
+                        .track(
+                            DataEntryParam::PortamentoTime,
+                            ui.add(egui::Slider::new(&mut pt, 0.0..=99.0).show_value(false)),
+                        )
                         .changed()
                     {
                         if let Ok(mut ctrl) = self.lock_controller() {
@@ -608,11 +1479,30 @@ impl Dx7App {
                         }
                     }
                     ui.label(format!("{:.0}", porta_time));
+                    self.draw_portamento_mode(ui);
                 }
             });
         }
     }
 
+    /// FINGERED glides only while another key is held; off is full time.
+    fn draw_portamento_mode(&mut self, ui: &mut egui::Ui) {
+        let mut fingered = self.snapshot.portamento_mode == PortamentoMode::Fingered;
+        if ui
+            .checkbox(&mut fingered, "FINGERED")
+            .on_hover_text("Glide only when the new key is played legato; off glides on every note")
+            .changed()
+        {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.set_portamento_mode(if fingered {
+                    PortamentoMode::Fingered
+                } else {
+                    PortamentoMode::FullTime
+                });
+            }
+        }
+    }
+
     fn draw_tune_and_utilities_compact(&mut self, ui: &mut egui::Ui) {
         let master_tune = self.snapshot.master_tune;
         let pb_range = self.snapshot.pitch_bend_range;
@@ -637,13 +1527,17 @@ impl Dx7App {
                 }
             }
         });
+        self.draw_transpose_controls(ui);
 
         // Second row: Pitch Bend and utilities
         ui.horizontal(|ui| {
             ui.label("BEND:");
             let mut pb = pb_range;
-            if ui
-                .add(egui::Slider::new(&mut pb, 0.0..=12.0).show_value(false))
+            if self
+                .track(
+                    DataEntryParam::PitchBendRange,
+                    ui.add(egui::Slider::new(&mut pb, 0.0..=12.0).show_value(false)),
+                )
                 .changed()
             {
                 if let Ok(mut ctrl) = self.lock_controller() {
@@ -660,36 +1554,287 @@ impl Dx7App {
                 }
             }
 
-            if ui.small_button("INIT").clicked() {
+            if ui
+                .small_button("INIT")
+                .on_hover_text("INIT VOICE: a plain sine voice, effects as at startup")
+                .clicked()
+            {
                 if let Ok(mut ctrl) = self.lock_controller() {
                     ctrl.voice_initialize();
                 }
             }
-        });
-    }
-
-    fn draw_membrane_buttons(&mut self, ui: &mut egui::Ui) {
-        ui.group(|ui| {
-            ui.horizontal(|ui| {
-                ui.spacing_mut().button_padding = egui::vec2(12.0, 6.0);
 
-                // Make buttons more DX7-like with consistent sizing
-                let button_size = egui::vec2(85.0, 25.0);
+            if ui
+                .small_button("INIT FN")
+                .on_hover_text(
+                    "INIT FUNCTION: poly, bend 2, portamento off, tune centred, no mod routes",
+                )
+                .clicked()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.function_initialize();
+                }
+            }
 
-                let voice_button = if self.display_mode == DisplayMode::Voice {
-                    egui::Button::new("VOICE")
-                        .fill(egui::Color32::from_rgb(180, 200, 220))
-                        .min_size(button_size)
-                } else {
-                    egui::Button::new("VOICE").min_size(button_size)
-                };
+            self.draw_vintage_toggle(ui);
+            self.draw_clock_source_selector(ui);
+        });
+    }
 
-                if ui.add(voice_button).clicked() {
-                    self.display_mode = DisplayMode::Voice;
-                    self.display_text = "VOICE SELECT".to_string();
+    /// KEY TRANSPOSE in semitones and octaves, on top of master tune.
+    fn draw_transpose_controls(&mut self, ui: &mut egui::Ui) {
+        let transpose = self.snapshot.transpose_semitones;
+        ui.horizontal(|ui| {
+            ui.label("TRANSPOSE:");
+            for (label, steps, hint) in [
+                ("−OCT", -12, "Down an octave"),
+                ("−", -1, "Down a semitone"),
+            ] {
+                if ui.small_button(label).on_hover_text(hint).clicked() {
+                    self.shift_transpose(steps);
                 }
+            }
+            ui.label(format!("{transpose:+}"));
+            for (label, steps, hint) in [("+", 1, "Up a semitone"), ("+OCT", 12, "Up an octave")] {
+                if ui.small_button(label).on_hover_text(hint).clicked() {
+                    self.shift_transpose(steps);
+                }
+            }
+            if ui.small_button("RST").clicked() {
+                self.shift_transpose(-(transpose as i32));
+            }
+        });
+    }
 
-                let op_select_button = if self.display_mode == DisplayMode::Operator {
+    /// Move KEY TRANSPOSE by `steps` semitones, within ±24.
+    fn shift_transpose(&mut self, steps: i32) {
+        let transpose = (self.snapshot.transpose_semitones as i32 + steps).clamp(-24, 24);
+        if let Ok(mut ctrl) = self.lock_controller() {
+            ctrl.set_transpose(transpose as i8);
+        }
+    }
+
+    /// Playable voice count and same-key behaviour for Poly mode.
+    fn draw_polyphony_selector(&mut self, ui: &mut egui::Ui) {
+        let current = self.snapshot.polyphony as usize;
+        ui.horizontal(|ui| {
+            ui.label("VOICES:");
+            for voices in POLYPHONY_CHOICES {
+                if ui
+                    .selectable_label(current == voices, voices.to_string())
+                    .clicked()
+                    && current != voices
+                {
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.set_polyphony(voices as u8);
+                    }
+                }
+            }
+
+            let mut stack = self.snapshot.same_note_mode == SameNoteMode::Stack;
+            if ui
+                .checkbox(&mut stack, "STACK")
+                .on_hover_text("Re-striking a sounding key adds a voice instead of restarting it")
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_same_note_mode(if stack {
+                        SameNoteMode::Stack
+                    } else {
+                        SameNoteMode::Retrigger
+                    });
+                }
+            }
+
+            let mut retrigger = self.snapshot.patch_change_mode == PatchChangeMode::Retrigger;
+            if ui
+                .checkbox(&mut retrigger, "RETRIG")
+                .on_hover_text(
+                    "Held notes restart on a new preset or algorithm instead of crossfading into it",
+                )
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_patch_change_mode(if retrigger {
+                        PatchChangeMode::Retrigger
+                    } else {
+                        PatchChangeMode::Crossfade
+                    });
+                }
+            }
+        });
+    }
+
+    /// Which held key the mono modes play.
+    fn draw_note_priority_selector(&mut self, ui: &mut egui::Ui) {
+        let current = self.snapshot.note_priority;
+        ui.horizontal(|ui| {
+            ui.label("PRIORITY:");
+            for (priority, label) in [
+                (NotePriority::Last, "LAST"),
+                (NotePriority::Low, "LOW"),
+                (NotePriority::High, "HIGH"),
+            ] {
+                if ui.selectable_label(current == priority, label).clicked() && current != priority
+                {
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.set_note_priority(priority);
+                    }
+                }
+            }
+        });
+    }
+
+    fn draw_vintage_toggle(&mut self, ui: &mut egui::Ui) {
+        let mut vintage = self.snapshot.vintage_mode;
+        if ui
+            .checkbox(&mut vintage, "VINTAGE")
+            .on_hover_text(
+                "Era-accurate quirks: companded DAC, 8-bit envelope steps, \
+                 LFO clock jitter, 12-bit sine without interpolation",
+            )
+            .changed()
+        {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.set_vintage_mode(vintage);
+            }
+        }
+
+        let mut dac = self.snapshot.output_dac;
+        let mut intensity = self.snapshot.output_dac_intensity;
+        let dac_changed = ui
+            .checkbox(&mut dac, "DX7 DAC")
+            .on_hover_text(
+                "The hardware's output stage on the master bus: 12-bit floating \
+                 DAC, sample-and-hold at 49.1 kHz and the gentle output low-pass",
+            )
+            .changed();
+        let intensity_changed = ui
+            .add_enabled(
+                dac,
+                egui::Slider::new(&mut intensity, 0.0..=1.0)
+                    .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)),
+            )
+            .on_hover_text("How much of the emulated output is mixed in")
+            .changed();
+        if dac_changed || intensity_changed {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                if dac_changed {
+                    ctrl.set_output_dac(dac);
+                }
+                if intensity_changed {
+                    ctrl.set_output_dac_intensity(intensity);
+                }
+            }
+        }
+
+        let mut noise = self.snapshot.noise_floor;
+        let mut level = self.snapshot.noise_level_db;
+        let mut hum_hz = self.snapshot.hum_hz;
+        let noise_changed = ui
+            .checkbox(&mut noise, "NOISE")
+            .on_hover_text("Hiss and mains hum under everything, ahead of the effects")
+            .changed();
+        let (level_changed, hum_changed) = ui
+            .add_enabled_ui(noise, |ui| {
+                let level_changed = ui
+                    .add(
+                        egui::Slider::new(&mut level, MIN_NOISE_DB..=MAX_NOISE_DB)
+                            .suffix(" dB")
+                            .max_decimals(0),
+                    )
+                    .changed();
+                let mut hum_changed = false;
+                for hz in [50.0, 60.0] {
+                    hum_changed |= ui
+                        .selectable_value(&mut hum_hz, hz, format!("{hz:.0} Hz"))
+                        .changed();
+                }
+                (level_changed, hum_changed)
+            })
+            .inner;
+        if noise_changed || level_changed || hum_changed {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                if noise_changed {
+                    ctrl.set_noise_floor(noise);
+                }
+                if level_changed {
+                    ctrl.set_noise_level(level);
+                }
+                if hum_changed {
+                    ctrl.set_hum_frequency(hum_hz);
+                }
+            }
+        }
+    }
+
+    fn clock_source(&self) -> ClockSource {
+        self._midi_handler
+            .as_ref()
+            .map_or(ClockSource::Internal, MidiHandler::clock_source)
+    }
+
+    /// INT/EXT tempo source. EXT follows MIDI clock from the input device.
+    fn draw_clock_source_selector(&mut self, ui: &mut egui::Ui) {
+        let current = self.clock_source();
+        ui.horizontal(|ui| {
+            ui.label("CLOCK:");
+            let Some(handler) = self._midi_handler.as_ref() else {
+                ui.add_enabled(false, egui::SelectableLabel::new(true, "INT"))
+                    .on_disabled_hover_text("No MIDI device to take clock from");
+                return;
+            };
+            for (source, label) in [
+                (ClockSource::Internal, "INT"),
+                (ClockSource::External, "EXT"),
+            ] {
+                if ui.selectable_label(current == source, label).clicked() && current != source {
+                    handler.set_clock_source(source);
+                }
+            }
+            if current == ClockSource::External {
+                let running = handler.clock_running();
+                ui.label(if running { "▶" } else { "■" })
+                    .on_hover_text("Transport state from MIDI Start/Continue/Stop");
+            }
+        });
+    }
+
+    fn draw_membrane_buttons(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.spacing_mut().button_padding = egui::vec2(12.0, 6.0);
+
+                // Make buttons more DX7-like with consistent sizing
+                let button_size = egui::vec2(85.0, 25.0);
+
+                let voice_button = if self.display_mode == DisplayMode::Voice {
+                    egui::Button::new("VOICE")
+                        .fill(egui::Color32::from_rgb(180, 200, 220))
+                        .min_size(button_size)
+                } else {
+                    egui::Button::new("VOICE").min_size(button_size)
+                };
+
+                if ui.add(voice_button).clicked() {
+                    self.display_mode = DisplayMode::Voice;
+                    self.display_text = "VOICE SELECT".to_string();
+                }
+
+                let library_button = if self.display_mode == DisplayMode::Library {
+                    egui::Button::new("LIBRARY")
+                        .fill(egui::Color32::from_rgb(180, 200, 220))
+                        .min_size(button_size)
+                } else {
+                    egui::Button::new("LIBRARY").min_size(button_size)
+                };
+
+                if ui.add(library_button).clicked() {
+                    self.display_mode = DisplayMode::Library;
+                    self.display_text = DisplayMode::Library.title().to_string();
+                }
+
+                let op_select_button = if self.display_mode == DisplayMode::Operator {
                     egui::Button::new("OPERATOR")
                         .fill(egui::Color32::from_rgb(180, 200, 220))
                         .min_size(button_size)
@@ -740,10 +1885,113 @@ impl Dx7App {
                     self.display_mode = DisplayMode::Midi;
                     self.display_text = "MIDI / CONTROLLERS".to_string();
                 }
+
+                let audio_button = if self.display_mode == DisplayMode::Audio {
+                    egui::Button::new("AUDIO")
+                        .fill(egui::Color32::from_rgb(180, 200, 220))
+                        .min_size(button_size)
+                } else {
+                    egui::Button::new("AUDIO").min_size(button_size)
+                };
+
+                if ui.add(audio_button).clicked() {
+                    self.display_mode = DisplayMode::Audio;
+                    self.display_text = DisplayMode::Audio.title().to_string();
+                }
+
+                let keys_button = if self.display_mode == DisplayMode::Keys {
+                    egui::Button::new("KEYS")
+                        .fill(egui::Color32::from_rgb(180, 200, 220))
+                        .min_size(button_size)
+                } else {
+                    egui::Button::new("KEYS").min_size(button_size)
+                };
+
+                if ui.add(keys_button).clicked() {
+                    self.display_mode = DisplayMode::Keys;
+                    self.display_text = DisplayMode::Keys.title().to_string();
+                }
+
+                let scope_button = if self.display_mode == DisplayMode::Scope {
+                    egui::Button::new("SCOPE")
+                        .fill(egui::Color32::from_rgb(180, 200, 220))
+                        .min_size(button_size)
+                } else {
+                    egui::Button::new("SCOPE").min_size(button_size)
+                };
+
+                if ui.add(scope_button).clicked() {
+                    self.display_mode = DisplayMode::Scope;
+                    self.display_text = DisplayMode::Scope.title().to_string();
+                }
+
+                let spectrum_button = if self.display_mode == DisplayMode::Spectrum {
+                    egui::Button::new("SPECTRUM")
+                        .fill(egui::Color32::from_rgb(180, 200, 220))
+                        .min_size(button_size)
+                } else {
+                    egui::Button::new("SPECTRUM").min_size(button_size)
+                };
+
+                if ui.add(spectrum_button).clicked() {
+                    self.display_mode = DisplayMode::Spectrum;
+                    self.display_text = DisplayMode::Spectrum.title().to_string();
+                }
+
+                ui.add_space(8.0);
+                ui.checkbox(&mut self.pin_operator_panel, "PIN OP")
+                    .on_hover_text("Keep the operator editor open in a side panel on every page");
             });
         });
     }
 
+    /// A-D compare strip: a click recalls a stored slot or stores into an
+    /// empty one; Shift+click stores over what is there.
+    fn draw_compare_slots(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("compare:").size(11.0).strong());
+            for (slot, name) in COMPARE_SLOT_NAMES.iter().enumerate() {
+                let key = self.key_bindings.key(KeyAction::Compare(slot as u8));
+                let hint = match self.compare.voice_name(slot) {
+                    Some(voice) => format!(
+                        "{voice}: click or {} to recall, Shift to store over it",
+                        key.name()
+                    ),
+                    None => format!("Empty: click or {} to store the current edit", key.name()),
+                };
+                let mut text = egui::RichText::new(*name).strong();
+                if !self.compare.is_stored(slot) {
+                    text = text.color(egui::Color32::from_gray(110));
+                }
+                let response = ui
+                    .selectable_label(self.compare.active() == Some(slot), text)
+                    .on_hover_text(hint);
+                if response.clicked() {
+                    let shift = ui.input(|i| i.modifiers.shift);
+                    self.use_compare_slot(slot, shift);
+                }
+            }
+        });
+    }
+
+    /// Recall `slot`, or store the current edit into it when it is empty or
+    /// `store` is set.
+    fn use_compare_slot(&mut self, slot: usize, store: bool) {
+        if store || !self.compare.is_stored(slot) {
+            self.compare.store(slot, &self.snapshot);
+            self.display_text = format!("STORED IN {}", COMPARE_SLOT_NAMES[slot]);
+            return;
+        }
+        if let Some(commands) = self.compare.recall(slot) {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                for command in commands {
+                    ctrl.send(command);
+                }
+            }
+            self.display_text = format!("COMPARE {}", COMPARE_SLOT_NAMES[slot]);
+        }
+    }
+
     fn draw_preset_selector(&mut self, ui: &mut egui::Ui) {
         ui.group(|ui| {
             ui.spacing_mut().item_spacing = egui::vec2(4.0, 3.0);
@@ -762,6 +2010,7 @@ impl Dx7App {
                     ui.colored_label(egui::Color32::GRAY, "(none)");
                 }
             });
+            self.draw_compare_slots(ui);
             ui.separator();
 
             // --- Search + collection filter ---
@@ -775,6 +2024,20 @@ impl Dx7App {
                 if ui.small_button("×").on_hover_text("Clear").clicked() {
                     self.preset_search.clear();
                 }
+                if self.preview.is_some() {
+                    ui.separator();
+                    if ui
+                        .checkbox(&mut self.preview_on_hover, "audition")
+                        .on_hover_text(
+                            "Play a short phrase with each preset you hover or step to \
+                             (Page Up / Page Down)",
+                        )
+                        .changed()
+                        && !self.preview_on_hover
+                    {
+                        self.stop_preview();
+                    }
+                }
             });
 
             let collections: Vec<String> = {
@@ -827,6 +2090,7 @@ impl Dx7App {
                 return;
             }
 
+            let mut hovered: Option<usize> = None;
             egui::ScrollArea::vertical()
                 .max_height(320.0)
                 .show(ui, |ui| {
@@ -859,101 +2123,1078 @@ impl Dx7App {
                             button
                         };
 
-                        if ui.add_sized([ui.available_width(), 18.0], button).clicked() {
-                            let preset = self.presets[global_idx].clone();
-                            self.selected_preset = global_idx;
-                            if let Ok(mut synth) = self.lock_engine() {
-                                preset.apply_to_synth(&mut synth);
-                            }
-                            self.display_text = format!("LOADED: {}", name);
+                        let response = ui.add_sized([ui.available_width(), 18.0], button);
+                        if response.hovered() {
+                            hovered = Some(global_idx);
+                        }
+                        if response.clicked() {
+                            self.select_preset(global_idx);
                         }
                     }
                 });
+            self.update_preview_hover(ui.ctx(), hovered);
         });
     }
 
-    fn handle_keyboard_input(&mut self, ctx: &egui::Context) {
-        use egui::Key;
-
-        let key_map = [
-            (Key::Z, 0),     // C
-            (Key::S, 1),     // C#
-            (Key::X, 2),     // D
-            (Key::D, 3),     // D#
-            (Key::C, 4),     // E
-            (Key::V, 5),     // F
-            (Key::G, 6),     // F#
-            (Key::B, 7),     // G
-            (Key::H, 8),     // G#
-            (Key::N, 9),     // A
-            (Key::J, 10),    // A#
-            (Key::M, 11),    // B
-            (Key::Q, 12),    // C (octave up)
-            (Key::Num2, 13), // C#
-            (Key::W, 14),    // D
-            (Key::Num3, 15), // D#
-            (Key::E, 16),    // E
-            (Key::R, 17),    // F
-            (Key::Num5, 18), // F#
-            (Key::T, 19),    // G
-            (Key::Num6, 20), // G#
-            (Key::Y, 21),    // A
-            (Key::Num7, 22), // A#
-            (Key::U, 23),    // B
-        ];
-
+    /// Debounce hover over the preset list: audition a preset once it has
+    /// been under the pointer for `PREVIEW_HOVER_DELAY`, and stop when the
+    /// pointer leaves the list.
+    fn update_preview_hover(&mut self, ctx: &egui::Context, hovered: Option<usize>) {
+        if !self.preview_on_hover || self.preview.is_none() {
+            return;
+        }
         let now = std::time::Instant::now();
+        match (hovered, self.preview_hover) {
+            (None, _) => {
+                if self.preview_hover.take().is_some() {
+                    self.stop_preview();
+                }
+            }
+            (Some(idx), Some((prev, _))) if idx == prev => {}
+            (Some(idx), _) => self.preview_hover = Some((idx, now)),
+        }
 
-        for (key, semitone) in &key_map {
-            if ctx.input(|i| i.key_pressed(*key)) {
-                let note = (self.current_octave * 12 + 12 + semitone) as u8;
-                if let Ok(mut ctrl) = self.lock_controller() {
-                    ctrl.note_on(note, 100);
+        let Some((idx, since)) = self.preview_hover else {
+            return;
+        };
+        if self.preview_played == Some(idx) {
+            return;
+        }
+        let elapsed = now.duration_since(since);
+        if elapsed >= PREVIEW_HOVER_DELAY {
+            self.audition_preset(idx);
+        } else {
+            // Wake up when the debounce expires even if the mouse stays still.
+            ctx.request_repaint_after(PREVIEW_HOVER_DELAY - elapsed);
+        }
+    }
+
+    /// Add an imported bank to the preset list under `collection`, replacing
+    /// an earlier import of the same file. The engine plays the first voice
+    /// of a bulk dump, so that one becomes the current preset.
+    fn import_bank(&mut self, collection: &str, presets: &[Dx7Preset]) {
+        self.presets.retain(|p| p.collection != collection);
+        let first = self.presets.len();
+        self.presets.extend(presets.iter().cloned().map(|mut p| {
+            p.collection = collection.to_string();
+            p
+        }));
+        if first < self.presets.len() {
+            self.selected_preset = first;
+        } else {
+            self.selected_preset = self.selected_preset.min(first.saturating_sub(1));
+        }
+        self.library_ab = [None; 2];
+        self.send_program_map();
+    }
+
+    /// Search, category tags, favorites and A/B audition over every loaded
+    /// preset, including imported banks.
+    fn draw_library_panel(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("search:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.library_filter.search)
+                    .hint_text("name, collection or tag…")
+                    .desired_width(180.0),
+            );
+            if ui.small_button("×").on_hover_text("Clear").clicked() {
+                self.library_filter.search.clear();
+            }
+            ui.separator();
+            ui.checkbox(&mut self.library_filter.favorites_only, "★ only");
+        });
+        ui.horizontal_wrapped(|ui| {
+            ui.label("category:");
+            if ui
+                .selectable_label(self.library_filter.category.is_none(), "all")
+                .clicked()
+            {
+                self.library_filter.category = None;
+            }
+            for category in CATEGORIES {
+                let active = self.library_filter.category.as_deref() == Some(category);
+                if ui.selectable_label(active, category).clicked() {
+                    self.library_filter.category = Some(category.to_string());
                 }
-                self.last_key_times.insert(*key, now);
-            } else if ctx.input(|i| i.key_released(*key)) {
-                if let Some(&_press_time) = self.last_key_times.get(key) {
-                    let note = (self.current_octave * 12 + 12 + semitone) as u8;
-                    if let Ok(mut ctrl) = self.lock_controller() {
-                        ctrl.note_off(note);
+            }
+        });
+
+        let listed = self.library.filter(&self.presets, &self.library_filter);
+        ui.label(
+            egui::RichText::new(format!("{} of {} voices", listed.len(), self.presets.len()))
+                .size(10.0)
+                .color(egui::Color32::GRAY),
+        );
+        ui.separator();
+
+        let mut changed = false;
+        let mut selected = None;
+        let row_height = 20.0;
+        egui::ScrollArea::vertical().max_height(300.0).show_rows(
+            ui,
+            row_height,
+            listed.len(),
+            |ui, rows| {
+                for &index in &listed[rows] {
+                    let preset = &self.presets[index];
+                    ui.horizontal(|ui| {
+                        let favorite = self.library.is_favorite(preset);
+                        let star = if favorite { "★" } else { "☆" };
+                        if ui.small_button(star).on_hover_text("Favorite").clicked() {
+                            self.library.set_favorite(preset, !favorite);
+                            changed = true;
+                        }
+                        let button = egui::Button::new(preset.name.as_str())
+                            .wrap_mode(egui::TextWrapMode::Truncate);
+                        let button = if index == self.selected_preset {
+                            button.fill(egui::Color32::from_rgb(60, 110, 60))
+                        } else {
+                            button
+                        };
+                        let clicked = ui.add_sized([140.0, 18.0], button).clicked();
+                        ui.colored_label(
+                            egui::Color32::from_rgb(180, 180, 80),
+                            preset.collection.as_str(),
+                        );
+                        if let Some((_, error)) = self
+                            .bank_errors
+                            .iter()
+                            .find(|(c, e)| *c == preset.collection && e.name == preset.name)
+                        {
+                            ui.colored_label(egui::Color32::from_rgb(230, 160, 60), "⚠")
+                                .on_hover_text(error.message.as_str());
+                        }
+                        ui.colored_label(egui::Color32::GRAY, self.library.tags(preset).join(" "));
+                        for (slot, label) in ["A", "B"].into_iter().enumerate() {
+                            let active = self.library_ab[slot] == Some(index);
+                            if ui
+                                .selectable_label(active, label)
+                                .on_hover_text(format!("Put in audition slot {label}"))
+                                .clicked()
+                            {
+                                self.library_ab[slot] = Some(index);
+                            }
+                        }
+                        if clicked {
+                            selected = Some(index);
+                        }
+                    });
+                }
+            },
+        );
+        if let Some(index) = selected {
+            self.select_preset(index);
+        }
+        if !self.bank_errors.is_empty() {
+            egui::CollapsingHeader::new(format!("IMPORT PROBLEMS ({})", self.bank_errors.len()))
+                .show(ui, |ui| {
+                    for (collection, error) in &self.bank_errors {
+                        let name = if error.name.is_empty() {
+                            "-"
+                        } else {
+                            error.name.as_str()
+                        };
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "{} #{:02} {}: {}",
+                                collection, error.voice, name, error.message
+                            ))
+                            .size(10.0),
+                        );
+                    }
+                });
+        }
+        ui.separator();
+
+        // Tags of the current voice.
+        if let Some(preset) = self.presets.get(self.selected_preset) {
+            let tags = self.library.tags(preset);
+            let mut toggled = None;
+            ui.horizontal_wrapped(|ui| {
+                ui.label(format!("{} tags:", preset.name));
+                for category in CATEGORIES {
+                    let on = tags.iter().any(|t| t == category);
+                    if ui.selectable_label(on, category).clicked() {
+                        toggled = Some(category);
                     }
-                    self.last_key_times.remove(key);
                 }
+            });
+            if let Some(category) = toggled {
+                let preset = preset.clone();
+                self.library.toggle_tag(&preset, category);
+                changed = true;
             }
         }
+        if ui
+            .add_enabled(
+                self.user_presets_dir.is_some(),
+                egui::Button::new("SAVE AS USER PRESET"),
+            )
+            .on_hover_text("Save the voice being edited to the user collection")
+            .clicked()
+        {
+            self.save_user_preset();
+        }
+
+        // A/B audition through the preview engine.
+        ui.horizontal(|ui| {
+            let names = self.library_ab.map(|slot| {
+                slot.and_then(|i| self.presets.get(i))
+                    .map(|p| p.name.clone())
+            });
+            let enabled = self.preview.is_some();
+            for (slot, label) in ["A", "B"].into_iter().enumerate() {
+                let text = format!("▶ {label}: {}", names[slot].as_deref().unwrap_or("-"));
+                if ui
+                    .add_enabled(enabled && names[slot].is_some(), egui::Button::new(text))
+                    .clicked()
+                {
+                    self.audition_slot(slot);
+                }
+            }
+            if ui
+                .add_enabled(
+                    enabled && names.iter().all(Option::is_some),
+                    egui::Button::new("⇄"),
+                )
+                .on_hover_text("Play the other slot")
+                .clicked()
+            {
+                self.audition_slot(1 - self.library_ab_last);
+            }
+            if !enabled {
+                ui.colored_label(egui::Color32::GRAY, "(no preview engine)");
+            }
+        });
 
-        if ctx.input(|i| i.key_pressed(Key::ArrowUp)) {
-            self.current_octave = (self.current_octave + 1).min(7);
+        if changed {
+            self.save_library();
         }
-        if ctx.input(|i| i.key_pressed(Key::ArrowDown)) {
-            self.current_octave = (self.current_octave - 1).max(0);
+        if !self.library_status.is_empty() {
+            ui.label(
+                egui::RichText::new(&self.library_status)
+                    .size(10.0)
+                    .color(egui::Color32::GRAY),
+            );
         }
+    }
 
-        if ctx.input(|i| i.key_pressed(Key::Space)) {
-            if let Ok(mut ctrl) = self.lock_controller() {
-                ctrl.panic();
+    /// Play the preset in A/B slot `slot` on the preview engine.
+    fn audition_slot(&mut self, slot: usize) {
+        let preset = self.library_ab[slot].and_then(|i| self.presets.get(i));
+        if let (Some(preview), Some(preset)) = (&mut self.preview, preset) {
+            preview.audition(preset);
+            self.library_ab_last = slot;
+        }
+    }
+
+    /// Write the librarian index, reporting failures on the LIBRARY page.
+    /// Save the edit buffer as a user preset and list it under "user",
+    /// replacing a saved voice of the same name.
+    fn save_user_preset(&mut self) {
+        let Some(dir) = self.user_presets_dir.clone() else {
+            return;
+        };
+        let mut preset = Dx7Preset::from_snapshot(&self.snapshot);
+        preset.collection = USER_COLLECTION.to_string();
+        match user_presets::save(&dir, &preset) {
+            Ok(path) => {
+                let saved = self
+                    .presets
+                    .iter()
+                    .position(|p| p.collection == USER_COLLECTION && p.name == preset.name);
+                self.selected_preset = match saved {
+                    Some(index) => {
+                        self.presets[index] = preset;
+                        index
+                    }
+                    None => {
+                        self.presets.push(preset);
+                        self.presets.len() - 1
+                    }
+                };
+                self.library_status = format!("Saved {}", path.display());
+                self.send_program_map();
             }
+            Err(e) => self.library_status = format!("Could not save user preset: {}", e),
         }
     }
-}
 
-impl eframe::App for Dx7App {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.render(ctx);
+    fn save_library(&mut self) {
+        let Some(path) = &self.library_path else {
+            return;
+        };
+        self.library_status = match self.library.save(path) {
+            Ok(()) => String::new(),
+            Err(e) => format!("Could not save {}: {}", path.display(), e),
+        };
     }
-}
 
-impl Dx7App {
-    fn draw_lfo_panel(&mut self, ui: &mut egui::Ui) {
-        ui.group(|ui| {
-            ui.vertical(|ui| {
-                ui.label("LFO CONTROLS");
-                ui.separator();
+    /// Interval of an operator ratio, plus the pitch it plays for the last
+    /// note: "2.00 = +1 octave | C3: 523.3 Hz".
+    fn ratio_readout(&self, ratio: f32, detune: f32) -> String {
+        let mut text = format!("{:.2} = {}", ratio, dx7_frequency::ratio_interval(ratio));
+        if let (Some(note), Some(key_hz)) = (self.snapshot.last_note, self.snapshot.last_note_hz) {
+            let hz = dx7_frequency::operator_frequency(key_hz, ratio, detune);
+            text.push_str(&format!(" | {}: {:.1} Hz", midi_note_name(note), hz));
+        }
+        text
+    }
 
-                let mut lfo_rate = self.snapshot.lfo_rate;
-                let mut lfo_delay = self.snapshot.lfo_delay;
-                let mut lfo_pitch_depth = self.snapshot.lfo_pitch_depth;
-                let mut lfo_amp_depth = self.snapshot.lfo_amp_depth;
+    /// Restore saved settings: octave and theme here, master volume through
+    /// the engine. The MIDI port and window size are used by `main` before
+    /// the app exists.
+    fn apply_settings(&mut self, settings: AppSettings) {
+        self.current_octave = settings.octave;
+        if let Ok(mut ctrl) = self.lock_controller() {
+            ctrl.set_master_volume(settings.master_volume);
+        }
+        if let Ok(mut learn) = self.midi_learn.lock() {
+            learn.set_bindings(settings.cc_bindings.clone());
+        }
+        self.settings = settings;
+        self.send_program_map();
+    }
+
+    /// Hand the engine the presets the program-change map points at, looked
+    /// up in the current list. Called whenever the map or the list changes.
+    fn send_program_map(&self) {
+        let map = self.settings.program_map.resolve(&self.presets);
+        if let Ok(mut ctrl) = self.lock_controller() {
+            ctrl.set_program_map(map);
+        }
+    }
+
+    /// Program-change map editor: which preset each incoming Program Change
+    /// number loads.
+    fn draw_program_map(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Program change map", |ui| {
+            ui.label(
+                egui::RichText::new(
+                    "Program Change numbers listed here load their preset; \
+                     the others load by position in the preset list.",
+                )
+                .size(10.0)
+                .color(egui::Color32::GRAY),
+            );
+            let mut removed = None;
+            egui::Grid::new("program_map_grid")
+                .num_columns(3)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    for (program, preset) in self.settings.program_map.iter() {
+                        ui.label(format!("PC {}", program));
+                        let text = format!("{} ({})", preset.name, preset.collection);
+                        if self
+                            .settings
+                            .program_map
+                            .find(program, &self.presets)
+                            .is_some()
+                        {
+                            ui.label(text);
+                        } else {
+                            ui.label(egui::RichText::new(text).color(egui::Color32::GRAY))
+                                .on_hover_text("Not in the preset list; the bank position loads");
+                        }
+                        if ui.small_button("✕").on_hover_text("Remove").clicked() {
+                            removed = Some(program);
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            let mut assigned = false;
+            ui.horizontal(|ui| {
+                let (program, preset) = &mut self.program_map_pick;
+                ui.label("PC");
+                ui.add(egui::DragValue::new(program).range(0..=127));
+                ui.label("→");
+                let selected = self
+                    .presets
+                    .get(*preset)
+                    .map(|p| format!("{} ({})", p.name, p.collection))
+                    .unwrap_or_else(|| "(no presets)".to_string());
+                egui::ComboBox::from_id_source("program_map_preset")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        for (index, p) in self.presets.iter().enumerate() {
+                            ui.selectable_value(
+                                preset,
+                                index,
+                                format!("{} ({})", p.name, p.collection),
+                            );
+                        }
+                    });
+                assigned = ui.button("ASSIGN").clicked();
+            });
+
+            let (program, preset) = self.program_map_pick;
+            if assigned {
+                if let Some(preset) = self.presets.get(preset) {
+                    self.settings.program_map.assign(program, preset);
+                }
+            }
+            if let Some(program) = removed {
+                self.settings.program_map.remove(program);
+            }
+            if assigned || removed.is_some() {
+                self.send_program_map();
+            }
+        });
+    }
+
+    /// MIDI-learn bindings: which hardware CC drives which parameter.
+    fn draw_cc_bindings(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("MIDI learn", |ui| {
+            ui.label(
+                egui::RichText::new(
+                    "Right-click a slider, then move a knob on the controller \
+                     to bind its CC. A bound CC no longer does its usual job.",
+                )
+                .size(10.0)
+                .color(egui::Color32::GRAY),
+            );
+            let Ok(mut learn) = self.midi_learn.lock() else {
+                return;
+            };
+            if let Some(param) = learn.armed() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Waiting for a CC for {}", param.label()));
+                    if ui.small_button("CANCEL").clicked() {
+                        learn.cancel();
+                    }
+                });
+            }
+            if learn.bindings().is_empty() {
+                ui.label("No CCs bound.");
+                return;
+            }
+            let mut removed = None;
+            egui::Grid::new("cc_bindings_grid")
+                .num_columns(3)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    for (cc, param) in learn.bindings().iter() {
+                        ui.label(format!("CC {}", cc));
+                        ui.label(param.label());
+                        if ui.small_button("✕").on_hover_text("Remove").clicked() {
+                            removed = Some(cc);
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(cc) = removed {
+                learn.remove(cc);
+            }
+        });
+    }
+
+    /// Write the current MIDI port, volume, octave, theme, window size and
+    /// CC bindings.
+    fn save_settings(&mut self) {
+        self.settings.master_volume = self.snapshot.master_volume;
+        self.settings.octave = self.current_octave;
+        if let Ok(learn) = self.midi_learn.lock() {
+            self.settings.cc_bindings = learn.bindings().clone();
+        }
+        if let Some(handler) = &self._midi_handler {
+            self.settings.midi_port = Some(handler.port_name().to_string());
+        }
+        let Some(path) = &self.settings_path else {
+            return;
+        };
+        self.settings_status = match self.settings.save(path) {
+            Ok(()) => format!("Saved to {}", path.display()),
+            Err(e) => format!("Could not save {}: {}", path.display(), e),
+        };
+    }
+
+    /// Reconnect MIDI input to `port`, keeping the channel and clock source.
+    fn connect_midi_port(&mut self, port: &str) {
+        let clock_source = self.clock_source();
+        // Close the old port first; some backends refuse a second connection.
+        self._midi_handler = None;
+        match MidiHandler::new(self.controller.clone(), Some(port), self.midi_learn.clone()) {
+            Ok(handler) => {
+                handler.set_channel(self.midi_channel_ui);
+                handler.set_clock_source(clock_source);
+                self.settings.midi_port = Some(handler.port_name().to_string());
+                self.settings_status = format!("MIDI input: {}", handler.port_name());
+                self._midi_handler = Some(handler);
+            }
+            Err(e) => self.settings_status = format!("MIDI input failed: {}", e),
+        }
+    }
+
+    /// Dialog for the settings restored on launch.
+    fn draw_settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("settings_grid")
+                    .num_columns(2)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Audio output:");
+                        ui.horizontal(|ui| {
+                            ui.label(format!(
+                                "{} / {}",
+                                self.audio_settings
+                                    .host
+                                    .as_deref()
+                                    .unwrap_or("default host"),
+                                self.audio_settings
+                                    .device
+                                    .as_deref()
+                                    .unwrap_or("default device")
+                            ));
+                            if ui.small_button("AUDIO page…").clicked() {
+                                self.display_mode = DisplayMode::Audio;
+                                self.display_text = DisplayMode::Audio.title().to_string();
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("MIDI input:");
+                        let current = self
+                            ._midi_handler
+                            .as_ref()
+                            .map(|h| h.port_name().to_string());
+                        let mut picked = None;
+                        egui::ComboBox::from_id_source("settings_midi_port")
+                            .selected_text(current.as_deref().unwrap_or("(none)"))
+                            .show_ui(ui, |ui| {
+                                for port in MidiHandler::input_ports() {
+                                    let active = current.as_deref() == Some(port.as_str());
+                                    if ui.selectable_label(active, &port).clicked() && !active {
+                                        picked = Some(port);
+                                    }
+                                }
+                            });
+                        if let Some(port) = picked {
+                            self.connect_midi_port(&port);
+                        }
+                        ui.end_row();
+
+                        ui.label("Master volume:");
+                        let mut volume = self.snapshot.master_volume;
+                        if ui.add(egui::Slider::new(&mut volume, 0.0..=1.0)).changed() {
+                            if let Ok(mut ctrl) = self.lock_controller() {
+                                ctrl.set_master_volume(volume);
+                            }
+                        }
+                        ui.end_row();
+
+                        ui.label("Keyboard octave:");
+                        ui.add(egui::DragValue::new(&mut self.current_octave).range(OCTAVE_RANGE));
+                        ui.end_row();
+
+                        ui.label("Theme:");
+                        ui.horizontal(|ui| {
+                            for theme in Theme::ALL {
+                                ui.selectable_value(&mut self.settings.theme, theme, theme.label());
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Window size:");
+                        let [width, height] = self.settings.window_size;
+                        ui.label(format!("{:.0} × {:.0}", width, height));
+                        ui.end_row();
+
+                        ui.label("Startup melody:");
+                        ui.checkbox(&mut self.settings.startup_melody, "play on launch")
+                            .on_hover_text("The AUDIO page's TEST TONE plays it on demand");
+                        ui.end_row();
+                    });
+                ui.separator();
+                self.draw_program_map(ui);
+                self.draw_cc_bindings(ui);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(
+                        egui::RichText::new("Saved on exit and restored on launch.")
+                            .size(10.0)
+                            .color(egui::Color32::GRAY),
+                    );
+                    if ui.button("SAVE NOW").clicked() {
+                        self.save_settings();
+                    }
+                });
+                if !self.settings_status.is_empty() {
+                    ui.label(
+                        egui::RichText::new(&self.settings_status)
+                            .size(10.0)
+                            .color(egui::Color32::GRAY),
+                    );
+                }
+            });
+        self.show_settings = open;
+    }
+
+    /// Shortcut list: (keys, what they do), following the current bindings.
+    fn shortcut_rows(&self) -> Vec<(String, &'static str)> {
+        let kb = &self.key_bindings;
+        let pair =
+            |a: KeyAction, b: KeyAction| format!("{} / {}", kb.key(a).name(), kb.key(b).name());
+        vec![
+            (
+                format!(
+                    "{}-{}, {}-{}",
+                    kb.key(KeyAction::Note(0)).name(),
+                    kb.key(KeyAction::Note(11)).name(),
+                    kb.key(KeyAction::Note(12)).name(),
+                    kb.key(KeyAction::Note(23)).name()
+                ),
+                "Play notes (Shift / Ctrl: other velocity)",
+            ),
+            (
+                pair(KeyAction::OctaveDown, KeyAction::OctaveUp),
+                "Octave down / up",
+            ),
+            ("Alt+1 … Alt+6".to_string(), "Select operator"),
+            (
+                pair(KeyAction::PrevAlgorithm, KeyAction::NextAlgorithm),
+                "Previous / next algorithm",
+            ),
+            (
+                pair(KeyAction::NudgeDown, KeyAction::NudgeUp),
+                "Nudge the slider last dragged",
+            ),
+            (
+                kb.key(KeyAction::NextDisplayMode).name().to_string(),
+                "Next page",
+            ),
+            (
+                pair(KeyAction::PrevPreset, KeyAction::NextPreset),
+                "Previous / next preset",
+            ),
+            (
+                (0..COMPARE_SLOTS as u8)
+                    .map(|slot| kb.key(KeyAction::Compare(slot)).name())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                "Compare slots A-D (Shift: store)",
+            ),
+            (kb.key(KeyAction::Hold).name().to_string(), "Hold"),
+            (kb.key(KeyAction::Panic).name().to_string(), "Panic"),
+            (
+                kb.key(KeyAction::Shortcuts).name().to_string(),
+                "Show / hide this list",
+            ),
+        ]
+    }
+
+    fn draw_shortcuts_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_shortcuts;
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_TOP, [-12.0, 48.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts_grid")
+                    .num_columns(2)
+                    .spacing([12.0, 4.0])
+                    .show(ui, |ui| {
+                        for (keys, action) in self.shortcut_rows() {
+                            ui.label(egui::RichText::new(keys).monospace());
+                            ui.label(action);
+                            ui.end_row();
+                        }
+                    });
+                ui.label(
+                    egui::RichText::new("Rebind them on the KEYS page.")
+                        .size(11.0)
+                        .color(egui::Color32::GRAY),
+                );
+            });
+        self.show_shortcuts = open;
+    }
+
+    fn stop_preview(&mut self) {
+        self.preview_played = None;
+        if let Some(preview) = &mut self.preview {
+            preview.stop();
+        }
+    }
+
+    /// Play preset `index` on the preview engine.
+    fn audition_preset(&mut self, index: usize) {
+        if let (Some(preview), Some(preset)) = (&mut self.preview, self.presets.get(index)) {
+            preview.audition(preset);
+        }
+        self.preview_played = Some(index);
+    }
+
+    /// Rarely-touched engine settings, collapsed by default.
+    fn draw_advanced_settings(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("ADVANCED")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("STEAL FADE:");
+                    let mut steal = self.snapshot.steal_fade_ms;
+                    if ui
+                        .add(egui::Slider::new(&mut steal, 0.0..=50.0).suffix(" ms"))
+                        .on_hover_text("Crossfade between a stolen voice and the new note")
+                        .changed()
+                    {
+                        if let Ok(mut ctrl) = self.lock_controller() {
+                            ctrl.set_steal_fade_time(steal);
+                        }
+                    }
+                    ui.label("ATTACK FADE:");
+                    let mut attack = self.snapshot.attack_fade_ms;
+                    if ui
+                        .add(egui::Slider::new(&mut attack, 0.0..=50.0).suffix(" ms"))
+                        .on_hover_text("Anti-click fade-in on every note-on")
+                        .changed()
+                    {
+                        if let Ok(mut ctrl) = self.lock_controller() {
+                            ctrl.set_attack_fade_time(attack);
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("BEND SMOOTHING:");
+                    let mut smoothing = self.snapshot.pitch_bend_smoothing_ms;
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut smoothing, 0.0..=MAX_BEND_SMOOTHING_MS)
+                                .suffix(" ms"),
+                        )
+                        .on_hover_text(
+                            "Time the pitch takes to follow the bend wheel; hides the steps of coarse controllers",
+                        )
+                        .changed()
+                    {
+                        if let Ok(mut ctrl) = self.lock_controller() {
+                            ctrl.set_pitch_bend_smoothing(smoothing);
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let mut watchdog = self.snapshot.voice_watchdog;
+                    if ui
+                        .checkbox(&mut watchdog, "STUCK-NOTE WATCHDOG")
+                        .on_hover_text(
+                            "Release voices no key or pedal has held for half a second, \
+                             e.g. after a lost MIDI note-off",
+                        )
+                        .changed()
+                    {
+                        if let Ok(mut ctrl) = self.lock_controller() {
+                            ctrl.set_voice_watchdog(watchdog);
+                        }
+                    }
+                    ui.label("VOICE TIMEOUT:");
+                    let mut timeout = self.snapshot.voice_timeout;
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut timeout, 0.0..=120.0).custom_formatter(
+                                |s, _| {
+                                    if s == 0.0 {
+                                        "OFF".to_string()
+                                    } else {
+                                        format!("{s:.0} s")
+                                    }
+                                },
+                            ),
+                        )
+                        .on_hover_text("Release any note this long after it was played")
+                        .changed()
+                    {
+                        if let Ok(mut ctrl) = self.lock_controller() {
+                            ctrl.set_voice_timeout(timeout);
+                        }
+                    }
+                    ui.label(format!("RELEASED: {}", self.snapshot.watchdog_releases));
+                });
+            });
+    }
+
+    /// DUAL / SPLIT play: mode, split point, the voice of part B and each
+    /// part's volume and transpose. Part A is the current voice.
+    fn draw_performance_section(&mut self, ui: &mut egui::Ui) {
+        let mut performance = self.snapshot.performance;
+        egui::CollapsingHeader::new("PERFORMANCE")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("MODE:");
+                    for mode in PerformanceMode::ALL {
+                        ui.selectable_value(&mut performance.mode, mode, mode.label());
+                    }
+                    ui.add_enabled_ui(performance.mode == PerformanceMode::Split, |ui| {
+                        ui.label("SPLIT POINT:");
+                        ui.add(
+                            egui::Slider::new(&mut performance.split_point, 0..=127)
+                                .custom_formatter(|n, _| midi_note_name(n as u8)),
+                        )
+                        .on_hover_text("First key of part B; part A plays below it");
+                    });
+                });
+                ui.add_enabled_ui(performance.uses_part_b(), |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("PART B VOICE:");
+                        let mut chosen = None;
+                        egui::ComboBox::from_id_source("part_b_voice")
+                            .selected_text(self.snapshot.part_b_name.as_str())
+                            .width(200.0)
+                            .show_ui(ui, |ui| {
+                                for (i, preset) in self.presets.iter().enumerate() {
+                                    let label = format!("{} [{}]", preset.name, preset.collection);
+                                    if ui.selectable_label(false, label).clicked() {
+                                        chosen = Some(i);
+                                    }
+                                }
+                            });
+                        if let Some(index) = chosen {
+                            self.load_part_b(index);
+                        }
+                    });
+                    for (name, part) in ["A", "B"].into_iter().zip(&mut performance.parts) {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("PART {name}  VOL:"));
+                            ui.add(egui::Slider::new(&mut part.volume, 0.0..=1.0));
+                            ui.label("TRANSPOSE:");
+                            ui.add(
+                                egui::DragValue::new(&mut part.transpose)
+                                    .range(PART_TRANSPOSE_RANGE)
+                                    .suffix(" st"),
+                            );
+                        });
+                    }
+                });
+            });
+        if performance != self.snapshot.performance {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.set_performance(performance);
+            }
+        }
+    }
+
+    /// Send preset `index` to the engine as the voice of part B.
+    fn load_part_b(&mut self, index: usize) {
+        let Some(preset) = self.presets.get(index).cloned() else {
+            return;
+        };
+        if let Ok(mut ctrl) = self.lock_controller() {
+            ctrl.load_part_b(preset);
+        }
+    }
+
+    /// Send preset `index` to the engine and make it the current voice.
+    fn select_preset(&mut self, index: usize) {
+        let Some(preset) = self.presets.get(index).cloned() else {
+            return;
+        };
+        self.selected_preset = index;
+        if let Ok(mut ctrl) = self.lock_controller() {
+            ctrl.apply_preset(preset.clone());
+        }
+        self.display_text = format!("LOADED: {}", preset.name);
+    }
+
+    fn handle_keyboard_input(&mut self, ctx: &egui::Context) {
+        // The KEYS editor swallows the next key press instead of playing it.
+        if let Some(action) = self.capturing_key {
+            let pressed = ctx.input(|i| {
+                i.events.iter().find_map(|e| match e {
+                    egui::Event::Key {
+                        key, pressed: true, ..
+                    } => Some(*key),
+                    _ => None,
+                })
+            });
+            if let Some(key) = pressed {
+                if key != egui::Key::Escape {
+                    self.key_bindings.set(action, key);
+                }
+                self.capturing_key = None;
+            }
+            return;
+        }
+
+        // The hidden debug panel. Consumed first so the D note key stays
+        // quiet.
+        let debug_chord = egui::Modifiers::COMMAND | egui::Modifiers::SHIFT;
+        if ctx.input_mut(|i| i.consume_key(debug_chord, egui::Key::D)) {
+            self.show_debug_panel = !self.show_debug_panel;
+        }
+
+        let now = std::time::Instant::now();
+        let velocity = ctx.input(|i| self.key_bindings.velocity.for_modifiers(i.modifiers));
+        // Alt turns the number row into operator keys.
+        let alt = ctx.input(|i| i.modifiers.alt);
+
+        for semitone in 0..crate::keybindings::NOTE_KEY_COUNT as i32 {
+            let key = self.key_bindings.key(KeyAction::Note(semitone as u8));
+            if !alt && ctx.input(|i| i.key_pressed(key)) {
+                let note = (self.current_octave * 12 + 12 + semitone) as u8;
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.note_on(note, velocity);
+                }
+                self.last_key_times.insert(key, now);
+            } else if ctx.input(|i| i.key_released(key)) {
+                if let Some(&_press_time) = self.last_key_times.get(&key) {
+                    let note = (self.current_octave * 12 + 12 + semitone) as u8;
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.note_off(note);
+                    }
+                    self.last_key_times.remove(&key);
+                }
+            }
+        }
+
+        let bindings = &self.key_bindings;
+        let pressed = |action: KeyAction| ctx.input(|i| i.key_pressed(bindings.key(action)));
+        let octave_up = pressed(KeyAction::OctaveUp);
+        let octave_down = pressed(KeyAction::OctaveDown);
+        let panic = pressed(KeyAction::Panic);
+        let next_mode = pressed(KeyAction::NextDisplayMode);
+        let prev_preset = pressed(KeyAction::PrevPreset);
+        let next_preset = pressed(KeyAction::NextPreset);
+        let hold = pressed(KeyAction::Hold);
+        let prev_algorithm = pressed(KeyAction::PrevAlgorithm);
+        let next_algorithm = pressed(KeyAction::NextAlgorithm);
+        let nudge = pressed(KeyAction::NudgeUp) as i32 - pressed(KeyAction::NudgeDown) as i32;
+        let shortcuts = pressed(KeyAction::Shortcuts);
+        let compare = (0..COMPARE_SLOTS).find(|&slot| pressed(KeyAction::Compare(slot as u8)));
+        let shift = ctx.input(|i| i.modifiers.shift);
+
+        if octave_up {
+            self.current_octave = (self.current_octave + 1).min(*OCTAVE_RANGE.end());
+        }
+        if octave_down {
+            self.current_octave = (self.current_octave - 1).max(*OCTAVE_RANGE.start());
+        }
+
+        if hold {
+            self.keyboard_hold = !self.keyboard_hold;
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.sustain_pedal(self.keyboard_hold);
+            }
+        }
+
+        if panic {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.panic();
+                // Panic leaves the pedal alone; let go of the hold as well.
+                if self.keyboard_hold {
+                    ctrl.sustain_pedal(false);
+                }
+            }
+            self.keyboard_hold = false;
+        }
+
+        if next_mode {
+            self.display_mode = self.display_mode.next();
+            self.display_text = self.display_mode.title().to_string();
+        }
+
+        if !self.presets.is_empty() && prev_preset != next_preset {
+            let count = self.presets.len();
+            let index = if prev_preset {
+                (self.selected_preset + count - 1) % count
+            } else {
+                (self.selected_preset + 1) % count
+            };
+            self.select_preset(index);
+            // Stepping through a bank plays each voice as it comes up.
+            if self.preview_on_hover {
+                self.audition_preset(index);
+            }
+        }
+
+        if shortcuts {
+            self.show_shortcuts = !self.show_shortcuts;
+        }
+
+        if let Some(slot) = compare {
+            self.use_compare_slot(slot, shift);
+        }
+
+        // Sliders don't take focus when clicked, so the last one dragged
+        // gets it and is what +/- nudge.
+        if let Some(id) = ctx.drag_started_id() {
+            ctx.memory_mut(|m| m.request_focus(id));
+        }
+
+        // The editing keys are ordinary characters; leave them to text
+        // fields that have focus.
+        let focused = ctx.memory(|m| m.focused());
+        if focused.is_some_and(|id| egui::TextEdit::load_state(ctx, id).is_some()) {
+            return;
+        }
+
+        if alt {
+            for (op, key) in OPERATOR_KEYS.into_iter().enumerate() {
+                if ctx.input(|i| i.key_pressed(key)) {
+                    self.selected_operator = op;
+                }
+            }
+        }
+
+        if prev_algorithm {
+            self.step_algorithm(-1);
+        }
+        if next_algorithm {
+            self.step_algorithm(1);
+        }
+
+        // +/- reach the focused widget as the arrow keys egui already
+        // handles. Octave keys were read above, so the injected arrows don't
+        // move it.
+        if nudge != 0 && focused.is_some() {
+            let keys = if nudge > 0 {
+                [egui::Key::ArrowRight, egui::Key::ArrowUp]
+            } else {
+                [egui::Key::ArrowLeft, egui::Key::ArrowDown]
+            };
+            ctx.input_mut(|i| {
+                i.events.extend(keys.map(|key| egui::Event::Key {
+                    key,
+                    physical_key: None,
+                    pressed: true,
+                    repeat: false,
+                    modifiers: egui::Modifiers::NONE,
+                }))
+            });
+        }
+    }
+}
+
+impl eframe::App for Dx7App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.render(ctx);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_settings();
+    }
+}
+
+impl Dx7App {
+    fn draw_lfo_panel(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label("LFO CONTROLS");
+                    ui.separator();
+                    self.draw_tempo_control(ui);
+                });
+                ui.separator();
+
+                let mut lfo_rate = self.snapshot.lfo_rate;
+                let mut lfo_delay = self.snapshot.lfo_delay;
+                let mut lfo_pitch_depth = self.snapshot.lfo_pitch_depth;
+                let mut lfo_amp_depth = self.snapshot.lfo_amp_depth;
                 let lfo_waveform = self.snapshot.lfo_waveform;
                 let mut lfo_key_sync = self.snapshot.lfo_key_sync;
 
@@ -963,8 +3204,11 @@ impl Dx7App {
                         ui.label("TIMING");
                         ui.horizontal(|ui| {
                             ui.label("Rate:");
-                            if ui
-                                .add(egui::Slider::new(&mut lfo_rate, 0.0..=99.0).integer())
+                            if self
+                                .track(
+                                    DataEntryParam::LfoRate,
+                                    ui.add(egui::Slider::new(&mut lfo_rate, 0.0..=99.0).integer()),
+                                )
                                 .changed()
                             {
                                 if let Ok(mut ctrl) = self.lock_controller() {
@@ -974,8 +3218,11 @@ impl Dx7App {
                         });
                         ui.horizontal(|ui| {
                             ui.label("Delay:");
-                            if ui
-                                .add(egui::Slider::new(&mut lfo_delay, 0.0..=99.0).integer())
+                            if self
+                                .track(
+                                    DataEntryParam::LfoDelay,
+                                    ui.add(egui::Slider::new(&mut lfo_delay, 0.0..=99.0).integer()),
+                                )
                                 .changed()
                             {
                                 if let Ok(mut ctrl) = self.lock_controller() {
@@ -983,6 +3230,13 @@ impl Dx7App {
                                 }
                             }
                         });
+                        self.tempo_sync_control(
+                            ui,
+                            "lfo_sync",
+                            self.snapshot.lfo_sync,
+                            LFO_SYNC_HINT,
+                            |ctrl, v| ctrl.set_lfo_param(LfoParam::SyncDivision, v),
+                        );
                         ui.label(format!(
                             "Freq: {:.2} Hz | Delay: {:.2}s",
                             self.snapshot.lfo_frequency_hz, self.snapshot.lfo_delay_seconds
@@ -994,8 +3248,14 @@ impl Dx7App {
                         ui.label("MODULATION");
                         ui.horizontal(|ui| {
                             ui.label("Pitch:");
-                            if ui
-                                .add(egui::Slider::new(&mut lfo_pitch_depth, 0.0..=99.0).integer())
+                            if self
+                                .track(
+                                    DataEntryParam::LfoPitchDepth,
+                                    ui.add(
+                                        egui::Slider::new(&mut lfo_pitch_depth, 0.0..=99.0)
+                                            .integer(),
+                                    ),
+                                )
                                 .changed()
                             {
                                 if let Ok(mut ctrl) = self.lock_controller() {
@@ -1005,8 +3265,13 @@ impl Dx7App {
                         });
                         ui.horizontal(|ui| {
                             ui.label("Amp:");
-                            if ui
-                                .add(egui::Slider::new(&mut lfo_amp_depth, 0.0..=99.0).integer())
+                            if self
+                                .track(
+                                    DataEntryParam::LfoAmpDepth,
+                                    ui.add(
+                                        egui::Slider::new(&mut lfo_amp_depth, 0.0..=99.0).integer(),
+                                    ),
+                                )
                                 .changed()
                             {
                                 if let Ok(mut ctrl) = self.lock_controller() {
@@ -1089,105 +3354,736 @@ impl Dx7App {
                             .changed()
                         {
                             if let Ok(mut ctrl) = self.lock_controller() {
-                                ctrl.set_pitch_bias_sensitivity(pitch_bias as u8);
+                                ctrl.set_pitch_bias_sensitivity(pitch_bias as u8);
+                            }
+                        }
+                    });
+                });
+
+                ui.separator();
+                self.draw_lfo2_section(ui);
+
+                ui.separator();
+                self.draw_pitch_eg_section(ui);
+
+                ui.separator();
+                let mod_pct = (self.snapshot.mod_wheel * 100.0) as i32;
+                ui.label(format!(
+                    "Mod Wheel: {}%{}",
+                    mod_pct,
+                    if mod_pct == 0 {
+                        " (move to enable)"
+                    } else {
+                        ""
+                    }
+                ));
+            });
+        });
+    }
+
+    /// Second LFO. Only operators with their "LFO 2" box ticked follow it,
+    /// through their own AMS and PMS.
+    fn draw_lfo2_section(&self, ui: &mut egui::Ui) {
+        let lfo2 = self.snapshot.lfo2;
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("LFO 2").strong());
+            let mut enabled = lfo2.enabled;
+            if ui.checkbox(&mut enabled, "enabled").changed() {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_lfo2_enabled(enabled);
+                }
+            }
+            ui.label(format!("{:.2} Hz", lfo2.frequency_hz));
+        });
+        if !lfo2.enabled {
+            return;
+        }
+
+        egui::Grid::new("lfo2_grid")
+            .num_columns(4)
+            .spacing([8.0, 4.0])
+            .show(ui, |ui| {
+                for (label, value, param) in [
+                    ("Rate:", lfo2.rate, LfoParam::Rate),
+                    ("Delay:", lfo2.delay, LfoParam::Delay),
+                    ("Pitch:", lfo2.pitch_depth, LfoParam::PitchDepth),
+                    ("Amp:", lfo2.amp_depth, LfoParam::AmpDepth),
+                ] {
+                    ui.label(label);
+                    let mut v = value;
+                    if ui
+                        .add(egui::Slider::new(&mut v, 0.0..=99.0).integer())
+                        .changed()
+                    {
+                        if let Ok(mut ctrl) = self.lock_controller() {
+                            ctrl.set_lfo2_param(param, v);
+                        }
+                    }
+                    if matches!(param, LfoParam::Delay | LfoParam::AmpDepth) {
+                        ui.end_row();
+                    }
+                }
+
+                ui.label("Wave:");
+                let mut waveform = lfo2.waveform;
+                egui::ComboBox::from_id_source("lfo2_waveform")
+                    .selected_text(waveform.name())
+                    .show_ui(ui, |ui| {
+                        for (i, &w) in crate::lfo::LFOWaveform::all().iter().enumerate() {
+                            if ui.selectable_value(&mut waveform, w, w.name()).clicked() {
+                                if let Ok(mut ctrl) = self.lock_controller() {
+                                    ctrl.set_lfo2_param(LfoParam::Waveform(i as u8), 0.0);
+                                }
+                            }
+                        }
+                    });
+                ui.label("Key Sync:");
+                let mut key_sync = lfo2.key_sync;
+                if ui.checkbox(&mut key_sync, "").changed() {
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.set_lfo2_param(LfoParam::KeySync, if key_sync { 1.0 } else { 0.0 });
+                    }
+                }
+                ui.end_row();
+            });
+        self.tempo_sync_control(ui, "lfo2_sync", lfo2.sync, LFO_SYNC_HINT, |ctrl, v| {
+            ctrl.set_lfo2_param(LfoParam::SyncDivision, v)
+        });
+    }
+
+    /// Engine tempo shared by the synced LFOs and the delay.
+    fn draw_tempo_control(&self, ui: &mut egui::Ui) {
+        ui.label("TEMPO:");
+        let mut bpm = self.snapshot.tempo_bpm;
+        if ui
+            .add_enabled(
+                self.clock_source() == ClockSource::Internal,
+                egui::DragValue::new(&mut bpm)
+                    .range(MIN_BPM..=MAX_BPM)
+                    .speed(0.5)
+                    .suffix(" BPM"),
+            )
+            .on_disabled_hover_text("Following external MIDI clock")
+            .changed()
+        {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.set_tempo(bpm);
+            }
+        }
+    }
+
+    /// "Tempo sync" checkbox plus note-division picker. `apply` gets the
+    /// sync payload shared by the LFOs and the delay: the division code, or
+    /// -1 for off.
+    fn tempo_sync_control<F>(
+        &self,
+        ui: &mut egui::Ui,
+        id: &str,
+        sync: Option<NoteDivision>,
+        hover: &str,
+        mut apply: F,
+    ) where
+        F: FnMut(&mut SynthController, f32),
+    {
+        ui.horizontal(|ui| {
+            let mut synced = sync.is_some();
+            let mut division = sync.unwrap_or_default();
+            let prev = (synced, division);
+            ui.checkbox(&mut synced, "Tempo sync").on_hover_text(hover);
+            ui.add_enabled_ui(synced, |ui| {
+                egui::ComboBox::from_id_source(id)
+                    .selected_text(division.label())
+                    .width(60.0)
+                    .show_ui(ui, |ui| {
+                        for d in NoteDivision::all() {
+                            ui.selectable_value(&mut division, d, d.label());
+                        }
+                    });
+            });
+            if (synced, division) != prev {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    apply(
+                        &mut ctrl,
+                        if synced {
+                            division.to_code() as f32
+                        } else {
+                            -1.0
+                        },
+                    );
+                }
+            }
+        });
+    }
+
+    /// Pitch EG panel — 4 rates + 4 levels matching the amplitude EG layout.
+    /// On the DX7, level 50 means "no pitch offset"; 0 ≈ −4 octaves and 99 ≈ +4 octaves.
+    fn draw_pitch_eg_section(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("PITCH EG").strong());
+            let mut peg_enabled = self.snapshot.pitch_eg.enabled;
+            if ui.checkbox(&mut peg_enabled, "enabled").changed() {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_pitch_eg_param(
+                        PitchEgParam::Enabled,
+                        if peg_enabled { 1.0 } else { 0.0 },
+                    );
+                }
+            }
+            ui.label(
+                egui::RichText::new("(L=50 → no offset; 0 ≈ −4 oct, 99 ≈ +4 oct)")
+                    .size(10.0)
+                    .color(egui::Color32::from_rgb(120, 120, 120)),
+            );
+        });
+
+        let peg = self.snapshot.pitch_eg;
+        egui::Grid::new("pitch_eg_grid")
+            .num_columns(4)
+            .spacing([8.0, 4.0])
+            .show(ui, |ui| {
+                self.pitch_eg_slider(ui, "R1:", peg.rate1, PitchEgParam::Rate1);
+                self.pitch_eg_slider(ui, "R2:", peg.rate2, PitchEgParam::Rate2);
+                ui.end_row();
+                self.pitch_eg_slider(ui, "L1:", peg.level1, PitchEgParam::Level1);
+                self.pitch_eg_slider(ui, "L2:", peg.level2, PitchEgParam::Level2);
+                ui.end_row();
+                self.pitch_eg_slider(ui, "R3:", peg.rate3, PitchEgParam::Rate3);
+                self.pitch_eg_slider(ui, "R4:", peg.rate4, PitchEgParam::Rate4);
+                ui.end_row();
+                self.pitch_eg_slider(ui, "L3:", peg.level3, PitchEgParam::Level3);
+                self.pitch_eg_slider(ui, "L4:", peg.level4, PitchEgParam::Level4);
+                ui.end_row();
+            });
+    }
+
+    /// One labelled 0..99 slider for a Pitch EG parameter. Mirrors the look of
+    /// the operator amplitude EG and the existing `routing_slider` helper.
+    fn pitch_eg_slider(&self, ui: &mut egui::Ui, label: &str, value: f32, param: PitchEgParam) {
+        ui.label(label);
+        let mut v = value;
+        if ui
+            .add(egui::Slider::new(&mut v, 0.0..=99.0).integer())
+            .changed()
+        {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.set_pitch_eg_param(param, v);
+            }
+        }
+    }
+
+    fn draw_effects_panel(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label("EFFECTS");
+                ui.separator();
+
+                self.draw_effect_order(ui);
+                ui.separator();
+
+                let order = self.snapshot.effect_order;
+                ui.columns(EFFECT_SLOTS, |columns| {
+                    for (column, slot) in columns.iter_mut().zip(order) {
+                        match slot {
+                            EffectSlot::Eq => self.draw_eq_effect(column),
+                            EffectSlot::Filter => self.draw_filter_effect(column),
+                            EffectSlot::Phaser => self.draw_phaser_effect(column),
+                            EffectSlot::Chorus => self.draw_chorus_effect(column),
+                            EffectSlot::AutoPan => self.draw_auto_pan_effect(column),
+                            EffectSlot::Delay => self.draw_delay_effect(column),
+                            EffectSlot::Reverb => self.draw_reverb_effect(column),
+                        }
+                        self.draw_effect_output(column, slot);
+                    }
+                });
+
+                ui.separator();
+                self.draw_voice_pan_controls(ui);
+
+                ui.separator();
+                self.draw_sub_bus_controls(ui);
+
+                ui.separator();
+                self.draw_master_compressor(ui);
+
+                ui.separator();
+                let chain = if self.snapshot.effects_bypass {
+                    "(bypassed)".to_string()
+                } else {
+                    order
+                        .iter()
+                        .map(|slot| slot.name())
+                        .collect::<Vec<_>>()
+                        .join(" -> ")
+                };
+                ui.label(format!(
+                    "Signal: Voices (tremolo/pan) -> {chain} -> Master Comp -> Output"
+                ));
+                ui.label("Sub bus: tagged operators -> (sends to Delay / Reverb) -> Output");
+            });
+        });
+    }
+
+    /// Output trim and wet-solo audition under each effect panel.
+    fn draw_effect_output(&mut self, ui: &mut egui::Ui, slot: EffectSlot) {
+        let effect = EffectType::for_slot(slot);
+        let mut gain_db = self.snapshot.effect_gains_db[slot.index()];
+        let soloed = self.snapshot.wet_solo == Some(slot);
+
+        ui.horizontal(|ui| {
+            ui.label("Out:");
+            if ui
+                .add(
+                    egui::Slider::new(&mut gain_db, -EFFECT_MAX_GAIN_DB..=EFFECT_MAX_GAIN_DB)
+                        .suffix(" dB"),
+                )
+                .on_hover_text("Output trim, applied while the effect is on")
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_effect_param(effect, EffectParam::OutputGain, gain_db);
+                }
+            }
+        });
+        if slot.has_wet_solo()
+            && ui
+                .selectable_label(soloed, "SOLO WET")
+                .on_hover_text(
+                    "Hear only this effect's wet signal: no dry, no sub bus, \
+                     nothing after it in the chain",
+                )
+                .clicked()
+        {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.set_effect_param(effect, EffectParam::SoloWet, if soloed { 0.0 } else { 1.0 });
+            }
+        }
+    }
+
+    /// Effect order strip: drag a block onto another to move it there.
+    fn draw_effect_order(&mut self, ui: &mut egui::Ui) {
+        let order = self.snapshot.effect_order;
+        let mut bypass = self.snapshot.effects_bypass;
+        let mut extensions = self.snapshot.extensions;
+        let mut moved = None;
+
+        ui.horizontal(|ui| {
+            ui.label("ORDER:")
+                .on_hover_text("Drag a block onto another to move it there");
+            for (index, slot) in order.iter().enumerate() {
+                if index > 0 {
+                    ui.label("->");
+                }
+                let response = ui
+                    .dnd_drag_source(egui::Id::new(("effect_slot", index)), index, |ui| {
+                        ui.add(egui::Button::new(slot.name()).sense(egui::Sense::hover()));
+                    })
+                    .response;
+                if let Some(from) = response.dnd_release_payload::<usize>() {
+                    moved = Some((*from, index));
+                }
+            }
+
+            ui.separator();
+            if ui
+                .checkbox(&mut bypass, "BYPASS")
+                .on_hover_text("Skip every effect; the settings are kept")
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_effects_bypass(bypass);
+                }
+            }
+            if ui
+                .checkbox(&mut extensions, "EXTENSIONS")
+                .on_hover_text(
+                    "Allow processing the DX7 never had (the resonant filter). \
+                     Off keeps the authentic signal path",
+                )
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_extensions(extensions);
+                }
+            }
+        });
+
+        if let Some((from, to)) = moved.filter(|(from, to)| from != to) {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.set_effect_order(move_effect_slot(order, from, to));
+            }
+        }
+    }
+
+    fn draw_voice_pan_controls(&mut self, ui: &mut egui::Ui) {
+        let mut rate = self.snapshot.voice_pan.rate;
+        let mut depth = self.snapshot.voice_pan.depth;
+        let mut stereo_phase = self.snapshot.voice_pan.stereo_phase;
+
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("VOICE TREMOLO / PAN").strong())
+                .on_hover_text(
+                    "Runs on every note separately, before the effects. \
+                     Stereo Phase 0 = tremolo, 1 = auto-pan.",
+                );
+            ui.label("Rate:");
+            if ui
+                .add(
+                    egui::Slider::new(&mut rate, 0.1..=10.0)
+                        .suffix(" Hz")
+                        .logarithmic(true),
+                )
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_voice_pan_rate(rate);
+                }
+            }
+            ui.label("Depth:");
+            if ui
+                .add(egui::Slider::new(&mut depth, 0.0..=1.0).show_value(true))
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_voice_pan_depth(depth);
+                }
+            }
+            ui.label("Stereo Phase:");
+            if ui
+                .add(egui::Slider::new(&mut stereo_phase, 0.0..=1.0).show_value(true))
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_voice_pan_stereo_phase(stereo_phase);
+                }
+            }
+        });
+
+        let mut spread = self.snapshot.voice_pan.spread;
+        let mut width = self.snapshot.voice_pan.spread_width;
+        let mut master_pan = self.snapshot.voice_pan.master_pan;
+
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("VOICE SPREAD").strong())
+                .on_hover_text(
+                    "Fixed stereo place of each new note. Round Robin alternates \
+                     L/R; Key Follow puts low keys left and high keys right.",
+                );
+            egui::ComboBox::from_id_source("voice_spread")
+                .selected_text(spread.name())
+                .show_ui(ui, |ui| {
+                    for s in VoiceSpread::all() {
+                        if ui.selectable_value(&mut spread, s, s.name()).clicked() {
+                            if let Ok(mut ctrl) = self.lock_controller() {
+                                ctrl.set_voice_spread(s);
                             }
                         }
-                    });
+                    }
                 });
+            ui.label("Width:");
+            if ui
+                .add(egui::Slider::new(&mut width, 0.0..=1.0).show_value(true))
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_voice_spread_width(width);
+                }
+            }
+            ui.label("Master Pan:");
+            if ui
+                .add(egui::Slider::new(&mut master_pan, -1.0..=1.0).show_value(true))
+                .on_hover_text("Shifts every voice; -1 = left, +1 = right")
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_master_pan(master_pan);
+                }
+            }
+        });
 
-                ui.separator();
-                self.draw_pitch_eg_section(ui);
+        self.draw_unison_controls(ui);
+    }
 
-                ui.separator();
-                let mod_pct = (self.snapshot.mod_wheel * 100.0) as i32;
-                ui.label(format!(
-                    "Mod Wheel: {}%{}",
-                    mod_pct,
-                    if mod_pct == 0 {
-                        " (move to enable)"
-                    } else {
-                        ""
+    /// Stacks per note, detune and stereo spread of the unison copies.
+    fn draw_unison_controls(&mut self, ui: &mut egui::Ui) {
+        let current = self.snapshot.unison_voices as usize;
+        let mut detune = self.snapshot.unison_detune;
+        let mut spread = self.snapshot.unison_spread;
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("UNISON").strong())
+                .on_hover_text(
+                    "Detuned, stereo-spread copies of every note. They run inside \
+                 the voice, so they cost CPU but no polyphony. Takes effect \
+                 from the next note.",
+                );
+            for stacks in 1..=MAX_UNISON {
+                let label = if stacks == 1 {
+                    "OFF".to_string()
+                } else {
+                    format!("×{}", stacks)
+                };
+                if ui.selectable_label(current == stacks, label).clicked() && current != stacks {
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.set_unison_voices(stacks as u8);
                     }
-                ));
+                }
+            }
+            ui.add_enabled_ui(current > 1, |ui| {
+                ui.label("Detune:");
+                if ui
+                    .add(
+                        egui::Slider::new(&mut detune, 0.0..=MAX_UNISON_DETUNE)
+                            .suffix(" ct")
+                            .show_value(true),
+                    )
+                    .changed()
+                {
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.set_unison_detune(detune);
+                    }
+                }
+                ui.label("Spread:");
+                if ui
+                    .add(egui::Slider::new(&mut spread, 0.0..=1.0).show_value(true))
+                    .changed()
+                {
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.set_unison_spread(spread);
+                    }
+                }
             });
         });
     }
 
-    /// Pitch EG panel — 4 rates + 4 levels matching the amplitude EG layout.
-    /// On the DX7, level 50 means "no pitch offset"; 0 ≈ −4 octaves and 99 ≈ +4 octaves.
-    fn draw_pitch_eg_section(&self, ui: &mut egui::Ui) {
+    fn draw_sub_bus_controls(&mut self, ui: &mut egui::Ui) {
+        let mut level = self.snapshot.sub_bus.level;
+        let mut delay_send = self.snapshot.sub_bus.delay_send;
+        let mut reverb_send = self.snapshot.sub_bus.reverb_send;
+
         ui.horizontal(|ui| {
-            ui.label(egui::RichText::new("PITCH EG").strong());
-            let mut peg_enabled = self.snapshot.pitch_eg.enabled;
-            if ui.checkbox(&mut peg_enabled, "enabled").changed() {
+            ui.label(egui::RichText::new("SUB BUS").strong())
+                .on_hover_text("Operators with 'Sub Bus' ON (OPERATOR page) play through here.");
+            ui.label("Level:");
+            if ui
+                .add(egui::Slider::new(&mut level, 0.0..=1.0).show_value(true))
+                .changed()
+            {
                 if let Ok(mut ctrl) = self.lock_controller() {
-                    ctrl.set_pitch_eg_param(
-                        PitchEgParam::Enabled,
-                        if peg_enabled { 1.0 } else { 0.0 },
-                    );
+                    ctrl.set_sub_bus_level(level);
+                }
+            }
+            ui.label("Delay Send:");
+            if ui
+                .add(egui::Slider::new(&mut delay_send, 0.0..=1.0).show_value(true))
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_sub_bus_delay_send(delay_send);
+                }
+            }
+            ui.label("Reverb Send:");
+            if ui
+                .add(egui::Slider::new(&mut reverb_send, 0.0..=1.0).show_value(true))
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_sub_bus_reverb_send(reverb_send);
                 }
             }
-            ui.label(
-                egui::RichText::new("(L=50 → no offset; 0 ≈ −4 oct, 99 ≈ +4 oct)")
-                    .size(10.0)
-                    .color(egui::Color32::from_rgb(120, 120, 120)),
-            );
         });
+    }
 
-        let peg = self.snapshot.pitch_eg;
-        egui::Grid::new("pitch_eg_grid")
-            .num_columns(4)
-            .spacing([8.0, 4.0])
-            .show(ui, |ui| {
-                self.pitch_eg_slider(ui, "R1:", peg.rate1, PitchEgParam::Rate1);
-                self.pitch_eg_slider(ui, "R2:", peg.rate2, PitchEgParam::Rate2);
-                ui.end_row();
-                self.pitch_eg_slider(ui, "L1:", peg.level1, PitchEgParam::Level1);
-                self.pitch_eg_slider(ui, "L2:", peg.level2, PitchEgParam::Level2);
-                ui.end_row();
-                self.pitch_eg_slider(ui, "R3:", peg.rate3, PitchEgParam::Rate3);
-                self.pitch_eg_slider(ui, "R4:", peg.rate4, PitchEgParam::Rate4);
-                ui.end_row();
-                self.pitch_eg_slider(ui, "L3:", peg.level3, PitchEgParam::Level3);
-                self.pitch_eg_slider(ui, "L4:", peg.level4, PitchEgParam::Level4);
-                ui.end_row();
+    fn draw_master_compressor(&mut self, ui: &mut egui::Ui) {
+        let comp = self.snapshot.compressor;
+        let mut enabled = comp.enabled;
+        let mut threshold = comp.threshold_db;
+        let mut ratio = comp.ratio;
+        let mut attack = comp.attack_ms;
+        let mut release = comp.release_ms;
+        let mut makeup = comp.makeup_db;
+        let mut changed = None;
+
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("MASTER COMP").strong())
+                .on_hover_text("Compressor / limiter on the master bus. Ratio 20 = limiter.");
+            if ui.checkbox(&mut enabled, "").changed() {
+                changed = Some((CompressorParam::Enabled, if enabled { 1.0 } else { 0.0 }));
+            }
+            ui.add_enabled_ui(enabled, |ui| {
+                ui.label("Thresh:");
+                if ui
+                    .add(egui::Slider::new(&mut threshold, MIN_THRESHOLD_DB..=0.0).suffix(" dB"))
+                    .changed()
+                {
+                    changed = Some((CompressorParam::Threshold, threshold));
+                }
+                ui.label("Ratio:");
+                if ui
+                    .add(egui::Slider::new(&mut ratio, 1.0..=LIMIT_RATIO).logarithmic(true))
+                    .changed()
+                {
+                    changed = Some((CompressorParam::Ratio, ratio));
+                }
+                ui.label("Attack:");
+                if ui
+                    .add(
+                        egui::Slider::new(&mut attack, 0.1..=100.0)
+                            .suffix(" ms")
+                            .logarithmic(true),
+                    )
+                    .changed()
+                {
+                    changed = Some((CompressorParam::Attack, attack));
+                }
+                ui.label("Release:");
+                if ui
+                    .add(
+                        egui::Slider::new(&mut release, 10.0..=2000.0)
+                            .suffix(" ms")
+                            .logarithmic(true),
+                    )
+                    .changed()
+                {
+                    changed = Some((CompressorParam::Release, release));
+                }
+                ui.label("Makeup:");
+                if ui
+                    .add(egui::Slider::new(&mut makeup, 0.0..=MAX_MAKEUP_DB).suffix(" dB"))
+                    .changed()
+                {
+                    changed = Some((CompressorParam::Makeup, makeup));
+                }
             });
-    }
 
-    /// One labelled 0..99 slider for a Pitch EG parameter. Mirrors the look of
-    /// the operator amplitude EG and the existing `routing_slider` helper.
-    fn pitch_eg_slider(&self, ui: &mut egui::Ui, label: &str, value: f32, param: PitchEgParam) {
-        ui.label(label);
-        let mut v = value;
-        if ui
-            .add(egui::Slider::new(&mut v, 0.0..=99.0).integer())
-            .changed()
-        {
+            // Gain-reduction meter: a full bar is 20 dB of reduction.
+            let gr = comp.gain_reduction_db;
+            let color = if gr > 0.5 {
+                egui::Color32::from_rgb(230, 140, 40)
+            } else {
+                egui::Color32::from_rgb(90, 160, 90)
+            };
+            ui.label("GR:");
+            ui.add(
+                egui::ProgressBar::new((gr / 20.0).clamp(0.0, 1.0))
+                    .desired_width(80.0)
+                    .fill(color)
+                    .text(format!("-{gr:.1} dB")),
+            );
+        });
+
+        if let Some((param, value)) = changed {
             if let Ok(mut ctrl) = self.lock_controller() {
-                ctrl.set_pitch_eg_param(param, v);
+                ctrl.set_compressor_param(param, value);
             }
         }
     }
 
-    fn draw_effects_panel(&mut self, ui: &mut egui::Ui) {
+    fn draw_eq_effect(&mut self, ui: &mut egui::Ui) {
         ui.group(|ui| {
             ui.vertical(|ui| {
-                ui.label("EFFECTS");
-                ui.separator();
+                ui.label(egui::RichText::new("EQ").strong())
+                    .on_hover_text("Low shelf, parametric mid, high shelf");
+
+                let eq = self.snapshot.eq;
+                let mut enabled = eq.enabled;
 
-                ui.columns(4, |columns| {
-                    self.draw_chorus_effect(&mut columns[0]);
-                    self.draw_auto_pan_effect(&mut columns[1]);
-                    self.draw_delay_effect(&mut columns[2]);
-                    self.draw_reverb_effect(&mut columns[3]);
+                ui.horizontal(|ui| {
+                    ui.label("Enable:");
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        if let Ok(mut ctrl) = self.lock_controller() {
+                            ctrl.set_effect_param(
+                                EffectType::Eq,
+                                EffectParam::Enabled,
+                                if enabled { 1.0 } else { 0.0 },
+                            );
+                        }
+                    }
                 });
 
-                ui.separator();
-                ui.label("Signal: Input -> Chorus -> AutoPan -> Delay -> Reverb -> Output");
+                ui.add_enabled_ui(enabled, |ui| {
+                    self.draw_eq_band(
+                        ui,
+                        "Low",
+                        eq.low,
+                        20.0..=1_000.0,
+                        EffectParam::EqLowGain,
+                        EffectParam::EqLowFreq,
+                    );
+                    self.draw_eq_band(
+                        ui,
+                        "Mid",
+                        eq.mid,
+                        100.0..=10_000.0,
+                        EffectParam::EqMidGain,
+                        EffectParam::EqMidFreq,
+                    );
+                    self.draw_eq_band(
+                        ui,
+                        "High",
+                        eq.high,
+                        1_000.0..=20_000.0,
+                        EffectParam::EqHighGain,
+                        EffectParam::EqHighFreq,
+                    );
+
+                    let mut q = eq.mid.q;
+                    ui.horizontal(|ui| {
+                        ui.label("Mid Q:");
+                        if ui
+                            .add(egui::Slider::new(&mut q, 0.3..=10.0).logarithmic(true))
+                            .changed()
+                        {
+                            if let Ok(mut ctrl) = self.lock_controller() {
+                                ctrl.set_effect_param(EffectType::Eq, EffectParam::EqMidQ, q);
+                            }
+                        }
+                    });
+                });
             });
         });
     }
 
+    /// Gain and frequency sliders of one EQ band.
+    fn draw_eq_band(
+        &mut self,
+        ui: &mut egui::Ui,
+        name: &str,
+        band: EqBand,
+        freq_range: std::ops::RangeInclusive<f32>,
+        gain_param: EffectParam,
+        freq_param: EffectParam,
+    ) {
+        let mut gain_db = band.gain_db;
+        let mut freq_hz = band.freq_hz;
+        ui.horizontal(|ui| {
+            ui.label(format!("{name}:"));
+            if ui
+                .add(
+                    egui::Slider::new(&mut gain_db, -EQ_MAX_GAIN_DB..=EQ_MAX_GAIN_DB).suffix(" dB"),
+                )
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_effect_param(EffectType::Eq, gain_param, gain_db);
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Freq:");
+            if ui
+                .add(
+                    egui::Slider::new(&mut freq_hz, freq_range)
+                        .suffix(" Hz")
+                        .logarithmic(true),
+                )
+                .changed()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_effect_param(EffectType::Eq, freq_param, freq_hz);
+                }
+            }
+        });
+    }
+
     fn draw_chorus_effect(&mut self, ui: &mut egui::Ui) {
         ui.group(|ui| {
             ui.vertical(|ui| {
@@ -1237,7 +4133,7 @@ impl Dx7App {
                         ui.label("Depth:");
                         if ui
                             .add(
-                                egui::Slider::new(&mut depth, 0.0..=10.0)
+                                egui::Slider::new(&mut depth, 0.0..=CHORUS_MAX_DEPTH_MS)
                                     .suffix(" ms")
                                     .show_value(true),
                             )
@@ -1251,33 +4147,231 @@ impl Dx7App {
                                 );
                             }
                         }
-                    });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Mix:");
+                        if ui
+                            .add(egui::Slider::new(&mut mix, 0.0..=1.0).show_value(true))
+                            .changed()
+                        {
+                            if let Ok(mut ctrl) = self.lock_controller() {
+                                ctrl.set_effect_param(EffectType::Chorus, EffectParam::Mix, mix);
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Feedback:");
+                        if ui
+                            .add(egui::Slider::new(&mut feedback, 0.0..=0.7).show_value(true))
+                            .changed()
+                        {
+                            if let Ok(mut ctrl) = self.lock_controller() {
+                                ctrl.set_effect_param(
+                                    EffectType::Chorus,
+                                    EffectParam::ChorusFeedback,
+                                    feedback,
+                                );
+                            }
+                        }
+                    });
+                });
+            });
+        });
+    }
+
+    fn draw_filter_effect(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new("FILTER").strong())
+                    .on_hover_text("Resonant low-pass with envelope follower and LFO (auto-wah)");
+
+                let filter = self.snapshot.filter;
+                let mut enabled = filter.enabled;
+
+                ui.add_enabled_ui(self.snapshot.extensions, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Enable:");
+                        if ui.checkbox(&mut enabled, "").changed() {
+                            if let Ok(mut ctrl) = self.lock_controller() {
+                                ctrl.set_effect_param(
+                                    EffectType::Filter,
+                                    EffectParam::Enabled,
+                                    if enabled { 1.0 } else { 0.0 },
+                                );
+                            }
+                        }
+                    });
+
+                    ui.add_enabled_ui(enabled, |ui| {
+                        let sliders = [
+                            (
+                                "Cutoff:",
+                                filter.cutoff_hz,
+                                FILTER_MIN_HZ..=FILTER_MAX_HZ,
+                                " Hz",
+                                EffectParam::FilterCutoff,
+                            ),
+                            (
+                                "Reso:",
+                                filter.resonance,
+                                0.0..=1.0,
+                                "",
+                                EffectParam::FilterResonance,
+                            ),
+                            (
+                                "Env:",
+                                filter.env_amount,
+                                -1.0..=1.0,
+                                "",
+                                EffectParam::FilterEnvAmount,
+                            ),
+                            (
+                                "LFO Rate:",
+                                filter.lfo_rate,
+                                0.05..=10.0,
+                                " Hz",
+                                EffectParam::FilterLfoRate,
+                            ),
+                            (
+                                "LFO Depth:",
+                                filter.lfo_depth,
+                                0.0..=1.0,
+                                "",
+                                EffectParam::FilterLfoDepth,
+                            ),
+                            ("Mix:", filter.mix, 0.0..=1.0, "", EffectParam::Mix),
+                        ];
+                        for (label, mut value, range, suffix, param) in sliders {
+                            let logarithmic = matches!(param, EffectParam::FilterCutoff);
+                            ui.horizontal(|ui| {
+                                ui.label(label);
+                                if ui
+                                    .add(
+                                        egui::Slider::new(&mut value, range)
+                                            .suffix(suffix)
+                                            .logarithmic(logarithmic),
+                                    )
+                                    .changed()
+                                {
+                                    if let Ok(mut ctrl) = self.lock_controller() {
+                                        ctrl.set_effect_param(EffectType::Filter, param, value);
+                                    }
+                                }
+                            });
+                        }
+                    });
+                });
+                if !self.snapshot.extensions {
+                    ui.label(egui::RichText::new("Turn on EXTENSIONS to use").weak());
+                }
+            });
+        });
+    }
+
+    fn draw_phaser_effect(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new("PHASER").strong());
+
+                let phaser = self.snapshot.phaser;
+                let mut enabled = phaser.enabled;
+
+                ui.horizontal(|ui| {
+                    ui.label("Enable:");
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        if let Ok(mut ctrl) = self.lock_controller() {
+                            ctrl.set_effect_param(
+                                EffectType::Phaser,
+                                EffectParam::Enabled,
+                                if enabled { 1.0 } else { 0.0 },
+                            );
+                        }
+                    }
+                });
+
+                ui.add_enabled_ui(enabled, |ui| {
                     ui.horizontal(|ui| {
-                        ui.label("Mix:");
-                        if ui
-                            .add(egui::Slider::new(&mut mix, 0.0..=1.0).show_value(true))
-                            .changed()
-                        {
-                            if let Ok(mut ctrl) = self.lock_controller() {
-                                ctrl.set_effect_param(EffectType::Chorus, EffectParam::Mix, mix);
+                        ui.label("Stages:");
+                        for stages in PHASER_STAGES {
+                            if ui
+                                .selectable_label(
+                                    phaser.stages as usize == stages,
+                                    stages.to_string(),
+                                )
+                                .clicked()
+                            {
+                                if let Ok(mut ctrl) = self.lock_controller() {
+                                    ctrl.set_effect_param(
+                                        EffectType::Phaser,
+                                        EffectParam::PhaserStages,
+                                        stages as f32,
+                                    );
+                                }
                             }
                         }
                     });
-                    ui.horizontal(|ui| {
-                        ui.label("Feedback:");
-                        if ui
-                            .add(egui::Slider::new(&mut feedback, 0.0..=0.7).show_value(true))
-                            .changed()
-                        {
-                            if let Ok(mut ctrl) = self.lock_controller() {
-                                ctrl.set_effect_param(
-                                    EffectType::Chorus,
-                                    EffectParam::ChorusFeedback,
-                                    feedback,
-                                );
+                    let sliders = [
+                        (
+                            "Rate:",
+                            phaser.rate,
+                            0.05..=5.0,
+                            " Hz",
+                            EffectParam::PhaserRate,
+                        ),
+                        (
+                            "Depth:",
+                            phaser.depth,
+                            0.0..=1.0,
+                            "",
+                            EffectParam::PhaserDepth,
+                        ),
+                        (
+                            "Feedback:",
+                            phaser.feedback,
+                            0.0..=PHASER_MAX_FEEDBACK,
+                            "",
+                            EffectParam::PhaserFeedback,
+                        ),
+                        ("Mix:", phaser.mix, 0.0..=1.0, "", EffectParam::Mix),
+                    ];
+                    for (label, mut value, range, suffix, param) in sliders {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            if ui
+                                .add(egui::Slider::new(&mut value, range).suffix(suffix))
+                                .changed()
+                            {
+                                if let Ok(mut ctrl) = self.lock_controller() {
+                                    ctrl.set_effect_param(EffectType::Phaser, param, value);
+                                }
+                            }
+                        });
+                    }
+                    if ui
+                        .button("VINTAGE EP")
+                        .on_hover_text(
+                            "Slow four-stage sweep, as on so many DX7 electric piano \
+                             records; try it with E.PIANO 1",
+                        )
+                        .clicked()
+                    {
+                        if let Ok(mut ctrl) = self.lock_controller() {
+                            let v = PHASER_VINTAGE_EP;
+                            ctrl.set_effect_param(
+                                EffectType::Phaser,
+                                EffectParam::PhaserStages,
+                                v.stages as f32,
+                            );
+                            for (param, value) in [
+                                (EffectParam::PhaserRate, v.rate),
+                                (EffectParam::PhaserDepth, v.depth),
+                                (EffectParam::PhaserFeedback, v.feedback),
+                                (EffectParam::Mix, v.mix),
+                            ] {
+                                ctrl.set_effect_param(EffectType::Phaser, param, value);
                             }
                         }
-                    });
+                    }
                 });
             });
         });
@@ -1357,6 +4451,7 @@ impl Dx7App {
                 let mut feedback = delay.feedback;
                 let mut mix = delay.mix;
                 let mut ping_pong = delay.ping_pong;
+                let (high_cut, low_cut) = (delay.high_cut_hz, delay.low_cut_hz);
 
                 ui.horizontal(|ui| {
                     ui.label("Enable:");
@@ -1372,30 +4467,46 @@ impl Dx7App {
                 });
 
                 ui.add_enabled_ui(enabled, |ui| {
+                    if delay.sync.is_some() {
+                        ui.label(format!("Time: {:.0} ms", delay.effective_time_ms));
+                    } else {
+                        ui.horizontal(|ui| {
+                            ui.label("Time:");
+                            if ui
+                                .add(
+                                    egui::Slider::new(&mut time_ms, 0.0..=1000.0)
+                                        .suffix(" ms")
+                                        .show_value(true),
+                                )
+                                .changed()
+                            {
+                                if let Ok(mut ctrl) = self.lock_controller() {
+                                    ctrl.set_effect_param(
+                                        EffectType::Delay,
+                                        EffectParam::DelayTime,
+                                        time_ms,
+                                    );
+                                }
+                            }
+                        });
+                    }
+                    self.tempo_sync_control(
+                        ui,
+                        "delay_sync",
+                        delay.sync,
+                        "Delay time follows a note division of the tempo",
+                        |ctrl, v| {
+                            ctrl.set_effect_param(EffectType::Delay, EffectParam::DelaySync, v)
+                        },
+                    );
                     ui.horizontal(|ui| {
-                        ui.label("Time:");
+                        ui.label("Feedback:");
                         if ui
                             .add(
-                                egui::Slider::new(&mut time_ms, 0.0..=1000.0)
-                                    .suffix(" ms")
+                                egui::Slider::new(&mut feedback, 0.0..=DELAY_MAX_FEEDBACK)
                                     .show_value(true),
                             )
                             .changed()
-                        {
-                            if let Ok(mut ctrl) = self.lock_controller() {
-                                ctrl.set_effect_param(
-                                    EffectType::Delay,
-                                    EffectParam::DelayTime,
-                                    time_ms,
-                                );
-                            }
-                        }
-                    });
-                    ui.horizontal(|ui| {
-                        ui.label("Feedback:");
-                        if ui
-                            .add(egui::Slider::new(&mut feedback, 0.0..=0.9).show_value(true))
-                            .changed()
                         {
                             if let Ok(mut ctrl) = self.lock_controller() {
                                 ctrl.set_effect_param(
@@ -1429,6 +4540,31 @@ impl Dx7App {
                             }
                         }
                     });
+                    // Feedback-path filters; the slider ends switch them off.
+                    for (label, value, param) in [
+                        ("High Cut:", high_cut, EffectParam::DelayHighCut),
+                        ("Low Cut:", low_cut, EffectParam::DelayLowCut),
+                    ] {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            let mut hz = value;
+                            if ui
+                                .add(
+                                    egui::Slider::new(
+                                        &mut hz,
+                                        DELAY_LOW_CUT_MIN_HZ..=DELAY_HIGH_CUT_MAX_HZ,
+                                    )
+                                    .logarithmic(true)
+                                    .suffix(" Hz"),
+                                )
+                                .changed()
+                            {
+                                if let Ok(mut ctrl) = self.lock_controller() {
+                                    ctrl.set_effect_param(EffectType::Delay, param, hz);
+                                }
+                            }
+                        });
+                    }
                 });
             });
         });
@@ -1445,6 +4581,8 @@ impl Dx7App {
                 let mut damping = reverb.damping;
                 let mut mix = reverb.mix;
                 let mut width = reverb.width;
+                let mut pre_delay = reverb.pre_delay_ms;
+                let reverb_type = reverb.reverb_type;
 
                 ui.horizontal(|ui| {
                     ui.label("Enable:");
@@ -1460,6 +4598,38 @@ impl Dx7App {
                 });
 
                 ui.add_enabled_ui(enabled, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Type:");
+                        for t in ReverbType::all() {
+                            if ui.selectable_label(reverb_type == t, t.name()).clicked() {
+                                if let Ok(mut ctrl) = self.lock_controller() {
+                                    ctrl.set_effect_param(
+                                        EffectType::Reverb,
+                                        EffectParam::ReverbType,
+                                        t.to_code() as f32,
+                                    );
+                                }
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Pre-Delay:");
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut pre_delay, 0.0..=REVERB_MAX_PRE_DELAY_MS)
+                                    .suffix(" ms"),
+                            )
+                            .changed()
+                        {
+                            if let Ok(mut ctrl) = self.lock_controller() {
+                                ctrl.set_effect_param(
+                                    EffectType::Reverb,
+                                    EffectParam::ReverbPreDelay,
+                                    pre_delay,
+                                );
+                            }
+                        }
+                    });
                     ui.horizontal(|ui| {
                         ui.label("Room Size:");
                         if ui
@@ -1521,9 +4691,31 @@ impl Dx7App {
         });
     }
 
+    /// Routing the voices run: the DX7 algorithm, or the matrix in custom
+    /// mode.
+    fn current_algorithm_info(&self) -> algorithms::AlgorithmInfo {
+        if self.snapshot.custom_algorithm {
+            self.snapshot.algorithm_matrix.info()
+        } else {
+            algorithms::get_algorithm_info(self.snapshot.algorithm)
+        }
+    }
+
     fn draw_algorithm_diagram_compact(&mut self, ui: &mut egui::Ui) {
         let current_alg = self.snapshot.algorithm;
-        let alg_info = algorithms::get_algorithm_info(current_alg);
+        let custom = self.snapshot.custom_algorithm;
+        let alg_info = self.current_algorithm_info();
+        // Custom routings can give several operators feedback.
+        let feedback_ops: Vec<usize> = if custom {
+            (0..6)
+                .filter(|&op| self.snapshot.algorithm_matrix.feedback(op) > 0.0)
+                .collect()
+        } else {
+            (alg_info.feedback_op as usize)
+                .checked_sub(1)
+                .into_iter()
+                .collect()
+        };
         let enabled_states = [
             self.snapshot.operators[0].enabled,
             self.snapshot.operators[1].enabled,
@@ -1547,26 +4739,24 @@ impl Dx7App {
                     // Compact header with algorithm selector
                     ui.horizontal(|ui| {
                         ui.label(egui::RichText::new("ALG").strong());
-                        if ui.small_button("<").clicked() && current_alg > 1 {
-                            if let Ok(mut ctrl) = self.lock_controller() {
-                                ctrl.set_algorithm(current_alg - 1);
-                            }
+                        if ui.small_button("<").clicked() {
+                            self.step_algorithm(-1);
                         }
                         ui.label(egui::RichText::new(format!("{:02}", current_alg)).strong());
-                        if ui.small_button(">").clicked() && current_alg < 32 {
-                            if let Ok(mut ctrl) = self.lock_controller() {
-                                ctrl.set_algorithm(current_alg + 1);
-                            }
+                        if ui.small_button(">").clicked() {
+                            self.step_algorithm(1);
                         }
-                        ui.label(
-                            egui::RichText::new(algorithms::get_algorithm_name(current_alg))
-                                .size(11.0),
-                        );
+                        let name = if custom {
+                            "Custom"
+                        } else {
+                            algorithms::get_algorithm_name(current_alg)
+                        };
+                        ui.label(egui::RichText::new(name).size(11.0));
                     });
 
                     let (response, painter) = ui.allocate_painter(
                         egui::vec2(ui.available_width(), 130.0),
-                        egui::Sense::hover(),
+                        egui::Sense::click(),
                     );
                     let rect = response.rect;
 
@@ -1580,6 +4770,26 @@ impl Dx7App {
                     let positions =
                         self.calculate_operator_positions_compact(&alg_info, layout_rect);
 
+                    // Click an operator to edit it, hover for its settings,
+                    // scroll the wheel to step through the algorithms.
+                    let op_radius = 11.0;
+                    let hovered = response
+                        .hover_pos()
+                        .and_then(|pos| operator_at(&positions, pos, op_radius));
+                    if let Some(op) = hovered {
+                        ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+                        if response.clicked() {
+                            self.selected_operator = op;
+                        }
+                        response
+                            .clone()
+                            .on_hover_text_at_pointer(self.operator_tooltip(op, &alg_info));
+                    }
+                    if response.hovered() {
+                        let delta = ui.input(|i| i.raw_scroll_delta.y);
+                        self.scroll_algorithm(delta);
+                    }
+
                     // Modulation connections
                     let connection_color = egui::Color32::from_rgb(100, 100, 100);
                     for (from, to) in &alg_info.connections {
@@ -1591,9 +4801,9 @@ impl Dx7App {
                         );
                     }
 
-                    // Feedback loop indicator
-                    if alg_info.feedback_op > 0 {
-                        let fb_pos = positions[(alg_info.feedback_op - 1) as usize];
+                    // Feedback loop indicators
+                    for &op in &feedback_ops {
+                        let fb_pos = positions[op];
                         let loop_center = fb_pos + egui::vec2(14.0, -8.0);
                         painter.circle_stroke(
                             loop_center,
@@ -1603,7 +4813,6 @@ impl Dx7App {
                     }
 
                     // Operators
-                    let op_radius = 11.0;
                     for (i, &pos) in positions.iter().enumerate() {
                         let op_num = (i + 1) as u8;
                         let is_carrier = alg_info.carriers.contains(&op_num);
@@ -1706,17 +4915,170 @@ impl Dx7App {
                         ui.add_space(6.0);
                         ui.label(egui::RichText::new("●").color(modulator_color).size(12.0));
                         ui.label(egui::RichText::new("Modulator").size(10.0));
-                        if alg_info.feedback_op > 0 {
+                        if !feedback_ops.is_empty() {
                             ui.add_space(6.0);
                             ui.label(egui::RichText::new("↻").color(feedback_color).size(12.0));
                             ui.label(egui::RichText::new("Feedback").size(10.0));
                         }
                     });
+
+                    ui.add_space(4.0);
+                    self.draw_custom_algorithm_editor(ui);
                 });
             });
         });
     }
 
+    /// Hover text for an operator in the algorithm diagram.
+    fn operator_tooltip(&self, op: usize, alg_info: &algorithms::AlgorithmInfo) -> String {
+        let snap = &self.snapshot.operators[op];
+        let role = if alg_info.carriers.contains(&(op as u8 + 1)) {
+            "carrier"
+        } else {
+            "modulator"
+        };
+        let pitch = if snap.fixed_frequency {
+            format!("Fixed {:.1} Hz", snap.fixed_freq_hz)
+        } else {
+            format!(
+                "Ratio {:.2} ({})",
+                snap.frequency_ratio,
+                dx7_frequency::ratio_interval(snap.frequency_ratio)
+            )
+        };
+        let state = if snap.enabled { "" } else { " (off)" };
+        format!(
+            "OP{} {}{}\n{}\nLevel {:.0}\nClick to edit",
+            op + 1,
+            role,
+            state,
+            pitch,
+            snap.output_level
+        )
+    }
+
+    /// Step the algorithm by whole mouse-wheel notches accumulated in
+    /// `algorithm_scroll`; wheel down moves to the next algorithm.
+    fn scroll_algorithm(&mut self, delta: f32) {
+        if delta == 0.0 {
+            return;
+        }
+        self.algorithm_scroll += delta;
+        let notches = (self.algorithm_scroll / ALGORITHM_SCROLL_NOTCH).trunc();
+        self.algorithm_scroll -= notches * ALGORITHM_SCROLL_NOTCH;
+        self.step_algorithm(-(notches as i32));
+    }
+
+    /// Move `steps` algorithms up or down, stopping at 1 and 32. Also
+    /// points data entry at the algorithm.
+    fn step_algorithm(&mut self, steps: i32) {
+        self.lcd.follow(DataEntryParam::Algorithm);
+        let current = self.snapshot.algorithm;
+        let next = (current as i32 + steps).clamp(1, 32) as u8;
+        if next != current {
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.set_algorithm(next);
+            }
+        }
+    }
+
+    /// CUSTOM switch and routing matrix: one row per modulating operator,
+    /// a column per target, plus the OUT (carrier) toggle and the row's
+    /// self-feedback amount. Upward links (e.g. 2 → 5) hear the source one
+    /// sample late.
+    fn draw_custom_algorithm_editor(&self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("CUSTOM ALGORITHM")
+            .default_open(self.snapshot.custom_algorithm)
+            .show(ui, |ui| {
+                let current = self.snapshot.algorithm_matrix;
+                let mut matrix = current;
+                let mut custom = self.snapshot.custom_algorithm;
+                let current_alg = self.snapshot.algorithm;
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .checkbox(&mut custom, "CUSTOM")
+                        .on_hover_text("Run the matrix below instead of the DX7 algorithm")
+                        .changed()
+                    {
+                        if let Ok(mut ctrl) = self.lock_controller() {
+                            ctrl.set_custom_algorithm(custom);
+                        }
+                    }
+                    if ui
+                        .button(format!("FROM ALG {:02}", current_alg))
+                        .on_hover_text("Start the matrix from the selected DX7 algorithm")
+                        .clicked()
+                    {
+                        let fb_op = algorithms::get_algorithm_info(current_alg).feedback_op;
+                        let feedback = (fb_op as usize)
+                            .checked_sub(1)
+                            .map_or(0.0, |op| self.snapshot.operators[op].feedback);
+                        matrix = AlgorithmMatrix::from_algorithm(current_alg, feedback);
+                    }
+                });
+
+                egui::Grid::new("custom_algorithm_grid")
+                    .num_columns(9)
+                    .spacing([4.0, 2.0])
+                    .show(ui, |ui| {
+                        ui.label(egui::RichText::new("MOD").size(10.0));
+                        for to in 0..6 {
+                            ui.label(egui::RichText::new(format!("→{}", to + 1)).size(10.0));
+                        }
+                        ui.label(egui::RichText::new("OUT").size(10.0));
+                        ui.label(egui::RichText::new("FB").size(10.0));
+                        ui.end_row();
+
+                        for from in 0..6 {
+                            ui.label(format!("OP{}", from + 1));
+                            for to in 0..6 {
+                                if from == to {
+                                    ui.label("·");
+                                    continue;
+                                }
+                                let on = matrix.connected(from, to);
+                                if ui
+                                    .selectable_label(on, if on { "●" } else { "○" })
+                                    .clicked()
+                                {
+                                    matrix.set_connection(from, to, !on);
+                                }
+                            }
+                            let carrier = matrix.is_carrier(from);
+                            if ui
+                                .selectable_label(carrier, if carrier { "●" } else { "○" })
+                                .clicked()
+                            {
+                                matrix.set_carrier(from, !carrier);
+                            }
+                            let mut fb = matrix.feedback(from);
+                            if ui
+                                .add(
+                                    egui::DragValue::new(&mut fb)
+                                        .range(0.0..=MAX_FEEDBACK)
+                                        .speed(0.05)
+                                        .fixed_decimals(1),
+                                )
+                                .changed()
+                            {
+                                matrix.set_feedback(from, fb);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if matrix.carrier_mask() == 0 {
+                    ui.colored_label(egui::Color32::from_rgb(220, 160, 60), "No carrier: silent");
+                }
+
+                if matrix != current {
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.set_algorithm_matrix(matrix);
+                    }
+                }
+            });
+    }
+
     /// Lay out the 6 operators as a Dexed-style algorithm diagram: each
     /// independent modulation chain becomes its own vertical column, with
     /// carriers at the bottom and modulators stacked directly above their
@@ -1856,10 +5218,10 @@ impl Dx7App {
     /// Operator selector strip: a row of 6 mini-panels distributed evenly
     /// across the full width. Each cell shows OP number, role (Carrier /
     /// Modulator / Feedback), live output level bar, and acts as a button
-    /// to select that operator.
+    /// to select that operator. Dragging or clicking a level bar sets that
+    /// operator's output level without selecting it.
     fn draw_operator_selector_strip(&mut self, ui: &mut egui::Ui) {
-        let current_alg = self.snapshot.algorithm;
-        let alg_info = algorithms::get_algorithm_info(current_alg);
+        let alg_info = self.current_algorithm_info();
 
         ui.group(|ui| {
             ui.label(egui::RichText::new("SELECT OPERATOR").size(10.0));
@@ -1871,7 +5233,7 @@ impl Dx7App {
                     let has_feedback = alg_info.feedback_op == op_num;
 
                     let enabled = self.snapshot.operators[op_idx].enabled;
-                    let level = self.snapshot.operators[op_idx].output_level;
+                    let mut level = self.snapshot.operators[op_idx].output_level;
 
                     let base_color = if !enabled {
                         egui::Color32::from_rgb(80, 80, 80)
@@ -1919,9 +5281,32 @@ impl Dx7App {
                             // into a long strip.
                             let bar_width = ui.available_width().min(90.0);
                             let bar_height = 10.0;
-                            let (bar_rect, _) = ui.allocate_exact_size(
+                            let (bar_rect, mut bar) = ui.allocate_exact_size(
                                 egui::vec2(bar_width, bar_height),
-                                egui::Sense::hover(),
+                                egui::Sense::click_and_drag(),
+                            );
+                            if bar.clicked() || bar.dragged() {
+                                if let Some(pos) = bar.interact_pointer_pos() {
+                                    let target = level_at(bar_rect, pos.x);
+                                    if target != level.round() {
+                                        level = target;
+                                        bar.mark_changed();
+                                        if let Ok(mut ctrl) = self.lock_controller() {
+                                            ctrl.set_operator_param(
+                                                op_idx as u8,
+                                                OperatorParam::Level,
+                                                level,
+                                            );
+                                        }
+                                    }
+                                }
+                            }
+                            let bar = bar
+                                .on_hover_cursor(egui::CursorIcon::ResizeHorizontal)
+                                .on_hover_text(format!("Drag to set OP{} output level", op_num));
+                            self.track(
+                                DataEntryParam::Operator(op_idx as u8, OperatorField::Level),
+                                bar,
                             );
                             ui.painter().rect_filled(
                                 bar_rect,
@@ -1944,18 +5329,134 @@ impl Dx7App {
                             );
 
                             ui.label(egui::RichText::new(format!("{:.0}", level)).size(10.0));
+
+                            // Live envelope of the latest note.
+                            let live = self.snapshot.operators[op_idx].recent_level.clamp(0.0, 1.0);
+                            let (meter_rect, _) = ui.allocate_exact_size(
+                                egui::vec2(bar_width, 4.0),
+                                egui::Sense::hover(),
+                            );
+                            ui.painter().rect_filled(
+                                meter_rect,
+                                1.0,
+                                egui::Color32::from_rgb(40, 40, 40),
+                            );
+                            ui.painter().rect_filled(
+                                egui::Rect::from_min_size(
+                                    meter_rect.min,
+                                    egui::vec2(live * bar_width, meter_rect.height()),
+                                ),
+                                1.0,
+                                egui::Color32::from_rgb(90, 220, 110),
+                            );
+
+                            // Output of the operator itself, across voices.
+                            let peak = self.snapshot.operators[op_idx].output_peak.clamp(0.0, 1.0);
+                            let (peak_rect, _) = ui.allocate_exact_size(
+                                egui::vec2(bar_width, 4.0),
+                                egui::Sense::hover(),
+                            );
+                            ui.painter().rect_filled(
+                                peak_rect,
+                                1.0,
+                                egui::Color32::from_rgb(40, 40, 40),
+                            );
+                            ui.painter().rect_filled(
+                                egui::Rect::from_min_size(
+                                    peak_rect.min,
+                                    egui::vec2(peak * bar_width, peak_rect.height()),
+                                ),
+                                1.0,
+                                egui::Color32::from_rgb(255, 180, 0),
+                            );
+
+                            let solo_mask = self.snapshot.operator_solo;
+                            let soloed = solo_mask & (1 << op_idx) != 0;
+                            if ui
+                                .selectable_label(soloed, egui::RichText::new("SOLO").size(9.0))
+                                .on_hover_text(
+                                    "Hear only the soloed operators, each as a carrier \
+                                     (a modulator keeps its own modulators)",
+                                )
+                                .clicked()
+                            {
+                                if let Ok(mut ctrl) = self.lock_controller() {
+                                    ctrl.set_operator_solo(solo_mask ^ (1 << op_idx));
+                                }
+                            }
                         });
                     });
                 }
             });
         });
+
+        // Keep the envelope and output meters moving while a note sounds.
+        if self
+            .snapshot
+            .operators
+            .iter()
+            .any(|op| op.recent_level > 0.0 || op.output_peak > 1e-3)
+        {
+            ui.ctx().request_repaint_after(self.frame_interval());
+        }
     }
 
     /// Full operator panel with all parameters and envelope
+    /// COPY OP / COPY EG / PASTE buttons of the operator header (laid out
+    /// right to left).
+    fn draw_operator_clipboard(&mut self, ui: &mut egui::Ui, op_idx: usize) {
+        let paste_hint = match &self.operator_clipboard {
+            Some(clip) => format!(
+                "Paste the {} of OP{} onto OP{}",
+                if clip.envelope_only {
+                    "envelope"
+                } else {
+                    "settings"
+                },
+                clip.source + 1,
+                op_idx + 1
+            ),
+            None => "Copy an operator first".to_string(),
+        };
+        let paste = ui
+            .add_enabled(
+                self.operator_clipboard.is_some(),
+                egui::Button::new("PASTE"),
+            )
+            .on_hover_text(paste_hint)
+            .on_disabled_hover_text("Copy an operator first");
+        if paste.clicked() {
+            if let Some(clip) = &self.operator_clipboard {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.paste_operator(op_idx as u8, &clip.params, clip.envelope_only);
+                }
+            }
+        }
+        for (label, envelope_only, hint) in [
+            (
+                "COPY EG",
+                true,
+                "Copy this operator's envelope rates and levels",
+            ),
+            (
+                "COPY OP",
+                false,
+                "Copy every setting of this operator except ON",
+            ),
+        ] {
+            if ui.button(label).on_hover_text(hint).clicked() {
+                self.operator_clipboard = Some(OperatorClipboard {
+                    source: op_idx,
+                    params: self.snapshot.operators[op_idx],
+                    envelope_only,
+                });
+            }
+        }
+    }
+
     fn draw_operator_full_panel(&mut self, ui: &mut egui::Ui) {
         let op_idx = self.selected_operator;
-        let current_alg = self.snapshot.algorithm;
-        let alg_info = algorithms::get_algorithm_info(current_alg);
+        let alg_info = self.current_algorithm_info();
         let op_num = (op_idx + 1) as u8;
         let is_carrier = alg_info.carriers.contains(&op_num);
         let has_feedback = alg_info.feedback_op == op_num;
@@ -1964,6 +5465,9 @@ impl Dx7App {
         let op_snap = &self.snapshot.operators[op_idx];
         let mut enabled = op_snap.enabled;
         let mut freq_ratio = op_snap.frequency_ratio;
+        let mut coarse = op_snap.coarse as f32;
+        let mut fine = op_snap.fine as f32;
+        let mut extended_ratio = op_snap.extended_ratio;
         let mut output_level = op_snap.output_level;
         let mut detune = op_snap.detune;
         let mut feedback = op_snap.feedback;
@@ -1975,7 +5479,17 @@ impl Dx7App {
         let mut r_curve = op_snap.key_scale_right_curve;
         let mut key_scale_rt = op_snap.key_scale_rate;
         let mut am_sens = op_snap.am_sensitivity as f32;
+        let mut pm_own = op_snap.pm_sensitivity.is_some();
+        let mut pm_sens = op_snap
+            .pm_sensitivity
+            .unwrap_or(self.snapshot.pitch_mod_sensitivity) as f32;
+        let mut on_lfo2 = op_snap.lfo2;
         let mut osc_sync = op_snap.oscillator_key_sync;
+        let mut sub_bus = op_snap.sub_bus;
+        let mut expression = op_snap.expression;
+        let mut waveform = op_snap.waveform;
+        let mut phase_offset = op_snap.phase_offset;
+        let live_phase = (op_snap.recent_level > 0.0).then_some(op_snap.recent_phase);
         let mut fixed_freq = op_snap.fixed_frequency;
         let mut fixed_hz = op_snap.fixed_freq_hz;
         let mut rate1 = op_snap.rate1;
@@ -2006,6 +5520,22 @@ impl Dx7App {
                             );
                         }
                     }
+                    ui.separator();
+                    let mut voice_sync = self.snapshot.osc_key_sync;
+                    if ui
+                        .checkbox(&mut voice_sync, "OSC KEY SYNC (all ops)")
+                        .on_hover_text(
+                            "ON: every operator restarts its phase on note-on.\n\
+                             OFF: phases free-run, for smoother mono legato.",
+                        )
+                        .changed()
+                    {
+                        if let Ok(mut ctrl) = self.lock_controller() {
+                            ctrl.set_osc_key_sync(voice_sync);
+                        }
+                    }
+                    ui.separator();
+                    self.draw_operator_clipboard(ui, op_idx);
                 });
             });
             ui.separator();
@@ -2019,36 +5549,132 @@ impl Dx7App {
                             .spacing([8.0, 4.0])
                             .show(ui, |ui| {
                                 ui.label("Ratio:");
-                                if ui
-                                    .add(
-                                        egui::Slider::new(&mut freq_ratio, 0.5..=31.0)
-                                            .step_by(1.0)
-                                            .custom_formatter(|n, _| {
-                                                format!(
-                                                    "{:.2}",
-                                                    crate::dx7_frequency::quantize_frequency_ratio(
-                                                        n as f32,
-                                                    )
-                                                )
-                                            }),
-                                    )
-                                    .changed()
-                                {
-                                    let q =
-                                        crate::dx7_frequency::quantize_frequency_ratio(freq_ratio);
+                                ui.horizontal(|ui| {
+                                    let ratio_changed = extended_ratio && {
+                                        let response = ui
+                                            .add(
+                                                egui::Slider::new(&mut freq_ratio, 0.5..=32.0)
+                                                    .step_by(0.001)
+                                                    .max_decimals(3),
+                                            )
+                                            .on_hover_text(
+                                                "Dragging snaps to 0.5 and whole ratios; \
+                                                 hold Alt to drag freely, or type a value.",
+                                            );
+                                        // Typed values are taken as they are.
+                                        if response.dragged() && !ui.input(|i| i.modifiers.alt) {
+                                            freq_ratio =
+                                                dx7_frequency::quantize_free_ratio(freq_ratio);
+                                        }
+                                        response.changed()
+                                    };
+                                    if !extended_ratio {
+                                        ui.monospace(format!("{freq_ratio:.2}"));
+                                    }
+                                    let ext_changed = ui
+                                        .checkbox(&mut extended_ratio, "EXT")
+                                        .on_hover_text(
+                                            "Free float ratio instead of DX7 COARSE / FINE. \
+                                             Turning it off snaps to the nearest setting.",
+                                        )
+                                        .changed();
                                     if let Ok(mut ctrl) = self.lock_controller() {
-                                        ctrl.set_operator_param(
-                                            op_idx as u8,
-                                            OperatorParam::Ratio,
-                                            q,
-                                        );
+                                        if ratio_changed {
+                                            ctrl.set_operator_param(
+                                                op_idx as u8,
+                                                OperatorParam::Ratio,
+                                                freq_ratio,
+                                            );
+                                        }
+                                        if ext_changed {
+                                            ctrl.set_operator_param(
+                                                op_idx as u8,
+                                                OperatorParam::ExtendedRatio,
+                                                if extended_ratio { 1.0 } else { 0.0 },
+                                            );
+                                        }
                                     }
-                                }
+                                });
                                 ui.end_row();
 
+                                if !fixed_freq {
+                                    ui.label("");
+                                    ui.label(
+                                        egui::RichText::new(self.ratio_readout(freq_ratio, detune))
+                                            .size(10.0)
+                                            .color(egui::Color32::GRAY),
+                                    );
+                                    ui.end_row();
+                                }
+
+                                if !extended_ratio {
+                                    ui.label("Coarse:");
+                                    if self
+                                        .track(
+                                            DataEntryParam::Operator(
+                                                op_idx as u8,
+                                                OperatorField::Coarse,
+                                            ),
+                                            ui.add(
+                                                egui::Slider::new(&mut coarse, 0.0..=31.0)
+                                                    .integer()
+                                                    .custom_formatter(|n, _| {
+                                                        if n < 0.5 {
+                                                            "0 (x0.5)".to_string()
+                                                        } else {
+                                                            format!("{n:.0}")
+                                                        }
+                                                    }),
+                                            ),
+                                        )
+                                        .changed()
+                                    {
+                                        if let Ok(mut ctrl) = self.lock_controller() {
+                                            ctrl.set_operator_param(
+                                                op_idx as u8,
+                                                OperatorParam::Coarse,
+                                                coarse,
+                                            );
+                                        }
+                                    }
+                                    ui.end_row();
+
+                                    ui.label("Fine:");
+                                    if self
+                                        .track(
+                                            DataEntryParam::Operator(
+                                                op_idx as u8,
+                                                OperatorField::Fine,
+                                            ),
+                                            ui.add(
+                                                egui::Slider::new(&mut fine, 0.0..=99.0).integer(),
+                                            ),
+                                        )
+                                        .changed()
+                                    {
+                                        if let Ok(mut ctrl) = self.lock_controller() {
+                                            ctrl.set_operator_param(
+                                                op_idx as u8,
+                                                OperatorParam::Fine,
+                                                fine,
+                                            );
+                                        }
+                                    }
+                                    ui.end_row();
+                                }
+
                                 ui.label("Level:");
-                                if ui
-                                    .add(egui::Slider::new(&mut output_level, 0.0..=99.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::Level,
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut output_level, 0.0..=99.0)
+                                                .integer(),
+                                        ),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2062,8 +5688,16 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("Detune:");
-                                if ui
-                                    .add(egui::Slider::new(&mut detune, -7.0..=7.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::Detune,
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut detune, -7.0..=7.0).integer(),
+                                        ),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2077,8 +5711,16 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("Vel Sens:");
-                                if ui
-                                    .add(egui::Slider::new(&mut vel_sens, 0.0..=7.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::VelocitySensitivity,
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut vel_sens, 0.0..=7.0).integer(),
+                                        ),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2093,8 +5735,17 @@ impl Dx7App {
 
                                 if has_feedback {
                                     ui.label("Feedback:");
-                                    if ui
-                                        .add(egui::Slider::new(&mut feedback, 0.0..=7.0).integer())
+                                    if self
+                                        .track(
+                                            DataEntryParam::Operator(
+                                                op_idx as u8,
+                                                OperatorField::Feedback,
+                                            ),
+                                            ui.add(
+                                                egui::Slider::new(&mut feedback, 0.0..=7.0)
+                                                    .integer(),
+                                            ),
+                                        )
                                         .changed()
                                     {
                                         if let Ok(mut ctrl) = self.lock_controller() {
@@ -2109,8 +5760,16 @@ impl Dx7App {
                                 }
 
                                 ui.label("AM Sens:");
-                                if ui
-                                    .add(egui::Slider::new(&mut am_sens, 0.0..=3.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::AmSensitivity,
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut am_sens, 0.0..=3.0).integer(),
+                                        ),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2123,6 +5782,49 @@ impl Dx7App {
                                 }
                                 ui.end_row();
 
+                                // Unticked, the operator follows the voice PMS.
+                                ui.label("PM Sens:");
+                                ui.horizontal(|ui| {
+                                    let own_changed = ui
+                                        .checkbox(&mut pm_own, "OWN")
+                                        .on_hover_text(
+                                            "Own LFO pitch depth instead of the voice PMS",
+                                        )
+                                        .changed();
+                                    let sens_changed = ui
+                                        .add_enabled(
+                                            pm_own,
+                                            egui::Slider::new(&mut pm_sens, 0.0..=7.0).integer(),
+                                        )
+                                        .changed();
+                                    if own_changed || sens_changed {
+                                        if let Ok(mut ctrl) = self.lock_controller() {
+                                            ctrl.set_operator_param(
+                                                op_idx as u8,
+                                                OperatorParam::PmSensitivity,
+                                                if pm_own { pm_sens } else { -1.0 },
+                                            );
+                                        }
+                                    }
+                                });
+                                ui.end_row();
+
+                                ui.label("LFO:");
+                                if ui
+                                    .checkbox(&mut on_lfo2, "LFO 2")
+                                    .on_hover_text("Follow the second LFO (when it is on)")
+                                    .changed()
+                                {
+                                    if let Ok(mut ctrl) = self.lock_controller() {
+                                        ctrl.set_operator_param(
+                                            op_idx as u8,
+                                            OperatorParam::Lfo2,
+                                            if on_lfo2 { 1.0 } else { 0.0 },
+                                        );
+                                    }
+                                }
+                                ui.end_row();
+
                                 ui.label("Key Sync:");
                                 if ui.checkbox(&mut osc_sync, "ON").changed() {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2135,6 +5837,102 @@ impl Dx7App {
                                 }
                                 ui.end_row();
 
+                                ui.label("Wave:");
+                                let prev_waveform = waveform;
+                                egui::ComboBox::from_id_source(("op_waveform", op_idx))
+                                    .selected_text(waveform.name())
+                                    .width(90.0)
+                                    .show_ui(ui, |ui| {
+                                        for w in OperatorWaveform::ALL {
+                                            ui.selectable_value(&mut waveform, w, w.name());
+                                        }
+                                    })
+                                    .response
+                                    .on_hover_text("TX81Z oscillator shape; W1 is the DX7 sine");
+                                if waveform != prev_waveform {
+                                    if let Ok(mut ctrl) = self.lock_controller() {
+                                        ctrl.set_operator_param(
+                                            op_idx as u8,
+                                            OperatorParam::Waveform,
+                                            waveform.to_code() as f32,
+                                        );
+                                    }
+                                }
+                                ui.end_row();
+
+                                ui.label("Phase:");
+                                ui.horizontal(|ui| {
+                                    let changed = ui
+                                        .add_enabled(
+                                            osc_sync,
+                                            egui::Slider::new(&mut phase_offset, 0.0..=360.0)
+                                                .integer()
+                                                .suffix("°"),
+                                        )
+                                        .on_hover_text(
+                                            "Start phase of each note (needs Key Sync). \
+                                             Sets the blend of same-ratio carriers, \
+                                             e.g. on algorithm 32.",
+                                        )
+                                        .on_disabled_hover_text("Needs Key Sync")
+                                        .changed();
+                                    if changed {
+                                        if let Ok(mut ctrl) = self.lock_controller() {
+                                            ctrl.set_operator_param(
+                                                op_idx as u8,
+                                                OperatorParam::PhaseOffset,
+                                                phase_offset,
+                                            );
+                                        }
+                                    }
+                                    if let Some(phase) = live_phase {
+                                        ui.weak(format!("now {:.0}°", phase)).on_hover_text(
+                                            "Oscillator phase in the latest note. Only \
+                                                 the difference between operators at the same \
+                                                 frequency holds still.",
+                                        );
+                                    }
+                                });
+                                ui.end_row();
+
+                                ui.label("Sub Bus:");
+                                if ui
+                                    .checkbox(&mut sub_bus, "ON")
+                                    .on_hover_text(
+                                        "Send this operator to the SUB bus (EFFECTS page) \
+                                         instead of the main carrier sum.",
+                                    )
+                                    .changed()
+                                {
+                                    if let Ok(mut ctrl) = self.lock_controller() {
+                                        ctrl.set_operator_param(
+                                            op_idx as u8,
+                                            OperatorParam::SubBus,
+                                            if sub_bus { 1.0 } else { 0.0 },
+                                        );
+                                    }
+                                }
+                                ui.end_row();
+
+                                ui.label("Expression:");
+                                if ui
+                                    .checkbox(&mut expression, "ON")
+                                    .on_hover_text(
+                                        "Scale this operator's level by the expression \
+                                         input (breath or CC11, MIDI page).",
+                                    )
+                                    .changed()
+                                {
+                                    if let Ok(mut ctrl) = self.lock_controller() {
+                                        ctrl.set_operator_param(
+                                            op_idx as u8,
+                                            OperatorParam::Expression,
+                                            if expression { 1.0 } else { 0.0 },
+                                        );
+                                    }
+                                }
+                                ui.end_row();
+
                                 ui.label("Fixed:");
                                 if ui.checkbox(&mut fixed_freq, "Hz").changed() {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2177,11 +5975,17 @@ impl Dx7App {
                             .spacing([8.0, 4.0])
                             .show(ui, |ui| {
                                 ui.label("Breakpoint:");
-                                if ui
-                                    .add(
-                                        egui::Slider::new(&mut breakpoint_note, 0.0..=127.0)
-                                            .integer()
-                                            .custom_formatter(|n, _| midi_note_name(n as u8)),
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::KeyScaleBreakpoint,
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut breakpoint_note, 0.0..=127.0)
+                                                .integer()
+                                                .custom_formatter(|n, _| midi_note_name(n as u8)),
+                                        ),
                                     )
                                     .changed()
                                 {
@@ -2196,8 +6000,17 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("Rate Scl:");
-                                if ui
-                                    .add(egui::Slider::new(&mut key_scale_rt, 0.0..=7.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::KeyScaleRate,
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut key_scale_rt, 0.0..=7.0)
+                                                .integer(),
+                                        ),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2211,8 +6024,16 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("L Depth:");
-                                if ui
-                                    .add(egui::Slider::new(&mut l_depth, 0.0..=99.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::KeyScaleLeftDepth,
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut l_depth, 0.0..=99.0).integer(),
+                                        ),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2226,8 +6047,16 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("R Depth:");
-                                if ui
-                                    .add(egui::Slider::new(&mut r_depth, 0.0..=99.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::KeyScaleRightDepth,
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut r_depth, 0.0..=99.0).integer(),
+                                        ),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2309,8 +6138,14 @@ impl Dx7App {
                             .spacing([8.0, 4.0])
                             .show(ui, |ui| {
                                 ui.label("R1:");
-                                if ui
-                                    .add(egui::Slider::new(&mut rate1, 0.0..=99.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::EgRate(0),
+                                        ),
+                                        ui.add(egui::Slider::new(&mut rate1, 0.0..=99.0).integer()),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2324,8 +6159,16 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("L1:");
-                                if ui
-                                    .add(egui::Slider::new(&mut level1, 0.0..=99.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::EgLevel(0),
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut level1, 0.0..=99.0).integer(),
+                                        ),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2339,8 +6182,14 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("R2:");
-                                if ui
-                                    .add(egui::Slider::new(&mut rate2, 0.0..=99.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::EgRate(1),
+                                        ),
+                                        ui.add(egui::Slider::new(&mut rate2, 0.0..=99.0).integer()),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2354,8 +6203,16 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("L2:");
-                                if ui
-                                    .add(egui::Slider::new(&mut level2, 0.0..=99.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::EgLevel(1),
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut level2, 0.0..=99.0).integer(),
+                                        ),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2369,8 +6226,14 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("R3:");
-                                if ui
-                                    .add(egui::Slider::new(&mut rate3, 0.0..=99.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::EgRate(2),
+                                        ),
+                                        ui.add(egui::Slider::new(&mut rate3, 0.0..=99.0).integer()),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2384,8 +6247,16 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("L3:");
-                                if ui
-                                    .add(egui::Slider::new(&mut level3, 0.0..=99.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::EgLevel(2),
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut level3, 0.0..=99.0).integer(),
+                                        ),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2399,8 +6270,14 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("R4:");
-                                if ui
-                                    .add(egui::Slider::new(&mut rate4, 0.0..=99.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::EgRate(3),
+                                        ),
+                                        ui.add(egui::Slider::new(&mut rate4, 0.0..=99.0).integer()),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2414,8 +6291,16 @@ impl Dx7App {
                                 ui.end_row();
 
                                 ui.label("L4:");
-                                if ui
-                                    .add(egui::Slider::new(&mut level4, 0.0..=99.0).integer())
+                                if self
+                                    .track(
+                                        DataEntryParam::Operator(
+                                            op_idx as u8,
+                                            OperatorField::EgLevel(3),
+                                        ),
+                                        ui.add(
+                                            egui::Slider::new(&mut level4, 0.0..=99.0).integer(),
+                                        ),
+                                    )
                                     .changed()
                                 {
                                     if let Ok(mut ctrl) = self.lock_controller() {
@@ -2430,10 +6315,236 @@ impl Dx7App {
                             });
                     });
                 });
+
+                ui.separator();
+                self.draw_envelope_editor(
+                    ui,
+                    op_idx,
+                    [rate1, rate2, rate3, rate4],
+                    [level1, level2, level3, level4],
+                );
+                self.draw_rate_scaling(
+                    ui,
+                    key_scale_rt,
+                    [rate1, rate2, rate3, rate4],
+                    [level1, level2, level3, level4],
+                );
             });
         });
     }
 
+    /// What RATE SCALING does to this envelope: its time to the sustain
+    /// level and its release time across the keyboard, the same at C1, C3
+    /// and C6 in figures, and a button per key that plays it while held.
+    fn draw_rate_scaling(
+        &mut self,
+        ui: &mut egui::Ui,
+        sensitivity: f32,
+        rates: [f32; 4],
+        levels: [f32; 4],
+    ) {
+        let sustain_color = egui::Color32::from_rgb(100, 200, 255);
+        let release_color = egui::Color32::from_rgb(255, 160, 60);
+        let mut held = None;
+        ui.label(egui::RichText::new("RATE SCALING").size(10.0).strong());
+        ui.horizontal(|ui| {
+            let (response, painter) =
+                ui.allocate_painter(egui::vec2(240.0, 72.0), egui::Sense::hover());
+            let rect = response.rect.shrink(6.0);
+            painter.rect_filled(response.rect, 4.0, egui::Color32::from_rgb(20, 24, 28));
+            let x_of = |note: u8| {
+                rect.left()
+                    + (note - PIANO_LOWEST_NOTE) as f32
+                        / (PIANO_HIGHEST_NOTE - PIANO_LOWEST_NOTE) as f32
+                        * rect.width()
+            };
+            let y_of =
+                |seconds: Option<f32>| rect.bottom() - rate_plot_height(seconds) * rect.height();
+            for note in RATE_SCALING_KEYS {
+                let x = x_of(note);
+                painter.line_segment(
+                    [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                    egui::Stroke::new(1.0, egui::Color32::from_rgb(70, 70, 70)),
+                );
+                painter.text(
+                    egui::pos2(x, rect.bottom()),
+                    egui::Align2::CENTER_BOTTOM,
+                    midi_note_name(note),
+                    egui::FontId::monospace(9.0),
+                    egui::Color32::from_rgb(120, 120, 120),
+                );
+            }
+            let (mut sustain_line, mut release_line) = (Vec::new(), Vec::new());
+            for note in PIANO_LOWEST_NOTE..=PIANO_HIGHEST_NOTE {
+                let t = key_envelope_times(sensitivity, note, rates, levels);
+                sustain_line.push(egui::pos2(x_of(note), y_of(t.to_sustain)));
+                release_line.push(egui::pos2(x_of(note), y_of(t.release)));
+            }
+            for (line, color) in [(sustain_line, sustain_color), (release_line, release_color)] {
+                painter.add(egui::Shape::line(line, egui::Stroke::new(1.5, color)));
+            }
+            response.on_hover_text(
+                "Time to the sustain level (blue) and release time (orange) \
+                 across the keyboard, log scale from 10 ms to 40 s",
+            );
+
+            egui::Grid::new("rate_scaling_grid")
+                .num_columns(4)
+                .spacing([8.0, 2.0])
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.label("SPEED");
+                    ui.label(egui::RichText::new("R1-R3").color(sustain_color));
+                    ui.label(egui::RichText::new("R4").color(release_color));
+                    ui.end_row();
+                    for note in RATE_SCALING_KEYS {
+                        let button = ui
+                            .button(format!("▶ {}", midi_note_name(note)))
+                            .on_hover_text("Hold to play this key");
+                        if button.is_pointer_button_down_on() {
+                            held = Some(note);
+                        }
+                        let t = key_envelope_times(sensitivity, note, rates, levels);
+                        ui.label(format!("×{:.2}", key_rate_scale_factor(sensitivity, note)));
+                        ui.label(format_seconds(t.to_sustain));
+                        ui.label(format_seconds(t.release));
+                        ui.end_row();
+                    }
+                });
+        });
+        let velocity = ui.input(|i| self.key_bindings.velocity.for_modifiers(i.modifiers));
+        self.hold_audition(held, velocity);
+    }
+
+    /// Sound `note` while a RATE SCALING audition button is held, releasing
+    /// the key held before.
+    fn hold_audition(&mut self, note: Option<u8>, velocity: u8) {
+        if note == self.rate_audition {
+            return;
+        }
+        if let Ok(mut ctrl) = self.lock_controller() {
+            if let Some(old) = self.rate_audition {
+                ctrl.note_off(old);
+            }
+            if let Some(new) = note {
+                ctrl.note_on(new, velocity);
+            }
+        }
+        self.rate_audition = note;
+    }
+
+    /// Graphical EG editor: draws the 4-rate/4-level envelope and lets the
+    /// user drag its breakpoints. Vertical drag sets the level, horizontal
+    /// drag sets the rate of the segment leading into the handle (longer
+    /// segment = slower rate).
+    fn draw_envelope_editor(
+        &self,
+        ui: &mut egui::Ui,
+        op_idx: usize,
+        rates: [f32; 4],
+        levels: [f32; 4],
+    ) {
+        const RATE_PARAMS: [EnvelopeParam; 4] = [
+            EnvelopeParam::Rate1,
+            EnvelopeParam::Rate2,
+            EnvelopeParam::Rate3,
+            EnvelopeParam::Rate4,
+        ];
+        const LEVEL_PARAMS: [EnvelopeParam; 4] = [
+            EnvelopeParam::Level1,
+            EnvelopeParam::Level2,
+            EnvelopeParam::Level3,
+            EnvelopeParam::Level4,
+        ];
+
+        let (response, painter) = ui.allocate_painter(
+            egui::vec2(ui.available_width(), 110.0),
+            egui::Sense::hover(),
+        );
+        let rect = response.rect.shrink(8.0);
+        painter.rect_filled(response.rect, 4.0, egui::Color32::from_rgb(20, 24, 28));
+
+        let points = envelope_points(rates, levels);
+        let to_screen = |(x, y): (f32, f32)| {
+            egui::pos2(
+                rect.left() + x / ENV_TOTAL_WIDTH * rect.width(),
+                rect.bottom() - y * rect.height(),
+            )
+        };
+        let screen: Vec<egui::Pos2> = points.iter().map(|&p| to_screen(p)).collect();
+
+        let curve_color = egui::Color32::from_rgb(100, 200, 255);
+        painter.add(egui::Shape::line(
+            screen.clone(),
+            egui::Stroke::new(2.0, curve_color),
+        ));
+        // Key-off marker: everything left of it is the held part of the note.
+        painter.line_segment(
+            [
+                egui::pos2(screen[4].x, rect.top()),
+                egui::pos2(screen[4].x, rect.bottom()),
+            ],
+            egui::Stroke::new(1.0, egui::Color32::from_rgb(70, 70, 70)),
+        );
+        painter.text(
+            egui::pos2(screen[4].x + 3.0, rect.top()),
+            egui::Align2::LEFT_TOP,
+            "KEY OFF",
+            egui::FontId::monospace(9.0),
+            egui::Color32::from_rgb(120, 120, 120),
+        );
+
+        for (handle, &point_idx) in ENV_HANDLE_POINTS.iter().enumerate() {
+            let pos = screen[point_idx];
+            let id = ui.id().with(("env_handle", op_idx, handle));
+            let handle_response = ui
+                .interact(
+                    egui::Rect::from_center_size(pos, egui::vec2(14.0, 14.0)),
+                    id,
+                    egui::Sense::drag(),
+                )
+                .on_hover_text(format!(
+                    "R{n} {:.0} / L{n} {:.0}",
+                    rates[handle],
+                    levels[handle],
+                    n = handle + 1
+                ));
+
+            let active = handle_response.hovered() || handle_response.dragged();
+            let fill = if active {
+                egui::Color32::from_rgb(255, 200, 0)
+            } else {
+                curve_color
+            };
+            painter.circle_filled(pos, 5.0, fill);
+            painter.text(
+                pos + egui::vec2(0.0, -10.0),
+                egui::Align2::CENTER_BOTTOM,
+                format!("{}", handle + 1),
+                egui::FontId::monospace(9.0),
+                egui::Color32::WHITE,
+            );
+
+            if !handle_response.dragged() {
+                continue;
+            }
+            let Some(pointer) = handle_response.interact_pointer_pos() else {
+                continue;
+            };
+            let norm_x = (pointer.x - rect.left()) / rect.width() * ENV_TOTAL_WIDTH;
+            let norm_y = (rect.bottom() - pointer.y) / rect.height();
+            let (rate, level) = envelope_drag_params(handle, norm_x, norm_y, rates, levels);
+            if let Ok(mut ctrl) = self.lock_controller() {
+                if rate != rates[handle] {
+                    ctrl.set_envelope_param(op_idx as u8, RATE_PARAMS[handle], rate);
+                }
+                if level != levels[handle] {
+                    ctrl.set_envelope_param(op_idx as u8, LEVEL_PARAMS[handle], level);
+                }
+            }
+        }
+    }
+
     fn draw_midi_panel(&mut self, ui: &mut egui::Ui) {
         ui.group(|ui| {
             ui.vertical(|ui| {
@@ -2453,6 +6564,10 @@ impl Dx7App {
                 self.draw_breath_routing(ui);
                 ui.add_space(4.0);
                 self.draw_foot_routing(ui);
+                ui.add_space(4.0);
+                self.draw_expression_routing(ui);
+                ui.add_space(4.0);
+                self.draw_mod_matrix(ui);
 
                 ui.add_space(6.0);
                 ui.separator();
@@ -2504,7 +6619,7 @@ impl Dx7App {
     fn draw_aftertouch_routing(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
             ui.label(
-                egui::RichText::new("AFTERTOUCH (0xD0)")
+                egui::RichText::new("AFTERTOUCH (0xD0 / 0xA0)")
                     .strong()
                     .color(egui::Color32::from_rgb(50, 90, 160)),
             );
@@ -2613,6 +6728,104 @@ impl Dx7App {
         });
     }
 
+    /// Expression input for wind controllers: which controller it follows
+    /// and the operators whose level it scales (usually the carriers).
+    fn draw_expression_routing(&mut self, ui: &mut egui::Ui) {
+        let source = self.snapshot.expression_source;
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new("EXPRESSION → OP LEVEL")
+                    .strong()
+                    .color(egui::Color32::from_rgb(50, 90, 160)),
+            )
+            .on_hover_text(
+                "The flagged operators' output follows the controller; with \
+                 CC11 selected it no longer scales the master output.",
+            );
+            for (choice, label) in [
+                (ExpressionSource::Breath, "BREATH CC2"),
+                (ExpressionSource::Expression, "EXPRESSION CC11"),
+            ] {
+                if ui.selectable_label(source == choice, label).clicked() && source != choice {
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.set_expression_source(choice);
+                    }
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            for op in 0..6 {
+                let mut on = self.snapshot.operators[op].expression;
+                if ui.checkbox(&mut on, format!("OP{}", op + 1)).changed() {
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.set_operator_param(
+                            op as u8,
+                            OperatorParam::Expression,
+                            if on { 1.0 } else { 0.0 },
+                        );
+                    }
+                }
+            }
+        });
+    }
+
+    /// Assignable source → destination routes, on top of the fixed
+    /// controller routings above.
+    fn draw_mod_matrix(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("MOD MATRIX")
+            .default_open(false)
+            .show(ui, |ui| {
+                let routes = *self.snapshot.mod_matrix.routes();
+                egui::Grid::new("mod_matrix_grid")
+                    .num_columns(4)
+                    .spacing([6.0, 4.0])
+                    .show(ui, |ui| {
+                        for (slot, current) in routes.iter().enumerate() {
+                            let mut enabled = current.is_some();
+                            let mut route = current.unwrap_or_default();
+                            let prev = (enabled, route);
+
+                            ui.checkbox(&mut enabled, format!("{}", slot + 1));
+                            ui.add_enabled_ui(enabled, |ui| {
+                                egui::ComboBox::from_id_source(("mod_src", slot))
+                                    .selected_text(route.source.name())
+                                    .width(90.0)
+                                    .show_ui(ui, |ui| {
+                                        for s in ModSource::all() {
+                                            ui.selectable_value(&mut route.source, s, s.name());
+                                        }
+                                    });
+                            });
+                            ui.add_enabled_ui(enabled, |ui| {
+                                egui::ComboBox::from_id_source(("mod_dst", slot))
+                                    .selected_text(route.destination.name())
+                                    .width(90.0)
+                                    .show_ui(ui, |ui| {
+                                        for d in ModDestination::all() {
+                                            ui.selectable_value(
+                                                &mut route.destination,
+                                                d,
+                                                d.name(),
+                                            );
+                                        }
+                                    });
+                            });
+                            ui.add_enabled(
+                                enabled,
+                                egui::Slider::new(&mut route.depth, -1.0..=1.0).text("depth"),
+                            );
+                            ui.end_row();
+
+                            if (enabled, route) != prev {
+                                if let Ok(mut ctrl) = self.lock_controller() {
+                                    ctrl.set_mod_route(slot as u8, enabled.then_some(route));
+                                }
+                            }
+                        }
+                    });
+            });
+    }
+
     /// Render a labelled 0..max integer slider for a routing destination.
     /// `apply` is called with the new value when the user changes it.
     fn routing_slider<F>(&self, ui: &mut egui::Ui, label: &str, value: u8, max: u8, mut apply: F)
@@ -2643,76 +6856,1059 @@ impl Dx7App {
             if ui.button("Load .syx").clicked() {
                 self.load_sysex_from_path();
             }
-            if ui.button("Save current voice").clicked() {
-                self.save_sysex_to_path();
+            if ui.button("Save current voice").clicked() {
+                self.save_sysex_to_path();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(format!("TUNING: {}", self.snapshot.tuning_name));
+            let equal = self.snapshot.tuning_name == EQUAL_TEMPERAMENT;
+            if ui
+                .add_enabled(!equal, egui::Button::new("12-TET").small())
+                .on_hover_text("Back to equal temperament")
+                .clicked()
+            {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_tuning(Tuning::default());
+                }
+            }
+        });
+        if !self.sysex_status.is_empty() {
+            ui.label(
+                egui::RichText::new(&self.sysex_status)
+                    .size(11.0)
+                    .color(egui::Color32::from_rgb(120, 120, 120)),
+            );
+        }
+    }
+
+    /// Transport bar for auditioning patches with a Standard MIDI File or
+    /// a looped phrase recorded with REC.
+    fn draw_midi_file_player(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("MIDI FILE").strong());
+            ui.add(egui::TextEdit::singleline(&mut self.midi_file_path).desired_width(200.0));
+            if ui.button("Load").clicked() {
+                self.load_midi_file();
+            }
+            self.draw_phrase_record_button(ui);
+            self.draw_automation_record_button(ui);
+
+            let Some(player) = &self.midi_player else {
+                ui.label(egui::RichText::new(&self.midi_file_status).size(11.0));
+                return;
+            };
+            let Some(status) = player.status() else {
+                return;
+            };
+            let label = if status.playing {
+                "■ STOP"
+            } else {
+                "▶ PLAY"
+            };
+            if ui.button(label).clicked() {
+                player.with(|seq, ctrl| {
+                    if status.playing {
+                        seq.stop(ctrl);
+                    } else {
+                        seq.start();
+                    }
+                });
+            }
+            let mut looping = status.looping;
+            if ui.checkbox(&mut looping, "LOOP").changed() {
+                player.with(|seq, _| seq.set_looping(looping));
+            }
+            let mut tempo = status.tempo_scale * 100.0;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut tempo)
+                        .range(MIN_TEMPO_SCALE * 100.0..=MAX_TEMPO_SCALE * 100.0)
+                        .speed(1.0)
+                        .suffix(" %"),
+                )
+                .on_hover_text("Playback tempo, relative to the file")
+                .changed()
+            {
+                player.with(|seq, _| seq.set_tempo_scale(tempo / 100.0));
+            }
+            let clock = |s: f32| format!("{}:{:04.1}", (s / 60.0) as u32, s % 60.0);
+            ui.label(format!(
+                "{} / {}",
+                clock(status.position),
+                clock(status.length)
+            ));
+            ui.label(egui::RichText::new(&self.midi_file_status).size(11.0));
+            if status.playing {
+                ui.ctx()
+                    .request_repaint_after(std::time::Duration::from_millis(100));
+            }
+        });
+    }
+
+    /// REC arms the phrase recorder; pressing it again loops what was
+    /// played through the transport.
+    fn draw_phrase_record_button(&mut self, ui: &mut egui::Ui) {
+        let recording = self
+            .lock_controller()
+            .map(|ctrl| ctrl.is_recording_phrase())
+            .unwrap_or(false);
+        let text = egui::RichText::new("● REC");
+        let button = if recording {
+            egui::Button::new(text.color(egui::Color32::WHITE))
+                .fill(egui::Color32::from_rgb(180, 40, 40))
+        } else {
+            egui::Button::new(text)
+        };
+        let hint = if recording {
+            "Stop recording and loop the phrase"
+        } else {
+            "Record the notes you play (MIDI or keyboard) into a looped phrase"
+        };
+        if !ui.add(button).on_hover_text(hint).clicked() {
+            return;
+        }
+
+        if !recording {
+            if let Some(player) = &self.midi_player {
+                player.with(|seq, ctrl| seq.stop(ctrl));
+            }
+            if let Ok(mut ctrl) = self.lock_controller() {
+                ctrl.start_phrase_recording();
+            }
+            self.midi_file_status = "Recording…".to_string();
+            return;
+        }
+
+        let phrase = self
+            .lock_controller()
+            .ok()
+            .and_then(|mut ctrl| ctrl.finish_phrase_recording());
+        match phrase {
+            Some((notes, length)) => {
+                self.midi_player = None;
+                let player = SequencerPlayer::spawn(
+                    Sequencer::looped_phrase(&notes, length),
+                    self.controller.clone(),
+                );
+                player.with(|seq, _| seq.start());
+                self.midi_player = Some(player);
+                self.midi_file_status = format!("Phrase: {} notes", notes.len());
+            }
+            None => self.midi_file_status = "Nothing recorded".to_string(),
+        }
+    }
+
+    /// AUTO captures the parameters moved while the player runs; pressing
+    /// it again lays them into the score so they replay on every pass.
+    fn draw_automation_record_button(&mut self, ui: &mut egui::Ui) {
+        let Some(player) = &self.midi_player else {
+            return;
+        };
+        let Some((recording, events)) =
+            player.with(|seq, ctrl| (ctrl.is_recording_automation(), seq.status().automation))
+        else {
+            return;
+        };
+        let text = egui::RichText::new("● AUTO");
+        let button = if recording {
+            egui::Button::new(text.color(egui::Color32::WHITE))
+                .fill(egui::Color32::from_rgb(180, 40, 40))
+        } else {
+            egui::Button::new(text)
+        };
+        let hint = if recording {
+            "Stop recording and replay the moves with the score"
+        } else {
+            "Record the parameters you move (sliders, mod wheel, bend) in time \
+             with the playing file or phrase"
+        };
+        if ui.add(button).on_hover_text(hint).clicked() {
+            if recording {
+                let start = self.automation_start;
+                let added = player.with(|seq, ctrl| {
+                    let take = ctrl.finish_automation_recording();
+                    let before = seq.status().automation;
+                    seq.add_automation(take, start);
+                    seq.status().automation - before
+                });
+                self.midi_file_status = format!("Automation: {} moves", added.unwrap_or(0));
+            } else if let Some(position) = player.with(|seq, ctrl| {
+                ctrl.start_automation_recording();
+                seq.start();
+                seq.status().position
+            }) {
+                self.automation_start = position;
+                self.midi_file_status = "Recording automation…".to_string();
+            }
+        }
+        if events > 0
+            && ui
+                .small_button("CLEAR AUTO")
+                .on_hover_text(format!("{} recorded moves", events))
+                .clicked()
+        {
+            player.with(|seq, _| seq.clear_automation());
+        }
+    }
+
+    /// Load `midi_file_path` into a new player, stopping the previous one.
+    fn load_midi_file(&mut self) {
+        let path = self.midi_file_path.trim().to_string();
+        let events = std::fs::read(&path)
+            .map_err(|e| format!("Read error ({}): {}", path, e))
+            .and_then(|bytes| parse_midi_file(&bytes).map_err(|e| format!("Parse error: {}", e)));
+        match events {
+            Ok(events) => {
+                // Drop the old player first so its notes are released.
+                self.midi_player = None;
+                self.midi_player = Some(SequencerPlayer::spawn(
+                    Sequencer::new(&events),
+                    self.controller.clone(),
+                ));
+                self.midi_file_status = format!("{} notes", events.len());
+            }
+            Err(e) => self.midi_file_status = e,
+        }
+    }
+
+    fn load_sysex_from_path(&mut self) {
+        let path = self.sysex_path.trim().to_string();
+        match std::fs::read(&path) {
+            Ok(bytes) => match crate::sysex::parse_message(&bytes) {
+                Ok(crate::sysex::SysexResult::SingleVoice(preset)) => {
+                    let name = preset.name.clone();
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.load_sysex_single_voice(*preset);
+                    }
+                    self.sysex_status = format!("Loaded single voice '{}' from {}", name, path);
+                }
+                Ok(crate::sysex::SysexResult::Bulk(presets)) => {
+                    let count = presets.len();
+                    let collection = std::path::Path::new(&path)
+                        .file_name()
+                        .map_or_else(|| path.clone(), |n| n.to_string_lossy().into_owned());
+                    self.import_bank(&collection, &presets);
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.load_sysex_bulk(presets);
+                    }
+                    self.sysex_status =
+                        format!("Loaded bulk dump ({} voices) from {}", count, path);
+                }
+                Ok(crate::sysex::SysexResult::Tuning(tuning)) => {
+                    self.sysex_status = format!("Loaded tuning '{}' from {}", tuning.name, path);
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.set_tuning(*tuning);
+                    }
+                }
+                Ok(crate::sysex::SysexResult::NoteTuning(changes)) => {
+                    let count = changes.len();
+                    if let Ok(mut ctrl) = self.lock_controller() {
+                        ctrl.retune_notes(changes);
+                    }
+                    self.sysex_status = format!("Retuned {} keys from {}", count, path);
+                }
+                Err(e) => match crate::sysex::parse_bank_file(&bytes) {
+                    Ok(bank) => self.import_bank_file(&path, bank),
+                    Err(_) => self.sysex_status = format!("Parse error: {}", e),
+                },
+            },
+            Err(e) => {
+                self.sysex_status = format!("Read error ({}): {}", path, e);
+            }
+        }
+    }
+
+    /// Load a bank that is not a clean VMEM dump, noting what was forgiven in
+    /// the status line and its bad voices on the LIBRARY page.
+    fn import_bank_file(&mut self, path: &str, bank: BankFile) {
+        let collection = std::path::Path::new(path)
+            .file_name()
+            .map_or_else(|| path.to_string(), |n| n.to_string_lossy().into_owned());
+        let mut notes = Vec::new();
+        if bank.header_len > 0 {
+            notes.push(format!("{}-byte header skipped", bank.header_len));
+        }
+        if bank.checksum_ok == Some(false) {
+            notes.push("checksum mismatch".to_string());
+        }
+        if !bank.errors.is_empty() {
+            notes.push(format!("{} bad voices, see LIBRARY", bank.errors.len()));
+        }
+        self.sysex_status = format!(
+            "Loaded bank ({} voices) from {}{}",
+            bank.presets.len(),
+            path,
+            if notes.is_empty() {
+                String::new()
+            } else {
+                format!(" — {}", notes.join(", "))
+            }
+        );
+
+        self.import_bank(&collection, &bank.presets);
+        self.bank_errors.retain(|(c, _)| *c != collection);
+        self.bank_errors.extend(
+            bank.errors
+                .into_iter()
+                .map(|error| (collection.clone(), error)),
+        );
+        if let Ok(mut ctrl) = self.lock_controller() {
+            ctrl.load_sysex_bulk(bank.presets);
+        }
+    }
+
+    fn save_sysex_to_path(&mut self) {
+        let path = self.sysex_path.trim().to_string();
+        let preset = Dx7Preset::from_snapshot(&self.snapshot);
+        let channel = self.midi_channel_ui.unwrap_or(0);
+        let bytes = crate::sysex::encode_single_voice(&preset, channel);
+        match std::fs::write(&path, &bytes) {
+            Ok(_) => {
+                self.sysex_status = format!(
+                    "Saved '{}' ({} bytes) to {}",
+                    preset.name,
+                    bytes.len(),
+                    path
+                );
+            }
+            Err(e) => {
+                self.sysex_status = format!("Write error ({}): {}", path, e);
+            }
+        }
+    }
+
+    fn draw_audio_panel(&mut self, ui: &mut egui::Ui) {
+        let host = self.audio_settings.host.clone();
+        let devices = self
+            .audio_devices
+            .get_or_insert_with(|| list_output_devices(host.as_deref()))
+            .clone();
+        // Device the rate / buffer lists are filtered against.
+        let selected = devices
+            .iter()
+            .find(|d| match &self.audio_settings.device {
+                Some(name) => &d.name == name,
+                None => d.is_default,
+            })
+            .cloned();
+
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new("AUDIO SETUP").size(14.0).strong());
+                ui.separator();
+
+                match &self.audio_engine {
+                    Some(audio) => {
+                        let info = audio.stream_info();
+                        let buffer = match (info.buffer_size, audio.buffer_latency()) {
+                            (_, Some((frames, ms))) => format!("{frames} frames ({ms:.1} ms)"),
+                            (Some(frames), None) => format!("{frames} frames"),
+                            (None, None) => "host default".to_string(),
+                        };
+                        ui.label(format!(
+                            "ACTIVE: {} ({}) | {} Hz | BUFFER: {}",
+                            info.device, info.host, info.sample_rate, buffer
+                        ));
+                    }
+                    None => {
+                        ui.label("(no audio engine running)");
+                    }
+                }
+                ui.add_space(4.0);
+
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("HOST").strong());
+                    let label = self
+                        .audio_settings
+                        .host
+                        .clone()
+                        .unwrap_or_else(|| "System default".to_string());
+                    egui::ComboBox::from_id_source("audio_host_combo")
+                        .selected_text(label)
+                        .show_ui(ui, |ui| {
+                            if ui
+                                .selectable_label(
+                                    self.audio_settings.host.is_none(),
+                                    "System default",
+                                )
+                                .clicked()
+                            {
+                                self.select_audio_host(None);
+                            }
+                            for host in available_hosts() {
+                                let current = self.audio_settings.host.as_ref() == Some(&host);
+                                if ui.selectable_label(current, &host).clicked() {
+                                    self.select_audio_host(Some(host));
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "JACK / ASIO are listed when built with the jack / asio features",
+                        );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("DEVICE").strong());
+                    let label = self
+                        .audio_settings
+                        .device
+                        .clone()
+                        .unwrap_or_else(|| "System default".to_string());
+                    egui::ComboBox::from_id_source("audio_device_combo")
+                        .selected_text(label)
+                        .width(320.0)
+                        .show_ui(ui, |ui| {
+                            if ui
+                                .selectable_label(
+                                    self.audio_settings.device.is_none(),
+                                    "System default",
+                                )
+                                .clicked()
+                            {
+                                self.select_audio_device(None);
+                            }
+                            for device in &devices {
+                                let text = if device.is_default {
+                                    format!("{} (default)", device.name)
+                                } else {
+                                    device.name.clone()
+                                };
+                                let current =
+                                    self.audio_settings.device.as_ref() == Some(&device.name);
+                                if ui.selectable_label(current, text).clicked() {
+                                    self.select_audio_device(Some(device.name.clone()));
+                                }
+                            }
+                        });
+                    if ui.button("REFRESH").clicked() {
+                        self.audio_devices = None;
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("SAMPLE RATE").strong());
+                    let rates = selected
+                        .as_ref()
+                        .map_or_else(|| COMMON_SAMPLE_RATES.to_vec(), |d| d.sample_rates.clone());
+                    let label = self
+                        .audio_settings
+                        .sample_rate
+                        .map_or_else(|| "Device default".to_string(), |r| format!("{r} Hz"));
+                    egui::ComboBox::from_id_source("audio_rate_combo")
+                        .selected_text(label)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.audio_settings.sample_rate,
+                                None,
+                                "Device default",
+                            );
+                            for rate in rates {
+                                ui.selectable_value(
+                                    &mut self.audio_settings.sample_rate,
+                                    Some(rate),
+                                    format!("{rate} Hz"),
+                                );
+                            }
+                        });
+
+                    ui.label(egui::RichText::new("BUFFER").strong());
+                    let range = selected.as_ref().and_then(|d| d.buffer_size_range);
+                    let label = self
+                        .audio_settings
+                        .buffer_size
+                        .map_or_else(|| "Host default".to_string(), |n| format!("{n} frames"));
+                    egui::ComboBox::from_id_source("audio_buffer_combo")
+                        .selected_text(label)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.audio_settings.buffer_size,
+                                None,
+                                "Host default",
+                            );
+                            for size in COMMON_BUFFER_SIZES {
+                                if range.is_some_and(|(min, max)| !(min..=max).contains(&size)) {
+                                    continue;
+                                }
+                                ui.selectable_value(
+                                    &mut self.audio_settings.buffer_size,
+                                    Some(size),
+                                    format!("{size} frames"),
+                                );
+                            }
+                        });
+                });
+
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(self.audio_engine.is_some(), egui::Button::new("APPLY"))
+                        .on_hover_text("Restart the audio stream with these settings")
+                        .clicked()
+                    {
+                        self.apply_audio_settings();
+                    }
+                    if ui
+                        .button("TEST TONE")
+                        .on_hover_text("Play a short arpeggio with the current voice")
+                        .clicked()
+                    {
+                        play_test_tone(self.controller.clone(), std::time::Duration::ZERO);
+                    }
+                    ui.label(&self.audio_status);
+                });
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("GUI FPS").strong());
+                    let mut fps = self.gui_fps.load(Ordering::Relaxed);
+                    egui::ComboBox::from_id_source("gui_fps_combo")
+                        .selected_text(fps.to_string())
+                        .show_ui(ui, |ui| {
+                            for choice in GUI_FPS_CHOICES {
+                                ui.selectable_value(&mut fps, choice, choice.to_string());
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Frame-rate cap for meters, scope and spectrum; lower saves CPU",
+                        );
+                    self.gui_fps.store(fps, Ordering::Relaxed);
+                });
+            });
+        });
+    }
+
+    /// Pick an audio host; the device list is reloaded from it and the
+    /// device, rate and buffer go back to its defaults.
+    fn select_audio_host(&mut self, host: Option<String>) {
+        if self.audio_settings.host != host {
+            self.audio_settings = AudioSettings {
+                host,
+                ..AudioSettings::default()
+            };
+            self.audio_devices = None;
+        }
+    }
+
+    /// Pick an output device; rate and buffer go back to its defaults since
+    /// the previous choice may not be supported there.
+    fn select_audio_device(&mut self, device: Option<String>) {
+        if self.audio_settings.device != device {
+            self.audio_settings = AudioSettings {
+                host: self.audio_settings.host.clone(),
+                device,
+                ..AudioSettings::default()
+            };
+        }
+    }
+
+    /// Rebuild the audio stream from the AUDIO page selection.
+    fn apply_audio_settings(&mut self) {
+        let Some(audio) = self.audio_engine.as_mut() else {
+            return;
+        };
+        match audio.reconfigure(&self.audio_settings) {
+            Ok(sample_rate) => {
+                if let Ok(mut ctrl) = self.lock_controller() {
+                    ctrl.set_sample_rate(sample_rate);
+                }
+                self.audio_status = format!("Stream restarted at {sample_rate} Hz");
+                if let Some(path) = AudioSettings::default_path() {
+                    if let Err(e) = self.audio_settings.save(&path) {
+                        log::warn!("Could not save audio settings: {e}");
+                    }
+                }
+            }
+            Err(e) => {
+                log::warn!("Audio reconfigure failed: {e}");
+                self.audio_status = format!("Error: {e}");
+            }
+        }
+    }
+
+    fn draw_scope_panel(&mut self, ui: &mut egui::Ui) {
+        let mut samples = vec![0.0; SCOPE_READ];
+        if let Ok(ctrl) = self.lock_controller() {
+            ctrl.scope_latest(&mut samples);
+        }
+        let start = scope_trigger_index(&samples, SCOPE_WINDOW);
+        let window = &samples[start..(start + SCOPE_WINDOW).min(samples.len())];
+        let peak = samples.iter().fold(0.0_f32, |m, s| m.max(s.abs()));
+
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new("OSCILLOSCOPE").strong());
+                ui.label(format!("peak {:.2}", peak));
+                if peak >= SCOPE_SATURATION_LEVEL {
+                    ui.colored_label(egui::Color32::from_rgb(220, 40, 40), "SATURATING");
+                }
+            });
+
+            let (response, painter) = ui.allocate_painter(
+                egui::vec2(ui.available_width(), 260.0),
+                egui::Sense::hover(),
+            );
+            let rect = response.rect;
+            painter.rect_filled(rect, 4.0, egui::Color32::from_rgb(10, 20, 12));
+
+            let y_for = |v: f32| rect.center().y - v.clamp(-1.0, 1.0) * rect.height() * 0.5;
+            let grid = egui::Stroke::new(1.0, egui::Color32::from_rgb(40, 60, 44));
+            painter.hline(rect.x_range(), rect.center().y, grid);
+            let limit = egui::Stroke::new(1.0, egui::Color32::from_rgb(120, 40, 40));
+            for level in [SCOPE_SATURATION_LEVEL, -SCOPE_SATURATION_LEVEL] {
+                painter.hline(rect.x_range(), y_for(level), limit);
+            }
+
+            let step = rect.width() / (SCOPE_WINDOW - 1) as f32;
+            let points: Vec<egui::Pos2> = window
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| egui::pos2(rect.left() + i as f32 * step, y_for(v)))
+                .collect();
+            painter.add(egui::Shape::line(
+                points,
+                egui::Stroke::new(1.5, egui::Color32::from_rgb(90, 255, 120)),
+            ));
+        });
+
+        // Keep the trace moving while the page is open.
+        ui.ctx().request_repaint_after(self.frame_interval());
+    }
+
+    fn draw_spectrum_panel(&mut self, ui: &mut egui::Ui) {
+        let mut samples = vec![0.0; SPECTRUM_SIZE];
+        let mut sample_rate = 44_100.0;
+        if let Ok(ctrl) = self.lock_controller() {
+            ctrl.scope_latest(&mut samples);
+            sample_rate = ctrl.sample_rate();
+        }
+        let db = spectrum::magnitude_db(&samples);
+        let nyquist = sample_rate * 0.5;
+        let (peak_bin, peak_db) =
+            db.iter()
+                .copied()
+                .enumerate()
+                .skip(1)
+                .fold((0, SPECTRUM_FLOOR_DB), |best, (k, v)| {
+                    if v > best.1 {
+                        (k, v)
+                    } else {
+                        best
+                    }
+                });
+
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new("SPECTRUM").strong());
+                if peak_bin > 0 {
+                    ui.label(format!(
+                        "peak {:.0} Hz  {:.1} dB",
+                        spectrum::bin_frequency(peak_bin, SPECTRUM_SIZE, sample_rate),
+                        peak_db
+                    ));
+                }
+            });
+
+            let (response, painter) = ui.allocate_painter(
+                egui::vec2(ui.available_width(), 260.0),
+                egui::Sense::hover(),
+            );
+            let rect = response.rect;
+            painter.rect_filled(rect, 4.0, egui::Color32::from_rgb(10, 12, 24));
+
+            let x_for = |hz: f32| {
+                rect.left() + spectrum_x_fraction(hz, SPECTRUM_MIN_HZ, nyquist) * rect.width()
+            };
+            let y_for =
+                |v: f32| rect.top() + (v / SPECTRUM_FLOOR_DB).clamp(0.0, 1.0) * rect.height();
+
+            let grid = egui::Stroke::new(1.0, egui::Color32::from_rgb(40, 44, 70));
+            let label_color = egui::Color32::from_rgb(110, 120, 160);
+            for hz in [100.0, 1_000.0, 10_000.0] {
+                if hz < nyquist {
+                    let x = x_for(hz);
+                    painter.vline(x, rect.y_range(), grid);
+                    painter.text(
+                        egui::pos2(x + 3.0, rect.bottom() - 3.0),
+                        egui::Align2::LEFT_BOTTOM,
+                        if hz >= 1_000.0 {
+                            format!("{}k", hz / 1_000.0)
+                        } else {
+                            format!("{}", hz)
+                        },
+                        egui::FontId::monospace(10.0),
+                        label_color,
+                    );
+                }
+            }
+            for level in [-24.0, -48.0, -72.0] {
+                let y = y_for(level);
+                painter.hline(rect.x_range(), y, grid);
+                painter.text(
+                    egui::pos2(rect.left() + 3.0, y - 1.0),
+                    egui::Align2::LEFT_BOTTOM,
+                    format!("{} dB", level),
+                    egui::FontId::monospace(10.0),
+                    label_color,
+                );
             }
+
+            let points: Vec<egui::Pos2> = db
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(k, &v)| {
+                    let hz = spectrum::bin_frequency(k, SPECTRUM_SIZE, sample_rate);
+                    egui::pos2(x_for(hz.max(SPECTRUM_MIN_HZ)), y_for(v))
+                })
+                .collect();
+            painter.add(egui::Shape::line(
+                points,
+                egui::Stroke::new(1.5, egui::Color32::from_rgb(120, 170, 255)),
+            ));
         });
-        if !self.sysex_status.is_empty() {
+
+        ui.ctx().request_repaint_after(self.frame_interval());
+    }
+
+    fn draw_keys_panel(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("LAYOUT:");
+                for layout in KeyboardLayout::all() {
+                    if ui.button(layout.name()).clicked() {
+                        self.key_bindings.apply_layout(layout);
+                    }
+                }
+                ui.separator();
+                if ui.button("Defaults").clicked() {
+                    self.key_bindings = KeyBindings::default();
+                }
+                if ui
+                    .add_enabled(self.key_bindings_path.is_some(), egui::Button::new("Save"))
+                    .clicked()
+                {
+                    self.save_key_bindings();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("VELOCITY:");
+                let velocity = &mut self.key_bindings.velocity;
+                for (label, value) in [
+                    ("Plain", &mut velocity.normal),
+                    ("Shift", &mut velocity.shift),
+                    ("Ctrl", &mut velocity.ctrl),
+                ] {
+                    ui.label(label);
+                    ui.add(egui::DragValue::new(value).range(1..=127));
+                }
+            });
             ui.label(
-                egui::RichText::new(&self.sysex_status)
+                egui::RichText::new("Click a key, then press the new key (Esc cancels).")
                     .size(11.0)
                     .color(egui::Color32::from_rgb(120, 120, 120)),
             );
+            ui.separator();
+
+            let actions: Vec<KeyAction> = KeyAction::all().collect();
+            egui::ScrollArea::vertical()
+                .max_height(320.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("key_bindings_grid")
+                        .num_columns(4)
+                        .spacing([12.0, 4.0])
+                        .show(ui, |ui| {
+                            for (i, &action) in actions.iter().enumerate() {
+                                ui.label(action.label());
+                                let text = if self.capturing_key == Some(action) {
+                                    "…".to_string()
+                                } else {
+                                    self.key_bindings.key(action).name().to_string()
+                                };
+                                if ui
+                                    .add(egui::Button::new(text).min_size(egui::vec2(70.0, 0.0)))
+                                    .clicked()
+                                {
+                                    self.capturing_key = Some(action);
+                                }
+                                if i % 2 == 1 {
+                                    ui.end_row();
+                                }
+                            }
+                        });
+                });
+
+            if !self.key_bindings_status.is_empty() {
+                ui.label(
+                    egui::RichText::new(&self.key_bindings_status)
+                        .size(11.0)
+                        .color(egui::Color32::from_rgb(120, 120, 120)),
+                );
+            }
+        });
+    }
+
+    fn save_key_bindings(&mut self) {
+        let Some(path) = self.key_bindings_path.clone() else {
+            return;
+        };
+        self.key_bindings_status = match self.key_bindings.save(&path) {
+            Ok(_) => format!("Saved key bindings to {}", path.display()),
+            Err(e) => format!("Write error ({}): {}", path.display(), e),
+        };
+    }
+
+    /// Keys drawn as held on the piano: everything the engine reports, plus
+    /// this window's own notes, which would otherwise light up a snapshot
+    /// late.
+    fn piano_lit_keys(&self) -> [bool; 128] {
+        let mut lit: [bool; 128] = std::array::from_fn(|n| self.snapshot.key_held(n as u8));
+        for semitone in 0..crate::keybindings::NOTE_KEY_COUNT as i32 {
+            let key = self.key_bindings.key(KeyAction::Note(semitone as u8));
+            if self.last_key_times.contains_key(&key) {
+                lit[(self.current_octave * 12 + 12 + semitone).clamp(0, 127) as usize] = true;
+            }
         }
+        if let Some(note) = self.piano_note {
+            lit[note as usize] = true;
+        }
+        lit
     }
 
-    fn load_sysex_from_path(&mut self) {
-        let path = self.sysex_path.trim().to_string();
-        match std::fs::read(&path) {
-            Ok(bytes) => match crate::sysex::parse_message(&bytes) {
-                Ok(crate::sysex::SysexResult::SingleVoice(preset)) => {
-                    let name = preset.name.clone();
-                    if let Ok(mut ctrl) = self.lock_controller() {
-                        ctrl.load_sysex_single_voice(*preset);
-                    }
-                    self.sysex_status = format!("Loaded single voice '{}' from {}", name, path);
-                }
-                Ok(crate::sysex::SysexResult::Bulk(presets)) => {
-                    let count = presets.len();
-                    if let Ok(mut ctrl) = self.lock_controller() {
-                        ctrl.load_sysex_bulk(presets);
-                    }
-                    self.sysex_status =
-                        format!("Loaded bulk dump ({} voices) from {}", count, path);
+    /// Piano strip across the bottom of the window. A key sounds while the
+    /// mouse button (or a finger: egui turns touches into pointer events) is
+    /// held on it, at the computer keyboard's velocity for the modifiers
+    /// held; dragging across the keys plays a glissando. Keys held from any
+    /// source, MIDI included, light up.
+    fn draw_piano(&mut self, ui: &mut egui::Ui) {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), PIANO_HEIGHT),
+            egui::Sense::click_and_drag(),
+        );
+
+        let key = response
+            .is_pointer_button_down_on()
+            .then(|| response.interact_pointer_pos())
+            .flatten()
+            .and_then(|pos| piano_key_at(rect, pos));
+        if key != self.piano_note {
+            let velocity = ui.input(|i| self.key_bindings.velocity.for_modifiers(i.modifiers));
+            if let Ok(mut ctrl) = self.lock_controller() {
+                if let Some(note) = self.piano_note {
+                    ctrl.note_off(note);
                 }
-                Err(e) => {
-                    self.sysex_status = format!("Parse error: {}", e);
+                if let Some(note) = key {
+                    ctrl.note_on(note, velocity);
                 }
-            },
-            Err(e) => {
-                self.sysex_status = format!("Read error ({}): {}", path, e);
             }
+            self.piano_note = key;
         }
-    }
 
-    fn save_sysex_to_path(&mut self) {
-        let path = self.sysex_path.trim().to_string();
-        let preset = Dx7Preset::from_snapshot(&self.snapshot);
-        let channel = self.midi_channel_ui.unwrap_or(0);
-        let bytes = crate::sysex::encode_single_voice(&preset, channel);
-        match std::fs::write(&path, &bytes) {
-            Ok(_) => {
-                self.sysex_status = format!(
-                    "Saved '{}' ({} bytes) to {}",
-                    preset.name,
-                    bytes.len(),
-                    path
+        let lit = self.piano_lit_keys();
+        let painter = ui.painter_at(rect);
+        let outline = egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 60, 60));
+        let highlight = egui::Color32::from_rgb(120, 170, 255);
+        for (note, key_rect) in piano_key_rects(rect) {
+            let fill = match (lit[note as usize], is_black_key(note)) {
+                (true, _) => highlight,
+                (false, true) => egui::Color32::from_rgb(30, 30, 30),
+                (false, false) => egui::Color32::WHITE,
+            };
+            painter.rect_filled(key_rect, 1.0, fill);
+            painter.rect_stroke(key_rect, 1.0, outline);
+            if note % 12 == 0 {
+                painter.text(
+                    key_rect.center_bottom() - egui::vec2(0.0, 3.0),
+                    egui::Align2::CENTER_BOTTOM,
+                    midi_note_name(note),
+                    egui::FontId::monospace(9.0),
+                    egui::Color32::from_rgb(120, 120, 120),
                 );
             }
-            Err(e) => {
-                self.sysex_status = format!("Write error ({}): {}", path, e);
-            }
         }
     }
 }
 
+/// Samples pulled from the engine's scope ring per frame, and how many of
+/// them are drawn after the trigger point.
+const SCOPE_READ: usize = 2048;
+const SCOPE_WINDOW: usize = 1024;
+/// Peak above which the scope flags the output as driving the final `tanh`
+/// saturator hard (tanh(1.5) ≈ 0.905).
+const SCOPE_SATURATION_LEVEL: f32 = 0.9;
+
+/// Index of the first rising zero crossing that still leaves `window`
+/// samples after it, so periodic waveforms stand still between frames.
+/// Falls back to 0 (free-running) when there is none, e.g. on silence.
+fn scope_trigger_index(samples: &[f32], window: usize) -> usize {
+    let last_start = samples.len().saturating_sub(window);
+    (1..=last_start)
+        .find(|&i| samples[i - 1] < 0.0 && samples[i] >= 0.0)
+        .unwrap_or(0)
+}
+
+/// FFT size for the spectrum page: the whole scope ring, ~11 Hz per bin at
+/// 44.1 kHz. The lowest frequency drawn on its log axis.
+const SPECTRUM_SIZE: usize = crate::fm_synth::SCOPE_CAPACITY;
+const SPECTRUM_MIN_HZ: f32 = 20.0;
+
+/// Horizontal position (0..=1) of `hz` on a log axis from `min_hz` to `max_hz`.
+fn spectrum_x_fraction(hz: f32, min_hz: f32, max_hz: f32) -> f32 {
+    ((hz / min_hz).ln() / (max_hz / min_hz).ln()).clamp(0.0, 1.0)
+}
+
 /// Max fraction of white blended into an active operator's fill (0..=1).
 /// Tunable: lower = subtler highlight, higher = whiter at full envelope.
 const ACTIVITY_BRIGHTEN_MAX: f32 = 0.6;
 
+/// Envelope editor geometry, in abstract units: every rate segment spans
+/// `ENV_MIN_SEGMENT..=1.0` (rate 99 → shortest), the sustain hold is drawn
+/// with a fixed width, and the canvas is always `ENV_TOTAL_WIDTH` wide so the
+/// curve doesn't rescale under the pointer while dragging.
+const ENV_MIN_SEGMENT: f32 = 0.1;
+const ENV_SUSTAIN_WIDTH: f32 = 0.6;
+const ENV_TOTAL_WIDTH: f32 = 4.0 + ENV_SUSTAIN_WIDTH;
+
+/// Indices into `envelope_points` of the four draggable breakpoints
+/// (end of R1..R4). Point 0 is the key-on start, point 4 the key-off.
+const ENV_HANDLE_POINTS: [usize; 4] = [1, 2, 3, 5];
+
+fn env_segment_width(rate: f32) -> f32 {
+    ENV_MIN_SEGMENT + (1.0 - ENV_MIN_SEGMENT) * (99.0 - rate.clamp(0.0, 99.0)) / 99.0
+}
+
+fn env_rate_for_width(width: f32) -> f32 {
+    let t = ((width - ENV_MIN_SEGMENT) / (1.0 - ENV_MIN_SEGMENT)).clamp(0.0, 1.0);
+    (99.0 - t * 99.0).round()
+}
+
+/// Breakpoints of the EG curve as (x, y) with y in 0..=1. The DX7 EG starts
+/// from L4, runs R1→L1, R2→L2, R3→L3, holds L3 until key-off and then
+/// returns to L4 at R4.
+fn envelope_points(rates: [f32; 4], levels: [f32; 4]) -> [(f32, f32); 6] {
+    let y = |level: f32| level.clamp(0.0, 99.0) / 99.0;
+    let mut x = 0.0;
+    let mut points = [(0.0, y(levels[3])); 6];
+    for (i, (&rate, &level)) in rates.iter().zip(&levels).take(3).enumerate() {
+        x += env_segment_width(rate);
+        points[i + 1] = (x, y(level));
+    }
+    x += ENV_SUSTAIN_WIDTH;
+    points[4] = (x, y(levels[2]));
+    x += env_segment_width(rates[3]);
+    points[5] = (x, y(levels[3]));
+    points
+}
+
+/// Map a drag of `handle` (0..=3) to a normalised pointer position into the
+/// (rate, level) pair for that stage, both rounded to DX7 integer steps.
+fn envelope_drag_params(
+    handle: usize,
+    norm_x: f32,
+    norm_y: f32,
+    rates: [f32; 4],
+    levels: [f32; 4],
+) -> (f32, f32) {
+    let points = envelope_points(rates, levels);
+    let segment_start = points[ENV_HANDLE_POINTS[handle] - 1].0;
+    let rate = env_rate_for_width(norm_x - segment_start);
+    let level = (norm_y.clamp(0.0, 1.0) * 99.0).round();
+    (rate, level)
+}
+
+/// Keys the RATE SCALING table lists: C1, C3 and C6.
+const RATE_SCALING_KEYS: [u8; 3] = [36, 60, 96];
+/// Span of the RATE SCALING plot, in seconds on a log scale.
+const RATE_PLOT_MIN_SECONDS: f32 = 0.01;
+const RATE_PLOT_MAX_SECONDS: f32 = 40.0;
+
+/// Envelope times on `note` for RATE SCALING `sensitivity`.
+fn key_envelope_times(
+    sensitivity: f32,
+    note: u8,
+    rates: [f32; 4],
+    levels: [f32; 4],
+) -> EnvelopeTimes {
+    envelope_times(rates, levels, key_rate_scale_factor(sensitivity, note))
+}
+
+/// Height in the RATE SCALING plot (0..=1) for a time in seconds; a
+/// segment that holds forever is drawn at the top.
+fn rate_plot_height(seconds: Option<f32>) -> f32 {
+    let Some(seconds) = seconds else {
+        return 1.0;
+    };
+    let span = (RATE_PLOT_MAX_SECONDS / RATE_PLOT_MIN_SECONDS).ln();
+    ((seconds / RATE_PLOT_MIN_SECONDS).ln() / span).clamp(0.0, 1.0)
+}
+
+/// A time as milliseconds under a second, seconds above; "hold" for None.
+fn format_seconds(seconds: Option<f32>) -> String {
+    match seconds {
+        None => "hold".to_string(),
+        Some(s) if s < 1.0 => format!("{:.0} ms", s * 1000.0),
+        Some(s) => format!("{:.2} s", s),
+    }
+}
+
+/// Range of the on-screen piano: the DX7's 61 keys, C1 to C6.
+const PIANO_LOWEST_NOTE: u8 = 36;
+const PIANO_HIGHEST_NOTE: u8 = 96;
+const PIANO_HEIGHT: f32 = 56.0;
+/// Black key size as a fraction of a white key's width and the strip height.
+const PIANO_BLACK_WIDTH: f32 = 0.6;
+const PIANO_BLACK_HEIGHT: f32 = 0.62;
+
+fn is_black_key(note: u8) -> bool {
+    matches!(note % 12, 1 | 3 | 6 | 8 | 10)
+}
+
+/// Key outlines of the on-screen piano filling `rect`, white keys first so
+/// the black keys are drawn over them.
+fn piano_key_rects(rect: egui::Rect) -> Vec<(u8, egui::Rect)> {
+    let notes = PIANO_LOWEST_NOTE..=PIANO_HIGHEST_NOTE;
+    let white_count = notes.clone().filter(|&n| !is_black_key(n)).count();
+    let white_width = rect.width() / white_count as f32;
+    let black_size = egui::vec2(
+        white_width * PIANO_BLACK_WIDTH,
+        rect.height() * PIANO_BLACK_HEIGHT,
+    );
+
+    let mut whites = Vec::with_capacity(white_count);
+    let mut blacks = Vec::new();
+    let mut white_index = 0;
+    for note in notes {
+        let x = rect.left() + white_index as f32 * white_width;
+        if is_black_key(note) {
+            // Centred on the line between its two white neighbours.
+            let min = egui::pos2(x - black_size.x / 2.0, rect.top());
+            blacks.push((note, egui::Rect::from_min_size(min, black_size)));
+        } else {
+            let min = egui::pos2(x, rect.top());
+            let size = egui::vec2(white_width, rect.height());
+            whites.push((note, egui::Rect::from_min_size(min, size)));
+            white_index += 1;
+        }
+    }
+    whites.extend(blacks);
+    whites
+}
+
+/// Note under `pos` on the on-screen piano in `rect`; black keys win where
+/// they overlap the white ones.
+fn piano_key_at(rect: egui::Rect, pos: egui::Pos2) -> Option<u8> {
+    piano_key_rects(rect)
+        .into_iter()
+        .rev()
+        .find(|(_, key)| key.contains(pos))
+        .map(|(note, _)| note)
+}
+
 /// Format a MIDI note number using the DX7/codebase convention
 /// (A-1 = MIDI 21, C3 = MIDI 60). Used by the Key Scaling Breakpoint slider.
-fn midi_note_name(midi: u8) -> String {
+pub(crate) fn midi_note_name(midi: u8) -> String {
     const NAMES: [&str; 12] = [
         "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
     ];
@@ -2720,6 +7916,21 @@ fn midi_note_name(midi: u8) -> String {
     format!("{}{}", NAMES[(midi as usize) % 12], octave)
 }
 
+/// Operator whose circle in the algorithm diagram contains `pos`.
+fn operator_at(positions: &[egui::Pos2; 6], pos: egui::Pos2, radius: f32) -> Option<usize> {
+    positions
+        .iter()
+        .position(|center| center.distance(pos) <= radius)
+}
+
+/// Output level (0-99, whole steps) for a pointer at `x` over a selector
+/// strip level bar; the bar's left edge is 0 and its right edge 99.
+fn level_at(bar: egui::Rect, x: f32) -> f32 {
+    ((x - bar.left()) / bar.width() * 99.0)
+        .round()
+        .clamp(0.0, 99.0)
+}
+
 /// Compact label for the Key Scaling curve dropdowns.
 /// Mirrors Dexed: -Lin / -Exp / +Exp / +Lin.
 fn key_scale_curve_label(curve: KeyScaleCurve) -> &'static str {
@@ -2734,7 +7945,7 @@ fn key_scale_curve_label(curve: KeyScaleCurve) -> &'static str {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::fm_synth::create_synth;
+    use crate::fm_synth::{create_synth, SynthEngine};
     use crate::presets::{PresetLfo, PresetOperator, PresetPitchEg};
 
     fn make_app() -> Dx7App {
@@ -2742,10 +7953,19 @@ mod tests {
     }
 
     fn make_app_with_presets(presets: Vec<Dx7Preset>) -> Dx7App {
+        make_app_with_presets_and_engine(presets).0
+    }
+
+    /// The app plus the engine its controller drives, standing in for the
+    /// audio thread.
+    fn make_app_and_engine() -> (Dx7App, SynthEngine) {
+        make_app_with_presets_and_engine(Vec::new())
+    }
+
+    fn make_app_with_presets_and_engine(presets: Vec<Dx7Preset>) -> (Dx7App, SynthEngine) {
         let (engine, controller) = create_synth(44_100.0);
-        let engine = Arc::new(Mutex::new(engine));
         let controller = Arc::new(Mutex::new(controller));
-        Dx7App::new_for_test(engine, controller, presets)
+        (Dx7App::new_for_test(controller, presets), engine)
     }
 
     fn make_preset(name: &str, alg: u8, collection: &str) -> Dx7Preset {
@@ -2782,7 +8002,7 @@ mod tests {
         assert_eq!(app.selected_operator, 0);
         assert_eq!(app.current_octave, 4);
         assert_eq!(app.display_text, "DX7 FM SYNTH");
-        assert!(app._audio_engine.is_none());
+        assert!(app.audio_engine.is_none());
         assert!(app._midi_handler.is_none());
         assert!(app.presets.is_empty());
         assert!(app.midi_channel_ui.is_none());
@@ -2797,19 +8017,16 @@ mod tests {
     }
 
     #[test]
-    fn lock_engine_and_controller_succeed() {
+    fn lock_controller_succeeds() {
         let app = make_app();
-        assert!(app.lock_engine().is_ok());
         assert!(app.lock_controller().is_ok());
     }
 
     #[test]
     fn update_snapshot_refreshes_field_from_controller() {
-        let mut app = make_app();
-        if let Ok(mut eng) = app.engine.lock() {
-            eng.set_algorithm(11);
-            eng.update_snapshot();
-        }
+        let (mut app, mut eng) = make_app_and_engine();
+        eng.set_algorithm(11);
+        eng.update_snapshot();
         app.update_snapshot();
         assert_eq!(app.snapshot.algorithm, 11);
     }
@@ -2943,36 +8160,380 @@ mod tests {
     }
 
     #[test]
-    fn load_sysex_handles_bulk_dump() {
-        let msg = crate::sysex::build_sysex_message(9, &vec![0u8; crate::sysex::VMEM_LEN]);
-        let path = temp_path("bulk.syx");
-        std::fs::write(&path, &msg).expect("write");
+    fn load_sysex_handles_bulk_dump() {
+        let msg = crate::sysex::build_sysex_message(9, &vec![0u8; crate::sysex::VMEM_LEN]);
+        let path = temp_path("bulk.syx");
+        std::fs::write(&path, &msg).expect("write");
+        let mut app = make_app();
+        app.sysex_path = path.to_string_lossy().into_owned();
+        app.load_sysex_from_path();
+        assert!(app.sysex_status.contains("bulk dump"));
+        // The bank joins the preset list under its file name, once.
+        let imported = |app: &Dx7App| {
+            app.presets
+                .iter()
+                .filter(|p| p.collection == "bulk.syx")
+                .count()
+        };
+        assert_eq!(imported(&app), 32);
+        assert_eq!(app.presets[app.selected_preset].collection, "bulk.syx");
+        app.load_sysex_from_path();
+        assert_eq!(imported(&app), 32);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn load_sysex_tolerates_a_headered_bank_and_lists_bad_voices() {
+        let mut data = vec![0u8; crate::sysex::VMEM_LEN];
+        data[14] = 120; // voice 1, OP6 output level
+        let mut file = b"EDITOR HEADER".to_vec();
+        file.extend(crate::sysex::build_sysex_message(9, &data));
+        let path = temp_path("headered.syx");
+        std::fs::write(&path, &file).expect("write");
+        let mut app = make_app();
+        app.sysex_path = path.to_string_lossy().into_owned();
+        app.load_sysex_from_path();
+        assert!(app.sysex_status.contains("13-byte header skipped"));
+        assert!(app.sysex_status.contains("1 bad voices"));
+        assert_eq!(
+            app.presets
+                .iter()
+                .filter(|p| p.collection == "headered.syx")
+                .count(),
+            32
+        );
+        assert_eq!(app.bank_errors.len(), 1);
+        assert_eq!(app.bank_errors[0].0, "headered.syx");
+
+        // Reloading replaces the bank's problems instead of piling them up.
+        app.load_sysex_from_path();
+        assert_eq!(app.bank_errors.len(), 1);
+        app.display_mode = DisplayMode::Library;
+        run_one_frame(|ctx| app.render(ctx));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn save_user_preset_writes_a_file_and_lists_it_once() {
+        let dir = temp_path("user-presets");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut app = make_app_with_presets(vec![make_preset("ONE", 1, "edu")]);
+        app.user_presets_dir = Some(dir.clone());
+        app.snapshot.preset_name = "MY VOICE".to_string();
+
+        app.save_user_preset();
+        assert!(app.library_status.starts_with("Saved"));
+        assert_eq!(app.presets.len(), 2);
+        assert_eq!(app.selected_preset, 1);
+        assert_eq!(app.presets[1].collection, USER_COLLECTION);
+
+        // Saving again under the same name replaces the entry.
+        app.save_user_preset();
+        assert_eq!(app.presets.len(), 2);
+        let loaded = user_presets::load_dir(&dir);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "MY VOICE");
+
+        app.display_mode = DisplayMode::Library;
+        run_one_frame(|ctx| app.render(ctx));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // ---------------------------------------------------------------------
+    // Render path coverage — drives the full GUI for one frame per mode.
+    // ---------------------------------------------------------------------
+
+    #[test]
+    fn render_voice_mode_completes_without_panic() {
+        let mut app = make_app_with_presets(vec![
+            make_preset("ONE", 1, "edu"),
+            make_preset("TWO", 5, "mark"),
+        ]);
+        app.display_mode = DisplayMode::Voice;
+        run_one_frame(|ctx| app.render(ctx));
+    }
+
+    #[test]
+    fn render_library_mode_completes_without_panic() {
+        let mut app = make_app_with_presets(vec![
+            make_preset("BASS 1", 1, "edu"),
+            make_preset("E.PIANO 1", 5, "mark"),
+        ]);
+        app.display_mode = DisplayMode::Library;
+        app.library_ab = [Some(0), Some(1)];
+        app.preview = Some(PresetPreview::new(44_100.0).0);
+        run_one_frame(|ctx| app.render(ctx));
+    }
+
+    #[test]
+    fn library_edits_are_saved_to_the_index() {
+        let path = temp_path("library.json");
+        let _ = std::fs::remove_file(&path);
+        let mut app = make_app_with_presets(vec![make_preset("BASS 1", 1, "edu")]);
+        app.library_path = Some(path.clone());
+        app.library.set_favorite(&app.presets[0], true);
+        app.save_library();
+        assert!(app.library_status.is_empty(), "{}", app.library_status);
+        let loaded = Library::load_or_default(&path);
+        assert!(loaded.is_favorite(&app.presets[0]));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn render_settings_dialog_completes_without_panic() {
+        let mut app = make_app();
+        app.show_settings = true;
+        app.settings.theme = Theme::Dark;
+        run_one_frame(|ctx| app.render(ctx));
+        assert!(app.show_settings);
+    }
+
+    #[test]
+    fn settings_are_saved_and_restored() {
+        let path = temp_path("settings.json");
+        let _ = std::fs::remove_file(&path);
+        let (mut app, mut engine) = make_app_and_engine();
+        app.settings_path = Some(path.clone());
+        app.current_octave = 2;
+        app.settings.theme = Theme::Dark;
+        app.lock_controller().unwrap().set_master_volume(0.4);
+        engine.process_commands();
+        engine.update_snapshot();
+        app.update_snapshot();
+        app.save_settings();
+
+        let (mut restored, mut engine) = make_app_and_engine();
+        restored.apply_settings(AppSettings::load_or_default(&path));
+        engine.process_commands();
+        engine.update_snapshot();
+        restored.update_snapshot();
+        assert_eq!(restored.current_octave, 2);
+        assert_eq!(restored.settings.theme, Theme::Dark);
+        assert!((restored.snapshot.master_volume - 0.4).abs() < 1e-6);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn midi_learn_binds_the_next_cc_and_is_saved() {
+        let path = temp_path("midi_learn.json");
+        let _ = std::fs::remove_file(&path);
+        let mut app = make_app();
+        app.settings_path = Some(path.clone());
+        let rate = DataEntryParam::LfoRate;
+
+        app.toggle_midi_learn(rate);
+        app.toggle_midi_learn(rate);
+        assert_eq!(app.midi_learn.lock().unwrap().armed(), None);
+        app.toggle_midi_learn(rate);
+        assert_eq!(app.midi_learn.lock().unwrap().armed(), Some(rate));
+
+        // What the MIDI input callback does with the next CC.
+        app.midi_learn.lock().unwrap().control_change(74, 100);
+        run_one_frame(|ctx| app.render(ctx));
+        assert_eq!(app.display_text, "CC 74 → LFO SPEED");
+        app.save_settings();
+
+        let mut restored = make_app();
+        restored.apply_settings(AppSettings::load_or_default(&path));
+        let bindings = restored.midi_learn.lock().unwrap().bindings().clone();
+        assert_eq!(bindings.get(74), Some(rate));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn ratio_readout_names_the_interval_and_the_last_note_pitch() {
+        let (mut app, mut engine) = make_app_and_engine();
+        assert_eq!(app.ratio_readout(3.0, 0.0), "3.00 = +1 oct +5th");
+        app.lock_controller().unwrap().note_on(60, 100);
+        engine.process_commands();
+        engine.update_snapshot();
+        app.update_snapshot();
+        assert_eq!(
+            app.ratio_readout(2.0, 0.0),
+            "2.00 = +1 octave | C3: 523.3 Hz"
+        );
+    }
+
+    #[test]
+    fn operator_at_hits_inside_the_circles_only() {
+        let mut positions = [egui::pos2(0.0, 0.0); 6];
+        for (i, pos) in positions.iter_mut().enumerate() {
+            *pos = egui::pos2(40.0 * i as f32, 20.0);
+        }
+        assert_eq!(
+            operator_at(&positions, egui::pos2(82.0, 25.0), 11.0),
+            Some(2)
+        );
+        assert_eq!(operator_at(&positions, egui::pos2(60.0, 20.0), 11.0), None);
+    }
+
+    #[test]
+    fn level_bar_maps_the_pointer_across_0_to_99() {
+        let bar = egui::Rect::from_min_size(egui::pos2(100.0, 0.0), egui::vec2(90.0, 10.0));
+        assert_eq!(level_at(bar, 100.0), 0.0);
+        assert_eq!(level_at(bar, 145.0), 50.0);
+        assert_eq!(level_at(bar, 190.0), 99.0);
+        // Dragging past either end pins the level there.
+        assert_eq!(level_at(bar, 20.0), 0.0);
+        assert_eq!(level_at(bar, 400.0), 99.0);
+    }
+
+    #[test]
+    fn wheel_over_the_diagram_steps_whole_algorithms() {
+        let (mut app, mut engine) = make_app_and_engine();
+        let algorithm = |app: &mut Dx7App, engine: &mut SynthEngine| {
+            engine.process_commands();
+            engine.update_snapshot();
+            app.update_snapshot();
+            app.snapshot.algorithm
+        };
+        let start = algorithm(&mut app, &mut engine);
+
+        // Half a notch is not enough; the second half completes it.
+        app.scroll_algorithm(-ALGORITHM_SCROLL_NOTCH / 2.0);
+        assert_eq!(algorithm(&mut app, &mut engine), start);
+        app.scroll_algorithm(-ALGORITHM_SCROLL_NOTCH / 2.0);
+        assert_eq!(algorithm(&mut app, &mut engine), start + 1);
+
+        app.scroll_algorithm(ALGORITHM_SCROLL_NOTCH * 40.0);
+        assert_eq!(algorithm(&mut app, &mut engine), 1);
+    }
+
+    #[test]
+    fn stepping_the_algorithm_points_data_entry_at_it() {
+        let (mut app, mut engine) = make_app_and_engine();
+        assert_eq!(app.lcd.param(), None);
+        run_one_frame(|ctx| app.render(ctx));
+
+        app.step_algorithm(1);
+        assert_eq!(app.lcd.param(), Some(DataEntryParam::Algorithm));
+        engine.process_commands();
+        engine.update_snapshot();
+        app.update_snapshot();
+        assert_eq!(DataEntryParam::Algorithm.value(&app.snapshot), 2);
+
+        app.lcd
+            .follow(DataEntryParam::Operator(2, OperatorField::EgLevel(0)));
+        run_one_frame(|ctx| app.render(ctx));
+    }
+
+    #[test]
+    fn lcd_buttons_page_through_parameters_and_voices() {
+        let (mut app, mut engine) = make_app_and_engine();
+        app.presets = vec![
+            Dx7Preset {
+                name: "FIRST".to_string(),
+                ..Dx7Preset::default()
+            },
+            Dx7Preset {
+                name: "SECOND".to_string(),
+                ..Dx7Preset::default()
+            },
+        ];
+        app.selected_preset = 0;
+
+        // MEMORY: the page buttons step the voice.
+        app.press_lcd(LcdButton::PageNext);
+        assert_eq!(app.selected_preset, 1);
+        app.press_lcd(LcdButton::PageNext);
+        assert_eq!(app.selected_preset, 0);
+
+        app.press_lcd(LcdButton::Edit);
+        app.press_lcd(LcdButton::OpSelect);
+        app.press_lcd(LcdButton::OpSelect);
+        assert_eq!(app.selected_operator, 2);
+        app.press_lcd(LcdButton::PageNext);
+        assert_eq!(
+            app.lcd.param(),
+            Some(DataEntryParam::Operator(2, OperatorField::Feedback))
+        );
+
+        app.press_lcd(LcdButton::Function);
+        app.press_lcd(LcdButton::PageNext);
+        let param = app.lcd.param().unwrap();
+        assert_eq!(param, DataEntryParam::VoiceMode);
+        if let Ok(mut ctrl) = app.lock_controller() {
+            param.set(&mut ctrl, 1);
+        }
+        engine.process_commands();
+        engine.update_snapshot();
+        app.update_snapshot();
+        assert_eq!(app.lcd.lines(&app.snapshot, 1)[1], "POLY/MONO   MONO");
+        run_one_frame(|ctx| app.render(ctx));
+    }
+
+    #[test]
+    fn transpose_buttons_step_by_semitone_and_octave() {
+        let (mut app, mut engine) = make_app_and_engine();
+        let mut sync = |app: &mut Dx7App| {
+            engine.process_commands();
+            engine.update_snapshot();
+            app.update_snapshot();
+        };
+        app.shift_transpose(12);
+        sync(&mut app);
+        app.shift_transpose(-1);
+        sync(&mut app);
+        assert_eq!(app.snapshot.transpose_semitones, 11);
+        app.shift_transpose(24);
+        sync(&mut app);
+        assert_eq!(app.snapshot.transpose_semitones, 24);
+        run_one_frame(|ctx| app.render(ctx));
+    }
+
+    #[test]
+    fn part_b_voice_comes_from_the_preset_list() {
+        let presets = vec![
+            make_preset("E.PIANO 1", 5, "edu"),
+            make_preset("STRINGS 1", 2, "edu"),
+        ];
+        let (mut app, mut engine) = make_app_with_presets_and_engine(presets);
+        app.load_part_b(1);
+        app.lock_controller()
+            .unwrap()
+            .set_performance(crate::performance::Performance {
+                mode: PerformanceMode::Split,
+                ..Default::default()
+            });
+        engine.process_commands();
+        engine.update_snapshot();
+        app.update_snapshot();
+        assert_eq!(app.snapshot.part_b_name, "STRINGS 1");
+        assert_eq!(app.snapshot.performance.mode, PerformanceMode::Split);
+        run_one_frame(|ctx| app.render(ctx));
+    }
+
+    #[test]
+    fn operator_tooltip_shows_ratio_and_level() {
         let mut app = make_app();
-        app.sysex_path = path.to_string_lossy().into_owned();
-        app.load_sysex_from_path();
-        assert!(app.sysex_status.contains("bulk dump"));
-        let _ = std::fs::remove_file(&path);
+        app.snapshot.operators[1].frequency_ratio = 2.0;
+        app.snapshot.operators[1].output_level = 80.0;
+        let info = algorithms::get_algorithm_info(1);
+        let text = app.operator_tooltip(1, &info);
+        assert!(text.starts_with("OP2 modulator"), "{text}");
+        assert!(text.contains("Ratio 2.00 (+1 octave)"), "{text}");
+        assert!(text.contains("Level 80"), "{text}");
     }
 
-    // ---------------------------------------------------------------------
-    // Render path coverage — drives the full GUI for one frame per mode.
-    // ---------------------------------------------------------------------
-
     #[test]
-    fn render_voice_mode_completes_without_panic() {
-        let mut app = make_app_with_presets(vec![
-            make_preset("ONE", 1, "edu"),
-            make_preset("TWO", 5, "mark"),
-        ]);
-        app.display_mode = DisplayMode::Voice;
+    fn render_operator_mode_completes_without_panic() {
+        let mut app = make_app();
+        app.display_mode = DisplayMode::Operator;
         run_one_frame(|ctx| app.render(ctx));
     }
 
     #[test]
-    fn render_operator_mode_completes_without_panic() {
-        let mut app = make_app();
+    fn pinned_operator_panel_renders_next_to_voice_page() {
+        let presets = vec![make_preset("FOO", 1, "edu"), make_preset("BAR", 2, "edu")];
+        let mut app = make_app_with_presets(presets);
+        app.pin_operator_panel = true;
+        app.display_mode = DisplayMode::Voice;
+        app.selected_operator = 3;
+        run_one_frame(|ctx| app.render(ctx));
         app.display_mode = DisplayMode::Operator;
         run_one_frame(|ctx| app.render(ctx));
+        assert!(app.pin_operator_panel);
+        assert_eq!(app.selected_operator, 3);
     }
 
     #[test]
@@ -3000,13 +8561,11 @@ mod tests {
     fn render_each_algorithm_in_operator_mode() {
         // Cycles through all 32 algorithms so the diagram layout / drawing code
         // is exercised on every routing.
-        let mut app = make_app();
+        let (mut app, mut eng) = make_app_and_engine();
         app.display_mode = DisplayMode::Operator;
         for alg in 1..=32u8 {
-            if let Ok(mut eng) = app.engine.lock() {
-                eng.set_algorithm(alg);
-                eng.update_snapshot();
-            }
+            eng.set_algorithm(alg);
+            eng.update_snapshot();
             run_one_frame(|ctx| app.render(ctx));
         }
     }
@@ -3039,27 +8598,23 @@ mod tests {
 
     #[test]
     fn render_with_active_voices_for_meter_path() {
-        let mut app = make_app();
+        let (mut app, mut eng) = make_app_and_engine();
         if let Ok(mut ctrl) = app.controller.lock() {
             ctrl.note_on(60, 100);
         }
-        if let Ok(mut eng) = app.engine.lock() {
-            eng.process_commands();
-            eng.update_snapshot();
-        }
+        eng.process_commands();
+        eng.update_snapshot();
         app.display_mode = DisplayMode::Operator;
         run_one_frame(|ctx| app.render(ctx));
     }
 
     #[test]
     fn render_with_effects_enabled_exercises_effect_drawers() {
-        let mut app = make_app();
-        if let Ok(mut eng) = app.engine.lock() {
-            eng.effects.chorus.enabled = true;
-            eng.effects.delay.enabled = true;
-            eng.effects.reverb.enabled = true;
-            eng.update_snapshot();
-        }
+        let (mut app, mut eng) = make_app_and_engine();
+        eng.effects.chorus.enabled = true;
+        eng.effects.delay.enabled = true;
+        eng.effects.reverb.enabled = true;
+        eng.update_snapshot();
         app.display_mode = DisplayMode::Effects;
         run_one_frame(|ctx| app.render(ctx));
     }
@@ -3071,14 +8626,12 @@ mod tests {
             crate::state_snapshot::VoiceMode::Mono,
             crate::state_snapshot::VoiceMode::MonoLegato,
         ] {
-            let mut app = make_app();
+            let (mut app, mut eng) = make_app_and_engine();
             if let Ok(mut ctrl) = app.controller.lock() {
                 ctrl.set_voice_mode(mode);
             }
-            if let Ok(mut eng) = app.engine.lock() {
-                eng.process_commands();
-                eng.update_snapshot();
-            }
+            eng.process_commands();
+            eng.update_snapshot();
             app.update_snapshot();
             run_one_frame(|ctx| app.render(ctx));
         }
@@ -3096,16 +8649,707 @@ mod tests {
 
     #[test]
     fn render_with_pitch_eg_active_in_lfo_panel() {
+        let (mut app, mut eng) = make_app_and_engine();
+        eng.pitch_eg.enabled = true;
+        eng.pitch_eg.level1 = 80.0;
+        eng.update_snapshot();
+        app.display_mode = DisplayMode::LFO;
+        run_one_frame(|ctx| app.render(ctx));
+    }
+
+    // ---------------------------------------------------------------------
+    // Preset preview on hover
+    // ---------------------------------------------------------------------
+
+    fn make_preview_app() -> Dx7App {
+        make_preview_app_and_engine().0
+    }
+
+    fn make_preview_app_and_engine() -> (Dx7App, SynthEngine) {
+        let presets = vec![make_preset("FOO", 1, "edu"), make_preset("BAR", 2, "edu")];
+        let (mut app, engine) = make_app_with_presets_and_engine(presets);
+        app.preview = Some(PresetPreview::new(44_100.0).0);
+        app.preview_on_hover = true;
+        (app, engine)
+    }
+
+    #[test]
+    fn preview_waits_for_hover_delay() {
+        let (mut app, mut eng) = make_preview_app_and_engine();
+        let ctx = egui::Context::default();
+        app.update_preview_hover(&ctx, Some(1));
+        assert!(app.preview_played.is_none());
+
+        // Pretend the pointer has been there long enough.
+        let past = std::time::Instant::now() - PREVIEW_HOVER_DELAY;
+        app.preview_hover = Some((1, past));
+        app.update_preview_hover(&ctx, Some(1));
+        assert_eq!(app.preview_played, Some(1));
+        // Main engine is untouched by the audition.
+        eng.process_commands();
+        assert_ne!(eng.preset_name, "BAR");
+    }
+
+    #[test]
+    fn program_map_reaches_the_engine_and_follows_imports() {
+        let presets = vec![make_preset("FOO", 1, "edu"), make_preset("BAR", 2, "edu")];
+        let (mut app, mut eng) = make_app_with_presets_and_engine(presets);
+        app.settings
+            .program_map
+            .assign(10, &make_preset("SYX VOICE", 7, "rom1a"));
+        app.send_program_map();
+        app.lock_controller().unwrap().program_change(10);
+        eng.process_commands();
+        assert_eq!(eng.preset_name, "Init Voice");
+
+        // The mapped voice arrives with a bank import.
+        app.import_bank("rom1a", &[make_preset("SYX VOICE", 7, "")]);
+        app.lock_controller().unwrap().program_change(10);
+        eng.process_commands();
+        assert_eq!(eng.preset_name, "SYX VOICE");
+    }
+
+    #[test]
+    fn stepping_presets_auditions_each_one() {
+        let mut app = make_preview_app();
+        run_frame_with_key(&mut app, egui::Key::PageDown);
+        assert_eq!(app.selected_preset, 1);
+        assert_eq!(app.preview_played, Some(1));
+        assert!(app.preview.as_ref().unwrap().is_playing());
+
+        app.preview_on_hover = false;
+        app.stop_preview();
+        run_frame_with_key(&mut app, egui::Key::PageDown);
+        assert_eq!(app.selected_preset, 0);
+        assert_eq!(app.preview_played, None);
+    }
+
+    #[test]
+    fn preview_resets_when_pointer_leaves_list() {
+        let mut app = make_preview_app();
+        let ctx = egui::Context::default();
+        let past = std::time::Instant::now() - PREVIEW_HOVER_DELAY;
+        app.preview_hover = Some((0, past));
+        app.update_preview_hover(&ctx, Some(0));
+        assert_eq!(app.preview_played, Some(0));
+
+        app.update_preview_hover(&ctx, None);
+        assert!(app.preview_hover.is_none());
+        assert!(app.preview_played.is_none());
+    }
+
+    #[test]
+    fn preview_disabled_ignores_hover() {
+        let mut app = make_preview_app();
+        app.preview_on_hover = false;
+        let ctx = egui::Context::default();
+        app.update_preview_hover(&ctx, Some(0));
+        assert!(app.preview_hover.is_none());
+    }
+
+    #[test]
+    fn render_voice_mode_with_preview_completes_without_panic() {
+        let mut app = make_preview_app();
+        run_one_frame(|ctx| app.render(ctx));
+    }
+
+    // ---------------------------------------------------------------------
+    // Key bindings
+    // ---------------------------------------------------------------------
+
+    /// Run one frame with a single key press event.
+    fn run_frame_with_key(app: &mut Dx7App, key: egui::Key) {
+        run_frame_with_key_and_modifiers(app, key, egui::Modifiers::NONE);
+    }
+
+    fn run_frame_with_key_and_modifiers(
+        app: &mut Dx7App,
+        key: egui::Key,
+        modifiers: egui::Modifiers,
+    ) {
+        let ctx = egui::Context::default();
+        let input = egui::RawInput {
+            events: vec![egui::Event::Key {
+                key,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers,
+            }],
+            modifiers,
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| app.render(ctx));
+    }
+
+    #[test]
+    fn render_keys_mode_completes_without_panic() {
         let mut app = make_app();
-        if let Ok(mut eng) = app.engine.lock() {
-            eng.pitch_eg.enabled = true;
-            eng.pitch_eg.level1 = 80.0;
-            eng.update_snapshot();
+        app.display_mode = DisplayMode::Keys;
+        app.capturing_key = Some(KeyAction::Panic);
+        run_one_frame(|ctx| app.render(ctx));
+    }
+
+    #[test]
+    fn capture_rebinds_action_to_next_pressed_key() {
+        let mut app = make_app();
+        app.capturing_key = Some(KeyAction::Panic);
+        run_frame_with_key(&mut app, egui::Key::F4);
+        assert!(app.capturing_key.is_none());
+        assert_eq!(app.key_bindings.key(KeyAction::Panic), egui::Key::F4);
+    }
+
+    #[test]
+    fn capture_cancelled_by_escape() {
+        let mut app = make_app();
+        app.capturing_key = Some(KeyAction::Panic);
+        run_frame_with_key(&mut app, egui::Key::Escape);
+        assert!(app.capturing_key.is_none());
+        assert_eq!(app.key_bindings.key(KeyAction::Panic), egui::Key::Space);
+    }
+
+    #[test]
+    fn bracket_keys_step_the_algorithm() {
+        let (mut app, mut engine) = make_app_and_engine();
+        let mut algorithm = |app: &mut Dx7App| {
+            engine.process_commands();
+            engine.update_snapshot();
+            app.update_snapshot();
+            app.snapshot.algorithm
+        };
+        run_frame_with_key(&mut app, egui::Key::CloseBracket);
+        assert_eq!(algorithm(&mut app), 2);
+        run_frame_with_key(&mut app, egui::Key::OpenBracket);
+        run_frame_with_key(&mut app, egui::Key::OpenBracket);
+        assert_eq!(algorithm(&mut app), 1);
+    }
+
+    #[test]
+    fn alt_number_keys_select_operators_instead_of_playing() {
+        let (mut app, mut engine) = make_app_and_engine();
+        run_frame_with_key_and_modifiers(&mut app, egui::Key::Num3, egui::Modifiers::ALT);
+        assert_eq!(app.selected_operator, 2);
+        assert!(app.last_key_times.is_empty());
+        engine.process_commands();
+        assert!(engine.voices().iter().all(|v| !v.active));
+
+        // Without Alt the key is still a note.
+        run_frame_with_key(&mut app, egui::Key::Num3);
+        assert_eq!(app.selected_operator, 2);
+        assert!(!app.last_key_times.is_empty());
+    }
+
+    #[test]
+    fn plus_and_minus_nudge_the_focused_slider() {
+        let mut app = make_app();
+        let ctx = egui::Context::default();
+        let mut value = 10.0_f32;
+        let mut frame = |key: Option<egui::Key>, value: &mut f32| {
+            let input = egui::RawInput {
+                events: key
+                    .map(|key| egui::Event::Key {
+                        key,
+                        physical_key: None,
+                        pressed: true,
+                        repeat: false,
+                        modifiers: egui::Modifiers::NONE,
+                    })
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            };
+            let _ = ctx.run(input, |ctx| {
+                app.handle_keyboard_input(ctx);
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let slider = ui.add(egui::Slider::new(value, 0.0..=99.0).integer());
+                    if key.is_none() {
+                        slider.request_focus();
+                    }
+                });
+            });
+        };
+        frame(None, &mut value);
+        frame(Some(egui::Key::Plus), &mut value);
+        assert_eq!(value, 11.0);
+        frame(Some(egui::Key::Minus), &mut value);
+        frame(Some(egui::Key::Minus), &mut value);
+        assert_eq!(value, 9.0);
+    }
+
+    #[test]
+    fn debug_panel_opens_with_ctrl_shift_d_and_shows_the_dump() {
+        let (mut app, mut engine) = make_app_and_engine();
+        let chord = egui::Modifiers::COMMAND | egui::Modifiers::SHIFT;
+        run_frame_with_key_and_modifiers(&mut app, egui::Key::D, chord);
+        assert!(app.show_debug_panel);
+        engine.process_commands();
+        engine.update_snapshot();
+        app.update_snapshot();
+        let dump = app.snapshot.debug_dump.as_ref().expect("dump");
+        // D is a note key; the chord must not have played it.
+        assert!(dump.voices.is_empty());
+        assert!(dump.held_notes.is_empty());
+        app.update_debug_dump();
+        assert!(app.debug_dump.is_some());
+        // The panel asks again only after DEBUG_DUMP_INTERVAL, so the
+        // engine doesn't build a dump for every snapshot.
+        engine.process_commands();
+        engine.update_snapshot();
+        app.update_snapshot();
+        assert!(app.snapshot.debug_dump.is_none());
+
+        run_frame_with_key_and_modifiers(&mut app, egui::Key::D, chord);
+        assert!(!app.show_debug_panel);
+        engine.process_commands();
+        engine.update_snapshot();
+        app.update_snapshot();
+        assert!(app.snapshot.debug_dump.is_none());
+    }
+
+    #[test]
+    fn shortcut_key_toggles_the_cheat_sheet() {
+        let mut app = make_app();
+        run_frame_with_key(&mut app, egui::Key::F1);
+        assert!(app.show_shortcuts);
+        run_one_frame(|ctx| app.render(ctx));
+        run_frame_with_key(&mut app, egui::Key::F1);
+        assert!(!app.show_shortcuts);
+
+        app.key_bindings
+            .set(KeyAction::NextAlgorithm, egui::Key::F9);
+        let rows = app.shortcut_rows();
+        assert!(rows
+            .iter()
+            .any(|(keys, action)| keys.ends_with("F9") && action.contains("algorithm")));
+    }
+
+    #[test]
+    fn compare_keys_store_then_recall_a_slot() {
+        let (mut app, mut engine) = make_app_and_engine();
+        engine.update_snapshot();
+        app.update_snapshot();
+        let stored_algorithm = app.snapshot.algorithm;
+
+        run_frame_with_key(&mut app, egui::Key::F5);
+        assert_eq!(app.display_text, "STORED IN A");
+
+        app.lock_controller()
+            .unwrap()
+            .set_algorithm(stored_algorithm % 32 + 1);
+        engine.process_commands();
+        engine.update_snapshot();
+        app.update_snapshot();
+        assert_ne!(app.snapshot.algorithm, stored_algorithm);
+
+        run_frame_with_key(&mut app, egui::Key::F5);
+        assert_eq!(app.display_text, "COMPARE A");
+        engine.process_commands();
+        engine.update_snapshot();
+        app.update_snapshot();
+        assert_eq!(app.snapshot.algorithm, stored_algorithm);
+    }
+
+    /// Render one second, long enough for a released Init Voice note to end.
+    fn render_a_second(engine: &mut SynthEngine) {
+        let mut left = vec![0.0; 44_100];
+        let mut right = vec![0.0; 44_100];
+        engine.process_block(&mut left, &mut right);
+    }
+
+    /// Velocity of the sounding voice playing `note`.
+    fn voice_velocity(engine: &SynthEngine, note: u8) -> Option<f32> {
+        engine
+            .voices()
+            .iter()
+            .find(|v| v.active && v.note == note)
+            .map(|v| v.velocity)
+    }
+
+    #[test]
+    fn note_keys_play_the_velocity_for_the_held_modifier() {
+        let (mut app, mut eng) = make_app_and_engine();
+        app.key_bindings.velocity.normal = 80;
+        run_frame_with_key(&mut app, egui::Key::Z);
+        run_frame_with_key_and_modifiers(&mut app, egui::Key::X, egui::Modifiers::SHIFT);
+        run_frame_with_key_and_modifiers(&mut app, egui::Key::C, egui::Modifiers::CTRL);
+        eng.process_commands();
+        assert_eq!(voice_velocity(&eng, 60), Some(80.0 / 127.0));
+        assert_eq!(voice_velocity(&eng, 62), Some(1.0));
+        assert_eq!(voice_velocity(&eng, 64), Some(50.0 / 127.0));
+    }
+
+    #[test]
+    fn hold_key_latches_notes_until_pressed_again() {
+        let (mut app, mut eng) = make_app_and_engine();
+        run_frame_with_key(&mut app, egui::Key::Enter);
+        assert!(app.keyboard_hold);
+        if let Ok(mut ctrl) = app.controller.lock() {
+            ctrl.note_on(60, 100);
+            ctrl.note_off(60);
         }
-        app.display_mode = DisplayMode::LFO;
+        render_a_second(&mut eng);
+        assert!(voice_velocity(&eng, 60).is_some());
+
+        run_frame_with_key(&mut app, egui::Key::Enter);
+        assert!(!app.keyboard_hold);
+        render_a_second(&mut eng);
+        assert!(voice_velocity(&eng, 60).is_none());
+    }
+
+    #[test]
+    fn panic_key_lets_go_of_the_hold() {
+        let (mut app, mut eng) = make_app_and_engine();
+        run_frame_with_key(&mut app, egui::Key::Enter);
+        run_frame_with_key(&mut app, egui::Key::Space);
+        eng.process_commands();
+        eng.update_snapshot();
+        app.update_snapshot();
+        assert!(!app.keyboard_hold);
+        assert!(!app.snapshot.sustain_pedal);
+    }
+
+    /// Lay `app` out and return the context plus the piano panel's rect.
+    fn piano_test_context(app: &mut Dx7App) -> (egui::Context, egui::Rect) {
+        let ctx = egui::Context::default();
+        // The panel settles on its height in the second frame.
+        for _ in 0..2 {
+            let _ = ctx.run(egui::RawInput::default(), |ctx| app.render(ctx));
+        }
+        let panel = egui::containers::panel::PanelState::load(&ctx, egui::Id::new("piano"))
+            .expect("piano panel laid out")
+            .rect;
+        (ctx, panel)
+    }
+
+    fn run_pointer_frame(ctx: &egui::Context, app: &mut Dx7App, events: Vec<egui::Event>) {
+        let input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| app.render(ctx));
+    }
+
+    fn primary_button(pos: egui::Pos2, pressed: bool) -> egui::Event {
+        egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn clicking_the_piano_plays_until_release() {
+        let (mut app, mut eng) = make_app_and_engine();
+        let (ctx, panel) = piano_test_context(&mut app);
+        // Low on the lowest white key, below the black keys.
+        let pos = egui::pos2(panel.left() + 12.0, panel.bottom() - 12.0);
+
+        let press = vec![egui::Event::PointerMoved(pos), primary_button(pos, true)];
+        run_pointer_frame(&ctx, &mut app, press);
+        eng.process_commands();
+        assert_eq!(app.piano_note, Some(PIANO_LOWEST_NOTE));
+        assert!(voice_velocity(&eng, PIANO_LOWEST_NOTE).is_some());
+
+        run_pointer_frame(&ctx, &mut app, vec![primary_button(pos, false)]);
+        assert_eq!(app.piano_note, None);
+        render_a_second(&mut eng);
+        assert!(voice_velocity(&eng, PIANO_LOWEST_NOTE).is_none());
+    }
+
+    #[test]
+    fn dragging_across_the_piano_plays_a_glissando() {
+        let (mut app, mut eng) = make_app_and_engine();
+        let (ctx, panel) = piano_test_context(&mut app);
+        let c1 = egui::pos2(panel.left() + 12.0, panel.bottom() - 12.0);
+        let d1 = c1 + egui::vec2(panel.width() / 36.0, 0.0);
+
+        let press = vec![egui::Event::PointerMoved(c1), primary_button(c1, true)];
+        run_pointer_frame(&ctx, &mut app, press);
+        run_pointer_frame(&ctx, &mut app, vec![egui::Event::PointerMoved(d1)]);
+        assert_eq!(app.piano_note, Some(38));
+        render_a_second(&mut eng);
+        assert!(voice_velocity(&eng, 36).is_none());
+        assert!(voice_velocity(&eng, 38).is_some());
+
+        // Dragging off the strip lets go of the key.
+        let above = egui::pos2(d1.x, panel.top() - 40.0);
+        run_pointer_frame(&ctx, &mut app, vec![egui::Event::PointerMoved(above)]);
+        assert_eq!(app.piano_note, None);
+    }
+
+    #[test]
+    fn piano_lights_keys_held_over_midi() {
+        let (mut app, mut eng) = make_app_and_engine();
+        if let Ok(mut ctrl) = app.controller.lock() {
+            ctrl.note_on(72, 100);
+        }
+        eng.process_commands();
+        eng.update_snapshot();
+        app.update_snapshot();
+        let lit = app.piano_lit_keys();
+        assert!(lit[72]);
+        assert_eq!(lit.iter().filter(|&&l| l).count(), 1);
+    }
+
+    #[test]
+    fn piano_keys_cover_the_dx7_range_without_gaps() {
+        let rect = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(720.0, 60.0));
+        let keys = piano_key_rects(rect);
+        assert_eq!(keys.len(), 61);
+        let whites: Vec<_> = keys.iter().filter(|(n, _)| !is_black_key(*n)).collect();
+        assert_eq!(whites.len(), 36);
+        assert_eq!(whites[0].1.left(), 0.0);
+        assert!((whites[35].1.right() - 720.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn piano_hit_test_prefers_black_keys() {
+        let rect = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(720.0, 60.0));
+        // White keys are 20 px wide; C#1 sits on the C1 / D1 boundary.
+        assert_eq!(piano_key_at(rect, egui::pos2(20.0, 10.0)), Some(37));
+        assert_eq!(piano_key_at(rect, egui::pos2(20.0, 55.0)), Some(38));
+        assert_eq!(piano_key_at(rect, egui::pos2(5.0, 55.0)), Some(36));
+        assert_eq!(piano_key_at(rect, egui::pos2(719.0, 30.0)), Some(96));
+        assert_eq!(piano_key_at(rect, egui::pos2(800.0, 30.0)), None);
+    }
+
+    #[test]
+    fn octave_key_follows_rebinding() {
+        let mut app = make_app();
+        app.key_bindings.set(KeyAction::OctaveUp, egui::Key::F8);
+        run_frame_with_key(&mut app, egui::Key::F8);
+        assert_eq!(app.current_octave, 5);
+        run_frame_with_key(&mut app, egui::Key::ArrowUp);
+        assert_eq!(app.current_octave, 5);
+    }
+
+    #[test]
+    fn next_display_mode_key_cycles_pages() {
+        let mut app = make_app();
+        run_frame_with_key(&mut app, egui::Key::Tab);
+        assert!(app.display_mode == DisplayMode::Library);
+        run_frame_with_key(&mut app, egui::Key::Tab);
+        assert!(app.display_mode == DisplayMode::Operator);
+    }
+
+    #[test]
+    fn preset_keys_wrap_around_the_list() {
+        let presets = vec![make_preset("FOO", 1, "edu"), make_preset("BAR", 2, "edu")];
+        let mut app = make_app_with_presets(presets);
+        run_frame_with_key(&mut app, egui::Key::PageUp);
+        assert_eq!(app.selected_preset, 1);
+        run_frame_with_key(&mut app, egui::Key::PageDown);
+        assert_eq!(app.selected_preset, 0);
+    }
+
+    #[test]
+    fn rate_scaling_shortens_high_keys_and_auditions_them() {
+        let rates = [90.0, 60.0, 50.0, 50.0];
+        let levels = [99.0, 80.0, 60.0, 0.0];
+        let flat: Vec<_> = RATE_SCALING_KEYS
+            .iter()
+            .map(|&note| key_envelope_times(0.0, note, rates, levels))
+            .collect();
+        assert!(flat.iter().all(|t| *t == flat[0]));
+        let [c1, c3, c6] =
+            RATE_SCALING_KEYS.map(|note| key_envelope_times(7.0, note, rates, levels));
+        assert!(c1.release > c3.release && c3.release > c6.release);
+        assert!(c1.to_sustain > c6.to_sustain);
+
+        assert_eq!(format_seconds(Some(0.0421)), "42 ms");
+        assert_eq!(format_seconds(Some(3.5)), "3.50 s");
+        assert_eq!(format_seconds(None), "hold");
+        assert_eq!(rate_plot_height(Some(RATE_PLOT_MIN_SECONDS)), 0.0);
+        assert_eq!(rate_plot_height(None), 1.0);
+
+        let (mut app, mut eng) = make_app_and_engine();
+        app.display_mode = DisplayMode::Operator;
+        run_one_frame(|ctx| app.render(ctx));
+        app.hold_audition(Some(96), 100);
+        eng.process_commands();
+        eng.update_snapshot();
+        app.update_snapshot();
+        assert!(app.snapshot.key_held(96));
+        app.hold_audition(None, 100);
+        eng.process_commands();
+        eng.update_snapshot();
+        app.update_snapshot();
+        assert!(!app.snapshot.key_held(96));
+    }
+
+    #[test]
+    fn save_key_bindings_without_path_is_a_no_op() {
+        let mut app = make_app();
+        app.save_key_bindings();
+        assert!(app.key_bindings_status.is_empty());
+    }
+
+    // ---------------------------------------------------------------------
+    // Oscilloscope
+    // ---------------------------------------------------------------------
+
+    #[test]
+    fn scope_trigger_finds_first_rising_zero_crossing() {
+        let samples = [0.5, -0.2, -0.1, 0.3, 0.6, -0.4, 0.2, 0.1];
+        assert_eq!(scope_trigger_index(&samples, 4), 3);
+        // No crossing leaves enough room for the window: free-run from 0.
+        assert_eq!(scope_trigger_index(&samples, 7), 0);
+        assert_eq!(scope_trigger_index(&[0.0; 16], 8), 0);
+    }
+
+    #[test]
+    fn render_scope_mode_draws_engine_output() {
+        let (mut app, mut eng) = make_app_and_engine();
+        for _ in 0..SCOPE_READ {
+            eng.process_stereo();
+        }
+        app.display_mode = DisplayMode::Scope;
+        run_one_frame(|ctx| app.render(ctx));
+    }
+
+    #[test]
+    fn choosing_an_audio_host_resets_the_device_and_keeps_it_afterwards() {
+        let mut app = make_app();
+        app.audio_settings.device = Some("hw:0".to_string());
+        app.audio_settings.buffer_size = Some(128);
+        app.audio_devices = Some(Vec::new());
+
+        app.select_audio_host(Some("JACK".to_string()));
+        assert_eq!(app.audio_settings.host.as_deref(), Some("JACK"));
+        assert_eq!(app.audio_settings.device, None);
+        assert_eq!(app.audio_settings.buffer_size, None);
+        assert!(
+            app.audio_devices.is_none(),
+            "device list reloads from the new host"
+        );
+
+        app.select_audio_device(Some("system".to_string()));
+        assert_eq!(app.audio_settings.host.as_deref(), Some("JACK"));
+        assert_eq!(app.audio_settings.device.as_deref(), Some("system"));
+    }
+
+    #[test]
+    fn audio_warning_follows_new_underruns_and_high_load() {
+        let mut app = make_app();
+        assert_eq!(app.audio_warning(), None);
+
+        app.snapshot.audio_stats.underruns = 2;
+        let warning = app
+            .audio_warning()
+            .expect("new underruns raise the warning");
+        assert!(warning.contains("x2"), "{warning}");
+        app.xrun_warning_until = Some(std::time::Instant::now());
+        assert_eq!(app.audio_warning(), None, "no new underruns: it times out");
+
+        app.snapshot.audio_stats.cpu_load = 0.95;
+        assert!(app.audio_warning().unwrap().contains("CPU 95%"));
+        run_one_frame(|ctx| app.render(ctx));
+    }
+
+    #[test]
+    fn update_snapshot_only_copies_newly_published_snapshots() {
+        let (mut app, mut eng) = make_app_and_engine();
+        app.update_snapshot();
+        app.snapshot.algorithm = 17; // stand-in for a stale local edit
+
+        app.update_snapshot();
+        assert_eq!(app.snapshot.algorithm, 17, "nothing published: no copy");
+
+        eng.update_snapshot();
+        app.update_snapshot();
+        assert_eq!(app.snapshot.algorithm, 1);
+    }
+
+    #[test]
+    fn repaint_notifier_stops_on_drop() {
+        let (app, mut eng) = make_app_and_engine();
+        let watcher = app.controller.lock().unwrap().snapshot_watcher();
+        let notifier =
+            RepaintNotifier::spawn(egui::Context::default(), watcher, app.gui_fps.clone());
+        eng.update_snapshot();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        drop(notifier); // joins the thread
+    }
+
+    #[test]
+    fn frame_interval_follows_the_fps_cap() {
+        assert_eq!(frame_interval(50), std::time::Duration::from_millis(20));
+        assert_eq!(frame_interval(0), std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn display_mode_cycle_includes_scope() {
+        assert!(DisplayMode::Midi.next() == DisplayMode::Audio);
+        assert!(DisplayMode::Audio.next() == DisplayMode::Keys);
+        assert!(DisplayMode::Keys.next() == DisplayMode::Scope);
+        assert!(DisplayMode::Scope.next() == DisplayMode::Spectrum);
+        assert!(DisplayMode::Spectrum.next() == DisplayMode::Voice);
+    }
+
+    // ---------------------------------------------------------------------
+    // Spectrum analyzer
+    // ---------------------------------------------------------------------
+
+    #[test]
+    fn spectrum_axis_is_logarithmic() {
+        assert_eq!(spectrum_x_fraction(20.0, 20.0, 20_000.0), 0.0);
+        assert_eq!(spectrum_x_fraction(20_000.0, 20.0, 20_000.0), 1.0);
+        // 200 Hz and 2 kHz sit one and two decades up a three-decade axis.
+        assert!((spectrum_x_fraction(200.0, 20.0, 20_000.0) - 1.0 / 3.0).abs() < 1e-5);
+        assert!((spectrum_x_fraction(2_000.0, 20.0, 20_000.0) - 2.0 / 3.0).abs() < 1e-5);
+        assert_eq!(spectrum_x_fraction(5.0, 20.0, 20_000.0), 0.0);
+    }
+
+    #[test]
+    fn render_spectrum_mode_draws_engine_output() {
+        let (mut app, mut eng) = make_app_and_engine();
+        for _ in 0..SPECTRUM_SIZE {
+            eng.process_stereo();
+        }
+        app.display_mode = DisplayMode::Spectrum;
         run_one_frame(|ctx| app.render(ctx));
     }
 
+    // ---------------------------------------------------------------------
+    // Envelope editor geometry
+    // ---------------------------------------------------------------------
+
+    #[test]
+    fn env_segment_width_round_trips_through_rate() {
+        for rate in [0.0, 1.0, 25.0, 50.0, 98.0, 99.0] {
+            assert_eq!(env_rate_for_width(env_segment_width(rate)), rate);
+        }
+        assert_eq!(env_segment_width(99.0), ENV_MIN_SEGMENT);
+        assert_eq!(env_segment_width(0.0), 1.0);
+    }
+
+    #[test]
+    fn envelope_points_follow_dx7_stage_order() {
+        let pts = envelope_points([99.0, 50.0, 50.0, 0.0], [99.0, 75.0, 50.0, 0.0]);
+        assert_eq!(pts[0].1, 0.0); // starts from L4
+        assert_eq!(pts[1].1, 1.0);
+        assert_eq!(pts[3].1, pts[4].1); // sustain holds L3
+        assert_eq!(pts[5].1, 0.0);
+        assert!(pts.windows(2).all(|w| w[1].0 > w[0].0));
+        assert!(pts[5].0 <= ENV_TOTAL_WIDTH + 1e-6);
+    }
+
+    #[test]
+    fn envelope_drag_sets_rate_from_segment_length_and_level_from_height() {
+        let rates = [99.0, 50.0, 50.0, 50.0];
+        let levels = [99.0, 75.0, 50.0, 0.0];
+        let pts = envelope_points(rates, levels);
+        // Drag handle 2 (end of R2) so its segment is the full unit wide, half-way up.
+        let (rate, level) = envelope_drag_params(1, pts[1].0 + 1.0, 0.5, rates, levels);
+        assert_eq!(rate, 0.0);
+        assert_eq!(level, 50.0);
+        // Dragging left of the segment start clamps to the fastest rate.
+        let (rate, level) = envelope_drag_params(3, pts[4].0 - 0.5, 1.5, rates, levels);
+        assert_eq!(rate, 99.0);
+        assert_eq!(level, 99.0);
+    }
+
     // ---------------------------------------------------------------------
     // Constants are stable
     // ---------------------------------------------------------------------
@@ -0,0 +1,553 @@
+//! Computer-keyboard bindings for the GUI.
+//!
+//! The defaults reproduce the original hard-coded QWERTY layout (Z-M lower
+//! octave, Q-U upper octave, number row for the upper sharps). Every action
+//! can be rebound from the KEYS page and is persisted as a flat JSON map of
+//! action id → egui key name, e.g. `{"note_0": "Z", "panic": "Space"}`.
+//! The note velocities share the map as numbers (`"velocity_shift": "127"`).
+//! Unknown ids or key names are ignored on load so a hand-edited file never
+//! prevents startup.
+
+use crate::compare::{COMPARE_SLOTS, COMPARE_SLOT_NAMES};
+use eframe::egui::{Key, Modifiers};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Number of note keys: two octaves, C to B.
+pub const NOTE_KEY_COUNT: usize = 24;
+
+const FILE_NAME: &str = "keybindings.json";
+
+/// Something a key can trigger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    /// Semitone offset (0..24) above the C of the current octave.
+    Note(u8),
+    OctaveUp,
+    OctaveDown,
+    Panic,
+    NextDisplayMode,
+    PrevPreset,
+    NextPreset,
+    /// Toggles the sustain pedal, latching the notes played while it is on.
+    Hold,
+    PrevAlgorithm,
+    NextAlgorithm,
+    /// Step the focused slider or number field down / up.
+    NudgeDown,
+    NudgeUp,
+    /// Shows or hides the shortcut cheat sheet.
+    Shortcuts,
+    /// Recalls compare slot 0..4 (A-D); with Shift, stores into it.
+    Compare(u8),
+}
+
+impl KeyAction {
+    const FIXED: [KeyAction; 12] = [
+        KeyAction::OctaveUp,
+        KeyAction::OctaveDown,
+        KeyAction::Panic,
+        KeyAction::Hold,
+        KeyAction::NextDisplayMode,
+        KeyAction::PrevPreset,
+        KeyAction::NextPreset,
+        KeyAction::PrevAlgorithm,
+        KeyAction::NextAlgorithm,
+        KeyAction::NudgeDown,
+        KeyAction::NudgeUp,
+        KeyAction::Shortcuts,
+    ];
+
+    /// Every action, notes first, in the order shown in the editor.
+    pub fn all() -> impl Iterator<Item = KeyAction> {
+        (0..NOTE_KEY_COUNT as u8)
+            .map(KeyAction::Note)
+            .chain(Self::FIXED)
+            .chain((0..COMPARE_SLOTS as u8).map(KeyAction::Compare))
+    }
+
+    /// Stable identifier used in the persisted file.
+    pub fn id(&self) -> String {
+        match self {
+            KeyAction::Note(n) => format!("note_{}", n),
+            KeyAction::OctaveUp => "octave_up".to_string(),
+            KeyAction::OctaveDown => "octave_down".to_string(),
+            KeyAction::Panic => "panic".to_string(),
+            KeyAction::NextDisplayMode => "next_display_mode".to_string(),
+            KeyAction::PrevPreset => "prev_preset".to_string(),
+            KeyAction::NextPreset => "next_preset".to_string(),
+            KeyAction::Hold => "hold".to_string(),
+            KeyAction::PrevAlgorithm => "prev_algorithm".to_string(),
+            KeyAction::NextAlgorithm => "next_algorithm".to_string(),
+            KeyAction::NudgeDown => "nudge_down".to_string(),
+            KeyAction::NudgeUp => "nudge_up".to_string(),
+            KeyAction::Shortcuts => "shortcuts".to_string(),
+            KeyAction::Compare(slot) => {
+                format!(
+                    "compare_{}",
+                    COMPARE_SLOT_NAMES[*slot as usize].to_lowercase()
+                )
+            }
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<KeyAction> {
+        if let Some(n) = id.strip_prefix("note_") {
+            return n
+                .parse::<u8>()
+                .ok()
+                .filter(|&n| (n as usize) < NOTE_KEY_COUNT)
+                .map(KeyAction::Note);
+        }
+        Self::all().skip(NOTE_KEY_COUNT).find(|a| a.id() == id)
+    }
+
+    /// Human-readable label for the editor.
+    pub fn label(&self) -> String {
+        const NAMES: [&str; 12] = [
+            "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+        ];
+        match self {
+            KeyAction::Note(n) => {
+                let octave = if *n < 12 { "" } else { " +1" };
+                format!("{}{}", NAMES[(*n as usize) % 12], octave)
+            }
+            KeyAction::OctaveUp => "Octave up".to_string(),
+            KeyAction::OctaveDown => "Octave down".to_string(),
+            KeyAction::Panic => "Panic".to_string(),
+            KeyAction::NextDisplayMode => "Next page".to_string(),
+            KeyAction::PrevPreset => "Previous preset".to_string(),
+            KeyAction::NextPreset => "Next preset".to_string(),
+            KeyAction::Hold => "Hold".to_string(),
+            KeyAction::PrevAlgorithm => "Previous algorithm".to_string(),
+            KeyAction::NextAlgorithm => "Next algorithm".to_string(),
+            KeyAction::NudgeDown => "Nudge slider down".to_string(),
+            KeyAction::NudgeUp => "Nudge slider up".to_string(),
+            KeyAction::Shortcuts => "Shortcut list".to_string(),
+            KeyAction::Compare(slot) => {
+                format!("Compare slot {}", COMPARE_SLOT_NAMES[*slot as usize])
+            }
+        }
+    }
+}
+
+/// Built-in note layouts selectable from the editor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyboardLayout {
+    Qwerty,
+    Qwertz,
+    Azerty,
+}
+
+impl KeyboardLayout {
+    pub fn all() -> [KeyboardLayout; 3] {
+        [
+            KeyboardLayout::Qwerty,
+            KeyboardLayout::Qwertz,
+            KeyboardLayout::Azerty,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyboardLayout::Qwerty => "QWERTY",
+            KeyboardLayout::Qwertz => "QWERTZ",
+            KeyboardLayout::Azerty => "AZERTY",
+        }
+    }
+
+    /// Note keys for this layout, laid out on the same physical keys as the
+    /// QWERTY default.
+    pub fn note_keys(&self) -> [Key; NOTE_KEY_COUNT] {
+        let mut keys = [
+            Key::Z,    // C
+            Key::S,    // C#
+            Key::X,    // D
+            Key::D,    // D#
+            Key::C,    // E
+            Key::V,    // F
+            Key::G,    // F#
+            Key::B,    // G
+            Key::H,    // G#
+            Key::N,    // A
+            Key::J,    // A#
+            Key::M,    // B
+            Key::Q,    // C (octave up)
+            Key::Num2, // C#
+            Key::W,    // D
+            Key::Num3, // D#
+            Key::E,    // E
+            Key::R,    // F
+            Key::Num5, // F#
+            Key::T,    // G
+            Key::Num6, // G#
+            Key::Y,    // A
+            Key::Num7, // A#
+            Key::U,    // B
+        ];
+        match self {
+            KeyboardLayout::Qwerty => {}
+            KeyboardLayout::Qwertz => {
+                keys[0] = Key::Y;
+                keys[21] = Key::Z;
+            }
+            KeyboardLayout::Azerty => {
+                keys[0] = Key::W;
+                keys[11] = Key::Comma;
+                keys[12] = Key::A;
+                keys[14] = Key::Z;
+            }
+        }
+        keys
+    }
+}
+
+/// Note-on velocities for the computer keyboard: `normal` on its own, the
+/// other two while Shift or Ctrl (Cmd on macOS) is held.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyVelocity {
+    pub normal: u8,
+    pub shift: u8,
+    pub ctrl: u8,
+}
+
+impl Default for KeyVelocity {
+    fn default() -> Self {
+        Self {
+            normal: 100,
+            shift: 127,
+            ctrl: 50,
+        }
+    }
+}
+
+impl KeyVelocity {
+    /// Velocity for a note played with `modifiers` held; Shift wins when
+    /// both are down.
+    pub fn for_modifiers(&self, modifiers: Modifiers) -> u8 {
+        if modifiers.shift {
+            self.shift
+        } else if modifiers.ctrl || modifiers.command {
+            self.ctrl
+        } else {
+            self.normal
+        }
+    }
+
+    /// Persisted id and value of each velocity.
+    fn entries(&self) -> [(&'static str, u8); 3] {
+        [
+            ("velocity", self.normal),
+            ("velocity_shift", self.shift),
+            ("velocity_ctrl", self.ctrl),
+        ]
+    }
+
+    fn slot(&mut self, id: &str) -> Option<&mut u8> {
+        match id {
+            "velocity" => Some(&mut self.normal),
+            "velocity_shift" => Some(&mut self.shift),
+            "velocity_ctrl" => Some(&mut self.ctrl),
+            _ => None,
+        }
+    }
+}
+
+/// The active key → action map.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBindings {
+    notes: [Key; NOTE_KEY_COUNT],
+    octave_up: Key,
+    octave_down: Key,
+    panic: Key,
+    next_display_mode: Key,
+    prev_preset: Key,
+    next_preset: Key,
+    hold: Key,
+    prev_algorithm: Key,
+    next_algorithm: Key,
+    nudge_down: Key,
+    nudge_up: Key,
+    shortcuts: Key,
+    compare: [Key; COMPARE_SLOTS],
+    pub velocity: KeyVelocity,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            notes: KeyboardLayout::Qwerty.note_keys(),
+            octave_up: Key::ArrowUp,
+            octave_down: Key::ArrowDown,
+            panic: Key::Space,
+            next_display_mode: Key::Tab,
+            prev_preset: Key::PageUp,
+            next_preset: Key::PageDown,
+            hold: Key::Enter,
+            prev_algorithm: Key::OpenBracket,
+            next_algorithm: Key::CloseBracket,
+            nudge_down: Key::Minus,
+            nudge_up: Key::Plus,
+            shortcuts: Key::F1,
+            compare: [Key::F5, Key::F6, Key::F7, Key::F8],
+            velocity: KeyVelocity::default(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: KeyAction) -> Key {
+        match action {
+            KeyAction::Note(n) => self.notes[n as usize],
+            KeyAction::OctaveUp => self.octave_up,
+            KeyAction::OctaveDown => self.octave_down,
+            KeyAction::Panic => self.panic,
+            KeyAction::NextDisplayMode => self.next_display_mode,
+            KeyAction::PrevPreset => self.prev_preset,
+            KeyAction::NextPreset => self.next_preset,
+            KeyAction::Hold => self.hold,
+            KeyAction::PrevAlgorithm => self.prev_algorithm,
+            KeyAction::NextAlgorithm => self.next_algorithm,
+            KeyAction::NudgeDown => self.nudge_down,
+            KeyAction::NudgeUp => self.nudge_up,
+            KeyAction::Shortcuts => self.shortcuts,
+            KeyAction::Compare(slot) => self.compare[slot as usize],
+        }
+    }
+
+    fn slot(&mut self, action: KeyAction) -> &mut Key {
+        match action {
+            KeyAction::Note(n) => &mut self.notes[n as usize],
+            KeyAction::OctaveUp => &mut self.octave_up,
+            KeyAction::OctaveDown => &mut self.octave_down,
+            KeyAction::Panic => &mut self.panic,
+            KeyAction::NextDisplayMode => &mut self.next_display_mode,
+            KeyAction::PrevPreset => &mut self.prev_preset,
+            KeyAction::NextPreset => &mut self.next_preset,
+            KeyAction::Hold => &mut self.hold,
+            KeyAction::PrevAlgorithm => &mut self.prev_algorithm,
+            KeyAction::NextAlgorithm => &mut self.next_algorithm,
+            KeyAction::NudgeDown => &mut self.nudge_down,
+            KeyAction::NudgeUp => &mut self.nudge_up,
+            KeyAction::Shortcuts => &mut self.shortcuts,
+            KeyAction::Compare(slot) => &mut self.compare[slot as usize],
+        }
+    }
+
+    /// Bind `key` to `action`. If another action already owns `key` it takes
+    /// over `action`'s previous key, so no key ever triggers two actions.
+    pub fn set(&mut self, action: KeyAction, key: Key) {
+        let previous = self.key(action);
+        if let Some(other) = self.action_for(key) {
+            *self.slot(other) = previous;
+        }
+        *self.slot(action) = key;
+    }
+
+    /// The action bound to `key`, if any.
+    pub fn action_for(&self, key: Key) -> Option<KeyAction> {
+        KeyAction::all().find(|&a| self.key(a) == key)
+    }
+
+    /// Replace the note keys with a built-in layout; other bindings are kept
+    /// unless the layout needs one of their keys.
+    pub fn apply_layout(&mut self, layout: KeyboardLayout) {
+        for (i, key) in layout.note_keys().into_iter().enumerate() {
+            self.set(KeyAction::Note(i as u8), key);
+        }
+    }
+
+    fn to_map(&self) -> BTreeMap<String, String> {
+        let mut map: BTreeMap<String, String> = KeyAction::all()
+            .map(|a| (a.id(), self.key(a).name().to_string()))
+            .collect();
+        for (id, velocity) in self.velocity.entries() {
+            map.insert(id.to_string(), velocity.to_string());
+        }
+        map
+    }
+
+    fn from_map(map: &BTreeMap<String, String>) -> Self {
+        let mut bindings = Self::default();
+        for (id, name) in map {
+            if let Some(slot) = bindings.velocity.slot(id) {
+                match name.parse::<u8>() {
+                    Ok(v @ 1..=127) => *slot = v,
+                    _ => log::warn!("Ignoring velocity {} = {}", id, name),
+                }
+                continue;
+            }
+            match (KeyAction::from_id(id), Key::from_name(name)) {
+                (Some(action), Some(key)) => bindings.set(action, key),
+                _ => log::warn!("Ignoring key binding {} = {}", id, name),
+            }
+        }
+        bindings
+    }
+
+    /// Default location of the bindings file, or None when no config
+    /// directory can be determined.
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(FILE_NAME))
+    }
+
+    /// Load bindings from `path`, falling back to the defaults when the file
+    /// is missing or unreadable.
+    pub fn load_or_default(path: &Path) -> Self {
+        let content = match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => return Self::default(),
+        };
+        match serde_json::from_str::<BTreeMap<String, String>>(&content) {
+            Ok(map) => Self::from_map(&map),
+            Err(e) => {
+                log::warn!("Invalid key bindings file {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&self.to_map()).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+/// Per-user configuration directory for this application:
+/// `$XDG_CONFIG_HOME/synth-fm-rs`, `%APPDATA%\synth-fm-rs` or
+/// `$HOME/.config/synth-fm-rs`, in that order.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    let non_empty = |var: &str| std::env::var_os(var).filter(|v| !v.is_empty());
+    non_empty("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| non_empty("APPDATA").map(PathBuf::from))
+        .or_else(|| non_empty("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .map(|dir| dir.join("synth-fm-rs"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("synth-fm-rs-keys-{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn defaults_match_original_qwerty_map() {
+        let kb = KeyBindings::default();
+        assert_eq!(kb.key(KeyAction::Note(0)), Key::Z);
+        assert_eq!(kb.key(KeyAction::Note(13)), Key::Num2);
+        assert_eq!(kb.key(KeyAction::Note(23)), Key::U);
+        assert_eq!(kb.key(KeyAction::Panic), Key::Space);
+        assert_eq!(kb.key(KeyAction::OctaveUp), Key::ArrowUp);
+        assert_eq!(kb.key(KeyAction::NextAlgorithm), Key::CloseBracket);
+        assert_eq!(kb.key(KeyAction::NudgeUp), Key::Plus);
+    }
+
+    #[test]
+    fn default_bindings_are_unique() {
+        let kb = KeyBindings::default();
+        let keys: std::collections::HashSet<Key> = KeyAction::all().map(|a| kb.key(a)).collect();
+        assert_eq!(keys.len(), KeyAction::all().count());
+    }
+
+    #[test]
+    fn action_ids_round_trip() {
+        for action in KeyAction::all() {
+            assert_eq!(KeyAction::from_id(&action.id()), Some(action));
+        }
+        assert_eq!(KeyAction::from_id("note_24"), None);
+        assert_eq!(KeyAction::from_id("compare_b"), Some(KeyAction::Compare(1)));
+        assert_eq!(KeyAction::from_id("bogus"), None);
+    }
+
+    #[test]
+    fn set_swaps_with_previous_owner() {
+        let mut kb = KeyBindings::default();
+        kb.set(KeyAction::Panic, Key::Z);
+        assert_eq!(kb.key(KeyAction::Panic), Key::Z);
+        assert_eq!(kb.key(KeyAction::Note(0)), Key::Space);
+        assert_eq!(kb.action_for(Key::Z), Some(KeyAction::Panic));
+    }
+
+    #[test]
+    fn qwertz_layout_swaps_y_and_z() {
+        let mut kb = KeyBindings::default();
+        kb.apply_layout(KeyboardLayout::Qwertz);
+        assert_eq!(kb.key(KeyAction::Note(0)), Key::Y);
+        assert_eq!(kb.key(KeyAction::Note(21)), Key::Z);
+    }
+
+    #[test]
+    fn azerty_layout_keeps_bindings_unique() {
+        let mut kb = KeyBindings::default();
+        kb.apply_layout(KeyboardLayout::Azerty);
+        assert_eq!(kb.key(KeyAction::Note(0)), Key::W);
+        assert_eq!(kb.key(KeyAction::Note(12)), Key::A);
+        let keys: std::collections::HashSet<Key> = KeyAction::all().map(|a| kb.key(a)).collect();
+        assert_eq!(keys.len(), KeyAction::all().count());
+    }
+
+    #[test]
+    fn modifiers_pick_the_velocity_layer() {
+        let velocity = KeyVelocity::default();
+        assert_eq!(velocity.for_modifiers(Modifiers::NONE), 100);
+        assert_eq!(velocity.for_modifiers(Modifiers::SHIFT), 127);
+        assert_eq!(velocity.for_modifiers(Modifiers::CTRL), 50);
+        assert_eq!(
+            velocity.for_modifiers(Modifiers::SHIFT | Modifiers::CTRL),
+            127
+        );
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_path("round_trip.json");
+        let mut kb = KeyBindings::default();
+        kb.apply_layout(KeyboardLayout::Azerty);
+        kb.set(KeyAction::NextPreset, Key::F5);
+        kb.velocity.shift = 90;
+        kb.save(&path).expect("save");
+        assert_eq!(KeyBindings::load_or_default(&path), kb);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn load_missing_file_gives_defaults() {
+        let kb = KeyBindings::load_or_default(&temp_path("does_not_exist.json"));
+        assert_eq!(kb, KeyBindings::default());
+    }
+
+    #[test]
+    fn load_ignores_unknown_entries() {
+        let path = temp_path("lenient.json");
+        std::fs::create_dir_all(path.parent().unwrap()).expect("mkdir");
+        std::fs::write(
+            &path,
+            r#"{"panic": "Escape", "warp_drive": "F1", "note_0": "NotAKey",
+                "velocity": "64", "velocity_ctrl": "200"}"#,
+        )
+        .expect("write");
+        let kb = KeyBindings::load_or_default(&path);
+        assert_eq!(kb.key(KeyAction::Panic), Key::Escape);
+        assert_eq!(kb.key(KeyAction::Note(0)), Key::Z);
+        assert_eq!(kb.velocity.normal, 64);
+        assert_eq!(kb.velocity.ctrl, KeyVelocity::default().ctrl);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn load_invalid_json_gives_defaults() {
+        let path = temp_path("garbage.json");
+        std::fs::create_dir_all(path.parent().unwrap()).expect("mkdir");
+        std::fs::write(&path, "not json").expect("write");
+        assert_eq!(KeyBindings::load_or_default(&path), KeyBindings::default());
+        let _ = std::fs::remove_file(&path);
+    }
+}
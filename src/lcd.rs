@@ -0,0 +1,331 @@
+//! DX7 front-panel LCD: two lines of sixteen characters and the mode the
+//! panel is in — EDIT, FUNCTION or MEMORY — as on the hardware.
+//!
+//! Each of EDIT and FUNCTION has a list of pages, one parameter each, and
+//! remembers the page it was left on. The page's parameter is what DATA
+//! ENTRY and YES / NO edit; in MEMORY mode they pick a voice instead.
+//! Touching a control anywhere else in the editor turns to that
+//! parameter's page, so the LCD always shows what is being edited.
+
+use crate::data_entry::{ControllerField, DataEntryParam, OperatorField};
+use crate::state_snapshot::SynthSnapshot;
+
+/// Characters per LCD line.
+pub const COLUMNS: usize = 16;
+/// Operators OP SELECT cycles through.
+const OPERATORS: u8 = 6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LcdMode {
+    Edit,
+    Function,
+    /// Voice selection; the DX7 powers up in this mode.
+    #[default]
+    Memory,
+}
+
+/// Buttons around the LCD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LcdButton {
+    Edit,
+    Function,
+    Memory,
+    PagePrev,
+    PageNext,
+    OpSelect,
+}
+
+/// An EDIT page: a voice parameter, or an operator parameter shown for the
+/// operator OP SELECT picked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EditPage {
+    Voice(DataEntryParam),
+    Operator(OperatorField),
+}
+
+/// EDIT pages in the order of the DX7's voice-edit buttons.
+const EDIT_PAGES: [EditPage; 28] = [
+    EditPage::Voice(DataEntryParam::Algorithm),
+    EditPage::Operator(OperatorField::Feedback),
+    EditPage::Voice(DataEntryParam::LfoWaveform),
+    EditPage::Voice(DataEntryParam::LfoRate),
+    EditPage::Voice(DataEntryParam::LfoDelay),
+    EditPage::Voice(DataEntryParam::LfoPitchDepth),
+    EditPage::Voice(DataEntryParam::LfoAmpDepth),
+    EditPage::Voice(DataEntryParam::LfoKeySync),
+    EditPage::Voice(DataEntryParam::PitchModSensitivity),
+    EditPage::Operator(OperatorField::AmSensitivity),
+    EditPage::Operator(OperatorField::EgRate(0)),
+    EditPage::Operator(OperatorField::EgRate(1)),
+    EditPage::Operator(OperatorField::EgRate(2)),
+    EditPage::Operator(OperatorField::EgRate(3)),
+    EditPage::Operator(OperatorField::EgLevel(0)),
+    EditPage::Operator(OperatorField::EgLevel(1)),
+    EditPage::Operator(OperatorField::EgLevel(2)),
+    EditPage::Operator(OperatorField::EgLevel(3)),
+    EditPage::Operator(OperatorField::KeyScaleBreakpoint),
+    EditPage::Operator(OperatorField::KeyScaleLeftDepth),
+    EditPage::Operator(OperatorField::KeyScaleRightDepth),
+    EditPage::Operator(OperatorField::KeyScaleRate),
+    EditPage::Operator(OperatorField::Level),
+    EditPage::Operator(OperatorField::VelocitySensitivity),
+    EditPage::Operator(OperatorField::Coarse),
+    EditPage::Operator(OperatorField::Fine),
+    EditPage::Operator(OperatorField::Detune),
+    EditPage::Voice(DataEntryParam::Transpose),
+];
+
+/// FUNCTION pages in the order of the DX7's function buttons.
+const FUNCTION_PAGES: [DataEntryParam; 20] = [
+    DataEntryParam::MasterTune,
+    DataEntryParam::VoiceMode,
+    DataEntryParam::PitchBendRange,
+    DataEntryParam::PortamentoMode,
+    DataEntryParam::PortamentoGlissando,
+    DataEntryParam::PortamentoTime,
+    DataEntryParam::Controller(ControllerField::ModWheelPitchBias),
+    DataEntryParam::Controller(ControllerField::ModWheelEgBias),
+    DataEntryParam::Controller(ControllerField::FootVolume),
+    DataEntryParam::Controller(ControllerField::FootPitch),
+    DataEntryParam::Controller(ControllerField::FootAmp),
+    DataEntryParam::Controller(ControllerField::FootEgBias),
+    DataEntryParam::Controller(ControllerField::BreathPitch),
+    DataEntryParam::Controller(ControllerField::BreathAmp),
+    DataEntryParam::Controller(ControllerField::BreathEgBias),
+    DataEntryParam::Controller(ControllerField::BreathPitchBias),
+    DataEntryParam::Controller(ControllerField::AftertouchPitch),
+    DataEntryParam::Controller(ControllerField::AftertouchAmp),
+    DataEntryParam::Controller(ControllerField::AftertouchEgBias),
+    DataEntryParam::Controller(ControllerField::AftertouchPitchBias),
+];
+
+/// Mode, page and operator of the front panel.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lcd {
+    mode: LcdMode,
+    edit_page: usize,
+    function_page: usize,
+    /// Operator the EDIT operator pages show, 0-based.
+    operator: u8,
+}
+
+impl Lcd {
+    pub fn mode(&self) -> LcdMode {
+        self.mode
+    }
+
+    pub fn operator(&self) -> u8 {
+        self.operator
+    }
+
+    /// Current page, 1-based, and how many the mode has; None in MEMORY.
+    pub fn page(&self) -> Option<(usize, usize)> {
+        match self.mode {
+            LcdMode::Edit => Some((self.edit_page + 1, EDIT_PAGES.len())),
+            LcdMode::Function => Some((self.function_page + 1, FUNCTION_PAGES.len())),
+            LcdMode::Memory => None,
+        }
+    }
+
+    /// Act on a panel button. The page buttons wrap around; in MEMORY mode
+    /// they are left to the caller, which steps the voice. OP SELECT only
+    /// does something in EDIT mode.
+    pub fn press(&mut self, button: LcdButton) {
+        match button {
+            LcdButton::Edit => self.mode = LcdMode::Edit,
+            LcdButton::Function => self.mode = LcdMode::Function,
+            LcdButton::Memory => self.mode = LcdMode::Memory,
+            LcdButton::PagePrev => self.turn(-1),
+            LcdButton::PageNext => self.turn(1),
+            LcdButton::OpSelect => {
+                if self.mode == LcdMode::Edit {
+                    self.operator = (self.operator + 1) % OPERATORS;
+                }
+            }
+        }
+    }
+
+    fn turn(&mut self, step: isize) {
+        let (page, count) = match self.mode {
+            LcdMode::Edit => (&mut self.edit_page, EDIT_PAGES.len()),
+            LcdMode::Function => (&mut self.function_page, FUNCTION_PAGES.len()),
+            LcdMode::Memory => return,
+        };
+        *page = (*page as isize + step).rem_euclid(count as isize) as usize;
+    }
+
+    /// Parameter on the current page; None in MEMORY mode.
+    pub fn param(&self) -> Option<DataEntryParam> {
+        match self.mode {
+            LcdMode::Edit => Some(match EDIT_PAGES[self.edit_page] {
+                EditPage::Voice(param) => param,
+                EditPage::Operator(field) => DataEntryParam::Operator(self.operator, field),
+            }),
+            LcdMode::Function => Some(FUNCTION_PAGES[self.function_page]),
+            LcdMode::Memory => None,
+        }
+    }
+
+    /// Turn to the page that shows `param`, switching mode (and operator)
+    /// as needed.
+    pub fn follow(&mut self, param: DataEntryParam) {
+        if let Some(page) = FUNCTION_PAGES.iter().position(|&p| p == param) {
+            self.mode = LcdMode::Function;
+            self.function_page = page;
+            return;
+        }
+        let page = match param {
+            DataEntryParam::Operator(op, field) => {
+                self.operator = op % OPERATORS;
+                EDIT_PAGES
+                    .iter()
+                    .position(|&p| p == EditPage::Operator(field))
+            }
+            _ => EDIT_PAGES.iter().position(|&p| p == EditPage::Voice(param)),
+        };
+        if let Some(page) = page {
+            self.mode = LcdMode::Edit;
+            self.edit_page = page;
+        }
+    }
+
+    /// The two LCD lines, each exactly [`COLUMNS`] characters. `memory` is
+    /// the 1-based number of the current voice.
+    pub fn lines(&self, snapshot: &SynthSnapshot, memory: usize) -> [String; 2] {
+        let top = match self.mode {
+            LcdMode::Edit => format!(
+                "E{:02} ALG{:02} OP{}",
+                self.edit_page + 1,
+                snapshot.algorithm,
+                self.operator + 1
+            ),
+            LcdMode::Function => format!("F{:02} FUNCTION", self.function_page + 1),
+            LcdMode::Memory => "MEMORY SELECT".to_string(),
+        };
+        let bottom = match self.param() {
+            Some(param) => {
+                // The top line already names the operator.
+                let label = match param {
+                    DataEntryParam::Operator(_, field) => field.label(),
+                    _ => param.label(),
+                };
+                let value = param.format_value(param.value(snapshot));
+                row(&label, &value)
+            }
+            None => format!("{:03} {}", memory, snapshot.preset_name),
+        };
+        [fit(&top), fit(&bottom)]
+    }
+}
+
+/// `label` on the left and `value` on the right of one line, shortening
+/// the label when both don't fit.
+fn row(label: &str, value: &str) -> String {
+    let value: String = value.chars().take(COLUMNS).collect();
+    let room = COLUMNS - value.chars().count();
+    let label: String = label.chars().take(room.saturating_sub(1)).collect();
+    format!("{label:<room$}{value}")
+}
+
+/// Pad or cut to one LCD line.
+fn fit(text: &str) -> String {
+    let text: String = text.chars().take(COLUMNS).collect();
+    format!("{text:<COLUMNS$}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buttons_switch_modes_and_turn_pages() {
+        let mut lcd = Lcd::default();
+        assert_eq!(lcd.mode(), LcdMode::Memory);
+        assert_eq!(lcd.param(), None);
+        lcd.press(LcdButton::PageNext);
+        assert_eq!(lcd.page(), None);
+
+        lcd.press(LcdButton::Edit);
+        assert_eq!(lcd.param(), Some(DataEntryParam::Algorithm));
+        lcd.press(LcdButton::PagePrev);
+        assert_eq!(lcd.param(), Some(DataEntryParam::Transpose));
+        lcd.press(LcdButton::PageNext);
+        lcd.press(LcdButton::PageNext);
+        lcd.press(LcdButton::OpSelect);
+        assert_eq!(
+            lcd.param(),
+            Some(DataEntryParam::Operator(1, OperatorField::Feedback))
+        );
+
+        // Each mode keeps its own page.
+        lcd.press(LcdButton::Function);
+        lcd.press(LcdButton::PageNext);
+        assert_eq!(lcd.param(), Some(DataEntryParam::VoiceMode));
+        lcd.press(LcdButton::OpSelect);
+        lcd.press(LcdButton::Edit);
+        assert_eq!(lcd.page(), Some((2, EDIT_PAGES.len())));
+        assert_eq!(lcd.operator(), 1);
+
+        for _ in 0..5 {
+            lcd.press(LcdButton::OpSelect);
+        }
+        assert_eq!(lcd.operator(), 0);
+    }
+
+    #[test]
+    fn every_page_can_be_reached_by_touching_its_parameter() {
+        let mut pages = Vec::new();
+        let mut lcd = Lcd::default();
+        for mode in [LcdButton::Edit, LcdButton::Function] {
+            lcd.press(mode);
+            let (_, count) = lcd.page().unwrap();
+            for _ in 0..count {
+                pages.push(lcd.param().unwrap());
+                lcd.press(LcdButton::PageNext);
+            }
+        }
+        for op in 0..6 {
+            lcd.press(LcdButton::OpSelect);
+            pages.push(DataEntryParam::Operator(op, OperatorField::EgLevel(2)));
+        }
+        let mut touched = Lcd::default();
+        for param in pages {
+            touched.follow(param);
+            assert_eq!(touched.param(), Some(param), "{}", param.label());
+        }
+    }
+
+    #[test]
+    fn lines_fill_the_sixteen_columns() {
+        let mut snapshot = SynthSnapshot {
+            algorithm: 5,
+            preset_name: "E.PIANO 1".to_string(),
+            ..SynthSnapshot::default()
+        };
+        snapshot.operators[2].level3 = 42.0;
+        let mut lcd = Lcd::default();
+        assert_eq!(
+            lcd.lines(&snapshot, 7),
+            ["MEMORY SELECT   ", "007 E.PIANO 1   "]
+        );
+
+        lcd.follow(DataEntryParam::Operator(2, OperatorField::EgLevel(2)));
+        assert_eq!(
+            lcd.lines(&snapshot, 7),
+            ["E17 ALG05 OP3   ", "EG LEVEL 3    42"]
+        );
+        lcd.follow(DataEntryParam::Algorithm);
+        assert_eq!(lcd.lines(&snapshot, 7)[1], "ALGORITHM SELE 5");
+        lcd.follow(DataEntryParam::MasterTune);
+        assert_eq!(
+            lcd.lines(&snapshot, 7),
+            ["F01 FUNCTION    ", "MASTER TUNE   +0"]
+        );
+
+        snapshot.preset_name = "A NAME LONGER THAN THE LCD".to_string();
+        lcd.press(LcdButton::Memory);
+        for line in lcd.lines(&snapshot, 128) {
+            assert_eq!(line.chars().count(), COLUMNS);
+        }
+    }
+}
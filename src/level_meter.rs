@@ -0,0 +1,159 @@
+//! Output level metering, run on the audio thread.
+//!
+//! Reads the master bus right before the output saturator: that is the level
+//! the volume, effects and compressor control, and anything at or above 1.0
+//! there is being squashed by the `tanh`, so it lights the clip indicator.
+
+/// How fast the peak reading falls back, in dB per second.
+const PEAK_FALL_DB_PER_SEC: f32 = 20.0;
+
+/// Averaging time of the RMS reading.
+const RMS_WINDOW_MS: f32 = 300.0;
+
+/// How long the clip indicator stays lit after the last clipped sample.
+const CLIP_HOLD_SECONDS: f32 = 1.5;
+
+/// One reading of both channels, linear 0..; 1.0 = full scale.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MeterLevels {
+    pub peak: [f32; 2],
+    pub rms: [f32; 2],
+    pub clip: bool,
+}
+
+pub struct OutputMeter {
+    peak: [f32; 2],
+    mean_square: [f32; 2],
+    clip_countdown: u32,
+    peak_fall: f32,
+    rms_coeff: f32,
+    clip_hold_samples: u32,
+}
+
+impl OutputMeter {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut meter = Self {
+            peak: [0.0; 2],
+            mean_square: [0.0; 2],
+            clip_countdown: 0,
+            peak_fall: 0.0,
+            rms_coeff: 0.0,
+            clip_hold_samples: 0,
+        };
+        meter.set_sample_rate(sample_rate);
+        meter
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.peak_fall = 10.0_f32.powf(-PEAK_FALL_DB_PER_SEC / 20.0 / sample_rate);
+        self.rms_coeff = 1.0 - (-1.0 / (RMS_WINDOW_MS * 0.001 * sample_rate)).exp();
+        self.clip_hold_samples = (CLIP_HOLD_SECONDS * sample_rate) as u32;
+    }
+
+    pub fn process(&mut self, l: f32, r: f32) {
+        for (ch, x) in [l, r].into_iter().enumerate() {
+            let level = x.abs();
+            self.peak[ch] = level.max(self.peak[ch] * self.peak_fall);
+            self.mean_square[ch] += self.rms_coeff * (x * x - self.mean_square[ch]);
+            if level >= 1.0 {
+                self.clip_countdown = self.clip_hold_samples;
+            }
+        }
+        self.clip_countdown = self.clip_countdown.saturating_sub(1);
+    }
+
+    pub fn levels(&self) -> MeterLevels {
+        MeterLevels {
+            peak: self.peak,
+            rms: self.mean_square.map(f32::sqrt),
+            clip: self.clip_countdown > 0,
+        }
+    }
+}
+
+/// Peak output of each operator (OP1 – OP6), before the voice and master
+/// gains, falling at the same rate as the output meter.
+pub struct OperatorMeter {
+    peak: [f32; 6],
+    peak_fall: f32,
+}
+
+impl OperatorMeter {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut meter = Self {
+            peak: [0.0; 6],
+            peak_fall: 0.0,
+        };
+        meter.set_sample_rate(sample_rate);
+        meter
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.peak_fall = 10.0_f32.powf(-PEAK_FALL_DB_PER_SEC / 20.0 / sample_rate);
+    }
+
+    /// One sample: the loudest output of each operator across the voices.
+    pub fn process(&mut self, levels: [f32; 6]) {
+        for (peak, level) in self.peak.iter_mut().zip(levels) {
+            *peak = level.max(*peak * self.peak_fall);
+        }
+    }
+
+    pub fn levels(&self) -> [f32; 6] {
+        self.peak
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SR: f32 = 44_100.0;
+
+    #[test]
+    fn sine_reads_its_peak_and_rms() {
+        let mut meter = OutputMeter::new(SR);
+        for i in 0..SR as usize {
+            let x = 0.5 * (2.0 * std::f32::consts::PI * 441.0 * i as f32 / SR).sin();
+            meter.process(x, 0.0);
+        }
+        let levels = meter.levels();
+        assert!((levels.peak[0] - 0.5).abs() < 0.01);
+        assert!((levels.rms[0] - 0.5 * std::f32::consts::FRAC_1_SQRT_2).abs() < 0.01);
+        assert_eq!(levels.peak[1], 0.0);
+        assert!(!levels.clip);
+    }
+
+    #[test]
+    fn peak_falls_and_clip_holds_then_clears() {
+        let mut meter = OutputMeter::new(SR);
+        meter.process(0.0, 1.2);
+        assert!(meter.levels().clip);
+        for _ in 0..SR as usize {
+            meter.process(0.0, 0.0);
+        }
+        let levels = meter.levels();
+        // One second at 20 dB/s: a tenth of the 1.2 peak.
+        assert!((levels.peak[1] - 0.12).abs() < 0.005);
+        assert!(levels.clip);
+        for _ in 0..SR as usize {
+            meter.process(0.0, 0.0);
+        }
+        assert!(!meter.levels().clip);
+    }
+
+    #[test]
+    fn operator_meter_holds_each_operator_peak_and_falls() {
+        let mut meter = OperatorMeter::new(SR);
+        meter.process([0.5, 0.0, 1.0, 0.0, 0.0, 0.2]);
+        meter.process([0.1, 0.3, 0.0, 0.0, 0.0, 0.0]);
+        let levels = meter.levels();
+        assert!((levels[0] - 0.5).abs() < 1e-3);
+        assert_eq!(levels[1], 0.3);
+        assert_eq!(levels[3], 0.0);
+        for _ in 0..SR as usize {
+            meter.process([0.0; 6]);
+        }
+        assert!((meter.levels()[2] - 0.1).abs() < 0.005);
+    }
+}
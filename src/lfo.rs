@@ -1,4 +1,7 @@
 use crate::optimization::fast_sin;
+use crate::tempo::{clamp_bpm, NoteDivision, DEFAULT_BPM};
+use crate::vintage::{VintageConfig, LFO_JITTER_DEPTH};
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
 /// DX7 ROM LFO rate-to-Hz table indexed 0..99 (rate parameter).
@@ -27,7 +30,7 @@ const LFO_FREQ_TABLE: [f32; 100] = [
     44.326241, 44.883303, 46.772685, 48.590865, 49.261084,
 ];
 
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub enum LFOWaveform {
     #[default]
     Triangle,
@@ -71,42 +74,104 @@ pub struct LFO {
     pub pitch_depth: f32, // Pitch modulation depth
     pub amp_depth: f32,   // Amplitude modulation depth
     pub waveform: LFOWaveform,
-    pub key_sync: bool, // Restart LFO on key press
+    pub key_sync: bool,             // Restart LFO on key press
+    pub sync: Option<NoteDivision>, // Tempo sync: one cycle per division instead of `rate`
 
     // Internal state
     phase: f32,         // Current phase (0.0 to 1.0)
     delay_counter: f32, // Delay countdown in seconds
     sample_rate: f32,
     last_sample_hold: f32, // For sample & hold waveform
-    sh_phase_trigger: f32, // Trigger point for S&H
+    sh_pending: bool,      // Draw a new S&H value on the next sample (phase wrapped / restarted)
     is_delayed: bool,      // Whether LFO is still in delay phase
 
     // Cached values for performance
     cached_rate_hz: f32,
     last_rate: f32,
+
+    vintage: VintageConfig,
+    jitter: f32,     // Rate factor for the current cycle when vintage LFO jitter is on
+    rate_scale: f32, // Mod-matrix factor on the speed (1.0 = patch rate)
+    value: f32,      // Waveform value (-1..1) of the latest `process` call
+    bpm: f32,        // Engine tempo, used when `sync` is set
+    seed: u32,       // Start of the random sequence; key sync returns to it
+    rng: u32,        // xorshift32 state for S&H and jitter draws; never 0
 }
 
 impl LFO {
     pub fn new(sample_rate: f32) -> Self {
-        Self {
+        let mut lfo = Self {
             rate: 50.0,        // Medium rate
             delay: 0.0,        // No delay by default
             pitch_depth: 25.0, // Moderate pitch modulation for testing
             amp_depth: 15.0,   // Moderate amplitude modulation for testing
             waveform: LFOWaveform::Triangle,
             key_sync: false,
+            sync: None,
 
             phase: 0.0,
             delay_counter: 0.0,
             sample_rate,
             last_sample_hold: 0.0,
-            sh_phase_trigger: 0.0,
+            sh_pending: true,
             is_delayed: false,
             cached_rate_hz: 0.0,
             last_rate: -1.0, // Initialize to -1 to force first calculation
+            vintage: VintageConfig::CLEAN,
+            jitter: 1.0,
+            rate_scale: 1.0,
+            value: 0.0,
+            bpm: DEFAULT_BPM,
+            seed: 1,
+            rng: 1,
+        };
+        lfo.seed(rand::random());
+        lfo
+    }
+
+    /// Restart the random sequence behind S&H and rate jitter from `seed`,
+    /// so a render repeats exactly. A new LFO starts from a random seed.
+    pub fn seed(&mut self, seed: u32) {
+        // xorshift never leaves 0; any other seed is a full-period start.
+        self.seed = if seed == 0 { 0x9E37_79B9 } else { seed };
+        self.rng = self.seed;
+    }
+
+    /// Next draw from the LFO's own generator, uniform in -1..1.
+    fn next_random(&mut self) -> f32 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        // Top 24 bits give every f32 step in 0..1.
+        (x >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0
+    }
+
+    pub fn set_vintage(&mut self, vintage: VintageConfig) {
+        self.vintage = vintage;
+        if !vintage.lfo_jitter {
+            self.jitter = 1.0;
         }
     }
 
+    /// Speed multiplier from the mod matrix, on top of `rate`.
+    pub fn set_rate_scale(&mut self, scale: f32) {
+        self.rate_scale = scale.max(0.0);
+    }
+
+    /// Raw waveform value (-1..1) from the latest `process` call, before
+    /// depth and mod wheel; 0 during the delay. Mod-matrix LFO source.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Phase and delay are tracked in cycles and seconds, so only the rate
+    /// used to advance them changes.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
     /// Convert DX7 rate (0-99) to Hz via the ROM `LFO_FREQ_TABLE`. Fractional
     /// rates are linearly interpolated between adjacent table entries so the
     /// GUI slider is smooth even though the underlying parameter is integer.
@@ -137,7 +202,9 @@ impl LFO {
     pub fn trigger(&mut self) {
         if self.key_sync {
             self.phase = 0.0;
-            self.sh_phase_trigger = 0.0;
+            self.sh_pending = true;
+            // Each key-synced note steps through the same S&H values.
+            self.rng = self.seed;
         }
 
         if self.delay > 0.0 {
@@ -178,15 +245,12 @@ impl LFO {
             }
 
             LFOWaveform::SampleHold => {
-                // Sample & hold: change value at specific phase points
-                if phase >= self.sh_phase_trigger && phase < self.sh_phase_trigger + 0.01 {
-                    // Generate new random value when crossing trigger point
-                    self.last_sample_hold = (rand::random::<f32>() * 2.0) - 1.0;
-                    self.sh_phase_trigger = if self.sh_phase_trigger < 0.5 {
-                        0.5
-                    } else {
-                        0.0
-                    };
+                // DX7 S&H draws one new random value per LFO period, at the
+                // start of the cycle. `process` flags the phase wrap, so the
+                // step lands on exactly one sample regardless of rate.
+                if self.sh_pending {
+                    self.last_sample_hold = self.next_random();
+                    self.sh_pending = false;
                 }
                 self.last_sample_hold
             }
@@ -195,18 +259,27 @@ impl LFO {
 
     /// Process one sample and return modulation values
     pub fn process(&mut self, mod_wheel: f32) -> (f32, f32) {
+        self.process_samples(mod_wheel, 1)
+    }
+
+    /// Control-rate variant of `process`: returns the modulation at the
+    /// current position, then advances delay and phase by `samples`.
+    pub fn process_samples(&mut self, mod_wheel: f32, samples: usize) -> (f32, f32) {
         // Handle delay phase
         if self.is_delayed {
-            self.delay_counter -= 1.0 / self.sample_rate;
+            self.delay_counter -= samples as f32 / self.sample_rate;
             if self.delay_counter <= 0.0 {
                 self.is_delayed = false;
             } else {
+                self.value = 0.0;
                 return (0.0, 0.0); // No modulation during delay
             }
         }
 
         // Calculate frequency and phase increment with caching
-        let frequency_hz = if (self.rate - self.last_rate).abs() > 0.01 {
+        let frequency_hz = if let Some(division) = self.sync {
+            division.hz(self.bpm)
+        } else if (self.rate - self.last_rate).abs() > 0.01 {
             self.last_rate = self.rate;
             self.cached_rate_hz = Self::dx7_rate_to_hz(self.rate);
             self.cached_rate_hz
@@ -217,15 +290,21 @@ impl LFO {
             return (0.0, 0.0); // No modulation if rate is 0
         }
 
-        let phase_increment = frequency_hz / self.sample_rate;
+        let phase_increment =
+            frequency_hz * self.jitter * self.rate_scale * samples as f32 / self.sample_rate;
 
         // Generate waveform
         let lfo_value = self.generate_waveform(self.phase);
+        self.value = lfo_value;
 
-        // Update phase for next sample
+        // Update phase for the next call; a wrap starts a new S&H period.
         self.phase += phase_increment;
-        while self.phase >= 1.0 {
-            self.phase -= 1.0;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.sh_pending = true;
+            if self.vintage.lfo_jitter {
+                self.jitter = 1.0 + self.next_random() * LFO_JITTER_DEPTH;
+            }
         }
 
         // Calculate modulation amounts
@@ -260,7 +339,7 @@ impl LFO {
         self.waveform = waveform;
         // Reset sample & hold state when changing waveform
         if waveform == LFOWaveform::SampleHold {
-            self.sh_phase_trigger = 0.0;
+            self.sh_pending = true;
             self.last_sample_hold = 0.0;
         }
     }
@@ -269,9 +348,21 @@ impl LFO {
         self.key_sync = key_sync;
     }
 
+    /// Lock the speed to a note division of the tempo; `None` goes back to `rate`.
+    pub fn set_sync(&mut self, sync: Option<NoteDivision>) {
+        self.sync = sync;
+    }
+
+    pub fn set_tempo(&mut self, bpm: f32) {
+        self.bpm = clamp_bpm(bpm);
+    }
+
     /// Get current LFO frequency in Hz (for display purposes)
     pub fn get_frequency_hz(&self) -> f32 {
-        Self::dx7_rate_to_hz(self.rate)
+        match self.sync {
+            Some(division) => division.hz(self.bpm),
+            None => Self::dx7_rate_to_hz(self.rate),
+        }
     }
 
     /// Get current delay time in seconds (for display purposes)
@@ -354,8 +445,10 @@ mod tests {
     #[test]
     fn set_waveform_to_sample_hold_resets_state() {
         let mut lfo = LFO::new(SR);
+        lfo.sh_pending = false;
         lfo.set_waveform(LFOWaveform::SampleHold);
         assert_eq!(lfo.waveform, LFOWaveform::SampleHold);
+        assert!(lfo.sh_pending);
     }
 
     #[test]
@@ -459,6 +552,49 @@ mod tests {
     // Process / waveforms
     // -----------------------------------------------------------------------
 
+    #[test]
+    fn process_samples_advances_like_repeated_process() {
+        let mut per_sample = LFO::new(SR);
+        let mut block = LFO::new(SR);
+        for lfo in [&mut per_sample, &mut block] {
+            lfo.set_rate(70.0);
+            lfo.set_waveform(LFOWaveform::SawUp);
+            lfo.set_pitch_depth(99.0);
+        }
+        for _ in 0..10 {
+            for _ in 0..16 {
+                per_sample.process(1.0);
+            }
+            block.process_samples(1.0, 16);
+        }
+        let (expected, _) = per_sample.process(1.0);
+        let (actual, _) = block.process(1.0);
+        assert!(expected != 0.0);
+        assert!((expected - actual).abs() < 1e-3);
+    }
+
+    #[test]
+    fn tempo_sync_replaces_the_rate() {
+        let division = NoteDivision::default(); // quarter note
+        let mut slow = LFO::new(SR);
+        let mut fast = LFO::new(SR);
+        for (lfo, rate) in [(&mut slow, 10.0), (&mut fast, 90.0)] {
+            lfo.set_rate(rate);
+            lfo.set_pitch_depth(99.0);
+            lfo.set_sync(Some(division));
+            lfo.set_tempo(120.0);
+        }
+        assert_eq!(slow.get_frequency_hz(), 2.0);
+        for _ in 0..100 {
+            assert_eq!(slow.process_samples(1.0, 16), fast.process_samples(1.0, 16));
+        }
+
+        slow.set_tempo(60.0);
+        assert_eq!(slow.get_frequency_hz(), 1.0);
+        slow.set_sync(None);
+        assert_eq!(slow.get_frequency_hz(), LFO::dx7_rate_to_hz(10.0));
+    }
+
     #[test]
     fn rate_zero_outputs_no_modulation() {
         let mut lfo = LFO::new(SR);
@@ -574,4 +710,98 @@ mod tests {
             "S&H should hold value for many samples, max_run={max_run}"
         );
     }
+
+    /// Drive an S&H LFO for `samples` and return (value changes, phase wraps).
+    fn count_sample_hold_steps(lfo: &mut LFO, samples: usize) -> (usize, usize) {
+        let mut changes = 0;
+        let mut wraps = 0;
+        let mut prev_phase = lfo.phase;
+        let (mut prev, _) = lfo.process(1.0);
+        for _ in 1..samples {
+            // A wrap during the previous call shows up in this sample's value.
+            let phase = lfo.phase;
+            let (p, _) = lfo.process(1.0);
+            if phase < prev_phase {
+                wraps += 1;
+            }
+            if p != prev {
+                changes += 1;
+            }
+            prev = p;
+            prev_phase = phase;
+        }
+        (changes, wraps)
+    }
+
+    fn sample_hold_lfo(rate: f32, sample_rate: f32) -> LFO {
+        let mut lfo = LFO::new(sample_rate);
+        lfo.set_waveform(LFOWaveform::SampleHold);
+        lfo.rate = rate;
+        lfo.pitch_depth = 99.0;
+        lfo
+    }
+
+    #[test]
+    fn sample_hold_steps_once_per_period_at_max_rate() {
+        let mut lfo = sample_hold_lfo(99.0, SR);
+        let (changes, wraps) = count_sample_hold_steps(&mut lfo, SR as usize);
+        // ~49 Hz for one second.
+        assert!((48..=50).contains(&wraps), "wraps={wraps}");
+        assert_eq!(changes, wraps);
+    }
+
+    #[test]
+    fn sample_hold_steps_once_per_period_at_min_rate() {
+        // Rate 1 is ~0.125 Hz; a low sample rate keeps two periods cheap.
+        let mut lfo = sample_hold_lfo(1.0, 1_000.0);
+        let (changes, wraps) = count_sample_hold_steps(&mut lfo, 17_000);
+        assert_eq!(wraps, 2);
+        assert_eq!(changes, wraps);
+    }
+
+    #[test]
+    fn sample_hold_survives_rate_faster_than_half_sample_rate() {
+        // 49 Hz at 60 Hz sample rate: nearly every sample wraps and must resample.
+        let mut lfo = sample_hold_lfo(99.0, 60.0);
+        let (changes, wraps) = count_sample_hold_steps(&mut lfo, 600);
+        assert!(wraps > 400, "wraps={wraps}");
+        assert_eq!(changes, wraps);
+    }
+
+    #[test]
+    fn sample_hold_key_sync_restarts_the_sequence_on_trigger() {
+        let mut lfo = sample_hold_lfo(99.0, 1_000.0);
+        lfo.set_key_sync(true);
+        let run = |lfo: &mut LFO| (0..100).map(|_| lfo.process(1.0).0).collect::<Vec<_>>();
+        let first = run(&mut lfo);
+        let held = *first.last().unwrap();
+        lfo.trigger();
+        let again = run(&mut lfo);
+        // A new value on the key, and the same steps as the first note.
+        assert_ne!(again[0], held);
+        assert_eq!(first, again);
+    }
+
+    #[test]
+    fn sample_hold_without_key_sync_keeps_drawing() {
+        let mut lfo = sample_hold_lfo(99.0, 1_000.0);
+        let run = |lfo: &mut LFO| (0..100).map(|_| lfo.process(1.0).0).collect::<Vec<_>>();
+        let first = run(&mut lfo);
+        lfo.trigger();
+        assert_ne!(first, run(&mut lfo));
+    }
+
+    #[test]
+    fn seeded_sample_hold_repeats_its_sequence() {
+        let steps = |seed: u32| {
+            let mut lfo = sample_hold_lfo(99.0, 1_000.0);
+            lfo.seed(seed);
+            (0..200).map(|_| lfo.process(1.0).0).collect::<Vec<_>>()
+        };
+        assert_eq!(steps(7), steps(7));
+        assert_ne!(steps(7), steps(8));
+        // Seed 0 would stall xorshift; it still steps.
+        let zero = steps(0);
+        assert!(zero.iter().any(|&p| p != zero[0]));
+    }
 }
@@ -0,0 +1,75 @@
+//! DX7-style FM synthesis engine.
+//!
+//! The engine modules build with no GUI, audio device or MIDI dependency, so
+//! the synth can be embedded in another application or driven from tests:
+//!
+//! ```
+//! use synth_fm_rs::fm_synth::create_synth;
+//!
+//! let (mut engine, mut controller) = create_synth(44_100.0);
+//! controller.note_on(60, 100);
+//! engine.process_commands();
+//! let (left, right) = engine.process_stereo();
+//! assert!(left.is_finite() && right.is_finite());
+//! ```
+//!
+//! The desktop app (egui window, cpal output, midir input) lives behind the
+//! default `gui` feature; build with `--no-default-features` to leave it out.
+//! The `plugin/` crate exports the same engine as a CLAP / VST3 instrument.
+
+pub mod algorithm_matrix;
+pub mod algorithms;
+pub mod command_queue;
+pub mod compare;
+pub mod compressor;
+pub mod dc_blocker;
+pub mod debug_dump;
+pub mod dx7_frequency;
+pub mod effects;
+pub mod envelope;
+pub mod fm_synth;
+pub mod level_meter;
+pub mod lfo;
+pub mod lock_free;
+pub mod mod_matrix;
+pub mod operator;
+pub mod optimization;
+pub mod performance;
+pub mod pitch_eg;
+pub mod preset_loader;
+pub mod preset_preview;
+pub mod presets;
+pub mod program_map;
+pub mod render;
+pub mod rpc;
+pub mod sequencer;
+pub mod smoothing;
+pub mod spectrum;
+pub mod state_snapshot;
+pub mod sysex;
+pub mod tempo;
+pub mod tuning;
+pub mod vintage;
+
+#[cfg(feature = "gui")]
+pub mod audio_engine;
+#[cfg(feature = "gui")]
+pub mod convert;
+#[cfg(feature = "gui")]
+pub mod data_entry;
+#[cfg(feature = "gui")]
+pub mod gui;
+#[cfg(feature = "gui")]
+pub mod keybindings;
+#[cfg(feature = "gui")]
+pub mod lcd;
+#[cfg(feature = "gui")]
+pub mod librarian;
+#[cfg(feature = "gui")]
+pub mod midi_handler;
+#[cfg(feature = "gui")]
+pub mod midi_learn;
+#[cfg(feature = "gui")]
+pub mod settings;
+#[cfg(feature = "gui")]
+pub mod user_presets;
@@ -0,0 +1,320 @@
+//! Patch librarian index: favorites and category tags for the preset list.
+//!
+//! Presets carry no stable id, so entries are keyed by `collection/name`.
+//! The index only stores what the user changed; a preset without saved tags
+//! is filed under the category its name suggests (`SYN-BASS 1` → bass), so
+//! a fresh library can already be browsed by category. Persisted as JSON in
+//! the config directory, next to the key bindings.
+
+use crate::keybindings::config_dir;
+use crate::presets::Dx7Preset;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "library.json";
+
+/// Categories offered for tagging, in display order.
+pub const CATEGORIES: [&str; 12] = [
+    "bass", "keys", "organ", "brass", "wind", "strings", "pad", "lead", "bell", "pluck", "perc",
+    "fx",
+];
+
+/// Upper-case name fragments that suggest a category. Checked in order and
+/// the first hit wins, so `BASSOON` is filed under wind before bass sees it.
+const CATEGORY_HINTS: [(&str, &[&str]); 12] = [
+    (
+        "wind",
+        &[
+            "FLUTE", "OBOE", "CLARI", "SAX", "BASSOON", "RECORDER", "PIPE",
+        ],
+    ),
+    ("bass", &["BASS"]),
+    (
+        "keys",
+        &["PIANO", "PNO", "RHODES", "CLAV", "HARPSI", "WURLI", "E.P"],
+    ),
+    ("organ", &["ORGAN"]),
+    ("brass", &["BRASS", "HORN", "TRUMP", "TUBA", "TROMB"]),
+    ("strings", &["STRING", "VIOLIN", "CELLO", "ORCH"]),
+    (
+        "bell",
+        &[
+            "BELL", "CHIME", "GLOCK", "VIBE", "MARIMBA", "XYLO", "GAMELAN",
+        ],
+    ),
+    (
+        "pluck",
+        &["GUITAR", "GUIT", "HARP", "KOTO", "PLUCK", "SITAR"],
+    ),
+    (
+        "perc",
+        &["DRUM", "PERC", "SNARE", "TOM", "TIMP", "STAB", "HIT"],
+    ),
+    ("pad", &["PAD", "CHOIR", "SPACE", "VOICE", "SWEEP", "ATMOS"]),
+    ("lead", &["LEAD", "SOLO", "SYN"]),
+    ("fx", &["FX", "NOISE", "CHAOS", "LASER"]),
+];
+
+/// Category suggested by a preset name, if any.
+pub fn guess_category(name: &str) -> Option<&'static str> {
+    let name = name.to_uppercase();
+    CATEGORY_HINTS
+        .iter()
+        .find(|(_, hints)| hints.iter().any(|hint| name.contains(hint)))
+        .map(|(category, _)| *category)
+}
+
+/// What the user set for one preset.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct LibraryEntry {
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    favorite: bool,
+    /// None = the category guessed from the name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+}
+
+/// Which presets the librarian lists.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LibraryFilter {
+    /// Words that must all appear in the name, collection or tags.
+    pub search: String,
+    pub category: Option<String>,
+    pub favorites_only: bool,
+}
+
+/// The on-disk librarian index.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Library {
+    entries: BTreeMap<String, LibraryEntry>,
+}
+
+impl Library {
+    fn key(preset: &Dx7Preset) -> String {
+        format!("{}/{}", preset.collection, preset.name)
+    }
+
+    pub fn is_favorite(&self, preset: &Dx7Preset) -> bool {
+        self.entries
+            .get(&Self::key(preset))
+            .is_some_and(|e| e.favorite)
+    }
+
+    pub fn set_favorite(&mut self, preset: &Dx7Preset, favorite: bool) {
+        self.update(preset, |entry| entry.favorite = favorite);
+    }
+
+    /// Tags of `preset`: the ones the user set, or else the guessed category.
+    pub fn tags(&self, preset: &Dx7Preset) -> Vec<String> {
+        match self
+            .entries
+            .get(&Self::key(preset))
+            .and_then(|e| e.tags.as_ref())
+        {
+            Some(tags) => tags.clone(),
+            None => guess_category(&preset.name)
+                .map(|c| vec![c.to_string()])
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Add or remove `tag`. The first edit replaces the guessed category
+    /// with an explicit tag list, which may end up empty.
+    pub fn toggle_tag(&mut self, preset: &Dx7Preset, tag: &str) {
+        let mut tags = self.tags(preset);
+        if let Some(pos) = tags.iter().position(|t| t == tag) {
+            tags.remove(pos);
+        } else {
+            tags.push(tag.to_string());
+            tags.sort_by_key(|t| CATEGORIES.iter().position(|c| c == t));
+        }
+        self.update(preset, |entry| entry.tags = Some(tags));
+    }
+
+    /// Apply `change` to the entry of `preset`, dropping entries that are
+    /// back to the defaults so the file only holds real edits.
+    fn update(&mut self, preset: &Dx7Preset, change: impl FnOnce(&mut LibraryEntry)) {
+        let key = Self::key(preset);
+        let entry = self.entries.entry(key.clone()).or_default();
+        change(entry);
+        let guessed = guess_category(&preset.name).map(|c| vec![c.to_string()]);
+        if entry.tags.is_some() && entry.tags == Some(guessed.unwrap_or_default()) {
+            entry.tags = None;
+        }
+        if *entry == LibraryEntry::default() {
+            self.entries.remove(&key);
+        }
+    }
+
+    pub fn matches(&self, preset: &Dx7Preset, filter: &LibraryFilter) -> bool {
+        if filter.favorites_only && !self.is_favorite(preset) {
+            return false;
+        }
+        let tags = self.tags(preset);
+        if let Some(category) = &filter.category {
+            if !tags.contains(category) {
+                return false;
+            }
+        }
+        let haystack =
+            format!("{} {} {}", preset.name, preset.collection, tags.join(" ")).to_lowercase();
+        filter
+            .search
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| haystack.contains(word))
+    }
+
+    /// Indices into `presets` of the ones `filter` lets through.
+    pub fn filter(&self, presets: &[Dx7Preset], filter: &LibraryFilter) -> Vec<usize> {
+        presets
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| self.matches(p, filter))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Default location of the index, or None when no config directory can
+    /// be determined.
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(FILE_NAME))
+    }
+
+    /// Load the index from `path`, starting empty when the file is missing
+    /// or unreadable.
+    pub fn load_or_default(path: &Path) -> Self {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("Invalid library index {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(name: &str, collection: &str) -> Dx7Preset {
+        Dx7Preset {
+            name: name.to_string(),
+            collection: collection.to_string(),
+            ..Dx7Preset::default()
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("synth-fm-rs-library-{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn categories_are_guessed_from_names() {
+        assert_eq!(guess_category("SYN-BASS 1"), Some("bass"));
+        assert_eq!(guess_category("Bassoon"), Some("wind"));
+        assert_eq!(guess_category("E.PIANO 1"), Some("keys"));
+        assert_eq!(guess_category("TUB BELLS"), Some("bell"));
+        assert_eq!(guess_category("WOBBLE"), None);
+        for (category, _) in CATEGORY_HINTS {
+            assert!(CATEGORIES.contains(&category), "{category}");
+        }
+    }
+
+    #[test]
+    fn editing_tags_replaces_the_guess() {
+        let mut library = Library::default();
+        let bass = preset("BASS 1", "edu");
+        assert_eq!(library.tags(&bass), vec!["bass"]);
+
+        library.toggle_tag(&bass, "pluck");
+        assert_eq!(library.tags(&bass), vec!["bass", "pluck"]);
+        library.toggle_tag(&bass, "bass");
+        library.toggle_tag(&bass, "pluck");
+        assert!(library.tags(&bass).is_empty());
+
+        // Back to the guess: nothing left to store.
+        library.toggle_tag(&bass, "bass");
+        assert_eq!(library, Library::default());
+    }
+
+    #[test]
+    fn filter_combines_search_category_and_favorites() {
+        let presets = vec![
+            preset("BASS 1", "edu"),
+            preset("E.PIANO 1", "edu"),
+            preset("E.PIANO 2", "rhodes1"),
+            preset("FLUTE", "edu"),
+        ];
+        let mut library = Library::default();
+        library.set_favorite(&presets[2], true);
+        library.toggle_tag(&presets[3], "pad");
+
+        let search = |text: &str| LibraryFilter {
+            search: text.to_string(),
+            ..LibraryFilter::default()
+        };
+        assert_eq!(library.filter(&presets, &search("")).len(), 4);
+        assert_eq!(library.filter(&presets, &search("piano rhodes")), vec![2]);
+        assert_eq!(library.filter(&presets, &search("PAD")), vec![3]);
+
+        let keys = LibraryFilter {
+            category: Some("keys".to_string()),
+            ..LibraryFilter::default()
+        };
+        assert_eq!(library.filter(&presets, &keys), vec![1, 2]);
+        let favorites = LibraryFilter {
+            favorites_only: true,
+            ..keys
+        };
+        assert_eq!(library.filter(&presets, &favorites), vec![2]);
+    }
+
+    #[test]
+    fn favorites_are_per_collection() {
+        let mut library = Library::default();
+        library.set_favorite(&preset("STRINGS", "edu"), true);
+        assert!(library.is_favorite(&preset("STRINGS", "edu")));
+        assert!(!library.is_favorite(&preset("STRINGS", "mark")));
+        library.set_favorite(&preset("STRINGS", "edu"), false);
+        assert_eq!(library, Library::default());
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_path("round_trip.json");
+        let mut library = Library::default();
+        library.set_favorite(&preset("BRASS 1", "edu"), true);
+        library.toggle_tag(&preset("CHAOS", "edu"), "pad");
+        library.save(&path).expect("save");
+        assert_eq!(Library::load_or_default(&path), library);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn load_missing_or_invalid_file_gives_an_empty_library() {
+        assert_eq!(
+            Library::load_or_default(&temp_path("does_not_exist.json")),
+            Library::default()
+        );
+        let path = temp_path("garbage.json");
+        std::fs::create_dir_all(path.parent().unwrap()).expect("mkdir");
+        std::fs::write(&path, "not json").expect("write");
+        assert_eq!(Library::load_or_default(&path), Library::default());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,15 +1,19 @@
-use std::sync::atomic::{AtomicU8, Ordering};
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicU32, AtomicU8, AtomicUsize, Ordering};
 
 /// Lock-free triple buffer for real-time parameter updates.
 ///
 /// Uses a single atomic byte to track buffer indices, ensuring atomic swaps.
-/// The byte layout is: [unused:2][back:2][read:2][write:2]
+/// The byte layout is: [unused:1][fresh:1][back:2][read:2][write:2]
 ///
 /// Writer (GUI thread): writes to write buffer, then swaps write<->back
-/// Reader (Audio thread): swaps read<->back, then reads from read buffer
+/// Reader (Audio thread): swaps read<->back if the back buffer holds a write
+/// it has not seen yet, then reads from read buffer
 pub struct TripleBuffer<T: Clone + Send> {
     buffers: [std::cell::UnsafeCell<T>; 3],
-    /// Packed indices: bits 0-1 = write, bits 2-3 = read, bits 4-5 = back
+    /// Packed indices: bits 0-1 = write, bits 2-3 = read, bits 4-5 = back,
+    /// bit 6 = back is newer than read
     indices: AtomicU8,
 }
 
@@ -17,6 +21,7 @@ impl<T: Clone + Send> TripleBuffer<T> {
     const WRITE_MASK: u8 = 0b00000011;
     const READ_MASK: u8 = 0b00001100;
     const BACK_MASK: u8 = 0b00110000;
+    const FRESH: u8 = 0b01000000;
     const WRITE_SHIFT: u8 = 0;
     const READ_SHIFT: u8 = 2;
     const BACK_SHIFT: u8 = 4;
@@ -56,7 +61,8 @@ impl<T: Clone + Send> TripleBuffer<T> {
             // New state: swap write and back
             let new_indices = (current & Self::READ_MASK) // keep read
                 | (back_idx << Self::WRITE_SHIFT)  // back becomes write
-                | (write_idx << Self::BACK_SHIFT); // write becomes back
+                | (write_idx << Self::BACK_SHIFT)  // write becomes back
+                | Self::FRESH;
 
             match self.indices.compare_exchange_weak(
                 current,
@@ -71,7 +77,8 @@ impl<T: Clone + Send> TripleBuffer<T> {
     }
 
     /// Read current data (Audio thread only).
-    /// Atomically swaps read and back buffers, then returns reference to read buffer.
+    /// Takes the newest write, if any arrived since the last read, and returns
+    /// a reference to the read buffer.
     #[allow(dead_code)]
     pub fn read(&self) -> &T {
         // Atomically swap read and back buffers using CAS loop
@@ -80,6 +87,12 @@ impl<T: Clone + Send> TripleBuffer<T> {
             let read_idx = (current & Self::READ_MASK) >> Self::READ_SHIFT;
             let back_idx = (current & Self::BACK_MASK) >> Self::BACK_SHIFT;
 
+            // Nothing written since the last read: swapping would hand back
+            // the older buffer.
+            if current & Self::FRESH == 0 {
+                return unsafe { &*self.buffers[read_idx as usize].get() };
+            }
+
             // New state: swap read and back
             let new_indices = (current & Self::WRITE_MASK) // keep write
                 | (back_idx << Self::READ_SHIFT)  // back becomes read
@@ -111,12 +124,232 @@ impl<T: Clone + Send> TripleBuffer<T> {
 unsafe impl<T: Clone + Send> Send for TripleBuffer<T> {}
 unsafe impl<T: Clone + Send> Sync for TripleBuffer<T> {}
 
+/// Single-owner handoff of a value between threads, without a lock.
+///
+/// The value lives behind one atomic pointer. `try_borrow` swaps it out, so
+/// the caller owns it exclusively until the returned guard drops and swaps it
+/// back. Nobody ever waits: a thread that finds the cell empty skips its turn.
+/// The audio callback borrows the synth engine this way every buffer, and the
+/// threads that stand in for it (command pump, stream rebuild) borrow it only
+/// while the callback is not running.
+pub struct HandoffCell<T: Send> {
+    value: AtomicPtr<T>,
+}
+
+impl<T: Send> HandoffCell<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value: AtomicPtr::new(Box::into_raw(Box::new(value))),
+        }
+    }
+
+    /// Take the value for the lifetime of the guard, or None if another
+    /// thread holds it right now.
+    pub fn try_borrow(&self) -> Option<Handoff<'_, T>> {
+        let raw = self.value.swap(ptr::null_mut(), Ordering::Acquire);
+        // Safety: a non-null pointer came from `Box::into_raw` and the swap
+        // made this thread its only holder.
+        (!raw.is_null()).then(|| Handoff {
+            cell: self,
+            value: Some(unsafe { Box::from_raw(raw) }),
+        })
+    }
+}
+
+impl<T: Send> Drop for HandoffCell<T> {
+    fn drop(&mut self) {
+        let raw = *self.value.get_mut();
+        if !raw.is_null() {
+            // Safety: as in `try_borrow`; `&mut self` rules out a guard.
+            drop(unsafe { Box::from_raw(raw) });
+        }
+    }
+}
+
+// Safety: the value is only ever reached through one guard at a time.
+unsafe impl<T: Send> Send for HandoffCell<T> {}
+unsafe impl<T: Send> Sync for HandoffCell<T> {}
+
+/// Exclusive access to the value of a `HandoffCell`; returns it on drop.
+pub struct Handoff<'a, T: Send> {
+    cell: &'a HandoffCell<T>,
+    value: Option<Box<T>>,
+}
+
+impl<T: Send> Deref for Handoff<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_deref().expect("value present until drop")
+    }
+}
+
+impl<T: Send> DerefMut for Handoff<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_deref_mut().expect("value present until drop")
+    }
+}
+
+impl<T: Send> Drop for Handoff<'_, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            self.cell
+                .value
+                .store(Box::into_raw(value), Ordering::Release);
+        }
+    }
+}
+
+/// Lock-free ring of the most recent audio samples, for visualisation.
+///
+/// Single producer (audio thread) pushes one sample at a time; any number of
+/// readers (GUI) copy out the latest window. Samples are stored as `f32` bits
+/// in atomics, so there is no unsafe code and no blocking, but a reader racing
+/// the writer may see a window that straddles one overwrite — fine for a
+/// scope, not for anything that needs sample-exact data.
+pub struct ScopeBuffer {
+    samples: Box<[AtomicU32]>,
+    /// Total samples ever pushed; the next write goes to `write_pos % len`.
+    write_pos: AtomicUsize,
+}
+
+impl ScopeBuffer {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            samples: (0..capacity).map(|_| AtomicU32::new(0)).collect(),
+            write_pos: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Append one sample (audio thread only).
+    #[inline]
+    pub fn push(&self, sample: f32) {
+        let pos = self.write_pos.load(Ordering::Relaxed);
+        self.samples[pos % self.samples.len()].store(sample.to_bits(), Ordering::Relaxed);
+        self.write_pos.store(pos.wrapping_add(1), Ordering::Release);
+    }
+
+    /// Fill `out` with the most recent `out.len()` samples, oldest first.
+    /// Slots never written yet read as silence.
+    pub fn copy_latest(&self, out: &mut [f32]) {
+        let len = self.samples.len();
+        let end = self.write_pos.load(Ordering::Acquire);
+        let count = out.len().min(len);
+        let start = end.wrapping_sub(count);
+        for (i, slot) in out.iter_mut().enumerate() {
+            *slot = if i < count {
+                let idx = start.wrapping_add(i) % len;
+                f32::from_bits(self.samples[idx].load(Ordering::Relaxed))
+            } else {
+                0.0
+            };
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::sync::Arc;
     use std::thread;
 
+    #[test]
+    fn scope_buffer_returns_latest_samples_oldest_first() {
+        let scope = ScopeBuffer::new(8);
+        for i in 0..5 {
+            scope.push(i as f32);
+        }
+        let mut out = [0.0; 3];
+        scope.copy_latest(&mut out);
+        assert_eq!(out, [2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn scope_buffer_wraps_around_capacity() {
+        let scope = ScopeBuffer::new(4);
+        for i in 0..10 {
+            scope.push(i as f32);
+        }
+        let mut out = [0.0; 4];
+        scope.copy_latest(&mut out);
+        assert_eq!(out, [6.0, 7.0, 8.0, 9.0]);
+
+        // Asking for more than the capacity pads the tail with silence.
+        let mut wide = [1.0; 6];
+        scope.copy_latest(&mut wide);
+        assert_eq!(wide, [6.0, 7.0, 8.0, 9.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn scope_buffer_concurrent_reads_stay_in_range() {
+        let scope = Arc::new(ScopeBuffer::new(256));
+        let writer_scope = scope.clone();
+        let writer = thread::spawn(move || {
+            for i in 0..20_000 {
+                writer_scope.push((i % 100) as f32 / 100.0);
+            }
+        });
+        let mut out = [0.0; 128];
+        for _ in 0..1_000 {
+            scope.copy_latest(&mut out);
+            assert!(out.iter().all(|s| (0.0..1.0).contains(s)));
+        }
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn handoff_cell_lends_to_one_holder_at_a_time() {
+        let cell = HandoffCell::new(vec![1, 2, 3]);
+        {
+            let mut held = cell.try_borrow().expect("free cell lends");
+            held.push(4);
+            assert!(
+                cell.try_borrow().is_none(),
+                "second borrower skips its turn"
+            );
+        }
+        assert_eq!(*cell.try_borrow().unwrap(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn handoff_cell_concurrent_borrowers_never_overlap() {
+        let cell = Arc::new(HandoffCell::new(0u64));
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let cell = cell.clone();
+                thread::spawn(move || {
+                    let mut done = 0;
+                    while done < 10_000 {
+                        if let Some(mut value) = cell.try_borrow() {
+                            // A torn read-modify-write would lose increments.
+                            let seen = *value;
+                            *value = seen + 1;
+                            done += 1;
+                        }
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert_eq!(*cell.try_borrow().unwrap(), 40_000);
+    }
+
+    #[test]
+    fn handoff_cell_drops_its_value() {
+        let value = Arc::new(());
+        let cell = HandoffCell::new(value.clone());
+        assert_eq!(Arc::strong_count(&value), 2);
+        drop(cell);
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+
     #[test]
     fn test_triple_buffer_basic() {
         let buffer = TripleBuffer::new(0u32);
@@ -138,6 +371,18 @@ mod tests {
         assert_eq!(*buffer.read(), 3);
     }
 
+    #[test]
+    fn test_triple_buffer_rereads_keep_the_latest_value() {
+        let buffer = TripleBuffer::new(0u32);
+        buffer.write(1);
+        buffer.write(2);
+        assert_eq!(*buffer.read(), 2);
+        assert_eq!(*buffer.read(), 2);
+        buffer.write(3);
+        assert_eq!(*buffer.read(), 3);
+        assert_eq!(*buffer.read(), 3);
+    }
+
     #[test]
     fn test_triple_buffer_concurrent() {
         let buffer = Arc::new(TripleBuffer::new(0u64));
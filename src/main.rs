@@ -1,92 +1,301 @@
 use eframe::egui;
+use std::path::PathBuf;
 use std::sync::atomic::AtomicUsize;
 use std::sync::{Arc, Mutex};
-use std::thread;
 use std::time::Duration;
 
-mod algorithms;
-mod audio_engine;
-mod command_queue;
-mod dc_blocker;
-mod dx7_frequency;
-mod effects;
-mod envelope;
-mod fm_synth;
-mod gui;
-mod lfo;
-mod lock_free;
-mod midi_handler;
-mod operator;
-mod optimization;
-mod pitch_eg;
-mod preset_loader;
-mod presets;
-mod state_snapshot;
-mod sysex;
-
-use audio_engine::{AudioEngine, AudioProbe};
-use fm_synth::{create_synth, SynthController};
-use gui::Dx7App;
-use midi_handler::MidiHandler;
-
-fn play_startup_melody(controller: Arc<Mutex<SynthController>>) {
-    play_melody(
-        controller,
-        Duration::from_millis(500),
-        Duration::from_millis(300),
-        Duration::from_millis(50),
-    );
+use synth_fm_rs::audio_engine::{play_test_tone, AudioEngine, AudioProbe, AudioSettings};
+use synth_fm_rs::fm_synth::{create_synth, MAX_POLYPHONY};
+use synth_fm_rs::gui::Dx7App;
+use synth_fm_rs::midi_handler::MidiHandler;
+use synth_fm_rs::midi_learn::MidiLearn;
+use synth_fm_rs::preset_preview::PresetPreview;
+use synth_fm_rs::settings::{AppSettings, MIN_WINDOW_SIZE};
+use synth_fm_rs::{convert, preset_loader, render, rpc, user_presets};
+
+/// Lets the window come up before the startup melody plays.
+const STARTUP_MELODY_DELAY: Duration = Duration::from_millis(500);
+
+/// Sample rate for `--rpc`: no device is opened, so there is nothing to probe.
+const RPC_SAMPLE_RATE: f32 = 44_100.0;
+
+/// Serve JSON-RPC requests from stdin until EOF, without a window or audio device.
+fn run_rpc() {
+    let (engine, controller) = create_synth(RPC_SAMPLE_RATE);
+    let presets = preset_loader::scan_patches_dir(std::path::Path::new("patches"));
+    let mut server = rpc::RpcServer::new(engine, controller, presets);
+
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    if let Err(e) = server.serve(stdin.lock(), stdout.lock()) {
+        log::error!("RPC I/O error: {}", e);
+    }
+}
+
+/// Options for `--render`. Defaults: `<input>.wav`, 44.1 kHz, init voice.
+#[derive(Debug, PartialEq)]
+struct RenderArgs {
+    input: PathBuf,
+    output: PathBuf,
+    sample_rate: u32,
+    preset: Option<String>,
+    tail_seconds: f32,
+}
+
+/// The argument after `flag`, None when the flag is absent.
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Result<Option<&'a String>, String> {
+    args.iter()
+        .position(|a| a == flag)
+        .map(|i| {
+            args.get(i + 1)
+                .ok_or_else(|| format!("{} needs a value", flag))
+        })
+        .transpose()
 }
 
-fn play_melody(
-    controller: Arc<Mutex<SynthController>>,
-    initial_delay: Duration,
-    note_duration: Duration,
-    note_gap: Duration,
-) {
-    thread::spawn(move || {
-        thread::sleep(initial_delay);
+/// `--rate`: output sample rate, 44.1 kHz when absent.
+fn parse_rate_arg(args: &[String]) -> Result<u32, String> {
+    match flag_value(args, "--rate")? {
+        Some(v) => v
+            .parse::<u32>()
+            .ok()
+            .filter(|r| (8_000..=192_000).contains(r))
+            .ok_or_else(|| format!("--rate must be 8000..192000, got {}", v)),
+        None => Ok(44_100),
+    }
+}
+
+fn parse_render_args(args: &[String]) -> Result<RenderArgs, String> {
+    let value_of = |flag: &str| flag_value(args, flag);
+    let input = PathBuf::from(value_of("--render")?.ok_or("--render needs an input file")?);
+    let output = match value_of("--out")? {
+        Some(path) => PathBuf::from(path),
+        None => input.with_extension("wav"),
+    };
+    let sample_rate = parse_rate_arg(args)?;
+    let tail_seconds = match value_of("--tail")? {
+        Some(v) => v
+            .parse::<f32>()
+            .ok()
+            .filter(|t| (0.0..=60.0).contains(t))
+            .ok_or_else(|| format!("--tail must be 0..60 seconds, got {}", v))?,
+        None => render::DEFAULT_TAIL_SECONDS,
+    };
+    Ok(RenderArgs {
+        input,
+        output,
+        sample_rate,
+        preset: value_of("--preset")?.cloned(),
+        tail_seconds,
+    })
+}
+
+/// Options for `--convert`. A SysEx input becomes a folder of user-preset
+/// files (default: the input's name without `.syx`); a `.json` file or a
+/// folder of them becomes SysEx banks (default: `<input>.syx`).
+#[derive(Debug, PartialEq)]
+struct ConvertArgs {
+    input: PathBuf,
+    output: PathBuf,
+    /// Also render a WAV of the audition phrase for every voice.
+    preview: bool,
+    sample_rate: u32,
+}
 
-        let notes = [60, 64, 67]; // C4, E4, G4
+fn parse_convert_args(args: &[String]) -> Result<ConvertArgs, String> {
+    let input = PathBuf::from(
+        flag_value(args, "--convert")?.ok_or("--convert needs an input file or folder")?,
+    );
+    let output = match flag_value(args, "--out")? {
+        Some(path) => PathBuf::from(path),
+        None if convert::is_preset_input(&input) => input.with_extension("syx"),
+        None => input.with_extension(""),
+    };
+    if output == input {
+        return Err(format!("--out would overwrite {}", input.display()));
+    }
+    Ok(ConvertArgs {
+        input,
+        output,
+        preview: args.iter().any(|a| a == "--preview"),
+        sample_rate: parse_rate_arg(args)?,
+    })
+}
 
-        for &note in &notes {
-            if let Ok(mut ctrl) = controller.lock() {
-                ctrl.note_on(note, 80);
-            }
+/// Convert between SysEx banks and user-preset files, without a window or
+/// audio device.
+fn run_convert(args: &ConvertArgs) -> Result<(), String> {
+    let write_error = |e: std::io::Error| format!("cannot write {}: {}", args.output.display(), e);
+    let (presets, preview_dir, preview_prefix) = if convert::is_preset_input(&args.input) {
+        let presets = convert::read_presets(&args.input)?;
+        let banks = convert::write_banks(&presets, &args.output).map_err(write_error)?;
+        for bank in &banks {
+            log::info!("Wrote {}", bank.display());
+        }
+        log::info!("{} voices in {} banks", presets.len(), banks.len());
+        let dir = args.output.parent().unwrap_or(std::path::Path::new("."));
+        let stem = args
+            .output
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
+        (presets, dir.to_path_buf(), format!("{}-", stem))
+    } else {
+        let bytes = std::fs::read(&args.input)
+            .map_err(|e| format!("cannot read {}: {}", args.input.display(), e))?;
+        let (presets, notes) =
+            convert::read_syx(&bytes).map_err(|e| format!("{}: {}", args.input.display(), e))?;
+        for note in notes {
+            log::warn!("{}: {}", args.input.display(), note);
+        }
+        let files = convert::write_presets(&presets, &args.output).map_err(write_error)?;
+        log::info!("Wrote {} voices to {}", files.len(), args.output.display());
+        (presets, args.output.clone(), String::new())
+    };
+    if args.preview {
+        let wavs =
+            convert::write_previews(&presets, &preview_dir, &preview_prefix, args.sample_rate)
+                .map_err(|e| format!("cannot write previews: {}", e))?;
+        log::info!(
+            "Rendered {} previews to {}",
+            wavs.len(),
+            preview_dir.display()
+        );
+    }
+    Ok(())
+}
 
-            thread::sleep(note_duration);
+/// `--voices N`: startup polyphony (1..=64), or `None` to keep the default.
+fn parse_voices_arg(args: &[String]) -> Result<Option<usize>, String> {
+    let Some(i) = args.iter().position(|a| a == "--voices") else {
+        return Ok(None);
+    };
+    let value = args.get(i + 1).ok_or("--voices needs a value")?;
+    value
+        .parse::<usize>()
+        .ok()
+        .filter(|n| (1..=MAX_POLYPHONY).contains(n))
+        .map(Some)
+        .ok_or_else(|| format!("--voices must be 1..{}, got {}", MAX_POLYPHONY, value))
+}
 
-            if let Ok(mut ctrl) = controller.lock() {
-                ctrl.note_off(note);
-            }
+/// Render a MIDI file (`.mid`/`.midi`) or note list to WAV, without a window
+/// or audio device.
+fn run_render(args: &RenderArgs) -> Result<(), String> {
+    let bytes = std::fs::read(&args.input)
+        .map_err(|e| format!("cannot read {}: {}", args.input.display(), e))?;
+    let is_midi = args
+        .input
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("mid") || e.eq_ignore_ascii_case("midi"));
+    let events = if is_midi {
+        render::parse_midi_file(&bytes)?
+    } else {
+        render::parse_note_list(&String::from_utf8_lossy(&bytes))?
+    };
 
-            thread::sleep(note_gap);
+    let preset = match &args.preset {
+        Some(name) => {
+            let presets = preset_loader::scan_patches_dir(std::path::Path::new("patches"));
+            Some(
+                presets
+                    .into_iter()
+                    .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+                    .ok_or_else(|| format!("no preset named '{}' in patches/", name))?,
+            )
         }
-    });
+        None => None,
+    };
+
+    let frames = render::render_events(
+        preset.as_ref(),
+        &events,
+        args.sample_rate as f32,
+        args.tail_seconds,
+    );
+    render::write_wav(&args.output, &frames, args.sample_rate)
+        .map_err(|e| format!("cannot write {}: {}", args.output.display(), e))?;
+    log::info!(
+        "Rendered {} notes, {:.2} s to {}",
+        events.len(),
+        frames.len() as f32 / args.sample_rate as f32,
+        args.output.display()
+    );
+    Ok(())
 }
 
 fn main() -> Result<(), eframe::Error> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--rpc") {
+        log::info!("Serving JSON-RPC on stdin/stdout");
+        run_rpc();
+        return Ok(());
+    }
+    if args.iter().any(|arg| arg == "--render") {
+        if let Err(e) = parse_render_args(&args).and_then(|a| run_render(&a)) {
+            log::error!("Render failed: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if args.iter().any(|arg| arg == "--convert") {
+        if let Err(e) = parse_convert_args(&args).and_then(|a| run_convert(&a)) {
+            log::error!("Convert failed: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let voices = match parse_voices_arg(&args) {
+        Ok(voices) => voices,
+        Err(e) => {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
     log::info!("Starting DX7-Style FM Synthesizer");
 
+    // Window size and MIDI port from the last session; the app restores
+    // the rest when it starts.
+    let settings = AppSettings::default_path()
+        .map(|path| AppSettings::load_or_default(&path))
+        .unwrap_or_default();
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([960.0, 720.0])
-            .with_resizable(false)
+            .with_inner_size(settings.window_size)
+            .with_min_inner_size(MIN_WINDOW_SIZE)
             .with_title("DX7-Style FM Synthesizer"),
         ..Default::default()
     };
 
-    let probe = AudioProbe::default_output();
+    // Host, device and buffer saved from the AUDIO page; fall back to the
+    // system default output if that setup is gone (unplugged, JACK not running).
+    let audio_settings = AudioSettings::default_path()
+        .map(|path| AudioSettings::load_or_default(&path))
+        .unwrap_or_default();
+    let probe = AudioProbe::with_settings(&audio_settings).unwrap_or_else(|e| {
+        if audio_settings != AudioSettings::default() {
+            log::warn!("Saved audio setup unavailable ({e}), using the default output");
+        }
+        AudioProbe::default_output()
+    });
     let sample_rate = probe.sample_rate();
 
-    let (engine, controller) = create_synth(sample_rate);
-    let engine = Arc::new(Mutex::new(engine));
+    let (mut engine, controller) = create_synth(sample_rate);
     let controller = Arc::new(Mutex::new(controller));
 
     let patches_dir = std::path::Path::new("patches");
-    let presets = preset_loader::scan_patches_dir(patches_dir);
+    let mut presets = preset_loader::scan_patches_dir(patches_dir);
+    // Voices saved from the LIBRARY page follow the bundled collections.
+    if let Some(dir) = user_presets::default_dir() {
+        presets.extend(user_presets::load_dir(&dir));
+    }
     if presets.is_empty() {
         log::warn!(
             "No presets found in {:?} — add JSON files to patches/ subdirectories",
@@ -94,20 +303,31 @@ fn main() -> Result<(), eframe::Error> {
         );
     }
 
-    // Apply the first preset and hand the full list to the engine (for MIDI PC).
-    if let Ok(mut eng) = engine.lock() {
-        eng.set_presets(presets.clone());
-        if let Some(first) = presets.first() {
-            first.apply_to_synth(&mut eng);
-        }
+    // Apply the startup voice count and first preset, and hand the full list to
+    // the engine (for MIDI PC), before the audio thread takes it over.
+    if let Some(voices) = voices {
+        engine.set_polyphony(voices);
     }
+    engine.set_presets(presets.clone());
+    if let Some(first) = presets.first() {
+        first.apply_to_synth(&mut engine);
+    }
+
+    // Second engine for auditioning presets on hover, mixed into the same stream.
+    let (preview, preview_engine) = PresetPreview::new(sample_rate);
 
     // Create audio engine
     let underrun_counter = Arc::new(AtomicUsize::new(0));
-    let audio_engine = AudioEngine::new(probe, engine.clone(), underrun_counter);
+    let audio_engine = AudioEngine::new(probe, engine, Some(preview_engine), underrun_counter);
 
-    // Create MIDI handler
-    let _midi_handler = match MidiHandler::new(controller.clone()) {
+    // Create MIDI handler. The GUI shares its learn table to arm learns and
+    // keep the bindings across port changes.
+    let midi_learn = Arc::new(Mutex::new(MidiLearn::new(settings.cc_bindings.clone())));
+    let _midi_handler = match MidiHandler::new(
+        controller.clone(),
+        settings.midi_port.as_deref(),
+        midi_learn.clone(),
+    ) {
         Ok(handler) => {
             log::info!("MIDI input initialized successfully");
             Some(handler)
@@ -119,20 +339,28 @@ fn main() -> Result<(), eframe::Error> {
         }
     };
 
-    // Play startup melody
-    play_startup_melody(controller.clone());
+    // Off unless enabled in the Settings dialog; the AUDIO page plays the
+    // same arpeggio on demand as its test tone.
+    if settings.startup_melody {
+        play_test_tone(controller.clone(), STARTUP_MELODY_DELAY);
+    }
 
     eframe::run_native(
         "DX7-Style FM Synthesizer",
         options,
-        Box::new(move |_cc| {
-            Ok(Box::new(Dx7App::new(
-                engine,
+        Box::new(move |cc| {
+            let mut app = Dx7App::new(
                 controller,
                 audio_engine,
                 _midi_handler,
+                midi_learn,
                 presets,
-            )))
+                Some(preview),
+            );
+            app.start_repaint_notifier(&cc.egui_ctx);
+            #[cfg(unix)]
+            app.listen_for_dump_signal();
+            Ok(Box::new(app))
         }),
     )
 }
@@ -141,36 +369,123 @@ fn main() -> Result<(), eframe::Error> {
 mod tests {
     use super::*;
 
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn render_args_defaults_and_overrides() {
+        let parsed = parse_render_args(&args(&["--render", "song.mid"])).unwrap();
+        assert_eq!(parsed.output, PathBuf::from("song.wav"));
+        assert_eq!(parsed.sample_rate, 44_100);
+        assert_eq!(parsed.preset, None);
+
+        let parsed = parse_render_args(&args(&[
+            "--render",
+            "notes.txt",
+            "--out",
+            "out.wav",
+            "--rate",
+            "96000",
+            "--preset",
+            "BRASS 1",
+            "--tail",
+            "0.5",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.output, PathBuf::from("out.wav"));
+        assert_eq!(parsed.sample_rate, 96_000);
+        assert_eq!(parsed.preset.as_deref(), Some("BRASS 1"));
+        assert_eq!(parsed.tail_seconds, 0.5);
+    }
+
+    #[test]
+    fn render_args_reject_bad_values() {
+        assert!(parse_render_args(&args(&["--render"])).is_err());
+        assert!(parse_render_args(&args(&["--render", "a.mid", "--rate", "7"])).is_err());
+        assert!(parse_render_args(&args(&["--render", "a.mid", "--tail", "x"])).is_err());
+    }
+
     #[test]
-    fn play_startup_melody_returns_immediately() {
-        let (_engine, controller) = fm_synth::create_synth(44_100.0);
-        let controller = Arc::new(Mutex::new(controller));
-        let start = std::time::Instant::now();
-        play_startup_melody(controller);
-        assert!(start.elapsed() < Duration::from_millis(100));
+    fn convert_args_default_the_output_by_direction() {
+        let parsed = parse_convert_args(&args(&["--convert", "rom1a.syx"])).unwrap();
+        assert_eq!(parsed.output, PathBuf::from("rom1a"));
+        assert!(!parsed.preview);
+        let parsed = parse_convert_args(&args(&["--convert", "pad.json", "--preview"])).unwrap();
+        assert_eq!(parsed.output, PathBuf::from("pad.syx"));
+        assert!(parsed.preview);
+        let parsed = parse_convert_args(&args(&[
+            "--convert",
+            "rom1a.syx",
+            "--out",
+            "voices",
+            "--rate",
+            "22050",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.output, PathBuf::from("voices"));
+        assert_eq!(parsed.sample_rate, 22_050);
+
+        assert!(parse_convert_args(&args(&["--convert"])).is_err());
+        assert!(parse_convert_args(&args(&["--convert", "bank"])).is_err());
     }
 
     #[test]
-    fn play_melody_eventually_pushes_notes() {
-        let (mut engine, controller) = fm_synth::create_synth(44_100.0);
-        let controller = Arc::new(Mutex::new(controller));
-        play_melody(
-            controller,
-            Duration::from_millis(0),
-            Duration::from_millis(5),
-            Duration::from_millis(1),
+    fn run_convert_writes_voice_files_and_previews() {
+        let dir = std::env::temp_dir().join(format!("dx7_convert_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("bank.syx");
+        std::fs::write(&input, synth_fm_rs::sysex::encode_bank(&[], 0)).unwrap();
+        let convert_args = ConvertArgs {
+            output: dir.join("bank"),
+            input,
+            preview: true,
+            sample_rate: 22_050,
+        };
+        run_convert(&convert_args).unwrap();
+        assert!(dir.join("bank/32-init-voice.json").is_file());
+        assert!(dir.join("bank/01-init-voice.wav").is_file());
+
+        let back = ConvertArgs {
+            input: dir.join("bank"),
+            output: dir.join("again.syx"),
+            preview: false,
+            sample_rate: 22_050,
+        };
+        run_convert(&back).unwrap();
+        assert_eq!(
+            std::fs::read(dir.join("again.syx")).unwrap(),
+            std::fs::read(dir.join("bank.syx")).unwrap()
         );
+        std::fs::remove_dir_all(&dir).ok();
+    }
 
-        let deadline = std::time::Instant::now() + Duration::from_millis(200);
-        let mut active_seen = false;
-        while std::time::Instant::now() < deadline {
-            engine.process_commands();
-            if engine.voices().iter().any(|v| v.active) {
-                active_seen = true;
-                break;
-            }
-            std::thread::sleep(Duration::from_millis(2));
-        }
-        assert!(active_seen, "expected at least one note-on from the melody");
+    #[test]
+    fn voices_arg_is_optional_and_range_checked() {
+        assert_eq!(parse_voices_arg(&args(&[])), Ok(None));
+        assert_eq!(parse_voices_arg(&args(&["--voices", "32"])), Ok(Some(32)));
+        assert!(parse_voices_arg(&args(&["--voices"])).is_err());
+        assert!(parse_voices_arg(&args(&["--voices", "0"])).is_err());
+        assert!(parse_voices_arg(&args(&["--voices", "65"])).is_err());
+    }
+
+    #[test]
+    fn run_render_writes_wav_from_note_list() {
+        let dir = std::env::temp_dir().join(format!("dx7_render_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("notes.txt");
+        std::fs::write(&input, "60 100 0 0.05\n").unwrap();
+        let render_args = RenderArgs {
+            output: dir.join("notes.wav"),
+            input,
+            sample_rate: 22_050,
+            preset: None,
+            tail_seconds: 0.0,
+        };
+        run_render(&render_args).unwrap();
+        let wav = std::fs::read(&render_args.output).unwrap();
+        // 0.05 s at 22.05 kHz, 16-bit stereo, plus the 44-byte header.
+        assert_eq!(wav.len(), 44 + 1103 * 4);
+        std::fs::remove_dir_all(&dir).ok();
     }
 }
@@ -1,21 +1,168 @@
 use crate::fm_synth::SynthController;
+use crate::midi_learn::MidiLearn;
+use crate::tempo::clamp_bpm;
 use midir::{MidiInput, MidiInputConnection};
 use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Sentinel for OMNI mode — accept any channel.
 const MIDI_OMNI: u8 = 0xFF;
 
+/// Undefined CC mapped to KEY TRANSPOSE: 64 is no shift and each step
+/// either side one semitone, so 40..=88 spans the ±24 range.
+pub const TRANSPOSE_CC: u8 = 3;
+
+/// MIDI clock pulses (0xF8) per quarter note.
+const CLOCK_PPQN: u32 = 24;
+
+/// A gap this long between pulses means the master paused its clock; the
+/// beat being measured is thrown away. At the slowest accepted tempo the
+/// pulses are 125 ms apart.
+const CLOCK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Where the engine tempo comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSource {
+    /// The TEMPO control.
+    Internal,
+    /// Incoming MIDI clock.
+    External,
+}
+
+/// Follows incoming MIDI clock and transport messages.
+///
+/// The tempo is measured over one beat (24 pulses) so USB jitter on single
+/// pulses averages out, and only reported when it moves by 0.1 BPM or more.
+#[derive(Debug)]
+pub struct MidiClock {
+    source: ClockSource,
+    /// Set by Start/Continue, cleared by Stop.
+    running: bool,
+    /// First pulse of the beat being measured.
+    beat_start: Option<Instant>,
+    last_pulse: Option<Instant>,
+    pulses: u32,
+    /// Last tempo reported to the engine.
+    bpm: Option<f32>,
+}
+
+impl Default for MidiClock {
+    fn default() -> Self {
+        Self {
+            source: ClockSource::Internal,
+            running: false,
+            beat_start: None,
+            last_pulse: None,
+            pulses: 0,
+            bpm: None,
+        }
+    }
+}
+
+impl MidiClock {
+    pub fn source(&self) -> ClockSource {
+        self.source
+    }
+
+    /// Switching source drops the tempo measured so far.
+    pub fn set_source(&mut self, source: ClockSource) {
+        self.source = source;
+        self.resync();
+        self.bpm = None;
+    }
+
+    /// True between a Start/Continue and a Stop from the clock master.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Tempo derived from the clock, once a full beat has been measured.
+    pub fn bpm(&self) -> Option<f32> {
+        self.bpm
+    }
+
+    /// One clock pulse at `now`. Returns the new tempo when a beat has just
+    /// been measured and it differs from the last one reported. Pulses are
+    /// ignored while the source is internal.
+    pub fn pulse(&mut self, now: Instant) -> Option<f32> {
+        if self.source != ClockSource::External {
+            return None;
+        }
+        let gap = self
+            .last_pulse
+            .map(|last| now.saturating_duration_since(last));
+        self.last_pulse = Some(now);
+        let beat_start = match self.beat_start {
+            Some(start) if gap.is_some_and(|gap| gap < CLOCK_TIMEOUT) => start,
+            _ => {
+                self.beat_start = Some(now);
+                self.pulses = 0;
+                return None;
+            }
+        };
+
+        self.pulses += 1;
+        if self.pulses < CLOCK_PPQN {
+            return None;
+        }
+        let beat = now.saturating_duration_since(beat_start).as_secs_f32();
+        self.beat_start = Some(now);
+        self.pulses = 0;
+        if beat <= 0.0 {
+            return None;
+        }
+
+        let bpm = (clamp_bpm(60.0 / beat) * 10.0).round() / 10.0;
+        if self.bpm.is_some_and(|last| (last - bpm).abs() < 0.1) {
+            return None;
+        }
+        self.bpm = Some(bpm);
+        Some(bpm)
+    }
+
+    /// Start (0xFA): the next pulse is the downbeat.
+    pub fn start(&mut self) {
+        self.running = true;
+        self.resync();
+    }
+
+    /// Continue (0xFB): resume from wherever the master was stopped.
+    pub fn resume(&mut self) {
+        self.running = true;
+        self.resync();
+    }
+
+    /// Stop (0xFC).
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    fn resync(&mut self) {
+        self.beat_start = None;
+        self.last_pulse = None;
+        self.pulses = 0;
+    }
+}
+
 pub struct MidiHandler {
     _connection: Option<MidiInputConnection<()>>,
     /// 0..15 = specific MIDI channel (1..16 to the user); MIDI_OMNI = listen on all.
     /// Shared with the midir callback so the GUI can change it without locking.
     channel_filter: Arc<AtomicU8>,
+    /// Shared with the midir callback; the GUI picks the clock source.
+    clock: Arc<Mutex<MidiClock>>,
+    port_name: String,
 }
 
 impl MidiHandler {
+    /// Connect to the input port named `preferred`, or to the first port
+    /// when it is None or no longer present. Control Changes go through
+    /// `learn` first, which the GUI shares to arm learns.
     pub fn new(
         controller: Arc<Mutex<SynthController>>,
+        preferred: Option<&str>,
+        learn: Arc<Mutex<MidiLearn>>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let midi_in = MidiInput::new("DX7 MIDI Input")?;
 
@@ -25,21 +172,37 @@ impl MidiHandler {
         }
 
         log::info!("Available MIDI inputs:");
+        let mut names = Vec::with_capacity(ports.len());
         for (i, port) in ports.iter().enumerate() {
-            log::info!("  {}: {}", i, midi_in.port_name(port)?);
+            let name = midi_in.port_name(port)?;
+            log::info!("  {}: {}", i, name);
+            names.push(name);
         }
 
-        let port = &ports[0];
-        log::info!("Using MIDI input: {}", midi_in.port_name(port)?);
+        let index = match preferred {
+            Some(wanted) => names.iter().position(|n| n == wanted).unwrap_or_else(|| {
+                log::warn!("MIDI input '{}' not found, using the first port", wanted);
+                0
+            }),
+            None => 0,
+        };
+        let port = &ports[index];
+        let port_name = names.swap_remove(index);
+        log::info!("Using MIDI input: {}", port_name);
 
         let channel_filter = Arc::new(AtomicU8::new(MIDI_OMNI));
         let filter_for_callback = channel_filter.clone();
+        let clock = Arc::new(Mutex::new(MidiClock::default()));
+        let clock_for_callback = clock.clone();
 
         let connection = midi_in.connect(
             port,
             "DX7 MIDI",
             move |_timestamp, message, _| {
-                Self::handle_midi_message(&controller, message, &filter_for_callback);
+                if !Self::handle_realtime(&controller, message, &clock_for_callback, Instant::now())
+                {
+                    Self::handle_midi_message(&controller, message, &filter_for_callback, &learn);
+                }
             },
             (),
         )?;
@@ -47,9 +210,28 @@ impl MidiHandler {
         Ok(Self {
             _connection: Some(connection),
             channel_filter,
+            clock,
+            port_name,
         })
     }
 
+    /// Names of the MIDI input ports present right now.
+    pub fn input_ports() -> Vec<String> {
+        let Ok(midi_in) = MidiInput::new("DX7 MIDI Probe") else {
+            return Vec::new();
+        };
+        midi_in
+            .ports()
+            .iter()
+            .filter_map(|port| midi_in.port_name(port).ok())
+            .collect()
+    }
+
+    /// Name of the connected input port.
+    pub fn port_name(&self) -> &str {
+        &self.port_name
+    }
+
     /// Configure which MIDI channel to listen on. `None` selects OMNI mode (default).
     /// `Some(0..15)` accepts only that 0-indexed channel (MIDI ch 1 = 0).
     pub fn set_channel(&self, channel: Option<u8>) {
@@ -71,10 +253,87 @@ impl MidiHandler {
         }
     }
 
+    /// Follow external MIDI clock or keep to the TEMPO control.
+    pub fn set_clock_source(&self, source: ClockSource) {
+        if let Ok(mut clock) = self.clock.lock() {
+            clock.set_source(source);
+        }
+    }
+
+    pub fn clock_source(&self) -> ClockSource {
+        self.clock
+            .lock()
+            .map_or(ClockSource::Internal, |clock| clock.source())
+    }
+
+    /// True while the external clock master is playing (after Start or
+    /// Continue, until Stop).
+    pub fn clock_running(&self) -> bool {
+        self.clock.lock().is_ok_and(|clock| clock.is_running())
+    }
+
+    /// System Real-Time messages: clock pulses and transport. They are a
+    /// single status byte and may arrive between the bytes of any other
+    /// message, so they are handled before channel filtering. Returns false
+    /// for anything else.
+    fn handle_realtime(
+        controller: &Arc<Mutex<SynthController>>,
+        message: &[u8],
+        clock: &Mutex<MidiClock>,
+        now: Instant,
+    ) -> bool {
+        let Some(&status) = message.first() else {
+            return false;
+        };
+        let Ok(mut clock) = clock.lock() else {
+            log::error!("Failed to acquire MIDI clock lock");
+            return status >= 0xF8;
+        };
+        match status {
+            0xF8 => {
+                if let Some(bpm) = clock.pulse(now) {
+                    log::debug!("MIDI clock tempo: {:.1} BPM", bpm);
+                    if let Ok(mut ctrl) = controller.lock() {
+                        ctrl.set_tempo(bpm);
+                    } else {
+                        log::error!("Failed to acquire controller lock for clock tempo");
+                    }
+                }
+            }
+            0xFA => {
+                log::info!("MIDI Start");
+                clock.start();
+            }
+            0xFB => {
+                log::info!("MIDI Continue");
+                clock.resume();
+            }
+            0xFC => {
+                log::info!("MIDI Stop");
+                clock.stop();
+            }
+            0xFF => {
+                log::info!("MIDI System Reset");
+                clock.stop();
+                if let Ok(mut ctrl) = controller.lock() {
+                    ctrl.panic();
+                    ctrl.reset_controllers();
+                } else {
+                    log::error!("Failed to acquire controller lock for system reset");
+                }
+            }
+            // Active Sensing and the undefined real-time bytes.
+            0xF9..=0xFE => {}
+            _ => return false,
+        }
+        true
+    }
+
     fn handle_midi_message(
         controller: &Arc<Mutex<SynthController>>,
         message: &[u8],
         channel_filter: &Arc<AtomicU8>,
+        learn: &Mutex<MidiLearn>,
     ) {
         if message.is_empty() {
             return;
@@ -155,14 +414,38 @@ impl MidiHandler {
                     let controller_num = message[1];
                     let value = message[2];
 
+                    // A learned binding replaces the CC's usual meaning.
+                    let learned = learn
+                        .lock()
+                        .ok()
+                        .and_then(|mut learn| learn.control_change(controller_num, value));
+                    if let Some((param, target)) = learned {
+                        log::debug!(
+                            "Control Change Ch{} CC{} ({}) Value:{}",
+                            channel,
+                            controller_num,
+                            param.label(),
+                            value
+                        );
+                        if let Ok(mut ctrl) = controller.lock() {
+                            param.set(&mut ctrl, target);
+                        } else {
+                            log::error!("Failed to acquire controller lock for learned CC");
+                        }
+                        return;
+                    }
+
                     let cc_name = match controller_num {
                         0 => "Bank Select MSB",
                         1 => "Mod Wheel",
                         2 => "Breath Controller",
+                        TRANSPOSE_CC => "Transpose",
                         4 => "Foot Controller",
                         11 => "Expression",
                         32 => "Bank Select LSB",
                         64 => "Sustain Pedal",
+                        120 => "All Sound Off",
+                        121 => "Reset All Controllers",
                         123 => "All Notes Off",
                         _ => "Unknown CC",
                     };
@@ -179,11 +462,14 @@ impl MidiHandler {
                             0 => ctrl.set_bank_msb(value),
                             1 => ctrl.mod_wheel(value as f32 / 127.0),
                             2 => ctrl.breath_controller(value as f32 / 127.0),
+                            TRANSPOSE_CC => ctrl.set_transpose(Self::transpose_from_cc(value)),
                             4 => ctrl.foot_controller(value as f32 / 127.0),
                             11 => ctrl.expression(value as f32 / 127.0),
                             32 => ctrl.set_bank_lsb(value),
                             64 => ctrl.sustain_pedal(value >= 64),
-                            123 => ctrl.panic(),
+                            120 => ctrl.panic(),
+                            121 => ctrl.reset_controllers(),
+                            123 => ctrl.all_notes_off(),
                             _ => {}
                         }
                     } else {
@@ -206,6 +492,27 @@ impl MidiHandler {
                 }
             }
 
+            // Polyphonic Key Pressure (0xA0) — note + pressure. Same routes as
+            // channel aftertouch, applied to that key's voice only.
+            0xA0 => {
+                if message.len() >= 3 {
+                    let note = message[1];
+                    let pressure = message[2];
+                    log::debug!(
+                        "Poly Aftertouch Ch{} Note:{} ({}) Pressure:{}",
+                        channel,
+                        note,
+                        Self::note_name(note),
+                        pressure
+                    );
+                    if let Ok(mut ctrl) = controller.lock() {
+                        ctrl.poly_aftertouch(note, pressure as f32 / 127.0);
+                    } else {
+                        log::error!("Failed to acquire controller lock for poly aftertouch");
+                    }
+                }
+            }
+
             0xE0 => {
                 if message.len() >= 3 {
                     let lsb = message[1] as i16;
@@ -265,12 +572,29 @@ impl MidiHandler {
                     ctrl.load_sysex_bulk(presets);
                 }
             }
+            Ok(SysexResult::Tuning(tuning)) => {
+                log::info!("SysEx: MTS tuning '{}' received", tuning.name);
+                if let Ok(mut ctrl) = controller.lock() {
+                    ctrl.set_tuning(*tuning);
+                }
+            }
+            Ok(SysexResult::NoteTuning(changes)) => {
+                log::debug!("SysEx: MTS retune of {} keys", changes.len());
+                if let Ok(mut ctrl) = controller.lock() {
+                    ctrl.retune_notes(changes);
+                }
+            }
             Err(e) => {
                 log::warn!("SysEx parse error ({} bytes): {}", message.len(), e);
             }
         }
     }
 
+    /// Semitone shift for a `TRANSPOSE_CC` value.
+    fn transpose_from_cc(value: u8) -> i8 {
+        (value.min(127) as i8 - 64).clamp(-24, 24)
+    }
+
     pub(crate) fn note_name(note: u8) -> String {
         let notes = [
             "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
@@ -286,7 +610,27 @@ impl MidiHandler {
         message: &[u8],
         channel_filter: &Arc<AtomicU8>,
     ) {
-        Self::handle_midi_message(controller, message, channel_filter);
+        Self::dispatch_learn(controller, message, channel_filter, &Mutex::default());
+    }
+
+    #[cfg(test)]
+    pub(crate) fn dispatch_learn(
+        controller: &Arc<Mutex<SynthController>>,
+        message: &[u8],
+        channel_filter: &Arc<AtomicU8>,
+        learn: &Mutex<MidiLearn>,
+    ) {
+        Self::handle_midi_message(controller, message, channel_filter, learn);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn dispatch_realtime(
+        controller: &Arc<Mutex<SynthController>>,
+        message: &[u8],
+        clock: &Mutex<MidiClock>,
+        now: Instant,
+    ) -> bool {
+        Self::handle_realtime(controller, message, clock, now)
     }
 
     #[cfg(test)]
@@ -365,13 +709,62 @@ mod tests {
     #[test]
     fn control_change_routes_recognised_ccs() {
         let (ctrl, filter) = make_controller();
-        for cc in [0u8, 1, 2, 4, 11, 32, 64, 123] {
+        for cc in [0u8, 1, 2, TRANSPOSE_CC, 4, 11, 32, 64, 120, 121, 123] {
             MidiHandler::dispatch(&ctrl, &[0xB0, cc, 64], &filter);
         }
         // Unknown CC: still handled (no-op)
         MidiHandler::dispatch(&ctrl, &[0xB0, 50, 64], &filter);
     }
 
+    #[test]
+    fn transpose_cc_is_centred_on_64() {
+        assert_eq!(MidiHandler::transpose_from_cc(64), 0);
+        assert_eq!(MidiHandler::transpose_from_cc(76), 12);
+        assert_eq!(MidiHandler::transpose_from_cc(52), -12);
+        assert_eq!(MidiHandler::transpose_from_cc(0), -24);
+        assert_eq!(MidiHandler::transpose_from_cc(127), 24);
+
+        let (mut engine, controller) = create_synth(44_100.0);
+        let ctrl = Arc::new(Mutex::new(controller));
+        let filter = Arc::new(AtomicU8::new(MidiHandler::omni_sentinel()));
+        MidiHandler::dispatch(&ctrl, &[0xB0, TRANSPOSE_CC, 71], &filter);
+        engine.process_commands();
+        engine.update_snapshot();
+        assert_eq!(ctrl.lock().unwrap().snapshot().transpose_semitones, 7);
+    }
+
+    #[test]
+    fn learned_ccs_replace_their_usual_meaning() {
+        use crate::data_entry::{DataEntryParam, OperatorField};
+
+        let (mut engine, controller) = create_synth(44_100.0);
+        let ctrl = Arc::new(Mutex::new(controller));
+        let filter = Arc::new(AtomicU8::new(MidiHandler::omni_sentinel()));
+        let learn = Mutex::new(MidiLearn::default());
+        let level = DataEntryParam::Operator(2, OperatorField::Level);
+
+        learn.lock().unwrap().arm(level);
+        MidiHandler::dispatch_learn(&ctrl, &[0xB0, TRANSPOSE_CC, 0], &filter, &learn);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snapshot = ctrl.lock().unwrap().snapshot();
+        assert_eq!(snapshot.operators[2].output_level, 0.0);
+        assert_eq!(snapshot.transpose_semitones, 0);
+
+        MidiHandler::dispatch_learn(&ctrl, &[0xB0, TRANSPOSE_CC, 64], &filter, &learn);
+        // Unbound CCs keep working.
+        MidiHandler::dispatch_learn(&ctrl, &[0xB0, 1, 127], &filter, &learn);
+        engine.process_commands();
+        engine.update_snapshot();
+        let snapshot = ctrl.lock().unwrap().snapshot();
+        assert_eq!(snapshot.operators[2].output_level, 50.0);
+        assert!(snapshot.mod_wheel > 0.9);
+        assert_eq!(
+            learn.lock().unwrap().take_learned(),
+            Some((TRANSPOSE_CC, level))
+        );
+    }
+
     #[test]
     fn control_change_truncated_is_ignored() {
         let (ctrl, filter) = make_controller();
@@ -390,6 +783,13 @@ mod tests {
         MidiHandler::dispatch(&ctrl, &[0xD0], &filter);
     }
 
+    #[test]
+    fn poly_aftertouch_dispatches() {
+        let (ctrl, filter) = make_controller();
+        MidiHandler::dispatch(&ctrl, &[0xA0, 60, 100], &filter);
+        MidiHandler::dispatch(&ctrl, &[0xA0, 60], &filter); // truncated: ignored
+    }
+
     #[test]
     fn pitch_bend_combines_lsb_and_msb() {
         let (ctrl, filter) = make_controller();
@@ -408,8 +808,8 @@ mod tests {
     #[test]
     fn unknown_status_byte_is_logged_but_safe() {
         let (ctrl, filter) = make_controller();
-        // 0xA0 = polyphonic key pressure (we don't handle it specifically)
-        MidiHandler::dispatch(&ctrl, &[0xA0, 60, 100], &filter);
+        // A data byte in the status slot (running status) matches no handler.
+        MidiHandler::dispatch(&ctrl, &[0x40, 60, 100], &filter);
     }
 
     #[test]
@@ -455,6 +855,8 @@ mod tests {
         MidiHandler {
             _connection: None,
             channel_filter: Arc::new(AtomicU8::new(MidiHandler::omni_sentinel())),
+            clock: Arc::new(Mutex::new(MidiClock::default())),
+            port_name: String::new(),
         }
     }
 
@@ -518,4 +920,132 @@ mod tests {
         let (ctrl, filter) = make_controller();
         MidiHandler::dispatch(&ctrl, &bytes, &filter);
     }
+
+    /// `count` pulses at `bpm`, starting at `t0`; returns the time after the last.
+    fn pulses(clock: &mut MidiClock, t0: Instant, bpm: f32, count: u32) -> (Instant, Vec<f32>) {
+        let period = Duration::from_secs_f32(60.0 / bpm / CLOCK_PPQN as f32);
+        let mut reported = Vec::new();
+        let mut t = t0;
+        for _ in 0..count {
+            reported.extend(clock.pulse(t));
+            t += period;
+        }
+        (t, reported)
+    }
+
+    #[test]
+    fn external_clock_reports_tempo_once_per_changed_beat() {
+        let mut clock = MidiClock::default();
+        clock.set_source(ClockSource::External);
+        let t0 = Instant::now();
+        // The first beat needs 25 pulses: the first one only starts the count.
+        let (t, reported) = pulses(&mut clock, t0, 132.0, 3 * CLOCK_PPQN + 1);
+        assert_eq!(reported, vec![132.0]);
+        assert_eq!(clock.bpm(), Some(132.0));
+
+        // The beat spanning the change blends both tempos; the next is exact.
+        let (_, reported) = pulses(&mut clock, t, 90.0, 2 * CLOCK_PPQN);
+        assert_eq!(reported.len(), 2, "{reported:?}");
+        assert!(reported[0] > 90.0 && reported[0] < 132.0, "{reported:?}");
+        assert!((reported[1] - 90.0).abs() <= 0.1, "{reported:?}");
+    }
+
+    #[test]
+    fn internal_source_ignores_pulses_and_gaps_restart_the_beat() {
+        let mut clock = MidiClock::default();
+        let t0 = Instant::now();
+        let (_, reported) = pulses(&mut clock, t0, 120.0, 4 * CLOCK_PPQN);
+        assert!(reported.is_empty());
+        assert_eq!(clock.bpm(), None);
+
+        clock.set_source(ClockSource::External);
+        let (t, _) = pulses(&mut clock, t0, 120.0, CLOCK_PPQN / 2);
+        // The master pauses mid-beat; the half beat before it is dropped.
+        let (_, reported) = pulses(
+            &mut clock,
+            t + Duration::from_secs(2),
+            120.0,
+            CLOCK_PPQN + 1,
+        );
+        assert_eq!(reported, vec![120.0]);
+    }
+
+    #[test]
+    fn transport_messages_drive_the_running_flag() {
+        let (_engine, ctrl) = create_synth(44_100.0);
+        let ctrl = Arc::new(Mutex::new(ctrl));
+        let clock = Mutex::new(MidiClock::default());
+        let now = Instant::now();
+
+        assert!(MidiHandler::dispatch_realtime(&ctrl, &[0xFA], &clock, now));
+        assert!(clock.lock().unwrap().is_running());
+        assert!(MidiHandler::dispatch_realtime(&ctrl, &[0xFC], &clock, now));
+        assert!(!clock.lock().unwrap().is_running());
+        assert!(MidiHandler::dispatch_realtime(&ctrl, &[0xFB], &clock, now));
+        assert!(clock.lock().unwrap().is_running());
+        assert!(MidiHandler::dispatch_realtime(&ctrl, &[0xFE], &clock, now));
+
+        // Channel and SysEx messages are left to the regular handler.
+        assert!(!MidiHandler::dispatch_realtime(
+            &ctrl,
+            &[0x90, 60, 100],
+            &clock,
+            now
+        ));
+        assert!(!MidiHandler::dispatch_realtime(
+            &ctrl,
+            &[0xF0, 0xF7],
+            &clock,
+            now
+        ));
+        assert!(!MidiHandler::dispatch_realtime(&ctrl, &[], &clock, now));
+    }
+
+    #[test]
+    fn system_reset_stops_the_clock_and_every_voice() {
+        let (mut engine, ctrl) = create_synth(44_100.0);
+        let ctrl = Arc::new(Mutex::new(ctrl));
+        let clock = Mutex::new(MidiClock::default());
+        let filter = Arc::new(AtomicU8::new(MidiHandler::omni_sentinel()));
+        let now = Instant::now();
+        MidiHandler::dispatch_realtime(&ctrl, &[0xFA], &clock, now);
+        MidiHandler::dispatch(&ctrl, &[0x90, 60, 100], &filter);
+        MidiHandler::dispatch(&ctrl, &[0xE0, 0, 100], &filter);
+
+        assert!(MidiHandler::dispatch_realtime(&ctrl, &[0xFF], &clock, now));
+        assert!(!clock.lock().unwrap().is_running());
+        engine.process_commands();
+        engine.update_snapshot();
+        let snapshot = ctrl.lock().unwrap().snapshot();
+        assert_eq!(snapshot.active_voices, 0);
+        assert_eq!(snapshot.pitch_bend, 0.0);
+    }
+
+    #[test]
+    fn clock_pulses_set_the_engine_tempo() {
+        let (mut engine, ctrl) = create_synth(44_100.0);
+        let ctrl = Arc::new(Mutex::new(ctrl));
+        let clock = Mutex::new(MidiClock::default());
+        clock.lock().unwrap().set_source(ClockSource::External);
+
+        let period = Duration::from_secs_f32(60.0 / 100.0 / CLOCK_PPQN as f32);
+        let mut t = Instant::now();
+        for _ in 0..=CLOCK_PPQN {
+            MidiHandler::dispatch_realtime(&ctrl, &[0xF8], &clock, t);
+            t += period;
+        }
+        engine.process_commands();
+        engine.update_snapshot();
+        let bpm = ctrl.lock().unwrap().snapshot().tempo_bpm;
+        assert!((bpm - 100.0).abs() <= 0.1, "{bpm}");
+    }
+
+    #[test]
+    fn handler_exposes_the_clock_source() {
+        let h = stub_handler();
+        assert_eq!(h.clock_source(), ClockSource::Internal);
+        h.set_clock_source(ClockSource::External);
+        assert_eq!(h.clock_source(), ClockSource::External);
+        assert!(!h.clock_running());
+    }
 }
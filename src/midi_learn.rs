@@ -0,0 +1,234 @@
+//! MIDI learn: hardware knobs bound to editor parameters.
+//!
+//! Right-clicking a slider arms learn for its parameter; the next Control
+//! Change that arrives binds its number to it. A bound CC then sweeps the
+//! parameter over its whole DX7 range, 0 at the bottom and 127 at the top,
+//! in place of whatever the CC did before.
+//!
+//! [`MidiLearn`] is shared as `Arc<Mutex<MidiLearn>>` between the GUI,
+//! which arms learn and edits the bindings, and the MIDI input callback,
+//! which completes learns and looks bindings up. The bindings are saved
+//! with the app settings.
+
+use crate::data_entry::{DataEntryParam, OperatorField};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// CCs a binding may use: all but Bank Select (0 and 32) and the channel
+/// mode messages (120-127), which keep their meaning.
+pub fn learnable(cc: u8) -> bool {
+    matches!(cc, 1..=31 | 33..=119)
+}
+
+/// Parameter value for CC value `value`: 0-127 spread over the
+/// parameter's range, rounded to the nearest step.
+pub fn scale(param: DataEntryParam, value: u8) -> i32 {
+    let range = param.range();
+    let span = range.end() - range.start();
+    range.start() + (value.min(127) as i32 * span + 63) / 127
+}
+
+/// False for targets no build produces: operators past OP6 or envelope
+/// stages past 4, from a hand-edited file.
+fn exists(param: DataEntryParam) -> bool {
+    match param {
+        DataEntryParam::Operator(op, field) => {
+            op < 6
+                && match field {
+                    OperatorField::EgRate(i) | OperatorField::EgLevel(i) => i < 4,
+                    _ => true,
+                }
+        }
+        _ => true,
+    }
+}
+
+/// CC number → parameter. Saved with the app settings as a JSON object
+/// keyed by CC number.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CcBindings {
+    entries: BTreeMap<u8, DataEntryParam>,
+}
+
+impl CcBindings {
+    /// Make `cc` drive `param`. A parameter follows one CC, so a CC it was
+    /// bound to before is released. Ignored for CCs that are not
+    /// [`learnable`].
+    pub fn bind(&mut self, cc: u8, param: DataEntryParam) {
+        if !learnable(cc) {
+            return;
+        }
+        self.entries.retain(|_, bound| *bound != param);
+        self.entries.insert(cc, param);
+    }
+
+    pub fn remove(&mut self, cc: u8) {
+        self.entries.remove(&cc);
+    }
+
+    pub fn get(&self, cc: u8) -> Option<DataEntryParam> {
+        self.entries.get(&cc).copied()
+    }
+
+    /// CC bound to `param`, if any.
+    pub fn cc_for(&self, param: DataEntryParam) -> Option<u8> {
+        self.iter()
+            .find(|&(_, bound)| bound == param)
+            .map(|(cc, _)| cc)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Bindings in CC order.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, DataEntryParam)> + '_ {
+        self.entries.iter().map(|(&cc, &param)| (cc, param))
+    }
+
+    /// Drop bindings on CCs that can't be learned or to parameters that
+    /// don't exist, from a hand-edited file.
+    pub fn clamped(mut self) -> Self {
+        self.entries
+            .retain(|&cc, &mut param| learnable(cc) && exists(param));
+        self
+    }
+}
+
+/// The bindings plus the learn in progress.
+#[derive(Debug, Default)]
+pub struct MidiLearn {
+    bindings: CcBindings,
+    /// Parameter waiting for a CC.
+    armed: Option<DataEntryParam>,
+    /// Binding made since the GUI last asked, for its status line.
+    learned: Option<(u8, DataEntryParam)>,
+}
+
+impl MidiLearn {
+    pub fn new(bindings: CcBindings) -> Self {
+        Self {
+            bindings,
+            ..Self::default()
+        }
+    }
+
+    pub fn bindings(&self) -> &CcBindings {
+        &self.bindings
+    }
+
+    pub fn set_bindings(&mut self, bindings: CcBindings) {
+        self.bindings = bindings;
+    }
+
+    pub fn remove(&mut self, cc: u8) {
+        self.bindings.remove(cc);
+    }
+
+    /// Bind the next learnable CC that arrives to `param`.
+    pub fn arm(&mut self, param: DataEntryParam) {
+        self.armed = Some(param);
+    }
+
+    pub fn cancel(&mut self) {
+        self.armed = None;
+    }
+
+    pub fn armed(&self) -> Option<DataEntryParam> {
+        self.armed
+    }
+
+    /// The binding the last learn made, once.
+    pub fn take_learned(&mut self) -> Option<(u8, DataEntryParam)> {
+        self.learned.take()
+    }
+
+    /// A Control Change from the MIDI input. Completes an armed learn, then
+    /// returns the parameter `cc` drives and the value to set it to; None
+    /// when the CC is unbound and keeps its usual meaning.
+    pub fn control_change(&mut self, cc: u8, value: u8) -> Option<(DataEntryParam, i32)> {
+        if let Some(param) = self.armed.filter(|_| learnable(cc)) {
+            self.bindings.bind(cc, param);
+            self.armed = None;
+            self.learned = Some((cc, param));
+        }
+        let param = self.bindings.get(cc)?;
+        Some((param, scale(param, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_entry::ControllerField;
+
+    const LEVEL: DataEntryParam = DataEntryParam::Operator(0, OperatorField::Level);
+
+    #[test]
+    fn cc_values_span_the_whole_range() {
+        assert_eq!(scale(LEVEL, 0), 0);
+        assert_eq!(scale(LEVEL, 64), 50);
+        assert_eq!(scale(LEVEL, 127), 99);
+        assert_eq!(scale(DataEntryParam::Algorithm, 127), 32);
+        assert_eq!(scale(DataEntryParam::Transpose, 0), -24);
+        assert_eq!(scale(DataEntryParam::Transpose, 64), 0);
+        assert_eq!(scale(DataEntryParam::LfoKeySync, 63), 0);
+        assert_eq!(scale(DataEntryParam::LfoKeySync, 64), 1);
+    }
+
+    #[test]
+    fn the_next_cc_completes_a_learn() {
+        let mut learn = MidiLearn::default();
+        assert_eq!(learn.control_change(74, 127), None);
+
+        learn.arm(LEVEL);
+        // Bank select and channel mode messages are passed over.
+        assert_eq!(learn.control_change(0, 1), None);
+        assert_eq!(learn.control_change(123, 0), None);
+        assert_eq!(learn.armed(), Some(LEVEL));
+
+        assert_eq!(learn.control_change(74, 127), Some((LEVEL, 99)));
+        assert_eq!(learn.armed(), None);
+        assert_eq!(learn.take_learned(), Some((74, LEVEL)));
+        assert_eq!(learn.take_learned(), None);
+        assert_eq!(learn.control_change(74, 0), Some((LEVEL, 0)));
+
+        // Learning the same parameter again moves it to the new CC.
+        learn.arm(LEVEL);
+        learn.control_change(1, 0);
+        assert_eq!(learn.bindings().cc_for(LEVEL), Some(1));
+        assert_eq!(learn.bindings().get(74), None);
+
+        learn.arm(DataEntryParam::Algorithm);
+        learn.cancel();
+        assert_eq!(learn.control_change(20, 0), None);
+        learn.remove(1);
+        assert!(learn.bindings().is_empty());
+    }
+
+    #[test]
+    fn saves_as_an_object_keyed_by_cc() {
+        let mut bindings = CcBindings::default();
+        bindings.bind(74, LEVEL);
+        bindings.bind(71, DataEntryParam::LfoRate);
+        bindings.bind(
+            72,
+            DataEntryParam::Controller(ControllerField::ModWheelPitchBias),
+        );
+        let json = serde_json::to_string(&bindings).unwrap();
+        assert_eq!(
+            json,
+            r#"{"71":"lfo_rate","72":{"controller":"mod_wheel_pitch_bias"},"74":{"operator":[0,"level"]}}"#
+        );
+        assert_eq!(serde_json::from_str::<CcBindings>(&json).unwrap(), bindings);
+
+        let edited: CcBindings = serde_json::from_str(
+            r#"{"20":"algorithm","32":"algorithm","21":{"operator":[6,"level"]},
+                "22":{"operator":[1,{"eg_rate":4}]},"23":{"operator":[1,{"eg_rate":3}]}}"#,
+        )
+        .unwrap();
+        let kept: Vec<u8> = edited.clamped().iter().map(|(cc, _)| cc).collect();
+        assert_eq!(kept, [20, 23]);
+    }
+}
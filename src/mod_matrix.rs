@@ -0,0 +1,367 @@
+//! Assignable modulation matrix.
+//!
+//! Up to `MAX_ROUTES` routes, each connecting one source to one destination
+//! with a signed depth. The matrix sits on top of the fixed DX7S controller
+//! assignments (the aftertouch / breath / foot sensitivities), which keep
+//! working unchanged. The engine evaluates it once per control block: the
+//! global pass drives the LFO and effect destinations, and each voice runs
+//! its own pass with its note velocity for pitch and operator levels.
+
+use crate::effects::{EffectModulation, CHORUS_MAX_DEPTH_MS};
+
+/// Number of route slots.
+pub const MAX_ROUTES: usize = 8;
+
+/// Pitch swing of a depth ±1 route at full source value.
+pub const PITCH_RANGE_SEMITONES: f32 = 12.0;
+
+/// LFO speed swing, in octaves, of a depth ±1 route at full source value.
+pub const LFO_RATE_RANGE_OCTAVES: f32 = 2.0;
+
+/// Where a route reads from. Controllers and velocity are 0..1; each LFO is
+/// its bipolar waveform (-1..1) before any depth or mod-wheel scaling, and
+/// LFO 2 reads 0 while it is switched off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModSource {
+    #[default]
+    ModWheel,
+    Breath,
+    Foot,
+    Aftertouch,
+    Velocity,
+    Lfo,
+    Lfo2,
+}
+
+impl ModSource {
+    pub fn all() -> [ModSource; 7] {
+        [
+            ModSource::ModWheel,
+            ModSource::Breath,
+            ModSource::Foot,
+            ModSource::Aftertouch,
+            ModSource::Velocity,
+            ModSource::Lfo,
+            ModSource::Lfo2,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModSource::ModWheel => "MOD WHEEL",
+            ModSource::Breath => "BREATH",
+            ModSource::Foot => "FOOT",
+            ModSource::Aftertouch => "AFTERTOUCH",
+            ModSource::Velocity => "VELOCITY",
+            ModSource::Lfo => "LFO",
+            ModSource::Lfo2 => "LFO 2",
+        }
+    }
+}
+
+/// What a route modulates. `OperatorLevel` takes a 0-based operator index.
+/// The effect destinations offset the parameter across its whole range at
+/// depth ±1 (chorus depth by `CHORUS_MAX_DEPTH_MS`, the others by 1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModDestination {
+    #[default]
+    Pitch,
+    OperatorLevel(u8),
+    LfoRate,
+    LfoDepth,
+    EffectMix,
+    ChorusDepth,
+    DelayFeedback,
+    ReverbMix,
+}
+
+impl ModDestination {
+    pub fn all() -> [ModDestination; 13] {
+        [
+            ModDestination::Pitch,
+            ModDestination::OperatorLevel(0),
+            ModDestination::OperatorLevel(1),
+            ModDestination::OperatorLevel(2),
+            ModDestination::OperatorLevel(3),
+            ModDestination::OperatorLevel(4),
+            ModDestination::OperatorLevel(5),
+            ModDestination::LfoRate,
+            ModDestination::LfoDepth,
+            ModDestination::EffectMix,
+            ModDestination::ChorusDepth,
+            ModDestination::DelayFeedback,
+            ModDestination::ReverbMix,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        const OP_LEVEL_NAMES: [&str; 6] = [
+            "OP1 LEVEL",
+            "OP2 LEVEL",
+            "OP3 LEVEL",
+            "OP4 LEVEL",
+            "OP5 LEVEL",
+            "OP6 LEVEL",
+        ];
+        match self {
+            ModDestination::Pitch => "PITCH",
+            ModDestination::OperatorLevel(op) => OP_LEVEL_NAMES[(*op as usize).min(5)],
+            ModDestination::LfoRate => "LFO RATE",
+            ModDestination::LfoDepth => "LFO DEPTH",
+            ModDestination::EffectMix => "EFFECT MIX",
+            ModDestination::ChorusDepth => "CHORUS DEPTH",
+            ModDestination::DelayFeedback => "DELAY FEEDBACK",
+            ModDestination::ReverbMix => "REVERB MIX",
+        }
+    }
+}
+
+/// One source → destination connection. Depth is -1..1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ModRoute {
+    pub source: ModSource,
+    pub destination: ModDestination,
+    pub depth: f32,
+}
+
+impl ModRoute {
+    pub fn new(source: ModSource, destination: ModDestination, depth: f32) -> Self {
+        Self {
+            source,
+            destination,
+            depth: depth.clamp(-1.0, 1.0),
+        }
+    }
+}
+
+/// Current value of every source.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ModSources {
+    pub mod_wheel: f32,
+    pub breath: f32,
+    pub foot: f32,
+    pub aftertouch: f32,
+    pub velocity: f32,
+    pub lfo: f32,
+    pub lfo2: f32,
+}
+
+impl ModSources {
+    fn value(&self, source: ModSource) -> f32 {
+        match source {
+            ModSource::ModWheel => self.mod_wheel,
+            ModSource::Breath => self.breath,
+            ModSource::Foot => self.foot,
+            ModSource::Aftertouch => self.aftertouch,
+            ModSource::Velocity => self.velocity,
+            ModSource::Lfo => self.lfo,
+            ModSource::Lfo2 => self.lfo2,
+        }
+    }
+}
+
+/// Summed route output per destination; all zero means "no change".
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ModAmounts {
+    /// Pitch offset in semitones.
+    pub pitch: f32,
+    /// Gain offset per operator: -1 silences it, +1 doubles it.
+    pub operator_level: [f32; 6],
+    /// LFO speed change in octaves.
+    pub lfo_rate: f32,
+    /// Offset on the LFO depth multiplier (1.0 = the patch's own depth).
+    pub lfo_depth: f32,
+    /// Offset added to every effect's wet mix.
+    pub effect_mix: f32,
+    /// Chorus depth offset in ms.
+    pub chorus_depth: f32,
+    pub delay_feedback: f32,
+    /// Offset added to the reverb wet mix, on top of `effect_mix`.
+    pub reverb_mix: f32,
+}
+
+impl ModAmounts {
+    pub fn operator_gain(&self, op: usize) -> f32 {
+        (1.0 + self.operator_level[op]).max(0.0)
+    }
+
+    pub fn lfo_rate_scale(&self) -> f32 {
+        2.0_f32.powf(self.lfo_rate)
+    }
+
+    pub fn lfo_depth_scale(&self) -> f32 {
+        (1.0 + self.lfo_depth).max(0.0)
+    }
+
+    pub fn effect_modulation(&self) -> EffectModulation {
+        EffectModulation {
+            mix: self.effect_mix,
+            chorus_depth_ms: self.chorus_depth,
+            delay_feedback: self.delay_feedback,
+            reverb_mix: self.reverb_mix,
+        }
+    }
+}
+
+/// The route slots. `Copy`, so it travels in commands and snapshots as is.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ModMatrix {
+    routes: [Option<ModRoute>; MAX_ROUTES],
+}
+
+impl ModMatrix {
+    /// Fill or clear one slot. Out-of-range slots are ignored.
+    pub fn set_route(&mut self, slot: usize, route: Option<ModRoute>) {
+        if let Some(entry) = self.routes.get_mut(slot) {
+            *entry = route.map(|r| ModRoute::new(r.source, r.destination, r.depth));
+        }
+    }
+
+    pub fn routes(&self) -> &[Option<ModRoute>; MAX_ROUTES] {
+        &self.routes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.iter().all(Option::is_none)
+    }
+
+    /// Sum every route's contribution for the given source values.
+    pub fn evaluate(&self, sources: &ModSources) -> ModAmounts {
+        let mut amounts = ModAmounts::default();
+        for route in self.routes.iter().flatten() {
+            let amount = sources.value(route.source) * route.depth;
+            match route.destination {
+                ModDestination::Pitch => amounts.pitch += amount * PITCH_RANGE_SEMITONES,
+                ModDestination::OperatorLevel(op) => {
+                    amounts.operator_level[(op as usize).min(5)] += amount;
+                }
+                ModDestination::LfoRate => amounts.lfo_rate += amount * LFO_RATE_RANGE_OCTAVES,
+                ModDestination::LfoDepth => amounts.lfo_depth += amount,
+                ModDestination::EffectMix => amounts.effect_mix += amount,
+                ModDestination::ChorusDepth => {
+                    amounts.chorus_depth += amount * CHORUS_MAX_DEPTH_MS;
+                }
+                ModDestination::DelayFeedback => amounts.delay_feedback += amount,
+                ModDestination::ReverbMix => amounts.reverb_mix += amount,
+            }
+        }
+        amounts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_matrix_changes_nothing() {
+        let matrix = ModMatrix::default();
+        assert!(matrix.is_empty());
+        let sources = ModSources {
+            mod_wheel: 1.0,
+            lfo: -1.0,
+            ..ModSources::default()
+        };
+        let amounts = matrix.evaluate(&sources);
+        assert_eq!(amounts, ModAmounts::default());
+        assert_eq!(amounts.operator_gain(0), 1.0);
+        assert_eq!(amounts.lfo_rate_scale(), 1.0);
+        assert_eq!(amounts.lfo_depth_scale(), 1.0);
+    }
+
+    #[test]
+    fn routes_scale_by_source_and_depth_and_sum() {
+        let mut matrix = ModMatrix::default();
+        matrix.set_route(
+            0,
+            Some(ModRoute::new(
+                ModSource::ModWheel,
+                ModDestination::Pitch,
+                0.5,
+            )),
+        );
+        matrix.set_route(
+            1,
+            Some(ModRoute::new(
+                ModSource::Velocity,
+                ModDestination::Pitch,
+                -0.25,
+            )),
+        );
+        matrix.set_route(
+            2,
+            Some(ModRoute::new(
+                ModSource::Breath,
+                ModDestination::OperatorLevel(2),
+                -1.0,
+            )),
+        );
+        matrix.set_route(
+            3,
+            Some(ModRoute::new(ModSource::Lfo, ModDestination::LfoRate, 1.0)),
+        );
+        let sources = ModSources {
+            mod_wheel: 1.0,
+            velocity: 1.0,
+            breath: 1.0,
+            lfo: 0.5,
+            ..ModSources::default()
+        };
+        let amounts = matrix.evaluate(&sources);
+        assert!((amounts.pitch - 0.25 * PITCH_RANGE_SEMITONES).abs() < 1e-6);
+        assert_eq!(amounts.operator_gain(2), 0.0);
+        assert_eq!(amounts.operator_gain(3), 1.0);
+        assert!((amounts.lfo_rate_scale() - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn lfo_routes_reach_the_effect_parameters() {
+        let mut matrix = ModMatrix::default();
+        let routes = [
+            (ModSource::Lfo, ModDestination::ChorusDepth, 0.5),
+            (ModSource::Lfo2, ModDestination::DelayFeedback, -0.4),
+            (ModSource::Lfo2, ModDestination::ReverbMix, 1.0),
+            (ModSource::ModWheel, ModDestination::EffectMix, 0.1),
+        ];
+        for (slot, (source, destination, depth)) in routes.into_iter().enumerate() {
+            matrix.set_route(slot, Some(ModRoute::new(source, destination, depth)));
+        }
+        let sources = ModSources {
+            mod_wheel: 1.0,
+            lfo: -1.0,
+            lfo2: 0.5,
+            ..ModSources::default()
+        };
+        let effects = matrix.evaluate(&sources).effect_modulation();
+        assert_eq!(effects.chorus_depth_ms, -0.5 * CHORUS_MAX_DEPTH_MS);
+        assert!((effects.delay_feedback + 0.2).abs() < 1e-6);
+        assert_eq!(effects.reverb_mix, 0.5);
+        assert_eq!(effects.mix, 0.1);
+    }
+
+    #[test]
+    fn set_route_clamps_depth_and_ignores_bad_slots() {
+        let mut matrix = ModMatrix::default();
+        let route = ModRoute {
+            source: ModSource::Foot,
+            destination: ModDestination::EffectMix,
+            depth: 3.0,
+        };
+        matrix.set_route(MAX_ROUTES, Some(route));
+        assert!(matrix.is_empty());
+        matrix.set_route(4, Some(route));
+        assert_eq!(matrix.routes()[4].map(|r| r.depth), Some(1.0));
+        matrix.set_route(4, None);
+        assert!(matrix.is_empty());
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let mut names: Vec<_> = ModDestination::all().iter().map(|d| d.name()).collect();
+        names.extend(ModSource::all().iter().map(|s| s.name()));
+        let len = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), len);
+    }
+}
@@ -1,5 +1,12 @@
+use crate::dx7_frequency::{self, coarse_fine_to_ratio, detune_factor};
 use crate::envelope::Envelope;
-use crate::optimization::{dx7_level_to_amplitude, fast_sin};
+use crate::optimization::{
+    coarse_wave_fixed, dx7_level_to_amplitude, fast_wave_fixed, phase_increment, phase_to_degrees,
+    radians_to_phase, OperatorWaveform, Phase,
+};
+use crate::smoothing::Smoothed;
+use crate::vintage::VintageConfig;
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
 /// DX7 AMS (amplitude mod sensitivity) ROM lookup, indexed 0..3.
@@ -45,7 +52,7 @@ const DX7_OUTLEVEL_DB_PER_SUBSTEP: f32 = 0.75 / 32.0;
 ///
 /// - `NegLin` / `PosLin`: linear ramp downward / upward from the breakpoint.
 /// - `NegExp` / `PosExp`: exponential ramp (faster taper near the edges).
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub enum KeyScaleCurve {
     #[default]
     NegLin,
@@ -115,8 +122,11 @@ fn outlevel_substeps_to_amplitude(substeps: i32) -> f32 {
 #[derive(Debug, Clone)]
 pub struct Operator {
     pub enabled: bool,
-    pub frequency_ratio: f32,
-    pub detune: f32,
+    pub frequency_ratio: f32, // What the oscillator runs at; follows coarse/fine unless extended
+    pub coarse: u8,           // DX7 COARSE 0-31 (0 = x0.5)
+    pub fine: u8,             // DX7 FINE 0-99
+    pub extended_ratio: bool, // Free float ratio instead of coarse/fine (not DX7)
+    pub detune: f32,          // -7..+7, DX7 panel detune
     pub output_level: f32,
     pub velocity_sensitivity: f32, // 0-7, how much velocity affects output
     pub key_scale_rate: f32,       // 0-7, envelope rate scaling
@@ -128,22 +138,39 @@ pub struct Operator {
     pub envelope: Envelope,
     pub feedback: f32,
     pub am_sensitivity: u8, // 0-3 LFO amp modulation depth scaling per operator
+    pub pm_sensitivity: Option<u8>, // 0-7 LFO pitch depth for this operator; None = voice PMS
+    pub lfo2: bool,         // Follow the second LFO instead of the main one
     pub oscillator_key_sync: bool, // OSC KEY SYNC: ON resets phase on note-on; OFF lets phase free-run
     pub fixed_frequency: bool,     // OSC MODE: false = RATIO (default), true = FIXED Hz
     pub fixed_freq_hz: f32,        // Absolute frequency in Hz when fixed_frequency = true
+    pub sub_bus: bool,             // Route this operator's output to the secondary sub bus
+    pub expression: bool,          // Scale the output level by the engine's expression input
+    pub waveform: OperatorWaveform, // Oscillator shape; Sine is the DX7
+    pub phase_offset: f32, // 0-360°, where a key-synced note starts the oscillator (not DX7)
 
     // Internal state
-    phase: f32,
-    phase_increment: f32,
+    phase: Phase, // Fixed point, wraps once per cycle
+    phase_increment: Phase,
     last_output: f32,
-    prev_output: f32, // DX7-authentic: two-sample average for feedback stability
+    prev_output: f32,    // DX7-authentic: two-sample average for feedback stability
+    current_output: f32, // Output of the latest process() call, 0 when silent (sub-bus tap)
     sample_rate: f32,
     base_frequency: f32,         // Store base frequency for real-time updates
     current_velocity: f32,       // Store velocity for real-time updates
     current_note: u8,            // Store MIDI note for key scaling
     current_lfo_amp_mod: f32,    // Latest LFO amp modulation value (-1..+1) staged by Voice
     current_eg_bias: f32,        // Static (non-oscillating) bias amount in 0..1 staged by Voice
+    level_mod: f32,              // Mod-matrix gain on the output level (1.0 = unchanged)
     cached_values: CachedValues, // Cached calculations for performance
+    vintage: VintageConfig,
+    // Ramps that follow `output_level`, `feedback` and `detune` while a note
+    // sounds, so live edits do not click; note-on snaps them.
+    level_ramp: Smoothed,
+    feedback_ramp: Smoothed,
+    detune_ramp: Smoothed,
+    // Expression input (breath / CC11) staged by Voice, ramped because
+    // controller data arrives in coarse steps.
+    expression_ramp: Smoothed,
 }
 
 impl Operator {
@@ -151,6 +178,9 @@ impl Operator {
         Self {
             enabled: true,
             frequency_ratio: 1.0,
+            coarse: 1,
+            fine: 0,
+            extended_ratio: false,
             detune: 0.0,
             output_level: 99.0,
             velocity_sensitivity: 0.0,
@@ -163,24 +193,43 @@ impl Operator {
             envelope: Envelope::new(sample_rate),
             feedback: 0.0,
             am_sensitivity: 0,
+            pm_sensitivity: None,
+            lfo2: false,
             oscillator_key_sync: true,
             fixed_frequency: false,
             fixed_freq_hz: 440.0,
+            sub_bus: false,
+            expression: false,
+            waveform: OperatorWaveform::Sine,
+            phase_offset: 0.0,
 
-            phase: 0.0,
-            phase_increment: 0.0,
+            phase: 0,
+            phase_increment: 0,
             last_output: 0.0,
             prev_output: 0.0,
+            current_output: 0.0,
             sample_rate,
             base_frequency: 440.0,
             current_velocity: 1.0,
             current_note: 60,
             current_lfo_amp_mod: 0.0,
             current_eg_bias: 0.0,
+            level_mod: 1.0,
             cached_values: CachedValues::new(),
+            vintage: VintageConfig::CLEAN,
+            level_ramp: Smoothed::new(1.0, sample_rate),
+            feedback_ramp: Smoothed::new(0.0, sample_rate),
+            detune_ramp: Smoothed::new(0.0, sample_rate),
+            expression_ramp: Smoothed::new(1.0, sample_rate),
         }
     }
 
+    /// Hardware quirks for this operator and its envelope.
+    pub fn set_vintage(&mut self, vintage: VintageConfig) {
+        self.vintage = vintage;
+        self.envelope.set_vintage(vintage);
+    }
+
     /// Stage the latest LFO amplitude modulation sample (already scaled by mod-wheel
     /// and depth). The Voice calls this before `process()` each sample so the operator
     /// can apply its own `am_sensitivity` (0-3) to scale the impact.
@@ -196,10 +245,27 @@ impl Operator {
         self.current_eg_bias = value.clamp(0.0, 1.0);
     }
 
+    /// Stage the expression input (0..1). Only operators with `expression`
+    /// set follow it, as a gain on their output level: a wind player's
+    /// breath swells the carriers it is routed to.
+    pub fn set_expression(&mut self, value: f32) {
+        self.expression_ramp.set_target(value.clamp(0.0, 1.0));
+    }
+
+    /// Stage the mod-matrix gain on this operator's level (0 = silent).
+    pub fn set_level_mod(&mut self, gain: f32) {
+        self.level_mod = gain.max(0.0);
+    }
+
     pub fn trigger(&mut self, frequency: f32, velocity: f32, note: u8) {
         self.base_frequency = frequency;
         self.current_velocity = velocity;
         self.current_note = note;
+        self.cached_values.params_dirty = true;
+        self.update_cached_values();
+        self.level_ramp.reset(self.cached_values.level_amplitude);
+        self.feedback_ramp.reset(self.feedback);
+        self.detune_ramp.reset(self.detune);
         self.update_frequency();
 
         // Apply key scale rate to envelope
@@ -207,14 +273,14 @@ impl Operator {
         self.envelope
             .trigger_with_key_scale(velocity, key_scale_factor);
 
-        // OSC KEY SYNC: when ON the phase resets so every note starts identically;
-        // when OFF the oscillator free-runs to mimic the analog/DX1 behaviour.
+        // OSC KEY SYNC: when ON the phase resets so every note starts identically
+        // (at the phase offset); when OFF the oscillator free-runs to mimic the
+        // analog/DX1 behaviour.
         if self.oscillator_key_sync {
-            self.phase = 0.0;
+            self.phase = radians_to_phase(self.phase_offset.to_radians());
         }
         self.last_output = 0.0;
         self.prev_output = 0.0;
-        self.cached_values.params_dirty = true;
     }
 
     fn update_cached_values(&mut self) {
@@ -287,6 +353,22 @@ impl Operator {
         self.envelope.release();
     }
 
+    /// Switch to a new sample rate: the envelope rates and the phase
+    /// increment are recomputed, the oscillator phase is kept.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.envelope.set_sample_rate(sample_rate);
+        for ramp in [
+            &mut self.level_ramp,
+            &mut self.feedback_ramp,
+            &mut self.detune_ramp,
+            &mut self.expression_ramp,
+        ] {
+            ramp.set_sample_rate(sample_rate);
+        }
+        self.update_frequency();
+    }
+
     pub fn update_frequency(&mut self) {
         // FIXED mode bypasses the note-tracked base frequency and uses an absolute Hz value.
         // Detune still applies as a fine cents offset, matching DX7 behaviour.
@@ -295,12 +377,12 @@ impl Operator {
         } else {
             self.base_frequency * self.frequency_ratio
         };
-        // DX7 detune: parameter range -7..+7 is a *fine* offset of roughly ±7 cents
-        // at the extremes (Hexter / Synthmania reference). The previous formula
-        // `1 + detune/100` treated the value as a percentage, producing ±7%
-        // (≈±117 cents — almost a semitone and a half) and made detuned patches
-        // sound like multiple instruments out of tune.
-        let detuned_freq = actual_freq * 2.0_f32.powf(self.detune / 1200.0);
+        // DX7 detune: parameter range -7..+7 is a *fine* offset of just under ±7
+        // cents at the extremes (see `dx7_frequency::DETUNE_CENTS_PER_STEP`). The
+        // previous formula `1 + detune/100` treated the value as a percentage,
+        // producing ±7% (≈±117 cents — almost a semitone and a half) and made
+        // detuned patches sound like multiple instruments out of tune.
+        let detuned_freq = actual_freq * detune_factor(self.detune_ramp.current());
 
         // Validate frequency range
         if detuned_freq.is_finite()
@@ -308,14 +390,9 @@ impl Operator {
             && self.sample_rate > 0.0
             && self.sample_rate.is_finite()
         {
-            self.phase_increment = (2.0 * PI * detuned_freq) / self.sample_rate;
-
-            // Validate phase increment
-            if !self.phase_increment.is_finite() || self.phase_increment.abs() > 100.0 {
-                self.phase_increment = 0.0;
-            }
+            self.phase_increment = phase_increment(detuned_freq, self.sample_rate);
         } else {
-            self.phase_increment = 0.0;
+            self.phase_increment = 0;
         }
     }
 
@@ -325,11 +402,34 @@ impl Operator {
         self.update_frequency();
     }
 
+    /// Set the ratio directly. COARSE / FINE follow to the nearest setting;
+    /// a ratio they cannot reach (e.g. 1.414) switches on extended mode.
     pub fn set_frequency_ratio(&mut self, ratio: f32) {
         self.frequency_ratio = ratio;
+        (self.coarse, self.fine) = dx7_frequency::ratio_to_coarse_fine(ratio);
+        self.extended_ratio = !dx7_frequency::is_dx7_ratio(ratio);
+        self.update_frequency();
+    }
+
+    /// Set the ratio the DX7 way; leaves extended mode.
+    pub fn set_coarse_fine(&mut self, coarse: u8, fine: u8) {
+        self.coarse = coarse.min(dx7_frequency::MAX_COARSE);
+        self.fine = fine.min(dx7_frequency::MAX_FINE);
+        self.extended_ratio = false;
+        self.frequency_ratio = coarse_fine_to_ratio(self.coarse, self.fine);
         self.update_frequency();
     }
 
+    /// Switch between the free float ratio and COARSE / FINE. Leaving
+    /// extended mode snaps the ratio to the nearest COARSE / FINE setting.
+    pub fn set_extended_ratio(&mut self, extended: bool) {
+        if extended {
+            self.extended_ratio = true;
+        } else {
+            self.set_coarse_fine(self.coarse, self.fine);
+        }
+    }
+
     pub fn set_detune(&mut self, detune: f32) {
         self.detune = detune;
         self.update_frequency();
@@ -391,6 +491,23 @@ impl Operator {
         self.am_sensitivity = sens.min(3);
     }
 
+    /// Start phase in degrees for key-synced notes; takes effect on the
+    /// next note-on.
+    pub fn set_phase_offset(&mut self, degrees: f32) {
+        self.phase_offset = degrees.clamp(0.0, 360.0);
+    }
+
+    /// Where the oscillator is in its cycle, in degrees (0..360).
+    pub fn phase_degrees(&self) -> f32 {
+        phase_to_degrees(self.phase)
+    }
+
+    /// Per-operator PMS override; `None` follows the voice-wide setting.
+    /// Read by the Voice when it applies the LFO pitch swing.
+    pub fn set_pm_sensitivity(&mut self, sens: Option<u8>) {
+        self.pm_sensitivity = sens.map(|s| s.min(7));
+    }
+
     pub fn process(&mut self, modulation: f32) -> f32 {
         self.process_inner(modulation, true)
     }
@@ -416,15 +533,40 @@ impl Operator {
         }
     }
 
+    /// `feedback` as the oscillator currently applies it, partway along the
+    /// ramp after a live edit. Cross-feedback loops read this too.
+    pub fn smoothed_feedback(&self) -> f32 {
+        self.feedback_ramp.current()
+    }
+
+    /// Output produced by the most recent `process` call (0 when the operator
+    /// was disabled or silent). Read by the algorithm router to tap operators
+    /// tagged for the sub bus without re-running them.
+    pub fn current_output(&self) -> f32 {
+        self.current_output
+    }
+
     fn process_inner(&mut self, modulation: f32, apply_self_feedback: bool) -> f32 {
         if !self.enabled {
+            self.current_output = 0.0;
             return 0.0;
         }
 
         self.update_cached_values();
+        self.level_ramp
+            .set_target(self.cached_values.level_amplitude);
+        let level_amplitude = self.level_ramp.advance();
+        self.feedback_ramp.set_target(self.feedback);
+        let feedback = self.feedback_ramp.advance();
+        self.detune_ramp.set_target(self.detune);
+        if self.detune_ramp.is_ramping() {
+            self.detune_ramp.advance();
+            self.update_frequency();
+        }
 
         let env_value = self.envelope.process();
         if env_value == 0.0 {
+            self.current_output = 0.0;
             return 0.0;
         }
 
@@ -438,9 +580,9 @@ impl Operator {
         // The real DX7 uses (y[n-1] + y[n-2]) >> (9 - fb) which averages
         // the last two outputs to reduce aliasing in the feedback loop.
         // At feedback=7: ~π radians max phase deviation.
-        let feedback_mod = if apply_self_feedback && self.feedback > 0.0 {
+        let feedback_mod = if apply_self_feedback && feedback > 0.0 {
             let avg_output = (self.last_output + self.prev_output) * 0.5;
-            avg_output * self.feedback * PI / 7.0
+            avg_output * feedback * PI / 7.0
         } else {
             0.0
         };
@@ -448,7 +590,12 @@ impl Operator {
         // Scale incoming modulation to DX7-authentic depth
         // Feedback has its own independent scaling (not multiplied by MOD_INDEX_SCALE)
         let total_modulation = (modulation * MOD_INDEX_SCALE) + feedback_mod;
-        let sin_result = fast_sin(self.phase + total_modulation);
+        let phase = self.phase.wrapping_add(radians_to_phase(total_modulation));
+        let sin_result = if self.vintage.coarse_sine {
+            coarse_wave_fixed(self.waveform, phase)
+        } else {
+            fast_wave_fixed(self.waveform, phase)
+        };
 
         // DX7 AMS table (0..3): how much the LFO amplitude modulation affects this op.
         // 0 = none, 3 = maximum. Values come straight from the DX7 ROM via
@@ -459,35 +606,28 @@ impl Operator {
         // EG Bias attenuates the op output by a static, controller-driven amount.
         // Gated by AMS (per DX7 manual): AMS=0 unaffected, AMS=3 fully attenuated up to ~70%.
         let eg_bias_factor = 1.0 - (self.current_eg_bias * ams_scale * 0.7);
+        let expression_factor = if self.expression {
+            self.expression_ramp.advance()
+        } else {
+            1.0
+        };
 
         let output = sin_result
             * env_value
-            * self.cached_values.level_amplitude
+            * level_amplitude
             * self.cached_values.velocity_factor
             * self.cached_values.key_scale_level_factor
             * amp_mod_factor
-            * eg_bias_factor;
+            * eg_bias_factor
+            * expression_factor
+            * self.level_mod;
 
-        // Update phase with bounds checking
-        if self.phase_increment.is_finite() && self.phase_increment.abs() < 100.0 {
-            self.phase += self.phase_increment;
-
-            // Optimized phase wrapping using conditional subtraction
-            // Much faster than modulo operations
-            const TWO_PI: f32 = 2.0 * PI;
-            while self.phase >= TWO_PI {
-                self.phase -= TWO_PI;
-            }
-            while self.phase < 0.0 {
-                self.phase += TWO_PI;
-            }
-        } else {
-            self.phase = 0.0;
-            self.phase_increment = 0.0;
-        }
+        // The accumulator wraps by itself at the end of each cycle.
+        self.phase = self.phase.wrapping_add(self.phase_increment);
 
         self.prev_output = self.last_output;
         self.last_output = output;
+        self.current_output = output;
         output
     }
 
@@ -496,35 +636,42 @@ impl Operator {
     }
 
     pub fn reset(&mut self) {
-        self.phase = 0.0;
+        self.phase = 0;
         self.last_output = 0.0;
         self.prev_output = 0.0;
+        self.current_output = 0.0;
         self.envelope.reset();
     }
 
-    /// DX7 Key Rate Scaling — port of `ScaleRate` in MSFA `dx7note.cc`.
-    ///
-    /// Reference is fixed at MIDI 21 (A-1) and is **independent** of the
-    /// per-operator level breakpoint (a previous version of this function
-    /// reused `key_scale_breakpoint`, which is wrong: the DX7 KRS uses a
-    /// hardware-implicit reference, not the patch's level scaling hinge).
-    ///
-    /// Integer ROM math:
-    ///     x          = clamp(midinote / 3 - 7, 0, 31)
-    ///     qratedelta = (sensitivity * x) >> 3
-    /// `qratedelta` is in quarter-rate-step units; 4 quarter-steps double the
-    /// envelope speed, so the multiplicative factor is `2^(qratedelta / 4)`.
     fn calculate_key_scale_factor(&self, note: u8) -> f32 {
-        if self.key_scale_rate == 0.0 {
-            return 1.0;
-        }
-        let x = ((note as i32) / 3 - 7).clamp(0, 31);
-        let sens = self.key_scale_rate.round().clamp(0.0, 7.0) as i32;
-        let qratedelta = (sens * x) >> 3;
-        2.0_f32.powf(qratedelta as f32 / 4.0)
+        key_rate_scale_factor(self.key_scale_rate, note)
     }
 }
 
+/// DX7 Key Rate Scaling — port of `ScaleRate` in MSFA `dx7note.cc`: how
+/// much faster an operator's envelope runs on `note` with rate scaling
+/// `sensitivity` (0-7).
+///
+/// Reference is fixed at MIDI 21 (A-1) and is **independent** of the
+/// per-operator level breakpoint (a previous version of this function
+/// reused `key_scale_breakpoint`, which is wrong: the DX7 KRS uses a
+/// hardware-implicit reference, not the patch's level scaling hinge).
+///
+/// Integer ROM math:
+///     x          = clamp(midinote / 3 - 7, 0, 31)
+///     qratedelta = (sensitivity * x) >> 3
+/// `qratedelta` is in quarter-rate-step units; 4 quarter-steps double the
+/// envelope speed, so the multiplicative factor is `2^(qratedelta / 4)`.
+pub fn key_rate_scale_factor(sensitivity: f32, note: u8) -> f32 {
+    if sensitivity == 0.0 {
+        return 1.0;
+    }
+    let x = ((note as i32) / 3 - 7).clamp(0, 31);
+    let sens = sensitivity.round().clamp(0.0, 7.0) as i32;
+    let qratedelta = (sens * x) >> 3;
+    2.0_f32.powf(qratedelta as f32 / 4.0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -603,6 +750,31 @@ mod tests {
         assert!(peak > 0.0);
     }
 
+    #[test]
+    fn coarse_fine_and_extended_ratio_stay_in_step() {
+        let mut op = Operator::new(SR);
+        op.set_coarse_fine(0, 50);
+        assert_eq!(op.frequency_ratio, 0.75);
+        assert!(!op.extended_ratio);
+
+        op.set_frequency_ratio(3.0);
+        assert_eq!((op.coarse, op.fine), (3, 0));
+        assert!(!op.extended_ratio);
+
+        op.set_frequency_ratio(1.414);
+        assert!(op.extended_ratio);
+        assert_eq!((op.coarse, op.fine), (1, 41));
+        assert_eq!(op.frequency_ratio, 1.414);
+
+        op.set_extended_ratio(false);
+        assert!(!op.extended_ratio);
+        assert!((op.frequency_ratio - 1.41).abs() < 1e-5);
+
+        op.set_extended_ratio(true);
+        assert!(op.extended_ratio);
+        assert!((op.frequency_ratio - 1.41).abs() < 1e-5);
+    }
+
     #[test]
     fn set_detune_changes_internal_value() {
         let mut op = Operator::new(SR);
@@ -637,9 +809,9 @@ mod tests {
         let phase_before = op.phase;
         op.trigger(440.0, 1.0, 60);
         // With key sync ON the phase should reset to 0
-        assert_eq!(op.phase, 0.0);
+        assert_eq!(op.phase, 0);
         // Sanity: previous phase had advanced
-        assert!(phase_before > 0.0);
+        assert!(phase_before > 0);
     }
 
     #[test]
@@ -656,6 +828,25 @@ mod tests {
         assert_eq!(op.phase, phase_before);
     }
 
+    #[test]
+    fn key_synced_trigger_starts_at_the_phase_offset() {
+        let mut op = Operator::new(SR);
+        op.set_phase_offset(90.0);
+        op.trigger(440.0, 1.0, 60);
+        assert!((op.phase_degrees() - 90.0).abs() < 1e-3);
+        op.set_phase_offset(400.0);
+        op.trigger(440.0, 1.0, 60);
+        assert_eq!(op.phase, 0, "360° is a whole cycle");
+
+        // Free-running oscillators ignore the offset.
+        op.set_phase_offset(180.0);
+        op.oscillator_key_sync = false;
+        op.process(0.0);
+        let phase_before = op.phase;
+        op.trigger(440.0, 1.0, 60);
+        assert_eq!(op.phase, phase_before);
+    }
+
     #[test]
     fn process_produces_audio_after_trigger() {
         let mut op = Operator::new(SR);
@@ -694,7 +885,7 @@ mod tests {
         op.trigger(440.0, 1.0, 60);
         warmup(&mut op, 256);
         op.reset();
-        assert_eq!(op.phase, 0.0);
+        assert_eq!(op.phase, 0);
         assert!(!op.is_active());
     }
 
@@ -728,7 +919,7 @@ mod tests {
     /// Recover the operator's tuned frequency from its phase increment so we can
     /// assert on cents-level deviations regardless of internal representation.
     fn frequency_from_phase_increment(op: &Operator) -> f32 {
-        op.phase_increment * op.sample_rate / (2.0 * PI)
+        (op.phase_increment as f64 * op.sample_rate as f64 / 4_294_967_296.0) as f32
     }
 
     fn cents_offset(actual_hz: f32, reference_hz: f32) -> f32 {
@@ -747,6 +938,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn set_sample_rate_keeps_the_oscillator_frequency() {
+        let mut op = Operator::new(SR);
+        op.trigger(440.0, 1.0, 60);
+        op.set_sample_rate(96_000.0);
+        let f = frequency_from_phase_increment(&op);
+        assert!((f - 440.0).abs() < 0.01, "got {f} Hz after rate switch");
+    }
+
     #[test]
     fn detune_plus_seven_is_about_seven_cents_sharp() {
         // DX7 detune ±7 should be a *fine* offset (≈ ±7 cents), not a wild
@@ -789,6 +989,20 @@ mod tests {
         assert_eq!(op.base_frequency, 880.0);
     }
 
+    #[test]
+    fn phase_comes_back_to_zero_after_whole_cycles() {
+        // 11.025 kHz at 44.1 kHz is exactly four samples per cycle, so after
+        // a second the oscillator must be back where it started.
+        let mut op = Operator::new(SR);
+        op.fixed_frequency = true;
+        op.fixed_freq_hz = SR / 4.0;
+        op.trigger(440.0, 1.0, 60);
+        for _ in 0..SR as usize {
+            op.process(0.0);
+        }
+        assert_eq!(op.phase, 0);
+    }
+
     // -----------------------------------------------------------------------
     // Modulation, AMS and EG bias
     // -----------------------------------------------------------------------
@@ -869,6 +1083,30 @@ mod tests {
         warmup(&mut op, 256);
     }
 
+    #[test]
+    fn expression_only_scales_flagged_operators() {
+        let mut plain = Operator::new(SR);
+        let mut flagged = Operator::new(SR);
+        flagged.expression = true;
+        for op in [&mut plain, &mut flagged] {
+            op.set_expression(0.25);
+            op.trigger(440.0, 1.0, 60);
+        }
+        // Past the expression ramp.
+        warmup(&mut plain, 1024);
+        warmup(&mut flagged, 1024);
+        let plain_peak = warmup(&mut plain, 1024);
+        let flagged_peak = warmup(&mut flagged, 1024);
+        assert!(
+            (flagged_peak / plain_peak - 0.25).abs() < 0.02,
+            "{flagged_peak} vs {plain_peak}"
+        );
+
+        flagged.set_expression(0.0);
+        warmup(&mut flagged, 1024);
+        assert_eq!(warmup(&mut flagged, 256), 0.0);
+    }
+
     // -----------------------------------------------------------------------
     // Self-feedback
     // -----------------------------------------------------------------------
@@ -1042,6 +1280,7 @@ mod tests {
         let peak_loud = warmup(&mut op, 4096);
 
         op.set_output_level(20.0);
+        warmup(&mut op, 1024); // let the level ramp settle
         let peak_quiet = warmup(&mut op, 4096);
 
         assert!(
@@ -1050,6 +1289,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn live_level_feedback_and_detune_edits_ramp_instead_of_stepping() {
+        let mut op = Operator::new(SR);
+        op.trigger(440.0, 1.0, 60);
+        warmup(&mut op, 64);
+        let loud = op.level_ramp.current();
+        let start_freq = frequency_from_phase_increment(&op);
+
+        op.set_output_level(20.0);
+        op.set_feedback(7.0);
+        op.set_detune(7.0);
+        op.process(0.0);
+        let quiet = dx7_level_to_amplitude(20);
+        let level = op.level_ramp.current();
+        assert!(level < loud && level > quiet + (loud - quiet) * 0.9);
+        assert!(op.smoothed_feedback() > 0.0 && op.smoothed_feedback() < 0.1);
+        let freq = frequency_from_phase_increment(&op);
+        assert!(freq > start_freq && cents_offset(freq, start_freq) < 0.1);
+
+        warmup(&mut op, 1024);
+        assert_eq!(op.level_ramp.current(), quiet);
+        assert_eq!(op.smoothed_feedback(), 7.0);
+        let cents = cents_offset(frequency_from_phase_increment(&op), 440.0);
+        assert!(
+            (cents - 7.0).abs() < 0.5,
+            "ramp ends on the new detune: {cents}"
+        );
+    }
+
+    #[test]
+    fn note_on_starts_at_the_current_settings_without_a_ramp() {
+        let mut op = Operator::new(SR);
+        op.feedback = 5.0;
+        op.output_level = 40.0;
+        op.invalidate_cache();
+        op.trigger(440.0, 1.0, 60);
+        assert_eq!(op.smoothed_feedback(), 5.0);
+        assert_eq!(op.level_ramp.current(), dx7_level_to_amplitude(40));
+        assert!(!op.level_ramp.is_ramping());
+    }
+
     #[test]
     fn set_velocity_sensitivity_takes_effect_mid_note() {
         let mut op_a = Operator::new(SR);
@@ -1,37 +1,188 @@
 //! Small DSP helpers shared across the synth.
 //!
-//! Only the sine wavetable is precomputed (it is hit per-sample on the audio
-//! thread); the rest are short formulas inlined into free functions because
-//! caching them in a global table costs more lines than it saves cycles.
+//! Only the operator wavetables and the polyphony scaling factors are
+//! precomputed (both are hit per-sample on the audio thread); the rest are
+//! short formulas inlined into free functions because caching them in a
+//! global table costs more lines than it saves cycles.
 
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 use std::sync::LazyLock;
 
 const SINE_TABLE_SIZE: usize = 4096;
 const SINE_TABLE_MASK: usize = SINE_TABLE_SIZE - 1; // power-of-two for cheap wrap
 
-/// One full cycle of `sin(2π · i / 4096)`, computed once at first access.
-static SINE_TABLE: LazyLock<[f32; SINE_TABLE_SIZE]> = LazyLock::new(|| {
-    let mut t = [0.0_f32; SINE_TABLE_SIZE];
-    for (i, slot) in t.iter_mut().enumerate() {
-        *slot = ((i as f32 / SINE_TABLE_SIZE as f32) * 2.0 * PI).sin();
-    }
-    t
+/// Oscillator phase in fixed point: the whole `u32` range is one cycle, so
+/// the accumulator wraps on overflow like the DX7's phase generator and
+/// never drifts. The top 12 bits index the wavetable, the rest are the
+/// interpolation fraction.
+pub type Phase = u32;
+
+/// One cycle, in `Phase` units.
+const PHASE_CYCLE: f64 = 4_294_967_296.0;
+/// Shift that leaves the table index of a `Phase`.
+const TABLE_SHIFT: u32 = 32 - SINE_TABLE_SIZE.trailing_zeros();
+const FRACTION_MASK: Phase = (1 << TABLE_SHIFT) - 1;
+
+/// One cycle of each operator waveform, 4096 entries, in `OperatorWaveform`
+/// order; table 0 is the plain sine. The TX81Z builds its eight from pieces
+/// of the sine: squared (`sin·|sin|`), half-wave rectified, and the "double
+/// speed" ones that play the shape twice in the first half of the cycle and
+/// stay silent in the second.
+static WAVE_TABLES: LazyLock<[[f32; SINE_TABLE_SIZE]; 8]> = LazyLock::new(|| {
+    std::array::from_fn(|waveform| {
+        std::array::from_fn(|i| wave_sample(waveform, i as f32 / SINE_TABLE_SIZE as f32))
+    })
 });
 
+/// Waveform `waveform` (table index) at `x`, 0..1 of the cycle.
+fn wave_sample(waveform: usize, x: f32) -> f32 {
+    let s = (x * 2.0 * PI).sin();
+    // The double-speed shapes: the same pieces at twice the rate, first
+    // half of the cycle only.
+    let s2 = if x < 0.5 { (x * 4.0 * PI).sin() } else { 0.0 };
+    match waveform {
+        0 => s,
+        1 => s * s.abs(),
+        2 => s.max(0.0),
+        3 => (s * s.abs()).max(0.0),
+        4 => s2,
+        5 => s2 * s2.abs(),
+        6 => s2.abs(),
+        _ => s2 * s2,
+    }
+}
+
+/// Operator oscillator waveform, TX81Z W1 – W8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OperatorWaveform {
+    #[default]
+    Sine,
+    SquaredSine,
+    HalfSine,
+    HalfSquaredSine,
+    DoubleSine,
+    DoubleSquaredSine,
+    DoubleAbsSine,
+    DoubleAbsSquaredSine,
+}
+
+impl OperatorWaveform {
+    pub const ALL: [OperatorWaveform; 8] = [
+        OperatorWaveform::Sine,
+        OperatorWaveform::SquaredSine,
+        OperatorWaveform::HalfSine,
+        OperatorWaveform::HalfSquaredSine,
+        OperatorWaveform::DoubleSine,
+        OperatorWaveform::DoubleSquaredSine,
+        OperatorWaveform::DoubleAbsSine,
+        OperatorWaveform::DoubleAbsSquaredSine,
+    ];
+
+    /// `OperatorParam::Waveform` payload, 0 – 7 (TX81Z W1 – W8).
+    pub fn from_code(code: u8) -> Self {
+        Self::ALL[(code as usize).min(7)]
+    }
+
+    pub fn to_code(self) -> u8 {
+        self as u8
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OperatorWaveform::Sine => "W1 Sine",
+            OperatorWaveform::SquaredSine => "W2 Squared",
+            OperatorWaveform::HalfSine => "W3 Half",
+            OperatorWaveform::HalfSquaredSine => "W4 Half Sq",
+            OperatorWaveform::DoubleSine => "W5 Alt",
+            OperatorWaveform::DoubleSquaredSine => "W6 Alt Sq",
+            OperatorWaveform::DoubleAbsSine => "W7 Camel",
+            OperatorWaveform::DoubleAbsSquaredSine => "W8 Camel Sq",
+        }
+    }
+}
+
+/// Per-sample phase step of a `frequency` Hz oscillator. Worked out in f64,
+/// so it is exact to a 2^32th of a cycle (10 µHz at 44.1 kHz).
+pub fn phase_increment(frequency: f32, sample_rate: f32) -> Phase {
+    let cycles = (frequency as f64 / sample_rate as f64).rem_euclid(1.0);
+    (cycles * PHASE_CYCLE).round() as u64 as Phase
+}
+
+/// `radians` of phase (or phase modulation) as a `Phase`. Accepts any real
+/// value, negative and multi-cycle included; the cast wraps it.
+pub fn radians_to_phase(radians: f32) -> Phase {
+    const PHASE_PER_RADIAN: f32 = (PHASE_CYCLE / std::f64::consts::TAU) as f32;
+    (radians * PHASE_PER_RADIAN) as i64 as Phase
+}
+
+/// Position of `phase` in its cycle, in degrees (0..360).
+pub fn phase_to_degrees(phase: Phase) -> f32 {
+    (phase as f64 / PHASE_CYCLE * 360.0) as f32
+}
+
+/// Linear-interpolated read of a one-cycle table.
+fn interpolated(table: &[f32; SINE_TABLE_SIZE], phase: Phase) -> f32 {
+    const FRACTION_SCALE: f32 = 1.0 / (FRACTION_MASK as f32 + 1.0);
+    let i0 = (phase >> TABLE_SHIFT) as usize;
+    let frac = (phase & FRACTION_MASK) as f32 * FRACTION_SCALE;
+    let y0 = table[i0];
+    let y1 = table[(i0 + 1) & SINE_TABLE_MASK];
+    y0 + (y1 - y0) * frac
+}
+
+/// Operator waveform at a fixed-point phase, interpolated.
+pub fn fast_wave_fixed(waveform: OperatorWaveform, phase: Phase) -> f32 {
+    interpolated(&WAVE_TABLES[waveform as usize], phase)
+}
+
+/// Operator waveform at a fixed-point phase, read like `coarse_sin`.
+pub fn coarse_wave_fixed(waveform: OperatorWaveform, phase: Phase) -> f32 {
+    const AMPLITUDE_STEPS: f32 = 2047.0;
+    let index = (phase >> TABLE_SHIFT) as usize;
+    (WAVE_TABLES[waveform as usize][index] * AMPLITUDE_STEPS).round() / AMPLITUDE_STEPS
+}
+
+/// `fast_sin` for any operator waveform.
+pub fn fast_wave(waveform: OperatorWaveform, phase: f32) -> f32 {
+    fast_wave_fixed(waveform, radians_to_phase(phase))
+}
+
+/// `coarse_sin` for any operator waveform.
+pub fn coarse_wave(waveform: OperatorWaveform, phase: f32) -> f32 {
+    coarse_wave_fixed(waveform, radians_to_phase(phase))
+}
+
 /// Sine lookup with linear interpolation. Accepts any real phase (negative,
 /// multi-cycle); wraps automatically. With 4096 entries the worst-case
 /// interpolation error is below 1e-6, well under the noise floor of the rest
 /// of the audio chain — Catmull-Rom interpolation buys nothing audible at
 /// this density and costs five extra multiplies per sample.
 pub fn fast_sin(phase: f32) -> f32 {
-    const INV_TWO_PI: f32 = 1.0 / (2.0 * PI);
-    let index_f = (phase * INV_TWO_PI).rem_euclid(1.0) * SINE_TABLE_SIZE as f32;
-    let i0 = index_f as usize & SINE_TABLE_MASK;
-    let frac = index_f - i0 as f32;
-    let y0 = SINE_TABLE[i0];
-    let y1 = SINE_TABLE[(i0 + 1) & SINE_TABLE_MASK];
-    y0 + (y1 - y0) * frac
+    fast_wave(OperatorWaveform::Sine, phase)
+}
+
+/// Sine read the way the DX7 reads its ROM: the same 4096-entry table
+/// (12-bit phase) without interpolation, output rounded to 12 bits. Used by
+/// vintage mode; the steps add the faint grit of the original.
+pub fn coarse_sin(phase: f32) -> f32 {
+    coarse_wave(OperatorWaveform::Sine, phase)
+}
+
+/// Below this (about -300 dB) a decaying state is treated as silence.
+const DENORMAL_THRESHOLD: f32 = 1e-15;
+
+/// `x`, or exactly 0 once it has decayed below -300 dB. Feedback loops
+/// (reverb combs, delay repeats, filter states) otherwise sink into
+/// subnormal floats after the input stops, and arithmetic on those can cost
+/// a hundred times more on x86, turning a fading tail into a CPU spike.
+#[inline]
+pub fn flush_denormal(x: f32) -> f32 {
+    if x.abs() < DENORMAL_THRESHOLD {
+        0.0
+    } else {
+        x
+    }
 }
 
 /// MIDI note number → Hz (equal temperament, A4 = 440 Hz).
@@ -39,8 +190,19 @@ pub fn midi_to_hz(note: u8) -> f32 {
     440.0 * 2.0_f32.powf((note as f32 - 69.0) / 12.0)
 }
 
-/// Polyphony attenuation: 1/√N (RMS-preserving). Returns 1.0 for n ≤ 1.
-pub fn voice_scale(n: usize) -> f32 {
+/// Voice counts covered by `VOICE_SCALE_TABLE`: 0..=64, the engine's
+/// maximum polyphony.
+const VOICE_SCALE_TABLE_SIZE: usize = 65;
+
+static VOICE_SCALE_TABLE: LazyLock<[f32; VOICE_SCALE_TABLE_SIZE]> = LazyLock::new(|| {
+    let mut t = [0.0_f32; VOICE_SCALE_TABLE_SIZE];
+    for (n, slot) in t.iter_mut().enumerate() {
+        *slot = voice_scale_exact(n);
+    }
+    t
+});
+
+fn voice_scale_exact(n: usize) -> f32 {
     if n <= 1 {
         1.0
     } else {
@@ -48,6 +210,15 @@ pub fn voice_scale(n: usize) -> f32 {
     }
 }
 
+/// Polyphony attenuation: 1/√N (RMS-preserving). Returns 1.0 for n ≤ 1.
+/// Table lookup up to 64 voices, computed beyond that.
+pub fn voice_scale(n: usize) -> f32 {
+    VOICE_SCALE_TABLE
+        .get(n)
+        .copied()
+        .unwrap_or_else(|| voice_scale_exact(n))
+}
+
 /// DX7 operator level (0..99) → linear amplitude.
 ///
 /// Each level step is ~0.75 dB and level 99 = 0 dB (unity), per the DX7
@@ -120,6 +291,15 @@ mod tests {
         assert!((neg + 1.0).abs() < 1e-3);
     }
 
+    #[test]
+    fn coarse_sin_holds_each_table_step() {
+        let step = 2.0 * PI / SINE_TABLE_SIZE as f32;
+        // Anywhere inside one table slot reads the same value.
+        assert_eq!(coarse_sin(100.2 * step), coarse_sin(100.8 * step));
+        assert!((coarse_sin(PI / 2.0) - 1.0).abs() < 1e-3);
+        assert!(coarse_sin(100.5 * step) != fast_sin(100.5 * step));
+    }
+
     #[test]
     fn fast_sin_periodic_above_two_pi() {
         let a = fast_sin(PI / 4.0);
@@ -127,6 +307,110 @@ mod tests {
         assert!((a - b).abs() < 1e-3);
     }
 
+    #[test]
+    fn flush_denormal_only_touches_tiny_values() {
+        assert_eq!(flush_denormal(f32::MIN_POSITIVE / 4.0), 0.0);
+        assert_eq!(flush_denormal(-1e-20), 0.0);
+        assert_eq!(flush_denormal(1e-6), 1e-6);
+        assert_eq!(flush_denormal(-0.5), -0.5);
+    }
+
+    // -----------------------------------------------------------------------
+    // Fixed-point phase
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn phase_increment_keeps_frequency_over_ten_seconds() {
+        // Count whole cycles the accumulator runs through in ten seconds: a
+        // float phase wrapped against 2π loses a good fraction of a cycle
+        // at high frequencies, the fixed-point step stays within 1/1000.
+        let sample_rate = 44_100.0;
+        for frequency in [27.5, 440.0, 4186.01, 12_543.85, 19_000.0] {
+            let step = phase_increment(frequency, sample_rate) as u64;
+            let samples = 10 * sample_rate as u64;
+            let cycles = (step * samples) as f64 / PHASE_CYCLE;
+            let expected = frequency as f64 * 10.0;
+            assert!(
+                (cycles - expected).abs() < 1e-3,
+                "{frequency} Hz: {cycles} cycles, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn radians_to_phase_wraps_negative_and_multi_cycle_values() {
+        let quarter = 1u32 << 30;
+        let close = |a: Phase, b: Phase| a.wrapping_sub(b).min(b.wrapping_sub(a)) < 1 << 8;
+        assert!(close(radians_to_phase(PI / 2.0), quarter));
+        assert!(close(radians_to_phase(-PI / 2.0), 3 * quarter));
+        assert!(close(radians_to_phase(5.0 * PI / 2.0), quarter));
+        assert_eq!(radians_to_phase(0.0), 0);
+    }
+
+    #[test]
+    fn fixed_phase_reads_the_sine_table() {
+        for i in 0..1024u32 {
+            let phase = i.wrapping_mul(4_194_301);
+            let exact = (phase as f64 / PHASE_CYCLE * std::f64::consts::TAU).sin() as f32;
+            let approx = fast_wave_fixed(OperatorWaveform::Sine, phase);
+            assert!(
+                (approx - exact).abs() < 1e-5,
+                "phase {phase}: {approx} vs {exact}"
+            );
+        }
+        // The coarse read holds each 12-bit step.
+        let slot = 100 << TABLE_SHIFT;
+        assert_eq!(
+            coarse_wave_fixed(OperatorWaveform::Sine, slot),
+            coarse_wave_fixed(OperatorWaveform::Sine, slot + FRACTION_MASK)
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Operator waveforms
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn sine_waveform_is_fast_sin() {
+        for i in 0..64 {
+            let phase = i as f32 * 0.37;
+            assert_eq!(fast_wave(OperatorWaveform::Sine, phase), fast_sin(phase));
+            assert_eq!(
+                coarse_wave(OperatorWaveform::Sine, phase),
+                coarse_sin(phase)
+            );
+        }
+    }
+
+    #[test]
+    fn half_and_double_waveforms_are_silent_in_the_second_half() {
+        let three_quarters = 1.5 * PI;
+        for w in [
+            OperatorWaveform::HalfSine,
+            OperatorWaveform::HalfSquaredSine,
+            OperatorWaveform::DoubleSine,
+            OperatorWaveform::DoubleAbsSquaredSine,
+        ] {
+            assert!(fast_wave(w, three_quarters).abs() < 1e-6, "{}", w.name());
+        }
+        // The double-speed shapes peak at an eighth of the cycle.
+        assert!((fast_wave(OperatorWaveform::DoubleSine, PI / 4.0) - 1.0).abs() < 1e-3);
+        assert!((fast_wave(OperatorWaveform::DoubleAbsSine, 3.0 * PI / 4.0) - 1.0).abs() < 1e-3);
+        // Squared sine keeps the sign of the sine.
+        assert!(fast_wave(OperatorWaveform::SquaredSine, three_quarters) < -0.99);
+    }
+
+    #[test]
+    fn waveform_codes_round_trip_and_clamp() {
+        for w in OperatorWaveform::ALL {
+            assert_eq!(OperatorWaveform::from_code(w.to_code()), w);
+        }
+        assert_eq!(
+            OperatorWaveform::from_code(200),
+            OperatorWaveform::DoubleAbsSquaredSine
+        );
+    }
+
     // -----------------------------------------------------------------------
     // midi_to_hz
     // -----------------------------------------------------------------------
@@ -157,6 +441,14 @@ mod tests {
         assert!(voice_scale(2) > voice_scale(4));
     }
 
+    #[test]
+    fn voice_scale_table_covers_64_voices() {
+        assert!((voice_scale(16) - 0.25).abs() < 1e-6);
+        assert!((voice_scale(64) - 0.125).abs() < 1e-6);
+        assert!(voice_scale(32) > voice_scale(64));
+        assert!(voice_scale(65) < voice_scale(64));
+    }
+
     // -----------------------------------------------------------------------
     // DX7 level
     // -----------------------------------------------------------------------
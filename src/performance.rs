@@ -0,0 +1,167 @@
+//! DX7II-style performance: two voices played together, either layered on
+//! every key (dual) or sharing the keyboard at a split point.
+//!
+//! Part A is the engine's own edit buffer; part B is a second engine the
+//! main one mixes in. Each part has its own volume and transpose on top of
+//! its voice's settings.
+
+/// How the keyboard is shared between the two parts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PerformanceMode {
+    /// Part A only.
+    #[default]
+    Single,
+    /// Both parts sound on every key.
+    Dual,
+    /// Part A below the split point, part B from it up.
+    Split,
+}
+
+impl PerformanceMode {
+    pub const ALL: [PerformanceMode; 3] = [
+        PerformanceMode::Single,
+        PerformanceMode::Dual,
+        PerformanceMode::Split,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PerformanceMode::Single => "SINGLE",
+            PerformanceMode::Dual => "DUAL",
+            PerformanceMode::Split => "SPLIT",
+        }
+    }
+}
+
+/// Level and key shift of one part.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PerformancePart {
+    /// 0..1, applied under the master volume.
+    pub volume: f32,
+    /// Semitones, -24..=24, added to the voice's own transpose.
+    pub transpose: i8,
+}
+
+impl Default for PerformancePart {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            transpose: 0,
+        }
+    }
+}
+
+/// Lowest and highest part transpose, in semitones.
+pub const PART_TRANSPOSE_RANGE: std::ops::RangeInclusive<i8> = -24..=24;
+
+/// Split point until one is chosen: C3 (middle C).
+pub const DEFAULT_SPLIT_POINT: u8 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Performance {
+    pub mode: PerformanceMode,
+    /// First key of part B in split mode.
+    pub split_point: u8,
+    /// Part A, then part B.
+    pub parts: [PerformancePart; 2],
+}
+
+impl Default for Performance {
+    fn default() -> Self {
+        Self {
+            mode: PerformanceMode::Single,
+            split_point: DEFAULT_SPLIT_POINT,
+            parts: [PerformancePart::default(); 2],
+        }
+    }
+}
+
+impl Performance {
+    /// Values clamped to their ranges.
+    pub fn clamped(mut self) -> Self {
+        self.split_point = self.split_point.min(127);
+        for part in &mut self.parts {
+            part.volume = if part.volume.is_finite() {
+                part.volume.clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+            part.transpose = part
+                .transpose
+                .clamp(*PART_TRANSPOSE_RANGE.start(), *PART_TRANSPOSE_RANGE.end());
+        }
+        self
+    }
+
+    /// Whether part B is in use.
+    pub fn uses_part_b(&self) -> bool {
+        self.mode != PerformanceMode::Single
+    }
+
+    /// Which parts a note-on for `note` plays, as (part A, part B).
+    pub fn route(&self, note: u8) -> (bool, bool) {
+        match self.mode {
+            PerformanceMode::Single => (true, false),
+            PerformanceMode::Dual => (true, true),
+            PerformanceMode::Split => (note < self.split_point, note >= self.split_point),
+        }
+    }
+
+    /// Volume and transpose `part` (0 = A, 1 = B) plays with. Part A is
+    /// left alone in single mode.
+    pub fn part(&self, part: usize) -> PerformancePart {
+        if self.uses_part_b() {
+            self.parts[part]
+        } else {
+            PerformancePart::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_are_routed_by_mode() {
+        let mut performance = Performance::default();
+        assert_eq!(performance.route(30), (true, false));
+        performance.mode = PerformanceMode::Dual;
+        assert_eq!(performance.route(30), (true, true));
+        performance.mode = PerformanceMode::Split;
+        performance.split_point = 48;
+        assert_eq!(performance.route(47), (true, false));
+        assert_eq!(performance.route(48), (false, true));
+    }
+
+    #[test]
+    fn part_settings_only_apply_with_two_parts() {
+        let mut performance = Performance::default();
+        performance.parts[0] = PerformancePart {
+            volume: 0.5,
+            transpose: -12,
+        };
+        assert_eq!(performance.part(0), PerformancePart::default());
+        performance.mode = PerformanceMode::Dual;
+        assert_eq!(performance.part(0).transpose, -12);
+    }
+
+    #[test]
+    fn clamped_keeps_values_in_range() {
+        let performance = Performance {
+            split_point: 200,
+            parts: [
+                PerformancePart::default(),
+                PerformancePart {
+                    volume: f32::NAN,
+                    transpose: 40,
+                },
+            ],
+            ..Performance::default()
+        }
+        .clamped();
+        assert_eq!(performance.split_point, 127);
+        assert_eq!(performance.parts[1].volume, 1.0);
+        assert_eq!(performance.parts[1].transpose, 24);
+    }
+}
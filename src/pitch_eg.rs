@@ -108,6 +108,13 @@ impl PitchEg {
         }
     }
 
+    /// Switch to a new sample rate, rescaling the increment of the stage in
+    /// flight so it keeps its duration.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.inc_per_sample *= self.sample_rate / sample_rate;
+        self.sample_rate = sample_rate;
+    }
+
     pub fn trigger(&mut self) {
         if !self.enabled {
             self.current_semitones = 0.0;
@@ -144,18 +151,25 @@ impl PitchEg {
     /// Process one sample. Returns the pitch offset in **semitones** (not Hz).
     /// 0.0 means no offset.
     pub fn process(&mut self) -> f32 {
+        self.process_samples(1)
+    }
+
+    /// Control-rate variant of `process`: advances the ramp by `samples`
+    /// at once. A stage boundary is clamped to, not carried over.
+    pub fn process_samples(&mut self, samples: usize) -> f32 {
         if !self.enabled || self.stage == PitchEgStage::Idle {
             return 0.0;
         }
 
+        let step = self.inc_per_sample * samples as f32;
         if self.rising {
-            self.current_semitones += self.inc_per_sample;
+            self.current_semitones += step;
             if self.current_semitones >= self.target_semitones {
                 self.current_semitones = self.target_semitones;
                 self.advance_stage();
             }
         } else {
-            self.current_semitones -= self.inc_per_sample;
+            self.current_semitones -= step;
             if self.current_semitones <= self.target_semitones {
                 self.current_semitones = self.target_semitones;
                 self.advance_stage();
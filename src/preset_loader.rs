@@ -1,5 +1,7 @@
+use crate::dx7_frequency;
 use crate::lfo::LFOWaveform;
 use crate::operator::KeyScaleCurve;
+use crate::optimization::OperatorWaveform;
 use crate::presets::{Dx7Preset, PresetLfo, PresetOperator, PresetPitchEg};
 use serde::{Deserialize, Deserializer};
 use std::path::Path;
@@ -92,6 +94,16 @@ struct JsonPatch {
     transpose: serde_json::Value,
     #[serde(default)]
     oscillator_key_sync: String,
+    /// Optional function parameters. The DX7 keeps these global, so most banks
+    /// omit them; patches that depend on mono or glide can carry them here.
+    #[serde(default)]
+    pitch_bend_range: Option<f32>,
+    #[serde(default)]
+    portamento_enable: Option<bool>,
+    #[serde(default)]
+    portamento_time: Option<f32>,
+    #[serde(default)]
+    mono_mode: Option<bool>,
 }
 
 /// Accept either a JSON number or a string-encoded number (some banks use "0" for amDepth).
@@ -205,12 +217,11 @@ fn convert_operator(json_op: &JsonOperator, top_feedback: f32, is_op6: bool) ->
     };
 
     let fixed_frequency = json_op.oscillator_mode.eq_ignore_ascii_case("fixed");
-    // DX7 fixed-mode frequency: f = 10^coarse * (1 + fine/100). Coarse 0..3 maps to
-    // 1Hz/10Hz/100Hz/1000Hz, Fine adds up to ~99% on top.
+    // DX7 fixed-mode frequency, the same log scale the SysEx loader uses.
     let fixed_freq_hz = if fixed_frequency {
-        let coarse = json_op.fixed_frequency_coarse.clamp(0.0, 3.0);
-        let fine = json_op.fixed_frequency_fine.clamp(0.0, 99.0);
-        10f32.powf(coarse) * (1.0 + fine / 100.0)
+        let coarse = json_op.fixed_frequency_coarse.clamp(0.0, 3.0) as u8;
+        let fine = json_op.fixed_frequency_fine.clamp(0.0, 99.0) as u8;
+        dx7_frequency::fixed_coarse_fine_to_hz(coarse, fine)
     } else {
         440.0
     };
@@ -248,6 +259,12 @@ fn convert_operator(json_op: &JsonOperator, top_feedback: f32, is_op6: bool) ->
         key_scale_right_depth: right_depth,
         am_sensitivity: json_op.am_sensitivity.min(3),
         oscillator_key_sync: true, // applied at patch-level below
+        sub_bus: false,
+        expression: false,
+        waveform: OperatorWaveform::Sine,
+        phase_offset: 0.0,
+        pm_sensitivity: None,
+        lfo2: false,
         fixed_frequency,
         fixed_freq_hz,
         envelope: (
@@ -265,12 +282,22 @@ fn convert_operator(json_op: &JsonOperator, top_feedback: f32, is_op6: bool) ->
 
 fn load_json_file(path: &Path, collection: &str) -> Option<Dx7Preset> {
     let content = std::fs::read_to_string(path).ok()?;
-    let patch: JsonPatch = serde_json::from_str(&content)
+    serde_json::from_str(&content)
+        .map_err(|e| e.to_string())
+        .and_then(|value| patch_from_value(value, collection))
         .map_err(|e| log::warn!("Failed to parse {:?}: {}", path, e))
-        .ok()?;
+        .ok()
+}
 
+/// Convert one voice in the dx7-patches JSON layout. User preset files
+/// without a format version are read through this too.
+pub(crate) fn patch_from_value(
+    value: serde_json::Value,
+    collection: &str,
+) -> Result<Dx7Preset, String> {
+    let patch: JsonPatch = serde_json::from_value(value).map_err(|e| e.to_string())?;
     if patch.operators.len() != 6 || patch.name.trim().is_empty() {
-        return None;
+        return Err("a voice needs a name and six operators".to_string());
     }
 
     let osc_key_sync = !patch.oscillator_key_sync.eq_ignore_ascii_case("off");
@@ -308,16 +335,16 @@ fn load_json_file(path: &Path, collection: &str) -> Option<Dx7Preset> {
         level4: p.level4,
     });
 
-    Some(Dx7Preset {
+    Ok(Dx7Preset {
         name: patch.name.trim().to_string(),
         collection: collection.to_string(),
         algorithm: patch.algorithm,
         operators,
         master_tune: None,
-        pitch_bend_range: None,
-        portamento_enable: None,
-        portamento_time: None,
-        mono_mode: None,
+        pitch_bend_range: patch.pitch_bend_range,
+        portamento_enable: patch.portamento_enable,
+        portamento_time: patch.portamento_time,
+        mono_mode: patch.mono_mode,
         transpose_semitones: parse_transpose(&patch.transpose),
         pitch_mod_sensitivity: pms,
         pitch_eg,
@@ -499,8 +526,8 @@ mod tests {
         };
         let op = convert_operator(&json_op, 0.0, false);
         assert!(op.fixed_frequency);
-        // 10^2 * (1 + 50/100) = 150
-        assert!((op.fixed_freq_hz - 150.0).abs() < 0.1);
+        // 10^(2 + 50/100) ≈ 316 Hz
+        assert!((op.fixed_freq_hz - 316.23).abs() < 0.1);
     }
 
     #[test]
@@ -648,4 +675,29 @@ mod tests {
         assert_eq!(op6.feedback, 7.0);
         assert_eq!(op6.key_scale_rate, 7.0);
     }
+
+    #[test]
+    fn brass_1_loads_mono_and_portamento() {
+        use crate::command_queue::create_command_queue;
+        use crate::fm_synth::SynthEngine;
+        use crate::state_snapshot::{create_snapshot_channel, VoiceMode};
+
+        let path = std::path::Path::new("patches/edu/brass-1.json");
+        if !path.exists() {
+            eprintln!("Skipping: {:?} not present", path);
+            return;
+        }
+        let preset = load_json_file(path, "edu").expect("brass-1.json must parse");
+        assert_eq!(preset.mono_mode, Some(true));
+        assert_eq!(preset.portamento_enable, Some(true));
+
+        let (_tx, rx) = create_command_queue();
+        let (snap_tx, _snap_rx) = create_snapshot_channel();
+        let mut engine = SynthEngine::new(44_100.0, rx, snap_tx);
+        preset.apply_to_synth(&mut engine);
+        assert_eq!(engine.get_voice_mode(), VoiceMode::Mono);
+        assert!(engine.get_portamento_enable());
+        assert_eq!(engine.get_portamento_time(), 30.0);
+        assert_eq!(engine.get_pitch_bend_range(), 2.0);
+    }
 }
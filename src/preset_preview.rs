@@ -0,0 +1,164 @@
+//! Preset audition while browsing.
+//!
+//! A second, lighter `SynthEngine` (no part B, effects bypassed) is mixed
+//! into the audio callback next to the main one and skipped while it is
+//! idle; the audio side owns it and this module only holds its controller. Auditioning a preset loads it into the preview engine and
+//! plays a short phrase there through a `SequencerPlayer`, so the edit
+//! buffer of the main engine is never touched.
+
+use crate::fm_synth::{create_preview_synth, SynthController, SynthEngine};
+use crate::presets::Dx7Preset;
+use crate::render::NoteEvent;
+use crate::sequencer::{Sequencer, SequencerPlayer};
+use std::sync::{Arc, Mutex};
+
+/// Notes of the phrase played on audition: a rising C major arpeggio.
+const PHRASE: [u8; 4] = [60, 64, 67, 72];
+/// Seconds from one phrase note to the next, and how long each is held.
+const NOTE_STEP: f32 = 0.2;
+const NOTE_LENGTH: f32 = 0.18;
+const VELOCITY: u8 = 90;
+/// Voices of the preview engine; enough for the phrase or a chordal score.
+const POLYPHONY: usize = 8;
+
+/// The default audition phrase as a score.
+pub fn default_phrase() -> Vec<NoteEvent> {
+    PHRASE
+        .iter()
+        .enumerate()
+        .map(|(i, &note)| NoteEvent {
+            start: i as f32 * NOTE_STEP,
+            duration: NOTE_LENGTH,
+            note,
+            velocity: VELOCITY,
+        })
+        .collect()
+}
+
+pub struct PresetPreview {
+    controller: Arc<Mutex<SynthController>>,
+    /// Score played on every audition.
+    phrase: Vec<NoteEvent>,
+    /// Plays the current audition. Replaced by the next one, so browsing
+    /// quickly doesn't stack phrases.
+    player: Option<SequencerPlayer>,
+}
+
+impl PresetPreview {
+    /// The preview and the engine it drives, for the audio callback to mix in.
+    pub fn new(sample_rate: f32) -> (Self, SynthEngine) {
+        let (mut engine, controller) = create_preview_synth(sample_rate);
+        engine.set_polyphony(POLYPHONY);
+        let preview = Self {
+            controller: Arc::new(Mutex::new(controller)),
+            phrase: default_phrase(),
+            player: None,
+        };
+        (preview, engine)
+    }
+
+    /// Audition with `phrase` instead of the arpeggio; an empty one puts
+    /// the arpeggio back.
+    pub fn set_phrase(&mut self, phrase: Vec<NoteEvent>) {
+        self.phrase = if phrase.is_empty() {
+            default_phrase()
+        } else {
+            phrase
+        };
+    }
+
+    /// Load `preset` into the preview engine and play the audition phrase.
+    /// Cancels any phrase still playing.
+    pub fn audition(&mut self, preset: &Dx7Preset) {
+        // Dropping the old player joins its thread and releases its notes.
+        self.player = None;
+        if let Ok(mut ctrl) = self.controller.lock() {
+            // Queued in this order, the panic lands before the preset swaps
+            // voice parameters.
+            ctrl.panic();
+            ctrl.apply_preset(preset.clone());
+        }
+        let player = SequencerPlayer::spawn(Sequencer::new(&self.phrase), self.controller.clone());
+        player.with(|seq, _| seq.start());
+        self.player = Some(player);
+    }
+
+    /// Whether an audition phrase is still playing.
+    pub fn is_playing(&self) -> bool {
+        self.player
+            .as_ref()
+            .and_then(|player| player.status())
+            .is_some_and(|status| status.playing)
+    }
+
+    /// Silence the preview engine and cancel the running phrase.
+    pub fn stop(&mut self) {
+        self.player = None;
+        if let Ok(mut ctrl) = self.controller.lock() {
+            ctrl.panic();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fm_synth::create_synth;
+
+    fn make_preset(name: &str, alg: u8) -> Dx7Preset {
+        Dx7Preset {
+            name: name.to_string(),
+            collection: "test".to_string(),
+            algorithm: alg,
+            ..Dx7Preset::default()
+        }
+    }
+
+    #[test]
+    fn audition_loads_preset_into_preview_engine_only() {
+        let (mut preview, mut eng) = PresetPreview::new(44_100.0);
+        let (main_engine, _ctrl) = create_synth(44_100.0);
+        preview.audition(&make_preset("PREVIEW", 7));
+
+        eng.process_commands();
+        assert_eq!(eng.preset_name, "PREVIEW");
+        assert_eq!(eng.get_algorithm(), 7);
+        assert_ne!(main_engine.preset_name, "PREVIEW");
+        preview.stop();
+    }
+
+    #[test]
+    fn audition_plays_notes_on_preview_engine() {
+        let (mut preview, mut eng) = PresetPreview::new(44_100.0);
+        preview.audition(&make_preset("PREVIEW", 1));
+        std::thread::sleep(std::time::Duration::from_millis(50));
+
+        eng.process_commands();
+        assert!(eng.voices().iter().any(|v| v.active));
+        assert!(preview.is_playing());
+        preview.stop();
+        assert!(!preview.is_playing());
+    }
+
+    #[test]
+    fn new_audition_cancels_previous_phrase() {
+        let (mut preview, mut eng) = PresetPreview::new(44_100.0);
+        preview.set_phrase(vec![NoteEvent {
+            start: 0.0,
+            duration: 10.0,
+            note: 40,
+            velocity: 100,
+        }]);
+        preview.audition(&make_preset("A", 1));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        preview.set_phrase(Vec::new());
+        preview.audition(&make_preset("B", 2));
+        eng.process_commands();
+        // The long note of the first phrase was let go and the engine
+        // panicked before B, so nothing of A is still held.
+        assert_eq!(eng.preset_name, "B");
+        assert!(!eng.voices().iter().any(|v| v.active && v.note == 40));
+        assert!(preview.is_playing());
+        preview.stop();
+    }
+}
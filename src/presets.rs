@@ -1,10 +1,17 @@
+use crate::command_queue::SynthCommand;
 use crate::fm_synth::SynthEngine;
 use crate::lfo::LFOWaveform;
 use crate::operator::KeyScaleCurve;
+use crate::optimization::OperatorWaveform;
 use crate::state_snapshot::SynthSnapshot;
+use serde::{Deserialize, Serialize};
 
 /// Per-operator parameters captured from a DX7 voice.
-#[derive(Clone, Debug)]
+///
+/// Serialized in user preset files (`user_presets.rs`); a field missing
+/// from a file takes its default, so adding one keeps older files loading.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PresetOperator {
     pub frequency_ratio: f32,
     pub output_level: f32,
@@ -18,7 +25,19 @@ pub struct PresetOperator {
     pub key_scale_left_depth: f32,
     pub key_scale_right_depth: f32,
     pub am_sensitivity: u8,
+    /// Per-operator PMS; None follows the voice PMS (not part of the DX7 format).
+    pub pm_sensitivity: Option<u8>,
+    /// Follow the second LFO (not part of the DX7 format).
+    pub lfo2: bool,
     pub oscillator_key_sync: bool,
+    /// Route this operator to the sub output bus (not part of the DX7 format).
+    pub sub_bus: bool,
+    /// Output level follows the expression input (not part of the DX7 format).
+    pub expression: bool,
+    /// Oscillator waveform (not part of the DX7 format).
+    pub waveform: OperatorWaveform,
+    /// Start phase of key-synced notes, 0–360° (not part of the DX7 format).
+    pub phase_offset: f32,
     pub fixed_frequency: bool,
     pub fixed_freq_hz: f32,
     /// Envelope: (r1, r2, r3, r4, l1, l2, l3, l4).
@@ -40,7 +59,13 @@ impl Default for PresetOperator {
             key_scale_left_depth: 0.0,
             key_scale_right_depth: 0.0,
             am_sensitivity: 0,
+            pm_sensitivity: None,
+            lfo2: false,
             oscillator_key_sync: true,
+            sub_bus: false,
+            expression: false,
+            waveform: OperatorWaveform::Sine,
+            phase_offset: 0.0,
             fixed_frequency: false,
             fixed_freq_hz: 440.0,
             envelope: (99.0, 50.0, 50.0, 50.0, 99.0, 75.0, 50.0, 0.0),
@@ -49,7 +74,8 @@ impl Default for PresetOperator {
 }
 
 /// Pitch envelope settings for a preset.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PresetPitchEg {
     pub rate1: f32,
     pub rate2: f32,
@@ -88,7 +114,8 @@ impl Default for PresetPitchEg {
 }
 
 /// LFO settings for a preset.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PresetLfo {
     pub waveform: LFOWaveform,
     pub rate: f32,
@@ -111,10 +138,13 @@ impl Default for PresetLfo {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 #[allow(dead_code)]
 pub struct Dx7Preset {
     pub name: String,
+    /// Where the preset was loaded from; not saved with it.
+    #[serde(skip)]
     pub collection: String,
     pub algorithm: u8,
     pub operators: [PresetOperator; 6],
@@ -131,6 +161,26 @@ pub struct Dx7Preset {
     pub lfo: Option<PresetLfo>,
 }
 
+impl Default for Dx7Preset {
+    fn default() -> Self {
+        Self {
+            name: "INIT VOICE".to_string(),
+            collection: String::new(),
+            algorithm: 1,
+            operators: std::array::from_fn(|_| PresetOperator::default()),
+            master_tune: None,
+            pitch_bend_range: None,
+            portamento_enable: None,
+            portamento_time: None,
+            mono_mode: None,
+            transpose_semitones: 0,
+            pitch_mod_sensitivity: 0,
+            pitch_eg: None,
+            lfo: None,
+        }
+    }
+}
+
 impl Dx7Preset {
     /// Build a preset from a live state snapshot. Used to export the current
     /// edit buffer (e.g. as a DX7 SysEx single-voice dump).
@@ -150,7 +200,13 @@ impl Dx7Preset {
                 key_scale_left_depth: op.key_scale_left_depth,
                 key_scale_right_depth: op.key_scale_right_depth,
                 am_sensitivity: op.am_sensitivity,
+                pm_sensitivity: op.pm_sensitivity,
+                lfo2: op.lfo2,
                 oscillator_key_sync: op.oscillator_key_sync,
+                sub_bus: op.sub_bus,
+                expression: op.expression,
+                waveform: op.waveform,
+                phase_offset: op.phase_offset,
                 fixed_frequency: op.fixed_frequency,
                 fixed_freq_hz: op.fixed_freq_hz,
                 envelope: (
@@ -198,16 +254,31 @@ impl Dx7Preset {
     }
 
     /// Apply this preset to the synth: algorithm, name, per-operator parameters,
-    /// optional global parameters, pitch EG, and LFO. Voice mode and portamento
-    /// stay as the synth had them unless explicitly set.
+    /// optional function parameters, pitch EG, and LFO. Function parameters the
+    /// preset leaves as `None` stay as the synth had them.
     pub fn apply_to_synth(&self, synth: &mut SynthEngine) {
         synth.set_algorithm(self.algorithm);
         synth.set_preset_name(self.name.clone());
 
         synth.set_transpose_semitones(self.transpose_semitones);
         synth.set_pitch_mod_sensitivity(self.pitch_mod_sensitivity);
+
+        // Function parameters go through the command handler so they get the
+        // same clamping and mono switch-over as the GUI controls.
+        if let Some(cents) = self.master_tune {
+            synth.apply_command(SynthCommand::SetMasterTune(cents));
+        }
         if let Some(range) = self.pitch_bend_range {
-            synth.set_pitch_bend_range(range);
+            synth.apply_command(SynthCommand::SetPitchBendRange(range));
+        }
+        if let Some(mono) = self.mono_mode {
+            synth.apply_command(SynthCommand::SetVoiceMode(u8::from(mono)));
+        }
+        if let Some(enable) = self.portamento_enable {
+            synth.apply_command(SynthCommand::SetPortamentoEnable(enable));
+        }
+        if let Some(time) = self.portamento_time {
+            synth.apply_command(SynthCommand::SetPortamentoTime(time));
         }
 
         // Pitch EG
@@ -241,7 +312,7 @@ impl Dx7Preset {
         for voice in synth.voices_mut() {
             for (i, op) in voice.operators.iter_mut().enumerate() {
                 let p = &self.operators[i];
-                op.frequency_ratio = p.frequency_ratio;
+                op.set_frequency_ratio(p.frequency_ratio);
                 op.output_level = p.output_level;
                 op.detune = p.detune;
                 op.feedback = p.feedback;
@@ -253,7 +324,13 @@ impl Dx7Preset {
                 op.key_scale_left_depth = p.key_scale_left_depth;
                 op.key_scale_right_depth = p.key_scale_right_depth;
                 op.am_sensitivity = p.am_sensitivity;
+                op.pm_sensitivity = p.pm_sensitivity;
+                op.lfo2 = p.lfo2;
                 op.oscillator_key_sync = p.oscillator_key_sync;
+                op.sub_bus = p.sub_bus;
+                op.expression = p.expression;
+                op.waveform = p.waveform;
+                op.set_phase_offset(p.phase_offset);
                 op.fixed_frequency = p.fixed_frequency;
                 op.fixed_freq_hz = p.fixed_freq_hz;
                 let (r1, r2, r3, r4, l1, l2, l3, l4) = p.envelope;
@@ -376,6 +453,47 @@ mod tests {
         preset.apply_to_synth(&mut engine);
         assert_eq!(engine.preset_name, "APPLIED");
         assert_eq!(engine.get_algorithm(), 11);
+        assert_eq!(engine.get_pitch_bend_range(), 3.0);
+    }
+
+    #[test]
+    fn apply_to_synth_leaves_unset_function_params_alone() {
+        let mut engine = make_engine();
+        engine.apply_command(SynthCommand::SetVoiceMode(1));
+        engine.apply_command(SynthCommand::SetPortamentoEnable(true));
+        let preset = Dx7Preset {
+            name: "POLY".to_string(),
+            collection: "test".to_string(),
+            algorithm: 1,
+            operators: std::array::from_fn(|_| PresetOperator::default()),
+            master_tune: None,
+            pitch_bend_range: None,
+            portamento_enable: None,
+            portamento_time: None,
+            mono_mode: None,
+            transpose_semitones: 0,
+            pitch_mod_sensitivity: 0,
+            pitch_eg: None,
+            lfo: None,
+        };
+        preset.apply_to_synth(&mut engine);
+        assert_eq!(
+            engine.get_voice_mode(),
+            crate::state_snapshot::VoiceMode::Mono
+        );
+        assert!(engine.get_portamento_enable());
+
+        let preset = Dx7Preset {
+            mono_mode: Some(false),
+            portamento_enable: Some(false),
+            ..preset
+        };
+        preset.apply_to_synth(&mut engine);
+        assert_eq!(
+            engine.get_voice_mode(),
+            crate::state_snapshot::VoiceMode::Poly
+        );
+        assert!(!engine.get_portamento_enable());
     }
 
     #[test]
@@ -0,0 +1,166 @@
+//! MIDI program-change map: Program Change numbers assigned to chosen
+//! presets, for controllers whose fixed program buttons don't line up with
+//! the preset list.
+//!
+//! Entries name a preset by collection and name rather than by position, so
+//! the map survives presets being added or the `patches/` folder changing.
+//! [`ProgramMap::resolve`] turns it into the table the engine looks up on
+//! each Program Change; a number with no entry, or whose preset is gone,
+//! falls back to bank select + program as before.
+
+use crate::presets::Dx7Preset;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Program Change numbers a controller can send.
+pub const PROGRAMS: usize = 128;
+
+/// A preset by collection and name.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresetRef {
+    pub collection: String,
+    pub name: String,
+}
+
+impl PresetRef {
+    pub fn of(preset: &Dx7Preset) -> Self {
+        Self {
+            collection: preset.collection.clone(),
+            name: preset.name.clone(),
+        }
+    }
+
+    fn matches(&self, preset: &Dx7Preset) -> bool {
+        preset.collection == self.collection && preset.name == self.name
+    }
+}
+
+/// Program number → preset. Saved with the app settings as a JSON object
+/// keyed by program number.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProgramMap {
+    entries: BTreeMap<u8, PresetRef>,
+}
+
+impl ProgramMap {
+    /// Make Program Change `program` (0-127) load `preset`, replacing what
+    /// it was mapped to.
+    pub fn assign(&mut self, program: u8, preset: &Dx7Preset) {
+        self.entries.insert(program & 0x7F, PresetRef::of(preset));
+    }
+
+    pub fn remove(&mut self, program: u8) {
+        self.entries.remove(&program);
+    }
+
+    pub fn get(&self, program: u8) -> Option<&PresetRef> {
+        self.entries.get(&program)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries in program order.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &PresetRef)> {
+        self.entries
+            .iter()
+            .map(|(&program, preset)| (program, preset))
+    }
+
+    /// Position in `presets` of the preset `program` is mapped to.
+    pub fn find(&self, program: u8, presets: &[Dx7Preset]) -> Option<usize> {
+        let target = self.get(program)?;
+        presets.iter().position(|p| target.matches(p))
+    }
+
+    /// Table for the engine: entry `n` is the preset Program Change `n`
+    /// loads, None where the list has no match. Empty when nothing is
+    /// mapped.
+    pub fn resolve(&self, presets: &[Dx7Preset]) -> Vec<Option<Dx7Preset>> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        (0..PROGRAMS as u8)
+            .map(|program| {
+                self.find(program, presets)
+                    .map(|index| presets[index].clone())
+            })
+            .collect()
+    }
+
+    /// Drop entries for numbers above 127, from a hand-edited file.
+    pub fn clamped(mut self) -> Self {
+        self.entries
+            .retain(|&program, _| (program as usize) < PROGRAMS);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(collection: &str, name: &str) -> Dx7Preset {
+        Dx7Preset {
+            name: name.to_string(),
+            collection: collection.to_string(),
+            ..Dx7Preset::default()
+        }
+    }
+
+    #[test]
+    fn entries_follow_their_preset_by_name() {
+        let mut map = ProgramMap::default();
+        assert!(map.resolve(&[preset("factory", "BRASS 1")]).is_empty());
+
+        map.assign(0, &preset("user", "MY PAD"));
+        map.assign(5, &preset("factory", "BRASS 1"));
+        map.assign(7, &preset("factory", "GONE"));
+        let presets = [
+            preset("factory", "E.PIANO 1"),
+            preset("factory", "BRASS 1"),
+            preset("user", "MY PAD"),
+        ];
+        assert_eq!(map.find(0, &presets), Some(2));
+        assert_eq!(map.find(5, &presets), Some(1));
+        assert_eq!(map.find(7, &presets), None);
+
+        let table = map.resolve(&presets);
+        assert_eq!(table.len(), PROGRAMS);
+        assert_eq!(table[0].as_ref().map(|p| p.name.as_str()), Some("MY PAD"));
+        assert_eq!(table[5].as_ref().map(|p| p.name.as_str()), Some("BRASS 1"));
+        assert!(table[1].is_none() && table[7].is_none());
+
+        // Reordering the list keeps the same voices on the same numbers.
+        let reordered = [presets[2].clone(), presets[1].clone(), presets[0].clone()];
+        assert_eq!(map.find(0, &reordered), Some(0));
+        map.remove(0);
+        assert_eq!(map.get(0), None);
+
+        // Numbers wrap to seven bits, as on the MIDI wire.
+        map.assign(128 + 3, &presets[0]);
+        assert_eq!(map.get(3).map(|p| p.name.as_str()), Some("E.PIANO 1"));
+    }
+
+    #[test]
+    fn saves_as_an_object_keyed_by_program() {
+        let mut map = ProgramMap::default();
+        map.assign(12, &preset("user", "LEAD"));
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(json, r#"{"12":{"collection":"user","name":"LEAD"}}"#);
+        assert_eq!(serde_json::from_str::<ProgramMap>(&json).unwrap(), map);
+
+        let edited: ProgramMap = serde_json::from_str(
+            r#"{"3":{"collection":"a","name":"X"},"200":{"collection":"a","name":"Y"}}"#,
+        )
+        .unwrap();
+        let kept: Vec<u8> = edited
+            .clamped()
+            .iter()
+            .map(|(program, _)| program)
+            .collect();
+        assert_eq!(kept, [3]);
+    }
+}
@@ -0,0 +1,508 @@
+//! Offline (non-realtime) rendering to WAV.
+//!
+//! Notes come from a Standard MIDI File or a plain-text note list and are
+//! played through a private engine built at the requested sample rate, one
+//! `process_block` call per stretch between note events. No audio device is touched, so the same
+//! input always renders the same file.
+
+use crate::fm_synth::create_synth;
+use crate::presets::Dx7Preset;
+use std::io::Write;
+use std::path::Path;
+
+/// Release tail rendered after the last note-off, so envelopes can finish.
+pub const DEFAULT_TAIL_SECONDS: f32 = 2.0;
+
+/// Seed for the engine's random draws, so S&H LFO patches render the same
+/// file every time too.
+pub const RENDER_SEED: u32 = 0x5EED_0D57;
+
+/// One note of the score, in seconds from the start of the render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteEvent {
+    pub start: f32,
+    pub duration: f32,
+    pub note: u8,
+    pub velocity: u8,
+}
+
+/// Parse a note list: one note per line as `note velocity start duration`
+/// (times in seconds). Blank lines and `#` comments are ignored.
+///
+/// ```text
+/// # C major triad, then a held C5
+/// 60 100 0.0 1.0
+/// 64 100 0.0 1.0
+/// 67 100 0.0 1.0
+/// 72  90 1.0 2.0
+/// ```
+pub fn parse_note_list(text: &str) -> Result<Vec<NoteEvent>, String> {
+    let mut events = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let bad = |what: &str| format!("line {}: {}", line_no + 1, what);
+        if fields.len() != 4 {
+            return Err(bad("expected `note velocity start duration`"));
+        }
+        let note = fields[0]
+            .parse::<u8>()
+            .ok()
+            .filter(|&n| n <= 127)
+            .ok_or_else(|| bad("note must be 0..127"))?;
+        let velocity = fields[1]
+            .parse::<u8>()
+            .ok()
+            .filter(|v| (1..=127).contains(v))
+            .ok_or_else(|| bad("velocity must be 1..127"))?;
+        let start = fields[2]
+            .parse::<f32>()
+            .ok()
+            .filter(|&s| s >= 0.0)
+            .ok_or_else(|| bad("start must be a non-negative number"))?;
+        let duration = fields[3]
+            .parse::<f32>()
+            .ok()
+            .filter(|&d| d >= 0.0)
+            .ok_or_else(|| bad("duration must be a non-negative number"))?;
+        events.push(NoteEvent {
+            start,
+            duration,
+            note,
+            velocity,
+        });
+    }
+    Ok(events)
+}
+
+/// Parse a Standard MIDI File (format 0 or 1, PPQN timing) into notes.
+/// Channels are merged; tempo changes on any track apply to all of them.
+pub fn parse_midi_file(bytes: &[u8]) -> Result<Vec<NoteEvent>, String> {
+    let mut reader = ByteReader::new(bytes);
+    if reader.take(4)? != b"MThd" {
+        return Err("not a Standard MIDI File (missing MThd)".to_string());
+    }
+    let header_len = reader.u32()? as usize;
+    let header = reader.take(header_len)?;
+    if header.len() < 6 {
+        return Err("MThd header too short".to_string());
+    }
+    let track_count = u16::from_be_bytes([header[2], header[3]]);
+    let division = u16::from_be_bytes([header[4], header[5]]);
+    if division & 0x8000 != 0 {
+        return Err("SMPTE time division is not supported".to_string());
+    }
+    let ticks_per_quarter = division.max(1) as f64;
+
+    // (tick, kind) where kind is Tempo(us per quarter) or a note on/off.
+    let mut raw = Vec::new();
+    for _ in 0..track_count {
+        let id = reader.take(4)?;
+        let len = reader.u32()? as usize;
+        let chunk = reader.take(len)?;
+        if id == b"MTrk" {
+            parse_track(chunk, &mut raw)?;
+        }
+    }
+    // Stable sort keeps each track's order for events on the same tick.
+    raw.sort_by_key(|e| e.0);
+
+    let mut events = Vec::new();
+    let mut open: Vec<(u8, u8, f64)> = Vec::new(); // (note, velocity, start)
+    let mut us_per_quarter = 500_000.0_f64;
+    let mut last_tick = 0u64;
+    let mut seconds = 0.0_f64;
+    for (tick, kind) in raw {
+        seconds += (tick - last_tick) as f64 / ticks_per_quarter * us_per_quarter / 1e6;
+        last_tick = tick;
+        match kind {
+            MidiEvent::Tempo(t) => us_per_quarter = t as f64,
+            MidiEvent::NoteOn(note, velocity) => open.push((note, velocity, seconds)),
+            MidiEvent::NoteOff(note) => {
+                if let Some(i) = open.iter().position(|&(n, _, _)| n == note) {
+                    let (note, velocity, start) = open.remove(i);
+                    events.push(NoteEvent {
+                        start: start as f32,
+                        duration: (seconds - start) as f32,
+                        note,
+                        velocity,
+                    });
+                }
+            }
+        }
+    }
+    // Notes never released end with the file.
+    for (note, velocity, start) in open {
+        events.push(NoteEvent {
+            start: start as f32,
+            duration: (seconds - start) as f32,
+            note,
+            velocity,
+        });
+    }
+    events.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(events)
+}
+
+enum MidiEvent {
+    Tempo(u32),
+    NoteOn(u8, u8),
+    NoteOff(u8),
+}
+
+fn parse_track(data: &[u8], out: &mut Vec<(u64, MidiEvent)>) -> Result<(), String> {
+    let mut reader = ByteReader::new(data);
+    let mut tick = 0u64;
+    let mut running_status = 0u8;
+    while !reader.is_empty() {
+        tick += reader.var_len()? as u64;
+        let mut status = reader.u8()?;
+        if status < 0x80 {
+            // Running status: this byte is the first data byte.
+            if running_status == 0 {
+                return Err("data byte without running status".to_string());
+            }
+            reader.pos -= 1;
+            status = running_status;
+        }
+        match status {
+            0xFF => {
+                let meta = reader.u8()?;
+                let len = reader.var_len()? as usize;
+                let payload = reader.take(len)?;
+                if meta == 0x51 && payload.len() == 3 {
+                    let t = u32::from_be_bytes([0, payload[0], payload[1], payload[2]]);
+                    out.push((tick, MidiEvent::Tempo(t)));
+                } else if meta == 0x2F {
+                    break;
+                }
+            }
+            0xF0 | 0xF7 => {
+                let len = reader.var_len()? as usize;
+                reader.take(len)?;
+            }
+            _ => {
+                running_status = status;
+                match status & 0xF0 {
+                    0x80 => {
+                        let note = reader.u8()?;
+                        reader.u8()?;
+                        out.push((tick, MidiEvent::NoteOff(note)));
+                    }
+                    0x90 => {
+                        let note = reader.u8()?;
+                        let velocity = reader.u8()?;
+                        let event = if velocity == 0 {
+                            MidiEvent::NoteOff(note)
+                        } else {
+                            MidiEvent::NoteOn(note, velocity)
+                        };
+                        out.push((tick, event));
+                    }
+                    0xC0 | 0xD0 => {
+                        reader.u8()?;
+                    }
+                    _ => {
+                        reader.take(2)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| "unexpected end of MIDI data".to_string())?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// MIDI variable-length quantity (up to 4 bytes).
+    fn var_len(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("variable-length quantity longer than 4 bytes".to_string())
+    }
+}
+
+/// Render `events` with `preset` (or the init voice) at `sample_rate`,
+/// followed by `tail_seconds` of release. Returns interleaved-ready frames.
+pub fn render_events(
+    preset: Option<&Dx7Preset>,
+    events: &[NoteEvent],
+    sample_rate: f32,
+    tail_seconds: f32,
+) -> Vec<[f32; 2]> {
+    let (mut engine, mut controller) = create_synth(sample_rate);
+    engine.seed_random(RENDER_SEED);
+    if let Some(preset) = preset {
+        preset.apply_to_synth(&mut engine);
+    }
+
+    let to_frame = |seconds: f32| (seconds.max(0.0) * sample_rate).round() as usize;
+    // (frame, is_note_on, note, velocity). Offs sort before ons on the same
+    // frame so a repeated note re-triggers instead of being cut; a note's own
+    // off therefore lands at least one frame after its on, or a zero-length
+    // note would never be released.
+    let mut timeline: Vec<(usize, bool, u8, u8)> = Vec::with_capacity(events.len() * 2);
+    for e in events {
+        let on = to_frame(e.start);
+        timeline.push((on, true, e.note, e.velocity));
+        timeline.push((to_frame(e.start + e.duration).max(on + 1), false, e.note, 0));
+    }
+    timeline.sort_by_key(|&(frame, on, _, _)| (frame, on));
+
+    let end = timeline.last().map_or(0, |t| t.0) + to_frame(tail_seconds);
+    let mut left = vec![0.0; end];
+    let mut right = vec![0.0; end];
+    let mut next = 0;
+    let mut frame = 0;
+    // Render in blocks that end at the next event, so every note lands on
+    // its exact frame.
+    while frame < end {
+        while next < timeline.len() && timeline[next].0 == frame {
+            let (_, on, note, velocity) = timeline[next];
+            if on {
+                controller.note_on(note, velocity);
+            } else {
+                controller.note_off(note);
+            }
+            next += 1;
+        }
+        let block_end = timeline.get(next).map_or(end, |t| t.0.min(end));
+        engine.process_block(&mut left[frame..block_end], &mut right[frame..block_end]);
+        frame = block_end;
+    }
+    left.into_iter().zip(right).map(|(l, r)| [l, r]).collect()
+}
+
+/// Write `frames` as a 16-bit PCM stereo WAV.
+pub fn write_wav(path: &Path, frames: &[[f32; 2]], sample_rate: u32) -> std::io::Result<()> {
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    file.write_all(&wav_bytes(frames, sample_rate))?;
+    file.flush()
+}
+
+fn wav_bytes(frames: &[[f32; 2]], sample_rate: u32) -> Vec<u8> {
+    const CHANNELS: u16 = 2;
+    const BITS: u16 = 16;
+    let block_align = CHANNELS * BITS / 8;
+    let data_len = frames.len() as u32 * block_align as u32;
+
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&CHANNELS.to_le_bytes());
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    out.extend_from_slice(&block_align.to_le_bytes());
+    out.extend_from_slice(&BITS.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for frame in frames {
+        for &s in frame {
+            let v = (s.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_list_parses_and_skips_comments() {
+        let text = "# header\n60 100 0.0 0.5\n\n64 80 0.25 1  # trailing\n";
+        let events = parse_note_list(text).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                NoteEvent {
+                    start: 0.0,
+                    duration: 0.5,
+                    note: 60,
+                    velocity: 100
+                },
+                NoteEvent {
+                    start: 0.25,
+                    duration: 1.0,
+                    note: 64,
+                    velocity: 80
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn note_list_reports_bad_lines() {
+        let err = parse_note_list("60 100 0.0 0.5\n200 100 0 1\n").unwrap_err();
+        assert!(err.starts_with("line 2"), "{}", err);
+        assert!(parse_note_list("60 100 0.0").is_err());
+        assert!(parse_note_list("60 0 0.0 1.0").is_err());
+    }
+
+    /// Format-0 file, 96 PPQN, 120 → 60 BPM tempo change at beat 1.
+    fn sample_midi() -> Vec<u8> {
+        let track: Vec<u8> = vec![
+            0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20, // tempo 500000 (120 BPM)
+            0x00, 0x90, 60, 100, // note on C4
+            0x60, 0x80, 60, 0, // 96 ticks later: off (0.5 s)
+            0x00, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40, // tempo 1000000 (60 BPM)
+            0x00, 0x90, 64, 90, // note on E4
+            0x60, 64, 0, // running status, vel 0 = off (1.0 s later)
+            0x00, 0xFF, 0x2F, 0x00, // end of track
+        ];
+        let mut bytes = b"MThd".to_vec();
+        bytes.extend_from_slice(&6u32.to_be_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 1, 0, 96]);
+        bytes.extend_from_slice(b"MTrk");
+        bytes.extend_from_slice(&(track.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&track);
+        bytes
+    }
+
+    #[test]
+    fn midi_file_follows_tempo_changes() {
+        let events = parse_midi_file(&sample_midi()).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].note, events[0].velocity), (60, 100));
+        assert!((events[0].start - 0.0).abs() < 1e-6);
+        assert!((events[0].duration - 0.5).abs() < 1e-6);
+        assert_eq!(events[1].note, 64);
+        assert!((events[1].start - 0.5).abs() < 1e-6);
+        assert!((events[1].duration - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn midi_file_rejects_garbage_and_truncation() {
+        assert!(parse_midi_file(b"RIFF....").is_err());
+        let mut truncated = sample_midi();
+        truncated.truncate(truncated.len() - 5);
+        assert!(parse_midi_file(&truncated).is_err());
+    }
+
+    #[test]
+    fn render_length_tracks_sample_rate_and_tail() {
+        let events = [NoteEvent {
+            start: 0.0,
+            duration: 0.1,
+            note: 69,
+            velocity: 100,
+        }];
+        let frames = render_events(None, &events, 22_050.0, 0.05);
+        assert_eq!(frames.len(), 2205 + 1103);
+        let peak = frames.iter().fold(0.0_f32, |m, f| m.max(f[0].abs()));
+        assert!(peak > 0.01, "render should not be silent");
+
+        assert_eq!(render_events(None, &events, 96_000.0, 0.0).len(), 9600);
+    }
+
+    /// Peak of the last 0.1 s of a render.
+    fn tail_peak(frames: &[[f32; 2]], sample_rate: f32) -> f32 {
+        let tail = (0.1 * sample_rate) as usize;
+        frames[frames.len() - tail..]
+            .iter()
+            .fold(0.0_f32, |m, f| m.max(f[0].abs()).max(f[1].abs()))
+    }
+
+    #[test]
+    fn zero_length_notes_from_a_note_list_are_released() {
+        let events = parse_note_list(
+            "69 100 0.1 0
+",
+        )
+        .unwrap();
+        assert_eq!(events[0].duration, 0.0);
+        let frames = render_events(None, &events, 22_050.0, 1.0);
+        let peak = tail_peak(&frames, 22_050.0);
+        assert!(peak < 0.01, "tail peak {peak}");
+    }
+
+    #[test]
+    fn zero_length_notes_from_a_midi_file_are_released() {
+        let track: Vec<u8> = vec![
+            0x00, 0x90, 69, 100, // note on A4
+            0x00, 0x80, 69, 0, // off on the same tick
+            0x00, 0xFF, 0x2F, 0x00, // end of track
+        ];
+        let mut bytes = b"MThd".to_vec();
+        bytes.extend_from_slice(&6u32.to_be_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 1, 0, 96]);
+        bytes.extend_from_slice(b"MTrk");
+        bytes.extend_from_slice(&(track.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&track);
+
+        let events = parse_midi_file(&bytes).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].duration, 0.0);
+        let frames = render_events(None, &events, 22_050.0, 1.0);
+        let peak = tail_peak(&frames, 22_050.0);
+        assert!(peak < 0.01, "tail peak {peak}");
+    }
+
+    #[test]
+    fn render_is_deterministic() {
+        let events = parse_note_list("60 100 0 0.05\n67 100 0.02 0.05").unwrap();
+        let a = render_events(None, &events, 44_100.0, 0.01);
+        let b = render_events(None, &events, 44_100.0, 0.01);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn wav_header_describes_16_bit_stereo() {
+        let bytes = wav_bytes(&[[0.0, 1.0], [-1.0, 0.5]], 48_000);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        assert_eq!(
+            u32::from_le_bytes(bytes[24..28].try_into().unwrap()),
+            48_000
+        );
+        assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), 8);
+        assert_eq!(bytes.len(), 44 + 8);
+        assert_eq!(i16::from_le_bytes([bytes[46], bytes[47]]), i16::MAX);
+        assert_eq!(i16::from_le_bytes([bytes[48], bytes[49]]), -i16::MAX);
+    }
+}
@@ -0,0 +1,576 @@
+//! Line-delimited JSON-RPC 2.0 control over stdin/stdout (`--rpc`).
+//!
+//! Each input line is one request; each request with an `id` gets exactly one
+//! response line. Requests without an `id` are notifications and get none.
+//! Commands travel through the same `SynthController` queue the GUI and MIDI
+//! use. No audio device is opened: the engine only advances on `render`, so a
+//! script gets the same samples on every run.
+//!
+//! Methods:
+//! - `note_on {note, velocity?}` / `note_off {note}` / `panic`
+//! - `set {param, value, operator?}` — global or per-operator (1..=6) parameter
+//! - `load_preset {index}` or `{name}` / `list_presets`
+//! - `render {frames, include_audio?}` — advance the engine, report peak/RMS
+//! - `snapshot` — current engine state
+
+use crate::command_queue::OperatorParam;
+use crate::fm_synth::{SynthController, SynthEngine};
+use crate::presets::Dx7Preset;
+use crate::state_snapshot::{
+    NotePriority, PatchChangeMode, PortamentoMode, SameNoteMode, SynthSnapshot, VoiceMode,
+};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+/// Longest single `render` request, in frames (60 s at 48 kHz).
+pub const MAX_RENDER_FRAMES: usize = 48_000 * 60;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message: message.into(),
+        }
+    }
+}
+
+pub struct RpcServer {
+    engine: SynthEngine,
+    controller: SynthController,
+    preset_names: Vec<String>,
+}
+
+impl RpcServer {
+    /// Take ownership of a freshly created engine/controller pair and hand the
+    /// bank to the engine so `load_preset` and MIDI-style program changes work.
+    pub fn new(
+        mut engine: SynthEngine,
+        controller: SynthController,
+        presets: Vec<Dx7Preset>,
+    ) -> Self {
+        let preset_names = presets.iter().map(|p| p.name.clone()).collect();
+        if let Some(first) = presets.first() {
+            first.apply_to_synth(&mut engine);
+        }
+        engine.set_presets(presets);
+        Self {
+            engine,
+            controller,
+            preset_names,
+        }
+    }
+
+    /// Serve requests until `input` reaches EOF.
+    pub fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_line(&line) {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle one request line; returns the response line, or `None` for a
+    /// notification.
+    pub fn handle_line(&mut self, line: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(e) => {
+                return Some(error_response(
+                    Value::Null,
+                    RpcError {
+                        code: PARSE_ERROR,
+                        message: e.to_string(),
+                    },
+                ))
+            }
+        };
+
+        let id = request.get("id").cloned();
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                RpcError {
+                    code: INVALID_REQUEST,
+                    message: "missing method".to_string(),
+                },
+            ));
+        };
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = self.dispatch(method, &params);
+        let id = id?;
+        Some(match result {
+            Ok(value) => json!({ "jsonrpc": "2.0", "id": id, "result": value }).to_string(),
+            Err(e) => error_response(id, e),
+        })
+    }
+
+    fn dispatch(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "note_on" => {
+                let note = note_param(params)?;
+                let velocity = match params.get("velocity") {
+                    Some(v) => v
+                        .as_u64()
+                        .filter(|v| (1..=127).contains(v))
+                        .ok_or_else(|| RpcError::invalid_params("velocity must be 1..127"))?
+                        as u8,
+                    None => 100,
+                };
+                self.controller.note_on(note, velocity);
+                Ok(Value::Null)
+            }
+            "note_off" => {
+                let note = note_param(params)?;
+                self.controller.note_off(note);
+                Ok(Value::Null)
+            }
+            "panic" => {
+                self.controller.panic();
+                Ok(Value::Null)
+            }
+            "set" => self.set_param(params).map(|_| Value::Null),
+            "load_preset" => self.load_preset(params),
+            "list_presets" => Ok(json!(self.preset_names)),
+            "render" => self.render(params),
+            "snapshot" => Ok(self.snapshot()),
+            _ => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("unknown method '{}'", method),
+            }),
+        }
+    }
+
+    fn set_param(&mut self, params: &Value) -> Result<(), RpcError> {
+        let name = params
+            .get("param")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::invalid_params("missing param"))?;
+        let value = params
+            .get("value")
+            .and_then(value_as_f32)
+            .ok_or_else(|| RpcError::invalid_params("missing or non-numeric value"))?;
+
+        if let Some(op) = params.get("operator") {
+            let op = op
+                .as_u64()
+                .filter(|op| (1..=6).contains(op))
+                .ok_or_else(|| RpcError::invalid_params("operator must be 1..6"))?;
+            let param = operator_param(name).ok_or_else(|| {
+                RpcError::invalid_params(format!("unknown operator param '{}'", name))
+            })?;
+            // The queue indexes operators from 0; the DX7 panel numbers them 1..6.
+            self.controller
+                .set_operator_param(op as u8 - 1, param, value);
+            return Ok(());
+        }
+
+        let ctrl = &mut self.controller;
+        match name {
+            "algorithm" => ctrl.set_algorithm(value as u8),
+            "custom_algorithm" => ctrl.set_custom_algorithm(value != 0.0),
+            "master_volume" => ctrl.set_master_volume(value),
+            "master_tune" => ctrl.set_master_tune(value),
+            "transpose" => ctrl.set_transpose(value as i8),
+            "pitch_bend_range" => ctrl.set_pitch_bend_range(value),
+            "portamento_enable" => ctrl.set_portamento_enable(value != 0.0),
+            "portamento_time" => ctrl.set_portamento_time(value),
+            "portamento_fingered" => ctrl.set_portamento_mode(if value != 0.0 {
+                PortamentoMode::Fingered
+            } else {
+                PortamentoMode::FullTime
+            }),
+            "voice_mode" => ctrl.set_voice_mode(match value as u8 {
+                1 => VoiceMode::Mono,
+                2 => VoiceMode::MonoLegato,
+                _ => VoiceMode::Poly,
+            }),
+            "note_priority" => ctrl.set_note_priority(match value as u8 {
+                1 => NotePriority::Low,
+                2 => NotePriority::High,
+                _ => NotePriority::Last,
+            }),
+            "pitch_bend" => ctrl.pitch_bend(value as i16),
+            "pitch_bend_smoothing" => ctrl.set_pitch_bend_smoothing(value),
+            "mod_wheel" => ctrl.mod_wheel(value),
+            "sustain" => ctrl.sustain_pedal(value != 0.0),
+            "vintage" => ctrl.set_vintage_mode(value != 0.0),
+            "output_dac" => ctrl.set_output_dac(value != 0.0),
+            "output_dac_intensity" => ctrl.set_output_dac_intensity(value),
+            "noise_floor" => ctrl.set_noise_floor(value != 0.0),
+            "noise_level_db" => ctrl.set_noise_level(value),
+            "hum_hz" => ctrl.set_hum_frequency(value),
+            "tempo" => ctrl.set_tempo(value),
+            "master_pan" => ctrl.set_master_pan(value),
+            "unison_voices" => ctrl.set_unison_voices(value.clamp(1.0, 255.0) as u8),
+            "unison_detune" => ctrl.set_unison_detune(value),
+            "unison_spread" => ctrl.set_unison_spread(value),
+            "operator_solo" => ctrl.set_operator_solo(value.clamp(0.0, 63.0) as u8),
+            "voice_spread_width" => ctrl.set_voice_spread_width(value),
+            "effects_bypass" => ctrl.set_effects_bypass(value != 0.0),
+            "extensions" => ctrl.set_extensions(value != 0.0),
+            "polyphony" => ctrl.set_polyphony(value.clamp(1.0, 64.0) as u8),
+            "stack_same_note" => ctrl.set_same_note_mode(if value != 0.0 {
+                SameNoteMode::Stack
+            } else {
+                SameNoteMode::Retrigger
+            }),
+            "retrigger_on_patch_change" => ctrl.set_patch_change_mode(if value != 0.0 {
+                PatchChangeMode::Retrigger
+            } else {
+                PatchChangeMode::Crossfade
+            }),
+            _ => {
+                return Err(RpcError::invalid_params(format!(
+                    "unknown param '{}'",
+                    name
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn load_preset(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let index = if let Some(index) = params.get("index") {
+            index
+                .as_u64()
+                .map(|i| i as usize)
+                .filter(|&i| i < self.preset_names.len())
+                .ok_or_else(|| RpcError::invalid_params("preset index out of range"))?
+        } else if let Some(name) = params.get("name").and_then(Value::as_str) {
+            self.preset_names
+                .iter()
+                .position(|n| n.eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| RpcError::invalid_params(format!("no preset named '{}'", name)))?
+        } else {
+            return Err(RpcError::invalid_params("expected index or name"));
+        };
+        self.controller.load_preset(index);
+        Ok(json!({ "index": index, "name": self.preset_names[index] }))
+    }
+
+    fn render(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let frames = params
+            .get("frames")
+            .and_then(Value::as_u64)
+            .map(|f| f as usize)
+            .filter(|&f| f <= MAX_RENDER_FRAMES)
+            .ok_or_else(|| {
+                RpcError::invalid_params(format!("frames must be 0..={}", MAX_RENDER_FRAMES))
+            })?;
+        let include_audio = params
+            .get("include_audio")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let mut left = vec![0.0; frames];
+        let mut right = vec![0.0; frames];
+        self.engine.process_block(&mut left, &mut right);
+        let mut peak = 0.0_f32;
+        let mut sum_sq = 0.0_f64;
+        for (&l, &r) in left.iter().zip(&right) {
+            peak = peak.max(l.abs()).max(r.abs());
+            sum_sq += (l as f64 * l as f64 + r as f64 * r as f64) * 0.5;
+        }
+        let rms = if frames > 0 {
+            (sum_sq / frames as f64).sqrt() as f32
+        } else {
+            0.0
+        };
+
+        let mut result = json!({ "frames": frames, "peak": peak, "rms": rms });
+        if include_audio {
+            result["left"] = json!(left);
+            result["right"] = json!(right);
+        }
+        Ok(result)
+    }
+
+    fn snapshot(&mut self) -> Value {
+        self.engine.process_commands();
+        self.engine.update_snapshot();
+        snapshot_json(&self.controller.snapshot())
+    }
+}
+
+fn note_param(params: &Value) -> Result<u8, RpcError> {
+    params
+        .get("note")
+        .and_then(Value::as_u64)
+        .filter(|&n| n <= 127)
+        .map(|n| n as u8)
+        .ok_or_else(|| RpcError::invalid_params("note must be 0..127"))
+}
+
+/// Numbers pass through; booleans map to 1/0 so on/off params read naturally.
+fn value_as_f32(value: &Value) -> Option<f32> {
+    match value {
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        v => v.as_f64().map(|v| v as f32),
+    }
+}
+
+fn operator_param(name: &str) -> Option<OperatorParam> {
+    Some(match name {
+        "ratio" => OperatorParam::Ratio,
+        "coarse" => OperatorParam::Coarse,
+        "fine" => OperatorParam::Fine,
+        "extended_ratio" => OperatorParam::ExtendedRatio,
+        "level" => OperatorParam::Level,
+        "detune" => OperatorParam::Detune,
+        "feedback" => OperatorParam::Feedback,
+        "velocity_sensitivity" => OperatorParam::VelocitySensitivity,
+        "key_scale_rate" => OperatorParam::KeyScaleRate,
+        "am_sensitivity" => OperatorParam::AmSensitivity,
+        "pm_sensitivity" => OperatorParam::PmSensitivity,
+        "lfo2" => OperatorParam::Lfo2,
+        "fixed_frequency" => OperatorParam::FixedFrequency,
+        "fixed_freq_hz" => OperatorParam::FixedFreqHz,
+        "sub_bus" => OperatorParam::SubBus,
+        "expression" => OperatorParam::Expression,
+        "waveform" => OperatorParam::Waveform,
+        "phase_offset" => OperatorParam::PhaseOffset,
+        "enabled" => OperatorParam::Enabled,
+        _ => return None,
+    })
+}
+
+fn snapshot_json(s: &SynthSnapshot) -> Value {
+    let note_priority = match s.note_priority {
+        NotePriority::Last => "last",
+        NotePriority::Low => "low",
+        NotePriority::High => "high",
+    };
+    let voice_mode = match s.voice_mode {
+        VoiceMode::Poly => "poly",
+        VoiceMode::Mono => "mono",
+        VoiceMode::MonoLegato => "mono_legato",
+    };
+    let operators: Vec<Value> = s
+        .operators
+        .iter()
+        .map(|op| {
+            json!({
+                "enabled": op.enabled,
+                "ratio": op.frequency_ratio,
+                "level": op.output_level,
+                "detune": op.detune,
+                "feedback": op.feedback,
+                "output_peak": op.output_peak,
+            })
+        })
+        .collect();
+    json!({
+        "preset_name": s.preset_name,
+        "algorithm": s.algorithm,
+        "custom_algorithm": s.custom_algorithm,
+        "active_voices": s.active_voices,
+        "polyphony": s.polyphony,
+        "stack_same_note": s.same_note_mode == SameNoteMode::Stack,
+        "retrigger_on_patch_change": s.patch_change_mode == PatchChangeMode::Retrigger,
+        "note_priority": note_priority,
+        "master_volume": s.master_volume,
+        "master_tune": s.master_tune,
+        "tuning": s.tuning_name,
+        "voice_mode": voice_mode,
+        "portamento_enable": s.portamento_enable,
+        "portamento_fingered": s.portamento_mode == PortamentoMode::Fingered,
+        "portamento_time": s.portamento_time,
+        "pitch_bend_range": s.pitch_bend_range,
+        "pitch_bend_smoothing": s.pitch_bend_smoothing_ms,
+        "transpose": s.transpose_semitones,
+        "tempo": s.tempo_bpm,
+        "master_pan": s.voice_pan.master_pan,
+        "unison_voices": s.unison_voices,
+        "unison_detune": s.unison_detune,
+        "unison_spread": s.unison_spread,
+        "operator_solo": s.operator_solo,
+        "effects_bypass": s.effects_bypass,
+        "extensions": s.extensions,
+        "output_dac": s.output_dac,
+        "output_dac_intensity": s.output_dac_intensity,
+        "noise_floor": s.noise_floor,
+        "noise_level_db": s.noise_level_db,
+        "hum_hz": s.hum_hz,
+        "gain_reduction_db": s.compressor.gain_reduction_db,
+        "output_peak": s.output_levels.peak,
+        "output_rms": s.output_levels.rms,
+        "clip": s.output_levels.clip,
+        "effect_order": s.effect_order.iter().map(|e| e.name()).collect::<Vec<_>>(),
+        "operators": operators,
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fm_synth::create_synth;
+
+    fn make_preset(name: &str, alg: u8) -> Dx7Preset {
+        Dx7Preset {
+            name: name.to_string(),
+            collection: "test".to_string(),
+            algorithm: alg,
+            ..Dx7Preset::default()
+        }
+    }
+
+    fn make_server() -> RpcServer {
+        let (engine, controller) = create_synth(44_100.0);
+        RpcServer::new(
+            engine,
+            controller,
+            vec![make_preset("FIRST", 1), make_preset("BRASS 1", 22)],
+        )
+    }
+
+    fn call(server: &mut RpcServer, line: &str) -> Value {
+        let response = server.handle_line(line).expect("request has an id");
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn note_on_then_render_produces_audio() {
+        let mut server = make_server();
+        call(
+            &mut server,
+            r#"{"jsonrpc":"2.0","id":1,"method":"note_on","params":{"note":60,"velocity":100}}"#,
+        );
+        let resp = call(
+            &mut server,
+            r#"{"jsonrpc":"2.0","id":2,"method":"render","params":{"frames":2048}}"#,
+        );
+        assert_eq!(resp["id"], 2);
+        assert_eq!(resp["result"]["frames"], 2048);
+        assert!(resp["result"]["peak"].as_f64().unwrap() > 0.01);
+        assert!(resp["result"].get("left").is_none());
+    }
+
+    #[test]
+    fn render_can_return_samples() {
+        let mut server = make_server();
+        let resp = call(
+            &mut server,
+            r#"{"id":1,"method":"render","params":{"frames":16,"include_audio":true}}"#,
+        );
+        assert_eq!(resp["result"]["left"].as_array().unwrap().len(), 16);
+        assert_eq!(resp["result"]["right"].as_array().unwrap().len(), 16);
+    }
+
+    #[test]
+    fn set_and_snapshot_round_trip() {
+        let mut server = make_server();
+        call(
+            &mut server,
+            r#"{"id":1,"method":"set","params":{"param":"algorithm","value":5}}"#,
+        );
+        call(
+            &mut server,
+            r#"{"id":2,"method":"set","params":{"param":"portamento_enable","value":true}}"#,
+        );
+        call(
+            &mut server,
+            r#"{"id":3,"method":"set","params":{"operator":2,"param":"level","value":42}}"#,
+        );
+        let snap = call(&mut server, r#"{"id":4,"method":"snapshot"}"#)["result"].clone();
+        assert_eq!(snap["algorithm"], 5);
+        assert_eq!(snap["portamento_enable"], true);
+        assert_eq!(snap["operators"][1]["level"], 42.0);
+    }
+
+    #[test]
+    fn load_preset_by_name() {
+        let mut server = make_server();
+        let resp = call(
+            &mut server,
+            r#"{"id":1,"method":"load_preset","params":{"name":"brass 1"}}"#,
+        );
+        assert_eq!(resp["result"]["index"], 1);
+        let snap = call(&mut server, r#"{"id":2,"method":"snapshot"}"#);
+        assert_eq!(snap["result"]["preset_name"], "BRASS 1");
+        assert_eq!(snap["result"]["algorithm"], 22);
+
+        let list = call(&mut server, r#"{"id":3,"method":"list_presets"}"#);
+        assert_eq!(list["result"], json!(["FIRST", "BRASS 1"]));
+    }
+
+    #[test]
+    fn errors_use_json_rpc_codes() {
+        let mut server = make_server();
+        assert_eq!(call(&mut server, "{not json")["error"]["code"], PARSE_ERROR);
+        assert_eq!(
+            call(&mut server, r#"{"id":1}"#)["error"]["code"],
+            INVALID_REQUEST
+        );
+        assert_eq!(
+            call(&mut server, r#"{"id":2,"method":"nope"}"#)["error"]["code"],
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            call(
+                &mut server,
+                r#"{"id":3,"method":"note_on","params":{"note":200}}"#
+            )["error"]["code"],
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            call(
+                &mut server,
+                r#"{"id":4,"method":"set","params":{"operator":7,"param":"level","value":1}}"#
+            )["error"]["code"],
+            INVALID_PARAMS
+        );
+    }
+
+    #[test]
+    fn notifications_get_no_response() {
+        let mut server = make_server();
+        assert!(server
+            .handle_line(r#"{"method":"note_on","params":{"note":60}}"#)
+            .is_none());
+    }
+
+    #[test]
+    fn serve_writes_one_line_per_request() {
+        let mut server = make_server();
+        let input = concat!(
+            r#"{"id":1,"method":"note_on","params":{"note":64}}"#,
+            "\n\n",
+            r#"{"method":"note_off","params":{"note":64}}"#,
+            "\n",
+            r#"{"id":2,"method":"render","params":{"frames":8}}"#,
+            "\n",
+        );
+        let mut out = Vec::new();
+        server.serve(input.as_bytes(), &mut out).unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&out).unwrap().lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains(r#""id":2"#));
+    }
+}
@@ -0,0 +1,686 @@
+//! MIDI file player for auditioning patches with real musical material.
+//!
+//! `Sequencer` is the transport: a score (from a Standard MIDI File or a
+//! note list, see [`crate::render`]) and a play head moved by wall-clock
+//! time. `SequencerPlayer` advances it on its own thread and sends the notes
+//! through the shared `SynthController`, the same way the MIDI input thread
+//! does, so playback keeps time whatever the GUI frame rate is.
+//!
+//! `PhraseRecorder` captures live playing into a score for the same player
+//! to loop. The player sends its notes as raw commands, so a phrase being
+//! recorded never picks up the playback.
+//!
+//! `AutomationRecorder` captures parameter moves (a slider ridden, the mod
+//! wheel) as timed commands; attached to a `Sequencer` they replay at the
+//! same point of the score on every pass. Replayed moves go out through
+//! `SynthController::replay`, so recording over a loop adds to it rather
+//! than re-recording the playback.
+
+use crate::command_queue::SynthCommand;
+use crate::fm_synth::SynthController;
+use crate::render::NoteEvent;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Playback speed range, as a multiple of the file's own tempo.
+pub const MIN_TEMPO_SCALE: f32 = 0.25;
+pub const MAX_TEMPO_SCALE: f32 = 4.0;
+
+/// How often the player thread checks for due events.
+const TICK: Duration = Duration::from_millis(1);
+
+/// One note-on or note-off of the score.
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    seconds: f32,
+    on: bool,
+    note: u8,
+    velocity: u8,
+}
+
+/// One recorded parameter move, `seconds` into the recording or the score.
+#[derive(Debug, Clone)]
+pub struct AutomationEvent {
+    pub seconds: f32,
+    pub command: SynthCommand,
+}
+
+/// Transport state, for display.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SequencerStatus {
+    pub playing: bool,
+    pub looping: bool,
+    pub tempo_scale: f32,
+    pub position: f32,
+    pub length: f32,
+    /// Automation events replayed with the score.
+    pub automation: usize,
+}
+
+pub struct Sequencer {
+    steps: Vec<Step>,
+    length: f32,
+    position: f32,
+    next: usize,
+    playing: bool,
+    looping: bool,
+    tempo_scale: f32,
+    /// Notes switched on and not yet off, released on stop and loop.
+    sounding: Vec<u8>,
+    /// Parameter moves in score time, sorted, and the next one due.
+    automation: Vec<AutomationEvent>,
+    next_automation: usize,
+}
+
+impl Sequencer {
+    pub fn new(events: &[NoteEvent]) -> Self {
+        let mut steps = Vec::with_capacity(events.len() * 2);
+        for e in events {
+            steps.push(Step {
+                seconds: e.start,
+                on: true,
+                note: e.note,
+                velocity: e.velocity,
+            });
+            steps.push(Step {
+                seconds: e.start + e.duration,
+                on: false,
+                note: e.note,
+                velocity: 0,
+            });
+        }
+        // Offs sort before ons at the same time so repeated notes retrigger.
+        steps.sort_by(|a, b| a.seconds.total_cmp(&b.seconds).then(a.on.cmp(&b.on)));
+        let length = steps.last().map_or(0.0, |s| s.seconds);
+        Self {
+            steps,
+            length,
+            position: 0.0,
+            next: 0,
+            playing: false,
+            looping: false,
+            tempo_scale: 1.0,
+            sounding: Vec::new(),
+            automation: Vec::new(),
+            next_automation: 0,
+        }
+    }
+
+    /// A recorded phrase that loops every `length` seconds (at least the
+    /// length of its notes).
+    pub fn looped_phrase(events: &[NoteEvent], length: f32) -> Self {
+        let mut sequencer = Self::new(events);
+        sequencer.length = sequencer.length.max(length);
+        sequencer.looping = true;
+        sequencer
+    }
+
+    pub fn status(&self) -> SequencerStatus {
+        SequencerStatus {
+            playing: self.playing,
+            looping: self.looping,
+            tempo_scale: self.tempo_scale,
+            position: self.position,
+            length: self.length,
+            automation: self.automation.len(),
+        }
+    }
+
+    /// Lay automation recorded while the play head was at `start` into the
+    /// score. Recording time runs at the tempo scale; on a loop it wraps,
+    /// so a take longer than one pass folds onto the same bars. Moves past
+    /// the end of a score that doesn't loop are dropped.
+    pub fn add_automation(&mut self, events: Vec<AutomationEvent>, start: f32) {
+        for mut event in events {
+            let seconds = start + event.seconds * self.tempo_scale;
+            event.seconds = if self.looping && self.length > 0.0 {
+                seconds.rem_euclid(self.length)
+            } else if seconds <= self.length {
+                seconds
+            } else {
+                continue;
+            };
+            self.automation.push(event);
+        }
+        // Stable, so moves at the same instant keep their order.
+        self.automation
+            .sort_by(|a, b| a.seconds.total_cmp(&b.seconds));
+        self.next_automation = self
+            .automation
+            .partition_point(|a| a.seconds <= self.position);
+    }
+
+    pub fn clear_automation(&mut self) {
+        self.automation.clear();
+        self.next_automation = 0;
+    }
+
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    pub fn set_tempo_scale(&mut self, scale: f32) {
+        self.tempo_scale = scale.clamp(MIN_TEMPO_SCALE, MAX_TEMPO_SCALE);
+    }
+
+    /// Play from the current position, or from the top after the end.
+    pub fn start(&mut self) {
+        if self.steps.is_empty() {
+            return;
+        }
+        if self.next >= self.steps.len() {
+            self.rewind();
+        }
+        self.playing = true;
+    }
+
+    /// Stop, release every sounding note and rewind.
+    pub fn stop(&mut self, controller: &mut SynthController) {
+        self.playing = false;
+        self.release_all(controller);
+        self.rewind();
+    }
+
+    /// Move the play head by `seconds` of wall-clock time and send the notes
+    /// that came due.
+    pub fn advance(&mut self, seconds: f32, controller: &mut SynthController) {
+        if !self.playing {
+            return;
+        }
+        self.position += seconds * self.tempo_scale;
+        loop {
+            while let Some(event) = self.automation.get(self.next_automation) {
+                if event.seconds > self.position {
+                    break;
+                }
+                controller.replay(event.command.clone());
+                self.next_automation += 1;
+            }
+            while let Some(&step) = self.steps.get(self.next) {
+                if step.seconds > self.position {
+                    return;
+                }
+                if step.on {
+                    controller.send(SynthCommand::NoteOn {
+                        note: step.note,
+                        velocity: step.velocity,
+                    });
+                    self.sounding.push(step.note);
+                } else {
+                    controller.send(SynthCommand::NoteOff { note: step.note });
+                    if let Some(i) = self.sounding.iter().position(|&n| n == step.note) {
+                        self.sounding.swap_remove(i);
+                    }
+                }
+                self.next += 1;
+            }
+
+            // Past the last note; wait for automation still due this pass.
+            if self.next_automation < self.automation.len() {
+                return;
+            }
+            self.release_all(controller);
+            if !self.looping || self.length <= 0.0 {
+                self.playing = false;
+                self.position = self.length;
+                return;
+            }
+            self.position -= self.length;
+            self.next = 0;
+            self.next_automation = 0;
+        }
+    }
+
+    fn rewind(&mut self) {
+        self.position = 0.0;
+        self.next = 0;
+        self.next_automation = 0;
+    }
+
+    fn release_all(&mut self, controller: &mut SynthController) {
+        for note in self.sounding.drain(..) {
+            controller.send(SynthCommand::NoteOff { note });
+        }
+    }
+}
+
+/// Notes played live, timed from when recording started.
+pub struct PhraseRecorder {
+    started: Instant,
+    /// Notes still held: (note, velocity, start).
+    open: Vec<(u8, u8, f32)>,
+    notes: Vec<NoteEvent>,
+}
+
+impl Default for PhraseRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhraseRecorder {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            open: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    fn now(&self) -> f32 {
+        self.started.elapsed().as_secs_f32()
+    }
+
+    pub fn note_on(&mut self, note: u8, velocity: u8) {
+        let at = self.now();
+        self.note_on_at(note, velocity, at);
+    }
+
+    pub fn note_off(&mut self, note: u8) {
+        let at = self.now();
+        self.note_off_at(note, at);
+    }
+
+    /// The phrase and its loop length, or None if nothing was played.
+    pub fn finish(self) -> Option<(Vec<NoteEvent>, f32)> {
+        let at = self.now();
+        self.finish_at(at)
+    }
+
+    fn note_on_at(&mut self, note: u8, velocity: u8, at: f32) {
+        self.open.push((note, velocity, at));
+    }
+
+    fn note_off_at(&mut self, note: u8, at: f32) {
+        if let Some(i) = self.open.iter().position(|&(n, _, _)| n == note) {
+            let (note, velocity, start) = self.open.remove(i);
+            self.notes.push(NoteEvent {
+                start,
+                duration: at - start,
+                note,
+                velocity,
+            });
+        }
+    }
+
+    /// Close held notes at `end`. The phrase starts at its first note (the
+    /// wait before it is dropped) and loops when recording stopped.
+    fn finish_at(mut self, end: f32) -> Option<(Vec<NoteEvent>, f32)> {
+        while let Some(&(note, _, _)) = self.open.first() {
+            self.note_off_at(note, end);
+        }
+        let origin = self.notes.iter().map(|n| n.start).min_by(f32::total_cmp)?;
+        for n in &mut self.notes {
+            n.start -= origin;
+        }
+        self.notes.sort_by(|a, b| a.start.total_cmp(&b.start));
+        Some((self.notes, end - origin))
+    }
+}
+
+/// Whether a command is a parameter move worth automating: the continuous
+/// controls a slider, knob or performance controller rides. Notes, the
+/// sustain pedal, mode switches and preset loads stay out.
+fn automatable(command: &SynthCommand) -> bool {
+    matches!(
+        command,
+        SynthCommand::SetOperatorParam { .. }
+            | SynthCommand::SetEnvelopeParam { .. }
+            | SynthCommand::SetPitchEgParam { .. }
+            | SynthCommand::SetLfoParam { .. }
+            | SynthCommand::SetLfo2Param { .. }
+            | SynthCommand::SetEffectParam { .. }
+            | SynthCommand::SetCompressorParam { .. }
+            | SynthCommand::SetMasterVolume(_)
+            | SynthCommand::SetMasterTune(_)
+            | SynthCommand::SetMasterPan(_)
+            | SynthCommand::SetPortamentoTime(_)
+            | SynthCommand::SetVoicePanRate(_)
+            | SynthCommand::SetVoicePanDepth(_)
+            | SynthCommand::SetVoicePanStereoPhase(_)
+            | SynthCommand::SetVoiceSpreadWidth(_)
+            | SynthCommand::SetUnisonDetune(_)
+            | SynthCommand::SetUnisonSpread(_)
+            | SynthCommand::SetSubBusLevel(_)
+            | SynthCommand::SetSubBusDelaySend(_)
+            | SynthCommand::SetSubBusReverbSend(_)
+            | SynthCommand::PitchBend(_)
+            | SynthCommand::ModWheel(_)
+            | SynthCommand::Aftertouch(_)
+            | SynthCommand::BreathController(_)
+            | SynthCommand::FootController(_)
+            | SynthCommand::Expression(_)
+    )
+}
+
+/// Parameter moves sent through the controller, timed from when recording
+/// started.
+pub struct AutomationRecorder {
+    started: Instant,
+    events: Vec<AutomationEvent>,
+}
+
+impl Default for AutomationRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AutomationRecorder {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Keep `command` if it is a parameter move; anything else is ignored.
+    pub fn record(&mut self, command: &SynthCommand) {
+        let at = self.started.elapsed().as_secs_f32();
+        self.record_at(command, at);
+    }
+
+    fn record_at(&mut self, command: &SynthCommand, at: f32) {
+        if automatable(command) {
+            self.events.push(AutomationEvent {
+                seconds: at,
+                command: command.clone(),
+            });
+        }
+    }
+
+    pub fn finish(self) -> Vec<AutomationEvent> {
+        self.events
+    }
+}
+
+/// Runs a `Sequencer` on a background thread. Dropping the player stops
+/// playback and joins the thread.
+pub struct SequencerPlayer {
+    sequencer: Arc<Mutex<Sequencer>>,
+    controller: Arc<Mutex<SynthController>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SequencerPlayer {
+    pub fn spawn(sequencer: Sequencer, controller: Arc<Mutex<SynthController>>) -> Self {
+        let sequencer = Arc::new(Mutex::new(sequencer));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let (seq, ctrl, stop) = (sequencer.clone(), controller.clone(), shutdown.clone());
+        let handle = std::thread::spawn(move || {
+            let mut last = Instant::now();
+            while !stop.load(Ordering::Relaxed) {
+                std::thread::sleep(TICK);
+                let now = Instant::now();
+                let elapsed = now.duration_since(last).as_secs_f32();
+                last = now;
+                // Lock order: sequencer, then controller (as in `with`).
+                if let Ok(mut seq) = seq.lock() {
+                    if seq.playing {
+                        if let Ok(mut ctrl) = ctrl.lock() {
+                            seq.advance(elapsed, &mut ctrl);
+                        }
+                    }
+                }
+            }
+        });
+        Self {
+            sequencer,
+            controller,
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+
+    /// Run `f` on the sequencer with the controller it plays through.
+    pub fn with<R>(&self, f: impl FnOnce(&mut Sequencer, &mut SynthController) -> R) -> Option<R> {
+        let mut seq = self.sequencer.lock().ok()?;
+        let mut ctrl = self.controller.lock().ok()?;
+        Some(f(&mut seq, &mut ctrl))
+    }
+
+    pub fn status(&self) -> Option<SequencerStatus> {
+        self.sequencer.lock().ok().map(|seq| seq.status())
+    }
+}
+
+impl Drop for SequencerPlayer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        self.with(|seq, ctrl| seq.stop(ctrl));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_queue::{create_command_queue, CommandReceiver, SynthCommand};
+    use crate::lock_free::ScopeBuffer;
+    use crate::state_snapshot::create_snapshot_channel;
+
+    fn note(start: f32, duration: f32, note: u8) -> NoteEvent {
+        note_v(start, duration, note, 100)
+    }
+
+    fn note_v(start: f32, duration: f32, note: u8, velocity: u8) -> NoteEvent {
+        NoteEvent {
+            start,
+            duration,
+            note,
+            velocity,
+        }
+    }
+
+    /// A controller whose commands land in the returned receiver.
+    fn recording_controller() -> (SynthController, CommandReceiver) {
+        let (tx, rx) = create_command_queue();
+        let (_snapshot_tx, snapshot_rx) = create_snapshot_channel();
+        let scope = Arc::new(ScopeBuffer::new(16));
+        (SynthController::new(tx, snapshot_rx, scope, 44_100.0), rx)
+    }
+
+    /// Notes sent since the last call: (on, note).
+    fn sent(rx: &mut CommandReceiver) -> Vec<(bool, u8)> {
+        std::iter::from_fn(|| rx.try_recv())
+            .filter_map(|cmd| match cmd {
+                SynthCommand::NoteOn { note, .. } => Some((true, note)),
+                SynthCommand::NoteOff { note } => Some((false, note)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn plays_in_time_and_stops_at_the_end() {
+        let (mut ctrl, mut rx) = recording_controller();
+        let mut seq = Sequencer::new(&[note(0.0, 0.5, 60), note(0.25, 0.5, 64)]);
+        assert_eq!(seq.status().length, 0.75);
+        seq.advance(0.1, &mut ctrl);
+        assert!(sent(&mut rx).is_empty(), "not started yet");
+
+        seq.start();
+        seq.advance(0.1, &mut ctrl);
+        assert_eq!(sent(&mut rx), [(true, 60)]);
+        seq.advance(0.2, &mut ctrl);
+        assert_eq!(sent(&mut rx), [(true, 64)]);
+        seq.advance(0.3, &mut ctrl);
+        assert_eq!(sent(&mut rx), [(false, 60)]);
+        seq.advance(1.0, &mut ctrl);
+        assert_eq!(sent(&mut rx), [(false, 64)]);
+        assert!(!seq.status().playing);
+    }
+
+    #[test]
+    fn loops_with_tempo_scale_and_stop_releases_notes() {
+        let (mut ctrl, mut rx) = recording_controller();
+        let mut seq = Sequencer::new(&[note(0.0, 1.0, 60), note(1.0, 1.0, 62)]);
+        seq.set_looping(true);
+        seq.set_tempo_scale(2.0);
+        seq.start();
+        // 1.25 s at double speed = 2.5 s of score: second pass, first note.
+        seq.advance(1.25, &mut ctrl);
+        assert_eq!(
+            sent(&mut rx),
+            [(true, 60), (false, 60), (true, 62), (false, 62), (true, 60)]
+        );
+        let status = seq.status();
+        assert!(status.playing);
+        assert!((status.position - 0.5).abs() < 1e-6);
+
+        seq.stop(&mut ctrl);
+        assert_eq!(sent(&mut rx), [(false, 60)]);
+        assert_eq!(seq.status().position, 0.0);
+
+        seq.set_tempo_scale(100.0);
+        assert_eq!(seq.status().tempo_scale, MAX_TEMPO_SCALE);
+    }
+
+    #[test]
+    fn recorded_phrase_starts_at_its_first_note_and_loops_at_stop() {
+        let mut rec = PhraseRecorder::new();
+        rec.note_on_at(60, 90, 1.0);
+        rec.note_on_at(64, 80, 1.5);
+        rec.note_off_at(60, 2.0);
+        rec.note_off_at(99, 2.1); // never pressed: ignored
+        let (notes, length) = rec.finish_at(3.0).unwrap();
+        assert_eq!(notes, [note_v(0.0, 1.0, 60, 90), note_v(0.5, 1.5, 64, 80)]);
+        assert_eq!(length, 2.0);
+        assert!(PhraseRecorder::new().finish_at(1.0).is_none());
+
+        let (mut ctrl, mut rx) = recording_controller();
+        let mut seq = Sequencer::looped_phrase(&notes, length);
+        assert_eq!(seq.status().length, 2.0);
+        seq.start();
+        seq.advance(2.25, &mut ctrl);
+        assert_eq!(
+            sent(&mut rx),
+            [(true, 60), (true, 64), (false, 60), (false, 64), (true, 60)]
+        );
+    }
+
+    fn volume(value: f32) -> SynthCommand {
+        SynthCommand::SetMasterVolume(value)
+    }
+
+    /// Master volume moves sent since the last call.
+    fn volumes(rx: &mut CommandReceiver) -> Vec<f32> {
+        std::iter::from_fn(|| rx.try_recv())
+            .filter_map(|cmd| match cmd {
+                SynthCommand::SetMasterVolume(v) => Some(v),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn automation_recorder_keeps_only_parameter_moves() {
+        let mut rec = AutomationRecorder::new();
+        rec.record_at(
+            &SynthCommand::NoteOn {
+                note: 60,
+                velocity: 100,
+            },
+            0.1,
+        );
+        rec.record_at(&volume(0.5), 0.2);
+        rec.record_at(&SynthCommand::SustainPedal(true), 0.3);
+        rec.record_at(&SynthCommand::ModWheel(0.7), 0.4);
+        let events = rec.finish();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].seconds, 0.2);
+        assert!(matches!(events[1].command, SynthCommand::ModWheel(_)));
+    }
+
+    #[test]
+    fn automation_replays_at_score_time_on_every_pass() {
+        let (mut ctrl, mut rx) = recording_controller();
+        let mut seq = Sequencer::looped_phrase(&[note(0.0, 0.5, 60)], 2.0);
+        // Recorded from 1.5 s in: the second move wraps to the top of the loop.
+        let take = vec![
+            AutomationEvent {
+                seconds: 0.0,
+                command: volume(0.25),
+            },
+            AutomationEvent {
+                seconds: 1.0,
+                command: volume(0.75),
+            },
+        ];
+        seq.add_automation(take, 1.5);
+        assert_eq!(seq.status().automation, 2);
+
+        seq.start();
+        seq.advance(1.0, &mut ctrl);
+        assert_eq!(volumes(&mut rx), [0.75]);
+        // The loop waits for the move at 1.5 s past the last note.
+        seq.advance(0.75, &mut ctrl);
+        assert_eq!(volumes(&mut rx), [0.25]);
+        seq.advance(0.75, &mut ctrl);
+        assert_eq!(volumes(&mut rx), [0.75]);
+
+        seq.clear_automation();
+        seq.advance(2.0, &mut ctrl);
+        assert!(volumes(&mut rx).is_empty());
+    }
+
+    #[test]
+    fn automation_past_the_end_of_an_unlooped_score_is_dropped() {
+        let mut seq = Sequencer::new(&[note(0.0, 1.0, 60)]);
+        seq.set_tempo_scale(2.0);
+        let take = [0.25, 0.75].map(|seconds| AutomationEvent {
+            seconds,
+            command: volume(seconds),
+        });
+        // 0.25 s at double speed lands at 0.5; 0.75 s would be at 1.5.
+        seq.add_automation(take.to_vec(), 0.0);
+        assert_eq!(seq.status().automation, 1);
+        assert_eq!(seq.automation[0].seconds, 0.5);
+    }
+
+    #[test]
+    fn controller_records_parameter_moves_but_not_their_replay() {
+        let (mut ctrl, mut rx) = recording_controller();
+        let mut seq = Sequencer::looped_phrase(&[note(0.0, 0.5, 60)], 1.0);
+        seq.add_automation(
+            vec![AutomationEvent {
+                seconds: 0.1,
+                command: volume(0.3),
+            }],
+            0.0,
+        );
+        ctrl.start_automation_recording();
+        assert!(ctrl.is_recording_automation());
+        seq.start();
+        seq.advance(0.2, &mut ctrl);
+        ctrl.set_master_volume(0.9);
+        let take = ctrl.finish_automation_recording();
+        assert_eq!(take.len(), 1);
+        assert!(matches!(take[0].command, SynthCommand::SetMasterVolume(v) if v == 0.9));
+        assert_eq!(volumes(&mut rx), [0.3, 0.9]);
+        assert!(!ctrl.is_recording_automation());
+        assert!(ctrl.finish_automation_recording().is_empty());
+    }
+
+    #[test]
+    fn controller_records_live_notes_but_not_playback() {
+        let (mut ctrl, _rx) = recording_controller();
+        assert!(ctrl.finish_phrase_recording().is_none());
+        ctrl.start_phrase_recording();
+        assert!(ctrl.is_recording_phrase());
+        ctrl.note_on(60, 100);
+        let mut seq = Sequencer::new(&[note(0.0, 0.1, 72)]);
+        seq.start();
+        seq.advance(1.0, &mut ctrl);
+        ctrl.note_off(60);
+        let (notes, _) = ctrl.finish_phrase_recording().unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].note, 60);
+        assert!(!ctrl.is_recording_phrase());
+    }
+}
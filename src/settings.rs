@@ -0,0 +1,204 @@
+//! Application settings restored on launch: MIDI input port, master volume,
+//! keyboard octave, theme, window size, whether to play the startup melody,
+//! the program-change map and the MIDI-learn CC bindings.
+//!
+//! Saved as JSON in the config directory when the window closes. The audio
+//! device keeps its own file (`audio.json`, written by the AUDIO page) since
+//! it is read before the window exists. Missing fields take their defaults,
+//! so files written by older versions still load.
+
+use crate::keybindings::config_dir;
+use crate::midi_learn::CcBindings;
+use crate::program_map::ProgramMap;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "settings.json";
+
+/// Lowest and highest octave of the computer keyboard.
+pub const OCTAVE_RANGE: std::ops::RangeInclusive<i32> = 0..=7;
+
+/// Smallest window that fits the panels, in points.
+pub const MIN_WINDOW_SIZE: [f32; 2] = [960.0, 720.0];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 2] = [Theme::Light, Theme::Dark];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+        }
+    }
+
+    pub fn visuals(self) -> egui::Visuals {
+        match self {
+            Theme::Light => egui::Visuals::light(),
+            Theme::Dark => egui::Visuals::dark(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// MIDI input port by name; None or a missing port = the first one.
+    pub midi_port: Option<String>,
+    pub master_volume: f32,
+    pub octave: i32,
+    pub theme: Theme,
+    /// Inner window size in points.
+    pub window_size: [f32; 2],
+    /// Play the test arpeggio when the app starts.
+    pub startup_melody: bool,
+    /// Presets MIDI Program Change numbers load.
+    pub program_map: ProgramMap,
+    /// Hardware CCs bound to parameters by MIDI learn.
+    pub cc_bindings: CcBindings,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            midi_port: None,
+            master_volume: 0.7,
+            octave: 4,
+            theme: Theme::default(),
+            window_size: MIN_WINDOW_SIZE,
+            startup_melody: false,
+            program_map: ProgramMap::default(),
+            cc_bindings: CcBindings::default(),
+        }
+    }
+}
+
+impl AppSettings {
+    /// Default location of the settings file, or None when no config
+    /// directory can be determined.
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(FILE_NAME))
+    }
+
+    /// Load settings from `path`, falling back to the defaults when the file
+    /// is missing or unreadable. Out-of-range values are clamped.
+    pub fn load_or_default(path: &Path) -> Self {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str::<Self>(&content)
+            .map(Self::clamped)
+            .unwrap_or_else(|e| {
+                log::warn!("Invalid settings file {}: {}", path.display(), e);
+                Self::default()
+            })
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    fn clamped(mut self) -> Self {
+        self.master_volume = if self.master_volume.is_finite() {
+            self.master_volume.clamp(0.0, 1.0)
+        } else {
+            Self::default().master_volume
+        };
+        self.octave = self
+            .octave
+            .clamp(*OCTAVE_RANGE.start(), *OCTAVE_RANGE.end());
+        for (size, min) in self.window_size.iter_mut().zip(MIN_WINDOW_SIZE) {
+            *size = if size.is_finite() { size.max(min) } else { min };
+        }
+        self.program_map = std::mem::take(&mut self.program_map).clamped();
+        self.cc_bindings = std::mem::take(&mut self.cc_bindings).clamped();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_entry::{DataEntryParam, OperatorField};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("synth-fm-rs-settings-{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_path("round_trip.json");
+        let mut program_map = ProgramMap::default();
+        program_map.assign(
+            7,
+            &crate::presets::Dx7Preset {
+                name: "MY PAD".to_string(),
+                collection: "user".to_string(),
+                ..Default::default()
+            },
+        );
+        let mut cc_bindings = CcBindings::default();
+        cc_bindings.bind(74, DataEntryParam::Operator(1, OperatorField::Level));
+        let settings = AppSettings {
+            midi_port: Some("Keystation 49".to_string()),
+            master_volume: 0.5,
+            octave: 3,
+            theme: Theme::Dark,
+            window_size: [1280.0, 800.0],
+            startup_melody: true,
+            program_map,
+            cc_bindings,
+        };
+        settings.save(&path).expect("save");
+        assert_eq!(AppSettings::load_or_default(&path), settings);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn missing_fields_and_files_take_the_defaults() {
+        assert_eq!(
+            AppSettings::load_or_default(&temp_path("does_not_exist.json")),
+            AppSettings::default()
+        );
+        let path = temp_path("partial.json");
+        std::fs::create_dir_all(path.parent().unwrap()).expect("mkdir");
+        std::fs::write(&path, r#"{"theme": "dark"}"#).expect("write");
+        let loaded = AppSettings::load_or_default(&path);
+        assert_eq!(loaded.theme, Theme::Dark);
+        assert_eq!(loaded.octave, 4);
+        assert!(!loaded.startup_melody);
+        std::fs::write(&path, "not json").expect("write");
+        assert_eq!(AppSettings::load_or_default(&path), AppSettings::default());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn out_of_range_values_are_clamped_on_load() {
+        let path = temp_path("clamped.json");
+        std::fs::create_dir_all(path.parent().unwrap()).expect("mkdir");
+        std::fs::write(
+            &path,
+            r#"{"master_volume": 3.0, "octave": -2, "window_size": [10.0, 2000.0]}"#,
+        )
+        .expect("write");
+        let loaded = AppSettings::load_or_default(&path);
+        assert_eq!(loaded.master_volume, 1.0);
+        assert_eq!(loaded.octave, 0);
+        assert_eq!(loaded.window_size, [MIN_WINDOW_SIZE[0], 2000.0]);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,146 @@
+//! Short linear ramps for parameters edited while notes sound.
+//!
+//! Writing a new level, feedback amount or volume straight into the signal
+//! path steps the waveform, which is heard as a click, or as zipper noise
+//! when a slider sends a run of small steps. A `Smoothed` value walks to each
+//! new target over `PARAM_SMOOTHING_MS` instead.
+
+/// Ramp length for every smoothed parameter, in ms.
+pub const PARAM_SMOOTHING_MS: f32 = 10.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Smoothed {
+    current: f32,
+    target: f32,
+    step: f32,
+    remaining: u32,
+    ramp_samples: u32,
+}
+
+impl Smoothed {
+    pub fn new(value: f32, sample_rate: f32) -> Self {
+        Self {
+            current: value,
+            target: value,
+            step: 0.0,
+            remaining: 0,
+            ramp_samples: Self::ramp_samples(sample_rate),
+        }
+    }
+
+    fn ramp_samples(sample_rate: f32) -> u32 {
+        (sample_rate * PARAM_SMOOTHING_MS / 1000.0).round().max(1.0) as u32
+    }
+
+    /// New ramp length for the next target; a ramp in progress keeps its step.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.ramp_samples = Self::ramp_samples(sample_rate);
+    }
+
+    /// Start ramping towards `target`. Cheap when it is already the target,
+    /// so callers can pass the raw parameter every sample.
+    #[inline]
+    pub fn set_target(&mut self, target: f32) {
+        if target == self.target {
+            return;
+        }
+        self.target = target;
+        self.remaining = self.ramp_samples;
+        self.step = (target - self.current) / self.ramp_samples as f32;
+    }
+
+    /// Jump to `value` with no ramp (note-on, where nothing is sounding yet).
+    pub fn reset(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.remaining = 0;
+    }
+
+    /// Advance one sample and return the value to use for it.
+    #[inline]
+    pub fn advance(&mut self) -> f32 {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            self.current = if self.remaining == 0 {
+                self.target
+            } else {
+                self.current + self.step
+            };
+        }
+        self.current
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn is_ramping(&self) -> bool {
+        self.remaining > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 44_100.0;
+    const RAMP: usize = 441; // PARAM_SMOOTHING_MS at SAMPLE_RATE
+
+    #[test]
+    fn holds_its_value_until_given_a_new_target() {
+        let mut value = Smoothed::new(0.5, SAMPLE_RATE);
+        for _ in 0..16 {
+            assert_eq!(value.advance(), 0.5);
+        }
+        assert!(!value.is_ramping());
+    }
+
+    #[test]
+    fn ramps_linearly_and_lands_exactly_on_the_target() {
+        let mut value = Smoothed::new(0.0, SAMPLE_RATE);
+        value.set_target(1.0);
+        let ramp: Vec<f32> = (0..RAMP).map(|_| value.advance()).collect();
+        assert!(ramp.windows(2).all(|w| w[1] > w[0]));
+        assert!((ramp[RAMP / 2 - 1] - 0.5).abs() < 0.01);
+        assert_eq!(ramp[RAMP - 1], 1.0);
+        assert!(!value.is_ramping());
+    }
+
+    #[test]
+    fn retargeting_mid_ramp_continues_from_the_current_value() {
+        let mut value = Smoothed::new(0.0, SAMPLE_RATE);
+        value.set_target(1.0);
+        for _ in 0..RAMP / 2 {
+            value.advance();
+        }
+        let midway = value.current();
+        value.set_target(0.0);
+        let next = value.advance();
+        assert!(
+            next < midway && midway - next < 0.01,
+            "no jump: {midway} -> {next}"
+        );
+        for _ in 0..RAMP {
+            value.advance();
+        }
+        assert_eq!(value.current(), 0.0);
+    }
+
+    #[test]
+    fn reset_jumps_without_a_ramp() {
+        let mut value = Smoothed::new(0.0, SAMPLE_RATE);
+        value.set_target(1.0);
+        value.reset(0.25);
+        assert!(!value.is_ramping());
+        assert_eq!(value.advance(), 0.25);
+    }
+
+    #[test]
+    fn ramp_length_follows_the_sample_rate() {
+        let mut value = Smoothed::new(0.0, SAMPLE_RATE);
+        value.set_sample_rate(SAMPLE_RATE * 2.0);
+        value.set_target(1.0);
+        let samples = std::iter::from_fn(|| value.is_ramping().then(|| value.advance())).count();
+        assert_eq!(samples, RAMP * 2);
+    }
+}
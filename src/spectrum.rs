@@ -0,0 +1,136 @@
+//! Spectrum analysis for the SPECTRUM page.
+//!
+//! Runs on the GUI thread over samples copied out of the engine's scope ring,
+//! so the audio callback never pays for (or allocates in) the FFT.
+
+use std::f32::consts::PI;
+
+/// Floor of the dB scale; bins below it are clamped so silence draws flat.
+pub const SPECTRUM_FLOOR_DB: f32 = -96.0;
+
+/// Magnitude spectrum of `samples` in dBFS, one value per bin `0..=n/2`.
+///
+/// A Hann window is applied first. Magnitudes are normalised so that a
+/// full-scale sine landing exactly on a bin reads 0 dB. `samples.len()` must
+/// be a power of two.
+pub fn magnitude_db(samples: &[f32]) -> Vec<f32> {
+    let n = samples.len();
+    assert!(n.is_power_of_two(), "FFT size must be a power of two");
+
+    let mut re: Vec<f32> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| s * hann(i, n))
+        .collect();
+    let mut im = vec![0.0; n];
+    fft_in_place(&mut re, &mut im);
+
+    // Hann coherent gain is 0.5; a sine of amplitude A peaks at A * n / 4.
+    let scale = 4.0 / n as f32;
+    (0..=n / 2)
+        .map(|k| {
+            let mag = (re[k] * re[k] + im[k] * im[k]).sqrt() * scale;
+            (20.0 * mag.max(1e-12).log10()).max(SPECTRUM_FLOOR_DB)
+        })
+        .collect()
+}
+
+/// Centre frequency of bin `k` for an FFT of size `n`.
+pub fn bin_frequency(k: usize, n: usize, sample_rate: f32) -> f32 {
+    k as f32 * sample_rate / n as f32
+}
+
+fn hann(i: usize, n: usize) -> f32 {
+    0.5 - 0.5 * (2.0 * PI * i as f32 / n as f32).cos()
+}
+
+/// Iterative radix-2 Cooley-Tukey FFT.
+fn fft_in_place(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    if n <= 1 {
+        return;
+    }
+
+    // Bit-reversal permutation.
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if j > i {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peak_bin(db: &[f32]) -> usize {
+        db.iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(k, _)| k)
+            .unwrap()
+    }
+
+    #[test]
+    fn sine_on_bin_peaks_at_that_bin_near_0_db() {
+        let n = 1024;
+        let samples: Vec<f32> = (0..n)
+            .map(|i| (2.0 * PI * 64.0 * i as f32 / n as f32).sin())
+            .collect();
+        let db = magnitude_db(&samples);
+        assert_eq!(db.len(), n / 2 + 1);
+        assert_eq!(peak_bin(&db), 64);
+        assert!(db[64].abs() < 0.5, "peak {} dB", db[64]);
+        // Hann sidelobes are well down a few bins away.
+        assert!(db[70] < -60.0);
+    }
+
+    #[test]
+    fn two_partials_show_up_as_two_peaks() {
+        let n = 2048;
+        let samples: Vec<f32> = (0..n)
+            .map(|i| {
+                let t = i as f32 / n as f32;
+                0.5 * (2.0 * PI * 100.0 * t).sin() + 0.25 * (2.0 * PI * 300.0 * t).sin()
+            })
+            .collect();
+        let db = magnitude_db(&samples);
+        assert!((db[100] - 20.0 * 0.5_f32.log10()).abs() < 0.5);
+        assert!((db[300] - 20.0 * 0.25_f32.log10()).abs() < 0.5);
+        assert!(db[200] < -60.0);
+    }
+
+    #[test]
+    fn silence_sits_on_the_floor() {
+        let db = magnitude_db(&[0.0; 256]);
+        assert!(db.iter().all(|&v| v == SPECTRUM_FLOOR_DB));
+    }
+
+    #[test]
+    fn bin_frequency_scales_with_sample_rate() {
+        assert_eq!(bin_frequency(0, 4096, 48_000.0), 0.0);
+        assert_eq!(bin_frequency(2048, 4096, 48_000.0), 24_000.0);
+    }
+}
@@ -1,6 +1,20 @@
+use crate::algorithm_matrix::AlgorithmMatrix;
+use crate::compressor::MasterCompressor;
+use crate::debug_dump::EngineDump;
+use crate::effects::{
+    EffectSlot, EqBand, Equalizer, ResonantFilter, ReverbType, VoiceSpread, DELAY_HIGH_CUT_MAX_HZ,
+    DELAY_LOW_CUT_MIN_HZ, EFFECT_SLOTS, PHASER_VINTAGE_EP,
+};
+use crate::level_meter::MeterLevels;
 use crate::lfo::LFOWaveform;
 use crate::lock_free::TripleBuffer;
+use crate::mod_matrix::ModMatrix;
 use crate::operator::KeyScaleCurve;
+use crate::optimization::OperatorWaveform;
+use crate::performance::Performance;
+use crate::tempo::{NoteDivision, DEFAULT_BPM};
+use crate::vintage::DEFAULT_NOISE_DB;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 /// Snapshot of a single operator's state for GUI display.
@@ -9,6 +23,9 @@ use std::sync::Arc;
 pub struct OperatorSnapshot {
     pub enabled: bool,
     pub frequency_ratio: f32,
+    pub coarse: u8,
+    pub fine: u8,
+    pub extended_ratio: bool,
     pub output_level: f32,
     pub detune: f32,
     pub feedback: f32,
@@ -20,7 +37,13 @@ pub struct OperatorSnapshot {
     pub key_scale_left_depth: f32,
     pub key_scale_right_depth: f32,
     pub am_sensitivity: u8,
+    pub pm_sensitivity: Option<u8>, // None = follows the voice PMS
+    pub lfo2: bool,
     pub oscillator_key_sync: bool,
+    pub sub_bus: bool,
+    pub expression: bool,
+    pub waveform: OperatorWaveform,
+    pub phase_offset: f32, // degrees, start phase of key-synced notes
     pub fixed_frequency: bool,
     pub fixed_freq_hz: f32,
     // Envelope parameters
@@ -34,6 +57,16 @@ pub struct OperatorSnapshot {
     pub level4: f32,
     /// Live envelope output (0..=1), max across active voices.
     pub live_level: f32,
+    /// Live envelope output (0..=1) of the most recently played voice;
+    /// 0 when no voice is sounding.
+    pub recent_level: f32,
+    /// Oscillator phase (degrees) in the most recently played voice; 0 when
+    /// no voice is sounding. Sampled at snapshot time, so only the relative
+    /// phase of operators running at the same frequency is steady.
+    pub recent_phase: f32,
+    /// Peak output of the operator across the sounding voices (0..=1,
+    /// before voice and master gains), falling like the output meter.
+    pub output_peak: f32,
 }
 
 impl Default for OperatorSnapshot {
@@ -41,6 +74,9 @@ impl Default for OperatorSnapshot {
         Self {
             enabled: true,
             frequency_ratio: 1.0,
+            coarse: 1,
+            fine: 0,
+            extended_ratio: false,
             output_level: 99.0,
             detune: 0.0,
             feedback: 0.0,
@@ -52,7 +88,13 @@ impl Default for OperatorSnapshot {
             key_scale_left_depth: 0.0,
             key_scale_right_depth: 0.0,
             am_sensitivity: 0,
+            pm_sensitivity: None,
+            lfo2: false,
             oscillator_key_sync: true,
+            sub_bus: false,
+            expression: false,
+            waveform: OperatorWaveform::Sine,
+            phase_offset: 0.0,
             fixed_frequency: false,
             fixed_freq_hz: 440.0,
             rate1: 99.0,
@@ -64,6 +106,81 @@ impl Default for OperatorSnapshot {
             level3: 50.0,
             level4: 0.0,
             live_level: 0.0,
+            recent_level: 0.0,
+            recent_phase: 0.0,
+            output_peak: 0.0,
+        }
+    }
+}
+
+/// Snapshot of EQ effect state
+#[derive(Debug, Clone, Copy)]
+pub struct EqSnapshot {
+    pub enabled: bool,
+    pub low: EqBand,
+    pub mid: EqBand,
+    pub high: EqBand,
+}
+
+impl Default for EqSnapshot {
+    fn default() -> Self {
+        let eq = Equalizer::new(44_100.0);
+        Self {
+            enabled: false,
+            low: eq.low(),
+            mid: eq.mid(),
+            high: eq.high(),
+        }
+    }
+}
+
+/// Snapshot of resonant filter (auto-wah) state
+#[derive(Debug, Clone, Copy)]
+pub struct FilterSnapshot {
+    pub enabled: bool,
+    pub cutoff_hz: f32,
+    pub resonance: f32,
+    pub env_amount: f32,
+    pub lfo_rate: f32,
+    pub lfo_depth: f32,
+    pub mix: f32,
+}
+
+impl Default for FilterSnapshot {
+    fn default() -> Self {
+        let filter = ResonantFilter::new(44_100.0);
+        Self {
+            enabled: filter.enabled,
+            cutoff_hz: filter.cutoff_hz,
+            resonance: filter.resonance,
+            env_amount: filter.env_amount,
+            lfo_rate: filter.lfo_rate,
+            lfo_depth: filter.lfo_depth,
+            mix: filter.mix,
+        }
+    }
+}
+
+/// Snapshot of phaser effect state
+#[derive(Debug, Clone, Copy)]
+pub struct PhaserSnapshot {
+    pub enabled: bool,
+    pub stages: u8,
+    pub rate: f32,
+    pub depth: f32,
+    pub feedback: f32,
+    pub mix: f32,
+}
+
+impl Default for PhaserSnapshot {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stages: PHASER_VINTAGE_EP.stages as u8,
+            rate: PHASER_VINTAGE_EP.rate,
+            depth: PHASER_VINTAGE_EP.depth,
+            feedback: PHASER_VINTAGE_EP.feedback,
+            mix: PHASER_VINTAGE_EP.mix,
         }
     }
 }
@@ -98,6 +215,10 @@ pub struct DelaySnapshot {
     pub feedback: f32,
     pub mix: f32,
     pub ping_pong: bool,
+    pub sync: Option<NoteDivision>, // tempo sync division, None = `time_ms`
+    pub effective_time_ms: f32,     // synced or free time actually in use
+    pub high_cut_hz: f32,
+    pub low_cut_hz: f32,
 }
 
 impl Default for DelaySnapshot {
@@ -108,6 +229,10 @@ impl Default for DelaySnapshot {
             feedback: 0.4,
             mix: 0.3,
             ping_pong: true,
+            sync: None,
+            effective_time_ms: 300.0,
+            high_cut_hz: DELAY_HIGH_CUT_MAX_HZ,
+            low_cut_hz: DELAY_LOW_CUT_MIN_HZ,
         }
     }
 }
@@ -138,6 +263,8 @@ pub struct ReverbSnapshot {
     pub damping: f32,
     pub mix: f32,
     pub width: f32,
+    pub pre_delay_ms: f32,
+    pub reverb_type: ReverbType,
 }
 
 impl Default for ReverbSnapshot {
@@ -148,6 +275,107 @@ impl Default for ReverbSnapshot {
             damping: 0.5,
             mix: 0.25,
             width: 1.0,
+            pre_delay_ms: 0.0,
+            reverb_type: ReverbType::Hall,
+        }
+    }
+}
+
+/// Snapshot of the secondary sub output bus
+#[derive(Debug, Clone, Copy)]
+pub struct SubBusSnapshot {
+    pub level: f32,
+    pub delay_send: f32,
+    pub reverb_send: f32,
+}
+
+impl Default for SubBusSnapshot {
+    fn default() -> Self {
+        Self {
+            level: 1.0,
+            delay_send: 0.0,
+            reverb_send: 0.0,
+        }
+    }
+}
+
+/// Snapshot of the second LFO
+#[derive(Debug, Clone, Copy)]
+pub struct Lfo2Snapshot {
+    pub enabled: bool,
+    pub rate: f32,
+    pub delay: f32,
+    pub pitch_depth: f32,
+    pub amp_depth: f32,
+    pub waveform: LFOWaveform,
+    pub key_sync: bool,
+    pub sync: Option<NoteDivision>,
+    pub frequency_hz: f32,
+}
+
+impl Default for Lfo2Snapshot {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rate: 50.0,
+            delay: 0.0,
+            pitch_depth: 25.0,
+            amp_depth: 15.0,
+            waveform: LFOWaveform::Triangle,
+            key_sync: false,
+            sync: None,
+            frequency_hz: 0.0,
+        }
+    }
+}
+
+/// Snapshot of the master compressor / limiter, with its live gain reduction
+#[derive(Debug, Clone, Copy)]
+pub struct CompressorSnapshot {
+    pub enabled: bool,
+    pub threshold_db: f32,
+    pub ratio: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    pub makeup_db: f32,
+    pub gain_reduction_db: f32, // 0 = not limiting
+}
+
+impl Default for CompressorSnapshot {
+    fn default() -> Self {
+        let c = MasterCompressor::new(44_100.0);
+        Self {
+            enabled: c.enabled,
+            threshold_db: c.threshold_db(),
+            ratio: c.ratio(),
+            attack_ms: c.attack_ms(),
+            release_ms: c.release_ms(),
+            makeup_db: c.makeup_db(),
+            gain_reduction_db: 0.0,
+        }
+    }
+}
+
+/// Snapshot of the per-voice tremolo / auto-pan settings
+#[derive(Debug, Clone, Copy)]
+pub struct VoicePanSnapshot {
+    pub rate: f32,
+    pub depth: f32,
+    pub stereo_phase: f32,
+    pub spread: VoiceSpread,
+    pub spread_width: f32,
+    pub master_pan: f32,
+}
+
+impl Default for VoicePanSnapshot {
+    fn default() -> Self {
+        Self {
+            rate: 4.0,
+            depth: 0.0,
+            stereo_phase: 1.0,
+            spread: VoiceSpread::Center,
+            spread_width: 1.0,
+            master_pan: 0.0,
         }
     }
 }
@@ -162,6 +390,62 @@ pub enum VoiceMode {
     MonoLegato,
 }
 
+/// Which held key the mono modes play: the newest, the lowest or the highest.
+/// The other held keys wait in a stack and take over on release.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NotePriority {
+    #[default]
+    Last,
+    Low,
+    High,
+}
+
+/// Controller the per-operator expression input follows: the breath
+/// controller (CC2) or MIDI expression (CC11).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ExpressionSource {
+    #[default]
+    Breath,
+    Expression,
+}
+
+/// What Poly mode does when a key that is already sounding is struck again:
+/// restart the same voice, or stack a fresh voice on top of the old one.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SameNoteMode {
+    #[default]
+    Retrigger,
+    Stack,
+}
+
+/// When portamento glides: on every note (full time), or only when the new
+/// key is played while another is still held (fingered), as on the DX7II.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PortamentoMode {
+    #[default]
+    FullTime,
+    Fingered,
+}
+
+/// What held notes do when a preset loads or the algorithm changes:
+/// morph into the new patch at the steal-fade rate, or restart on it with
+/// fresh envelopes while the old sound fades out underneath.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PatchChangeMode {
+    #[default]
+    Crossfade,
+    Retrigger,
+}
+
+/// Health of the audio callback, reported by the audio backend: buffers that
+/// ran dry or missed their deadline since the app started, and the smoothed
+/// render time as a fraction of the buffer time (1.0 = no headroom).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AudioStats {
+    pub underruns: u32,
+    pub cpu_load: f32,
+}
+
 /// Pitch envelope state mirrored to GUI for display.
 #[derive(Debug, Clone, Copy)]
 pub struct PitchEgSnapshot {
@@ -201,20 +485,55 @@ pub struct SynthSnapshot {
     // Voice info
     pub preset_name: String,
     pub algorithm: u8,
+    /// Voices run `algorithm_matrix` instead of `algorithm`.
+    pub custom_algorithm: bool,
+    pub algorithm_matrix: AlgorithmMatrix,
     pub active_voices: u8,
+    /// Keys down from any source (MIDI, GUI, player), bit = MIDI note.
+    pub held_keys: u128,
+    /// Key of the latest note-on and its pitch in Hz after transpose and
+    /// tuning, which a ratio-1 operator plays.
+    pub last_note: Option<u8>,
+    pub last_note_hz: Option<f32>,
 
     // Global parameters
     pub master_volume: f32,
     pub master_tune: f32,
+    /// Name of the per-key tuning table ("12-TET" when none is loaded).
+    pub tuning_name: String,
     pub voice_mode: VoiceMode,
+    pub same_note_mode: SameNoteMode,
+    pub patch_change_mode: PatchChangeMode,
+    pub note_priority: NotePriority,
+    pub mod_matrix: ModMatrix, // assignable controller routes
     pub portamento_enable: bool,
+    pub portamento_mode: PortamentoMode,
     pub portamento_time: f32,
     pub portamento_glissando: bool, // portamento step ON/OFF
+    pub osc_key_sync: bool,         // voice-level OSC KEY SYNC (any operator synced)
     pub pitch_bend_range: f32,
-    pub transpose_semitones: i8, // -24..+24 semitones, 0 means C3 (DX7 reference)
-    pub pitch_mod_sensitivity: u8, // 0-7 PMS (LFO pitch depth scaler)
-    pub eg_bias_sensitivity: u8, // 0-7 EG Bias routing from Mod Wheel
-    pub pitch_bias_sensitivity: u8, // 0-7 Pitch Bias routing from Mod Wheel
+    pub pitch_bend_smoothing_ms: f32, // glide time of the bend towards the wheel
+    pub transpose_semitones: i8,      // -24..+24 semitones, 0 means C3 (DX7 reference)
+    pub pitch_mod_sensitivity: u8,    // 0-7 PMS (LFO pitch depth scaler)
+    pub eg_bias_sensitivity: u8,      // 0-7 EG Bias routing from Mod Wheel
+    pub pitch_bias_sensitivity: u8,   // 0-7 Pitch Bias routing from Mod Wheel
+    pub steal_fade_ms: f32,           // voice-steal crossfade length
+    pub attack_fade_ms: f32,          // note-on anti-click fade-in length
+    pub voice_watchdog: bool,         // stuck-note watchdog on
+    pub voice_timeout: f32,           // seconds before any voice is released, 0 = never
+    pub watchdog_releases: u32,       // voices the watchdog released, both parts
+    pub sub_bus: SubBusSnapshot,      // level and sends of the operator sub bus
+    pub voice_pan: VoicePanSnapshot,  // per-voice tremolo / auto-pan
+    pub vintage_mode: bool,           // era-accurate DAC / EG / LFO / sine quirks
+    pub output_dac: bool,             // DX7 DAC + hold + low-pass on the master bus
+    pub output_dac_intensity: f32,    // its share of the mix, 0..1
+    pub noise_floor: bool,            // hiss + mains hum ahead of the effects
+    pub noise_level_db: f32,          // its level, dBFS
+    pub hum_hz: f32,                  // mains frequency, 50 or 60
+    pub unison_voices: u8,            // operator stacks per note, 1 = unison off
+    pub unison_detune: f32,           // cents between the centre and outer stacks
+    pub unison_spread: f32,           // stereo width of the stacks, 0..1
+    pub polyphony: u8,                // playable voices in Poly mode
 
     // Real-time controllers
     pub pitch_bend: f32,
@@ -224,6 +543,8 @@ pub struct SynthSnapshot {
     pub breath: f32,
     pub foot: f32,
     pub expression: f32,
+    /// Controller that scales operators with `expression` set.
+    pub expression_source: ExpressionSource,
 
     // Aftertouch routing sensitivities (0-7 each)
     pub aftertouch_pitch_sens: u8,
@@ -252,15 +573,41 @@ pub struct SynthSnapshot {
     pub lfo_key_sync: bool,
     pub lfo_frequency_hz: f32,
     pub lfo_delay_seconds: f32,
+    pub lfo_sync: Option<NoteDivision>, // tempo sync division, None = free rate
+    pub tempo_bpm: f32,
+    pub lfo2: Lfo2Snapshot,
 
     // Pitch EG state
     pub pitch_eg: PitchEgSnapshot,
 
     // Effects state (detailed for effects panel)
+    pub eq: EqSnapshot,
+    pub filter: FilterSnapshot,
+    pub phaser: PhaserSnapshot,
     pub chorus: ChorusSnapshot,
     pub auto_pan: AutoPanSnapshot,
     pub delay: DelaySnapshot,
     pub reverb: ReverbSnapshot,
+    pub effect_order: [EffectSlot; EFFECT_SLOTS],
+    pub effects_bypass: bool,
+    /// Effects beyond the DX7's signal path (the filter) may run.
+    pub extensions: bool,
+    /// Output trim per effect in dB, by `EffectSlot::index`.
+    pub effect_gains_db: [f32; EFFECT_SLOTS],
+    pub wet_solo: Option<EffectSlot>,
+    pub compressor: CompressorSnapshot,
+    pub output_levels: MeterLevels, // peak / RMS / clip at the output
+    /// Soloed operators, bit `i` = OP `i + 1`; 0 = normal output.
+    pub operator_solo: u8,
+    pub audio_stats: AudioStats,
+
+    // Performance (dual / split)
+    pub performance: Performance,
+    /// Voice loaded into part B.
+    pub part_b_name: String,
+
+    /// Engine state report, in the snapshot after the GUI asks for one.
+    pub debug_dump: Option<EngineDump>,
 
     // Operator states (detailed for editor)
     pub operators: [OperatorSnapshot; 6],
@@ -271,19 +618,49 @@ impl Default for SynthSnapshot {
         Self {
             preset_name: "Init Voice".to_string(),
             algorithm: 1,
+            custom_algorithm: false,
+            algorithm_matrix: AlgorithmMatrix::default(),
             active_voices: 0,
+            held_keys: 0,
+            last_note: None,
+            last_note_hz: None,
 
             master_volume: 0.7,
             master_tune: 0.0,
+            tuning_name: crate::tuning::EQUAL_TEMPERAMENT.to_string(),
             voice_mode: VoiceMode::Poly,
+            same_note_mode: SameNoteMode::Retrigger,
+            patch_change_mode: PatchChangeMode::Crossfade,
+            note_priority: NotePriority::Last,
+            mod_matrix: ModMatrix::default(),
             portamento_enable: false,
+            portamento_mode: PortamentoMode::FullTime,
             portamento_time: 50.0,
             portamento_glissando: false,
+            osc_key_sync: true,
             pitch_bend_range: 2.0,
+            pitch_bend_smoothing_ms: 5.0,
             transpose_semitones: 0,
-            pitch_mod_sensitivity: 0,
+            pitch_mod_sensitivity: 3,
             eg_bias_sensitivity: 0,
             pitch_bias_sensitivity: 0,
+            steal_fade_ms: 2.0,
+            attack_fade_ms: 5.0,
+            voice_watchdog: false,
+            voice_timeout: 0.0,
+            watchdog_releases: 0,
+            sub_bus: SubBusSnapshot::default(),
+            voice_pan: VoicePanSnapshot::default(),
+            vintage_mode: false,
+            output_dac: false,
+            output_dac_intensity: 1.0,
+            noise_floor: false,
+            noise_level_db: DEFAULT_NOISE_DB,
+            hum_hz: 50.0,
+            unison_voices: 1,
+            unison_detune: 10.0,
+            unison_spread: 0.5,
+            polyphony: 16,
 
             pitch_bend: 0.0,
             mod_wheel: 0.0,
@@ -292,6 +669,7 @@ impl Default for SynthSnapshot {
             breath: 0.0,
             foot: 0.0,
             expression: 1.0,
+            expression_source: ExpressionSource::Breath,
 
             aftertouch_pitch_sens: 0,
             aftertouch_amp_sens: 0,
@@ -316,28 +694,60 @@ impl Default for SynthSnapshot {
             lfo_key_sync: false,
             lfo_frequency_hz: 0.0,
             lfo_delay_seconds: 0.0,
+            lfo_sync: None,
+            tempo_bpm: DEFAULT_BPM,
+            lfo2: Lfo2Snapshot::default(),
 
             pitch_eg: PitchEgSnapshot::default(),
 
+            eq: EqSnapshot::default(),
+            filter: FilterSnapshot::default(),
+            phaser: PhaserSnapshot::default(),
             chorus: ChorusSnapshot::default(),
             auto_pan: AutoPanSnapshot::default(),
             delay: DelaySnapshot::default(),
             reverb: ReverbSnapshot::default(),
-
-            operators: [OperatorSnapshot::default(); 6],
+            effect_order: EffectSlot::DEFAULT_ORDER,
+            effects_bypass: false,
+            extensions: false,
+            effect_gains_db: [0.0; EFFECT_SLOTS],
+            wet_solo: None,
+            compressor: CompressorSnapshot::default(),
+            output_levels: MeterLevels::default(),
+            operator_solo: 0,
+            audio_stats: AudioStats::default(),
+
+            performance: Performance::default(),
+            part_b_name: "Init Voice".to_string(),
+
+            debug_dump: None,
+
+            // INIT VOICE: only OP1 sounds.
+            operators: std::array::from_fn(|i| OperatorSnapshot {
+                output_level: if i == 0 { 99.0 } else { 0.0 },
+                ..OperatorSnapshot::default()
+            }),
         }
     }
 }
 
+impl SynthSnapshot {
+    pub fn key_held(&self, note: u8) -> bool {
+        note < 128 && self.held_keys & (1 << note) != 0
+    }
+}
+
 /// Sender side of snapshot channel (audio thread)
 pub struct SnapshotSender {
     buffer: Arc<TripleBuffer<SynthSnapshot>>,
+    sequence: Arc<AtomicU64>,
 }
 
 impl SnapshotSender {
     /// Update the snapshot (audio thread)
     pub fn send(&self, snapshot: SynthSnapshot) {
         self.buffer.write(snapshot);
+        self.sequence.fetch_add(1, Ordering::Release);
     }
 }
 
@@ -345,9 +755,37 @@ impl SnapshotSender {
 #[allow(dead_code)]
 pub struct SnapshotReceiver {
     buffer: Arc<TripleBuffer<SynthSnapshot>>,
+    sequence: Arc<AtomicU64>,
+}
+
+/// Cheap, cloneable view of how many snapshots have been published, for a
+/// thread that only needs to know *that* something changed (e.g. to wake
+/// the GUI) without reading the snapshot itself.
+#[derive(Clone)]
+pub struct SnapshotWatcher {
+    sequence: Arc<AtomicU64>,
+}
+
+impl SnapshotWatcher {
+    /// Number of snapshots published so far.
+    pub fn sequence(&self) -> u64 {
+        self.sequence.load(Ordering::Acquire)
+    }
 }
 
 impl SnapshotReceiver {
+    /// Number of snapshots published so far; compare with an earlier value
+    /// to skip `get` when nothing new arrived.
+    pub fn sequence(&self) -> u64 {
+        self.sequence.load(Ordering::Acquire)
+    }
+
+    pub fn watcher(&self) -> SnapshotWatcher {
+        SnapshotWatcher {
+            sequence: self.sequence.clone(),
+        }
+    }
+
     /// Get the latest snapshot (GUI thread)
     /// This swaps in the latest data from the audio thread
     pub fn get(&self) -> &SynthSnapshot {
@@ -364,12 +802,14 @@ impl SnapshotReceiver {
 /// Create a new snapshot channel pair (sender, receiver)
 pub fn create_snapshot_channel() -> (SnapshotSender, SnapshotReceiver) {
     let buffer = Arc::new(TripleBuffer::new(SynthSnapshot::default()));
+    let sequence = Arc::new(AtomicU64::new(0));
 
     (
         SnapshotSender {
             buffer: buffer.clone(),
+            sequence: sequence.clone(),
         },
-        SnapshotReceiver { buffer },
+        SnapshotReceiver { buffer, sequence },
     )
 }
 
@@ -409,6 +849,21 @@ mod tests {
         assert_eq!(snapshot.active_voices, 3);
     }
 
+    #[test]
+    fn sequence_counts_published_snapshots() {
+        let (sender, receiver) = create_snapshot_channel();
+        let watcher = receiver.watcher();
+        assert_eq!(receiver.sequence(), 0);
+
+        sender.send(SynthSnapshot::default());
+        sender.send(SynthSnapshot::default());
+        assert_eq!(receiver.sequence(), 2);
+        assert_eq!(watcher.sequence(), 2);
+
+        receiver.get();
+        assert_eq!(receiver.sequence(), 2, "reading does not publish");
+    }
+
     #[test]
     fn test_snapshot_concurrent() {
         use std::sync::Arc;
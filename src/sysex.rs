@@ -7,11 +7,23 @@
 //! - **32-voice bulk (VMEM)** — 4104 bytes total. The voice data block is
 //!   4096 bytes (32 voices × 128 bytes packed). Used by "voice memory dump".
 //!
+//! MIDI Tuning Standard messages (Universal SysEx) are routed to
+//! [`crate::tuning`].
+//!
+//! Bank files from archives are often not a clean VMEM dump: old editors
+//! prepend their own header, cartridge images carry the bare 4096 data bytes,
+//! and some dumps are truncated or have a stale checksum.
+//! [`parse_bank_file`] reads those too and reports what it had to forgive.
+//!
 //! References: DX7 Owner's Manual Vol. 4 (System Exclusive), DX7S manual chapter 7.
 
+use crate::algorithms;
+use crate::dx7_frequency;
 use crate::lfo::LFOWaveform;
 use crate::operator::KeyScaleCurve;
+use crate::optimization::OperatorWaveform;
 use crate::presets::{Dx7Preset, PresetLfo, PresetOperator, PresetPitchEg};
+use crate::tuning::{self, MtsMessage, Tuning, UNIVERSAL_NON_REALTIME, UNIVERSAL_REALTIME};
 
 /// Yamaha manufacturer SysEx ID.
 pub(crate) const YAMAHA_ID: u8 = 0x43;
@@ -31,6 +43,10 @@ pub const VMEM_LEN: usize = 32 * VMEM_VOICE_LEN; // 4096
 pub enum SysexResult {
     SingleVoice(Box<Dx7Preset>),
     Bulk(Vec<Dx7Preset>),
+    /// MTS bulk tuning dump.
+    Tuning(Box<Tuning>),
+    /// MTS single note tuning change: `(key, cents offset)` pairs.
+    NoteTuning(Vec<(u8, f32)>),
 }
 
 #[derive(Debug)]
@@ -41,8 +57,19 @@ pub enum SysexError {
     UnsupportedSubStatus(u8),
     UnsupportedFormat(u8),
     TruncatedData,
-    LengthMismatch { declared: usize, actual: usize },
-    ChecksumMismatch { expected: u8, computed: u8 },
+    LengthMismatch {
+        declared: usize,
+        actual: usize,
+    },
+    ChecksumMismatch {
+        expected: u8,
+        computed: u8,
+    },
+    /// Universal SysEx that is not an MTS message this synth understands.
+    NotTuning {
+        sub_id1: u8,
+        sub_id2: u8,
+    },
 }
 
 impl std::fmt::Display for SysexError {
@@ -70,6 +97,11 @@ impl std::fmt::Display for SysexError {
                 "checksum mismatch (expected 0x{:02X}, computed 0x{:02X})",
                 expected, computed
             ),
+            Self::NotTuning { sub_id1, sub_id2 } => write!(
+                f,
+                "universal SysEx 0x{:02X} 0x{:02X} is not a supported tuning message",
+                sub_id1, sub_id2
+            ),
         }
     }
 }
@@ -78,8 +110,18 @@ impl std::error::Error for SysexError {}
 
 /// Parse a complete DX7 SysEx message (`F0 ... F7`).
 ///
-/// On success returns either a single voice or a 32-voice bank, ready to load.
+/// On success returns either a single voice or a 32-voice bank, ready to load,
+/// or an MTS tuning table / note retune.
 pub fn parse_message(bytes: &[u8]) -> Result<SysexResult, SysexError> {
+    if matches!(
+        bytes.get(1),
+        Some(&UNIVERSAL_NON_REALTIME | &UNIVERSAL_REALTIME)
+    ) {
+        return match tuning::parse_mts(bytes)? {
+            MtsMessage::Table(table) => Ok(SysexResult::Tuning(table)),
+            MtsMessage::NoteChanges(changes) => Ok(SysexResult::NoteTuning(changes)),
+        };
+    }
     if bytes.len() < 8 {
         return Err(SysexError::TooShort);
     }
@@ -144,6 +186,92 @@ pub fn parse_message(bytes: &[u8]) -> Result<SysexResult, SysexError> {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Tolerant bank files (headered dumps, bare cartridge data)
+// ---------------------------------------------------------------------------
+
+/// A problem with one voice of a bank file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoiceError {
+    /// Voice number in the bank, 1-32.
+    pub voice: usize,
+    /// Voice name, empty when the voice could not be read.
+    pub name: String,
+    pub message: String,
+}
+
+/// A 32-voice bank read by [`parse_bank_file`].
+#[derive(Debug, Default)]
+pub struct BankFile {
+    /// Every voice that could be read, in bank order.
+    pub presets: Vec<Dx7Preset>,
+    /// Bytes skipped ahead of the voice data (an editor's file header).
+    pub header_len: usize,
+    /// Whether the stored checksum matched; None when the file carries none.
+    pub checksum_ok: Option<bool>,
+    /// Voices that were clamped into range or could not be loaded.
+    pub errors: Vec<VoiceError>,
+}
+
+/// Read a 32-voice bank from a file that may not be a clean VMEM dump.
+///
+/// The dump is found anywhere in the file, so an editor's header is skipped;
+/// without one, the last 4096 bytes are taken as bare cartridge data. A
+/// checksum mismatch is reported rather than refused, out-of-range values
+/// are clamped, and a truncated bank loads the voices it has. Each such
+/// voice is listed in [`BankFile::errors`].
+pub fn parse_bank_file(bytes: &[u8]) -> Result<BankFile, SysexError> {
+    let dump = bytes
+        .windows(4)
+        .position(|w| w[0] == 0xF0 && w[1] == YAMAHA_ID && w[2] & 0xF0 == 0 && w[3] == 9);
+    let (header_len, data, checksum) = match dump {
+        Some(start) => {
+            let data_start = (start + 6).min(bytes.len());
+            let data_end = (data_start + VMEM_LEN).min(bytes.len());
+            let checksum = bytes.get(data_end).copied().filter(|&b| b < 0x80);
+            (start, &bytes[data_start..data_end], checksum)
+        }
+        None if bytes.len() >= VMEM_LEN => {
+            let start = bytes.len() - VMEM_LEN;
+            (start, &bytes[start..], None)
+        }
+        None => return Err(SysexError::TooShort),
+    };
+    if data.len() < VMEM_VOICE_LEN {
+        return Err(SysexError::TruncatedData);
+    }
+
+    let mut bank = BankFile {
+        header_len,
+        checksum_ok: checksum
+            .filter(|_| data.len() == VMEM_LEN)
+            .map(|c| c == compute_checksum(data)),
+        ..BankFile::default()
+    };
+    for voice in 0..32 {
+        let Some(block) = data.get(voice * VMEM_VOICE_LEN..(voice + 1) * VMEM_VOICE_LEN) else {
+            bank.errors.push(VoiceError {
+                voice: voice + 1,
+                name: String::new(),
+                message: "truncated, not loaded".to_string(),
+            });
+            continue;
+        };
+        let mut vced = unpack_vmem_voice(block);
+        let clamped = conform(&mut vced);
+        let preset = parse_vced(&vced, &format!("Bulk #{:02}", voice + 1))?;
+        if clamped > 0 {
+            bank.errors.push(VoiceError {
+                voice: voice + 1,
+                name: preset.name.clone(),
+                message: format!("{} values out of range, clamped", clamped),
+            });
+        }
+        bank.presets.push(preset);
+    }
+    Ok(bank)
+}
+
 /// Encode a preset as a single-voice SysEx message (163 bytes).
 ///
 /// `channel` is the 0-indexed MIDI channel embedded in the header byte.
@@ -195,9 +323,8 @@ pub(crate) fn build_sysex_message(format: u8, payload: &[u8]) -> Vec<u8> {
 
 /// Parse a 155-byte VCED block into a preset.
 fn parse_vced(data: &[u8], collection: &str) -> Result<Dx7Preset, SysexError> {
-    if data.len() != VCED_LEN {
-        return Err(SysexError::TruncatedData);
-    }
+    let mut data: [u8; VCED_LEN] = data.try_into().map_err(|_| SysexError::TruncatedData)?;
+    conform(&mut data);
 
     let mut operators: [PresetOperator; 6] = std::array::from_fn(|_| PresetOperator::default());
 
@@ -238,8 +365,9 @@ fn parse_vced(data: &[u8], collection: &str) -> Result<Dx7Preset, SysexError> {
 
     let name = parse_voice_name(&data[145..155]);
 
-    // Apply DX7 conventions: feedback lives on OP6 (index 5); osc key sync is global.
-    operators[5].feedback = feedback;
+    // Apply DX7 conventions: feedback lives on the algorithm's feedback
+    // operator; osc key sync is global.
+    operators[feedback_operator(algorithm)].feedback = feedback;
     for op in operators.iter_mut() {
         op.oscillator_key_sync = osc_key_sync;
     }
@@ -288,20 +416,16 @@ fn parse_vced_operator(block: &[u8]) -> PresetOperator {
     let frequency_ratio = if fixed_frequency {
         // In fixed mode the ratio field is unused — keep a sane default.
         1.0
-    } else if coarse == 0 {
-        // DX7 convention: coarse=0 → 0.5×.
-        0.5
     } else {
-        (coarse as f32) * (1.0 + (fine as f32) / 100.0)
+        dx7_frequency::coarse_fine_to_ratio(coarse, fine)
     };
     let fixed_freq_hz = if fixed_frequency {
-        let c = (coarse & 0x03) as f32;
-        10f32.powf(c) * (1.0 + (fine as f32) / 100.0)
+        dx7_frequency::fixed_coarse_fine_to_hz(coarse, fine)
     } else {
         440.0
     };
 
-    let detune = (detune_raw as i16 - 7) as f32;
+    let detune = dx7_frequency::detune_from_stored(detune_raw);
 
     let breakpoint_midi = breakpoint.saturating_add(21).min(127); // DX7 stores BP-21
 
@@ -319,6 +443,12 @@ fn parse_vced_operator(block: &[u8]) -> PresetOperator {
         key_scale_right_depth: kls_rd.clamp(0.0, 99.0),
         am_sensitivity: ams & 0x03,
         oscillator_key_sync: true, // overridden by patch-level flag
+        sub_bus: false,
+        expression: false,
+        waveform: OperatorWaveform::Sine,
+        phase_offset: 0.0,
+        pm_sensitivity: None,
+        lfo2: false,
         fixed_frequency,
         fixed_freq_hz,
         envelope: (r1, r2, r3, r4, l1, l2, l3, l4),
@@ -343,139 +473,163 @@ fn parse_vmem(data: &[u8]) -> Result<Vec<Dx7Preset>, SysexError> {
 }
 
 fn parse_vmem_voice(block: &[u8], collection: &str) -> Dx7Preset {
-    let mut operators: [PresetOperator; 6] = std::array::from_fn(|_| PresetOperator::default());
+    let mut vced = unpack_vmem_voice(block);
+    conform(&mut vced);
+    parse_vced(&vced, collection).expect("unpacked voice is VCED_LEN bytes")
+}
 
-    // Each operator occupies 17 packed bytes; SysEx orders OP6..OP1.
-    for sysex_idx in 0..6 {
-        let base = sysex_idx * 17;
-        let preset_idx = 5 - sysex_idx;
-        operators[preset_idx] = parse_vmem_operator(&block[base..base + 17]);
+/// Unpack a 128-byte VMEM voice into the 155-byte VCED layout, the way
+/// Dexed reads cartridges. Bit fields are masked; whole-byte fields are
+/// copied as they are and left to [`conform`].
+fn unpack_vmem_voice(block: &[u8]) -> [u8; VCED_LEN] {
+    let mut vced = [0u8; VCED_LEN];
+    for op in 0..6 {
+        let packed = &block[op * 17..op * 17 + 17];
+        let out = &mut vced[op * 21..op * 21 + 21];
+        // Rates, levels, breakpoint and both depths.
+        out[..11].copy_from_slice(&packed[..11]);
+        out[11] = packed[11] & 0x03; // left curve
+        out[12] = (packed[11] >> 2) & 0x03; // right curve
+        out[13] = packed[12] & 0x07; // rate scaling
+        out[20] = (packed[12] >> 3) & 0x0F; // detune
+        out[14] = packed[13] & 0x03; // AMS
+        out[15] = (packed[13] >> 2) & 0x07; // key velocity sensitivity
+        out[16] = packed[14]; // output level
+        out[17] = packed[15] & 0x01; // oscillator mode
+        out[18] = (packed[15] >> 1) & 0x1F; // coarse
+        out[19] = packed[16]; // fine
     }
+    vced[126..134].copy_from_slice(&block[102..110]); // pitch EG
+    vced[134] = block[110];
+    vced[135] = block[111] & 0x07; // feedback
+    vced[136] = (block[111] >> 3) & 0x01; // oscillator key sync
+    vced[137..141].copy_from_slice(&block[112..116]); // LFO speed, delay, PMD, AMD
+    vced[141] = block[116] & 0x01; // LFO key sync
+    vced[142] = (block[116] >> 1) & 0x07; // LFO wave
+    vced[143] = (block[116] >> 4) & 0x07; // PMS
+    vced[144] = block[117];
+    vced[145..155].copy_from_slice(&block[118..128]);
+    vced
+}
 
-    let pitch_eg = PresetPitchEg {
-        rate1: block[102] as f32,
-        rate2: block[103] as f32,
-        rate3: block[104] as f32,
-        rate4: block[105] as f32,
-        level1: block[106] as f32,
-        level2: block[107] as f32,
-        level3: block[108] as f32,
-        level4: block[109] as f32,
-    };
-
-    let algorithm = (block[110] & 0x1F) + 1;
-    // byte 111: bits 0-2 = feedback, bit 3 = oscillator key sync
-    let feedback = (block[111] & 0x07) as f32;
-    let osc_key_sync = (block[111] & 0x08) != 0;
-
-    let lfo_speed = block[112] as f32;
-    let lfo_delay = block[113] as f32;
-    let lfo_pmd = block[114] as f32;
-    let lfo_amd = block[115] as f32;
-    // byte 116: bit 0 = LFO sync, bits 1-3 = LFO wave, bits 4-6 = PMS
-    let lfo_sync = (block[116] & 0x01) != 0;
-    let lfo_wave_code = (block[116] >> 1) & 0x07;
-    let pms = (block[116] >> 4) & 0x07;
+/// Pack a VCED voice into the 128-byte VMEM layout.
+fn pack_vmem_voice(vced: &[u8]) -> [u8; VMEM_VOICE_LEN] {
+    let mut block = [0u8; VMEM_VOICE_LEN];
+    for op in 0..6 {
+        let unpacked = &vced[op * 21..op * 21 + 21];
+        let out = &mut block[op * 17..op * 17 + 17];
+        out[..11].copy_from_slice(&unpacked[..11]);
+        out[11] = (unpacked[12] << 2) | unpacked[11];
+        out[12] = (unpacked[20] << 3) | unpacked[13];
+        out[13] = (unpacked[15] << 2) | unpacked[14];
+        out[14] = unpacked[16];
+        out[15] = (unpacked[18] << 1) | unpacked[17];
+        out[16] = unpacked[19];
+    }
+    block[102..110].copy_from_slice(&vced[126..134]);
+    block[110] = vced[134];
+    block[111] = (vced[136] << 3) | vced[135];
+    block[112..116].copy_from_slice(&vced[137..141]);
+    block[116] = (vced[143] << 4) | (vced[142] << 1) | vced[141];
+    block[117] = vced[144];
+    block[118..128].copy_from_slice(&vced[145..155]);
+    block
+}
 
-    let lfo = PresetLfo {
-        waveform: lfo_wave_from_dx7(lfo_wave_code),
-        rate: lfo_speed,
-        delay: lfo_delay,
-        pitch_mod_depth: lfo_pmd,
-        amp_mod_depth: lfo_amd,
-        key_sync: lfo_sync,
-    };
+/// Encode up to 32 presets as a 32-voice bulk dump (4104 bytes), the format
+/// Dexed and the DX7 read as a cartridge. Missing voices are filled with
+/// INIT VOICE; presets past the 32nd are dropped.
+///
+/// `channel` is the 0-indexed MIDI channel embedded in the header byte.
+pub fn encode_bank(presets: &[Dx7Preset], channel: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(VMEM_LEN);
+    for voice in 0..32 {
+        let vced = match presets.get(voice) {
+            Some(preset) => encode_vced(preset),
+            None => init_vced().to_vec(),
+        };
+        data.extend_from_slice(&pack_vmem_voice(&vced));
+    }
 
-    let transpose_semitones = (block[117] as i16 - 24).clamp(-24, 24) as i8;
-    let name = parse_voice_name(&block[118..128]);
+    let mut out = Vec::with_capacity(VMEM_LEN + 8);
+    out.extend_from_slice(&[0xF0, YAMAHA_ID, channel & 0x0F, 0x09, 0x20, 0x00]);
+    out.extend_from_slice(&data);
+    out.push(compute_checksum(&data));
+    out.push(0xF7);
+    out
+}
 
-    operators[5].feedback = feedback;
-    for op in operators.iter_mut() {
-        op.oscillator_key_sync = osc_key_sync;
+/// The DX7's INIT VOICE in VCED layout: OP1 alone at full level, a plain
+/// sine at ratio 1.
+fn init_vced() -> [u8; VCED_LEN] {
+    const OPERATOR: [u8; 21] = [
+        99, 99, 99, 99, 99, 99, 99, 0, 39, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 7,
+    ];
+    let mut vced = [0u8; VCED_LEN];
+    for op in 0..6 {
+        vced[op * 21..op * 21 + 21].copy_from_slice(&OPERATOR);
     }
+    vced[5 * 21 + 16] = 99; // OP1 output level
+    vced[126..134].copy_from_slice(&[99, 99, 99, 99, 50, 50, 50, 50]);
+    vced[136] = 1;
+    vced[137..145].copy_from_slice(&[35, 0, 0, 0, 1, 0, 3, 24]);
+    vced[145..155].copy_from_slice(b"INIT VOICE");
+    vced
+}
 
-    Dx7Preset {
-        name,
-        collection: collection.to_string(),
-        algorithm,
-        operators,
-        master_tune: None,
-        pitch_bend_range: None,
-        portamento_enable: None,
-        portamento_time: None,
-        mono_mode: None,
-        transpose_semitones,
-        pitch_mod_sensitivity: pms,
-        pitch_eg: Some(pitch_eg),
-        lfo: Some(lfo),
+// ---------------------------------------------------------------------------
+// DX7 parameter ranges — the compatibility layer
+// ---------------------------------------------------------------------------
+//
+// Every voice, single or from a bank, is clamped into the DX7's ranges
+// before it is parsed. Real cartridges carry the odd rate of 127, a detune
+// of +8 or an LFO wave code 6-7; those map onto the nearest value the
+// engine handles (wave 6-7 plays Sample & Hold) instead of loading out of
+// range.
+
+/// Largest legal value of each VCED byte.
+const VCED_MAX: [u8; VCED_LEN] = vced_max();
+
+const fn vced_max() -> [u8; VCED_LEN] {
+    const OPERATOR: [u8; 21] = [
+        99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 3, 3, 7, 3, 7, 99, 1, 31, 99, 14,
+    ];
+    const GLOBAL: [u8; 19] = [
+        99, 99, 99, 99, 99, 99, 99, 99, 31, 7, 1, 99, 99, 99, 99, 1, 5, 7, 48,
+    ];
+    // The name bytes (145-154) keep the 7-bit default.
+    let mut max = [0x7F; VCED_LEN];
+    let mut i = 0;
+    while i < 126 {
+        max[i] = OPERATOR[i % 21];
+        i += 1;
+    }
+    while i < 145 {
+        max[i] = GLOBAL[i - 126];
+        i += 1;
     }
+    max
 }
 
-fn parse_vmem_operator(block: &[u8]) -> PresetOperator {
-    let r1 = block[0] as f32;
-    let r2 = block[1] as f32;
-    let r3 = block[2] as f32;
-    let r4 = block[3] as f32;
-    let l1 = block[4] as f32;
-    let l2 = block[5] as f32;
-    let l3 = block[6] as f32;
-    let l4 = block[7] as f32;
-    let breakpoint = block[8];
-    let kls_ld = block[9] as f32;
-    let kls_rd = block[10] as f32;
-    // byte 11: bits 0-1 = LC, bits 2-3 = RC
-    let kls_lc = block[11] & 0x03;
-    let kls_rc = (block[11] >> 2) & 0x03;
-    // byte 12: bits 0-2 = RS, bits 3-4 = AMS
-    let krs = block[12] & 0x07;
-    let ams = (block[12] >> 3) & 0x03;
-    let kvs = block[13] & 0x07;
-    let level = block[14] as f32;
-    // byte 15: bit 0 = oscillator mode, bits 1-5 = coarse
-    let osc_mode = block[15] & 0x01;
-    let coarse = (block[15] >> 1) & 0x1F;
-    let fine = block[16];
-    let detune_raw = (block[12] >> 5) & 0x0F; // VMEM stores detune in upper bits of byte 12... or 17?
-
-    // Note: in the real VMEM format the detune sits in bits 4-7 of byte 12 (combined
-    // with KRS+AMS). Some references shuffle the layout; we read it from there.
-    // Treat 7 as center as in VCED.
-    let detune = (detune_raw as i16 - 7) as f32;
-
-    let fixed_frequency = osc_mode == 1;
-    let frequency_ratio = if fixed_frequency {
-        1.0
-    } else if coarse == 0 {
-        0.5
-    } else {
-        (coarse as f32) * (1.0 + (fine as f32) / 100.0)
-    };
-    let fixed_freq_hz = if fixed_frequency {
-        let c = (coarse & 0x03) as f32;
-        10f32.powf(c) * (1.0 + (fine as f32) / 100.0)
-    } else {
-        440.0
-    };
-
-    let breakpoint_midi = breakpoint.saturating_add(21).min(127);
+/// Clamp a VCED voice into the DX7's ranges; returns how many values were
+/// out of range.
+fn conform(vced: &mut [u8; VCED_LEN]) -> usize {
+    let mut clamped = 0;
+    for (byte, max) in vced.iter_mut().zip(VCED_MAX) {
+        if *byte > max {
+            *byte = max;
+            clamped += 1;
+        }
+    }
+    clamped
+}
 
-    PresetOperator {
-        frequency_ratio,
-        output_level: level,
-        detune,
-        feedback: 0.0,
-        velocity_sensitivity: kvs as f32,
-        key_scale_rate: krs as f32,
-        key_scale_breakpoint: breakpoint_midi,
-        key_scale_left_curve: KeyScaleCurve::from_dx7_code(kls_lc),
-        key_scale_right_curve: KeyScaleCurve::from_dx7_code(kls_rc),
-        key_scale_left_depth: kls_ld.clamp(0.0, 99.0),
-        key_scale_right_depth: kls_rd.clamp(0.0, 99.0),
-        am_sensitivity: ams,
-        oscillator_key_sync: true,
-        fixed_frequency,
-        fixed_freq_hz,
-        envelope: (r1, r2, r3, r4, l1, l2, l3, l4),
+/// Operator (0-based) carrying the voice's FEEDBACK in `algorithm`: OP6 in
+/// most algorithms, OP2, OP3 or OP4 in the rest.
+fn feedback_operator(algorithm: u8) -> usize {
+    match algorithms::topology(algorithm).feedback_op {
+        0 => 5,
+        op => op as usize - 1,
     }
 }
 
@@ -503,9 +657,14 @@ fn encode_vced(preset: &Dx7Preset) -> Vec<u8> {
     buf[133] = clamp_99(peg.level4);
 
     buf[134] = preset.algorithm.saturating_sub(1).min(31);
-    // DX7 stores feedback (per-algorithm operator) at the patch level. We took it
-    // off OP6 so we round-trip cleanly.
-    buf[135] = (preset.operators[5].feedback.round() as u8).min(7);
+    // DX7 stores feedback at the patch level. It is read back onto the
+    // algorithm's feedback operator; take whichever operator carries it here.
+    let feedback = preset
+        .operators
+        .iter()
+        .map(|op| op.feedback)
+        .fold(0.0, f32::max);
+    buf[135] = (feedback.round() as u8).min(7);
     buf[136] = if preset.operators.iter().any(|op| op.oscillator_key_sync) {
         1
     } else {
@@ -554,27 +713,16 @@ fn encode_vced_operator(op: &PresetOperator, out: &mut [u8]) {
     out[16] = clamp_99(op.output_level);
     out[17] = if op.fixed_frequency { 1 } else { 0 };
     if op.fixed_frequency {
-        // Map Hz back to coarse (1/10/100/1000) + fine (0..99).
-        let log10 = op.fixed_freq_hz.max(0.1).log10();
-        let coarse = log10.floor().clamp(0.0, 3.0) as u8;
-        let base = 10f32.powi(coarse as i32);
-        let fine = ((op.fixed_freq_hz / base - 1.0) * 100.0).clamp(0.0, 99.0) as u8;
+        let (coarse, fine) = dx7_frequency::hz_to_fixed_coarse_fine(op.fixed_freq_hz);
         out[18] = coarse;
         out[19] = fine;
     } else {
-        // Inverse of `coarse * (1 + fine/100)` with the coarse=0 / 0.5× quirk.
-        if (op.frequency_ratio - 0.5).abs() < 0.01 {
-            out[18] = 0;
-            out[19] = 0;
-        } else {
-            let coarse = op.frequency_ratio.floor().clamp(1.0, 31.0) as u8;
-            let frac = op.frequency_ratio / coarse as f32 - 1.0;
-            let fine = (frac * 100.0).round().clamp(0.0, 99.0) as u8;
-            out[18] = coarse;
-            out[19] = fine;
-        }
+        // Extended (non-DX7) ratios go out as the nearest coarse/fine.
+        let (coarse, fine) = dx7_frequency::ratio_to_coarse_fine(op.frequency_ratio);
+        out[18] = coarse;
+        out[19] = fine;
     }
-    out[20] = ((op.detune.round() as i16 + 7).clamp(0, 14)) as u8;
+    out[20] = dx7_frequency::detune_to_stored(op.detune);
 }
 
 fn clamp_99(v: f32) -> u8 {
@@ -821,6 +969,10 @@ mod tests {
                 expected: 0x10,
                 computed: 0x20,
             },
+            SysexError::NotTuning {
+                sub_id1: 0x06,
+                sub_id2: 0x01,
+            },
         ];
         for e in errs {
             let msg = format!("{}", e);
@@ -828,6 +980,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn universal_tuning_messages_are_routed_to_mts() {
+        let mut table = Tuning::default();
+        table.set_offset_cents(64, -13.7);
+        let dump = crate::tuning::encode_bulk_dump(&table, 0x7F, 0);
+        match parse_message(&dump).expect("parse MTS dump") {
+            SysexResult::Tuning(parsed) => {
+                assert!((parsed.offset_cents(64) + 13.7).abs() < 0.01)
+            }
+            other => panic!("expected a tuning table, got {:?}", other),
+        }
+
+        let retune = [
+            0xF0, 0x7F, 0x7F, 0x08, 0x02, 0x00, 0x01, 60, 60, 0x20, 0x00, 0xF7,
+        ];
+        match parse_message(&retune).expect("parse note change") {
+            SysexResult::NoteTuning(changes) => assert_eq!(changes, vec![(60, 25.0)]),
+            other => panic!("expected note changes, got {:?}", other),
+        }
+    }
+
     // ----------------------------------------------------------------------
     // VMEM bulk dump roundtrip
     // ----------------------------------------------------------------------
@@ -1004,4 +1177,341 @@ mod tests {
         let result = parse_vmem(&[0u8; 100]);
         assert!(matches!(result, Err(SysexError::TruncatedData)));
     }
+
+    /// 32 packed voices named `VOICE 01` … `VOICE 32`.
+    fn named_bank() -> Vec<u8> {
+        let mut data = vec![0u8; VMEM_LEN];
+        for (i, block) in data.chunks_mut(VMEM_VOICE_LEN).enumerate() {
+            block[118..128].copy_from_slice(format!("VOICE {:02}  ", i + 1).as_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn bank_file_skips_an_editor_header() {
+        let mut file = b"DX7 EDITOR v1.2\0\0\0".to_vec();
+        let header = file.len();
+        file.extend(build_sysex_message(9, &named_bank()));
+        // A strict parse refuses the file, the bank loader finds the dump.
+        assert!(parse_message(&file).is_err());
+        let bank = parse_bank_file(&file).expect("headered bank");
+        assert_eq!(bank.header_len, header);
+        assert_eq!(bank.checksum_ok, Some(true));
+        assert!(bank.errors.is_empty());
+        assert_eq!(bank.presets.len(), 32);
+        assert_eq!(bank.presets[6].name, "VOICE 07");
+    }
+
+    #[test]
+    fn bank_file_reads_bare_cartridge_data() {
+        let bank = parse_bank_file(&named_bank()).expect("bare bank");
+        assert_eq!(bank.header_len, 0);
+        assert_eq!(bank.checksum_ok, None);
+        assert_eq!(bank.presets[31].name, "VOICE 32");
+
+        let mut file = vec![0xAA; 64];
+        file.extend(named_bank());
+        assert_eq!(parse_bank_file(&file).unwrap().header_len, 64);
+    }
+
+    #[test]
+    fn bank_file_reports_bad_voices_instead_of_failing() {
+        let mut data = named_bank();
+        // Voice 3: OP6 output level 120, algorithm 40.
+        data[2 * VMEM_VOICE_LEN + 14] = 120;
+        data[2 * VMEM_VOICE_LEN + 110] = 40;
+        let mut file = build_sysex_message(9, &named_bank());
+        file[6..6 + VMEM_LEN].copy_from_slice(&data);
+        assert!(matches!(
+            parse_message(&file),
+            Err(SysexError::ChecksumMismatch { .. })
+        ));
+
+        let bank = parse_bank_file(&file).expect("bank");
+        assert_eq!(bank.checksum_ok, Some(false));
+        assert_eq!(bank.presets.len(), 32);
+        assert_eq!(
+            bank.errors,
+            vec![VoiceError {
+                voice: 3,
+                name: "VOICE 03".to_string(),
+                message: "2 values out of range, clamped".to_string(),
+            }]
+        );
+        assert_eq!(bank.presets[2].operators[5].output_level, 99.0);
+        assert_eq!(bank.presets[2].algorithm, 32);
+
+        // Cut off halfway through voice 31: 30 voices, two reported missing.
+        file.truncate(6 + 30 * VMEM_VOICE_LEN + 40);
+        let bank = parse_bank_file(&file).expect("truncated bank");
+        assert_eq!(bank.presets.len(), 30);
+        assert_eq!(bank.checksum_ok, None);
+        let missing: Vec<usize> = bank.errors.iter().map(|e| e.voice).collect();
+        assert_eq!(missing, vec![3, 31, 32]);
+    }
+
+    #[test]
+    fn bank_file_rejects_files_without_voice_data() {
+        assert!(parse_bank_file(b"not a bank").is_err());
+        let mut stub = build_sysex_message(9, &[0u8; 40]);
+        stub.truncate(20);
+        assert!(matches!(
+            parse_bank_file(&stub),
+            Err(SysexError::TruncatedData)
+        ));
+    }
+
+    // ----------------------------------------------------------------------
+    // Dexed / DX7 compatibility
+    // ----------------------------------------------------------------------
+
+    /// ROM1A E.PIANO 1 packed by hand from the DX7 VMEM layout, OP6 first:
+    /// R1-R4, L1-L4, BP, LD, RD, RC<<2|LC, DET<<3|RS, KVS<<2|AMS, OL,
+    /// COARSE<<1|MODE, FINE per operator.
+    #[rustfmt::skip]
+    const EPIANO_1: [u8; VMEM_VOICE_LEN] = [
+        95, 29, 20, 50, 99, 95, 0, 0, 41, 0, 19, 0, 115, 24, 79, 2, 0, // OP6
+        95, 20, 20, 50, 99, 95, 0, 0, 0, 0, 0, 0, 3, 0, 99, 2, 0,      // OP5
+        95, 29, 20, 50, 99, 95, 0, 0, 0, 0, 0, 0, 59, 24, 89, 2, 0,    // OP4
+        95, 20, 20, 50, 99, 95, 0, 0, 0, 0, 0, 0, 59, 8, 99, 2, 0,     // OP3
+        95, 50, 35, 78, 99, 75, 0, 0, 0, 0, 0, 0, 59, 28, 58, 28, 0,   // OP2
+        96, 25, 25, 67, 99, 75, 0, 0, 0, 0, 0, 0, 83, 8, 99, 2, 0,     // OP1
+        94, 67, 95, 60, 50, 50, 50, 50, // pitch EG
+        4,  // algorithm 5
+        6,  // feedback 6, oscillator key sync off
+        15, 33, 0, 0, // LFO speed, delay, PMD, AMD
+        40, // PMS 2, sine, LFO key sync off
+        24, // transpose C3
+        b'E', b'.', b'P', b'I', b'A', b'N', b'O', b' ', b'1', b' ',
+    ];
+
+    /// The voices under `patches/` that were converted from DX7 cartridges;
+    /// `edu` holds hand-made examples with off-grid values.
+    fn rom_voices() -> Vec<Dx7Preset> {
+        crate::preset_loader::scan_patches_dir(std::path::Path::new("patches"))
+            .into_iter()
+            .filter(|p| p.collection != "edu")
+            .collect()
+    }
+
+    /// Peak level of `notes` held for a tenth of a second on `preset`.
+    fn render_peak(preset: &Dx7Preset, notes: &[u8]) -> f32 {
+        let (mut engine, mut ctrl) = crate::fm_synth::create_synth(44_100.0);
+        preset.apply_to_synth(&mut engine);
+        for &note in notes {
+            ctrl.note_on(note, 100);
+        }
+        engine.process_commands();
+        let mut left = vec![0.0; 4410];
+        let mut right = vec![0.0; 4410];
+        engine.process_block(&mut left, &mut right);
+        let samples = left.iter().chain(&right);
+        assert!(samples.clone().all(|s| s.is_finite()), "{}", preset.name);
+        samples.fold(0.0, |peak, s| peak.max(s.abs()))
+    }
+
+    /// Tiny xorshift, so the garbage voices are the same on every run.
+    fn noise(seed: &mut u32) -> u8 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 17;
+        *seed ^= *seed << 5;
+        (*seed >> 24) as u8
+    }
+
+    #[test]
+    fn rom_voice_unpacks_like_dexed() {
+        let mut data = vec![0u8; VMEM_LEN];
+        data[..VMEM_VOICE_LEN].copy_from_slice(&EPIANO_1);
+        let SysexResult::Bulk(bank) = parse_message(&build_sysex_message(9, &data)).unwrap() else {
+            panic!("expected bulk result");
+        };
+        let voice = &bank[0];
+
+        assert_eq!(voice.name, "E.PIANO 1");
+        assert_eq!(voice.algorithm, 5);
+        assert_eq!(voice.operators[5].feedback, 6.0);
+        let ratios: Vec<f32> = voice
+            .operators
+            .iter()
+            .map(|op| op.frequency_ratio)
+            .collect();
+        assert_eq!(ratios, [1.0, 14.0, 1.0, 1.0, 1.0, 1.0]);
+        let detunes: Vec<f32> = voice.operators.iter().map(|op| op.detune).collect();
+        assert_eq!(detunes, [3.0, 0.0, 0.0, 0.0, -7.0, 7.0]);
+        let velocity: Vec<f32> = voice
+            .operators
+            .iter()
+            .map(|op| op.velocity_sensitivity)
+            .collect();
+        assert_eq!(velocity, [2.0, 7.0, 2.0, 6.0, 0.0, 6.0]);
+        assert!(voice.operators.iter().all(|op| op.key_scale_rate == 3.0
+            && op.am_sensitivity == 0
+            && !op.oscillator_key_sync));
+        assert_eq!(
+            voice.operators[0].envelope,
+            (96.0, 25.0, 25.0, 67.0, 99.0, 75.0, 0.0, 0.0)
+        );
+        assert_eq!(voice.operators[1].output_level, 58.0);
+        assert_eq!(voice.operators[5].key_scale_breakpoint, 62);
+        assert_eq!(voice.operators[5].key_scale_right_depth, 19.0);
+        let lfo = voice.lfo.as_ref().unwrap();
+        assert_eq!(
+            (lfo.waveform, lfo.rate, lfo.delay),
+            (LFOWaveform::Sine, 15.0, 33.0)
+        );
+        assert_eq!(voice.pitch_mod_sensitivity, 2);
+        assert_eq!(voice.transpose_semitones, 0);
+
+        // The same voice from the JSON collection sounds the same.
+        if let Some(json) = rom_voices()
+            .into_iter()
+            .find(|p| p.collection == "mark" && p.name == "E.PIANO 1")
+        {
+            assert_eq!(json.algorithm, voice.algorithm);
+            for (a, b) in json.operators.iter().zip(&voice.operators) {
+                assert_eq!(a.frequency_ratio, b.frequency_ratio);
+                assert_eq!(a.detune, b.detune);
+                assert_eq!(a.envelope, b.envelope);
+                assert_eq!(a.output_level, b.output_level);
+            }
+            let (json_peak, syx_peak) = (render_peak(&json, &[60]), render_peak(voice, &[60]));
+            assert!(syx_peak > 0.01);
+            assert!((json_peak - syx_peak).abs() < 1e-4 * json_peak.max(1.0));
+        }
+    }
+
+    #[test]
+    fn feedback_lands_on_the_algorithms_feedback_operator() {
+        let mut data = vec![0u8; VMEM_LEN];
+        for (voice, algorithm) in [(0, 1u8), (1, 2), (2, 4), (3, 18)] {
+            let block = &mut data[voice * VMEM_VOICE_LEN..(voice + 1) * VMEM_VOICE_LEN];
+            block[110] = algorithm - 1;
+            block[111] = 5;
+        }
+        let presets = parse_vmem(&data).unwrap();
+        // OP6, OP2, OP4 and OP3 carry the loop in algorithms 1, 2, 4 and 18.
+        for (preset, op) in presets.iter().zip([5, 1, 3, 2]) {
+            let carrying: Vec<usize> = (0..6)
+                .filter(|&i| preset.operators[i].feedback > 0.0)
+                .collect();
+            assert_eq!(carrying, [op], "algorithm {}", preset.algorithm);
+        }
+        // And is written back from wherever it sits.
+        let again = parse_vmem(&encode_bank(&presets, 0)[6..6 + VMEM_LEN]).unwrap();
+        assert_eq!(again[1].operators[1].feedback, 5.0);
+    }
+
+    #[test]
+    fn rom_voices_round_trip_through_a_bank() {
+        let voices = rom_voices();
+        if voices.is_empty() {
+            eprintln!("Skipping: patches/ not present");
+            return;
+        }
+        let close = |a: f32, b: f32| (a - b).abs() <= 1e-3 * b.abs().max(1.0);
+        for chunk in voices.chunks(32) {
+            let dump = encode_bank(chunk, 0);
+            assert_eq!(dump.len(), VMEM_LEN + 8);
+            let SysexResult::Bulk(bank) = parse_message(&dump).expect("bank") else {
+                panic!("expected bulk result");
+            };
+            for (original, parsed) in chunk.iter().zip(&bank) {
+                let name = original.name.as_str();
+                assert_eq!(
+                    parsed.name,
+                    name.chars().take(10).collect::<String>().trim_end()
+                );
+                assert_eq!(parsed.algorithm, original.algorithm, "{name}");
+                assert_eq!(parsed.transpose_semitones, original.transpose_semitones);
+                assert_eq!(parsed.pitch_mod_sensitivity, original.pitch_mod_sensitivity);
+                let feedback =
+                    |p: &Dx7Preset| p.operators.iter().map(|op| op.feedback).fold(0.0, f32::max);
+                assert_eq!(feedback(parsed), feedback(original), "{name}");
+                for (a, b) in original.operators.iter().zip(&parsed.operators) {
+                    assert_eq!(a.fixed_frequency, b.fixed_frequency, "{name}");
+                    if !a.fixed_frequency {
+                        assert!(close(b.frequency_ratio, a.frequency_ratio), "{name}");
+                    }
+                    // Some cartridges carry values past the DX7's ranges (a
+                    // rate of 127, detune +8); they load clamped.
+                    let (r1, r2, r3, r4, l1, l2, l3, l4) = a.envelope;
+                    let envelope = [r1, r2, r3, r4, l1, l2, l3, l4].map(|v| v.min(99.0));
+                    let (r1, r2, r3, r4, l1, l2, l3, l4) = b.envelope;
+                    assert_eq!(envelope, [r1, r2, r3, r4, l1, l2, l3, l4], "{name}");
+                    assert_eq!(a.output_level.min(99.0), b.output_level, "{name}");
+                    assert_eq!(a.detune.clamp(-7.0, 7.0), b.detune, "{name}");
+                    assert_eq!(a.velocity_sensitivity.min(7.0), b.velocity_sensitivity);
+                    assert_eq!(a.key_scale_rate.min(7.0), b.key_scale_rate, "{name}");
+                    assert_eq!(a.am_sensitivity.min(3), b.am_sensitivity, "{name}");
+                    assert_eq!(a.key_scale_breakpoint, b.key_scale_breakpoint, "{name}");
+                }
+                if let (Some(a), Some(b)) = (&original.lfo, &parsed.lfo) {
+                    let original = (a.waveform, a.rate.min(99.0), a.delay.min(99.0));
+                    assert_eq!(original, (b.waveform, b.rate, b.delay), "{name}");
+                }
+            }
+            for voice in bank.iter().take(chunk.len()) {
+                assert!(
+                    render_peak(voice, &[48, 60, 67]) > 0.0,
+                    "{} is silent",
+                    voice.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn short_banks_are_padded_with_init_voice() {
+        let dump = encode_bank(&[], 3);
+        assert_eq!(dump[2], 3);
+        let SysexResult::Bulk(bank) = parse_message(&dump).unwrap() else {
+            panic!("expected bulk result");
+        };
+        let init = &bank[31];
+        assert_eq!(init.name, "INIT VOICE");
+        assert_eq!(init.algorithm, 1);
+        assert_eq!(init.operators[0].output_level, 99.0);
+        assert!(init.operators[1..].iter().all(|op| op.output_level == 0.0));
+        assert!(render_peak(init, &[69]) > 0.1);
+    }
+
+    #[test]
+    fn fixed_frequencies_use_the_dx7_log_scale() {
+        let mut block = EPIANO_1;
+        block[15] = (2 << 1) | 1; // OP6 fixed, coarse 2 (100 Hz decade)
+        block[16] = 50;
+        let voice = parse_vmem_voice(&block, "test");
+        assert!(voice.operators[5].fixed_frequency);
+        assert!((voice.operators[5].fixed_freq_hz - 316.23).abs() < 0.01);
+
+        let vced = encode_vced(&voice);
+        assert_eq!((vced[17], vced[18], vced[19]), (1, 2, 50));
+    }
+
+    #[test]
+    fn garbage_voices_load_and_play_without_panicking() {
+        let mut seed = 0x1234_5678;
+        for _ in 0..4 {
+            let data: Vec<u8> = (0..VMEM_LEN).map(|_| noise(&mut seed) & 0x7F).collect();
+            let SysexResult::Bulk(bank) = parse_message(&build_sysex_message(9, &data)).unwrap()
+            else {
+                panic!("expected bulk result");
+            };
+            for voice in bank.iter().step_by(4) {
+                assert!((1..=32).contains(&voice.algorithm));
+                assert!(voice.operators.iter().all(|op| op.output_level <= 99.0
+                    && op.envelope.0 <= 99.0
+                    && (-7.0..=7.0).contains(&op.detune)));
+                render_peak(voice, &[36, 96]);
+            }
+
+            let vced: Vec<u8> = (0..VCED_LEN).map(|_| noise(&mut seed) & 0x7F).collect();
+            let SysexResult::SingleVoice(voice) =
+                parse_message(&build_sysex_message(0, &vced)).unwrap()
+            else {
+                panic!("expected single voice");
+            };
+            render_peak(&voice, &[60]);
+        }
+    }
 }
@@ -0,0 +1,190 @@
+//! Engine tempo and note divisions for tempo-synced modulation.
+//!
+//! The engine keeps one BPM value. Anything that can lock to it (the LFOs,
+//! the delay) stores a `NoteDivision` and converts it to Hz or seconds
+//! against the current tempo, so a tempo change retunes them all at once.
+
+/// Tempo at start-up.
+pub const DEFAULT_BPM: f32 = 120.0;
+
+/// Accepted tempo range.
+pub const MIN_BPM: f32 = 20.0;
+pub const MAX_BPM: f32 = 300.0;
+
+/// Clamp a tempo to `MIN_BPM..=MAX_BPM`.
+pub fn clamp_bpm(bpm: f32) -> f32 {
+    bpm.clamp(MIN_BPM, MAX_BPM)
+}
+
+/// Straight note lengths, whole note down to a 32nd.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteValue {
+    Whole,
+    Half,
+    Quarter,
+    Eighth,
+    Sixteenth,
+    ThirtySecond,
+}
+
+impl NoteValue {
+    const ALL: [NoteValue; 6] = [
+        NoteValue::Whole,
+        NoteValue::Half,
+        NoteValue::Quarter,
+        NoteValue::Eighth,
+        NoteValue::Sixteenth,
+        NoteValue::ThirtySecond,
+    ];
+
+    /// Length in quarter-note beats.
+    fn beats(self) -> f32 {
+        match self {
+            NoteValue::Whole => 4.0,
+            NoteValue::Half => 2.0,
+            NoteValue::Quarter => 1.0,
+            NoteValue::Eighth => 0.5,
+            NoteValue::Sixteenth => 0.25,
+            NoteValue::ThirtySecond => 0.125,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            NoteValue::Whole => "1/1",
+            NoteValue::Half => "1/2",
+            NoteValue::Quarter => "1/4",
+            NoteValue::Eighth => "1/8",
+            NoteValue::Sixteenth => "1/16",
+            NoteValue::ThirtySecond => "1/32",
+        }
+    }
+}
+
+/// Straight, dotted (×1.5) or triplet (×2/3) feel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteFeel {
+    Straight,
+    Dotted,
+    Triplet,
+}
+
+impl NoteFeel {
+    const ALL: [NoteFeel; 3] = [NoteFeel::Straight, NoteFeel::Dotted, NoteFeel::Triplet];
+
+    fn factor(self) -> f32 {
+        match self {
+            NoteFeel::Straight => 1.0,
+            NoteFeel::Dotted => 1.5,
+            NoteFeel::Triplet => 2.0 / 3.0,
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            NoteFeel::Straight => "",
+            NoteFeel::Dotted => ".",
+            NoteFeel::Triplet => "T",
+        }
+    }
+}
+
+/// A note length relative to the tempo, e.g. a dotted eighth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteDivision {
+    pub value: NoteValue,
+    pub feel: NoteFeel,
+}
+
+impl Default for NoteDivision {
+    fn default() -> Self {
+        Self::new(NoteValue::Quarter, NoteFeel::Straight)
+    }
+}
+
+impl NoteDivision {
+    /// Number of divisions, and of command codes.
+    pub const COUNT: usize = NoteValue::ALL.len() * NoteFeel::ALL.len();
+
+    pub const fn new(value: NoteValue, feel: NoteFeel) -> Self {
+        Self { value, feel }
+    }
+
+    /// Every division, longest note value first, each in straight, dotted
+    /// and triplet feel. The index is the command code.
+    pub fn all() -> [NoteDivision; Self::COUNT] {
+        std::array::from_fn(Self::from_index)
+    }
+
+    fn from_index(index: usize) -> Self {
+        let feels = NoteFeel::ALL.len();
+        Self::new(
+            NoteValue::ALL[(index / feels).min(NoteValue::ALL.len() - 1)],
+            NoteFeel::ALL[index % feels],
+        )
+    }
+
+    /// Command code of this division (index in `all()`).
+    pub fn to_code(self) -> u8 {
+        let value = NoteValue::ALL.iter().position(|&v| v == self.value);
+        let feel = NoteFeel::ALL.iter().position(|&f| f == self.feel);
+        (value.unwrap_or(0) * NoteFeel::ALL.len() + feel.unwrap_or(0)) as u8
+    }
+
+    /// Inverse of `to_code`; out-of-range codes clamp to the shortest triplet.
+    pub fn from_code(code: u8) -> Self {
+        Self::from_index((code as usize).min(Self::COUNT - 1))
+    }
+
+    /// Length in quarter-note beats.
+    pub fn beats(self) -> f32 {
+        self.value.beats() * self.feel.factor()
+    }
+
+    /// Length in seconds at `bpm`.
+    pub fn seconds(self, bpm: f32) -> f32 {
+        self.beats() * 60.0 / clamp_bpm(bpm)
+    }
+
+    /// Rate in Hz of one cycle per division at `bpm`.
+    pub fn hz(self, bpm: f32) -> f32 {
+        1.0 / self.seconds(bpm)
+    }
+
+    /// Display label: "1/4", "1/8." (dotted), "1/8T" (triplet).
+    pub fn label(self) -> String {
+        format!("{}{}", self.value.name(), self.feel.suffix())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn divisions_convert_against_the_tempo() {
+        let quarter = NoteDivision::default();
+        assert_eq!(quarter.seconds(120.0), 0.5);
+        assert_eq!(quarter.hz(120.0), 2.0);
+        let dotted_eighth = NoteDivision::new(NoteValue::Eighth, NoteFeel::Dotted);
+        assert!((dotted_eighth.seconds(120.0) - 0.375).abs() < 1e-6);
+        let eighth_triplet = NoteDivision::new(NoteValue::Eighth, NoteFeel::Triplet);
+        assert!((eighth_triplet.hz(120.0) - 6.0).abs() < 1e-5);
+        // Out-of-range tempos are clamped.
+        assert_eq!(quarter.seconds(0.0), quarter.seconds(MIN_BPM));
+    }
+
+    #[test]
+    fn codes_round_trip_and_labels_are_unique() {
+        let all = NoteDivision::all();
+        for (i, d) in all.iter().enumerate() {
+            assert_eq!(d.to_code() as usize, i);
+            assert_eq!(NoteDivision::from_code(i as u8), *d);
+        }
+        assert_eq!(NoteDivision::from_code(255), all[NoteDivision::COUNT - 1]);
+        let mut labels: Vec<_> = all.iter().map(|d| d.label()).collect();
+        labels.sort();
+        labels.dedup();
+        assert_eq!(labels.len(), NoteDivision::COUNT);
+    }
+}
@@ -0,0 +1,299 @@
+//! Per-key microtuning from the MIDI Tuning Standard.
+//!
+//! A `Tuning` stores each key's offset from 12-tone equal temperament in
+//! cents; the engine adds it to MASTER TUNE when a note starts. Tables
+//! arrive as MTS SysEx, the format DAW microtuning plugins send:
+//!
+//! - **Bulk tuning dump** (non-real-time `7E dev 08 01`) — 408 bytes,
+//!   replaces the whole table.
+//! - **Single note tuning change** (real-time `7F dev 08 02`) — retunes a
+//!   list of keys, including ones already sounding.
+//!
+//! Each key's pitch is three data bytes `xx yy zz`: the equal-tempered
+//! semitone `xx` plus a 14-bit fraction of a semitone. `7F 7F 7F` means
+//! "leave this key alone".
+
+use crate::sysex::SysexError;
+
+/// Universal Non-Real-Time SysEx ID.
+pub(crate) const UNIVERSAL_NON_REALTIME: u8 = 0x7E;
+/// Universal Real-Time SysEx ID.
+pub(crate) const UNIVERSAL_REALTIME: u8 = 0x7F;
+
+/// Sub-ID #1 shared by all MTS messages.
+const MTS_SUB_ID: u8 = 0x08;
+const BULK_DUMP: u8 = 0x01;
+const SINGLE_NOTE_CHANGE: u8 = 0x02;
+
+/// Length of a bulk tuning dump, framing included.
+pub const MTS_BULK_DUMP_LEN: usize = 408;
+const NAME_LEN: usize = 16;
+/// Offset of the first key's frequency bytes in a bulk dump.
+const BULK_DATA_START: usize = 22;
+
+/// Name of the default table.
+pub const EQUAL_TEMPERAMENT: &str = "12-TET";
+/// Name of a table single note tuning changes have edited.
+pub const LIVE_TUNING: &str = "MTS (live)";
+
+/// One cent offset per MIDI key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tuning {
+    pub name: String,
+    cents: [f32; 128],
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            name: EQUAL_TEMPERAMENT.to_string(),
+            cents: [0.0; 128],
+        }
+    }
+}
+
+impl Tuning {
+    /// Offset of `note` from equal temperament, in cents.
+    pub fn offset_cents(&self, note: u8) -> f32 {
+        self.cents.get(note as usize).copied().unwrap_or(0.0)
+    }
+
+    pub fn set_offset_cents(&mut self, note: u8, cents: f32) {
+        if let Some(offset) = self.cents.get_mut(note as usize) {
+            *offset = cents;
+        }
+    }
+
+    /// True when every key sits on equal temperament.
+    pub fn is_equal_tempered(&self) -> bool {
+        self.cents.iter().all(|&c| c == 0.0)
+    }
+}
+
+/// A parsed MTS message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MtsMessage {
+    /// Bulk dump: a whole new table.
+    Table(Box<Tuning>),
+    /// Single note tuning change: `(key, cents offset)` pairs.
+    NoteChanges(Vec<(u8, f32)>),
+}
+
+/// Parse a Universal SysEx message (`F0 7E …` or `F0 7F …`) as MTS.
+pub fn parse_mts(bytes: &[u8]) -> Result<MtsMessage, SysexError> {
+    if bytes.len() < 6 {
+        return Err(SysexError::TooShort);
+    }
+    if bytes.first() != Some(&0xF0) || bytes.last() != Some(&0xF7) {
+        return Err(SysexError::InvalidFraming);
+    }
+    match (bytes[1], bytes[3], bytes[4]) {
+        (UNIVERSAL_NON_REALTIME, MTS_SUB_ID, BULK_DUMP) => parse_bulk_dump(bytes),
+        (UNIVERSAL_REALTIME, MTS_SUB_ID, SINGLE_NOTE_CHANGE) => parse_note_changes(bytes),
+        (_, sub_id1, sub_id2) => Err(SysexError::NotTuning { sub_id1, sub_id2 }),
+    }
+}
+
+/// `F0 7E dev 08 01 tt <name ×16> <xx yy zz ×128> cs F7`
+fn parse_bulk_dump(bytes: &[u8]) -> Result<MtsMessage, SysexError> {
+    if bytes.len() != MTS_BULK_DUMP_LEN {
+        return Err(SysexError::LengthMismatch {
+            declared: MTS_BULK_DUMP_LEN,
+            actual: bytes.len(),
+        });
+    }
+    let checksum_at = MTS_BULK_DUMP_LEN - 2;
+    let computed = mts_checksum(&bytes[1..checksum_at]);
+    if computed != bytes[checksum_at] {
+        return Err(SysexError::ChecksumMismatch {
+            expected: bytes[checksum_at],
+            computed,
+        });
+    }
+
+    let name: String = bytes[6..6 + NAME_LEN]
+        .iter()
+        .map(|&b| {
+            if (0x20..0x7F).contains(&b) {
+                b as char
+            } else {
+                ' '
+            }
+        })
+        .collect();
+    let mut tuning = Tuning {
+        name: name.trim_end().to_string(),
+        ..Tuning::default()
+    };
+    for (key, data) in bytes[BULK_DATA_START..checksum_at]
+        .chunks_exact(3)
+        .enumerate()
+    {
+        if let Some(cents) = decode_offset(key as u8, [data[0], data[1], data[2]]) {
+            tuning.set_offset_cents(key as u8, cents);
+        }
+    }
+    Ok(MtsMessage::Table(Box::new(tuning)))
+}
+
+/// `F0 7F dev 08 02 tt ll <kk xx yy zz ×ll> F7`
+fn parse_note_changes(bytes: &[u8]) -> Result<MtsMessage, SysexError> {
+    if bytes.len() < 8 {
+        return Err(SysexError::TooShort);
+    }
+    let count = bytes[6] as usize;
+    let actual = (bytes.len() - 8) / 4;
+    if bytes.len() != 8 + 4 * count {
+        return Err(SysexError::LengthMismatch {
+            declared: count,
+            actual,
+        });
+    }
+    let changes = bytes[7..bytes.len() - 1]
+        .chunks_exact(4)
+        .filter_map(|change| {
+            let key = change[0] & 0x7F;
+            decode_offset(key, [change[1], change[2], change[3]]).map(|cents| (key, cents))
+        })
+        .collect();
+    Ok(MtsMessage::NoteChanges(changes))
+}
+
+/// Cents between `key` and the pitch in `xx yy zz`; `None` for "no change".
+fn decode_offset(key: u8, [xx, yy, zz]: [u8; 3]) -> Option<f32> {
+    if [xx, yy, zz] == [0x7F; 3] {
+        return None;
+    }
+    let fraction = (((yy as u32 & 0x7F) << 7) | (zz as u32 & 0x7F)) as f32 / 16384.0;
+    let semitone = (xx & 0x7F) as f32 + fraction;
+    Some((semitone - key as f32) * 100.0)
+}
+
+/// Pitch of `key` shifted by `cents`, as `xx yy zz`. Clamped to the
+/// representable range, key 0 to just under key 128.
+fn encode_offset(key: u8, cents: f32) -> [u8; 3] {
+    let semitone = (key as f32 + cents / 100.0).clamp(0.0, 127.0 + 16383.0 / 16384.0);
+    let whole = semitone.floor();
+    let fraction = (((semitone - whole) * 16384.0).round() as u32).min(16383);
+    [whole as u8, (fraction >> 7) as u8, (fraction & 0x7F) as u8]
+}
+
+/// XOR of every byte from the `7E` up to the last data byte, in 7 bits.
+fn mts_checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |acc, &b| acc ^ b) & 0x7F
+}
+
+/// Encode `tuning` as a bulk tuning dump for tuning program `program`.
+pub fn encode_bulk_dump(tuning: &Tuning, device: u8, program: u8) -> Vec<u8> {
+    let mut out = Vec::with_capacity(MTS_BULK_DUMP_LEN);
+    out.extend_from_slice(&[
+        0xF0,
+        UNIVERSAL_NON_REALTIME,
+        device & 0x7F,
+        MTS_SUB_ID,
+        BULK_DUMP,
+        program & 0x7F,
+    ]);
+    let name = tuning.name.bytes().filter(|b| (0x20..0x7F).contains(b));
+    out.extend(name.chain(std::iter::repeat(b' ')).take(NAME_LEN));
+    for key in 0..128u8 {
+        out.extend_from_slice(&encode_offset(key, tuning.offset_cents(key)));
+    }
+    out.push(mts_checksum(&out[1..]));
+    out.push(0xF7);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quarter_tone_table() -> Tuning {
+        let mut tuning = Tuning {
+            name: "Quarter".to_string(),
+            ..Tuning::default()
+        };
+        for key in (1..128u8).step_by(2) {
+            tuning.set_offset_cents(key, -50.0);
+        }
+        tuning
+    }
+
+    #[test]
+    fn bulk_dump_round_trips() {
+        let tuning = quarter_tone_table();
+        let bytes = encode_bulk_dump(&tuning, 0x7F, 3);
+        assert_eq!(bytes.len(), MTS_BULK_DUMP_LEN);
+        let Ok(MtsMessage::Table(parsed)) = parse_mts(&bytes) else {
+            panic!("not a table");
+        };
+        assert_eq!(parsed.name, "Quarter");
+        for key in 0..128u8 {
+            let expected = tuning.offset_cents(key);
+            assert!(
+                (parsed.offset_cents(key) - expected).abs() < 0.01,
+                "key {key}"
+            );
+        }
+        assert!(!parsed.is_equal_tempered());
+        assert!(Tuning::default().is_equal_tempered());
+    }
+
+    #[test]
+    fn bulk_dump_checks_length_and_checksum() {
+        let mut bytes = encode_bulk_dump(&quarter_tone_table(), 0, 0);
+        bytes[100] ^= 0x01;
+        assert!(matches!(
+            parse_mts(&bytes),
+            Err(SysexError::ChecksumMismatch { .. })
+        ));
+        bytes.remove(100);
+        assert!(matches!(
+            parse_mts(&bytes),
+            Err(SysexError::LengthMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn single_note_changes_skip_no_change_entries() {
+        // Key 60 to A4 + 50 cents (69.5), key 61 "no change".
+        let bytes = [
+            0xF0, 0x7F, 0x7F, 0x08, 0x02, 0x00, 0x02, 60, 69, 0x40, 0x00, 61, 0x7F, 0x7F, 0x7F,
+            0xF7,
+        ];
+        let Ok(MtsMessage::NoteChanges(changes)) = parse_mts(&bytes) else {
+            panic!("not a note change");
+        };
+        assert_eq!(changes, vec![(60, 950.0)]);
+
+        let short = [
+            0xF0, 0x7F, 0x7F, 0x08, 0x02, 0x00, 0x02, 60, 69, 0x40, 0x00, 0xF7,
+        ];
+        assert!(matches!(
+            parse_mts(&short),
+            Err(SysexError::LengthMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn other_universal_messages_are_rejected() {
+        // Identity request.
+        let bytes = [0xF0, 0x7E, 0x7F, 0x06, 0x01, 0xF7];
+        assert!(matches!(
+            parse_mts(&bytes),
+            Err(SysexError::NotTuning {
+                sub_id1: 0x06,
+                sub_id2: 0x01
+            })
+        ));
+    }
+
+    #[test]
+    fn offsets_encode_to_the_nearest_step() {
+        assert_eq!(encode_offset(69, 0.0), [69, 0, 0]);
+        assert_eq!(encode_offset(69, 50.0), [69, 0x40, 0x00]);
+        assert_eq!(decode_offset(69, [69, 0x40, 0x00]), Some(50.0));
+        assert_eq!(encode_offset(0, -100.0), [0, 0, 0]);
+        assert_eq!(encode_offset(127, 300.0), [127, 0x7F, 0x7F]);
+    }
+}
@@ -0,0 +1,220 @@
+//! User presets: voices saved from the editor, one JSON file each, in the
+//! `presets` folder of the config directory.
+//!
+//! Every file carries a `version`. Loading upgrades an older file one step
+//! at a time through [`MIGRATIONS`] before reading it, so a later format
+//! change only needs a new step and the files already on disk keep loading.
+//! Within a version, a field missing from the file takes its default; that
+//! is how a new parameter joins without a version bump. A file without a
+//! `version` is version 0: a voice in the dx7-patches layout the `patches/`
+//! collections use, copied into the folder by hand.
+
+use crate::keybindings::config_dir;
+use crate::preset_loader;
+use crate::presets::Dx7Preset;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Format version written by this build.
+pub const FORMAT_VERSION: u32 = 1;
+/// Collection name user presets are listed under.
+pub const USER_COLLECTION: &str = "user";
+
+const DIR_NAME: &str = "presets";
+
+/// Upgrade steps: `MIGRATIONS[n]` turns a version `n` file into version
+/// `n + 1`.
+const MIGRATIONS: [fn(Value) -> Result<Value, String>; FORMAT_VERSION as usize] =
+    [from_dx7_patches];
+
+/// Version 0 → 1: the dx7-patches layout, converted the way the `patches/`
+/// collections are.
+fn from_dx7_patches(value: Value) -> Result<Value, String> {
+    let preset = preset_loader::patch_from_value(value, USER_COLLECTION)?;
+    serde_json::to_value(&preset).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+struct UserPresetFile<'a> {
+    version: u32,
+    #[serde(flatten)]
+    preset: &'a Dx7Preset,
+}
+
+/// Default folder for user presets, or None when no config directory can be
+/// determined.
+pub fn default_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(DIR_NAME))
+}
+
+/// The preset as a current-version file.
+pub fn to_json(preset: &Dx7Preset) -> String {
+    let file = UserPresetFile {
+        version: FORMAT_VERSION,
+        preset,
+    };
+    serde_json::to_string_pretty(&file).expect("presets always serialize")
+}
+
+/// Read a user preset file of any version up to [`FORMAT_VERSION`].
+pub fn from_json(json: &str) -> Result<Dx7Preset, String> {
+    let mut value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let version = match value.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("invalid version {}", v))?,
+    };
+    if version > FORMAT_VERSION {
+        return Err(format!(
+            "saved in format {}, newer than this build reads ({})",
+            version, FORMAT_VERSION
+        ));
+    }
+    for migrate in &MIGRATIONS[version as usize..] {
+        value = migrate(value)?;
+    }
+    let mut preset: Dx7Preset = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    preset.collection = USER_COLLECTION.to_string();
+    Ok(preset)
+}
+
+/// File name for a voice: its name in lower case, with anything but letters
+/// and digits turned into `-`.
+pub fn file_name(name: &str) -> String {
+    let stem: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let stem = stem.trim_matches('-');
+    format!("{}.json", if stem.is_empty() { "voice" } else { stem })
+}
+
+/// Save `preset` into `dir`, replacing a saved voice of the same name.
+pub fn save(dir: &Path, preset: &Dx7Preset) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(file_name(&preset.name));
+    std::fs::write(&path, to_json(preset))?;
+    Ok(path)
+}
+
+/// Every user preset in `dir`, sorted by file name. Files that fail to load
+/// are logged and skipped.
+pub fn load_dir(dir: &Path) -> Vec<Dx7Preset> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| {
+            std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|json| from_json(&json))
+                .map_err(|e| log::warn!("Skipping user preset {}: {}", path.display(), e))
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lfo::LFOWaveform;
+    use crate::operator::KeyScaleCurve;
+
+    const V0: &str = include_str!("../tests/fixtures/user-presets/v0-e-piano-1.json");
+    const V1: &str = include_str!("../tests/fixtures/user-presets/v1-e-piano-1.json");
+    const V1_SPARSE: &str = include_str!("../tests/fixtures/user-presets/v1-sparse.json");
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("synth-fm-rs-user-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn every_version_loads_the_same_voice() {
+        let old = from_json(V0).expect("version 0");
+        let new = from_json(V1).expect("version 1");
+        for preset in [&old, &new] {
+            assert_eq!(preset.name, "E.PIANO 1");
+            assert_eq!(preset.collection, USER_COLLECTION);
+            assert_eq!(preset.algorithm, 5);
+            assert_eq!(preset.operators[1].frequency_ratio, 14.0);
+            assert_eq!(preset.operators[5].key_scale_breakpoint, 62);
+            assert_eq!(preset.operators[5].key_scale_right_depth, 19.0);
+            assert_eq!(
+                preset.operators[0].envelope,
+                (96.0, 25.0, 25.0, 67.0, 99.0, 75.0, 0.0, 0.0)
+            );
+            assert_eq!(preset.lfo.as_ref().unwrap().waveform, LFOWaveform::Sine);
+            assert_eq!(preset.pitch_eg.as_ref().unwrap().rate1, 94.0);
+        }
+        // Migrating and saving gives the current format.
+        assert_eq!(from_json(&to_json(&old)).unwrap().operators[0].detune, 3.0);
+    }
+
+    #[test]
+    fn fields_missing_from_a_file_take_their_defaults() {
+        let preset = from_json(V1_SPARSE).expect("sparse file");
+        assert_eq!(preset.name, "SINE LEAD");
+        assert_eq!(preset.algorithm, 32);
+        assert_eq!(preset.operators[0].output_level, 80.0);
+        // Written before pitch EG, fixed frequency and scaling curves.
+        assert!(preset.pitch_eg.is_none());
+        assert!(!preset.operators[0].fixed_frequency);
+        assert_eq!(
+            preset.operators[0].key_scale_left_curve,
+            KeyScaleCurve::default()
+        );
+        assert_eq!(preset.operators[3].output_level, 99.0);
+    }
+
+    #[test]
+    fn newer_or_broken_files_are_refused() {
+        let newer = format!(r#"{{"version": {}, "name": "X"}}"#, FORMAT_VERSION + 1);
+        assert!(from_json(&newer).unwrap_err().contains("newer"));
+        assert!(from_json(r#"{"version": "one"}"#).is_err());
+        assert!(from_json(r#"{"name": "NO OPS"}"#).is_err());
+        assert!(from_json("not json").is_err());
+    }
+
+    #[test]
+    fn save_and_load_a_folder() {
+        let dir = temp_dir("folder");
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(load_dir(&dir).is_empty());
+
+        let mut preset = from_json(V1).unwrap();
+        preset.name = "My E.Piano/2".to_string();
+        let path = save(&dir, &preset).expect("save");
+        assert_eq!(path.file_name().unwrap(), "my-e-piano-2.json");
+        std::fs::write(dir.join("old.json"), V0).unwrap();
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let loaded = load_dir(&dir);
+        let names: Vec<&str> = loaded.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["My E.Piano/2", "E.PIANO 1"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn file_names_are_safe() {
+        assert_eq!(file_name("BRASS 1"), "brass-1.json");
+        assert_eq!(file_name("  ../x  "), "x.json");
+        assert_eq!(file_name("***"), "voice.json");
+    }
+}
@@ -0,0 +1,396 @@
+//! Era-accurate rendering quirks of the original DX7 hardware.
+//!
+//! `VintageConfig` is a small `Copy` struct handed to the operators,
+//! envelopes, LFO and output stage; each one checks its own flag per sample.
+//! The VINTAGE toggle switches between `CLEAN` and `DX7`, so the same patch
+//! can be A/B-ed between the modern rendering and the dirty one.
+//!
+//! [`OutputDac`] is separate: the hardware's converter, sample-and-hold and
+//! output filter as a last block on the master bus, with its own switch and
+//! an intensity, for the duller, grainier top end of the real instrument.
+//! So is [`NoiseFloor`], the hiss and mains hum an old unit adds under
+//! everything it plays.
+
+/// Which hardware quirks are emulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct VintageConfig {
+    /// Floating-point DAC on the output: 12-bit mantissa, 3-bit exponent.
+    pub dac_companding: bool,
+    /// Envelope output rounded down to 8-bit steps, so slow decays zipper.
+    pub envelope_quantization: bool,
+    /// LFO rate drifts from cycle to cycle like the CPU-clocked original.
+    pub lfo_jitter: bool,
+    /// 4096-entry sine table read without interpolation, 12-bit amplitude.
+    pub coarse_sine: bool,
+}
+
+impl VintageConfig {
+    pub const CLEAN: Self = Self {
+        dac_companding: false,
+        envelope_quantization: false,
+        lfo_jitter: false,
+        coarse_sine: false,
+    };
+
+    pub const DX7: Self = Self {
+        dac_companding: true,
+        envelope_quantization: true,
+        lfo_jitter: true,
+        coarse_sine: true,
+    };
+
+    /// Config for the single VINTAGE toggle.
+    pub fn from_enabled(enabled: bool) -> Self {
+        if enabled {
+            Self::DX7
+        } else {
+            Self::CLEAN
+        }
+    }
+
+    /// True when any quirk is on.
+    pub fn is_enabled(&self) -> bool {
+        *self != Self::CLEAN
+    }
+}
+
+/// Largest cycle-to-cycle LFO rate deviation with jitter on (±2 %).
+pub const LFO_JITTER_DEPTH: f32 = 0.02;
+
+/// Envelope resolution in vintage mode.
+const ENVELOPE_STEPS: f32 = 255.0;
+
+/// Mantissa resolution of the vintage DAC (12 bits signed).
+const DAC_MANTISSA_STEPS: f32 = 2047.0;
+
+/// Number of binary exponent ranges of the vintage DAC (3 bits).
+const DAC_EXPONENT_RANGES: i32 = 8;
+
+/// Output sample rate of the DX7's tone generator (Hz).
+pub const DX7_SAMPLE_RATE: f32 = 49_096.0;
+
+/// Corner of the output stage's reconstruction low-pass (Hz); two gentle
+/// poles, 12 dB/octave above it.
+pub const OUTPUT_LOWPASS_HZ: f32 = 12_000.0;
+
+/// Longest hold, in host samples, the output stage models. Host rates above
+/// three times the DX7's get this much droop and no more.
+const MAX_HOLD: f32 = 3.0;
+
+/// Quietest and loudest noise floor level (dBFS RMS).
+pub const MIN_NOISE_DB: f32 = -100.0;
+pub const MAX_NOISE_DB: f32 = -40.0;
+/// Default noise floor level: about where a DX7's line output sits.
+pub const DEFAULT_NOISE_DB: f32 = -78.0;
+
+/// Hum level relative to the hiss, and its harmonics relative to the
+/// mains fundamental: rectifier buzz puts most of it on the second.
+const HUM_LEVEL: f32 = 0.5;
+const HUM_HARMONICS: [f32; 3] = [0.6, 1.0, 0.3];
+/// RMS of a uniform -1..1 draw, to scale the hiss to its level.
+const UNIFORM_RMS: f32 = 0.577_350_3;
+
+/// Round an envelope level (0..=1) down to the 8-bit grid.
+pub fn quantize_envelope(level: f32) -> f32 {
+    (level * ENVELOPE_STEPS).floor() / ENVELOPE_STEPS
+}
+
+/// Quantise a sample like the DX7's floating-point DAC: the 12-bit mantissa
+/// is scaled by the smallest of eight binary ranges that holds the sample,
+/// so quiet passages keep fine steps while loud ones get coarse ones.
+pub fn dac_compand(sample: f32) -> f32 {
+    let sample = sample.clamp(-1.0, 1.0);
+    let magnitude = sample.abs();
+    let mut shift = 0;
+    while shift < DAC_EXPONENT_RANGES - 1 && magnitude < 0.5_f32.powi(shift + 1) {
+        shift += 1;
+    }
+    let scale = DAC_MANTISSA_STEPS * 2.0_f32.powi(shift);
+    (sample * scale).round() / scale
+}
+
+/// Mean of the straight-line signal through `history` (newest first) over
+/// the last `width` samples. This is the droop a zero-order hold of that
+/// width puts on the spectrum: nothing at DC, more towards Nyquist.
+fn box_average(history: &[f32; 4], width: f32) -> f32 {
+    let mut sum = 0.0;
+    for k in 0..3 {
+        let span = (width - k as f32).clamp(0.0, 1.0);
+        if span == 0.0 {
+            break;
+        }
+        let (newer, older) = (history[k], history[k + 1]);
+        sum += span * newer + 0.5 * span * span * (older - newer);
+    }
+    sum / width
+}
+
+/// The DX7's output stage as a final block: each sample through the
+/// floating-point DAC, held for one DX7 sample period, then smoothed by the
+/// analog low-pass. `intensity` blends it with the clean signal.
+///
+/// The hold's stair-steps are images above the DX7's 24.5 kHz Nyquist,
+/// which the analog filter removes; what is left to hear is the hold's
+/// high-frequency droop, so it is modelled as an average over one DX7
+/// period at the host rate.
+#[derive(Debug, Clone)]
+pub struct OutputDac {
+    pub enabled: bool,
+    intensity: f32,
+    /// Host samples per DX7 sample, capped at `MAX_HOLD`.
+    hold: f32,
+    lowpass_coeff: f32,
+    /// Per channel: DAC output, newest first.
+    history: [[f32; 4]; 2],
+    /// Per channel: the two low-pass poles.
+    lowpass: [[f32; 2]; 2],
+}
+
+impl OutputDac {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut dac = Self {
+            enabled: false,
+            intensity: 1.0,
+            hold: 1.0,
+            lowpass_coeff: 1.0,
+            history: [[0.0; 4]; 2],
+            lowpass: [[0.0; 2]; 2],
+        };
+        dac.set_sample_rate(sample_rate);
+        dac
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.hold = (sample_rate / DX7_SAMPLE_RATE).min(MAX_HOLD);
+        let corner = OUTPUT_LOWPASS_HZ.min(0.45 * sample_rate);
+        self.lowpass_coeff = 1.0 - (-std::f32::consts::TAU * corner / sample_rate).exp();
+        self.reset();
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    /// Share of the emulated output in the mix, 0 (clean) to 1.
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity.clamp(0.0, 1.0);
+    }
+
+    /// Forget the held samples and filter state.
+    pub fn reset(&mut self) {
+        self.history = [[0.0; 4]; 2];
+        self.lowpass = [[0.0; 2]; 2];
+    }
+
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if !self.enabled {
+            return (left, right);
+        }
+        (self.channel(0, left), self.channel(1, right))
+    }
+
+    fn channel(&mut self, channel: usize, input: f32) -> f32 {
+        let history = &mut self.history[channel];
+        history.copy_within(0..3, 1);
+        history[0] = dac_compand(input);
+        let held = box_average(history, self.hold);
+        let [pole1, pole2] = &mut self.lowpass[channel];
+        *pole1 += self.lowpass_coeff * (held - *pole1);
+        *pole2 += self.lowpass_coeff * (*pole1 - *pole2);
+        input + self.intensity * (*pole2 - input)
+    }
+}
+
+/// Background hiss and mains hum, mixed in after the voices and ahead of
+/// the effects the way a hardware unit's analog noise reaches the amp.
+/// Off by default. All state is a few scalars, so it runs on the audio
+/// thread without allocating.
+#[derive(Debug, Clone)]
+pub struct NoiseFloor {
+    pub enabled: bool,
+    level_db: f32,
+    /// RMS of the hiss, linear.
+    gain: f32,
+    hum_hz: f32,
+    /// Mains cycle position, 0..1.
+    hum_phase: f32,
+    hum_step: f32,
+    sample_rate: f32,
+    /// xorshift32 state; never 0.
+    rng: u32,
+}
+
+impl NoiseFloor {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut noise = Self {
+            enabled: false,
+            level_db: DEFAULT_NOISE_DB,
+            gain: 0.0,
+            hum_hz: 50.0,
+            hum_phase: 0.0,
+            hum_step: 0.0,
+            sample_rate,
+            rng: 1,
+        };
+        noise.set_level_db(DEFAULT_NOISE_DB);
+        noise.set_hum_hz(50.0);
+        noise.seed(rand::random());
+        noise
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.set_hum_hz(self.hum_hz);
+    }
+
+    pub fn level_db(&self) -> f32 {
+        self.level_db
+    }
+
+    pub fn set_level_db(&mut self, level_db: f32) {
+        self.level_db = level_db.clamp(MIN_NOISE_DB, MAX_NOISE_DB);
+        self.gain = 10.0_f32.powf(self.level_db / 20.0);
+    }
+
+    pub fn hum_hz(&self) -> f32 {
+        self.hum_hz
+    }
+
+    /// Mains frequency: 50 or 60 Hz, whichever `hz` is nearer.
+    pub fn set_hum_hz(&mut self, hz: f32) {
+        self.hum_hz = if hz >= 55.0 { 60.0 } else { 50.0 };
+        self.hum_step = self.hum_hz / self.sample_rate;
+    }
+
+    /// Restart the hiss from `seed`, so a render repeats exactly.
+    pub fn seed(&mut self, seed: u32) {
+        self.rng = if seed == 0 { 0x9E37_79B9 } else { seed };
+        self.hum_phase = 0.0;
+    }
+
+    /// Next sample; 0 while off.
+    pub fn next_sample(&mut self) -> f32 {
+        if !self.enabled {
+            return 0.0;
+        }
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        let hiss = (x >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0;
+
+        let angle = std::f32::consts::TAU * self.hum_phase;
+        let hum: f32 = HUM_HARMONICS
+            .iter()
+            .enumerate()
+            .map(|(i, level)| level * (angle * (i + 1) as f32).sin())
+            .sum();
+        self.hum_phase = (self.hum_phase + self.hum_step).fract();
+
+        self.gain * (hiss / UNIFORM_RMS + HUM_LEVEL * hum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggle_maps_to_the_two_presets() {
+        assert_eq!(VintageConfig::from_enabled(false), VintageConfig::CLEAN);
+        assert_eq!(VintageConfig::from_enabled(true), VintageConfig::DX7);
+        assert!(!VintageConfig::default().is_enabled());
+        assert!(VintageConfig::DX7.is_enabled());
+    }
+
+    #[test]
+    fn envelope_quantization_steps_down() {
+        assert_eq!(quantize_envelope(1.0), 1.0);
+        assert_eq!(quantize_envelope(0.0), 0.0);
+        assert_eq!(quantize_envelope(0.5 / ENVELOPE_STEPS), 0.0);
+        let q = quantize_envelope(0.3);
+        assert!(q <= 0.3 && 0.3 - q < 1.0 / ENVELOPE_STEPS);
+    }
+
+    #[test]
+    fn dac_step_size_follows_the_exponent() {
+        let step = |x: f32| (dac_compand(x) - x).abs();
+        // Full scale: one mantissa step is 1/2047; quiet: 128x finer.
+        assert!(step(0.9) <= 0.5 / DAC_MANTISSA_STEPS + 1e-7);
+        assert!(step(0.001) <= 0.5 / (DAC_MANTISSA_STEPS * 128.0) + 1e-9);
+        assert_ne!(dac_compand(0.9), 0.9);
+        assert_eq!(dac_compand(0.0), 0.0);
+        assert_eq!(dac_compand(-0.5), -dac_compand(0.5));
+        assert_eq!(dac_compand(2.0), 1.0);
+    }
+
+    #[test]
+    fn noise_floor_sits_at_its_level_and_repeats_from_a_seed() {
+        let sample_rate = 48_000.0;
+        let mut noise = NoiseFloor::new(sample_rate);
+        assert_eq!(noise.next_sample(), 0.0);
+
+        noise.enabled = true;
+        noise.set_level_db(-60.0);
+        noise.seed(7);
+        let take =
+            |noise: &mut NoiseFloor| (0..48_000).map(|_| noise.next_sample()).collect::<Vec<_>>();
+        let first = take(&mut noise);
+        let rms = (first.iter().map(|s| s * s).sum::<f32>() / first.len() as f32).sqrt();
+        let db = 20.0 * rms.log10();
+        // Hiss at the level, the hum a little on top.
+        assert!((-60.0..-58.0).contains(&db), "{db}");
+        noise.seed(7);
+        assert_eq!(take(&mut noise), first);
+
+        // Mains is 50 or 60 Hz; the level stays in range.
+        noise.set_hum_hz(62.0);
+        assert_eq!(noise.hum_hz(), 60.0);
+        noise.set_hum_hz(40.0);
+        assert_eq!(noise.hum_hz(), 50.0);
+        noise.set_level_db(-200.0);
+        assert_eq!(noise.level_db(), MIN_NOISE_DB);
+    }
+
+    #[test]
+    fn hold_droop_keeps_dc_and_dulls_nyquist() {
+        assert_eq!(box_average(&[0.5; 4], 0.9), 0.5);
+        assert_eq!(box_average(&[0.5; 4], 2.5), 0.5);
+        let nyquist = [1.0, -1.0, 1.0, -1.0];
+        assert_eq!(box_average(&nyquist, 1.0), 0.0);
+        let half = box_average(&nyquist, 0.5);
+        assert!((half - 0.5).abs() < 1e-6, "{half}");
+    }
+
+    #[test]
+    fn output_dac_rolls_off_the_top_and_follows_its_intensity() {
+        let sample_rate = 44_100.0;
+        let level = |dac: &mut OutputDac, hz: f32| {
+            dac.reset();
+            let mut peak = 0.0_f32;
+            for n in 0..4410 {
+                let x = 0.5 * (std::f32::consts::TAU * hz * n as f32 / sample_rate).sin();
+                let (l, _) = dac.process(x, x);
+                if n > 2205 {
+                    peak = peak.max(l.abs());
+                }
+            }
+            peak
+        };
+        let mut dac = OutputDac::new(sample_rate);
+        assert_eq!(dac.process(0.3, -0.2), (0.3, -0.2));
+        assert!((level(&mut dac, 15_000.0) - 0.5).abs() < 0.01);
+
+        dac.enabled = true;
+        assert!((level(&mut dac, 200.0) - 0.5).abs() < 0.01);
+        let top = level(&mut dac, 15_000.0);
+        assert!(top < 0.3, "{top}");
+
+        dac.set_intensity(0.5);
+        let blended = level(&mut dac, 15_000.0);
+        assert!(blended > top && blended < 0.5, "{blended}");
+        dac.set_intensity(0.0);
+        assert_eq!(dac.process(0.3, -0.2), (0.3, -0.2));
+    }
+}
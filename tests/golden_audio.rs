@@ -0,0 +1,227 @@
+//! Golden-audio regression suite: one short phrase per algorithm, rendered
+//! offline and reduced to a fingerprint (per-frame level and octave-band
+//! spectrum), compared against `tests/golden/algorithm-NN.txt`.
+//!
+//! A DSP change that should not alter the sound (envelope rewrite, SIMD
+//! operator loop) must pass unchanged. One that is meant to alter it
+//! regenerates the files, and the diff shows which algorithms moved:
+//!
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test --no-default-features --test golden_audio
+//! ```
+
+use std::path::PathBuf;
+use synth_fm_rs::algorithms::get_algorithm_info;
+use synth_fm_rs::lfo::LFOWaveform;
+use synth_fm_rs::presets::{Dx7Preset, PresetLfo, PresetOperator};
+use synth_fm_rs::render::{render_events, NoteEvent};
+use synth_fm_rs::spectrum::{bin_frequency, magnitude_db};
+
+const SAMPLE_RATE: f32 = 44_100.0;
+const TAIL_SECONDS: f32 = 0.2;
+/// Fingerprint frame length and hop, in samples.
+const FRAME: usize = 2048;
+/// Upper edges of the octave bands (Hz); the last band runs to Nyquist.
+const BAND_EDGES: [f32; 8] = [125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0];
+
+/// Level difference allowed per frame, relative to the louder of the two.
+const RMS_TOLERANCE: f32 = 0.01;
+/// Band difference allowed, in dB, for bands above `BAND_FLOOR_DB`.
+const BAND_TOLERANCE_DB: f32 = 0.5;
+/// Bands this quiet in both renders are noise floor and not compared.
+const BAND_FLOOR_DB: f32 = -80.0;
+
+/// Every operator sounds and modulates: distinct ratios, some detune,
+/// velocity and key scaling on, feedback on the algorithm's feedback
+/// operator as a DX7 voice has it, and an S&H LFO on pitch so the seeded
+/// random draws are covered too.
+fn test_voice(algorithm: u8) -> Dx7Preset {
+    const RATIOS: [f32; 6] = [1.0, 2.0, 1.0, 3.0, 0.5, 7.0];
+    let feedback_op = match get_algorithm_info(algorithm).feedback_op {
+        0 => 5,
+        op => op as usize - 1,
+    };
+    Dx7Preset {
+        name: format!("GOLDEN {:02}", algorithm),
+        algorithm,
+        operators: std::array::from_fn(|i| PresetOperator {
+            frequency_ratio: RATIOS[i],
+            output_level: 82.0 - 2.0 * i as f32,
+            detune: i as f32 - 2.0,
+            feedback: if i == feedback_op { 6.0 } else { 0.0 },
+            velocity_sensitivity: 3.0,
+            key_scale_rate: 2.0,
+            key_scale_right_depth: 20.0,
+            envelope: (90.0, 60.0, 40.0, 70.0, 99.0, 85.0, 70.0, 0.0),
+            ..PresetOperator::default()
+        }),
+        pitch_mod_sensitivity: 3,
+        lfo: Some(PresetLfo {
+            waveform: LFOWaveform::SampleHold,
+            rate: 70.0,
+            pitch_mod_depth: 20.0,
+            ..PresetLfo::default()
+        }),
+        ..Dx7Preset::default()
+    }
+}
+
+/// A low note, then an overlapping softer high one.
+fn phrase() -> [NoteEvent; 2] {
+    [
+        NoteEvent {
+            start: 0.0,
+            duration: 0.3,
+            note: 48,
+            velocity: 100,
+        },
+        NoteEvent {
+            start: 0.1,
+            duration: 0.3,
+            note: 67,
+            velocity: 70,
+        },
+    ]
+}
+
+/// Per frame: left RMS, right RMS, then the mid signal's mean power in each
+/// octave band, in dB.
+fn fingerprint(frames: &[[f32; 2]]) -> Vec<Vec<f32>> {
+    let rms = |samples: &mut dyn Iterator<Item = f32>| {
+        (samples.map(|s| s * s).sum::<f32>() / FRAME as f32).sqrt()
+    };
+    frames
+        .chunks_exact(FRAME)
+        .map(|chunk| {
+            let mid: Vec<f32> = chunk.iter().map(|[l, r]| 0.5 * (l + r)).collect();
+            let spectrum = magnitude_db(&mid);
+            let mut row = vec![
+                rms(&mut chunk.iter().map(|f| f[0])),
+                rms(&mut chunk.iter().map(|f| f[1])),
+            ];
+            let mut bands = vec![(0.0_f32, 0_usize); BAND_EDGES.len() + 1];
+            for (k, db) in spectrum.iter().enumerate().skip(1) {
+                let hz = bin_frequency(k, FRAME, SAMPLE_RATE);
+                let band = BAND_EDGES.iter().take_while(|&&edge| hz >= edge).count();
+                bands[band].0 += 10.0_f32.powf(db / 10.0);
+                bands[band].1 += 1;
+            }
+            row.extend(
+                bands
+                    .iter()
+                    .map(|&(power, bins)| 10.0 * (power / bins as f32).log10()),
+            );
+            row
+        })
+        .collect()
+}
+
+fn to_text(fingerprint: &[Vec<f32>]) -> String {
+    let mut text =
+        String::from("# rms_l rms_r band_db[<125 <250 <500 <1k <2k <4k <8k <16k >=16k]\n");
+    for row in fingerprint {
+        let fields: Vec<String> = row.iter().map(|v| format!("{:.4}", v)).collect();
+        text.push_str(&fields.join(" "));
+        text.push('\n');
+    }
+    text
+}
+
+fn from_text(text: &str) -> Vec<Vec<f32>> {
+    text.lines()
+        .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+        .map(|line| {
+            line.split_whitespace()
+                .map(|v| v.parse().expect("golden values are numbers"))
+                .collect()
+        })
+        .collect()
+}
+
+/// Differences beyond tolerance, one line each; empty when they match.
+fn compare(golden: &[Vec<f32>], rendered: &[Vec<f32>]) -> Vec<String> {
+    if golden.len() != rendered.len() {
+        return vec![format!(
+            "{} frames, golden has {}",
+            rendered.len(),
+            golden.len()
+        )];
+    }
+    let mut errors = Vec::new();
+    for (frame, (want, got)) in golden.iter().zip(rendered).enumerate() {
+        for (column, (&w, &g)) in want.iter().zip(got).enumerate() {
+            let ok = if column < 2 {
+                (w - g).abs() <= RMS_TOLERANCE * w.max(g) + 1e-4
+            } else {
+                (w < BAND_FLOOR_DB && g < BAND_FLOOR_DB) || (w - g).abs() <= BAND_TOLERANCE_DB
+            };
+            if !ok {
+                errors.push(format!(
+                    "frame {} column {}: golden {:.4}, rendered {:.4}",
+                    frame, column, w, g
+                ));
+            }
+        }
+    }
+    errors
+}
+
+fn golden_path(algorithm: u8) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("algorithm-{:02}.txt", algorithm))
+}
+
+#[test]
+fn algorithms_match_their_golden_fingerprints() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures = Vec::new();
+    for algorithm in 1..=32 {
+        let frames = render_events(
+            Some(&test_voice(algorithm)),
+            &phrase(),
+            SAMPLE_RATE,
+            TAIL_SECONDS,
+        );
+        let rendered = fingerprint(&frames);
+        let path = golden_path(algorithm);
+        if update {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, to_text(&rendered)).unwrap();
+            continue;
+        }
+        let golden = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            panic!(
+                "{}: {} (run with UPDATE_GOLDEN=1 to create it)",
+                path.display(),
+                e
+            )
+        });
+        for error in compare(&from_text(&golden), &rendered) {
+            failures.push(format!("algorithm {}: {}", algorithm, error));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} differences from the golden files:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn renders_repeat_sample_for_sample() {
+    let voice = test_voice(5);
+    let first = render_events(Some(&voice), &phrase(), SAMPLE_RATE, TAIL_SECONDS);
+    let second = render_events(Some(&voice), &phrase(), SAMPLE_RATE, TAIL_SECONDS);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn the_comparison_catches_a_level_change() {
+    let frames = render_events(Some(&test_voice(1)), &phrase(), SAMPLE_RATE, TAIL_SECONDS);
+    let golden = fingerprint(&frames);
+    let louder: Vec<[f32; 2]> = frames.iter().map(|[l, r]| [l * 1.1, r * 1.1]).collect();
+    assert!(compare(&golden, &from_text(&to_text(&golden))).is_empty());
+    assert!(!compare(&golden, &fingerprint(&louder)).is_empty());
+}